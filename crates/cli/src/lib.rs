@@ -1,13 +1,36 @@
 use clap::{Parser, Subcommand};
 use anyhow::Result;
-use core::{ShellmindError, ShellmindConfig, generate_command_rest, generate_command_grpc};
+use core::{ShellmindError, ShellmindConfig};
+use core::transport::{LiveTransport, RecordingTransport, ReplayTransport, Transport};
+use std::sync::Arc;
 use ui::CLIInterface;
 
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  success
+  1  other failure or invalid arguments
+  2  configuration error
+  3  API or authentication error
+  4  blocked by a pattern, policy or budget
+  5  the executed command failed
+  6  rejected or cancelled by the user";
+
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = EXIT_CODES_HELP)]
 pub struct Cli {
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+    /// Never contact the API; answer from the fixture given with --replay
+    #[arg(long, global = true, requires = "replay")]
+    pub offline: bool,
+    /// Replay recorded model responses from this fixture file
+    #[arg(long, global = true, conflicts_with = "record")]
+    pub replay: Option<String>,
+    /// Record model responses to this fixture file
+    #[arg(long, global = true)]
+    pub record: Option<String>,
+    /// Go through approvals and auditing without running commands or changing anything
+    #[arg(long, global = true)]
+    pub simulate: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -25,6 +48,320 @@ enum Commands {
         #[arg(short, long)]
         text: String,
     },
+    /// Ask a question and get an explanation; nothing is executed
+    Ask {
+        /// The question to answer
+        question: String,
+    },
+    /// Translate command output, an error message or a man page excerpt, keeping code as is
+    Translate {
+        /// Text to translate; read from stdin when omitted
+        text: Option<String>,
+        /// Target language code; defaults to the configured language
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Ask the model for a structured review of a diff
+    Review {
+        /// Review staged changes instead of unstaged ones
+        #[arg(long, conflicts_with_all = ["range", "patch"])]
+        staged: bool,
+        /// Review a revision range, e.g. main..HEAD
+        #[arg(long, conflicts_with = "patch")]
+        range: Option<String>,
+        /// Review a patch file
+        patch: Option<String>,
+        /// Also write the review as Markdown to this file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Run many prompts from a JSONL file concurrently and write the results as JSONL
+    Batch {
+        /// JSONL file with one prompt per line (`{"id": "...", "prompt": "..."}` or a JSON string)
+        file: String,
+        /// Where to write the results; defaults to stdout
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Maximum number of requests in flight
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Maximum number of requests started per minute
+        #[arg(short, long, default_value_t = 60)]
+        rate: u32,
+    },
+    /// Run prompt/config regression suites
+    Eval {
+        #[command(subcommand)]
+        command: EvalCommands,
+    },
+    /// Generate tools from OpenAPI specs
+    Openapi {
+        #[command(subcommand)]
+        command: OpenapiCommands,
+    },
+    /// Serve the editor integration protocol (JSON-RPC with Content-Length framing)
+    #[cfg(feature = "server")]
+    #[command(name = "lsp-ish")]
+    LspIsh {
+        /// Communicate over stdin/stdout
+        #[arg(long)]
+        stdio: bool,
+    },
+    /// Manage secrets in secure storage
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommands,
+    },
+    /// Export or import a portable Shellmind profile
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    /// Check configuration, API access and the local environment
+    Doctor,
+    /// Print the shell hook that shares your aliases and functions with Shellmind
+    Init {
+        /// bash or zsh
+        shell: String,
+    },
+    /// Run the project's tests and have Shellmind explain or fix the first failure
+    Test {
+        /// Test command to run instead of the detected one
+        #[arg(short, long)]
+        command: Option<String>,
+        /// Only explain the failure; never modify files
+        #[arg(long)]
+        explain: bool,
+    },
+    /// Generate a command and run it, subject to the approval policy, in one invocation
+    Run {
+        /// What the command should do
+        prompt: String,
+        /// Approve without asking; dangerous commands and `always` approval mode still ask
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Generate a command without running it
+    Suggest {
+        /// What the command should do
+        prompt: String,
+        /// Print only the command to stdout, for `$(shellmind suggest --print ...)` and the
+        /// shell widget; notes go to stderr
+        #[arg(long)]
+        print: bool,
+    },
+    /// Show token and cost usage against the configured budgets
+    Usage,
+    /// Compare latency, token counts and cost of models on a fixed set of prompts
+    Bench {
+        /// Models to compare, comma separated, optionally with an API: gemini-1.5-flash,gemini-1.5-pro@grpc
+        /// (default: model_name, summary_model_name and budget_fallback_model)
+        #[arg(short, long, value_delimiter = ',')]
+        models: Vec<String>,
+        /// Times each prompt is sent to each model
+        #[arg(short, long, default_value_t = 1)]
+        runs: usize,
+        /// File with one prompt per line to use instead of the built-in set
+        #[arg(short, long)]
+        prompts: Option<String>,
+    },
+    /// Compare system prompt variants
+    Prompts {
+        #[command(subcommand)]
+        command: PromptsCommands,
+    },
+    /// Restore files changed by a tool from the undo store (the newest change by default)
+    Undo {
+        /// Snapshot ID printed by the tool
+        id: Option<String>,
+        /// List snapshots instead of restoring one
+        #[arg(short, long)]
+        list: bool,
+    },
+    /// Run in the background, serving the editor protocol on ~/.shellmind/daemon/daemon.sock
+    #[cfg(feature = "server")]
+    Daemon,
+    /// Install a systemd user unit or launchd agent that starts the daemon at login
+    #[cfg(feature = "server")]
+    InstallService {
+        /// Stop the service and remove it
+        #[arg(long)]
+        uninstall: bool,
+        /// Print the unit or plist instead of installing it
+        #[arg(long)]
+        print: bool,
+    },
+    /// Generate a complete, commented script file for a task
+    Script {
+        /// What the script should do
+        task: String,
+        /// bash or python
+        #[arg(short, long, default_value = "bash")]
+        lang: String,
+        /// File to write (default: derived from the task)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Skip shellcheck / py_compile
+        #[arg(long)]
+        no_lint: bool,
+    },
+    /// Summarize a Terraform plan, answer questions about it and optionally apply it
+    TfReview {
+        /// Saved plan to review (binary, `terraform show -json` or `terraform plan -json`
+        /// output) instead of running `terraform plan`
+        #[arg(short, long)]
+        plan: Option<String>,
+        /// Terraform configuration directory to plan (default: the current directory)
+        #[arg(short, long)]
+        dir: Option<String>,
+    },
+    /// Manage additional repository roots searched alongside the current directory
+    Workspace {
+        #[command(subcommand)]
+        command: WorkspaceCommands,
+    },
+    /// Manage interactive sessions
+    Session {
+        #[command(subcommand)]
+        command: SessionCommands,
+    },
+    /// Securely delete stored data
+    Purge {
+        /// Delete session transcripts
+        #[arg(long)]
+        sessions: bool,
+        /// Delete command history
+        #[arg(long)]
+        history: bool,
+        /// Delete saved memory
+        #[arg(long)]
+        memory: bool,
+        /// Delete the feedback log and the prompt variant trials
+        #[arg(long)]
+        feedback: bool,
+        /// Delete all stored data, including audit logs, undo snapshots, feedback and scratch scripts
+        #[arg(long)]
+        all: bool,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum WorkspaceCommands {
+    /// Register a repository root
+    Add {
+        path: String,
+    },
+    /// Unregister a repository root
+    Remove {
+        path: String,
+    },
+    /// List the workspace roots
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+enum SessionCommands {
+    /// List saved sessions with their titles, newest first
+    List {
+        /// Only sessions whose titles match this, allowing for typos
+        query: Option<String>,
+        /// Most sessions to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Export a session as a redacted Markdown transcript, optionally uploading it
+    Share {
+        /// Session ID (the most recent session by default)
+        id: Option<String>,
+        /// Upload as a secret gist with the GitHub token from secure storage
+        #[arg(long, conflicts_with = "paste")]
+        gist: bool,
+        /// Upload to the paste service set in share_paste_url
+        #[arg(long)]
+        paste: bool,
+        /// Make the gist public
+        #[arg(long, requires = "gist")]
+        public: bool,
+        /// Write the transcript to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Upload without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Continue a session that crashed or was killed, including a pending approval
+    Recover {
+        /// Session ID (the most recent crashed session by default)
+        id: Option<String>,
+        /// List recoverable sessions instead
+        #[arg(short, long)]
+        list: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileCommands {
+    /// Write config (without secrets), custom tools, OpenAPI specs and memory to a file
+    Export {
+        file: String,
+    },
+    /// Replace the current setup with a profile; the local API key is kept
+    Import {
+        file: String,
+        /// Accept changes to security-relevant settings without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum OpenapiCommands {
+    /// Register operations of an OpenAPI spec as tools
+    Import {
+        /// Path to the OpenAPI spec (JSON or YAML)
+        spec: String,
+        /// operationIds to expose (comma separated); defaults to all operations
+        #[arg(long, value_delimiter = ',')]
+        operations: Vec<String>,
+        /// Secure storage key holding the bearer token
+        #[arg(long)]
+        auth_secret: Option<String>,
+        /// Base URL overriding the spec's servers entry
+        #[arg(long)]
+        base_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SecretCommands {
+    /// Store a secret; the value is prompted for, or read from stdin when piped, so it
+    /// stays out of `ps` and the shell history
+    Set {
+        key: String,
+    },
+    /// Delete a secret
+    Delete {
+        key: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum EvalCommands {
+    /// Run a YAML suite of (prompt, expected, forbidden) cases against the current config
+    Run {
+        /// Path to the YAML suite
+        suite: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PromptsCommands {
+    /// Summarize how each `system_prompts` variant did: requests served, commands that
+    /// succeeded, failed or were declined, and `/good` and `/bad` feedback
+    Report,
 }
 
 #[derive(Subcommand, Debug)]
@@ -33,18 +370,158 @@ enum ConfigCommands {
     Show,
     /// Set a configuration value
     Set {
-        /// The configuration key to set (e.g., api_key, model_name, temperature, api_type, grpc_endpoint, system_prompt)
+        /// The configuration key to set (e.g., api_key, model_name, temperature, api_type, grpc_endpoint, system_prompt, prompt_sampling, intent_check, max_fix_attempts, verify_after_execution, context_token_budget, summary_model_name, generate_session_titles, load_dotenv, dotenv_allowlist (comma separated), use_shell_aliases, startup_context, track_file_changes, feedback_steering, feedback_examples, language, tts_enabled, tts_command, pager, status_line (comma separated), prompt_format, edit_mode, completion_type, key_bindings.<key>, production_safe_mode, production_hostnames (comma separated), production_kube_contexts (comma separated), auto_correct_tool_names, banner_text, banner_font, startup_tips, encrypt_storage, max_history_entries, max_session_age_days, audit_log_retention_days, audit_log, audit_syslog, audit_webhook_url, max_undo_snapshots, file_cache_mb, grep_index, agent_max_steps, agent_step_timeout_secs, agent_max_duration_secs, agent_token_budget, approval_mode, sandbox_backend, request_timeout_secs, connect_timeout_secs, keepalive_interval_secs, autosave_interval_secs, daily_token_budget, monthly_token_budget, daily_cost_budget, monthly_cost_budget, budget_policy, budget_fallback_model, sync_remote, github_api_url, github_token_secret, docker_socket, cloud_context, fleet_parallelism, fleet_inventory, directory_context, explain_risk, share_paste_url, share_paste_token_secret, local_first, prompt_prefix, prompt_suffix, inline_images, approval_webhook_url, approval_webhook_token_secret, approval_webhook_after_secs, stream_responses)
         key: String,
         /// The value to set
         value: String,
     },
+    /// Sync config, custom tools and memory with a git repository; secrets stay in the keyring
+    Sync {
+        /// Repository URL; saved as `sync_remote` for later runs
+        remote: Option<String>,
+        /// Resolve conflicting changes in favor of `local` or `remote`
+        #[arg(long)]
+        prefer: Option<String>,
+    },
+}
+
+/// Options of `shellmind script`, which writes through the session's file tool approvals.
+#[derive(Debug, Clone)]
+pub struct ScriptOptions {
+    pub task: String,
+    pub language: String,
+    pub output: Option<String>,
+    pub lint: bool,
+}
+
+/// Options of `shellmind test`, whose workflow runs in the interactive session.
+#[derive(Debug, Clone)]
+pub struct TestOptions {
+    pub command: Option<String>,
+    pub explain_only: bool,
+}
+
+/// Options of `shellmind tf-review`, which asks follow-up questions in the session.
+#[derive(Debug, Clone)]
+pub struct TfReviewOptions {
+    pub plan: Option<String>,
+    pub dir: Option<String>,
+}
+
+/// Options of `shellmind run`.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub prompt: String,
+    pub yes: bool,
+}
+
+/// Options of `shellmind suggest`.
+#[derive(Debug, Clone)]
+pub struct SuggestOptions {
+    pub prompt: String,
+    pub print_only: bool,
 }
 
 impl Cli {
+    pub fn parse_args(args: Vec<String>) -> Self {
+        Cli::try_parse_from(args).unwrap_or_else(|e| {
+            let _ = e.print();
+            // clap exits with 2 by default, which is reserved for configuration errors
+            std::process::exit(if e.use_stderr() { core::exit_code::FAILURE } else { core::exit_code::SUCCESS })
+        })
+    }
+
+    /// True when a subcommand was given; otherwise the interactive session should start.
+    pub fn has_command(&self) -> bool {
+        self.command.is_some()
+    }
+
+    /// False for commands that must work before Shellmind is fully configured.
+    pub fn needs_setup(&self) -> bool {
+        match self.command {
+            Some(Commands::Doctor) | Some(Commands::Init { .. }) => false,
+            #[cfg(feature = "server")]
+            Some(Commands::InstallService { .. }) => false,
+            _ => true,
+        }
+    }
+
+    /// Set for `shellmind test`, which needs the interactive session's tool approvals.
+    pub fn test_options(&self) -> Option<TestOptions> {
+        match &self.command {
+            Some(Commands::Test { command, explain }) => Some(TestOptions { command: command.clone(), explain_only: *explain }),
+            _ => None,
+        }
+    }
+
+    /// Set for `shellmind session recover` without `--list`, which continues the session
+    /// in the interactive loop. Holds the requested session ID, if any.
+    pub fn recover_session(&self) -> Option<Option<String>> {
+        match &self.command {
+            Some(Commands::Session { command: SessionCommands::Recover { id, list: false } }) => Some(id.clone()),
+            _ => None,
+        }
+    }
+
+    /// Set for `shellmind run`, which needs the session's approval and risk checks.
+    pub fn run_options(&self) -> Option<RunOptions> {
+        match &self.command {
+            Some(Commands::Run { prompt, yes }) => Some(RunOptions { prompt: prompt.clone(), yes: *yes }),
+            _ => None,
+        }
+    }
+
+    /// Set for `shellmind suggest`, which shares the session's checks with `run`.
+    pub fn suggest_options(&self) -> Option<SuggestOptions> {
+        match &self.command {
+            Some(Commands::Suggest { prompt, print }) => Some(SuggestOptions { prompt: prompt.clone(), print_only: *print }),
+            _ => None,
+        }
+    }
+
+    /// Set for `shellmind script`.
+    pub fn script_options(&self) -> Option<ScriptOptions> {
+        match &self.command {
+            Some(Commands::Script { task, lang, output, no_lint }) => Some(ScriptOptions {
+                task: task.clone(),
+                language: lang.to_lowercase(),
+                output: output.clone(),
+                lint: !no_lint,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Set for `shellmind tf-review`.
+    pub fn tf_review_options(&self) -> Option<TfReviewOptions> {
+        match &self.command {
+            Some(Commands::TfReview { plan, dir }) => Some(TfReviewOptions { plan: plan.clone(), dir: dir.clone() }),
+            _ => None,
+        }
+    }
+
+    /// Builds the model transport selected by the --replay/--record flags.
+    pub fn transport(&self) -> Result<Arc<dyn Transport>, ShellmindError> {
+        if let Some(path) = &self.replay {
+            return Ok(Arc::new(ReplayTransport::from_file(std::path::Path::new(path))?));
+        }
+        if let Some(path) = &self.record {
+            return Ok(Arc::new(RecordingTransport::new(Box::new(LiveTransport::new()), path)));
+        }
+        Ok(Arc::new(LiveTransport::new()))
+    }
+
     pub async fn run(args: Vec<String>, ui: &CLIInterface) -> Result<(), ShellmindError> {
-        let cli = Cli::parse_from(args);
+        Cli::parse_from(args).execute(ui).await
+    }
 
-        match &cli.command {
+    pub async fn execute(&self, ui: &CLIInterface) -> Result<(), ShellmindError> {
+        let Some(command) = &self.command else {
+            return Ok(());
+        };
+        let transport = self.transport()?;
+
+        match command {
             Commands::Version => {
                 println!("Shellmind CLI Version: {}", env!("CARGO_PKG_VERSION"));
             }
@@ -59,9 +536,132 @@ impl Cli {
                     println!("  API Type: {:?}", config.api_type);
                     println!("  gRPC Endpoint: {}", config.grpc_endpoint);
                     println!("  System Prompt: {}", config.system_prompt);
+                    println!("  Prompt Variants: {}", core::prompt_trials::variant_names(&config).join(", "));
+                    println!("  Prompt Sampling: {:?}", config.prompt_sampling);
+                    println!("  Intent Check: {}", config.intent_check);
+                    println!("  Max Fix Attempts: {}", config.max_fix_attempts);
+                    println!("  Verify After Execution: {}", config.verify_after_execution);
+                    println!("  Context Token Budget: {}", config.context_token_budget);
+                    println!("  Summary Model Name: {}", config.summary_model_name);
+                    println!("  Generate Session Titles: {}", config.generate_session_titles);
+                    println!("  Load .env: {}", config.load_dotenv);
+                    println!("  .env Allowlist: {}", if config.dotenv_allowlist.is_empty() { "all".to_string() } else { config.dotenv_allowlist.join(", ") });
+                    println!("  Use Shell Aliases: {}", config.use_shell_aliases);
+                    println!("  Startup Context: {}", config.startup_context);
+                    println!("  Track File Changes: {}", config.track_file_changes);
+                    println!("  Feedback Steering: {} ({} examples)", config.feedback_steering, config.feedback_examples);
+                    println!("  Language: {} ({})", config.language, config.resolved_language());
+                    println!("  TTS Enabled: {}", config.tts_enabled);
+                    println!("  TTS Command: {}", if config.tts_command.is_empty() { "auto" } else { &config.tts_command });
+                    println!("  Pager: {}", config.pager);
+                    println!("  Status Line: {}", if config.status_line.is_empty() { "off".to_string() } else { config.status_line.join(", ") });
+                    println!("  Prompt Format: {}", config.prompt_format);
+                    println!("  Edit Mode: {}", config.edit_mode);
+                    println!("  Completion Type: {}", config.completion_type);
+                    for (key, action) in &config.key_bindings {
+                        println!("  Key Binding: {} = {}", key, action);
+                    }
+                    println!("  Simulate Execution: {}", config.simulate_execution);
+                    println!("  Production Safe Mode: {}", config.production_safe_mode);
+                    println!("  Production Hostnames: {}", config.production_hostnames.join(", "));
+                    println!("  Production Kube Contexts: {}", config.production_kube_contexts.join(", "));
+                    if let Some(production) = &config.production {
+                        println!("  Production Detected: {} (approval mode and file tools restricted)", production.reason);
+                    }
+                    println!("  Auto-correct Tool Names: {}", config.auto_correct_tool_names);
+                    println!("  Banner Text: {}", if config.banner_text.is_empty() { "none" } else { &config.banner_text });
+                    println!("  Banner Font: {}", if config.banner_font.is_empty() { "standard" } else { &config.banner_font });
+                    println!("  Startup Tips: {}", config.startup_tips);
+                    println!("  Encrypt Storage: {}", config.encrypt_storage);
+                    println!("  Max History Entries: {}", config.max_history_entries);
+                    println!("  Max Session Age (days): {}", config.max_session_age_days);
+                    println!("  Audit Log Retention (days): {}", config.audit_log_retention_days);
+                    println!("  Audit Log: {}", config.audit_log);
+                    println!("  Audit Syslog: {}", config.audit_syslog);
+                    println!("  Audit Webhook URL: {}", if config.audit_webhook_url.is_empty() { "none" } else { &config.audit_webhook_url });
+                    println!("  Max Undo Snapshots: {}", config.max_undo_snapshots);
+                    println!("  File Cache: {} MiB", config.file_cache_mb);
+                    println!("  Grep Index: {}", config.grep_index);
+                    println!("  Agent Max Steps: {}", config.agent_max_steps);
+                    println!("  Agent Step Timeout (s): {}", config.agent_step_timeout_secs);
+                    println!("  Agent Max Duration (s): {}", config.agent_max_duration_secs);
+                    println!("  Agent Token Budget: {}", config.agent_token_budget);
+                    println!("  Approval Mode: {:?}", config.approval_mode);
+                    println!("  Sandbox Backend: {}", config.sandbox_backend);
+                    println!("  Request Timeout (s): {}", config.request_timeout_secs);
+                    println!("  Connect Timeout (s): {}", config.connect_timeout_secs);
+                    println!("  Keepalive Interval (s): {}", config.keepalive_interval_secs);
+                    println!("  Autosave Interval (s): {}", config.autosave_interval_secs);
+                    println!("  Daily Token Budget: {}", config.daily_token_budget);
+                    println!("  Monthly Token Budget: {}", config.monthly_token_budget);
+                    println!("  Daily Cost Budget ($): {:.2}", config.daily_cost_budget);
+                    println!("  Monthly Cost Budget ($): {:.2}", config.monthly_cost_budget);
+                    println!("  Budget Policy: {:?}", config.budget_policy);
+                    println!("  Budget Fallback Model: {}", config.budget_fallback_model);
+                    println!("  Sync Remote: {}", if config.sync_remote.is_empty() { "none" } else { &config.sync_remote });
+                    println!("  GitHub API URL: {}", config.github_api_url);
+                    println!("  GitHub Token Secret: {}", config.github_token_secret);
+                    println!("  Docker Socket: {}", if config.docker_socket.is_empty() { "auto" } else { &config.docker_socket });
+                    println!("  Cloud Context: {}", config.cloud_context);
+                    println!("  Fleet Parallelism: {}", config.fleet_parallelism);
+                    println!("  Fleet Inventory: {}", if config.fleet_inventory.is_empty() { "auto" } else { &config.fleet_inventory });
+                    println!("  Directory Context: {}", config.directory_context);
+                    println!("  Explain Risk: {}", config.explain_risk);
+                    println!("  Share Paste URL: {}", if config.share_paste_url.is_empty() { "none" } else { &config.share_paste_url });
+                    println!("  Share Paste Token Secret: {}", config.share_paste_token_secret);
+                    println!("  Local First: {}", config.local_first);
+                    println!("  Prompt Prefix: {}", if config.prompt_prefix.is_empty() { "none" } else { &config.prompt_prefix });
+                    println!("  Prompt Suffix: {}", if config.prompt_suffix.is_empty() { "none" } else { &config.prompt_suffix });
+                    println!("  Inline Images: {}", config.inline_images);
+                    println!("  Approval Webhook URL: {}", if config.approval_webhook_url.is_empty() { "none" } else { &config.approval_webhook_url });
+                    println!("  Approval Webhook Token Secret: {}", config.approval_webhook_token_secret);
+                    println!("  Approval Webhook After: {}s", config.approval_webhook_after_secs);
+                    println!("  Stream Responses: {}", config.stream_responses);
+                    println!("  Blocked Commands: {}", config.blocked_commands.join(", "));
+                    println!("  Disabled Tools: {}", config.disabled_tools.join(", "));
+                    println!("  Workspace Roots: {}", config.workspace_roots.join(", "));
+                    let mut limited: Vec<_> = config.tools.limits.iter().collect();
+                    limited.sort_by(|a, b| a.0.cmp(b.0));
+                    for (tool, limits) in limited {
+                        println!(
+                            "  Tool Limits [{}]: timeout {}s, memory {} MB, files {}",
+                            tool,
+                            limits.timeout_secs.map_or("-".to_string(), |v| v.to_string()),
+                            limits.max_memory_mb.map_or("-".to_string(), |v| v.to_string()),
+                            limits.max_files.map_or("-".to_string(), |v| v.to_string())
+                        );
+                    }
+                    if !config.policy.banned_commands.is_empty() {
+                        println!("  Policy Banned Commands: {}", config.policy.banned_commands.join(", "));
+                    }
+                }
+                ConfigCommands::Sync { remote, prefer } => {
+                    let mut config = core::ConfigManager::load_configuration()?;
+                    let prefer = prefer.as_deref().map(str::parse::<core::sync::Prefer>).transpose()?;
+                    let remote = match remote {
+                        Some(remote) => remote.clone(),
+                        None if !config.sync_remote.is_empty() => config.sync_remote.clone(),
+                        None => return Err(ShellmindError::Other("No sync repository configured. Run `shellmind config sync <remote>` first".to_string())),
+                    };
+                    config.sync_remote = remote.clone();
+                    let (_, report) = core::sync::sync(&config, &remote, prefer)?;
+                    if report.pulled.is_empty() {
+                        println!("Already up to date with {}.", remote);
+                    } else {
+                        println!("Pulled {} changed file(s) from {}:", report.pulled.len(), remote);
+                        for file in &report.pulled {
+                            println!("  {}", file);
+                        }
+                    }
+                    if report.committed && report.pushed {
+                        println!("Pushed local changes.");
+                    }
                 }
                 ConfigCommands::Set { key, value } => {
                     let mut config = core::ConfigManager::load_configuration()?;
+                    if config.policy.is_locked(key) {
+                        return Err(ShellmindError::Other(format!("'{}' is set by the system policy ({}) and cannot be changed", key, core::policy::SYSTEM_POLICY_PATH)));
+                    }
                     match key.as_str() {
                         "api_key" => config.api_key = value.clone(),
                         "model_name" => config.model_name = value.clone(),
@@ -80,7 +680,248 @@ impl Cli {
                         }
                         "grpc_endpoint" => config.grpc_endpoint = value.clone(),
                         "system_prompt" => config.system_prompt = value.clone(),
-                        _ => return Err(ShellmindError::Other(format!("Unknown config key: {}", key))),
+                        "prompt_sampling" => {
+                            config.prompt_sampling = match value.to_lowercase().as_str() {
+                                "off" => core::prompt_trials::PromptSampling::Off,
+                                "random" => core::prompt_trials::PromptSampling::Random,
+                                "best" => core::prompt_trials::PromptSampling::Best,
+                                _ => return Err(ShellmindError::Other("Invalid prompt sampling. Use 'off', 'random' or 'best'".to_string())),
+                            };
+                        }
+                        "intent_check" => {
+                            config.intent_check = value.parse().map_err(|_| ShellmindError::Other("Invalid intent check value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "max_fix_attempts" => {
+                            config.max_fix_attempts = value.parse().map_err(|_| ShellmindError::Other("Invalid max fix attempts value".to_string()))?;
+                        }
+                        "verify_after_execution" => {
+                            config.verify_after_execution = value.parse().map_err(|_| ShellmindError::Other("Invalid verify after execution value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "context_token_budget" => {
+                            config.context_token_budget = value.parse().map_err(|_| ShellmindError::Other("Invalid context token budget value".to_string()))?;
+                        }
+                        "summary_model_name" => config.summary_model_name = value.clone(),
+                        "generate_session_titles" => {
+                            config.generate_session_titles = value.parse().map_err(|_| ShellmindError::Other("Invalid generate session titles value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "load_dotenv" => {
+                            config.load_dotenv = value.parse().map_err(|_| ShellmindError::Other("Invalid load dotenv value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "dotenv_allowlist" => {
+                            config.dotenv_allowlist = value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect();
+                        }
+                        "use_shell_aliases" => {
+                            config.use_shell_aliases = value.parse().map_err(|_| ShellmindError::Other("Invalid use shell aliases value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "startup_context" => {
+                            config.startup_context = value.parse().map_err(|_| ShellmindError::Other("Invalid startup context value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "track_file_changes" => {
+                            config.track_file_changes = value.parse().map_err(|_| ShellmindError::Other("Invalid track file changes value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "feedback_steering" => {
+                            config.feedback_steering = value.parse().map_err(|_| ShellmindError::Other("Invalid feedback steering value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "feedback_examples" => {
+                            config.feedback_examples = value.parse().map_err(|_| ShellmindError::Other("Invalid feedback examples value".to_string()))?;
+                        }
+                        "language" => {
+                            if !["auto", "en", "tr"].contains(&value.to_lowercase().as_str()) {
+                                return Err(ShellmindError::Other("Invalid language. Use 'auto', 'en' or 'tr'".to_string()));
+                            }
+                            config.language = value.to_lowercase();
+                        }
+                        "tts_enabled" => {
+                            config.tts_enabled = value.parse().map_err(|_| ShellmindError::Other("Invalid TTS enabled value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "tts_command" => config.tts_command = value.clone(),
+                        "pager" => {
+                            if !["builtin", "external", "off"].contains(&value.as_str()) {
+                                return Err(ShellmindError::Other("Invalid pager. Use 'builtin', 'external' or 'off'".to_string()));
+                            }
+                            config.pager = value.clone();
+                        }
+                        "status_line" => {
+                            let segments: Vec<String> = value.split(',').map(|v| v.trim().to_lowercase()).filter(|v| !v.is_empty() && v != "off").collect();
+                            if let Some(unknown) = segments.iter().find(|s| !["model", "profile", "approval", "tokens", "cwd"].contains(&s.as_str())) {
+                                return Err(ShellmindError::Other(format!("Unknown status line part '{}'. Use model, profile, approval, tokens and cwd, or 'off'", unknown)));
+                            }
+                            config.status_line = segments;
+                        }
+                        "prompt_format" => config.prompt_format = value.clone(),
+                        "edit_mode" => {
+                            if ui::keymap::edit_mode(value).is_none() {
+                                return Err(ShellmindError::Other("Invalid edit mode. Use 'emacs' or 'vi'".to_string()));
+                            }
+                            config.edit_mode = value.to_lowercase();
+                        }
+                        "completion_type" => {
+                            if ui::keymap::completion_type(value).is_none() {
+                                return Err(ShellmindError::Other("Invalid completion type. Use 'circular' or 'list'".to_string()));
+                            }
+                            config.completion_type = value.to_lowercase();
+                        }
+                        _ if key.starts_with("key_bindings.") => {
+                            let binding = &key["key_bindings.".len()..];
+                            if value.is_empty() {
+                                config.key_bindings.remove(binding);
+                            } else {
+                                if ui::keymap::parse_key(binding).is_none() {
+                                    return Err(ShellmindError::Other(format!("Invalid key `{}`; use e.g. ctrl-p, alt-b, shift-tab or f5", binding)));
+                                }
+                                if ui::keymap::parse_action(value).is_none() {
+                                    return Err(ShellmindError::Other(format!("Unknown action `{}`; use insert:<text> or one of: {}", value, ui::keymap::ACTIONS.join(", "))));
+                                }
+                                config.key_bindings.insert(binding.to_string(), value.clone());
+                            }
+                        }
+                        "production_safe_mode" => {
+                            config.production_safe_mode = value.parse().map_err(|_| ShellmindError::Other("Invalid production safe mode value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "production_hostnames" => {
+                            config.production_hostnames = value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect();
+                        }
+                        "production_kube_contexts" => {
+                            config.production_kube_contexts = value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect();
+                        }
+                        "auto_correct_tool_names" => {
+                            config.auto_correct_tool_names = value.parse().map_err(|_| ShellmindError::Other("Invalid auto-correct tool names value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "banner_text" => config.banner_text = value.clone(),
+                        "banner_font" => config.banner_font = value.clone(),
+                        "startup_tips" => {
+                            config.startup_tips = value.parse().map_err(|_| ShellmindError::Other("Invalid startup tips value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "encrypt_storage" => {
+                            config.encrypt_storage = value.parse().map_err(|_| ShellmindError::Other("Invalid encrypt storage value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "max_history_entries" => {
+                            config.max_history_entries = value.parse().map_err(|_| ShellmindError::Other("Invalid max history entries value".to_string()))?;
+                        }
+                        "max_session_age_days" => {
+                            config.max_session_age_days = value.parse().map_err(|_| ShellmindError::Other("Invalid max session age value".to_string()))?;
+                        }
+                        "audit_log_retention_days" => {
+                            config.audit_log_retention_days = value.parse().map_err(|_| ShellmindError::Other("Invalid audit log retention value".to_string()))?;
+                        }
+                        "audit_log" => {
+                            config.audit_log = value.parse().map_err(|_| ShellmindError::Other("Invalid audit log value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "audit_syslog" => {
+                            config.audit_syslog = value.parse().map_err(|_| ShellmindError::Other("Invalid audit syslog value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "audit_webhook_url" => config.audit_webhook_url = value.clone(),
+                        "max_undo_snapshots" => {
+                            config.max_undo_snapshots = value.parse().map_err(|_| ShellmindError::Other("Invalid max undo snapshots value".to_string()))?;
+                        }
+                        "file_cache_mb" => {
+                            config.file_cache_mb = value.parse().map_err(|_| ShellmindError::Other("Invalid file cache size".to_string()))?;
+                        }
+                        "grep_index" => {
+                            config.grep_index = value.parse().map_err(|_| ShellmindError::Other("Invalid grep index value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "agent_max_steps" => {
+                            config.agent_max_steps = value.parse().map_err(|_| ShellmindError::Other("Invalid agent max steps value".to_string()))?;
+                        }
+                        "agent_step_timeout_secs" => {
+                            config.agent_step_timeout_secs = value.parse().map_err(|_| ShellmindError::Other("Invalid agent step timeout".to_string()))?;
+                        }
+                        "agent_max_duration_secs" => {
+                            config.agent_max_duration_secs = value.parse().map_err(|_| ShellmindError::Other("Invalid agent max duration".to_string()))?;
+                        }
+                        "agent_token_budget" => {
+                            config.agent_token_budget = value.parse().map_err(|_| ShellmindError::Other("Invalid agent token budget".to_string()))?;
+                        }
+                        "approval_mode" => {
+                            config.approval_mode = match value.to_lowercase().as_str() {
+                                "default" => core::ApprovalMode::Default,
+                                "always" => core::ApprovalMode::Always,
+                                "suggest" => core::ApprovalMode::Suggest,
+                                _ => return Err(ShellmindError::Other("Invalid approval mode. Use 'default', 'always' or 'suggest'".to_string())),
+                            };
+                        }
+                        "sandbox_backend" => config.sandbox_backend = value.clone(),
+                        "request_timeout_secs" => {
+                            config.request_timeout_secs = value.parse().map_err(|_| ShellmindError::Other("Invalid request timeout value".to_string()))?;
+                        }
+                        "connect_timeout_secs" => {
+                            config.connect_timeout_secs = value.parse().map_err(|_| ShellmindError::Other("Invalid connect timeout value".to_string()))?;
+                        }
+                        "keepalive_interval_secs" => {
+                            config.keepalive_interval_secs = value.parse().map_err(|_| ShellmindError::Other("Invalid keepalive interval value".to_string()))?;
+                        }
+                        "autosave_interval_secs" => {
+                            config.autosave_interval_secs = value.parse().map_err(|_| ShellmindError::Other("Invalid autosave interval value".to_string()))?;
+                        }
+                        "daily_token_budget" => {
+                            config.daily_token_budget = value.parse().map_err(|_| ShellmindError::Other("Invalid daily token budget value".to_string()))?;
+                        }
+                        "monthly_token_budget" => {
+                            config.monthly_token_budget = value.parse().map_err(|_| ShellmindError::Other("Invalid monthly token budget value".to_string()))?;
+                        }
+                        "daily_cost_budget" => {
+                            config.daily_cost_budget = value.parse().map_err(|_| ShellmindError::Other("Invalid daily cost budget value".to_string()))?;
+                        }
+                        "monthly_cost_budget" => {
+                            config.monthly_cost_budget = value.parse().map_err(|_| ShellmindError::Other("Invalid monthly cost budget value".to_string()))?;
+                        }
+                        "budget_policy" => {
+                            config.budget_policy = match value.to_lowercase().as_str() {
+                                "warn" => core::budget::BudgetPolicy::Warn,
+                                "fallback" => core::budget::BudgetPolicy::Fallback,
+                                "refuse" => core::budget::BudgetPolicy::Refuse,
+                                _ => return Err(ShellmindError::Other("Invalid budget policy. Use 'warn', 'fallback' or 'refuse'".to_string())),
+                            };
+                        }
+                        "budget_fallback_model" => config.budget_fallback_model = value.clone(),
+                        "sync_remote" => config.sync_remote = value.clone(),
+                        "github_api_url" => {
+                            if !value.starts_with("https://") && !value.starts_with("http://") {
+                                return Err(ShellmindError::Other("Invalid GitHub API URL. Use an http(s) URL".to_string()));
+                            }
+                            config.github_api_url = value.trim_end_matches('/').to_string();
+                        }
+                        "github_token_secret" => config.github_token_secret = value.clone(),
+                        "docker_socket" => config.docker_socket = value.clone(),
+                        "cloud_context" => {
+                            config.cloud_context = value.parse().map_err(|_| ShellmindError::Other("Invalid cloud context value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "fleet_parallelism" => {
+                            config.fleet_parallelism = value.parse().ok().filter(|n| *n > 0).ok_or_else(|| ShellmindError::Other("Invalid fleet parallelism value. Use a positive number".to_string()))?;
+                        }
+                        "fleet_inventory" => config.fleet_inventory = value.clone(),
+                        "directory_context" => {
+                            config.directory_context = value.parse().map_err(|_| ShellmindError::Other("Invalid directory context value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "explain_risk" => {
+                            config.explain_risk = value.parse().map_err(|_| ShellmindError::Other("Invalid explain risk value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "share_paste_url" => config.share_paste_url = value.clone(),
+                        "share_paste_token_secret" => config.share_paste_token_secret = value.clone(),
+                        "local_first" => {
+                            config.local_first = value.parse().map_err(|_| ShellmindError::Other("Invalid local first value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        "prompt_prefix" => config.prompt_prefix = value.clone(),
+                        "prompt_suffix" => config.prompt_suffix = value.clone(),
+                        "inline_images" => {
+                            if !["auto", "kitty", "iterm2", "sixel", "off"].contains(&value.as_str()) {
+                                return Err(ShellmindError::Other("Invalid inline images value. Use 'auto', 'kitty', 'iterm2', 'sixel' or 'off'".to_string()));
+                            }
+                            config.inline_images = value.clone();
+                        }
+                        "approval_webhook_url" => config.approval_webhook_url = value.clone(),
+                        "approval_webhook_token_secret" => config.approval_webhook_token_secret = value.clone(),
+                        "approval_webhook_after_secs" => {
+                            config.approval_webhook_after_secs = value.parse().map_err(|_| ShellmindError::Other("Invalid approval webhook delay. Use a number of seconds".to_string()))?;
+                        }
+                        "stream_responses" => {
+                            config.stream_responses = value.parse().map_err(|_| ShellmindError::Other("Invalid stream responses value. Use 'true' or 'false'".to_string()))?;
+                        }
+                        _ => {
+                            let known = core::ConfigManager::config_keys(&config);
+                            let suggestions = core::fuzzy::closest(key, known.iter().map(String::as_str));
+                            return Err(ShellmindError::Other(format!("Unknown config key `{}`{}", key, core::fuzzy::did_you_mean(&suggestions))));
+                        }
                     }
                     core::ConfigManager::save_configuration(&config)?;
                     println!("Configuration updated successfully.");
@@ -90,22 +931,558 @@ impl Cli {
                 let config = core::ConfigManager::load_configuration()?;
                 let indicator = ui.start_thinking_indicator();
                 ui.print_status("Generating command...");
-                let result = match config.api_type {
-                    core::ApiType::Rest => generate_command_rest(&config, text, &[]).await,
-                    core::ApiType::Grpc => generate_command_grpc(&config, text, &[]).await,
-                };
+                let result = transport.generate(&config, &config.wrap_prompt(text), &[]).await;
                 ui.stop_thinking_indicator(indicator);
                 ui.print_status("Command generation complete.");
+                ui.print_command(&result?);
+            }
+            Commands::Ask { question } => {
+                let config = core::ConfigManager::load_configuration()?;
+                let indicator = ui.start_thinking_indicator();
+                let result = transport.generate(&config, &core::build_ask_prompt(question), &[]).await;
+                ui.stop_thinking_indicator(indicator);
+                ui.print_markdown(&result?);
+            }
+            Commands::Translate { text, to } => {
+                let config = core::ConfigManager::load_configuration()?;
+                let input = match text {
+                    Some(text) => text.clone(),
+                    None => std::io::read_to_string(std::io::stdin())
+                        .map_err(|e| ShellmindError::Other(format!("Failed to read stdin: {}", e)))?,
+                };
+                if input.trim().is_empty() {
+                    return Err(ShellmindError::Other("Nothing to translate; pass the text or pipe output into `shellmind translate`".to_string()));
+                }
+                let (input, truncated) = core::translate::truncate(&input);
+                if truncated {
+                    eprintln!("Only the first {} characters are translated.", core::translate::MAX_INPUT_CHARS);
+                }
+                let language = to.as_deref().unwrap_or(config.resolved_language());
+                let indicator = ui.start_thinking_indicator();
+                let result = transport.generate(&config, &core::translate::build_translation_prompt(input, language), &[]).await;
+                ui.stop_thinking_indicator(indicator);
+                println!("{}", result?.trim_end());
+            }
+            Commands::Review { staged, range, patch, output } => {
+                let config = core::ConfigManager::load_configuration()?;
+                let source = match (patch, range) {
+                    (Some(patch), _) => core::review::DiffSource::Patch(patch.into()),
+                    (None, Some(range)) => core::review::DiffSource::Range(range.clone()),
+                    (None, None) if *staged => core::review::DiffSource::Staged,
+                    (None, None) => core::review::DiffSource::Working,
+                };
+                let diff = core::review::collect_diff(&source)?;
+
+                let indicator = ui.start_thinking_indicator();
+                let result = transport.generate(&config, &core::review::build_review_prompt(&diff), &[]).await;
+                ui.stop_thinking_indicator(indicator);
+                let review = core::review::parse_review(&result?)?;
 
-                match result {
-                    Ok(command) => {
-                        ui.print_command(&command);
+                if !review.summary.is_empty() {
+                    println!("{}", review.summary.trim());
+                }
+                for file in &review.files {
+                    ui.print_section(&file.path);
+                    for finding in &file.findings {
+                        let location = finding.line.map(|l| format!("line {}: ", l)).unwrap_or_default();
+                        ui.print_finding(&finding.severity, &format!("{} {}{}", finding.category, location, finding.message));
                     }
-                    Err(e) => {
-                        ui.print_error(&format!("Error generating command: {}", e));
+                }
+                println!("\n{} finding(s) in {} file(s).", review.finding_count(), review.files.len());
+                if let Some(path) = output {
+                    std::fs::write(path, review.to_markdown())
+                        .map_err(|e| ShellmindError::Other(format!("Failed to write review to '{}': {}", path, e)))?;
+                    println!("Review written to {}.", path);
+                }
+            }
+            Commands::Batch { file, output, concurrency, rate } => {
+                let config = core::ConfigManager::load_configuration()?;
+                let content = std::fs::read_to_string(file)
+                    .map_err(|e| ShellmindError::Other(format!("Failed to read batch file '{}': {}", file, e)))?;
+                let items = core::batch::parse_batch_file(&content)?;
+
+                ui.print_status(&format!("Running {} prompts (concurrency {}, {} req/min)...", items.len(), concurrency, rate));
+                let results = core::batch::run_batch(Arc::clone(&transport), &config, items, *concurrency, *rate).await;
+                let failed = results.iter().filter(|r| r.error.is_some()).count();
+
+                let mut lines = Vec::with_capacity(results.len());
+                for result in &results {
+                    lines.push(serde_json::to_string(result)?);
+                }
+                let jsonl = lines.join("\n") + "\n";
+                match output {
+                    Some(path) => std::fs::write(path, jsonl)
+                        .map_err(|e| ShellmindError::Other(format!("Failed to write batch results to '{}': {}", path, e)))?,
+                    None => print!("{}", jsonl),
+                }
+                ui.print_status(&format!("Batch complete: {} succeeded, {} failed.", results.len() - failed, failed));
+            }
+            Commands::Openapi { command } => match command {
+                OpenapiCommands::Import { spec, operations, auth_secret, base_url } => {
+                    let source = core::openapi::OpenApiSource {
+                        spec: std::fs::canonicalize(spec)
+                            .map_err(|e| ShellmindError::Other(format!("Failed to resolve spec path '{}': {}", spec, e)))?
+                            .to_string_lossy()
+                            .into_owned(),
+                        operations: operations.clone(),
+                        auth_secret: auth_secret.clone(),
+                        base_url: base_url.clone(),
+                    };
+                    let tools = core::openapi::load_openapi_tools(&source)?;
+                    for tool in &tools {
+                        println!("  {} - {}", core::BaseTool::name(tool), core::BaseTool::description(tool));
                     }
+
+                    let mut config = core::ConfigManager::load_configuration()?;
+                    config.openapi_tools.retain(|s| s.spec != source.spec);
+                    config.openapi_tools.push(source);
+                    core::ConfigManager::save_configuration(&config)?;
+                    println!("Imported {} tool(s).", tools.len());
+                }
+            },
+            #[cfg(feature = "server")]
+            Commands::LspIsh { stdio } => {
+                if !stdio {
+                    return Err(ShellmindError::Other("Only --stdio is supported".to_string()));
                 }
+                let config = core::ConfigManager::load_configuration()?;
+                let mut tools = core::ToolRegistry::from_config(&config)?;
+                tools.discover_tools().await?;
+                let mut server = core::editor::EditorServer::new(config, Arc::clone(&transport), tools, core::session::SessionManager::new());
+                server.run_stdio().await?;
             }
+            Commands::Secret { command } => match command {
+                SecretCommands::Set { key } => {
+                    let value = if std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+                        dialoguer::Password::new().with_prompt(format!("Value for '{}'", key)).interact()?
+                    } else {
+                        let mut value = String::new();
+                        std::io::Read::read_to_string(&mut std::io::stdin(), &mut value)
+                            .map_err(|e| ShellmindError::Other(format!("Failed to read the secret from stdin: {}", e)))?;
+                        value.trim_end_matches(['\r', '\n']).to_string()
+                    };
+                    if value.is_empty() {
+                        return Err(ShellmindError::Other("The secret is empty".to_string()));
+                    }
+                    extensions::secure_storage::store_secret(key, &value).map_err(ShellmindError::Other)?;
+                    println!("Secret '{}' stored.", key);
+                }
+                SecretCommands::Delete { key } => {
+                    extensions::secure_storage::delete_secret(key).map_err(ShellmindError::Other)?;
+                    println!("Secret '{}' deleted.", key);
+                }
+            },
+            Commands::Profile { command } => match command {
+                ProfileCommands::Export { file } => {
+                    let config = core::ConfigManager::load_configuration()?;
+                    let profile = core::profile::Profile::collect(&config)?;
+                    profile.export(std::path::Path::new(file))?;
+                    println!("Profile exported to {} ({} memory file(s)).", file, profile.memory.len());
+                }
+                ProfileCommands::Import { file, yes } => {
+                    let current = core::ConfigManager::load_configuration()?;
+                    let profile = core::profile::Profile::load(std::path::Path::new(file))?;
+                    // A shared profile must not quietly change what runs unasked or where data goes
+                    let changes = profile.security_changes(&current)?;
+                    if !changes.is_empty() && !yes {
+                        println!("The profile changes security-relevant settings:");
+                        for change in &changes {
+                            println!("  {}", change);
+                        }
+                        let confirmed = dialoguer::Confirm::new()
+                            .with_prompt("Apply these settings?")
+                            .default(false)
+                            .interact()
+                            .map_err(|e| ShellmindError::Other(format!("Failed to read confirmation: {}", e)))?;
+                        if !confirmed {
+                            return Err(ShellmindError::Rejected);
+                        }
+                    }
+                    let config = profile.apply(&current)?;
+                    println!(
+                        "Profile imported: {} custom tool(s), {} allowed command(s), {} OpenAPI source(s).",
+                        config.custom_tools.len(),
+                        config.allowed_commands.len(),
+                        config.openapi_tools.len()
+                    );
+                }
+            },
+            Commands::Doctor => {
+                let results = core::doctor::run_checks(core::ConfigManager::load_configuration()).await;
+                let mut failed = 0;
+                for result in &results {
+                    if result.ok {
+                        ui.print_status(&format!("OK   {}: {}", result.name, result.detail));
+                        continue;
+                    }
+                    let label = if result.optional { "WARN" } else { "FAIL" };
+                    ui.print_error(&format!("{} {}: {}", label, result.name, result.detail));
+                    if let Some(fix) = &result.fix {
+                        println!("     Fix: {}", fix);
+                    }
+                    if !result.optional {
+                        failed += 1;
+                    }
+                }
+                if failed > 0 {
+                    return Err(ShellmindError::Other(format!("{} check(s) failed", failed)));
+                }
+                println!("All required checks passed.");
+            }
+            Commands::Init { shell } => {
+                print!("{}", core::shell_env::init_script(shell)?);
+            }
+            Commands::Test { .. } => {
+                return Err(ShellmindError::Other("The test workflow runs in the shellmind binary".to_string()));
+            }
+            Commands::Script { .. } => {
+                return Err(ShellmindError::Other("Script generation runs in the shellmind binary".to_string()));
+            }
+            Commands::Run { .. } => {
+                return Err(ShellmindError::Other("One-shot execution runs in the shellmind binary".to_string()));
+            }
+            Commands::Suggest { .. } => {
+                return Err(ShellmindError::Other("Command suggestions run in the shellmind binary".to_string()));
+            }
+            Commands::TfReview { .. } => {
+                return Err(ShellmindError::Other("Terraform plan reviews run in the shellmind binary".to_string()));
+            }
+            Commands::Usage => {
+                let config = core::ConfigManager::load_configuration()?;
+                let ledger = core::budget::Ledger::load()?;
+                let limit = |budget: u64| if budget == 0 { "unlimited".to_string() } else { budget.to_string() };
+                let cost_limit = |budget: f64| if budget == 0.0 { "unlimited".to_string() } else { format!("${:.2}", budget) };
+                let mut table = ui::table::Table::new(["Period", "Tokens", "In", "Out", "Token budget", "Cost", "Cost budget"]);
+                for (label, usage, tokens, cost) in [
+                    ("Today", ledger.today(), config.daily_token_budget, config.daily_cost_budget),
+                    ("This month", ledger.this_month(), config.monthly_token_budget, config.monthly_cost_budget),
+                ] {
+                    table.add_row([
+                        label.to_string(),
+                        usage.tokens().to_string(),
+                        usage.input_tokens.to_string(),
+                        usage.output_tokens.to_string(),
+                        limit(tokens),
+                        format!("${:.4}", usage.cost),
+                        cost_limit(cost),
+                    ]);
+                }
+                ui.print_table(&table);
+                if let Some(reason) = core::budget::exceeded(&config, &ledger) {
+                    ui.print_error(&format!("{}; budget_policy is {:?}", reason, config.budget_policy));
+                }
+            }
+            Commands::Bench { models, runs, prompts } => {
+                let config = core::ConfigManager::load_configuration()?;
+                let targets = if models.is_empty() {
+                    core::bench::default_targets(&config)
+                } else {
+                    models.iter().map(|m| core::bench::Target::parse(m)).collect::<Result<Vec<_>, _>>()?
+                };
+                let prompts: Vec<String> = match prompts {
+                    Some(path) => std::fs::read_to_string(path)
+                        .map_err(|e| ShellmindError::Other(format!("Failed to read prompts '{}': {}", path, e)))?
+                        .lines()
+                        .map(str::trim)
+                        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                        .map(str::to_string)
+                        .collect(),
+                    None => core::bench::PROMPTS.iter().map(|p| p.to_string()).collect(),
+                };
+                if prompts.is_empty() {
+                    return Err(ShellmindError::Other("No prompts to run".to_string()));
+                }
+                ui.print_status(&format!(
+                    "Running {} prompt(s) x {} run(s) against {} model(s); usage counts toward your budgets",
+                    prompts.len(),
+                    (*runs).max(1),
+                    targets.len()
+                ));
+
+                let results = core::bench::run(&config, &targets, &prompts, *runs, |config, prompt| {
+                    let transport = Arc::clone(&transport);
+                    async move {
+                        let history = core::context::initial_history(&config);
+                        transport.generate(&config, &prompt, &history).await
+                    }
+                }).await;
+
+                let seconds = |d: Option<std::time::Duration>| d.map_or("-".to_string(), |d| format!("{:.2}s", d.as_secs_f64()));
+                let mut table = ui::table::Table::new(["Model", "Requests", "Failed", "Median", "p90", "Avg in", "Avg out", "Cost/request", "Total cost"]);
+                for result in &results {
+                    let succeeded = result.latencies.len().max(1) as u64;
+                    table.add_row([
+                        result.target.label(),
+                        result.requests().to_string(),
+                        result.failures.to_string(),
+                        seconds(result.percentile(0.5)),
+                        seconds(result.percentile(0.9)),
+                        (result.input_tokens / succeeded).to_string(),
+                        (result.output_tokens / succeeded).to_string(),
+                        result.cost.map_or("unknown".to_string(), |c| format!("${:.5}", c / succeeded as f64)),
+                        result.cost.map_or("unknown".to_string(), |c| format!("${:.4}", c)),
+                    ]);
+                }
+                ui.print_table(&table);
+                for result in results.iter().filter(|r| r.failures > 0) {
+                    ui.print_error(&format!("{}: {} request(s) failed; last error: {}", result.target.label(), result.failures, result.last_error.as_deref().unwrap_or("")));
+                }
+                println!("Token counts are estimates (~4 characters per token); set model_prices for models without a known price.");
+            }
+            Commands::Prompts { command: PromptsCommands::Report } => {
+                let config = core::ConfigManager::load_configuration()?;
+                let stats = core::prompt_trials::summarize(&core::prompt_trials::load()?, &core::prompt_trials::variant_names(&config));
+                let mut table = ui::table::Table::new(["Variant", "Sessions", "Requests", "Succeeded", "Failed", "Declined", "Good", "Bad", "Score"]);
+                for variant in &stats {
+                    table.add_row([
+                        variant.name.clone(),
+                        variant.sessions.to_string(),
+                        variant.requests.to_string(),
+                        variant.succeeded.to_string(),
+                        variant.failed.to_string(),
+                        variant.declined.to_string(),
+                        variant.good.to_string(),
+                        variant.bad.to_string(),
+                        if variant.signals() == 0 { "-".to_string() } else { format!("{:.2}", variant.score()) },
+                    ]);
+                }
+                ui.print_table(&table);
+                match core::prompt_trials::best(&stats) {
+                    Some(best) => ui.print_status(&format!("`{}` performs best so far ({:.0}% positive signals)", best.name, best.score() * 100.0)),
+                    None => ui.print_status(&format!(
+                        "Not enough data yet; at least two variants need {} signals each",
+                        core::prompt_trials::MIN_SIGNALS
+                    )),
+                }
+                if config.prompt_sampling == core::prompt_trials::PromptSampling::Off {
+                    ui.print_status("prompt_sampling is off; set it to `random` or `best` to collect data");
+                }
+            }
+            Commands::Undo { id, list } => {
+                if *list {
+                    let mut table = ui::table::Table::new(["ID", "Created", "Files", "Description"]);
+                    for snapshot in core::undo::list()? {
+                        table.add_row([
+                            snapshot.id.clone(),
+                            snapshot.created_at.format("%Y-%m-%d %H:%M").to_string(),
+                            snapshot.files.len().to_string(),
+                            snapshot.description.clone(),
+                        ]);
+                    }
+                    ui.print_table(&table);
+                } else {
+                    let snapshot = core::undo::restore(id.as_deref())?;
+                    for file in &snapshot.files {
+                        println!("Restored {}", file.path.display());
+                    }
+                    ui.print_status(&format!("Undid: {}", snapshot.description));
+                }
+            }
+            Commands::Session { command } => match command {
+                SessionCommands::List { query, limit } => {
+                    let mut sessions = core::session::list_sessions()?;
+                    if let Some(query) = query {
+                        sessions = core::session::search_sessions(sessions, query);
+                    }
+                    if sessions.is_empty() {
+                        println!("No saved sessions.");
+                        return Ok(());
+                    }
+                    let mut table = ui::table::Table::new(["Session", "Title", "Date", "Turns", "Models"]);
+                    for session in sessions.into_iter().take(*limit) {
+                        table.add_row([
+                            session.id,
+                            session.title,
+                            session.created_at.format("%Y-%m-%d %H:%M").to_string(),
+                            session.turns.to_string(),
+                            session.models.join(", "),
+                        ]);
+                    }
+                    ui.print_table(&table);
+                }
+                SessionCommands::Share { id, gist, paste, public, output, yes } => {
+                    let config = core::ConfigManager::load_configuration()?;
+                    let id = match id {
+                        Some(id) => id.clone(),
+                        None => core::session::list_sessions()?
+                            .into_iter()
+                            .next()
+                            .map(|s| s.id)
+                            .ok_or_else(|| ShellmindError::Other("No saved sessions.".to_string()))?,
+                    };
+                    let session = core::session::SessionFile::load(&id)?;
+                    let markdown = core::share::render(&session, core::redact::active());
+                    if !gist && !paste {
+                        match output {
+                            Some(path) => {
+                                std::fs::write(path, &markdown).map_err(|e| ShellmindError::Other(format!("Failed to write '{}': {}", path, e)))?;
+                                ui.print_status(&format!("Wrote the redacted transcript to {}", path));
+                            }
+                            None => println!("{}", markdown),
+                        }
+                        return Ok(());
+                    }
+                    if let Some(path) = output {
+                        std::fs::write(path, &markdown).map_err(|e| ShellmindError::Other(format!("Failed to write '{}': {}", path, e)))?;
+                    }
+                    // Once uploaded the transcript is out of our hands, so it is never sent unasked
+                    if !yes {
+                        let destination = if *gist {
+                            format!("a {} gist", if *public { "public" } else { "secret" })
+                        } else {
+                            config.share_paste_url.clone()
+                        };
+                        let turns = session.transcript.iter().filter(|c| c.role == "user").count();
+                        let confirmed = dialoguer::Confirm::new()
+                            .with_prompt(format!("Upload the redacted transcript ({} turn(s), {} bytes) to {}? Review it first with `shellmind session share {}`", turns, markdown.len(), destination, id))
+                            .default(false)
+                            .interact()
+                            .map_err(|e| ShellmindError::Other(format!("Failed to read confirmation: {}", e)))?;
+                        if !confirmed {
+                            return Err(ShellmindError::Rejected);
+                        }
+                    }
+                    let url = if *gist {
+                        core::share::upload_gist(&config, &session, &markdown, *public).await?
+                    } else {
+                        core::share::upload_paste(&config, &markdown).await?
+                    };
+                    println!("{}", url);
+                }
+                SessionCommands::Recover { list: true, .. } => {
+                    let checkpoints = core::recovery::recoverable()?;
+                    if checkpoints.is_empty() {
+                        println!("No crashed sessions.");
+                        return Ok(());
+                    }
+                    let mut table = ui::table::Table::new(["Session", "Updated", "State", "Directory"]);
+                    for checkpoint in checkpoints {
+                        let pending = match (&checkpoint.pending_reply, &checkpoint.pending_input) {
+                            (Some(_), _) => "awaiting approval",
+                            (None, Some(_)) => "mid-turn",
+                            (None, None) => "idle",
+                        };
+                        table.add_row([
+                            checkpoint.session_id.clone(),
+                            checkpoint.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+                            pending.to_string(),
+                            checkpoint.working_dir.display().to_string(),
+                        ]);
+                    }
+                    ui.print_table(&table);
+                }
+                SessionCommands::Recover { .. } => {
+                    return Err(ShellmindError::Other("Session recovery runs in the shellmind binary".to_string()));
+                }
+            },
+            #[cfg(feature = "server")]
+            Commands::Daemon => {
+                let config = core::ConfigManager::load_configuration()?;
+                core::daemon::run(config, Arc::clone(&transport)).await?;
+            }
+            #[cfg(feature = "server")]
+            Commands::InstallService { uninstall, print } => {
+                if *print {
+                    let manager = core::service::ServiceManager::detect()?;
+                    let executable = std::env::current_exe()
+                        .map_err(|e| ShellmindError::Other(format!("Failed to locate the shellmind executable: {}", e)))?;
+                    print!("{}", manager.definition(&executable)?);
+                } else if *uninstall {
+                    let path = core::service::uninstall()?;
+                    println!("Service stopped and {} removed.", path.display());
+                } else {
+                    let path = core::service::install()?;
+                    println!("Service installed at {} and started.", path.display());
+                }
+            }
+            Commands::Workspace { command } => match command {
+                WorkspaceCommands::Add { path } => {
+                    let mut config = core::ConfigManager::load_configuration()?;
+                    let root = core::workspace::Workspace::add(&mut config, std::path::Path::new(path))?;
+                    core::ConfigManager::save_configuration(&config)?;
+                    println!("Added workspace root {}.", root.display());
+                }
+                WorkspaceCommands::Remove { path } => {
+                    let mut config = core::ConfigManager::load_configuration()?;
+                    if !core::workspace::Workspace::remove(&mut config, std::path::Path::new(path)) {
+                        return Err(ShellmindError::Other(format!("'{}' is not a registered workspace root", path)));
+                    }
+                    core::ConfigManager::save_configuration(&config)?;
+                    println!("Removed workspace root {}.", path);
+                }
+                WorkspaceCommands::List => {
+                    let config = core::ConfigManager::load_configuration()?;
+                    let mut table = ui::table::Table::new(["Root", "State"]);
+                    for root in core::workspace::Workspace::from_config(&config).roots {
+                        table.add_row([root.display().to_string(), "ok".to_string()]);
+                    }
+                    for root in config.workspace_roots.iter().filter(|r| !std::path::Path::new(r).is_dir()) {
+                        table.add_row([root.clone(), "missing".to_string()]);
+                    }
+                    ui.print_table(&table);
+                }
+            },
+            Commands::Purge { sessions, history, memory, feedback, all, yes } => {
+                use core::retention::DataKind;
+                let kinds: Vec<DataKind> = if *all {
+                    DataKind::ALL.to_vec()
+                } else {
+                    [(*sessions, DataKind::Sessions), (*history, DataKind::History), (*memory, DataKind::Memory), (*feedback, DataKind::Feedback), (*feedback, DataKind::PromptTrials)]
+                        .into_iter()
+                        .filter(|(selected, _)| *selected)
+                        .map(|(_, kind)| kind)
+                        .collect()
+                };
+                if kinds.is_empty() {
+                    return Err(ShellmindError::Other("Nothing to purge; pass --sessions, --history, --memory, --feedback or --all".to_string()));
+                }
+                if !yes {
+                    let confirmed = dialoguer::Confirm::new()
+                        .with_prompt(format!("Permanently delete {:?}?", kinds))
+                        .default(false)
+                        .interact()
+                        .map_err(|e| ShellmindError::Other(format!("Failed to read confirmation: {}", e)))?;
+                    if !confirmed {
+                        return Err(ShellmindError::Rejected);
+                    }
+                }
+                for kind in kinds {
+                    let removed = core::retention::purge(kind)?;
+                    println!("{:?}: {} file(s) deleted.", kind, removed);
+                }
+            }
+            Commands::Eval { command } => match command {
+                EvalCommands::Run { suite } => {
+                    let config = core::ConfigManager::load_configuration()?;
+                    let content = std::fs::read_to_string(suite)
+                        .map_err(|e| ShellmindError::Other(format!("Failed to read eval suite '{}': {}", suite, e)))?;
+                    let suite = core::eval::EvalSuite::from_yaml(&content)?;
+
+                    let outcomes = core::eval::run_suite(&suite, |prompt| {
+                        let config = config.clone();
+                        let transport = Arc::clone(&transport);
+                        async move { transport.generate(&config, &prompt, &[]).await }
+                    }).await?;
+
+                    let failed = outcomes.iter().filter(|o| !o.passed).count();
+                    for outcome in &outcomes {
+                        if outcome.passed {
+                            ui.print_status(&format!("PASS {}", outcome.name));
+                        } else {
+                            ui.print_error(&format!(
+                                "FAIL {}: `{}` {}",
+                                outcome.name,
+                                outcome.response,
+                                outcome.reason.as_deref().unwrap_or("")
+                            ));
+                        }
+                    }
+                    println!("{} passed, {} failed", outcomes.len() - failed, failed);
+                    if failed > 0 {
+                        return Err(ShellmindError::Other(format!("{} eval case(s) failed", failed)));
+                    }
+                }
+            },
         }
         Ok(())
     }
@@ -0,0 +1,285 @@
+//! Planning and accounting for multi-step agent runs. A model reply may hold several tool
+//! calls, one per line. They form a task graph executed in stages: consecutive calls that
+//! only read run concurrently, and every other call runs on its own, in the order given, so
+//! a write never races the reads around it. A run is bounded by a step count, a per-step
+//! timeout, a wall-clock limit and a token budget (`agent_*` settings). A watchdog
+//! (`[agent]` table) halts runs that loop, flood output, touch too many files or keep
+//! failing, so a human can decide how to go on. What happened is kept as a trace that ends
+//! up in the audit log.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ShellmindConfig, ToolResult};
+
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub name: String,
+    pub params: serde_json::Value,
+}
+
+/// The tool calls in `reply`, one per non-empty line. Empty when any line is something
+/// else, since the reply is then an answer for the user.
+pub fn parse_tool_calls(reply: &str) -> Vec<ToolCall> {
+    let lines: Vec<&str> = reply.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    let calls: Vec<ToolCall> = lines
+        .iter()
+        .map_while(|line| crate::parse_tool_call(line))
+        .map(|(name, params)| ToolCall { name, params })
+        .collect();
+    if calls.len() == lines.len() {
+        calls
+    } else {
+        Vec::new()
+    }
+}
+
+/// Groups calls into stages run one after another. `concurrent` tells which calls may share
+/// a stage with their neighbours.
+pub fn stages(calls: &[ToolCall], concurrent: impl Fn(&ToolCall) -> bool) -> Vec<Vec<usize>> {
+    let mut stages: Vec<Vec<usize>> = Vec::new();
+    let mut open = false;
+    for (index, call) in calls.iter().enumerate() {
+        let parallel = concurrent(call);
+        match stages.last_mut() {
+            Some(stage) if parallel && open => stage.push(index),
+            _ => stages.push(vec![index]),
+        }
+        open = parallel;
+    }
+    stages
+}
+
+#[derive(Debug, Clone)]
+pub struct AgentLimits {
+    pub max_steps: usize,
+    pub step_timeout: Duration,
+    pub max_duration: Option<Duration>,
+    /// Input and output tokens for the whole run.
+    pub token_budget: Option<u64>,
+}
+
+impl AgentLimits {
+    pub fn from_config(config: &ShellmindConfig) -> Self {
+        AgentLimits {
+            max_steps: config.agent_max_steps.max(1),
+            step_timeout: Duration::from_secs(config.agent_step_timeout_secs.max(1)),
+            max_duration: (config.agent_max_duration_secs > 0).then(|| Duration::from_secs(config.agent_max_duration_secs)),
+            token_budget: (config.agent_token_budget > 0).then_some(config.agent_token_budget),
+        }
+    }
+}
+
+/// Watchdog thresholds, set in the `[agent]` table of `config.toml`; 0 turns a check off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    /// Times the same tool may be called with the same parameters in one run.
+    pub repeated_calls: usize,
+    /// Bytes by which a step's tool output may exceed the previous step's.
+    pub output_growth_bytes: usize,
+    /// Distinct files the run may create or modify.
+    pub max_file_modifications: usize,
+    /// Tool calls in a row that may fail.
+    pub repeated_failures: usize,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        WatchdogConfig { repeated_calls: 3, output_growth_bytes: 512 * 1024, max_file_modifications: 25, repeated_failures: 3 }
+    }
+}
+
+/// Watches the calls and results of a run for runaway behaviour.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    config: WatchdogConfig,
+    calls: HashMap<String, usize>,
+    failures: usize,
+    modified: HashSet<PathBuf>,
+    /// Files already accepted by the user when they let the run go on.
+    modified_allowance: usize,
+    previous_output: usize,
+    step_output: usize,
+}
+
+impl Watchdog {
+    pub fn new(config: WatchdogConfig) -> Self {
+        Watchdog { config, ..Default::default() }
+    }
+
+    pub fn observe_call(&mut self, call: &ToolCall) {
+        *self.calls.entry(format!("{} {}", call.name, call.params)).or_default() += 1;
+    }
+
+    pub fn observe_result(&mut self, result: &ToolResult) {
+        self.failures = if result.is_error { self.failures + 1 } else { 0 };
+        self.modified.extend(result.artifacts.iter().cloned());
+        self.step_output += result.display.len();
+    }
+
+    /// Why the run should halt after the step just observed, if it should.
+    pub fn end_step(&mut self) -> Option<String> {
+        let (previous, output) = (self.previous_output, std::mem::take(&mut self.step_output));
+        self.previous_output = output;
+        let config = &self.config;
+        if config.repeated_calls > 0 {
+            if let Some((call, count)) = self.calls.iter().filter(|(_, count)| **count >= config.repeated_calls).max_by_key(|(_, count)| **count) {
+                let tool = call.split_whitespace().next().unwrap_or_default();
+                return Some(format!("`{}` was called {} times with the same parameters", tool, count));
+            }
+        }
+        if config.repeated_failures > 0 && self.failures >= config.repeated_failures {
+            return Some(format!("{} tool calls in a row failed", self.failures));
+        }
+        if config.max_file_modifications > 0 && self.modified.len() >= self.modified_allowance + config.max_file_modifications {
+            return Some(format!("{} files were created or modified", self.modified.len()));
+        }
+        if config.output_growth_bytes > 0 && output > previous + config.output_growth_bytes {
+            return Some(format!("tool output grew from {} to {} bytes in one step", previous, output));
+        }
+        None
+    }
+
+    /// Starts the checks over after the user lets the run continue.
+    pub fn reset(&mut self) {
+        self.calls.clear();
+        self.failures = 0;
+        self.modified_allowance = self.modified.len();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The model answered without a tool call.
+    Completed,
+    /// A call was refused or could not be run, which ends the run.
+    Stopped,
+    StepLimit,
+    TimeLimit,
+    TokenBudget,
+    Cancelled,
+    /// A model request failed.
+    Failed,
+    /// The watchdog halted the run and the user stopped it.
+    Watchdog,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallStatus {
+    Succeeded,
+    Failed,
+    Refused,
+    TimedOut,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CallTrace {
+    pub tool: String,
+    pub stage: usize,
+    /// Ran alongside other calls of its stage.
+    pub concurrent: bool,
+    pub status: CallStatus,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepTrace {
+    pub step: usize,
+    pub calls: Vec<CallTrace>,
+}
+
+/// Machine-readable record of a run, written to the audit log as an `agent_run` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentTrace {
+    pub outcome: Outcome,
+    pub steps: Vec<StepTrace>,
+    pub elapsed_ms: u64,
+    pub tokens: u64,
+}
+
+fn tokens_used() -> u64 {
+    let usage = crate::budget::session_usage();
+    usage.input_tokens + usage.output_tokens
+}
+
+/// A run in progress: its limits, the clock and the trace so far.
+pub struct AgentRun {
+    pub limits: AgentLimits,
+    pub watchdog: Watchdog,
+    started: Instant,
+    tokens_at_start: u64,
+    steps: Vec<StepTrace>,
+}
+
+impl AgentRun {
+    pub fn start(limits: AgentLimits, watchdog: WatchdogConfig) -> Self {
+        AgentRun { limits, watchdog: Watchdog::new(watchdog), started: Instant::now(), tokens_at_start: tokens_used(), steps: Vec::new() }
+    }
+
+    pub fn tokens(&self) -> u64 {
+        tokens_used().saturating_sub(self.tokens_at_start)
+    }
+
+    /// The limit that stops the run before its next step, if any.
+    pub fn exhausted(&self) -> Option<Outcome> {
+        if self.steps.len() >= self.limits.max_steps {
+            return Some(Outcome::StepLimit);
+        }
+        if self.limits.max_duration.map_or(false, |max| self.started.elapsed() >= max) {
+            return Some(Outcome::TimeLimit);
+        }
+        if self.limits.token_budget.map_or(false, |budget| self.tokens() >= budget) {
+            return Some(Outcome::TokenBudget);
+        }
+        None
+    }
+
+    /// Time a stage may take: the step timeout, cut short by the wall-clock limit.
+    pub fn stage_timeout(&self) -> Duration {
+        match self.limits.max_duration {
+            Some(max) => self.limits.step_timeout.min(max.saturating_sub(self.started.elapsed())),
+            None => self.limits.step_timeout,
+        }
+    }
+
+    pub fn begin_step(&mut self) {
+        let step = self.steps.len() + 1;
+        self.steps.push(StepTrace { step, calls: Vec::new() });
+    }
+
+    pub fn record(&mut self, call: CallTrace) {
+        if let Some(step) = self.steps.last_mut() {
+            step.calls.push(call);
+        }
+    }
+
+    pub fn finish(self, outcome: Outcome) -> AgentTrace {
+        let tokens = self.tokens();
+        AgentTrace { outcome, steps: self.steps, elapsed_ms: self.started.elapsed().as_millis() as u64, tokens }
+    }
+}
+
+/// Feeds the results of one step back to the model.
+pub fn build_results_prompt(results: &[(String, ToolResult)]) -> String {
+    if let [(name, result)] = results {
+        return crate::testing::build_tool_result_prompt(name, result);
+    }
+    let mut prompt = String::new();
+    for (name, result) in results {
+        let status = if result.is_error { "failed" } else { "succeeded" };
+        let content = match &result.llm_content {
+            serde_json::Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        prompt.push_str(&format!("Tool `{}` {}:\n{}\n\n", name, status, content));
+    }
+    prompt.push_str("Continue with the next tool calls, or reply with a short explanation when done.");
+    prompt
+}
@@ -0,0 +1,38 @@
+//! Settings for the shared `gemini_client::GeminiClient`, taken from the config, so model
+//! requests reuse pooled connections instead of reconnecting each time.
+
+use std::time::Duration;
+
+use gemini_client::{ClientOptions, GeminiClient, GenerateRequest};
+
+use crate::{GeminiContent, InlineData, ShellmindConfig, ShellmindError};
+
+pub fn client_options(config: &ShellmindConfig) -> ClientOptions {
+    ClientOptions {
+        request_timeout: Duration::from_secs(config.request_timeout_secs),
+        connect_timeout: Duration::from_secs(config.connect_timeout_secs),
+        keepalive_interval: Duration::from_secs(config.keepalive_interval_secs),
+        ..ClientOptions::default()
+    }
+}
+
+pub fn new_client(config: &ShellmindConfig) -> Result<GeminiClient, ShellmindError> {
+    Ok(GeminiClient::new(client_options(config))?)
+}
+
+/// A request for `prompt` with the configured model, key and temperature.
+pub fn generate_request<'a>(
+    config: &'a ShellmindConfig,
+    prompt: &'a str,
+    images: &'a [InlineData],
+    history: &'a [GeminiContent],
+) -> GenerateRequest<'a> {
+    GenerateRequest {
+        api_key: &config.api_key,
+        model: &config.model_name,
+        temperature: config.temperature,
+        prompt,
+        images,
+        history,
+    }
+}
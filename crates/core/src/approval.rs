@@ -0,0 +1,100 @@
+//! What a user sees before approving a tool call: its description, full parameters and,
+//! for file tools, the paths and diff involved.
+
+use serde_json::Value;
+
+use crate::BaseTool;
+
+/// Diff lines shown before the rest are elided.
+const MAX_DIFF_LINES: usize = 80;
+
+#[derive(Debug, Clone)]
+pub struct ApprovalPreview {
+    pub title: String,
+    pub description: String,
+    /// Parameters as pretty-printed JSON.
+    pub params: String,
+    /// Unified diff or affected paths; diff lines keep their `+`/`-` prefix.
+    pub details: Vec<String>,
+}
+
+pub fn build_preview(tool: &dyn BaseTool, params: &Value) -> ApprovalPreview {
+    ApprovalPreview {
+        title: tool.display_name().to_string(),
+        description: tool.get_description(params),
+        params: serde_json::to_string_pretty(params).unwrap_or_else(|_| params.to_string()),
+        details: file_details(tool.name(), params),
+    }
+}
+
+fn str_param<'a>(params: &'a Value, key: &str) -> Option<&'a str> {
+    params.get(key).and_then(|v| v.as_str())
+}
+
+/// Unified diff between two versions of a file, cut to `MAX_DIFF_LINES`.
+pub fn unified_diff(path: &str, old: &str, new: &str) -> Vec<String> {
+    let diff = similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string();
+    let mut lines: Vec<String> = diff.lines().map(str::to_string).collect();
+    if lines.len() > MAX_DIFF_LINES {
+        let hidden = lines.len() - MAX_DIFF_LINES;
+        lines.truncate(MAX_DIFF_LINES);
+        lines.push(format!("... {} more diff line(s)", hidden));
+    }
+    lines
+}
+
+fn file_details(tool_name: &str, params: &Value) -> Vec<String> {
+    match tool_name {
+        "edit_file" => {
+            let (Some(path), Some(old), Some(new)) = (str_param(params, "file_path"), str_param(params, "old_string"), str_param(params, "new_string")) else {
+                return Vec::new();
+            };
+            match std::fs::read_to_string(path) {
+                Ok(content) if content.contains(old) => unified_diff(path, &content, &content.replace(old, new)),
+                Ok(_) => vec![format!("{}: the text to replace was not found", path)],
+                Err(e) => vec![format!("{}: {}", path, e)],
+            }
+        }
+        "multi_edit" => {
+            let Ok(params) = crate::parse_tool_params::<crate::tools::MultiEditParams>(tool_name, params) else {
+                return Vec::new();
+            };
+            match crate::tools::plan_multi_edit(&params) {
+                Ok(edits) if edits.is_empty() => vec!["No file matches".to_string()],
+                Ok(edits) => {
+                    let replacements: usize = edits.iter().map(|e| e.replacements).sum();
+                    let mut details = vec![format!("{} replacement(s) in {} file(s)", replacements, edits.len())];
+                    for edit in &edits {
+                        details.extend(unified_diff(&edit.path.display().to_string(), &edit.original, &edit.updated));
+                    }
+                    details
+                }
+                Err(e) => vec![e.to_string()],
+            }
+        }
+        "write_file" => {
+            let (Some(path), Some(content)) = (str_param(params, "path"), str_param(params, "content")) else {
+                return Vec::new();
+            };
+            match std::fs::read_to_string(path) {
+                Ok(existing) => unified_diff(path, &existing, content),
+                Err(_) => vec![format!("New file: {} ({} line(s))", path, content.lines().count())],
+            }
+        }
+        _ => {
+            let mut paths: Vec<String> = ["path", "file_path"]
+                .iter()
+                .filter_map(|key| str_param(params, key))
+                .map(|p| format!("Path: {}", p))
+                .collect();
+            if let Some(list) = params.get("paths").and_then(|v| v.as_array()) {
+                paths.extend(list.iter().filter_map(|p| p.as_str()).map(|p| format!("Path: {}", p)));
+            }
+            paths
+        }
+    }
+}
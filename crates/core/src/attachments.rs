@@ -0,0 +1,81 @@
+//! Images sent to the model alongside a prompt: files referenced in the prompt and
+//! clipboard contents pasted with `/paste-image`.
+
+use base64::Engine;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{InlineData, ShellmindError};
+
+/// The API rejects inline requests larger than 20 MB; keep headroom for the prompt.
+const MAX_IMAGE_BYTES: usize = 15 * 1024 * 1024;
+
+fn mime_type(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "webp" => Some("image/webp"),
+        "heic" => Some("image/heic"),
+        "heif" => Some("image/heif"),
+        _ => None,
+    }
+}
+
+fn inline(mime_type: &str, bytes: &[u8]) -> Result<InlineData, ShellmindError> {
+    if bytes.is_empty() {
+        return Err(ShellmindError::Other("The image is empty".to_string()));
+    }
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(ShellmindError::Other(format!("The image is {} MB; the limit is {} MB", bytes.len() / (1024 * 1024), MAX_IMAGE_BYTES / (1024 * 1024))));
+    }
+    Ok(InlineData {
+        mime_type: mime_type.to_string(),
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+    })
+}
+
+pub fn load_image(path: &Path) -> Result<InlineData, ShellmindError> {
+    let mime_type = mime_type(path)
+        .ok_or_else(|| ShellmindError::Other(format!("'{}' is not a supported image (png, jpeg, webp, heic)", path.display())))?;
+    if crate::redact::active().blocks(path) {
+        return Err(ShellmindError::Blocked(format!("'{}' is {}", path.display(), crate::redact::NOTE)));
+    }
+    let bytes = std::fs::read(path)
+        .map_err(|e| ShellmindError::Other(format!("Failed to read image '{}': {}", path.display(), e)))?;
+    inline(mime_type, &bytes)
+}
+
+/// Existing image files mentioned in `prompt`, e.g. `what does ~/shot.png mean`.
+pub fn referenced_images(prompt: &str) -> Vec<PathBuf> {
+    prompt
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| c == '"' || c == '\'' || c == ',' || c == '?' || c == '(' || c == ')'))
+        .map(|word| match word.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(word)),
+            None => PathBuf::from(word),
+        })
+        .filter(|path| mime_type(path).is_some() && path.is_file())
+        .collect()
+}
+
+/// Reads a PNG from the clipboard with `pngpaste` (macOS), `wl-paste` (Wayland) or `xclip` (X11).
+pub fn clipboard_image() -> Result<InlineData, ShellmindError> {
+    let candidates: &[(&str, &[&str])] = &[
+        ("pngpaste", &["-"]),
+        ("wl-paste", &["--no-newline", "--type", "image/png"]),
+        ("xclip", &["-selection", "clipboard", "-target", "image/png", "-out"]),
+    ];
+    let mut errors = Vec::new();
+    for (program, args) in candidates {
+        match Command::new(program).args(*args).output() {
+            Ok(output) if output.status.success() && !output.stdout.is_empty() => return inline("image/png", &output.stdout),
+            Ok(output) => errors.push(format!("{}: {}", program, String::from_utf8_lossy(&output.stderr).trim())),
+            Err(_) => {}
+        }
+    }
+    if errors.is_empty() {
+        Err(ShellmindError::Other("No clipboard tool found; install wl-clipboard, xclip or pngpaste".to_string()))
+    } else {
+        Err(ShellmindError::Other(format!("No image in the clipboard ({})", errors.join("; "))))
+    }
+}
@@ -0,0 +1,238 @@
+//! Audit trail of AI-initiated actions: executed commands, modified files, granted
+//! approvals and agent runs. Events go to daily files under `~/.shellmind/audit/` and can
+//! be forwarded to syslog and to a webhook signed with HMAC-SHA256. Each event is appended
+//! as its own line; with `encrypt_storage` that line is sealed on its own.
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::retention::DataKind;
+use crate::{persist, ShellmindConfig, ShellmindError};
+
+/// Secure storage key holding the webhook signing secret.
+pub const HMAC_SECRET_NAME: &str = "audit_hmac_key";
+
+/// Header carrying `sha256=<hex digest>` of the request body.
+pub const SIGNATURE_HEADER: &str = "X-Shellmind-Signature";
+
+/// Webhook deliveries still in flight.
+static PENDING_WEBHOOKS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    CommandExecuted,
+    FileModified,
+    ApprovalGranted,
+    CommandPreviewed,
+    /// A finished agent run; the detail is its `agent::AgentTrace`.
+    AgentRun,
+    /// An approval answered through `remote_approval`.
+    RemoteApproval,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub kind: AuditKind,
+    pub user: String,
+    pub host: String,
+    /// The command, file path or approved action.
+    pub subject: String,
+    /// Exit code, tool name or other detail, depending on `kind`.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub detail: serde_json::Value,
+    /// Recorded while execution was simulated; nothing actually ran.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub simulated: bool,
+}
+
+impl AuditEvent {
+    pub fn new(kind: AuditKind, subject: impl Into<String>, detail: serde_json::Value) -> Self {
+        AuditEvent {
+            timestamp: Utc::now(),
+            kind,
+            user: std::env::var("USER").unwrap_or_default(),
+            host: hostname(),
+            subject: crate::secrets::mask(&subject.into()),
+            detail,
+            simulated: crate::simulation::enabled(),
+        }
+    }
+}
+
+pub(crate) fn hostname() -> String {
+    std::fs::read_to_string("/etc/hostname")
+        .map(|h| h.trim().to_string())
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_default()
+}
+
+/// One stored line: the event itself, or with `encrypt_storage` the sealed event in
+/// base64, so each line stays independent and the file can be appended to.
+fn encode_line(line: &str) -> Result<String, ShellmindError> {
+    if crate::crypto::is_enabled() {
+        Ok(base64::engine::general_purpose::STANDARD.encode(persist::seal(line)?))
+    } else {
+        Ok(line.to_string())
+    }
+}
+
+fn decode_line(line: &str) -> Result<String, ShellmindError> {
+    if line.starts_with('{') {
+        return Ok(line.to_string());
+    }
+    let sealed = base64::engine::general_purpose::STANDARD
+        .decode(line)
+        .map_err(|e| ShellmindError::Other(format!("Invalid audit log line: {}", e)))?;
+    String::from_utf8(crate::crypto::decrypt(&sealed)?)
+        .map_err(|e| ShellmindError::Other(format!("Audit log line is not valid UTF-8: {}", e)))
+}
+
+/// Reads the events of one daily audit file, plain or encrypted.
+pub fn read_events(path: &std::path::Path) -> Result<Vec<AuditEvent>, ShellmindError> {
+    let data = std::fs::read(path)
+        .map_err(|e| ShellmindError::Other(format!("Failed to read '{}': {}", path.display(), e)))?;
+    // Earlier versions sealed the whole file at once
+    let text = if crate::crypto::is_encrypted(&data) {
+        persist::read_private(path)?
+    } else {
+        String::from_utf8(data)
+            .map_err(|e| ShellmindError::Other(format!("'{}' is not valid UTF-8: {}", path.display(), e)))?
+    };
+    let mut events = Vec::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let json = decode_line(line)?;
+        events.push(serde_json::from_str(&json)?);
+    }
+    Ok(events)
+}
+
+fn append_local(line: &str) -> Result<(), ShellmindError> {
+    let dir = DataKind::Audit.path()?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| ShellmindError::Other(format!("Failed to create audit directory: {}", e)))?;
+    // One file per day so retention can prune by age
+    let path = dir.join(format!("{}.jsonl", Utc::now().format("%Y-%m-%d")));
+    let encoded = encode_line(line)?;
+    persist::with_lock(&path, || {
+        // A file sealed as a whole by an earlier version is rewritten line by line once
+        if std::fs::read(&path).map(|data| crate::crypto::is_encrypted(&data)).unwrap_or(false) {
+            let mut lines = Vec::new();
+            for existing in persist::read_private(&path)?.lines().filter(|l| !l.trim().is_empty()) {
+                lines.push(encode_line(existing)?);
+            }
+            lines.push(encoded.clone());
+            return persist::atomic_write(&path, lines.join("\n") + "\n");
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to open '{}': {}", path.display(), e)))?;
+        file.write_all(format!("{}\n", encoded).as_bytes())
+            .map_err(|e| ShellmindError::Other(format!("Failed to write '{}': {}", path.display(), e)))
+    })
+}
+
+/// Sends an RFC 3164 message to the local syslog socket (facility auth, severity notice).
+fn send_syslog(line: &str) -> Result<(), ShellmindError> {
+    let socket = std::os::unix::net::UnixDatagram::unbound()
+        .map_err(|e| ShellmindError::Other(format!("Failed to open syslog socket: {}", e)))?;
+    let message = format!("<37>shellmind[{}]: {}", std::process::id(), line);
+    socket
+        .send_to(message.as_bytes(), "/dev/log")
+        .map_err(|e| ShellmindError::Other(format!("Failed to write to syslog: {}", e)))?;
+    Ok(())
+}
+
+/// Hex HMAC-SHA256 of `body`, as sent in `SIGNATURE_HEADER`.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+    let tag = hmac::sign(&key, body);
+    format!("sha256={}", tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+async fn send_webhook(url: String, body: String) -> Result<(), ShellmindError> {
+    let mut request = crate::http_client().post(&url).header("Content-Type", "application/json");
+    if let Ok(secret) = extensions::secure_storage::retrieve_secret(HMAC_SECRET_NAME) {
+        request = request.header(SIGNATURE_HEADER, sign(secret.as_bytes(), body.as_bytes()));
+    }
+    let response = request.body(body).send().await?;
+    if !response.status().is_success() {
+        return Err(ShellmindError::Other(format!("Audit webhook returned {}", response.status())));
+    }
+    Ok(())
+}
+
+/// Records `event` locally and forwards it as configured. Forwarding failures are
+/// reported on stderr and never block the action being audited.
+pub fn record(config: &ShellmindConfig, event: AuditEvent) {
+    let line = match serde_json::to_string(&event) {
+        Ok(line) => line,
+        Err(e) => return eprintln!("Warning: failed to serialize audit event: {}", e),
+    };
+    if config.audit_log {
+        if let Err(e) = append_local(&line) {
+            eprintln!("Warning: failed to write audit log: {}", e);
+        }
+    }
+    if config.audit_syslog {
+        if let Err(e) = send_syslog(&line) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+    if !config.audit_webhook_url.is_empty() {
+        let url = config.audit_webhook_url.clone();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                PENDING_WEBHOOKS.fetch_add(1, Ordering::SeqCst);
+                handle.spawn(async move {
+                    if let Err(e) = send_webhook(url, line).await {
+                        eprintln!("Warning: failed to forward audit event: {}", e);
+                    }
+                    PENDING_WEBHOOKS.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+            Err(_) => eprintln!("Warning: audit webhook skipped outside the async runtime"),
+        }
+    }
+}
+
+/// Waits up to `timeout` for webhook deliveries still in flight, so events recorded just
+/// before exit are not lost with the runtime. Returns how many were abandoned.
+pub async fn drain(timeout: Duration) -> usize {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while PENDING_WEBHOOKS.load(Ordering::SeqCst) > 0 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    PENDING_WEBHOOKS.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_events_plain_lines() {
+        let path = std::env::temp_dir().join(format!("shellmind-audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let first = serde_json::to_string(&AuditEvent::new(AuditKind::CommandExecuted, "ls", serde_json::json!(0))).unwrap();
+        let second = serde_json::to_string(&AuditEvent::new(AuditKind::FileModified, "a.txt", serde_json::Value::Null)).unwrap();
+        std::fs::write(&path, format!("{}\n{}\n", encode_line(&first).unwrap(), encode_line(&second).unwrap())).unwrap();
+        let events = read_events(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, AuditKind::CommandExecuted);
+        assert_eq!(events[1].subject, "a.txt");
+    }
+}
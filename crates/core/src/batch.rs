@@ -0,0 +1,102 @@
+//! Bulk generation of many prompts with bounded concurrency and a request rate limit.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+
+use crate::transport::Transport;
+use crate::{ShellmindConfig, ShellmindError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub id: Option<String>,
+    pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Parses a JSONL batch file: each line is either `{"id": ..., "prompt": ...}` or a bare JSON string.
+pub fn parse_batch_file(content: &str) -> Result<Vec<BatchItem>, ShellmindError> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            if let Ok(prompt) = serde_json::from_str::<String>(line) {
+                return Ok(BatchItem { id: None, prompt });
+            }
+            serde_json::from_str::<BatchItem>(line)
+                .map_err(|e| ShellmindError::Other(format!("Invalid batch entry on line {}: {}", i + 1, e)))
+        })
+        .collect()
+}
+
+/// Runs every item through `transport` with at most `concurrency` requests in flight
+/// and at most `requests_per_minute` requests started per minute. Results keep input order.
+pub async fn run_batch(
+    transport: Arc<dyn Transport>,
+    config: &ShellmindConfig,
+    items: Vec<BatchItem>,
+    concurrency: usize,
+    requests_per_minute: u32,
+) -> Vec<BatchResult> {
+    let config = Arc::new(config.clone());
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let interval = Duration::from_secs_f64(60.0 / requests_per_minute.max(1) as f64);
+    let ticker = Arc::new(Mutex::new(tokio::time::interval(interval)));
+
+    let handles: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            let transport = Arc::clone(&transport);
+            let config = Arc::clone(&config);
+            let semaphore = Arc::clone(&semaphore);
+            let ticker = Arc::clone(&ticker);
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("batch semaphore closed");
+                ticker.lock().await.tick().await;
+
+                let (response, error) = match transport.generate(&config, &config.wrap_prompt(&item.prompt), &[]).await {
+                    Ok(response) => (Some(response), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                BatchResult { id: item.id, prompt: item.prompt, response, error }
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        match handle.await {
+            Ok(result) => results.push(result),
+            Err(e) => results.push(BatchResult {
+                id: None,
+                prompt: String::new(),
+                response: None,
+                error: Some(format!("Batch task failed: {}", e)),
+            }),
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch_file_reports_source_line() {
+        let err = parse_batch_file("\"list files\"\n\n{not json}\n").unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{}", err);
+    }
+}
@@ -0,0 +1,147 @@
+//! `shellmind bench`: runs a fixed set of everyday prompts against several models and
+//! compares their latency, token counts and estimated cost.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::context::{estimate_tokens, initial_history};
+use crate::{ApiType, ShellmindConfig, ShellmindError};
+
+/// Prompts covering the kinds of requests Shellmind gets: short commands, pipelines,
+/// explanations and a small script.
+pub const PROMPTS: &[&str] = &[
+    "list the 10 largest files under the current directory",
+    "find all TODO comments in *.rs files and count them per file",
+    "show which process is listening on port 8080",
+    "compress the logs directory into a dated tar.gz, excluding *.tmp",
+    "explain what `find . -mtime +30 -name '*.log' -delete` does",
+    "write a bash script that retries a command up to 5 times with exponential backoff",
+];
+
+/// A model to benchmark, optionally over a specific API: `gemini-1.5-flash` or
+/// `gemini-1.5-pro@grpc`.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub model: String,
+    pub api_type: Option<ApiType>,
+}
+
+impl Target {
+    pub fn parse(spec: &str) -> Result<Self, ShellmindError> {
+        let (model, api) = match spec.trim().split_once('@') {
+            Some((model, api)) => (model, Some(api)),
+            None => (spec.trim(), None),
+        };
+        let api_type = match api.map(str::to_lowercase).as_deref() {
+            None => None,
+            Some("rest") => Some(ApiType::Rest),
+            Some("grpc") => Some(ApiType::Grpc),
+            Some(other) => return Err(ShellmindError::Other(format!("Unknown API type '{}' in '{}'; use rest or grpc", other, spec))),
+        };
+        if model.is_empty() {
+            return Err(ShellmindError::Other(format!("No model in '{}'", spec)));
+        }
+        Ok(Target { model: model.to_string(), api_type })
+    }
+
+    pub fn label(&self) -> String {
+        match self.api_type {
+            Some(ApiType::Rest) => format!("{}@rest", self.model),
+            Some(ApiType::Grpc) => format!("{}@grpc", self.model),
+            None => self.model.clone(),
+        }
+    }
+
+    /// `config` with this target's model and API.
+    pub fn config(&self, config: &ShellmindConfig) -> ShellmindConfig {
+        let mut config = config.clone();
+        config.model_name = self.model.clone();
+        if let Some(api_type) = &self.api_type {
+            config.api_type = api_type.clone();
+        }
+        config
+    }
+}
+
+/// The models configured for everyday use: the main model, the summary model and the
+/// budget fallback, without duplicates.
+pub fn default_targets(config: &ShellmindConfig) -> Vec<Target> {
+    let mut models: Vec<&str> = Vec::new();
+    for model in [&config.model_name, &config.summary_model_name, &config.budget_fallback_model] {
+        if !model.is_empty() && !models.contains(&model.as_str()) {
+            models.push(model);
+        }
+    }
+    models.into_iter().map(|model| Target { model: model.to_string(), api_type: None }).collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub target: Target,
+    pub latencies: Vec<Duration>,
+    pub failures: usize,
+    /// Estimated, as for budgets (~4 characters per token).
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated USD; `None` when the model has no known price.
+    pub cost: Option<f64>,
+    pub last_error: Option<String>,
+}
+
+impl BenchResult {
+    /// The latency below which `share` (0.0–1.0) of the successful requests finished.
+    pub fn percentile(&self, share: f64) -> Option<Duration> {
+        let mut sorted = self.latencies.clone();
+        sorted.sort();
+        let index = ((sorted.len() as f64 * share).ceil() as usize).checked_sub(1)?;
+        sorted.get(index.min(sorted.len() - 1)).copied()
+    }
+
+    pub fn requests(&self) -> usize {
+        self.latencies.len() + self.failures
+    }
+}
+
+/// Sends every prompt `runs` times to each target through `generate`, one request at a
+/// time so latencies don't affect each other. Failed requests are counted, not fatal.
+pub async fn run<F, Fut>(config: &ShellmindConfig, targets: &[Target], prompts: &[String], runs: usize, generate: F) -> Vec<BenchResult>
+where
+    F: Fn(ShellmindConfig, String) -> Fut,
+    Fut: Future<Output = Result<String, ShellmindError>>,
+{
+    let mut results = Vec::with_capacity(targets.len());
+    for target in targets {
+        let target_config = target.config(config);
+        let preamble_tokens = estimate_tokens(&initial_history(&target_config)) as u64;
+        let mut result = BenchResult {
+            target: target.clone(),
+            latencies: Vec::new(),
+            failures: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            cost: Some(0.0),
+            last_error: None,
+        };
+        for _ in 0..runs.max(1) {
+            for prompt in prompts {
+                let started = Instant::now();
+                match generate(target_config.clone(), prompt.clone()).await {
+                    Ok(response) => {
+                        result.latencies.push(started.elapsed());
+                        let input = preamble_tokens + prompt.chars().count() as u64 / 4 + 1;
+                        let output = response.chars().count() as u64 / 4 + 1;
+                        result.input_tokens += input;
+                        result.output_tokens += output;
+                        result.cost = result.cost.zip(crate::budget::estimate_cost(config, &target.model, input, output)).map(|(a, b)| a + b);
+                    }
+                    Err(e) => {
+                        result.failures += 1;
+                        result.last_error = Some(e.to_string());
+                    }
+                }
+            }
+        }
+        results.push(result);
+    }
+    results
+}
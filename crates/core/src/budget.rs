@@ -0,0 +1,188 @@
+//! Daily and monthly token and cost budgets. Usage of every model call is kept in
+//! `~/.shellmind/usage.json`; once a budget is spent, `budget_policy` decides whether
+//! calls go ahead with a warning, switch to `budget_fallback_model`, or are refused.
+
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::{persist, ShellmindConfig, ShellmindError};
+
+/// Days of usage kept in the ledger; enough for the current and previous month.
+const KEEP_DAYS: i64 = 62;
+
+/// Usage of this process, shown in the status line.
+static SESSION_USAGE: Mutex<Usage> = Mutex::new(Usage { input_tokens: 0, output_tokens: 0, cost: 0.0 });
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPolicy {
+    /// Print a warning and make the call anyway.
+    Warn,
+    /// Make the call with `budget_fallback_model`.
+    Fallback,
+    /// Refuse the call.
+    Refuse,
+}
+
+impl Default for BudgetPolicy {
+    fn default() -> Self {
+        BudgetPolicy::Warn
+    }
+}
+
+/// USD per million tokens, e.g. `[model_prices."gemini-1.5-pro"]`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// List prices for the models Shellmind ships defaults for; `model_prices` overrides them.
+fn builtin_price(model: &str) -> Option<ModelPrice> {
+    let (input_per_million, output_per_million) = if model.starts_with("gemini-1.5-flash") {
+        (0.075, 0.30)
+    } else if model.starts_with("gemini-1.5-pro") {
+        (1.25, 5.00)
+    } else if model.starts_with("gemini-pro") || model.starts_with("gemini-1.0-pro") {
+        (0.50, 1.50)
+    } else {
+        return None;
+    };
+    Some(ModelPrice { input_per_million, output_per_million })
+}
+
+pub fn price(config: &ShellmindConfig, model: &str) -> Option<ModelPrice> {
+    config.model_prices.get(model).copied().or_else(|| builtin_price(model))
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated USD; calls to models without a known price count as free.
+    pub cost: f64,
+}
+
+impl Usage {
+    pub fn tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Usage per day (`YYYY-MM-DD`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ledger {
+    pub days: BTreeMap<NaiveDate, Usage>,
+}
+
+fn ledger_path() -> Result<PathBuf, ShellmindError> {
+    let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+    Ok(home_dir.join(".shellmind").join("usage.json"))
+}
+
+impl Ledger {
+    pub fn load() -> Result<Self, ShellmindError> {
+        let path = ledger_path()?;
+        if !path.exists() {
+            return Ok(Ledger::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to read '{}': {}", path.display(), e)))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn today(&self) -> Usage {
+        self.days.get(&Utc::now().date_naive()).copied().unwrap_or_default()
+    }
+
+    pub fn this_month(&self) -> Usage {
+        let today = Utc::now().date_naive();
+        let mut total = Usage::default();
+        for usage in self.days.iter().filter(|(day, _)| day.year() == today.year() && day.month() == today.month()).map(|(_, u)| u) {
+            total.add(usage);
+        }
+        total
+    }
+}
+
+/// Usage of the model calls made by this process so far.
+pub fn session_usage() -> Usage {
+    *SESSION_USAGE.lock().unwrap()
+}
+
+/// USD for a call, if the model's price is known.
+pub fn estimate_cost(config: &ShellmindConfig, model: &str, input_tokens: u64, output_tokens: u64) -> Option<f64> {
+    price(config, model).map(|p| (input_tokens as f64 * p.input_per_million + output_tokens as f64 * p.output_per_million) / 1_000_000.0)
+}
+
+/// Adds a call to today's usage.
+pub fn record(config: &ShellmindConfig, model: &str, input_tokens: u64, output_tokens: u64) -> Result<(), ShellmindError> {
+    let cost = estimate_cost(config, model, input_tokens, output_tokens).unwrap_or(0.0);
+    SESSION_USAGE.lock().unwrap().add(&Usage { input_tokens, output_tokens, cost });
+    let path = ledger_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", dir.display(), e)))?;
+    }
+    persist::with_lock(&path, || {
+        let mut ledger = Ledger::load()?;
+        let today = Utc::now().date_naive();
+        ledger.days.entry(today).or_default().add(&Usage { input_tokens, output_tokens, cost });
+        ledger.days.retain(|day, _| (today - *day).num_days() < KEEP_DAYS);
+        persist::atomic_write(&path, serde_json::to_string_pretty(&ledger)?)
+    })
+}
+
+/// What to do with the next call, given the usage so far.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetDecision {
+    Allow,
+    /// Over budget; go ahead but tell the user.
+    Warn(String),
+    /// Over budget; use this model instead.
+    Fallback(String),
+    Refuse(String),
+}
+
+/// Describes the first exceeded budget, if any. Zero disables a budget.
+pub fn exceeded(config: &ShellmindConfig, ledger: &Ledger) -> Option<String> {
+    let today = ledger.today();
+    let month = ledger.this_month();
+    if config.daily_token_budget > 0 && today.tokens() >= config.daily_token_budget {
+        return Some(format!("daily token budget of {} reached ({} used)", config.daily_token_budget, today.tokens()));
+    }
+    if config.monthly_token_budget > 0 && month.tokens() >= config.monthly_token_budget {
+        return Some(format!("monthly token budget of {} reached ({} used)", config.monthly_token_budget, month.tokens()));
+    }
+    if config.daily_cost_budget > 0.0 && today.cost >= config.daily_cost_budget {
+        return Some(format!("daily cost budget of ${:.2} reached (${:.2} spent)", config.daily_cost_budget, today.cost));
+    }
+    if config.monthly_cost_budget > 0.0 && month.cost >= config.monthly_cost_budget {
+        return Some(format!("monthly cost budget of ${:.2} reached (${:.2} spent)", config.monthly_cost_budget, month.cost));
+    }
+    None
+}
+
+pub fn check(config: &ShellmindConfig) -> Result<BudgetDecision, ShellmindError> {
+    let Some(reason) = exceeded(config, &Ledger::load()?) else {
+        return Ok(BudgetDecision::Allow);
+    };
+    Ok(match config.budget_policy {
+        BudgetPolicy::Warn => BudgetDecision::Warn(reason),
+        // Falling back to the model already in use would change nothing
+        BudgetPolicy::Fallback if config.budget_fallback_model.is_empty() || config.budget_fallback_model == config.model_name => {
+            BudgetDecision::Refuse(reason)
+        }
+        BudgetPolicy::Fallback => BudgetDecision::Fallback(config.budget_fallback_model.clone()),
+        BudgetPolicy::Refuse => BudgetDecision::Refuse(reason),
+    })
+}
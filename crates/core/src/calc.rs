@@ -0,0 +1,494 @@
+//! `calculate`: a small deterministic evaluator for arithmetic, unit conversion and date
+//! math, so answers like "seconds in 3.5 days" don't depend on the model's arithmetic.
+//!
+//! Quantities carry one dimension (time, data, length or mass) and are kept in base units
+//! (seconds, bytes, metres, grams). A month is 30 days and a year 365 days. Dates are
+//! written `2024-03-01` or `2024-03-01T10:30`, and `now` and `today` are local time.
+
+use chrono::{Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::signal::unix::Signal;
+
+use crate::progress::ProgressSender;
+use crate::{ConfirmationDetails, ShellmindError, ToolResult, TypedTool};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    None,
+    Time,
+    Data,
+    Length,
+    Mass,
+}
+
+struct Unit {
+    names: &'static [&'static str],
+    dimension: Dimension,
+    /// Base units per unit.
+    factor: f64,
+}
+
+const DAY: f64 = 86_400.0;
+
+const UNITS: &[Unit] = &[
+    Unit { names: &["ns", "nanosecond", "nanoseconds"], dimension: Dimension::Time, factor: 1e-9 },
+    Unit { names: &["us", "µs", "microsecond", "microseconds"], dimension: Dimension::Time, factor: 1e-6 },
+    Unit { names: &["ms", "millisecond", "milliseconds"], dimension: Dimension::Time, factor: 1e-3 },
+    Unit { names: &["s", "sec", "secs", "second", "seconds"], dimension: Dimension::Time, factor: 1.0 },
+    Unit { names: &["min", "mins", "minute", "minutes"], dimension: Dimension::Time, factor: 60.0 },
+    Unit { names: &["h", "hr", "hrs", "hour", "hours"], dimension: Dimension::Time, factor: 3_600.0 },
+    Unit { names: &["d", "day", "days"], dimension: Dimension::Time, factor: DAY },
+    Unit { names: &["w", "wk", "week", "weeks"], dimension: Dimension::Time, factor: 7.0 * DAY },
+    Unit { names: &["month", "months"], dimension: Dimension::Time, factor: 30.0 * DAY },
+    Unit { names: &["y", "yr", "year", "years"], dimension: Dimension::Time, factor: 365.0 * DAY },
+    Unit { names: &["bit", "bits"], dimension: Dimension::Data, factor: 0.125 },
+    Unit { names: &["B", "byte", "bytes"], dimension: Dimension::Data, factor: 1.0 },
+    Unit { names: &["KB", "kB", "kb"], dimension: Dimension::Data, factor: 1e3 },
+    Unit { names: &["MB", "mb"], dimension: Dimension::Data, factor: 1e6 },
+    Unit { names: &["GB", "gb"], dimension: Dimension::Data, factor: 1e9 },
+    Unit { names: &["TB", "tb"], dimension: Dimension::Data, factor: 1e12 },
+    Unit { names: &["PB", "pb"], dimension: Dimension::Data, factor: 1e15 },
+    Unit { names: &["KiB", "kib"], dimension: Dimension::Data, factor: 1024.0 },
+    Unit { names: &["MiB", "mib"], dimension: Dimension::Data, factor: 1024.0 * 1024.0 },
+    Unit { names: &["GiB", "gib"], dimension: Dimension::Data, factor: 1024.0 * 1024.0 * 1024.0 },
+    Unit { names: &["TiB", "tib"], dimension: Dimension::Data, factor: 1024.0 * 1024.0 * 1024.0 * 1024.0 },
+    Unit { names: &["PiB", "pib"], dimension: Dimension::Data, factor: 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0 },
+    Unit { names: &["mm", "millimetre", "millimetres", "millimeter", "millimeters"], dimension: Dimension::Length, factor: 1e-3 },
+    Unit { names: &["cm", "centimetre", "centimetres", "centimeter", "centimeters"], dimension: Dimension::Length, factor: 1e-2 },
+    Unit { names: &["m", "metre", "metres", "meter", "meters"], dimension: Dimension::Length, factor: 1.0 },
+    Unit { names: &["km", "kilometre", "kilometres", "kilometer", "kilometers"], dimension: Dimension::Length, factor: 1e3 },
+    Unit { names: &["inch", "inches"], dimension: Dimension::Length, factor: 0.0254 },
+    Unit { names: &["ft", "foot", "feet"], dimension: Dimension::Length, factor: 0.3048 },
+    Unit { names: &["mi", "mile", "miles"], dimension: Dimension::Length, factor: 1609.344 },
+    Unit { names: &["mg", "milligram", "milligrams"], dimension: Dimension::Mass, factor: 1e-3 },
+    Unit { names: &["g", "gram", "grams"], dimension: Dimension::Mass, factor: 1.0 },
+    Unit { names: &["kg", "kilogram", "kilograms"], dimension: Dimension::Mass, factor: 1e3 },
+    Unit { names: &["oz", "ounce", "ounces"], dimension: Dimension::Mass, factor: 28.349523125 },
+    Unit { names: &["lb", "lbs", "pound", "pounds"], dimension: Dimension::Mass, factor: 453.59237 },
+];
+
+/// The unit called `name`, and the name as listed, which is how results are shown.
+fn unit(name: &str) -> Option<(&'static Unit, &'static str)> {
+    UNITS.iter().find_map(|u| u.names.iter().find(|n| **n == name).map(|n| (u, *n)))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    /// `value` is in base units; `unit` is how it was written, for display.
+    Quantity { value: f64, dimension: Dimension, unit: Option<&'static str> },
+    Date(NaiveDateTime),
+}
+
+fn number(value: f64) -> Value {
+    Value::Quantity { value, dimension: Dimension::None, unit: None }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Date(NaiveDateTime),
+    Word(String),
+    Symbol(char),
+}
+
+fn error(message: impl Into<String>) -> ShellmindError {
+    ShellmindError::Other(message.into())
+}
+
+fn parse_date(text: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ShellmindError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).map_or(false, |n| n.is_ascii_digit())) {
+            let start = i;
+            // A date: four digits, then `-`
+            if i + 4 < chars.len() && chars[i..i + 4].iter().all(|d| d.is_ascii_digit()) && chars[i + 4] == '-' {
+                let end = (i..chars.len()).find(|&j| !(chars[j].is_ascii_alphanumeric() || matches!(chars[j], '-' | ':'))).unwrap_or(chars.len());
+                let text: String = chars[start..end].iter().collect();
+                if let Some(date) = parse_date(&text) {
+                    tokens.push(Token::Date(date));
+                    i = end;
+                    continue;
+                }
+            }
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_') {
+                i += 1;
+            }
+            // Exponent, unless the `e` starts a word such as `eur`
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let digits_at = if matches!(chars.get(i + 1), Some('+') | Some('-')) { i + 2 } else { i + 1 };
+                if chars.get(digits_at).map_or(false, |d| d.is_ascii_digit()) {
+                    i = digits_at;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            tokens.push(Token::Number(text.parse().map_err(|_| error(format!("Invalid number '{}'", text)))?));
+        } else if c.is_alphabetic() || c == 'µ' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == 'µ') {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect()));
+        } else if "+-*/%^(),".contains(c) {
+            tokens.push(Token::Symbol(c));
+            i += 1;
+        } else {
+            return Err(error(format!("Unexpected character '{}'", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), ShellmindError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(error(format!("Expected '{}'", symbol)))
+        }
+    }
+
+    /// A unit name at the current position, consumed if present.
+    fn unit(&mut self) -> Option<(&'static Unit, &'static str)> {
+        let Some(Token::Word(word)) = self.peek() else {
+            return None;
+        };
+        let found = unit(word)?;
+        self.position += 1;
+        Some(found)
+    }
+
+    fn additive(&mut self) -> Result<Value, ShellmindError> {
+        let mut left = self.term()?;
+        loop {
+            if self.eat('+') {
+                left = add(left, self.term()?, 1.0)?;
+            } else if self.eat('-') {
+                left = add(left, self.term()?, -1.0)?;
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Value, ShellmindError> {
+        let mut left = self.unary()?;
+        loop {
+            if self.eat('*') {
+                left = multiply(left, self.unary()?)?;
+            } else if self.eat('/') {
+                left = divide(left, self.unary()?)?;
+            } else if self.eat('%') {
+                let (a, b) = (dimensionless(left)?, dimensionless(self.unary()?)?);
+                left = number(a % b);
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Value, ShellmindError> {
+        if self.eat('-') {
+            return multiply(number(-1.0), self.unary()?);
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Value, ShellmindError> {
+        let base = self.postfix()?;
+        if self.eat('^') {
+            let exponent = dimensionless(self.unary()?)?;
+            return Ok(number(dimensionless(base)?.powf(exponent)));
+        }
+        Ok(base)
+    }
+
+    /// A primary value, followed by a unit for literals such as `3.5 days`.
+    fn postfix(&mut self) -> Result<Value, ShellmindError> {
+        let value = self.primary()?;
+        match (value, self.unit()) {
+            (Value::Quantity { value, dimension: Dimension::None, .. }, Some((unit, name))) => {
+                Ok(Value::Quantity { value: value * unit.factor, dimension: unit.dimension, unit: Some(name) })
+            }
+            (_, Some((_, name))) => Err(error(format!("'{}' can't follow a value that already has a unit", name))),
+            (value, None) => Ok(value),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Value>, ShellmindError> {
+        self.expect('(')?;
+        let mut arguments = Vec::new();
+        if !self.eat(')') {
+            loop {
+                arguments.push(self.additive()?);
+                if self.eat(')') {
+                    break;
+                }
+                self.expect(',')?;
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn primary(&mut self) -> Result<Value, ShellmindError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(number(n)),
+            Some(Token::Date(date)) => Ok(Value::Date(date)),
+            Some(Token::Symbol('(')) => {
+                let value = self.additive()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(Token::Word(word)) => match word.as_str() {
+                "pi" => Ok(number(std::f64::consts::PI)),
+                "e" => Ok(number(std::f64::consts::E)),
+                "now" => Ok(Value::Date(Local::now().naive_local())),
+                "today" => Ok(Value::Date(Local::now().date_naive().and_hms_opt(0, 0, 0).unwrap_or_default())),
+                name if self.peek() == Some(&Token::Symbol('(')) => {
+                    let arguments = self.arguments()?;
+                    function(name, &arguments)
+                }
+                // A bare unit is one of it: `day in seconds`
+                name => match unit(name) {
+                    Some((unit, name)) => Ok(Value::Quantity { value: unit.factor, dimension: unit.dimension, unit: Some(name) }),
+                    None => Err(error(format!("Unknown name '{}'", name))),
+                },
+            },
+            Some(Token::Symbol(c)) => Err(error(format!("Unexpected '{}'", c))),
+            None => Err(error("The expression ends too early")),
+        }
+    }
+}
+
+fn dimensionless(value: Value) -> Result<f64, ShellmindError> {
+    match value {
+        Value::Quantity { value, dimension: Dimension::None, .. } => Ok(value),
+        Value::Quantity { unit, .. } => Err(error(format!("Expected a plain number, not a value in {}", unit.unwrap_or("units")))),
+        Value::Date(_) => Err(error("Expected a number, not a date")),
+    }
+}
+
+fn seconds(value: f64) -> ChronoDuration {
+    ChronoDuration::milliseconds((value * 1000.0).round() as i64)
+}
+
+/// `left + sign * right`.
+fn add(left: Value, right: Value, sign: f64) -> Result<Value, ShellmindError> {
+    match (left, right) {
+        (Value::Quantity { value: a, dimension: da, unit }, Value::Quantity { value: b, dimension: db, unit: unit_b }) if da == db => {
+            Ok(Value::Quantity { value: a + sign * b, dimension: da, unit: unit.or(unit_b) })
+        }
+        (Value::Date(date), Value::Quantity { value, dimension: Dimension::Time, .. }) => Ok(Value::Date(date + seconds(sign * value))),
+        (Value::Quantity { value, dimension: Dimension::Time, .. }, Value::Date(date)) if sign > 0.0 => Ok(Value::Date(date + seconds(value))),
+        (Value::Date(a), Value::Date(b)) if sign < 0.0 => {
+            let difference = (a - b).num_milliseconds() as f64 / 1000.0;
+            let unit = if difference % DAY == 0.0 { "days" } else { "h" };
+            Ok(Value::Quantity { value: difference, dimension: Dimension::Time, unit: Some(unit) })
+        }
+        (Value::Date(_), Value::Date(_)) => Err(error("Dates can't be added; subtract them to get the time between")),
+        (Value::Date(_), _) | (_, Value::Date(_)) => Err(error("Only times such as `90 days` can be added to a date")),
+        _ => Err(error("Can't add or subtract values of different kinds, e.g. days and bytes")),
+    }
+}
+
+fn multiply(left: Value, right: Value) -> Result<Value, ShellmindError> {
+    match (left, right) {
+        (Value::Quantity { value: a, dimension: Dimension::None, .. }, Value::Quantity { value: b, dimension, unit })
+        | (Value::Quantity { value: b, dimension, unit }, Value::Quantity { value: a, dimension: Dimension::None, .. }) => {
+            Ok(Value::Quantity { value: a * b, dimension, unit })
+        }
+        (Value::Date(_), _) | (_, Value::Date(_)) => Err(error("Dates can't be multiplied")),
+        _ => Err(error("Only one factor of a product may have a unit")),
+    }
+}
+
+fn divide(left: Value, right: Value) -> Result<Value, ShellmindError> {
+    match (left, right) {
+        (_, Value::Quantity { value, .. }) if value == 0.0 => Err(error("Division by zero")),
+        (Value::Quantity { value: a, dimension, unit }, Value::Quantity { value: b, dimension: Dimension::None, .. }) => {
+            Ok(Value::Quantity { value: a / b, dimension, unit })
+        }
+        // `1 day / 15 min` is how many times one fits into the other
+        (Value::Quantity { value: a, dimension: da, .. }, Value::Quantity { value: b, dimension: db, .. }) if da == db => Ok(number(a / b)),
+        (Value::Date(_), _) | (_, Value::Date(_)) => Err(error("Dates can't be divided")),
+        _ => Err(error("Can't divide values of different kinds")),
+    }
+}
+
+fn function(name: &str, arguments: &[Value]) -> Result<Value, ShellmindError> {
+    let single = || -> Result<f64, ShellmindError> {
+        match arguments {
+            [value] => dimensionless(*value),
+            _ => Err(error(format!("{}() takes one argument", name))),
+        }
+    };
+    let value = match name {
+        "sqrt" => single()?.sqrt(),
+        "abs" => single()?.abs(),
+        "floor" => single()?.floor(),
+        "ceil" => single()?.ceil(),
+        "round" => single()?.round(),
+        "ln" => single()?.ln(),
+        "log" | "log10" => single()?.log10(),
+        "log2" => single()?.log2(),
+        "exp" => single()?.exp(),
+        "min" | "max" => {
+            let numbers = arguments.iter().map(|a| dimensionless(*a)).collect::<Result<Vec<_>, _>>()?;
+            let fold = if name == "min" { f64::min } else { f64::max };
+            numbers.into_iter().reduce(fold).ok_or_else(|| error(format!("{}() needs arguments", name)))?
+        }
+        // Unix seconds to a date, and back
+        "timestamp" | "from_unix" => {
+            let seconds = single()?;
+            let date = chrono::DateTime::from_timestamp(seconds.floor() as i64, 0).ok_or_else(|| error("Timestamp out of range"))?;
+            return Ok(Value::Date(date.naive_utc()));
+        }
+        "unix" => match arguments {
+            [Value::Date(date)] => date.and_utc().timestamp() as f64,
+            _ => return Err(error("unix() takes a date")),
+        },
+        _ => return Err(error(format!("Unknown function '{}'", name))),
+    };
+    Ok(number(value))
+}
+
+fn format_number(value: f64) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let text = format!("{:.10}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn format_value(value: Value) -> String {
+    match value {
+        Value::Quantity { value, unit: Some(name), .. } => {
+            let factor = unit(name).map_or(1.0, |(u, _)| u.factor);
+            format!("{} {}", format_number(value / factor), name)
+        }
+        Value::Quantity { value, unit: None, .. } => format_number(value),
+        Value::Date(date) if date.format("%H:%M:%S").to_string() == "00:00:00" => date.format("%Y-%m-%d (%A)").to_string(),
+        Value::Date(date) => date.format("%Y-%m-%d %H:%M:%S (%A)").to_string(),
+    }
+}
+
+/// Evaluates `expression`; `<expression> in <unit>` (or `to`) converts the result.
+pub fn evaluate(expression: &str) -> Result<String, ShellmindError> {
+    let mut tokens = tokenize(expression)?;
+    // `in`/`to` followed by a unit at the end is a conversion
+    let target = match tokens.as_slice() {
+        [.., Token::Word(keyword), Token::Word(name)] if matches!(keyword.as_str(), "in" | "to" | "as") => {
+            let target = unit(name).ok_or_else(|| error(format!("Unknown unit '{}'", name)))?;
+            tokens.truncate(tokens.len() - 2);
+            Some(target)
+        }
+        _ => None,
+    };
+    let mut parser = Parser { tokens, position: 0 };
+    let value = parser.additive()?;
+    if parser.peek().is_some() {
+        return Err(error("Unexpected input after the expression; operators between values can't be left out"));
+    }
+    let value = match (value, target) {
+        (Value::Quantity { value, dimension, .. }, Some((target, name))) if dimension == target.dimension => {
+            Value::Quantity { value, dimension, unit: Some(name) }
+        }
+        (_, Some((_, name))) => return Err(error(format!("The result can't be converted to {}", name))),
+        (value, None) => value,
+    };
+    Ok(format_value(value))
+}
+
+pub struct CalcTool;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CalcParams {
+    /// The expression, e.g. `3.5 days in seconds`, `1.5 GiB in MB`, `2024-03-01 + 90 days`,
+    /// `2024-12-25 - today` or `1 day / 15 min`.
+    pub expression: String,
+}
+
+impl TypedTool for CalcTool {
+    type Params = CalcParams;
+
+    fn name(&self) -> &'static str {
+        "calculate"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Calculator"
+    }
+
+    fn description(&self) -> &'static str {
+        "Evaluates arithmetic, unit conversions and date math exactly; use it instead of doing arithmetic yourself. \
+         Supports + - * / % ^, parentheses, sqrt/abs/floor/ceil/round/ln/log/log2/exp/min/max, pi, and units of time \
+         (ms, s, min, h, days, weeks, months = 30 days, years = 365 days), data (bit, B, KB..PB, KiB..PiB), length and mass. \
+         Convert with `<expr> in <unit>`. Dates are YYYY-MM-DD or YYYY-MM-DDTHH:MM, plus `now` and `today`; add or \
+         subtract times, subtract dates, and convert with timestamp(<unix seconds>) and unix(<date>)."
+    }
+
+    fn get_description(&self, params: &CalcParams) -> String {
+        format!("Calculate {}", params.expression)
+    }
+
+    fn should_confirm_execute(&self, _params: &CalcParams) -> Option<ConfirmationDetails> {
+        None // Pure computation
+    }
+
+    fn execute(&self, params: CalcParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        Box::pin(async move {
+            Ok(match evaluate(&params.expression) {
+                Ok(result) => ToolResult::success(format!("{} = {}", params.expression.trim(), result))
+                    .with_content(serde_json::json!({ "expression": params.expression, "result": result })),
+                Err(e) => ToolResult::error(e.to_string()),
+            })
+        })
+    }
+}
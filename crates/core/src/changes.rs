@@ -0,0 +1,104 @@
+//! Workspace change tracking: hashes the files under a directory before and after a
+//! command runs so its side effects can be reported even when its output is silent.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Files beyond this count are not tracked.
+const MAX_FILES: usize = 20_000;
+/// Larger files are compared by size and modification time instead of content.
+const MAX_HASHED_BYTES: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Fingerprint {
+    Digest(Vec<u8>),
+    Metadata(u64, Option<std::time::SystemTime>),
+}
+
+/// Fingerprints of the files under a directory, keyed by relative path.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceSnapshot {
+    files: HashMap<PathBuf, Fingerprint>,
+    /// More than `MAX_FILES` files were found; changes to the rest are not reported.
+    pub truncated: bool,
+}
+
+fn fingerprint(path: &Path) -> Option<Fingerprint> {
+    let metadata = path.symlink_metadata().ok()?;
+    if metadata.is_symlink() {
+        let target = std::fs::read_link(path).ok()?;
+        return Some(Fingerprint::Digest(target.to_string_lossy().as_bytes().to_vec()));
+    }
+    if metadata.len() > MAX_HASHED_BYTES {
+        return Some(Fingerprint::Metadata(metadata.len(), metadata.modified().ok()));
+    }
+    let content = std::fs::read(path).ok()?;
+    Some(Fingerprint::Digest(ring::digest::digest(&ring::digest::SHA256, &content).as_ref().to_vec()))
+}
+
+impl WorkspaceSnapshot {
+    /// Fingerprints the files under `root`, honoring `.gitignore` and skipping `.git`.
+    pub fn take(root: &Path) -> Self {
+        let mut snapshot = WorkspaceSnapshot::default();
+        let walker = ignore::WalkBuilder::new(root)
+            .hidden(false)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build();
+        for entry in walker.filter_map(|e| e.ok()) {
+            if entry.file_type().map_or(true, |t| t.is_dir()) {
+                continue;
+            }
+            if snapshot.files.len() >= MAX_FILES {
+                snapshot.truncated = true;
+                break;
+            }
+            let Ok(relative) = entry.path().strip_prefix(root) else {
+                continue;
+            };
+            if let Some(fingerprint) = fingerprint(entry.path()) {
+                snapshot.files.insert(relative.to_path_buf(), fingerprint);
+            }
+        }
+        snapshot
+    }
+
+    /// Files created, modified and deleted between this snapshot and `after`, relative to
+    /// the snapshot root.
+    pub fn diff(&self, after: &WorkspaceSnapshot) -> Changes {
+        let mut changes = Changes::default();
+        for (path, fingerprint) in &after.files {
+            match self.files.get(path) {
+                None => changes.created.push(path.clone()),
+                Some(previous) if previous != fingerprint => changes.modified.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.deleted = self
+            .files
+            .keys()
+            .filter(|path| !after.files.contains_key(*path))
+            .cloned()
+            .collect();
+        // A truncated walk may miss files that exist, so absences prove nothing
+        if self.truncated || after.truncated {
+            changes.deleted.clear();
+        }
+        changes.created.sort();
+        changes.modified.sort();
+        changes.deleted.sort();
+        changes
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Changes {
+    pub created: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    pub deleted: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
@@ -0,0 +1,222 @@
+//! Cloud CLI awareness. With `cloud_context`, the accounts the `aws`, `gcloud` and `az`
+//! CLIs are logged in to are read once per process with their read-only identity
+//! commands and added to the system prompt, so generated commands target the right
+//! account, project and region. Account IDs and user names are partially masked before
+//! they are sent. Separately, commands that change cloud resources are recognized so the
+//! risk assessment can classify them as dangerous.
+
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// How long each CLI may take to answer; `aws sts` goes over the network.
+const CLI_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloudAccount {
+    pub provider: &'static str,
+    /// Descriptions such as "account ****1234" or "region eu-west-1", already masked.
+    pub details: Vec<String>,
+}
+
+/// Keeps the last four characters: `123456789012` becomes `********9012`.
+pub fn mask_id(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+    let kept: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", "*".repeat(chars.len() - 4), kept)
+}
+
+/// Keeps the first letter of the local part and the domain: `alice@example.com` becomes
+/// `a****@example.com`. Values that aren't e-mail addresses are masked with `mask_id`.
+pub fn mask_user(value: &str) -> String {
+    match value.split_once('@') {
+        Some((local, domain)) if !local.is_empty() => {
+            let first: String = local.chars().take(1).collect();
+            format!("{}{}@{}", first, "*".repeat(local.chars().count() - 1), domain)
+        }
+        _ => mask_id(value),
+    }
+}
+
+/// Masks the account ID inside an ARN, e.g. `arn:aws:iam::********9012:user/alice`.
+fn mask_arn(arn: &str) -> String {
+    let mut parts: Vec<String> = arn.split(':').map(str::to_string).collect();
+    if parts.len() > 4 && !parts[4].is_empty() {
+        parts[4] = mask_id(&parts[4]);
+    }
+    parts.join(":")
+}
+
+fn on_path(program: &str) -> bool {
+    extensions::which::find_in_path(program).is_some()
+}
+
+/// Stdout of a CLI call that succeeded within `CLI_TIMEOUT`.
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    if !on_path(program) {
+        return None;
+    }
+    let mut child = Command::new(program).args(args).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn().ok()?;
+    let deadline = Instant::now() + CLI_TIMEOUT;
+    loop {
+        match child.try_wait().ok()? {
+            Some(status) if status.success() => break,
+            Some(_) => return None,
+            None if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            None => std::thread::sleep(Duration::from_millis(50)),
+        }
+    }
+    let mut stdout = String::new();
+    child.stdout.take()?.read_to_string(&mut stdout).ok()?;
+    Some(stdout)
+}
+
+fn json(program: &str, args: &[&str]) -> Option<serde_json::Value> {
+    serde_json::from_str(&run(program, args)?).ok()
+}
+
+fn aws() -> Option<CloudAccount> {
+    let identity = json("aws", &["sts", "get-caller-identity", "--output", "json"])?;
+    let mut details = Vec::new();
+    if let Some(account) = identity["Account"].as_str() {
+        details.push(format!("account {}", mask_id(account)));
+    }
+    if let Some(arn) = identity["Arn"].as_str() {
+        details.push(format!("identity {}", mask_arn(arn)));
+    }
+    if let Ok(profile) = std::env::var("AWS_PROFILE") {
+        details.push(format!("profile {}", profile));
+    }
+    let region = std::env::var("AWS_REGION")
+        .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+        .ok()
+        .or_else(|| run("aws", &["configure", "get", "region"]).map(|r| r.trim().to_string()))
+        .filter(|r| !r.is_empty());
+    if let Some(region) = region {
+        details.push(format!("region {}", region));
+    }
+    Some(CloudAccount { provider: "AWS", details })
+}
+
+fn gcloud() -> Option<CloudAccount> {
+    let config = json("gcloud", &["config", "list", "--format=json"])?;
+    let mut details = Vec::new();
+    if let Some(account) = config["core"]["account"].as_str() {
+        details.push(format!("account {}", mask_user(account)));
+    }
+    if let Some(project) = config["core"]["project"].as_str() {
+        details.push(format!("project {}", project));
+    }
+    if let Some(region) = config["compute"]["region"].as_str() {
+        details.push(format!("region {}", region));
+    }
+    if let Some(zone) = config["compute"]["zone"].as_str() {
+        details.push(format!("zone {}", zone));
+    }
+    // A config without an account means nobody is logged in
+    (!details.is_empty()).then_some(CloudAccount { provider: "Google Cloud", details })
+}
+
+fn azure() -> Option<CloudAccount> {
+    let account = json("az", &["account", "show", "--output", "json"])?;
+    let mut details = Vec::new();
+    if let Some(name) = account["name"].as_str() {
+        details.push(format!("subscription {}", name));
+    }
+    if let Some(id) = account["id"].as_str() {
+        details.push(format!("subscription id {}", mask_id(id)));
+    }
+    if let Some(user) = account["user"]["name"].as_str() {
+        details.push(format!("user {}", mask_user(user)));
+    }
+    if let Some(tenant) = account["tenantId"].as_str() {
+        details.push(format!("tenant {}", mask_id(tenant)));
+    }
+    Some(CloudAccount { provider: "Azure", details })
+}
+
+/// Accounts of the installed and logged-in CLIs, asked in parallel.
+pub fn gather() -> Vec<CloudAccount> {
+    let probes: [fn() -> Option<CloudAccount>; 3] = [aws, gcloud, azure];
+    let handles: Vec<_> = probes.into_iter().map(std::thread::spawn).collect();
+    handles.into_iter().filter_map(|handle| handle.join().ok().flatten()).collect()
+}
+
+/// `gather()`, run once per process.
+pub fn accounts() -> &'static [CloudAccount] {
+    static ACCOUNTS: OnceLock<Vec<CloudAccount>> = OnceLock::new();
+    ACCOUNTS.get_or_init(gather)
+}
+
+/// Note for the system prompt; empty when no CLI is logged in.
+pub fn prompt_note(accounts: &[CloudAccount]) -> String {
+    if accounts.is_empty() {
+        return String::new();
+    }
+    let lines: Vec<String> = accounts.iter().map(|a| format!("- {}: {}", a.provider, a.details.join(", "))).collect();
+    format!(
+        "The user's cloud CLIs are logged in to the following accounts (identifiers partially masked):\n{}\nCloud commands run against these accounts, regions and projects unless you pass others explicitly; do so when the request names a different one.",
+        lines.join("\n")
+    )
+}
+
+/// Verbs of AWS operations that change resources.
+const AWS_MUTATING: &[&str] = &[
+    "create", "delete", "put", "update", "modify", "terminate", "run", "start", "stop", "reboot", "attach", "detach", "associate",
+    "disassociate", "remove", "add", "set", "tag", "untag", "deregister", "register", "revoke", "authorize", "invoke", "deploy",
+    "restore", "reset", "replace", "import", "enable", "disable", "cancel", "purge", "send", "publish", "upload", "copy", "release",
+    "allocate", "apply", "execute", "batch-write", "batch-delete", "restart", "rotate", "force",
+];
+
+/// `gcloud` and `az` verbs that change resources.
+const VERB_MUTATING: &[&str] = &[
+    "create", "delete", "update", "patch", "deploy", "start", "stop", "reset", "resize", "restart", "apply", "import", "enable",
+    "disable", "rollback", "cancel", "remove", "add", "set", "attach", "detach", "deallocate", "scale", "purge", "assign", "upgrade",
+    "swap", "invoke", "redeploy", "reimage", "restore", "rotate", "revoke", "grant",
+];
+
+/// Whether `word` is one of `verbs`, alone or leading a hyphenated operation such as
+/// `delete-bucket` or `add-iam-policy-binding`.
+fn is_verb(word: &str, verbs: &[&str]) -> bool {
+    verbs.iter().any(|verb| word == *verb || word.strip_prefix(verb).map_or(false, |rest| rest.starts_with('-')))
+}
+
+fn positional<'a>(args: &[&'a str]) -> Vec<&'a str> {
+    // Flags may take values (`--region eu-west-1`); values never look like verbs, so the
+    // occasional flag value among the positionals is harmless
+    args.iter().filter(|a| !a.starts_with('-')).copied().collect()
+}
+
+/// Why `program args` changes cloud resources, if it does. Changes to the CLI's own
+/// configuration (`aws configure`, `gcloud config`, `az account set`) don't count.
+pub fn mutation(program: &str, args: &[&str]) -> Option<String> {
+    let words = positional(args);
+    match program {
+        "aws" => {
+            let (service, operation) = (*words.first()?, *words.get(1)?);
+            let mutating = match service {
+                "configure" | "help" => false,
+                "s3" => matches!(operation, "cp" | "mv" | "rm" | "sync" | "mb" | "rb" | "website"),
+                _ => is_verb(operation, AWS_MUTATING),
+            };
+            mutating.then(|| format!("changes AWS resources (aws {} {})", service, operation))
+        }
+        "gcloud" | "az" => {
+            if matches!(words.first(), Some(&"config") | Some(&"help")) || (program == "az" && words.starts_with(&["account", "set"])) {
+                return None;
+            }
+            let verb = words.iter().position(|w| is_verb(w, VERB_MUTATING))?;
+            let provider = if program == "az" { "Azure" } else { "Google Cloud" };
+            Some(format!("changes {} resources ({} {})", provider, program, words[..=verb].join(" ")))
+        }
+        _ => None,
+    }
+}
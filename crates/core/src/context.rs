@@ -0,0 +1,130 @@
+//! Conversation history budgeting and rolling summarization.
+
+use crate::{get_system_prompt_text, GeminiContent, GeminiPart, ShellmindConfig};
+
+/// Number of leading history entries holding the system prompt exchange, never summarized.
+pub const PREAMBLE_LEN: usize = 2;
+
+/// Number of most recent history entries kept verbatim when older turns are summarized.
+pub const KEEP_RECENT: usize = 6;
+
+const SUMMARY_MARKER: &str = "Conversation so far:";
+
+/// The opening exchange of every conversation: the system prompt and the model's acknowledgement.
+pub fn initial_history(config: &ShellmindConfig) -> Vec<GeminiContent> {
+    vec![
+        GeminiContent {
+            role: "user".to_string(),
+            parts: vec![GeminiPart { text: get_system_prompt_text(config), inline_data: None }],
+        },
+        GeminiContent {
+            role: "model".to_string(),
+            parts: vec![GeminiPart { text: "Okay, I'm ready. What can I help you with?".to_string(), inline_data: None }],
+        },
+    ]
+}
+
+/// Rough token estimate (~4 characters per token), good enough for budgeting.
+pub fn estimate_tokens(history: &[GeminiContent]) -> usize {
+    history
+        .iter()
+        .flat_map(|c| c.parts.iter())
+        .map(|p| p.text.chars().count() / 4 + 1)
+        .sum()
+}
+
+/// True when the history uses more than 80% of `budget` and has turns old enough to fold.
+pub fn needs_compression(history: &[GeminiContent], budget: usize) -> bool {
+    history.len() > PREAMBLE_LEN + KEEP_RECENT && estimate_tokens(history) * 5 > budget * 4
+}
+
+/// Returns the range of history entries that should be folded into a summary.
+pub fn compressible_range(history: &[GeminiContent]) -> std::ops::Range<usize> {
+    PREAMBLE_LEN..history.len().saturating_sub(KEEP_RECENT).max(PREAMBLE_LEN)
+}
+
+/// Builds the prompt asking a model to summarize `turns` into a compact note.
+pub fn build_summary_prompt(turns: &[GeminiContent]) -> String {
+    let mut prompt = String::from(
+        "Summarize the following conversation between a user and a terminal assistant into a compact note. Keep facts that matter for later requests: paths, hostnames, decisions, commands that worked or failed. Reply with the note only.\n\n",
+    );
+    prompt.push_str(&render_turns(turns));
+    prompt
+}
+
+/// Summarizes `turns` without a model call by keeping the first line of each turn.
+pub fn local_summary(turns: &[GeminiContent]) -> String {
+    turns
+        .iter()
+        .map(|c| {
+            let first_line = c.parts.first().and_then(|p| p.text.lines().next()).unwrap_or("");
+            format!("- {}: {}", c.role, truncate(first_line, 160))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replaces the compressible turns with a single summary exchange.
+pub fn apply_summary(history: &mut Vec<GeminiContent>, summary: &str) {
+    let range = compressible_range(history);
+    if range.is_empty() {
+        return;
+    }
+    history.splice(
+        range,
+        vec![
+            GeminiContent {
+                role: "user".to_string(),
+                parts: vec![GeminiPart { text: format!("{}\n{}", SUMMARY_MARKER, summary.trim()), inline_data: None }],
+            },
+            GeminiContent {
+                role: "model".to_string(),
+                parts: vec![GeminiPart { text: "Understood, I'll keep that context in mind.".to_string(), inline_data: None }],
+            },
+        ],
+    );
+}
+
+fn render_turns(turns: &[GeminiContent]) -> String {
+    turns
+        .iter()
+        .map(|c| {
+            let text = c.parts.iter().map(|p| p.text.as_str()).collect::<Vec<_>>().join("\n");
+            format!("{}: {}", c.role, text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}...", text.chars().take(max_chars).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(role: &str, text: &str) -> GeminiContent {
+        GeminiContent {
+            role: role.to_string(),
+            parts: vec![GeminiPart { text: text.to_string(), inline_data: None }],
+        }
+    }
+
+    #[test]
+    fn test_apply_summary_keeps_preamble_and_recent_turns() {
+        let mut history: Vec<GeminiContent> = (0..12)
+            .map(|i| turn(if i % 2 == 0 { "user" } else { "model" }, &format!("turn {}", i)))
+            .collect();
+        apply_summary(&mut history, "summary");
+
+        assert_eq!(history.len(), PREAMBLE_LEN + 2 + KEEP_RECENT);
+        assert_eq!(history[0].parts[0].text, "turn 0");
+        assert!(history[PREAMBLE_LEN].parts[0].text.ends_with("summary"));
+        assert_eq!(history.last().unwrap().parts[0].text, "turn 11");
+    }
+}
@@ -0,0 +1,155 @@
+//! Optional at-rest encryption (ChaCha20-Poly1305) for sessions, history and other state.
+//! The 256-bit key is generated on first use and kept in `~/.shellmind/storage.key`,
+//! readable only by the owner. A key that can't be read is an error, never replaced:
+//! a new key would make everything encrypted so far unrecoverable.
+
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::ShellmindError;
+
+const MAGIC: &[u8] = b"SMENC1";
+/// Secure storage entry earlier versions kept the key in; moved to the key file on first use.
+const LEGACY_KEY_SECRET_NAME: &str = "storage_encryption_key";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns encryption of newly written state on or off for this process.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn key_path() -> Result<PathBuf, ShellmindError> {
+    let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+    Ok(home_dir.join(".shellmind").join("storage.key"))
+}
+
+/// Creates the key file with `key` unless it exists. Another process creating it first
+/// is not an error; its key is the one used.
+fn create_key_file(path: &std::path::Path, key: &[u8]) -> Result<bool, ShellmindError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", parent.display(), e)))?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = match options.open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return Ok(false),
+        Err(e) => return Err(ShellmindError::Other(format!("Failed to create storage key '{}': {}", path.display(), e))),
+    };
+    let encoded = base64::engine::general_purpose::STANDARD.encode(key);
+    file.write_all(encoded.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| ShellmindError::Other(format!("Failed to write storage key '{}': {}", path.display(), e)))?;
+    Ok(true)
+}
+
+fn read_key_file(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    // A racing creator may not have written the key yet
+    for _ in 0..50 {
+        let encoded = std::fs::read_to_string(path)?;
+        if !encoded.trim().is_empty() {
+            return base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    Err(std::io::Error::new(ErrorKind::InvalidData, "the key file is empty"))
+}
+
+/// The storage key. With `create`, a missing key is generated (or moved from secure
+/// storage); otherwise, as when decrypting, a missing key is an error.
+fn storage_key(create: bool) -> Result<LessSafeKey, ShellmindError> {
+    let path = key_path()?;
+    let key_bytes = match read_key_file(&path) {
+        Ok(key) => key,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let legacy = extensions::secure_storage::retrieve_secret(LEGACY_KEY_SECRET_NAME).ok();
+            if legacy.is_none() && !create {
+                return Err(ShellmindError::Other(format!("The storage encryption key '{}' is missing; encrypted data can't be read", path.display())));
+            }
+            let key = match legacy {
+                Some(encoded) => base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|e| ShellmindError::Other(format!("Invalid storage encryption key: {}", e)))?,
+                None => {
+                    let mut key = vec![0u8; CHACHA20_POLY1305.key_len()];
+                    SystemRandom::new()
+                        .fill(&mut key)
+                        .map_err(|_| ShellmindError::Other("Failed to generate storage encryption key".to_string()))?;
+                    key
+                }
+            };
+            if create_key_file(&path, &key)? {
+                let _ = extensions::secure_storage::delete_secret(LEGACY_KEY_SECRET_NAME);
+                key
+            } else {
+                read_key_file(&path).map_err(|e| ShellmindError::Other(format!("Failed to read storage key '{}': {}", path.display(), e)))?
+            }
+        }
+        Err(e) => return Err(ShellmindError::Other(format!("Failed to read storage key '{}': {}", path.display(), e))),
+    };
+    let unbound = UnboundKey::new(&CHACHA20_POLY1305, &key_bytes)
+        .map_err(|_| ShellmindError::Other("Invalid storage encryption key length".to_string()))?;
+    Ok(LessSafeKey::new(unbound))
+}
+
+/// Encrypts `plaintext` as `MAGIC || nonce || ciphertext+tag`.
+pub fn encrypt(plaintext: &[u8]) -> Result<Vec<u8>, ShellmindError> {
+    let key = storage_key(true)?;
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce_bytes)
+        .map_err(|_| ShellmindError::Other("Failed to generate nonce".to_string()))?;
+
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce_bytes), Aad::from(MAGIC), &mut in_out)
+        .map_err(|_| ShellmindError::Other("Encryption failed".to_string()))?;
+
+    let mut output = Vec::with_capacity(MAGIC.len() + NONCE_LEN + in_out.len());
+    output.extend_from_slice(MAGIC);
+    output.extend_from_slice(&nonce_bytes);
+    output.extend_from_slice(&in_out);
+    Ok(output)
+}
+
+/// Decrypts data produced by `encrypt`; plaintext input is returned unchanged so
+/// enabling encryption doesn't strand existing files.
+pub fn decrypt(data: &[u8]) -> Result<Vec<u8>, ShellmindError> {
+    if !is_encrypted(data) {
+        return Ok(data.to_vec());
+    }
+    let rest = &data[MAGIC.len()..];
+    if rest.len() < NONCE_LEN {
+        return Err(ShellmindError::Other("Encrypted data is truncated".to_string()));
+    }
+    let (nonce_bytes, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+        .map_err(|_| ShellmindError::Other("Invalid nonce".to_string()))?;
+
+    let key = storage_key(false)?;
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(MAGIC), &mut in_out)
+        .map_err(|_| ShellmindError::Other("Decryption failed: wrong key or corrupted data".to_string()))?;
+    Ok(plaintext.to_vec())
+}
@@ -0,0 +1,159 @@
+//! Declarative tools defined in config.toml (`[[custom_tools]]`) or `.shellmind/tools/*.toml`:
+//! a name, a JSON schema for the parameters, and a shell command template.
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use tokio::signal::unix::Signal;
+
+use crate::progress::ProgressSender;
+use crate::{BaseTool, ConfirmationDetails, ShellmindError, ToolResult};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomToolDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub description: String,
+    /// JSON schema of the parameters, e.g. `{ type = "object", properties = { env = { type = "string" } }, required = ["env"] }`.
+    #[serde(default = "default_parameters")]
+    pub parameters: serde_json::Value,
+    /// Shell command with `{{param}}` placeholders; values are single-quoted before substitution.
+    pub command: String,
+}
+
+fn default_parameters() -> serde_json::Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+/// Loads every `*.toml` tool definition from `dir`; a missing directory yields no tools.
+pub fn load_tool_definitions(dir: &Path) -> Result<Vec<CustomToolDefinition>, ShellmindError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let pattern = format!("{}/*.toml", dir.display());
+    let mut definitions = Vec::new();
+    for entry in glob::glob(&pattern).map_err(|e| ShellmindError::Other(format!("Invalid glob pattern: {}", e)))? {
+        let path = entry.map_err(|e| ShellmindError::Other(format!("Error matching glob pattern: {}", e)))?;
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to read tool definition '{}': {}", path.display(), e)))?;
+        let definition: CustomToolDefinition = toml::from_str(&content)
+            .map_err(|e| ShellmindError::Other(format!("Invalid tool definition '{}': {}", path.display(), e)))?;
+        definitions.push(definition);
+    }
+    Ok(definitions)
+}
+
+/// A `BaseTool` materialized from a `CustomToolDefinition`. Always asks for confirmation
+/// and shows the rendered command.
+pub struct CustomTool {
+    // Tools live for the whole process; leaking gives BaseTool its 'static strings.
+    name: &'static str,
+    display_name: &'static str,
+    description: &'static str,
+    parameters: serde_json::Value,
+    command: String,
+}
+
+impl CustomTool {
+    pub fn new(definition: CustomToolDefinition) -> Self {
+        let display_name = definition.display_name.clone().unwrap_or_else(|| definition.name.clone());
+        CustomTool {
+            name: crate::tool_source::intern(definition.name),
+            display_name: crate::tool_source::intern(display_name),
+            description: crate::tool_source::intern(definition.description),
+            parameters: definition.parameters,
+            command: definition.command,
+        }
+    }
+
+    fn required_params(&self) -> Vec<&str> {
+        self.parameters
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Substitutes `{{param}}` placeholders with shell-quoted parameter values.
+    pub fn render_command(&self, params: &serde_json::Value) -> Result<String, ShellmindError> {
+        let placeholder = regex::Regex::new(r"\{\{\s*([A-Za-z0-9_]+)\s*\}\}").expect("valid placeholder regex");
+        let mut missing = None;
+        let rendered = placeholder.replace_all(&self.command, |caps: &regex::Captures| {
+            let key = &caps[1];
+            match params.get(key) {
+                Some(serde_json::Value::String(s)) => shell_quote(s),
+                Some(serde_json::Value::Null) | None => {
+                    missing = Some(key.to_string());
+                    String::new()
+                }
+                Some(other) => shell_quote(&other.to_string()),
+            }
+        });
+        match missing {
+            Some(key) => Err(ShellmindError::Other(format!("Missing parameter '{}' for {}", key, self.name))),
+            None => Ok(rendered.into_owned()),
+        }
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+impl BaseTool for CustomTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.display_name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn parameter_schema(&self) -> serde_json::Value {
+        self.parameters.clone()
+    }
+
+    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
+        params.is_object() && self.required_params().iter().all(|key| params.get(key).is_some())
+    }
+
+    fn get_description(&self, params: &serde_json::Value) -> String {
+        match self.render_command(params) {
+            Ok(command) => format!("Run {}: {}", self.display_name, command),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn should_confirm_execute(&self, params: &serde_json::Value) -> Option<ConfirmationDetails> {
+        let command = self.render_command(params).unwrap_or_else(|_| self.command.clone());
+        Some(ConfirmationDetails { message: format!("This will run custom tool '{}': '{}'. Are you sure?", self.name, command) })
+    }
+
+    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let command = self.render_command(&params);
+        Box::pin(async move {
+            let command = crate::limits::limit_shell_command(&command?);
+            let output = tokio::process::Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|e| ShellmindError::Other(format!("Failed to execute command: {}", e)))?;
+
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            if output.status.success() {
+                Ok(ToolResult::success(stdout))
+            } else {
+                Ok(ToolResult::error(format!("Command failed with exit code {:?}: {}", output.status.code(), stderr)))
+            }
+        })
+    }
+}
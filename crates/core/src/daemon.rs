@@ -0,0 +1,123 @@
+//! `shellmind daemon`: a long-running process that serves the editor protocol on a
+//! Unix socket, with sessions shared across connections, and applies retention hourly.
+//! It is what `shellmind install-service` runs at login.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::BufReader;
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::editor::EditorServer;
+use crate::session::SessionManager;
+use crate::transport::Transport;
+use crate::{ShellmindConfig, ShellmindError, ToolRegistry};
+
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub fn socket_path() -> Result<PathBuf, ShellmindError> {
+    let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+    Ok(home_dir.join(".shellmind").join("daemon").join("daemon.sock"))
+}
+
+/// Binds the socket, replacing a stale one left by a daemon that did not exit cleanly.
+async fn bind() -> Result<UnixListener, ShellmindError> {
+    let path = socket_path()?;
+    if path.exists() {
+        if UnixStream::connect(&path).await.is_ok() {
+            return Err(ShellmindError::Other(format!("A Shellmind daemon is already listening on {}", path.display())));
+        }
+        std::fs::remove_file(&path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to remove stale socket '{}': {}", path.display(), e)))?;
+    }
+    // Connections run commands as this user, so only this user may reach the socket. It
+    // lives in a private directory so there is no moment where others could connect
+    // between `bind` and `chmod`.
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    if let Some(parent) = path.parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(parent)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", parent.display(), e)))?;
+        // `create` leaves the mode of an existing directory alone
+        std::fs::set_permissions(parent, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| ShellmindError::Other(format!("Failed to set permissions on '{}': {}", parent.display(), e)))?;
+    }
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| ShellmindError::Other(format!("Failed to listen on '{}': {}", path.display(), e)))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| ShellmindError::Other(format!("Failed to set permissions on '{}': {}", path.display(), e)))?;
+    Ok(listener)
+}
+
+async fn serve_connection(
+    config: ShellmindConfig,
+    transport: Arc<dyn Transport>,
+    sessions: SessionManager,
+    stream: UnixStream,
+) -> Result<(), ShellmindError> {
+    let mut tools = ToolRegistry::from_config(&config)?;
+    tools.discover_tools().await?;
+    let mut server = EditorServer::new(config, transport, tools, sessions);
+    let (reader, writer) = stream.into_split();
+    server.run(BufReader::new(reader), writer).await
+}
+
+/// Runs until SIGTERM or Ctrl-C, then stops the processes its sessions left running and
+/// removes the socket.
+pub async fn run(config: ShellmindConfig, transport: Arc<dyn Transport>) -> Result<(), ShellmindError> {
+    let listener = bind().await?;
+    let path = socket_path()?;
+    println!("Shellmind daemon listening on {}", path.display());
+
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .map_err(|e| ShellmindError::Other(format!("Failed to install signal handler: {}", e)))?;
+    let mut maintenance = tokio::time::interval(MAINTENANCE_INTERVAL);
+    // Shared by all connections, so clients resuming the same session work on one copy
+    let sessions = SessionManager::new();
+
+    // Tool registries are not `Send`, so connections run as local tasks
+    let local = tokio::task::LocalSet::new();
+    let result = local
+        .run_until(async {
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => {
+                        let (stream, _) = accepted
+                            .map_err(|e| ShellmindError::Other(format!("Failed to accept a connection: {}", e)))?;
+                        let config = config.clone();
+                        let transport = Arc::clone(&transport);
+                        let sessions = sessions.clone();
+                        tokio::task::spawn_local(async move {
+                            if let Err(e) = serve_connection(config, transport, sessions, stream).await {
+                                eprintln!("Connection ended with an error: {}", e);
+                            }
+                        });
+                    }
+                    _ = maintenance.tick() => {
+                        if let Err(e) = crate::retention::apply_retention(&config) {
+                            eprintln!("Retention failed: {}", e);
+                        }
+                    }
+                    _ = terminate.recv() => break,
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+            }
+            Ok(())
+        })
+        .await;
+
+    let _ = std::fs::remove_file(&path);
+    let report = tokio::task::spawn_blocking(|| crate::processes::shutdown(crate::processes::GRACE_PERIOD))
+        .await
+        .unwrap_or_default();
+    for process in &report.stopped {
+        println!("Stopped {} ({})", process.pid, crate::secrets::mask(&process.command));
+    }
+    for process in &report.still_running {
+        eprintln!("Still running after shutdown: {} ({})", process.pid, crate::secrets::mask(&process.command));
+    }
+    crate::audit::drain(Duration::from_secs(5)).await;
+    result
+}
@@ -0,0 +1,450 @@
+//! Container tools that talk to the Docker Engine API over its Unix socket. Podman serves
+//! the same API, so either works: `docker_socket` picks the socket, otherwise
+//! `DOCKER_HOST`, the Docker socket and the rootless and rootful Podman sockets are tried.
+//! Listing containers and reading logs need no confirmation; `docker_exec` runs a command
+//! inside a container and is treated as dangerous.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::signal::unix::Signal;
+
+use crate::progress::ProgressSender;
+use crate::{ConfirmationDetails, SafetyLevel, ShellmindConfig, ShellmindError, ToolResult, TypedTool};
+
+const DEFAULT_TAIL: u32 = 200;
+
+/// How long `docker_exec` waits for the command unless the call sets `timeout_secs`.
+const DEFAULT_EXEC_TIMEOUT_SECS: u64 = 120;
+
+/// The socket of the Docker-compatible engine; shared by the container tools.
+#[derive(Debug, Clone)]
+pub struct DockerClient {
+    /// `docker_socket` from the config; empty detects the socket on each call.
+    pub socket: String,
+}
+
+impl DockerClient {
+    pub fn from_config(config: &ShellmindConfig) -> Self {
+        DockerClient { socket: config.docker_socket.clone() }
+    }
+
+    fn socket_path(&self) -> Result<PathBuf, ShellmindError> {
+        if !self.socket.is_empty() {
+            return Ok(PathBuf::from(self.socket.trim_start_matches("unix://")));
+        }
+        let mut candidates = Vec::new();
+        if let Some(path) = std::env::var("DOCKER_HOST").ok().and_then(|host| host.strip_prefix("unix://").map(PathBuf::from)) {
+            candidates.push(path);
+        }
+        candidates.push(PathBuf::from("/var/run/docker.sock"));
+        if let Some(runtime) = std::env::var_os("XDG_RUNTIME_DIR") {
+            candidates.push(PathBuf::from(runtime).join("podman/podman.sock"));
+        }
+        candidates.push(PathBuf::from("/run/podman/podman.sock"));
+        candidates.into_iter().find(|path| path.exists()).ok_or_else(|| {
+            ShellmindError::Other("No Docker or Podman socket found; start the engine or set docker_socket".to_string())
+        })
+    }
+
+    /// Sends one request and returns the status code and body. The connection is closed
+    /// after each request, so the body ends where the stream does.
+    async fn call(&self, method: &str, path: &str, body: Option<&serde_json::Value>) -> Result<(u16, Vec<u8>), ShellmindError> {
+        let socket = self.socket_path()?;
+        let mut stream = tokio::net::UnixStream::connect(&socket)
+            .await
+            .map_err(|e| ShellmindError::Other(format!("Failed to connect to '{}': {}", socket.display(), e)))?;
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let mut request = format!("{} {} HTTP/1.1\r\nHost: docker\r\nConnection: close\r\n", method, path);
+        if method == "POST" {
+            request.push_str(&format!("Content-Type: application/json\r\nContent-Length: {}\r\n", body.len()));
+        }
+        request.push_str("\r\n");
+        request.push_str(&body);
+
+        let io_error = |e: std::io::Error| ShellmindError::Other(format!("Docker API request failed: {}", e));
+        stream.write_all(request.as_bytes()).await.map_err(io_error)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.map_err(io_error)?;
+        parse_response(&response)
+    }
+
+    async fn json(&self, method: &str, path: &str, body: Option<&serde_json::Value>) -> Result<serde_json::Value, ShellmindError> {
+        let (status, body) = self.call(method, path, body).await?;
+        let value: serde_json::Value = if body.is_empty() { serde_json::Value::Null } else { serde_json::from_slice(&body)? };
+        if status >= 400 {
+            let message = value.get("message").and_then(|m| m.as_str()).unwrap_or("no details");
+            return Err(ShellmindError::Other(format!("Docker API returned {}: {}", status, message)));
+        }
+        Ok(value)
+    }
+}
+
+fn parse_response(response: &[u8]) -> Result<(u16, Vec<u8>), ShellmindError> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| ShellmindError::Other("Malformed response from the Docker API".to_string()))?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let body = &response[split + 4..];
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| ShellmindError::Other("Malformed status line from the Docker API".to_string()))?;
+    let chunked = head.lines().any(|line| {
+        let line = line.to_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+    Ok((status, if chunked { dechunk(body) } else { body.to_vec() }))
+}
+
+fn dechunk(mut body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Some(end) = body.windows(2).position(|w| w == b"\r\n") {
+        let size_line = String::from_utf8_lossy(&body[..end]);
+        let Ok(size) = usize::from_str_radix(size_line.split(';').next().unwrap_or("").trim(), 16) else {
+            break;
+        };
+        let start = end + 2;
+        if size == 0 || start + size > body.len() {
+            break;
+        }
+        out.extend_from_slice(&body[start..start + size]);
+        body = &body[(start + size + 2).min(body.len())..];
+    }
+    out
+}
+
+/// Output of a container without a TTY comes in frames with an 8-byte header naming the
+/// stream; with a TTY it is plain text.
+fn demultiplex(raw: &[u8]) -> String {
+    let framed = raw.len() >= 8 && raw[0] <= 2 && raw[1..4] == [0, 0, 0];
+    if !framed {
+        return String::from_utf8_lossy(raw).into_owned();
+    }
+    let mut out = Vec::new();
+    let mut rest = raw;
+    while rest.len() >= 8 {
+        let size = u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let end = (8 + size).min(rest.len());
+        out.extend_from_slice(&rest[8..end]);
+        rest = &rest[end..];
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Percent-encodes a container name or ID for use in a path.
+fn path_segment(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+pub struct DockerListTool {
+    pub client: DockerClient,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DockerListParams {
+    /// Include stopped and restarting containers (default true).
+    pub all: Option<bool>,
+    /// Only containers whose name contains this text.
+    pub name: Option<String>,
+}
+
+impl TypedTool for DockerListTool {
+    type Params = DockerListParams;
+
+    fn name(&self) -> &'static str {
+        "docker_list_containers"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Docker Containers"
+    }
+
+    fn description(&self) -> &'static str {
+        "Lists Docker or Podman containers with their image, state, status and ports."
+    }
+
+    fn get_description(&self, params: &DockerListParams) -> String {
+        match &params.name {
+            Some(name) => format!("List containers matching '{}'", name),
+            None => "List containers".to_string(),
+        }
+    }
+
+    fn should_confirm_execute(&self, _params: &DockerListParams) -> Option<ConfirmationDetails> {
+        None // Read-only
+    }
+
+    fn execute(&self, params: DockerListParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let mut path = format!("/containers/json?all={}", params.all.unwrap_or(true));
+            if let Some(name) = &params.name {
+                let filters = serde_json::json!({ "name": [name] }).to_string();
+                path.push_str(&format!("&filters={}", path_segment(&filters)));
+            }
+            let containers = client.json("GET", &path, None).await?;
+            let rows: Vec<serde_json::Value> = containers
+                .as_array()
+                .map(|list| {
+                    list.iter()
+                        .map(|c| {
+                            let name = c["Names"][0].as_str().unwrap_or("").trim_start_matches('/');
+                            let ports: Vec<String> = c["Ports"]
+                                .as_array()
+                                .map(|ports| {
+                                    ports
+                                        .iter()
+                                        .map(|p| match p["PublicPort"].as_u64() {
+                                            Some(public) => format!("{}->{}/{}", public, p["PrivatePort"], p["Type"].as_str().unwrap_or("tcp")),
+                                            None => format!("{}/{}", p["PrivatePort"], p["Type"].as_str().unwrap_or("tcp")),
+                                        })
+                                        .collect()
+                                })
+                                .unwrap_or_default();
+                            serde_json::json!({
+                                "id": c["Id"].as_str().unwrap_or("").chars().take(12).collect::<String>(),
+                                "name": name,
+                                "image": c["Image"],
+                                "state": c["State"],
+                                "status": c["Status"],
+                                "ports": ports.join(", "),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            if rows.is_empty() {
+                return Ok(ToolResult::success("No containers"));
+            }
+            let display = rows
+                .iter()
+                .map(|r| format!("{} {} ({}): {}", r["id"].as_str().unwrap_or(""), r["name"].as_str().unwrap_or(""), r["image"].as_str().unwrap_or(""), r["status"].as_str().unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(ToolResult::success(display).with_content(serde_json::Value::Array(rows)))
+        })
+    }
+}
+
+pub struct DockerLogsTool {
+    pub client: DockerClient,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DockerLogsParams {
+    /// Container name or ID.
+    pub container: String,
+    /// Number of lines from the end of the log (default 200).
+    pub tail: Option<u32>,
+    /// Only lines from the last this many minutes.
+    pub since_minutes: Option<u64>,
+}
+
+impl TypedTool for DockerLogsTool {
+    type Params = DockerLogsParams;
+
+    fn name(&self) -> &'static str {
+        "docker_logs"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Docker Logs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Shows a container's state (restart count, exit code, OOM kill, last error) and the end of its log."
+    }
+
+    fn get_description(&self, params: &DockerLogsParams) -> String {
+        format!("Read the last {} log lines of {}", params.tail.unwrap_or(DEFAULT_TAIL), params.container)
+    }
+
+    fn should_confirm_execute(&self, _params: &DockerLogsParams) -> Option<ConfirmationDetails> {
+        None // Read-only
+    }
+
+    fn execute(&self, params: DockerLogsParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let container = path_segment(&params.container);
+            let inspect = client.json("GET", &format!("/containers/{}/json", container), None).await?;
+            let state = &inspect["State"];
+            let summary = serde_json::json!({
+                "status": state["Status"],
+                "restarting": state["Restarting"],
+                "restart_count": inspect["RestartCount"],
+                "exit_code": state["ExitCode"],
+                "oom_killed": state["OOMKilled"],
+                "error": state["Error"],
+                "started_at": state["StartedAt"],
+                "finished_at": state["FinishedAt"],
+                "health": state["Health"]["Status"],
+            });
+
+            let mut path = format!("/containers/{}/logs?stdout=1&stderr=1&timestamps=1&tail={}", container, params.tail.unwrap_or(DEFAULT_TAIL));
+            if let Some(minutes) = params.since_minutes {
+                let since = chrono::Utc::now().timestamp() - (minutes as i64) * 60;
+                path.push_str(&format!("&since={}", since));
+            }
+            let (status, body) = client.call("GET", &path, None).await?;
+            if status >= 400 {
+                return Ok(ToolResult::error(format!("Docker API returned {}: {}", status, String::from_utf8_lossy(&body))));
+            }
+            // Logs routinely echo tokens and connection strings
+            let logs = crate::secrets::mask(&demultiplex(&body));
+            let display = format!(
+                "{}: {} (restarts: {}, exit code: {}, OOM killed: {})\n\n{}",
+                params.container,
+                state["Status"].as_str().unwrap_or("unknown"),
+                inspect["RestartCount"],
+                state["ExitCode"],
+                state["OOMKilled"],
+                logs.trim_end()
+            );
+            Ok(ToolResult::success(display).with_content(serde_json::json!({ "state": summary, "logs": logs })))
+        })
+    }
+}
+
+pub struct DockerExecTool {
+    pub client: DockerClient,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DockerExecParams {
+    /// Container name or ID.
+    pub container: String,
+    /// Command run with `sh -c` inside the container.
+    pub command: String,
+    /// Seconds to wait for the command to finish (default 120).
+    pub timeout_secs: Option<u64>,
+}
+
+impl TypedTool for DockerExecTool {
+    type Params = DockerExecParams;
+
+    fn name(&self) -> &'static str {
+        "docker_exec"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Docker Exec"
+    }
+
+    fn description(&self) -> &'static str {
+        "Runs a shell command inside a running container and returns its output and exit code."
+    }
+
+    fn get_description(&self, params: &DockerExecParams) -> String {
+        format!("Run '{}' in container {}", params.command, params.container)
+    }
+
+    fn should_confirm_execute(&self, params: &DockerExecParams) -> Option<ConfirmationDetails> {
+        Some(ConfirmationDetails {
+            message: format!("This will run '{}' inside container {}. Are you sure?", params.command, params.container),
+        })
+    }
+
+    fn safety_level(&self, _params: &DockerExecParams) -> SafetyLevel {
+        // Containers often hold production data and run as root
+        SafetyLevel::Dangerous
+    }
+
+    fn execute(&self, params: DockerExecParams, signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let create = serde_json::json!({
+                "AttachStdout": true,
+                "AttachStderr": true,
+                "Cmd": ["sh", "-c", params.command],
+            });
+            let exec = client.json("POST", &format!("/containers/{}/exec", path_segment(&params.container)), Some(&create)).await?;
+            let id = exec["Id"].as_str().ok_or_else(|| ShellmindError::Other("The Docker API returned no exec ID".to_string()))?.to_string();
+            let start = serde_json::json!({ "Detach": false, "Tty": false });
+            let timeout = params.timeout_secs.unwrap_or(DEFAULT_EXEC_TIMEOUT_SECS);
+            let cancelled = async move {
+                match signal {
+                    Some(mut signal) => {
+                        signal.recv().await;
+                    }
+                    None => std::future::pending::<()>().await,
+                }
+            };
+            // The engine cannot stop an exec, so giving up only closes the connection
+            let (status, body) = tokio::select! {
+                response = client.call("POST", &format!("/exec/{}/start", id), Some(&start)) => response?,
+                _ = tokio::time::sleep(std::time::Duration::from_secs(timeout)) => {
+                    return Ok(ToolResult::error(format!("The command did not finish within {} seconds and may still be running in {}", timeout, params.container)));
+                }
+                _ = cancelled => {
+                    return Ok(ToolResult::error(format!("Cancelled; the command may still be running in {}", params.container)));
+                }
+            };
+            if status >= 400 {
+                return Ok(ToolResult::error(format!("Docker API returned {}: {}", status, String::from_utf8_lossy(&body))));
+            }
+            let output = demultiplex(&body);
+            let inspect = client.json("GET", &format!("/exec/{}/json", id), None).await?;
+            let exit_code = inspect["ExitCode"].as_i64();
+            let content = serde_json::json!({ "exit_code": exit_code, "output": output });
+            if exit_code == Some(0) {
+                Ok(ToolResult::success(output).with_content(content))
+            } else {
+                Ok(ToolResult::error(format!("Command failed with exit code {:?}: {}", exit_code, output)).with_content(content))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response_plain_body() {
+        let (status, body) = parse_response(b"HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\n\r\n{\"message\":\"no such container\"}").unwrap();
+        assert_eq!(status, 404);
+        assert_eq!(body, b"{\"message\":\"no such container\"}");
+    }
+
+    #[test]
+    fn test_parse_response_chunked_body() {
+        let (status, body) = parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n").unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(body, b"hello");
+    }
+
+    #[test]
+    fn test_parse_response_rejects_malformed_input() {
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+        assert!(parse_response(b"garbage\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_dechunk_joins_chunks() {
+        assert_eq!(dechunk(b"3;ext=1\r\nabc\r\na\r\n0123456789\r\n0\r\n\r\n"), b"abc0123456789");
+        // A truncated chunk ends the body instead of reading past it
+        assert_eq!(dechunk(b"3\r\nabc\r\nff\r\nshort"), b"abc");
+    }
+
+    #[test]
+    fn test_demultiplex_frames() {
+        let mut raw = vec![1, 0, 0, 0, 0, 0, 0, 4];
+        raw.extend_from_slice(b"out\n");
+        raw.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 4]);
+        raw.extend_from_slice(b"err\n");
+        assert_eq!(demultiplex(&raw), "out\nerr\n");
+    }
+
+    #[test]
+    fn test_demultiplex_tty_output() {
+        assert_eq!(demultiplex(b"plain tty output\n"), "plain tty output\n");
+    }
+}
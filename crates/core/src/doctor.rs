@@ -0,0 +1,139 @@
+//! Environment diagnostics for `shellmind doctor`.
+
+use std::path::Path;
+
+use gemini_client::GeminiClient;
+use crate::{ApiType, ConfigManager, SandboxManager, ShellmindConfig, ShellmindError};
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+    /// What the user can do about a failure.
+    pub fix: Option<String>,
+    /// Failures of optional checks are warnings and don't fail the diagnosis.
+    pub optional: bool,
+}
+
+impl CheckResult {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        CheckResult { name: name.to_string(), ok: true, detail: detail.into(), fix: None, optional: false }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        CheckResult { name: name.to_string(), ok: false, detail: detail.into(), fix: Some(fix.into()), optional: false }
+    }
+
+    fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+}
+
+/// Optional external programs used by tools, with what they enable.
+const OPTIONAL_BINARIES: &[(&str, &str)] = &[
+    ("git", "version control context and config sync"),
+    ("docker", "container tools"),
+    ("ffmpeg", "video processing"),
+];
+
+fn check_writable(name: &str, dir: &Path) -> CheckResult {
+    let probe = dir.join(format!(".doctor-{}", uuid::Uuid::new_v4()));
+    let result = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&probe, b"ok"));
+    let _ = std::fs::remove_file(&probe);
+    match result {
+        Ok(()) => CheckResult::pass(name, format!("{} is writable", dir.display())),
+        Err(e) => CheckResult::fail(
+            name,
+            format!("{} is not writable: {}", dir.display(), e),
+            format!("Check ownership and permissions, e.g. `chown -R $USER {}`", dir.display()),
+        ),
+    }
+}
+
+async fn check_api_key(client: &GeminiClient, config: &ShellmindConfig) -> CheckResult {
+    if let Err(e) = ConfigManager::validate_configuration(config) {
+        return CheckResult::fail("API key", e.to_string(), "Run `shellmind config set api_key <key>` or export GEMINI_API_KEY");
+    }
+    match client.check_api_key(&config.api_key).await {
+        Ok(()) => CheckResult::pass("API key", "Gemini API accepted the key"),
+        Err(gemini_client::Error::Status { status, .. }) => CheckResult::fail(
+            "API key",
+            format!("Gemini API returned {}", status),
+            "Verify the key at https://aistudio.google.com/app/apikey and that the API is enabled",
+        ),
+        Err(e) => CheckResult::fail("API key", format!("Could not reach the Gemini API: {}", e), "Check network access and proxy settings"),
+    }
+}
+
+async fn check_grpc(client: &GeminiClient, config: &ShellmindConfig) -> CheckResult {
+    match client.connect_grpc(&config.grpc_endpoint).await {
+        Ok(()) => CheckResult::pass("gRPC endpoint", format!("Connected to {}", config.grpc_endpoint)),
+        Err(e) => CheckResult::fail(
+            "gRPC endpoint",
+            format!("Could not connect to {}: {}", config.grpc_endpoint, e),
+            "Check grpc_endpoint (it must be an https:// URL with a valid certificate) or use api_type = \"Rest\"",
+        ),
+    }
+}
+
+/// Runs every check. `config` is the result of loading the configuration, so a broken
+/// config file is reported instead of aborting the diagnosis.
+pub async fn run_checks(config: Result<ShellmindConfig, ShellmindError>) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    let config = match config {
+        Ok(config) => {
+            results.push(CheckResult::pass("Configuration", "~/.shellmind/config.toml loaded"));
+            Some(config)
+        }
+        Err(e) => {
+            results.push(CheckResult::fail(
+                "Configuration",
+                e.to_string(),
+                "Fix or remove ~/.shellmind/config.toml; `shellmind config show` lists the expected keys",
+            ));
+            None
+        }
+    };
+
+    if let Some(config) = &config {
+        match crate::api_client::new_client(config) {
+            Ok(client) => {
+                results.push(check_api_key(&client, config).await);
+                if matches!(config.api_type, ApiType::Grpc) {
+                    results.push(check_grpc(&client, config).await);
+                }
+            }
+            Err(e) => results.push(CheckResult::fail("HTTP client", e.to_string(), "Check TLS and proxy environment variables")),
+        }
+
+        results.push(match SandboxManager::ensure_backend(&config.sandbox_backend) {
+            Ok(()) => CheckResult::pass("Sandbox backend", format!("'{}'", config.sandbox_backend)),
+            Err(e) => CheckResult::fail(
+                "Sandbox backend",
+                e.to_string(),
+                "Set sandbox_backend = \"none\", or ask your administrator if it is required by /etc/shellmind/policy.toml",
+            ),
+        });
+    }
+
+    for (binary, purpose) in OPTIONAL_BINARIES {
+        let result = match extensions::which::find_in_path(binary) {
+            Some(path) => CheckResult::pass(binary, path.display().to_string()),
+            None => CheckResult::fail(binary, format!("Not found in PATH; needed for {}", purpose), format!("Install {} with your package manager", binary)),
+        };
+        results.push(result.optional());
+    }
+
+    match dirs::home_dir() {
+        Some(home) => {
+            let state_dir = home.join(".shellmind");
+            results.push(check_writable("State directory", &state_dir));
+            results.push(check_writable("Sessions directory", &state_dir.join("sessions")));
+        }
+        None => results.push(CheckResult::fail("State directory", "Could not find home directory", "Set the HOME environment variable")),
+    }
+
+    results
+}
@@ -0,0 +1,367 @@
+//! JSON-RPC over stdio for editor plugins (VS Code, Neovim). Messages use LSP-style
+//! `Content-Length` framing.
+//!
+//! Requests: `initialize`, `generate {prompt, sessionId?}`, `approve {proposalId, approved}`,
+//! `sessions/list`, `shutdown`. Notifications sent to the editor: `toolEvent` with
+//! `{proposalId, kind: "started" | "progress" | "output" | "finished", current?, total?, message?,
+//! stream?, text?, output?, isError?}`; shell commands send each stdout/stderr line as `output`.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::audit::{self, AuditEvent, AuditKind};
+use crate::session::SessionManager;
+use crate::transport::Transport;
+use crate::{parse_tool_call, ApprovalMode, SecurityManager, ShellmindConfig, ShellmindError, ToolRegistry, ToolResult};
+
+/// Largest message body accepted; bigger ones are skipped without being buffered.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// A framed message, or why its body could not be used. Either way the stream stays in
+/// sync, so the server answers with an error and keeps serving.
+enum Incoming {
+    Message(Value),
+    Invalid { code: i64, message: String },
+}
+
+enum ProposedAction {
+    Tool { name: String, params: Value },
+    Shell { command: String },
+}
+
+pub struct EditorServer {
+    config: ShellmindConfig,
+    transport: Arc<dyn Transport>,
+    tools: ToolRegistry,
+    sessions: SessionManager,
+    proposals: HashMap<u64, ProposedAction>,
+    next_proposal_id: u64,
+}
+
+impl EditorServer {
+    pub fn new(config: ShellmindConfig, transport: Arc<dyn Transport>, tools: ToolRegistry, sessions: SessionManager) -> Self {
+        EditorServer {
+            config,
+            transport,
+            tools,
+            sessions,
+            proposals: HashMap::new(),
+            next_proposal_id: 1,
+        }
+    }
+
+    /// Serves requests from stdin until `shutdown` or end of input.
+    pub async fn run_stdio(&mut self) -> Result<(), ShellmindError> {
+        self.run(BufReader::new(tokio::io::stdin()), tokio::io::stdout()).await
+    }
+
+    /// Serves one client on `reader`/`writer` until it sends `shutdown` or disconnects.
+    pub async fn run<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin>(&mut self, mut reader: R, mut writer: W) -> Result<(), ShellmindError> {
+        while let Some(incoming) = read_message(&mut reader).await? {
+            let message = match incoming {
+                Incoming::Message(message) => message,
+                Incoming::Invalid { code, message } => {
+                    write_message(&mut writer, &json!({ "jsonrpc": "2.0", "id": null, "error": { "code": code, "message": message } })).await?;
+                    continue;
+                }
+            };
+            let id = message.get("id").cloned();
+            let method = message.get("method").and_then(|m| m.as_str()).unwrap_or("").to_string();
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            if method == "shutdown" {
+                write_message(&mut writer, &json!({ "jsonrpc": "2.0", "id": id, "result": null })).await?;
+                break;
+            }
+
+            let result = self.handle(&method, params, &mut writer).await;
+            // Notifications (no id) get no response
+            if let Some(id) = id {
+                let response = match result {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32000, "message": e.to_string() } }),
+                };
+                write_message(&mut writer, &response).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle<W: AsyncWrite + Unpin>(&mut self, method: &str, params: Value, writer: &mut W) -> Result<Value, ShellmindError> {
+        match method {
+            "initialize" => Ok(json!({
+                "serverInfo": { "name": "shellmind", "version": env!("CARGO_PKG_VERSION") },
+                "capabilities": { "generate": true, "approve": true, "toolEvents": true, "sessions": true },
+            })),
+            "generate" => self.generate(params).await,
+            "approve" => self.approve(params, writer).await,
+            "sessions/list" => Ok(json!(self.sessions.ids().await)),
+            _ => Err(ShellmindError::Other(format!("Unknown method: {}", method))),
+        }
+    }
+
+    async fn generate(&mut self, params: Value) -> Result<Value, ShellmindError> {
+        let prompt = params
+            .get("prompt")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| ShellmindError::Other("Missing 'prompt' parameter".to_string()))?
+            .to_string();
+        // An unknown ID continues a session started in the terminal
+        let session = match params.get("sessionId").and_then(|s| s.as_str()) {
+            Some(id) => self.sessions.get_or_resume(id, &self.config).await?,
+            None => self.sessions.create(&self.config).await?,
+        };
+        let mut session = session.lock().await;
+        let session_id = session.id().to_string();
+
+        let response = self.transport.generate(&self.config, &self.config.wrap_prompt(&prompt), &session.request_history()).await?;
+        session.record_turn(&prompt, &response)?;
+        drop(session);
+
+        // Multi-line replies are informational; anything else is a proposal needing approval
+        if response.contains('\n') {
+            return Ok(json!({ "sessionId": session_id, "response": response }));
+        }
+        let (action, description, requires_approval, preview) = match parse_tool_call(&response) {
+            Some((name, tool_params)) => {
+                let tool = self.tools.get_tool(&name).ok_or_else(|| {
+                    ShellmindError::Other(format!("Unknown tool `{}`{}", name, crate::fuzzy::did_you_mean(&self.tools.similar_tool_names(&name))))
+                })?;
+                let preview = crate::approval::build_preview(tool, &tool_params);
+                let requires_approval = tool.should_confirm_execute(&tool_params).is_some()
+                    || tool.safety_level(&tool_params) == crate::SafetyLevel::Dangerous
+                    || self.config.approval_mode == ApprovalMode::Always;
+                let preview_json = json!({ "params": tool_params, "details": preview.details });
+                (ProposedAction::Tool { name, params: tool_params }, preview.description, requires_approval, preview_json)
+            }
+            None => (
+                ProposedAction::Shell { command: response.trim().to_string() },
+                format!("Run shell command: {}", response.trim()),
+                true,
+                Value::Null,
+            ),
+        };
+
+        let proposal_id = self.next_proposal_id;
+        self.next_proposal_id += 1;
+        self.proposals.insert(proposal_id, action);
+        Ok(json!({
+            "sessionId": session_id,
+            "response": response,
+            "proposal": { "id": proposal_id, "description": description, "requiresApproval": requires_approval, "preview": preview },
+        }))
+    }
+
+    async fn approve<W: AsyncWrite + Unpin>(&mut self, params: Value, writer: &mut W) -> Result<Value, ShellmindError> {
+        let proposal_id = params
+            .get("proposalId")
+            .and_then(|p| p.as_u64())
+            .ok_or_else(|| ShellmindError::Other("Missing 'proposalId' parameter".to_string()))?;
+        let approved = params.get("approved").and_then(|a| a.as_bool()).unwrap_or(false);
+        let action = self
+            .proposals
+            .remove(&proposal_id)
+            .ok_or_else(|| ShellmindError::Other(format!("Unknown proposal: {}", proposal_id)))?;
+        if !approved {
+            return Ok(json!({ "proposalId": proposal_id, "status": "rejected" }));
+        }
+        if self.config.approval_mode == ApprovalMode::Suggest {
+            return Err(ShellmindError::Other("Approval mode 'suggest' never executes proposals".to_string()));
+        }
+        let shell_command = match &action {
+            ProposedAction::Tool { params, .. } => params.get("command").and_then(|c| c.as_str()),
+            ProposedAction::Shell { command } => Some(command.as_str()),
+        };
+        if let Some(command) = shell_command {
+            SecurityManager::check_command(&self.config, command)?;
+        }
+        let subject = match &action {
+            ProposedAction::Tool { name, .. } => shell_command.unwrap_or(name).to_string(),
+            ProposedAction::Shell { command } => command.clone(),
+        };
+        audit::record(&self.config, AuditEvent::new(AuditKind::ApprovalGranted, &subject, json!({ "proposalId": proposal_id, "source": "editor" })));
+
+        send_tool_event(writer, json!({ "proposalId": proposal_id, "kind": "started" })).await?;
+        let result = match action {
+            ProposedAction::Tool { name, params } => match self.tools.get_tool(&name) {
+                Some(tool) => {
+                    let (sender, mut receiver) = crate::progress::channel();
+                    let future = tool.execute(params, None, Some(sender));
+                    tokio::pin!(future);
+                    loop {
+                        tokio::select! {
+                            result = &mut future => break result?,
+                            Some(update) = receiver.recv() => {
+                                send_tool_event(writer, json!({
+                                    "proposalId": proposal_id,
+                                    "kind": "progress",
+                                    "current": update.current,
+                                    "total": update.total,
+                                    "message": update.message,
+                                }))
+                                .await?;
+                            }
+                        }
+                    }
+                }
+                None => ToolResult::error(format!("Unknown tool `{}`{}", name, crate::fuzzy::did_you_mean(&self.tools.similar_tool_names(&name)))),
+            },
+            ProposedAction::Shell { command } if crate::simulation::enabled() => {
+                audit::record(&self.config, AuditEvent::new(AuditKind::CommandExecuted, &command, json!({ "exit_code": 0 })));
+                ToolResult::success(crate::simulation::command_output(&command))
+            }
+            ProposedAction::Shell { command } => {
+                let mut child = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .stdout(std::process::Stdio::piped())
+                    .stderr(std::process::Stdio::piped())
+                    .spawn()
+                    .map_err(|e| ShellmindError::Other(format!("Failed to execute command: {}", e)))?;
+                let mut stdout_lines = child.stdout.take().map(|out| BufReader::new(out).lines());
+                let mut stderr_lines = child.stderr.take().map(|err| BufReader::new(err).lines());
+                let (mut stdout, mut stderr) = (String::new(), String::new());
+                // Lines reach the editor as they are printed, not only in the `finished` event
+                while stdout_lines.is_some() || stderr_lines.is_some() {
+                    let (stream, line) = tokio::select! {
+                        line = next_line(&mut stdout_lines), if stdout_lines.is_some() => ("stdout", line),
+                        line = next_line(&mut stderr_lines), if stderr_lines.is_some() => ("stderr", line),
+                    };
+                    let Some(line) = line else {
+                        if stream == "stdout" { stdout_lines = None } else { stderr_lines = None }
+                        continue;
+                    };
+                    send_tool_event(writer, json!({ "proposalId": proposal_id, "kind": "output", "stream": stream, "text": line })).await?;
+                    let buffer = if stream == "stdout" { &mut stdout } else { &mut stderr };
+                    buffer.push_str(&line);
+                    buffer.push('\n');
+                }
+                let status = child
+                    .wait()
+                    .await
+                    .map_err(|e| ShellmindError::Other(format!("Failed to wait for command: {}", e)))?;
+                audit::record(&self.config, AuditEvent::new(AuditKind::CommandExecuted, &command, json!({ "exit_code": status.code() })));
+                if status.success() {
+                    ToolResult::success(stdout)
+                } else {
+                    ToolResult::error(format!("Command failed with exit code {:?}: {}", status.code(), stderr))
+                }
+            }
+        };
+        for path in &result.artifacts {
+            audit::record(&self.config, AuditEvent::new(AuditKind::FileModified, path.to_string_lossy(), json!({ "proposalId": proposal_id })));
+        }
+        send_tool_event(writer, json!({
+            "proposalId": proposal_id,
+            "kind": "finished",
+            "output": result.display,
+            "isError": result.is_error,
+        }))
+        .await?;
+        Ok(json!({ "proposalId": proposal_id, "status": "executed", "result": result }))
+    }
+}
+
+/// The next line of a child's output stream; `None` once it ends or can't be read.
+async fn next_line<R: tokio::io::AsyncRead + Unpin>(lines: &mut Option<tokio::io::Lines<BufReader<R>>>) -> Option<String> {
+    match lines {
+        Some(lines) => lines.next_line().await.ok().flatten(),
+        None => None,
+    }
+}
+
+async fn send_tool_event<W: AsyncWrite + Unpin>(writer: &mut W, params: Value) -> Result<(), ShellmindError> {
+    write_message(writer, &json!({ "jsonrpc": "2.0", "method": "toolEvent", "params": params })).await
+}
+
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Incoming>, ShellmindError> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        let read = reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| ShellmindError::Other(format!("Failed to read from stdin: {}", e)))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(length) = line.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = content_length.ok_or_else(|| ShellmindError::Other("Missing Content-Length header".to_string()))?;
+    if length > MAX_MESSAGE_BYTES {
+        tokio::io::copy(&mut (&mut *reader).take(length as u64), &mut tokio::io::sink())
+            .await
+            .map_err(|e| ShellmindError::Other(format!("Failed to read message body: {}", e)))?;
+        return Ok(Some(Incoming::Invalid {
+            code: -32600,
+            message: format!("Message of {} bytes exceeds the {} byte limit", length, MAX_MESSAGE_BYTES),
+        }));
+    }
+    let mut body = vec![0u8; length];
+    reader
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| ShellmindError::Other(format!("Failed to read message body: {}", e)))?;
+    Ok(Some(match serde_json::from_slice(&body) {
+        Ok(message) => Incoming::Message(message),
+        Err(e) => Incoming::Invalid { code: -32700, message: format!("Parse error: {}", e) },
+    }))
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<(), ShellmindError> {
+    let body = serde_json::to_string(message)?;
+    let framed = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+    writer
+        .write_all(framed.as_bytes())
+        .await
+        .map_err(|e| ShellmindError::Other(format!("Failed to write to stdout: {}", e)))?;
+    writer.flush().await.map_err(|e| ShellmindError::Other(format!("Failed to flush stdout: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framed(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[tokio::test]
+    async fn test_malformed_body_keeps_stream_in_sync() {
+        let input = framed("{not json") + &framed(r#"{"id":1,"method":"initialize"}"#);
+        let mut reader = input.as_bytes();
+        match read_message(&mut reader).await.unwrap() {
+            Some(Incoming::Invalid { code, .. }) => assert_eq!(code, -32700),
+            _ => panic!("expected a parse error"),
+        }
+        match read_message(&mut reader).await.unwrap() {
+            Some(Incoming::Message(message)) => assert_eq!(message["method"], "initialize"),
+            _ => panic!("expected the next message"),
+        }
+        assert!(read_message(&mut reader).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_is_skipped() {
+        let body = " ".repeat(MAX_MESSAGE_BYTES + 1);
+        let input = framed(&body) + &framed(r#"{"id":2,"method":"shutdown"}"#);
+        let mut reader = input.as_bytes();
+        match read_message(&mut reader).await.unwrap() {
+            Some(Incoming::Invalid { code, .. }) => assert_eq!(code, -32600),
+            _ => panic!("expected the body to be rejected"),
+        }
+        match read_message(&mut reader).await.unwrap() {
+            Some(Incoming::Message(message)) => assert_eq!(message["method"], "shutdown"),
+            _ => panic!("expected the next message"),
+        }
+    }
+}
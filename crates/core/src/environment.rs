@@ -0,0 +1,38 @@
+//! Controlled loading of `.env` files and masked views of the environment for `/env`.
+
+use crate::ShellmindConfig;
+
+/// A variable set from `.env`, or one that was skipped because it is not allowed.
+#[derive(Debug, Clone)]
+pub struct DotenvVar {
+    pub name: String,
+    pub loaded: bool,
+}
+
+/// Loads `.env` from the current directory (or a parent) according to `load_dotenv` and
+/// `dotenv_allowlist`. Variables already set in the environment are never overridden.
+pub fn load_dotenv(config: &ShellmindConfig) -> Vec<DotenvVar> {
+    if !config.load_dotenv {
+        return Vec::new();
+    }
+    let Ok(entries) = dotenv::dotenv_iter() else {
+        return Vec::new();
+    };
+    let mut vars = Vec::new();
+    for (name, value) in entries.filter_map(Result::ok) {
+        let allowed = config.dotenv_allowlist.is_empty() || config.dotenv_allowlist.iter().any(|a| a == &name);
+        let loaded = allowed && std::env::var_os(&name).is_none();
+        if loaded {
+            std::env::set_var(&name, value);
+        }
+        vars.push(DotenvVar { name, loaded });
+    }
+    vars
+}
+
+/// Every variable an executed command inherits, sorted by name, with masked values.
+pub fn visible_to_commands() -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = std::env::vars().map(|(name, value)| (name, crate::secrets::mask_value(&value))).collect();
+    vars.sort();
+    vars
+}
@@ -0,0 +1,93 @@
+//! Prompt/config regression suites: each case checks a generated command against
+//! expected and forbidden patterns.
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+use crate::ShellmindError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalSuite {
+    pub cases: Vec<EvalCase>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub prompt: String,
+    /// Regex the generated command must match.
+    #[serde(default)]
+    pub expected: Option<String>,
+    /// Regex the generated command must not match.
+    #[serde(default)]
+    pub forbidden: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalOutcome {
+    pub name: String,
+    pub response: String,
+    pub passed: bool,
+    pub reason: Option<String>,
+}
+
+impl EvalSuite {
+    pub fn from_yaml(content: &str) -> Result<Self, ShellmindError> {
+        serde_yaml::from_str(content).map_err(|e| ShellmindError::Other(format!("Invalid eval suite: {}", e)))
+    }
+}
+
+impl EvalCase {
+    pub fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.prompt.clone())
+    }
+
+    /// Checks `response` against the case's expected and forbidden patterns.
+    pub fn check(&self, response: &str) -> Result<EvalOutcome, ShellmindError> {
+        let mut reason = None;
+        if let Some(expected) = &self.expected {
+            let regex = regex::Regex::new(expected)
+                .map_err(|e| ShellmindError::Other(format!("Invalid expected pattern '{}': {}", expected, e)))?;
+            if !regex.is_match(response) {
+                reason = Some(format!("does not match expected pattern /{}/", expected));
+            }
+        }
+        if let Some(forbidden) = &self.forbidden {
+            let regex = regex::Regex::new(forbidden)
+                .map_err(|e| ShellmindError::Other(format!("Invalid forbidden pattern '{}': {}", forbidden, e)))?;
+            if regex.is_match(response) {
+                reason = Some(format!("matches forbidden pattern /{}/", forbidden));
+            }
+        }
+        Ok(EvalOutcome {
+            name: self.display_name(),
+            response: response.to_string(),
+            passed: reason.is_none(),
+            reason,
+        })
+    }
+}
+
+/// Runs every case of `suite` through `generate`, which maps a prompt to the model's reply.
+/// Generation errors count as failures rather than aborting the run.
+pub async fn run_suite<F, Fut>(suite: &EvalSuite, generate: F) -> Result<Vec<EvalOutcome>, ShellmindError>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<String, ShellmindError>>,
+{
+    let mut outcomes = Vec::with_capacity(suite.cases.len());
+    for case in &suite.cases {
+        let outcome = match generate(case.prompt.clone()).await {
+            Ok(response) => case.check(response.trim())?,
+            Err(e) => EvalOutcome {
+                name: case.display_name(),
+                response: String::new(),
+                passed: false,
+                reason: Some(format!("generation failed: {}", e)),
+            },
+        };
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
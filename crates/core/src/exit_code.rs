@@ -0,0 +1,25 @@
+//! Exit codes of the `shellmind` binary, so wrapper scripts and shell keybindings can
+//! branch on the outcome of an invocation.
+
+use crate::ShellmindError;
+
+pub const SUCCESS: i32 = 0;
+/// Any failure without a more specific code, including invalid arguments.
+pub const FAILURE: i32 = 1;
+/// The configuration could not be loaded or is incomplete, e.g. no API key.
+pub const CONFIG_ERROR: i32 = 2;
+/// The model API could not be reached or refused the request.
+pub const API_ERROR: i32 = 3;
+/// A generated command or the request itself was blocked by a pattern, the system policy
+/// or the budget.
+pub const BLOCKED: i32 = 4;
+/// The executed command exited unsuccessfully.
+pub const COMMAND_FAILED: i32 = 5;
+/// The user rejected the command or cancelled the request.
+pub const REJECTED: i32 = 6;
+
+/// Exit code for a failed invocation. Errors other than `ShellmindError` are generic
+/// failures.
+pub fn for_error(error: &anyhow::Error) -> i32 {
+    error.downcast_ref::<ShellmindError>().map_or(FAILURE, ShellmindError::exit_code)
+}
@@ -0,0 +1,68 @@
+//! Feedback on generated commands (`/good`, `/bad <reason>`), kept in
+//! `~/.shellmind/feedback.jsonl` and optionally fed back as few-shot steering.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::retention::DataKind;
+use crate::{persist, ShellmindError};
+
+/// Oldest entries beyond this are dropped when the log is written.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackEntry {
+    pub timestamp: DateTime<Utc>,
+    /// Directory the command was generated in; steering only uses entries from the same project.
+    pub project: String,
+    pub prompt: String,
+    pub command: String,
+    pub good: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl FeedbackEntry {
+    pub fn new(prompt: &str, command: &str, good: bool, reason: Option<String>) -> Self {
+        FeedbackEntry {
+            timestamp: Utc::now(),
+            project: current_project(),
+            prompt: prompt.to_string(),
+            command: command.to_string(),
+            good,
+            reason,
+        }
+    }
+}
+
+fn current_project() -> String {
+    std::env::current_dir().map(|d| d.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+pub fn record(entry: FeedbackEntry) -> Result<(), ShellmindError> {
+    persist::append_jsonl(&DataKind::Feedback.path()?, entry, MAX_ENTRIES)
+}
+
+/// The newest `limit` entries recorded in the current directory, oldest first.
+pub fn recent_for_project(limit: usize) -> Vec<FeedbackEntry> {
+    let project = current_project();
+    let Ok(entries) = DataKind::Feedback.path().and_then(|path| persist::read_jsonl::<FeedbackEntry>(&path)) else {
+        return Vec::new();
+    };
+    let mut recent: Vec<FeedbackEntry> = entries.into_iter().rev().filter(|e| e.project == project).take(limit).collect();
+    recent.reverse();
+    recent
+}
+
+/// Few-shot note for the system prompt built from past feedback; empty without entries.
+pub fn steering_note(entries: &[FeedbackEntry]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    let mut note = String::from("Feedback the user gave on earlier answers in this project. Repeat what worked and avoid the mistakes:");
+    for entry in entries {
+        let verdict = if entry.good { "good".to_string() } else { format!("bad ({})", entry.reason.as_deref().unwrap_or("no reason given")) };
+        note.push_str(&format!("\n- Request: {}\n  Answer: {}\n  Verdict: {}", entry.prompt, entry.command, verdict));
+    }
+    note
+}
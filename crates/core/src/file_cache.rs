@@ -0,0 +1,108 @@
+//! Contents of recently read files, shared by the file tools so an agent that reads or
+//! searches the same files on every step doesn't go back to disk each time. Entries are
+//! keyed by path and checked against the file's modification time and size; writes made
+//! through the file tools drop them immediately. The least recently used files are
+//! evicted once `file_cache_mb` is exceeded.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Files larger than this are always read from disk.
+const MAX_CACHED_FILE_BYTES: usize = 4 * 1024 * 1024;
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(64 * 1024 * 1024);
+static CACHE: Mutex<Cache> = Mutex::new(Cache { entries: BTreeMap::new(), bytes: 0, clock: 0 });
+
+struct Entry {
+    modified: SystemTime,
+    contents: Arc<[u8]>,
+    last_used: u64,
+}
+
+struct Cache {
+    entries: BTreeMap<PathBuf, Entry>,
+    bytes: usize,
+    clock: u64,
+}
+
+impl Cache {
+    fn get(&mut self, path: &Path, modified: SystemTime, len: u64) -> Option<Arc<[u8]>> {
+        self.clock += 1;
+        let clock = self.clock;
+        let entry = self.entries.get_mut(path)?;
+        if entry.modified != modified || entry.contents.len() as u64 != len {
+            return None;
+        }
+        entry.last_used = clock;
+        Some(Arc::clone(&entry.contents))
+    }
+
+    fn insert(&mut self, path: PathBuf, modified: SystemTime, contents: Arc<[u8]>, capacity: usize) {
+        self.remove(&path);
+        self.bytes += contents.len();
+        self.entries.insert(path, Entry { modified, contents, last_used: self.clock });
+        while self.bytes > capacity {
+            let Some(oldest) = self.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(path, _)| path.clone()) else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.bytes -= entry.contents.len();
+        }
+    }
+}
+
+/// Sets the cache size from `file_cache_mb`; 0 disables caching.
+pub fn set_capacity_mb(megabytes: usize) {
+    CAPACITY.store(megabytes * 1024 * 1024, Ordering::Relaxed);
+    if megabytes == 0 {
+        clear();
+    }
+}
+
+fn key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Reads `path`, from the cache when the file hasn't changed since it was last read.
+pub async fn read(path: &Path) -> std::io::Result<Arc<[u8]>> {
+    let capacity = CAPACITY.load(Ordering::Relaxed);
+    let metadata = tokio::fs::metadata(path).await?;
+    let cacheable = capacity > 0 && metadata.len() as usize <= MAX_CACHED_FILE_BYTES;
+    let modified = match metadata.modified() {
+        Ok(modified) if cacheable => modified,
+        _ => return Ok(tokio::fs::read(path).await?.into()),
+    };
+    let key = key(path);
+    if let Some(contents) = CACHE.lock().unwrap().get(&key, modified, metadata.len()) {
+        return Ok(contents);
+    }
+    let contents: Arc<[u8]> = tokio::fs::read(path).await?.into();
+    CACHE.lock().unwrap().insert(key, modified, Arc::clone(&contents), capacity);
+    Ok(contents)
+}
+
+/// Like `tokio::fs::read_to_string`, through the cache.
+pub async fn read_to_string(path: &Path) -> std::io::Result<String> {
+    let contents = read(path).await?;
+    String::from_utf8(contents.to_vec()).map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))
+}
+
+/// Drops `path` after a write, so the next read sees the new contents even when the
+/// modification time didn't change.
+pub fn invalidate(path: &Path) {
+    CACHE.lock().unwrap().remove(&key(path));
+}
+
+pub fn clear() {
+    let mut cache = CACHE.lock().unwrap();
+    cache.entries.clear();
+    cache.bytes = 0;
+}
@@ -0,0 +1,366 @@
+//! Fleet mode (`/fleet`): a remediation for many hosts, such as "restart nginx on
+//! web01-web05", becomes one plan of hosts and steps. Once the plan is approved the steps
+//! run on every host over SSH, a bounded number of hosts at a time, and each host's
+//! result is reported as it finishes.
+
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use crate::{ShellmindConfig, ShellmindError};
+
+/// Options every SSH connection gets: never prompt, and give up on unreachable hosts.
+const SSH_OPTIONS: &[&str] = &["-o", "BatchMode=yes", "-o", "ConnectTimeout=10", "-o", "ServerAliveInterval=15"];
+
+/// Inventory tried when `fleet_inventory` is empty and `ANSIBLE_INVENTORY` isn't set.
+/// Files in the current directory are not picked up: a checked-out repository could
+/// otherwise choose the hosts.
+const DEFAULT_INVENTORY: &str = "/etc/ansible/hosts";
+
+/// Longest a step may run on one host before it is killed.
+const STEP_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// What the model returns for a fleet task, before host patterns are expanded.
+#[derive(Debug, Clone, Deserialize)]
+struct Reply {
+    #[serde(default)]
+    summary: String,
+    hosts: Vec<String>,
+    steps: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FleetPlan {
+    pub summary: String,
+    pub hosts: Vec<String>,
+    /// Commands run on each host in order; a failing step ends that host's run.
+    pub steps: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HostResult {
+    pub host: String,
+    /// Index of the step that failed, if one did.
+    pub failed_step: Option<usize>,
+    pub exit_code: Option<i32>,
+    /// Combined output of the steps that ran.
+    pub output: String,
+    pub duration: Duration,
+}
+
+impl HostResult {
+    pub fn success(&self) -> bool {
+        self.failed_step.is_none()
+    }
+}
+
+pub fn build_fleet_prompt(task: &str) -> String {
+    format!(
+        "Turn the following task into a plan to run on a fleet of hosts over SSH. Reply with JSON only, in the form \
+         {{\"summary\": \"...\", \"hosts\": [\"web01-web05\"], \"steps\": [\"sudo systemctl restart nginx\"]}}. \
+         `hosts` holds the hosts exactly as the task names them: names, ranges like web01-web05, or Ansible inventory \
+         groups written as @group. `steps` are shell commands run on every host in order; each must be non-interactive \
+         and should check its own result where that is cheap (e.g. `systemctl is-active nginx` after a restart).\n\nTask: {}",
+        task
+    )
+}
+
+/// Expands `web01-web05`, `web[01:05]` and `web{01..05}` into the hosts they name,
+/// keeping the zero padding. Anything else is a single host.
+fn expand_range(pattern: &str) -> Vec<String> {
+    let numbered = |prefix: &str, from: &str, to: &str, suffix: &str| -> Option<Vec<String>> {
+        let (start, end) = (from.parse::<u64>().ok()?, to.parse::<u64>().ok()?);
+        if start > end || end - start > 1000 {
+            return None;
+        }
+        let width = if from.starts_with('0') { from.len() } else { 0 };
+        Some((start..=end).map(|n| format!("{}{:0width$}{}", prefix, n, suffix, width = width)).collect())
+    };
+    for (open, separator, close) in [('[', ":", ']'), ('{', "..", '}')] {
+        if let (Some(start), Some(end)) = (pattern.find(open), pattern.find(close)) {
+            if let Some((from, to)) = pattern.get(start + 1..end).and_then(|inner| inner.split_once(separator)) {
+                if let Some(hosts) = numbered(&pattern[..start], from, to, &pattern[end + 1..]) {
+                    return hosts;
+                }
+            }
+        }
+    }
+    // `web01-web05`: both ends share the prefix before their numbers; hostnames may
+    // contain dashes themselves, so every dash is tried
+    for (index, _) in pattern.match_indices('-') {
+        let (left, right) = (&pattern[..index], &pattern[index + 1..]);
+        let digits = |s: &str| s.len() - s.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        let (left_digits, right_digits) = (digits(left), digits(right));
+        if left_digits == 0 || right_digits == 0 {
+            continue;
+        }
+        let (prefix, from) = left.split_at(left.len() - left_digits);
+        let (other, to) = right.split_at(right.len() - right_digits);
+        if prefix == other {
+            if let Some(hosts) = numbered(prefix, from, to, "") {
+                return hosts;
+            }
+        }
+    }
+    vec![pattern.to_string()]
+}
+
+fn inventory_path(config: &ShellmindConfig) -> Option<PathBuf> {
+    if !config.fleet_inventory.is_empty() {
+        return Some(PathBuf::from(&config.fleet_inventory));
+    }
+    if let Ok(path) = std::env::var("ANSIBLE_INVENTORY") {
+        return Some(PathBuf::from(path));
+    }
+    Some(PathBuf::from(DEFAULT_INVENTORY)).filter(|path| path.is_file())
+}
+
+/// Rejects hosts ssh could read as an option (`-oProxyCommand=…`) or that hold anything
+/// but letters, digits and `._@:-`.
+fn validate_host(host: &str) -> Result<(), ShellmindError> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "._@:-".contains(c);
+    if host.starts_with('-') || !host.chars().all(allowed) {
+        return Err(ShellmindError::Other(format!("Invalid host '{}' in the fleet plan", host)));
+    }
+    Ok(())
+}
+
+/// Hosts of `group` in an INI Ansible inventory, following `[group:children]`.
+fn inventory_group(content: &str, group: &str, depth: usize) -> Vec<String> {
+    let mut hosts = Vec::new();
+    let mut section = String::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.to_string();
+            continue;
+        }
+        let Some(entry) = line.split_whitespace().next() else {
+            continue;
+        };
+        if section == group || (group == "all" && !section.contains(':')) {
+            hosts.extend(expand_range(entry));
+        } else if section == format!("{}:children", group) && depth < 8 {
+            hosts.extend(inventory_group(content, entry, depth + 1));
+        }
+    }
+    hosts
+}
+
+fn resolve_hosts(config: &ShellmindConfig, patterns: &[String]) -> Result<Vec<String>, ShellmindError> {
+    let mut hosts: Vec<String> = Vec::new();
+    for pattern in patterns.iter().flat_map(|p| p.split([',', ' '])).map(str::trim).filter(|p| !p.is_empty()) {
+        let expanded = match pattern.strip_prefix('@') {
+            Some(group) => {
+                let path = inventory_path(config).ok_or_else(|| ShellmindError::Other(format!("No Ansible inventory found for group '{}'; set fleet_inventory", group)))?;
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| ShellmindError::Other(format!("Failed to read inventory '{}': {}", path.display(), e)))?;
+                let members = inventory_group(&content, group, 0);
+                if members.is_empty() {
+                    return Err(ShellmindError::Other(format!("Group '{}' has no hosts in {}", group, path.display())));
+                }
+                members
+            }
+            None => expand_range(pattern),
+        };
+        for host in expanded {
+            validate_host(&host)?;
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+    }
+    Ok(hosts)
+}
+
+/// Parses the model's reply, tolerating a surrounding Markdown code fence, and expands
+/// its host patterns.
+pub fn parse_plan(config: &ShellmindConfig, reply: &str) -> Result<FleetPlan, ShellmindError> {
+    let trimmed = reply.trim();
+    let json = match (trimmed.find('{'), trimmed.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => return Err(ShellmindError::Other("The model did not return a JSON fleet plan".to_string())),
+    };
+    let reply: Reply = serde_json::from_str(json)?;
+    let hosts = resolve_hosts(config, &reply.hosts)?;
+    let steps: Vec<String> = reply.steps.into_iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+    if hosts.is_empty() || steps.is_empty() {
+        return Err(ShellmindError::Other("The fleet plan names no hosts or no steps".to_string()));
+    }
+    Ok(FleetPlan { summary: reply.summary, hosts, steps })
+}
+
+/// Waits for `child`, killing it when `cancelled` is set or `STEP_TIMEOUT` passes.
+/// Returns its exit code and why it was stopped, if it was.
+fn wait_step(child: &mut Child, cancelled: &AtomicBool) -> (Option<i32>, Option<String>) {
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return (status.code(), None),
+            Ok(None) => {}
+            Err(e) => return (None, Some(format!("Failed to wait for ssh: {}", e))),
+        }
+        let reason = if cancelled.load(Ordering::Relaxed) {
+            Some("cancelled".to_string())
+        } else if started.elapsed() > STEP_TIMEOUT {
+            Some(format!("timed out after {}s", STEP_TIMEOUT.as_secs()))
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            let _ = child.kill();
+            let _ = child.wait();
+            return (None, Some(reason));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn run_step(host: &str, step: &str, cancelled: &AtomicBool) -> (Option<i32>, String) {
+    if crate::simulation::enabled() {
+        return (Some(0), crate::simulation::command_output(&format!("ssh {} {}", host, step)));
+    }
+    let spawned = Command::new("ssh")
+        .args(SSH_OPTIONS)
+        .arg("--")
+        .arg(host)
+        .arg(step)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => return (None, format!("Failed to run ssh: {}", e)),
+    };
+    // Pipes are drained on their own threads so a chatty step can't fill them and stall
+    let readers: Vec<_> = [child.stdout.take().map(|r| Box::new(r) as Box<dyn Read + Send>), child.stderr.take().map(|r| Box::new(r) as Box<dyn Read + Send>)]
+        .into_iter()
+        .flatten()
+        .map(|mut reader| {
+            std::thread::spawn(move || {
+                let mut bytes = Vec::new();
+                let _ = reader.read_to_end(&mut bytes);
+                String::from_utf8_lossy(&bytes).into_owned()
+            })
+        })
+        .collect();
+    let (exit_code, stopped) = wait_step(&mut child, cancelled);
+    let mut text: String = readers.into_iter().filter_map(|reader| reader.join().ok()).collect();
+    if let Some(reason) = stopped {
+        text.push_str(&format!("\n{}\n", reason));
+    }
+    (exit_code, text)
+}
+
+fn run_host(host: &str, steps: &[String], cancelled: &AtomicBool) -> HostResult {
+    let started = Instant::now();
+    let mut output = String::new();
+    for (index, step) in steps.iter().enumerate() {
+        let (exit_code, text) = run_step(host, step, cancelled);
+        output.push_str(&text);
+        if exit_code != Some(0) {
+            return HostResult { host: host.to_string(), failed_step: Some(index), exit_code, output, duration: started.elapsed() };
+        }
+    }
+    HostResult { host: host.to_string(), failed_step: None, exit_code: Some(0), output, duration: started.elapsed() }
+}
+
+/// A running fleet plan. Results arrive on `results` as hosts finish; the channel closes
+/// once every started host is done.
+pub struct FleetRun {
+    pub results: mpsc::UnboundedReceiver<HostResult>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl FleetRun {
+    /// Kills the running steps and starts no further hosts.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Runs the plan on at most `parallelism` hosts at a time.
+pub fn run(plan: &FleetPlan, parallelism: usize) -> FleetRun {
+    let (sender, results) = mpsc::unbounded_channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let hosts = Arc::new(plan.hosts.clone());
+    let steps = Arc::new(plan.steps.clone());
+    let next = Arc::new(AtomicUsize::new(0));
+    for _ in 0..parallelism.clamp(1, hosts.len().max(1)) {
+        let (hosts, steps, next, sender, cancelled) = (hosts.clone(), steps.clone(), next.clone(), sender.clone(), cancelled.clone());
+        std::thread::spawn(move || {
+            while !cancelled.load(Ordering::Relaxed) {
+                let Some(host) = hosts.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    break;
+                };
+                if sender.send(run_host(host, &steps, &cancelled)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    FleetRun { results, cancelled }
+}
+
+/// The hosts as written for the review; all of them, since the one approval covers each.
+pub fn host_list(hosts: &[String]) -> String {
+    hosts.join(", ")
+}
+
+/// Markdown report of a finished run, for the transcript.
+pub fn report(plan: &FleetPlan, results: &[HostResult]) -> String {
+    let succeeded = results.iter().filter(|r| r.success()).count();
+    let mut report = format!("Fleet run: {} of {} hosts succeeded.\n", succeeded, results.len());
+    for result in results.iter().filter(|r| !r.success()) {
+        let step = result.failed_step.and_then(|i| plan.steps.get(i)).map_or("", String::as_str);
+        let last = result.output.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("");
+        report.push_str(&format!("- {}: `{}` failed (exit {:?}) {}\n", result.host, step, result.exit_code, last));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_range_keeps_padding() {
+        assert_eq!(expand_range("web01-web03"), vec!["web01", "web02", "web03"]);
+        assert_eq!(expand_range("db[8:10].example.com"), vec!["db8.example.com", "db9.example.com", "db10.example.com"]);
+        assert_eq!(expand_range("node{001..002}"), vec!["node001", "node002"]);
+    }
+
+    #[test]
+    fn test_expand_range_leaves_other_names_alone() {
+        assert_eq!(expand_range("my-host-1"), vec!["my-host-1"]);
+        assert_eq!(expand_range("web05-web01"), vec!["web05-web01"]);
+        assert_eq!(expand_range("web1-db2"), vec!["web1-db2"]);
+    }
+
+    #[test]
+    fn test_inventory_group_follows_children() {
+        let inventory = "[web]\nweb[01:02] ansible_user=deploy\n\n[db]\ndb1\n# db2\n\n[prod:children]\nweb\ndb\n";
+        assert_eq!(inventory_group(inventory, "web", 0), vec!["web01", "web02"]);
+        assert_eq!(inventory_group(inventory, "prod", 0), vec!["web01", "web02", "db1"]);
+        assert_eq!(inventory_group(inventory, "all", 0), vec!["web01", "web02", "db1"]);
+        assert!(inventory_group(inventory, "missing", 0).is_empty());
+    }
+
+    #[test]
+    fn test_validate_host_rejects_options_and_shell_text() {
+        assert!(validate_host("web01.example.com").is_ok());
+        assert!(validate_host("deploy@10.0.0.1").is_ok());
+        assert!(validate_host("-oProxyCommand=sh").is_err());
+        assert!(validate_host("web01;reboot").is_err());
+        assert!(validate_host("web 01").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Typo suggestions for tool names and config keys, ranked by Jaro-Winkler similarity.
+
+/// Names at least this similar are offered as suggestions.
+const SUGGEST_THRESHOLD: f64 = 0.8;
+/// A name this similar, and clearly closer than any other, may be used in place of an
+/// unknown one.
+const AUTO_CORRECT_THRESHOLD: f64 = 0.93;
+/// How much closer the best match must be than the runner-up to be auto-corrected.
+const AUTO_CORRECT_MARGIN: f64 = 0.03;
+/// Most suggestions listed in a message.
+const MAX_SUGGESTIONS: usize = 3;
+
+fn jaro(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        if let Some(j) = (start..end).find(|&j| !b_matched[j] && b[j] == *ca) {
+            b_matched[j] = true;
+            a_matches.push(*ca);
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+    let b_matches = b.iter().zip(&b_matched).filter(|(_, matched)| **matched).map(|(c, _)| *c);
+    let transpositions = a_matches.iter().zip(b_matches).filter(|(x, y)| **x != *y).count() / 2;
+    let m = a_matches.len() as f64;
+    (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0
+}
+
+/// Jaro-Winkler similarity between 0 (nothing alike) and 1 (equal), ignoring case.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let score = jaro(&a, &b);
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+    score + prefix as f64 * 0.1 * (1.0 - score)
+}
+
+/// The candidates most similar to `name`, best first.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut scored: Vec<(f64, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (similarity(name, candidate), candidate))
+        .filter(|(score, _)| *score >= SUGGEST_THRESHOLD)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored.into_iter().take(MAX_SUGGESTIONS).map(|(_, candidate)| candidate).collect()
+}
+
+/// The candidate `name` was almost certainly meant to be, if there is one.
+pub fn auto_correct<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let mut scored: Vec<(f64, &str)> = candidates.into_iter().map(|candidate| (similarity(name, candidate), candidate)).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let (best, candidate) = *scored.first()?;
+    let runner_up = scored.get(1).map_or(0.0, |(score, _)| *score);
+    (best >= AUTO_CORRECT_THRESHOLD && best - runner_up >= AUTO_CORRECT_MARGIN).then_some(candidate)
+}
+
+/// ", did you mean `a` or `b`?" for appending to an error, or nothing without suggestions.
+pub fn did_you_mean(suggestions: &[&str]) -> String {
+    let quoted: Vec<String> = suggestions.iter().map(|s| format!("`{}`", s)).collect();
+    match quoted.split_last() {
+        None => String::new(),
+        Some((last, [])) => format!(", did you mean {}?", last),
+        Some((last, rest)) => format!(", did you mean {} or {}?", rest.join(", "), last),
+    }
+}
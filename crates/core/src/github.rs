@@ -0,0 +1,402 @@
+//! GitHub tools over the REST API: list and open issues, read pull request diffs and
+//! comment on issues and pull requests. The token is read from secure storage
+//! (`github_token_secret`); the repository defaults to the `origin` remote of the working
+//! directory. Writes always need confirmation and show exactly what will be posted.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::Command;
+use tokio::signal::unix::Signal;
+
+use crate::progress::ProgressSender;
+use crate::{secrets, ConfirmationDetails, ShellmindConfig, ShellmindError, ToolResult, TypedTool};
+
+const DEFAULT_LIMIT: u32 = 20;
+
+/// Where requests go and which secret authenticates them; shared by the GitHub tools.
+#[derive(Debug, Clone)]
+pub struct GitHubClient {
+    pub api_url: String,
+    pub token_secret: String,
+}
+
+impl GitHubClient {
+    pub fn from_config(config: &ShellmindConfig) -> Self {
+        GitHubClient {
+            api_url: config.github_api_url.trim_end_matches('/').to_string(),
+            token_secret: config.github_token_secret.clone(),
+        }
+    }
+
+    /// A request to `path` accepting JSON; `diff` asks for a unified diff instead.
+    fn request(&self, method: reqwest::Method, path: &str, diff: bool) -> Result<reqwest::RequestBuilder, ShellmindError> {
+        let token = extensions::secure_storage::retrieve_secret(&self.token_secret).map_err(|e| {
+            ShellmindError::Other(format!("{}; store a token with `shellmind secret set {}`", e, self.token_secret))
+        })?;
+        Ok(crate::http_client()
+            .request(method, format!("{}{}", self.api_url, path))
+            .bearer_auth(token)
+            .header("User-Agent", "shellmind")
+            .header("Accept", if diff { "application/vnd.github.diff" } else { "application/vnd.github+json" })
+            .header("X-GitHub-Api-Version", "2022-11-28"))
+    }
+
+    /// Creates a gist holding one file and returns its URL.
+    pub async fn create_gist(&self, description: &str, filename: &str, content: &str, public: bool) -> Result<String, ShellmindError> {
+        let payload = serde_json::json!({
+            "description": description,
+            "public": public,
+            "files": { filename: { "content": content } },
+        });
+        let body = send(self.request(reqwest::Method::POST, "/gists", false)?.json(&payload))
+            .await?
+            .map_err(|e| ShellmindError::Other(e.display))?;
+        let gist: serde_json::Value = serde_json::from_str(&body)?;
+        gist["html_url"].as_str().map(str::to_string).ok_or_else(|| ShellmindError::Other("GitHub did not return the gist URL".to_string()))
+    }
+}
+
+/// `owner/name` from a remote URL such as `git@github.com:owner/name.git` or
+/// `https://github.com/owner/name`.
+pub fn repo_from_remote(url: &str) -> Option<String> {
+    let path = url.trim().trim_end_matches('/').trim_end_matches(".git");
+    let mut segments = path.rsplit(|c| c == '/' || c == ':');
+    let name = segments.next().filter(|s| !s.is_empty())?;
+    let owner = segments.next().filter(|s| !s.is_empty() && !s.contains('@'))?;
+    Some(format!("{}/{}", owner, name))
+}
+
+/// The repository of the `origin` remote of `dir`.
+pub fn default_repo(dir: &Path) -> Option<String> {
+    let output = Command::new("git").args(["remote", "get-url", "origin"]).current_dir(dir).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    repo_from_remote(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn resolve_repo(repo: &Option<String>) -> Result<String, ShellmindError> {
+    if let Some(repo) = repo {
+        return match repo.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => Ok(repo.clone()),
+            _ => Err(ShellmindError::Other(format!("Repository must be given as owner/name, not '{}'", repo))),
+        };
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    default_repo(&cwd).ok_or_else(|| {
+        ShellmindError::Other("No repository given and the working directory has no GitHub `origin` remote".to_string())
+    })
+}
+
+fn repo_label(repo: &Option<String>) -> String {
+    resolve_repo(repo).unwrap_or_else(|_| "?".to_string())
+}
+
+/// Sends `request` and returns the JSON or text body, or a tool error with GitHub's message.
+async fn send(request: reqwest::RequestBuilder) -> Result<Result<String, ToolResult>, ShellmindError> {
+    let response = request.send().await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if status.is_success() {
+        return Ok(Ok(text));
+    }
+    let message = serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|body| body.get("message").and_then(|m| m.as_str()).map(str::to_string))
+        .unwrap_or(text);
+    Ok(Err(ToolResult::error(format!("GitHub returned {}: {}", status, message))))
+}
+
+fn preview(body: &str) -> String {
+    const MAX_PREVIEW_LINES: usize = 20;
+    let lines: Vec<&str> = body.lines().collect();
+    if lines.len() <= MAX_PREVIEW_LINES {
+        return body.to_string();
+    }
+    format!("{}\n... ({} more lines)", lines[..MAX_PREVIEW_LINES].join("\n"), lines.len() - MAX_PREVIEW_LINES)
+}
+
+pub struct GitHubListIssuesTool {
+    pub client: GitHubClient,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListIssuesParams {
+    /// Repository as `owner/name`; defaults to the `origin` remote of the working directory.
+    pub repo: Option<String>,
+    /// `open` (default), `closed` or `all`.
+    pub state: Option<String>,
+    /// Comma-separated labels the issues must all have.
+    pub labels: Option<String>,
+    /// Maximum number of issues to return (default 20, at most 100).
+    pub limit: Option<u32>,
+}
+
+impl TypedTool for GitHubListIssuesTool {
+    type Params = ListIssuesParams;
+
+    fn name(&self) -> &'static str {
+        "github_list_issues"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GitHub Issues"
+    }
+
+    fn description(&self) -> &'static str {
+        "Lists issues and pull requests of a GitHub repository, newest first."
+    }
+
+    fn get_description(&self, params: &ListIssuesParams) -> String {
+        format!("List {} issues in {}", params.state.as_deref().unwrap_or("open"), repo_label(&params.repo))
+    }
+
+    fn should_confirm_execute(&self, _params: &ListIssuesParams) -> Option<ConfirmationDetails> {
+        None // Read-only
+    }
+
+    fn execute(&self, params: ListIssuesParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let repo = resolve_repo(&params.repo)?;
+            let state = params.state.unwrap_or_else(|| "open".to_string());
+            if !["open", "closed", "all"].contains(&state.as_str()) {
+                return Ok(ToolResult::error(format!("Unknown issue state '{}'; use open, closed or all", state)));
+            }
+            let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 100).to_string();
+            let mut query = vec![("state", state), ("per_page", limit)];
+            if let Some(labels) = params.labels {
+                query.push(("labels", labels));
+            }
+            let request = client.request(reqwest::Method::GET, &format!("/repos/{}/issues", repo), false)?.query(&query);
+            let text = match send(request).await? {
+                Ok(text) => text,
+                Err(result) => return Ok(result),
+            };
+            let issues: Vec<serde_json::Value> = serde_json::from_str(&text)?;
+            let rows: Vec<serde_json::Value> = issues
+                .iter()
+                .map(|issue| {
+                    let kind = if issue.get("pull_request").is_some() { "pr" } else { "issue" };
+                    let labels: Vec<&str> = issue["labels"]
+                        .as_array()
+                        .map(|labels| labels.iter().filter_map(|l| l["name"].as_str()).collect())
+                        .unwrap_or_default();
+                    serde_json::json!({
+                        "number": issue["number"],
+                        "kind": kind,
+                        "title": issue["title"],
+                        "state": issue["state"],
+                        "author": issue["user"]["login"],
+                        "labels": labels.join(", "),
+                        "updated": issue["updated_at"],
+                    })
+                })
+                .collect();
+            if rows.is_empty() {
+                return Ok(ToolResult::success(format!("No matching issues in {}", repo)));
+            }
+            let display = rows
+                .iter()
+                .map(|row| format!("#{} [{}] {}", row["number"], row["state"].as_str().unwrap_or(""), row["title"].as_str().unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(ToolResult::success(display).with_content(serde_json::Value::Array(rows)))
+        })
+    }
+}
+
+pub struct GitHubCreateIssueTool {
+    pub client: GitHubClient,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateIssueParams {
+    /// Repository as `owner/name`; defaults to the `origin` remote of the working directory.
+    pub repo: Option<String>,
+    pub title: String,
+    /// Markdown body, e.g. a description with the stack trace in a code block.
+    pub body: String,
+    /// Labels to add; they must already exist in the repository.
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+impl TypedTool for GitHubCreateIssueTool {
+    type Params = CreateIssueParams;
+
+    fn name(&self) -> &'static str {
+        "github_create_issue"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GitHub New Issue"
+    }
+
+    fn description(&self) -> &'static str {
+        "Opens an issue in a GitHub repository. Secrets in the title and body are masked before posting."
+    }
+
+    fn get_description(&self, params: &CreateIssueParams) -> String {
+        format!("Open an issue in {}: {}", repo_label(&params.repo), secrets::mask(&params.title))
+    }
+
+    fn should_confirm_execute(&self, params: &CreateIssueParams) -> Option<ConfirmationDetails> {
+        let mut message = format!("This will open an issue in {}:\n\n{}\n\n{}", repo_label(&params.repo), secrets::mask(&params.title), preview(&secrets::mask(&params.body)));
+        if !params.labels.is_empty() {
+            message.push_str(&format!("\n\nLabels: {}", params.labels.join(", ")));
+        }
+        message.push_str("\n\nAre you sure?");
+        Some(ConfirmationDetails { message })
+    }
+
+    fn execute(&self, params: CreateIssueParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let repo = resolve_repo(&params.repo)?;
+            let payload = serde_json::json!({
+                "title": secrets::mask(&params.title),
+                "body": secrets::mask(&params.body),
+                "labels": params.labels,
+            });
+            let request = client.request(reqwest::Method::POST, &format!("/repos/{}/issues", repo), false)?.json(&payload);
+            let text = match send(request).await? {
+                Ok(text) => text,
+                Err(result) => return Ok(result),
+            };
+            let issue: serde_json::Value = serde_json::from_str(&text)?;
+            let display = format!("Opened issue #{} in {}: {}", issue["number"], repo, issue["html_url"].as_str().unwrap_or(""));
+            Ok(ToolResult::success(display).with_content(serde_json::json!({ "number": issue["number"], "url": issue["html_url"] })))
+        })
+    }
+}
+
+pub struct GitHubPrDiffTool {
+    pub client: GitHubClient,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PrDiffParams {
+    /// Repository as `owner/name`; defaults to the `origin` remote of the working directory.
+    pub repo: Option<String>,
+    /// Pull request number.
+    pub number: u64,
+}
+
+impl TypedTool for GitHubPrDiffTool {
+    type Params = PrDiffParams;
+
+    fn name(&self) -> &'static str {
+        "github_pr_diff"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GitHub PR Diff"
+    }
+
+    fn description(&self) -> &'static str {
+        "Returns the title, description and unified diff of a GitHub pull request."
+    }
+
+    fn get_description(&self, params: &PrDiffParams) -> String {
+        format!("Read the diff of {}#{}", repo_label(&params.repo), params.number)
+    }
+
+    fn should_confirm_execute(&self, _params: &PrDiffParams) -> Option<ConfirmationDetails> {
+        None // Read-only
+    }
+
+    fn execute(&self, params: PrDiffParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let repo = resolve_repo(&params.repo)?;
+            let path = format!("/repos/{}/pulls/{}", repo, params.number);
+            let text = match send(client.request(reqwest::Method::GET, &path, false)?).await? {
+                Ok(text) => text,
+                Err(result) => return Ok(result),
+            };
+            let pull: serde_json::Value = serde_json::from_str(&text)?;
+            let request = client.request(reqwest::Method::GET, &path, true)?;
+            let diff = match send(request).await? {
+                Ok(diff) => diff,
+                Err(result) => return Ok(result),
+            };
+            let display = format!(
+                "{}#{}: {} ({} → {}, {})\n\n{}\n\n{}",
+                repo,
+                params.number,
+                pull["title"].as_str().unwrap_or(""),
+                pull["head"]["ref"].as_str().unwrap_or(""),
+                pull["base"]["ref"].as_str().unwrap_or(""),
+                pull["state"].as_str().unwrap_or(""),
+                pull["body"].as_str().unwrap_or("").trim(),
+                diff
+            );
+            Ok(ToolResult::success(display))
+        })
+    }
+}
+
+pub struct GitHubCommentTool {
+    pub client: GitHubClient,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CommentParams {
+    /// Repository as `owner/name`; defaults to the `origin` remote of the working directory.
+    pub repo: Option<String>,
+    /// Issue or pull request number.
+    pub number: u64,
+    /// Markdown comment.
+    pub body: String,
+}
+
+impl TypedTool for GitHubCommentTool {
+    type Params = CommentParams;
+
+    fn name(&self) -> &'static str {
+        "github_comment"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GitHub Comment"
+    }
+
+    fn description(&self) -> &'static str {
+        "Comments on a GitHub issue or pull request. Secrets in the comment are masked before posting."
+    }
+
+    fn get_description(&self, params: &CommentParams) -> String {
+        format!("Comment on {}#{}", repo_label(&params.repo), params.number)
+    }
+
+    fn should_confirm_execute(&self, params: &CommentParams) -> Option<ConfirmationDetails> {
+        Some(ConfirmationDetails {
+            message: format!(
+                "This will comment on {}#{}:\n\n{}\n\nAre you sure?",
+                repo_label(&params.repo),
+                params.number,
+                preview(&secrets::mask(&params.body))
+            ),
+        })
+    }
+
+    fn execute(&self, params: CommentParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let repo = resolve_repo(&params.repo)?;
+            let payload = serde_json::json!({ "body": secrets::mask(&params.body) });
+            let path = format!("/repos/{}/issues/{}/comments", repo, params.number);
+            let text = match send(client.request(reqwest::Method::POST, &path, false)?.json(&payload)).await? {
+                Ok(text) => text,
+                Err(result) => return Ok(result),
+            };
+            let comment: serde_json::Value = serde_json::from_str(&text)?;
+            let display = format!("Commented on {}#{}: {}", repo, params.number, comment["html_url"].as_str().unwrap_or(""));
+            Ok(ToolResult::success(display).with_content(serde_json::json!({ "url": comment["html_url"] })))
+        })
+    }
+}
@@ -0,0 +1,245 @@
+//! Trigram index of the files under the search roots, consulted by `search_file_content`
+//! (with `grep_index`) so repeated searches only read files that can match. An index is
+//! built on the first search of a set of roots and kept for the process. Before a search
+//! it is brought up to date by re-reading files whose modification time or size changed;
+//! that check is skipped for `REFRESH_AFTER` unless files were written or a command ran
+//! since, see `invalidate`.
+//!
+//! Trigrams are taken from the ASCII-lowercased content, and a regex is reduced to the
+//! trigrams of the literal text every match must contain. Patterns with alternation or
+//! unusual flags are not reduced and search every file.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::ShellmindError;
+
+/// Beyond this many files the index is not built and searches read every file.
+const MAX_INDEXED_FILES: usize = 100_000;
+/// Larger files are not indexed; they are searched on every query.
+const MAX_INDEXED_FILE_BYTES: u64 = 1024 * 1024;
+/// How long an index is trusted without re-checking the files, absent writes.
+const REFRESH_AFTER: Duration = Duration::from_secs(2);
+
+/// Bumped whenever files may have changed; indexes built at an older generation refresh.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static INDEXES: Mutex<BTreeMap<Vec<PathBuf>, GrepIndex>> = Mutex::new(BTreeMap::new());
+
+struct IndexedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    /// Sorted trigram codes, or `None` for files that are searched unconditionally.
+    trigrams: Option<Vec<u32>>,
+}
+
+#[derive(Default)]
+struct GrepIndex {
+    /// In walk order, so results come back in the order a plain search finds them.
+    files: Vec<PathBuf>,
+    indexed: HashMap<PathBuf, IndexedFile>,
+    generation: u64,
+    refreshed: Option<Instant>,
+}
+
+fn trigram(window: &[u8]) -> u32 {
+    (window[0].to_ascii_lowercase() as u32) << 16 | (window[1].to_ascii_lowercase() as u32) << 8 | window[2].to_ascii_lowercase() as u32
+}
+
+fn trigrams_of(bytes: &[u8]) -> Vec<u32> {
+    let mut trigrams: Vec<u32> = bytes.windows(3).map(trigram).collect();
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    trigrams
+}
+
+fn index_file(path: &Path, modified: Option<SystemTime>, len: u64) -> IndexedFile {
+    let trigrams = if len <= MAX_INDEXED_FILE_BYTES { std::fs::read(path).ok().map(|bytes| trigrams_of(&bytes)) } else { None };
+    IndexedFile { modified, len, trigrams }
+}
+
+impl GrepIndex {
+    /// Re-walks the roots, indexing new and changed files and forgetting removed ones.
+    /// False when there are too many files to index.
+    fn refresh(&mut self, roots: &[PathBuf]) -> Result<bool, ShellmindError> {
+        let walker = crate::workspace::Workspace::walker(roots).ok_or_else(|| ShellmindError::Other("No directory to search".to_string()))?;
+        let mut files = Vec::new();
+        let mut indexed = HashMap::new();
+        for entry in walker {
+            let entry = entry.map_err(|e| ShellmindError::Other(format!("Error walking directory: {}", e)))?;
+            if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+                continue;
+            }
+            if files.len() == MAX_INDEXED_FILES {
+                self.indexed.clear();
+                self.files.clear();
+                return Ok(false);
+            }
+            let path = entry.into_path();
+            let metadata = std::fs::metadata(&path).ok();
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            let len = metadata.as_ref().map_or(0, |m| m.len());
+            let file = match self.indexed.remove(&path) {
+                Some(file) if file.modified == modified && file.len == len => file,
+                _ => index_file(&path, modified, len),
+            };
+            indexed.insert(path.clone(), file);
+            files.push(path);
+        }
+        self.files = files;
+        self.indexed = indexed;
+        Ok(true)
+    }
+
+    fn candidates(&self, required: &[u32]) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|path| match self.indexed.get(*path).and_then(|file| file.trigrams.as_ref()) {
+                Some(trigrams) => required.iter().all(|t| trigrams.binary_search(t).is_ok()),
+                None => true,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Ends the current literal run, keeping it unless it is inside a group.
+fn flush(literals: &mut Vec<String>, current: &mut String, in_group: bool) {
+    let literal = std::mem::take(current);
+    if !in_group {
+        literals.push(literal);
+    }
+}
+
+/// Literal runs every match of `pattern` contains, or `None` when the pattern can't be
+/// reduced safely.
+fn required_literals(pattern: &str) -> Option<Vec<String>> {
+    let (pattern, case_insensitive) = match pattern.strip_prefix("(?i)") {
+        Some(rest) => (rest, true),
+        None => (pattern, false),
+    };
+    if pattern.contains("(?") {
+        return None;
+    }
+    let mut literals = Vec::new();
+    let mut current = String::new();
+    let mut groups = 0usize;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '|' => return None,
+            '\\' => match chars.next() {
+                // `\<` and `\>` are word boundaries, not literal brackets
+                Some(escaped) if escaped.is_ascii_punctuation() && escaped != '<' && escaped != '>' => current.push(escaped),
+                Some(escaped) => {
+                    flush(&mut literals, &mut current, groups > 0);
+                    // Skip the operand of `\x41`, `\u{e7}`, `\pL` and the like
+                    let operand = match escaped {
+                        'x' => 2,
+                        'u' => 4,
+                        'U' => 8,
+                        'p' | 'P' => 1,
+                        _ => 0,
+                    };
+                    if operand > 0 && chars.peek() == Some(&'{') {
+                        for c in chars.by_ref() {
+                            if c == '}' {
+                                break;
+                            }
+                        }
+                    } else {
+                        for _ in 0..operand {
+                            chars.next();
+                        }
+                    }
+                }
+                None => {}
+            },
+            '[' => {
+                flush(&mut literals, &mut current, groups > 0);
+                // Skip the class, including a leading `]` or `^]`, escaped characters and
+                // nested classes such as `[[:alpha:]]`
+                let mut depth = 1;
+                let mut first = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '^' if first => continue,
+                        ']' if first => {}
+                        '[' => depth += 1,
+                        ']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    first = false;
+                }
+            }
+            // The preceding character may be absent from a match
+            '*' | '?' | '{' => {
+                current.pop();
+                flush(&mut literals, &mut current, groups > 0);
+                if c == '{' {
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                }
+            }
+            // Text inside a group may be optional or repeated zero times, so it's not required
+            '(' => {
+                flush(&mut literals, &mut current, groups > 0);
+                groups += 1;
+            }
+            ')' => {
+                flush(&mut literals, &mut current, groups > 0);
+                groups = groups.saturating_sub(1);
+            }
+            '.' | '^' | '$' | '+' => flush(&mut literals, &mut current, groups > 0),
+            _ => current.push(c),
+        }
+    }
+    flush(&mut literals, &mut current, groups > 0);
+    if case_insensitive {
+        // Only ASCII folds the same way in the index and in the regex, and `k` and `s` also
+        // match the Kelvin sign and long s
+        let folds_elsewhere = |c: char| !c.is_ascii() || matches!(c.to_ascii_lowercase(), 'k' | 's');
+        literals = literals.into_iter().flat_map(|literal| literal.split(folds_elsewhere).map(str::to_string).collect::<Vec<_>>()).collect();
+    }
+    Some(literals.into_iter().filter(|literal| literal.len() >= 3).collect())
+}
+
+/// Records that files may have changed, so the next search re-checks them.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Files under `roots` that may contain a match for `pattern`, in walk order, or `None`
+/// when the roots have too many files to index.
+pub fn candidates(roots: &[PathBuf], pattern: &str) -> Result<Option<Vec<PathBuf>>, ShellmindError> {
+    let mut indexes = INDEXES.lock().unwrap();
+    let index = indexes.entry(roots.to_vec()).or_default();
+    let generation = GENERATION.load(Ordering::Relaxed);
+    let fresh = index.generation == generation && index.refreshed.map_or(false, |at| at.elapsed() < REFRESH_AFTER);
+    if !fresh {
+        if !index.refresh(roots)? {
+            return Ok(None);
+        }
+        index.generation = generation;
+        index.refreshed = Some(Instant::now());
+    }
+    let required: Vec<u32> = required_literals(pattern)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|literal| literal.as_bytes().windows(3).map(trigram).collect::<Vec<_>>())
+        .collect();
+    Ok(Some(index.candidates(&required)))
+}
@@ -0,0 +1,176 @@
+//! Local check that a generated command does what the prompt asked for. The operations a
+//! command performs (deleting files, killing processes, installing packages, ...) are
+//! compared with intent keywords in the prompt, in English and Turkish. An operation the
+//! prompt gives no reason for, such as `rm` for "list the log files", is a mismatch; the
+//! command is flagged and needs explicit confirmation. Prompts without any recognizable
+//! intent are not judged.
+
+use std::path::Path;
+
+use crate::{secrets, shell_parser, CommandEffect, SecurityManager};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    Delete,
+    Overwrite,
+    Move,
+    ChangePermissions,
+    StopProcess,
+    ManagePackages,
+    ManageServices,
+    Power,
+    FormatDisk,
+}
+
+impl Operation {
+    /// Word prefixes in a prompt that ask for this operation.
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Operation::Delete => &[
+                "delete", "remov", "rm", "erase", "clean", "clear", "purge", "wipe", "prune", "drop", "discard", "reset", "uninstall", "trash",
+                "free", "sil", "kaldır", "temizle", "boşalt", "arındır",
+            ],
+            Operation::Overwrite => &[
+                "write", "save", "overwrit", "replac", "edit", "modif", "chang", "updat", "set", "fix", "truncat", "empty", "reset", "yaz",
+                "kaydet", "değiştir", "düzenle", "güncelle", "düzelt", "boşalt",
+            ],
+            Operation::Move => &["move", "renam", "mv", "organi", "relocat", "taşı", "adlandır", "düzenle"],
+            Operation::ChangePermissions => &[
+                "permission", "chmod", "chown", "owner", "executabl", "access", "privileg", "izin", "sahip", "yetki", "çalıştırılabilir",
+            ],
+            Operation::StopProcess => &["kill", "stop", "terminat", "end", "quit", "clos", "restart", "durdur", "sonlandır", "öldür", "kapat"],
+            Operation::ManagePackages => &[
+                "install", "uninstall", "remov", "upgrad", "updat", "package", "kur", "yükle", "kaldır", "güncelle", "paket",
+            ],
+            Operation::ManageServices => &[
+                "servic", "daemon", "start", "stop", "restart", "enabl", "disabl", "reload", "servis", "başlat", "durdur", "etkinleştir", "devre",
+            ],
+            Operation::Power => &["reboot", "restart", "shutdown", "shut", "power", "halt", "yeniden", "kapat"],
+            Operation::FormatDisk => &["format", "partition", "wipe", "flash", "burn", "biçimlendir", "bölüm"],
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Operation::Delete => "deletes files",
+            Operation::Overwrite => "overwrites or edits existing files",
+            Operation::Move => "moves or renames files",
+            Operation::ChangePermissions => "changes permissions or ownership",
+            Operation::StopProcess => "stops processes",
+            Operation::ManagePackages => "installs or removes packages",
+            Operation::ManageServices => "starts, stops or reconfigures services",
+            Operation::Power => "reboots or shuts down the machine",
+            Operation::FormatDisk => "writes directly to a disk",
+        }
+    }
+}
+
+/// Word prefixes of requests that only look at things.
+const READ_KEYWORDS: &[&str] = &[
+    "list", "show", "display", "find", "search", "look", "count", "print", "check", "view", "read", "get", "what", "which", "where", "how",
+    "size", "status", "tell", "explain", "compare", "monitor", "watch", "listele", "göster", "bul", "ara", "say", "kontrol", "oku", "nedir",
+    "hangi", "nerede", "kaç", "görüntüle", "boyut", "durum", "izle", "karşılaştır",
+];
+
+/// An operation the command performs that the prompt didn't ask for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub operation: Operation,
+    /// The part of the command performing it, with secrets masked.
+    pub source: String,
+}
+
+impl Mismatch {
+    /// E.g. "deletes files (`rm -rf build`)".
+    pub fn explanation(&self) -> String {
+        format!("{} (`{}`)", self.operation.describe(), self.source)
+    }
+}
+
+fn words_of(prompt: &str) -> Vec<String> {
+    prompt.to_lowercase().split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_string).collect()
+}
+
+fn mentions(words: &[String], keywords: &[&str]) -> bool {
+    words.iter().any(|word| keywords.iter().any(|keyword| word.starts_with(keyword)))
+}
+
+/// The operations `stage` performs, judged from its program and arguments.
+fn stage_operations(name: &str, args: &[&str]) -> Vec<Operation> {
+    let subcommand = args.iter().find(|a| !a.starts_with('-')).copied().unwrap_or("");
+    let has = |flag: &str| args.contains(&flag);
+    match name {
+        "rm" | "rmdir" | "unlink" | "shred" | "srm" => vec![Operation::Delete],
+        "find" if has("-delete") || args.windows(2).any(|w| matches!(w, ["-exec" | "-execdir", "rm"])) => vec![Operation::Delete],
+        "git" if subcommand == "clean" || (subcommand == "reset" && has("--hard")) => vec![Operation::Delete],
+        "git" if subcommand == "push" && (has("-f") || has("--force") || has("--force-with-lease")) => vec![Operation::Overwrite],
+        "truncate" => vec![Operation::Overwrite],
+        "sed" | "perl" if args.iter().any(|a| a.starts_with("-i") || a.starts_with("--in-place")) => vec![Operation::Overwrite],
+        "mv" => vec![Operation::Move],
+        "chmod" | "chown" | "chgrp" | "setfacl" => vec![Operation::ChangePermissions],
+        "kill" | "pkill" | "killall" | "xkill" => vec![Operation::StopProcess],
+        "apt" | "apt-get" | "dnf" | "yum" | "zypper" | "brew" | "snap" | "pip" | "pip3" | "npm" | "cargo"
+            if matches!(subcommand, "install" | "remove" | "uninstall" | "purge" | "autoremove" | "erase" | "upgrade") =>
+        {
+            vec![Operation::ManagePackages]
+        }
+        "pacman" if args.iter().any(|a| a.starts_with("-S") || a.starts_with("-R") || a.starts_with("-U")) => vec![Operation::ManagePackages],
+        "systemctl" if matches!(subcommand, "reboot" | "poweroff" | "halt") => vec![Operation::Power],
+        "systemctl" | "service" | "launchctl"
+            if args.iter().any(|a| matches!(*a, "start" | "stop" | "restart" | "reload" | "enable" | "disable" | "mask" | "unload" | "load")) =>
+        {
+            vec![Operation::ManageServices]
+        }
+        "reboot" | "shutdown" | "poweroff" | "halt" => vec![Operation::Power],
+        _ if ["mkfs", "dd", "fdisk", "parted", "wipefs"].iter().any(|d| name == *d || name.starts_with(&format!("{}.", d))) => {
+            vec![Operation::FormatDisk]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The operations `command` performs that `prompt` doesn't ask for. Overwrites only count
+/// for files that exist, resolved against `cwd`.
+pub fn mismatches(prompt: &str, command: &str, cwd: &Path) -> Vec<Mismatch> {
+    let words = words_of(prompt);
+    let operations = [
+        Operation::Delete,
+        Operation::Overwrite,
+        Operation::Move,
+        Operation::ChangePermissions,
+        Operation::StopProcess,
+        Operation::ManagePackages,
+        Operation::ManageServices,
+        Operation::Power,
+        Operation::FormatDisk,
+    ];
+    let recognized = mentions(&words, READ_KEYWORDS) || operations.iter().any(|op| mentions(&words, op.keywords()));
+    if !recognized {
+        return Vec::new();
+    }
+
+    let mut performed: Vec<Mismatch> = Vec::new();
+    let mut add = |operation: Operation, source: String| {
+        if !performed.iter().any(|m| m.operation == operation) {
+            performed.push(Mismatch { operation, source: secrets::mask(&source) });
+        }
+    };
+    for stage in &shell_parser::parse(command).commands {
+        let words = stage.effective_words();
+        let Some(program) = words.first() else {
+            continue;
+        };
+        let name = program.value.rsplit('/').next().unwrap_or("");
+        let args: Vec<&str> = words[1..].iter().map(|w| w.value.as_str()).collect();
+        for operation in stage_operations(name, &args) {
+            add(operation, stage.normalized());
+        }
+    }
+    for effect in SecurityManager::command_effects(command, cwd) {
+        if let CommandEffect::Overwrites(path) = effect {
+            add(Operation::Overwrite, format!("> {}", path));
+        }
+    }
+    performed.retain(|m| !mentions(&words, m.operation.keywords()));
+    performed
+}
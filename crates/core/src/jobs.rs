@@ -0,0 +1,210 @@
+//! Background jobs: commands started with `/bg` that run while the session goes on, and
+//! the tool calls the agent runs in parallel. Each job keeps the tail of its output for
+//! `/jobs` and the pane view, which lets a build and its tests be watched side by side.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::ShellmindError;
+
+/// Older lines beyond this are dropped from a job's scrollback.
+pub const MAX_LINES: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobKind {
+    /// A shell command started with `/bg`.
+    Command,
+    /// A tool call from an agent stage.
+    AgentStep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobStatus {
+    Running,
+    /// The exit code, if the process wasn't killed by a signal.
+    Exited(Option<i32>),
+    /// Stopped with `/stop`.
+    Stopped,
+    /// An agent step that finished; `false` when the tool reported an error.
+    Done(bool),
+}
+
+impl JobStatus {
+    pub fn is_running(self) -> bool {
+        self == JobStatus::Running
+    }
+}
+
+struct State {
+    lines: VecDeque<String>,
+    /// Lines dropped from the front to stay within `MAX_LINES`.
+    dropped: usize,
+    status: JobStatus,
+    finished: Option<Instant>,
+    /// Set once the end of a `/bg` job has been announced.
+    reported: bool,
+}
+
+pub struct Job {
+    pub id: usize,
+    pub kind: JobKind,
+    pub title: String,
+    pub started: Instant,
+    pid: Option<u32>,
+    state: Mutex<State>,
+}
+
+impl Job {
+    fn new(id: usize, kind: JobKind, title: &str, pid: Option<u32>) -> Self {
+        Job {
+            id,
+            kind,
+            title: title.to_string(),
+            started: Instant::now(),
+            pid,
+            state: Mutex::new(State { lines: VecDeque::new(), dropped: 0, status: JobStatus::Running, finished: None, reported: false }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn push_line(&self, line: &str) {
+        let mut state = self.state();
+        state.lines.push_back(line.to_string());
+        if state.lines.len() > MAX_LINES {
+            state.lines.pop_front();
+            state.dropped += 1;
+        }
+    }
+
+    pub fn finish(&self, status: JobStatus) {
+        let mut state = self.state();
+        // `/stop` wins over the exit status the killed process reports afterwards
+        if state.status == JobStatus::Running {
+            state.status = status;
+            state.finished = Some(Instant::now());
+        }
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.state().status
+    }
+
+    /// How long the job ran, or has been running.
+    pub fn elapsed(&self) -> Duration {
+        self.state().finished.unwrap_or_else(Instant::now).duration_since(self.started)
+    }
+
+    /// The last `MAX_LINES` lines of output.
+    pub fn output(&self) -> Vec<String> {
+        self.state().lines.iter().cloned().collect()
+    }
+
+    /// Lines of output so far, including dropped ones.
+    pub fn line_count(&self) -> usize {
+        let state = self.state();
+        state.dropped + state.lines.len()
+    }
+}
+
+fn forward(job: Arc<Job>, stream: impl Read + Send + 'static) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            match line {
+                Ok(line) => job.push_line(&line),
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+/// The jobs of this session, numbered from 1.
+#[derive(Default)]
+pub struct Jobs {
+    jobs: Mutex<Vec<Arc<Job>>>,
+}
+
+impl Jobs {
+    fn add(&self, kind: JobKind, title: &str, pid: Option<u32>) -> Arc<Job> {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let job = Arc::new(Job::new(jobs.len() + 1, kind, title, pid));
+        jobs.push(job.clone());
+        job
+    }
+
+    /// Starts `command` (built by the caller for the platform's shell) with its output
+    /// captured line by line. `title` is the command as typed.
+    pub fn spawn(&self, title: &str, mut command: Command) -> Result<Arc<Job>, ShellmindError> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| ShellmindError::Other(format!("Failed to start '{}': {}", title, e)))?;
+        let job = self.add(JobKind::Command, title, Some(child.id()));
+        if let Some(stdout) = child.stdout.take() {
+            forward(job.clone(), stdout);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward(job.clone(), stderr);
+        }
+        let waiter = job.clone();
+        std::thread::spawn(move || {
+            let status = child.wait().ok().and_then(|s| s.code());
+            waiter.finish(JobStatus::Exited(status));
+        });
+        Ok(job)
+    }
+
+    /// Registers an agent tool call as it starts; the caller adds its progress and output
+    /// while it runs and finishes it.
+    pub fn track(&self, title: &str) -> Arc<Job> {
+        self.add(JobKind::AgentStep, title, None)
+    }
+
+    pub fn get(&self, id: usize) -> Option<Arc<Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner()).get(id.wrapping_sub(1)).cloned()
+    }
+
+    pub fn list(&self) -> Vec<Arc<Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// `/bg` jobs that ended since the last call, to announce between prompts.
+    pub fn take_finished(&self) -> Vec<Arc<Job>> {
+        self.list()
+            .into_iter()
+            .filter(|job| {
+                let mut state = job.state();
+                let newly = job.kind == JobKind::Command && !state.status.is_running() && !state.reported;
+                state.reported |= newly;
+                newly
+            })
+            .collect()
+    }
+
+    /// Sends SIGTERM to a running `/bg` job. Returns false if there was nothing to stop.
+    pub fn stop(&self, id: usize) -> bool {
+        let Some(job) = self.get(id) else {
+            return false;
+        };
+        let Some(pid) = job.pid.filter(|_| job.status().is_running()) else {
+            return false;
+        };
+        let stopped = Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .stderr(Stdio::null())
+            .status()
+            .map_or(false, |s| s.success());
+        if stopped {
+            job.finish(JobStatus::Stopped);
+            job.state().reported = true;
+        }
+        stopped
+    }
+}
@@ -5,21 +5,84 @@ use config as config_rs;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use thiserror::Error;
-use tonic::transport::Channel;
-use http::uri;
 
+pub mod agent;
+pub mod api_client;
+pub mod approval;
+#[cfg(feature = "image")]
+pub mod attachments;
+pub mod audit;
+pub mod batch;
+pub mod bench;
+pub mod budget;
+pub mod calc;
+pub mod changes;
+pub mod cloud;
+pub mod context;
+pub mod crypto;
+pub mod custom_tools;
+#[cfg(feature = "server")]
+pub mod daemon;
+pub mod docker;
+pub mod doctor;
+#[cfg(feature = "server")]
+pub mod editor;
+pub mod environment;
+pub mod eval;
+pub mod exit_code;
+pub mod feedback;
+pub mod file_cache;
+pub mod fleet;
+pub mod fuzzy;
+pub mod github;
+pub mod grep_index;
+pub mod intent;
+pub mod jobs;
+pub mod limits;
+pub mod offline_help;
+pub mod openapi;
+pub mod persist;
+pub mod plan;
+pub mod plot;
+pub mod policy;
+pub mod preview;
+pub mod processes;
+pub mod production;
+pub mod profile;
+pub mod project;
+pub mod progress;
+pub mod prompt_trials;
+pub mod rate_limit;
+pub mod recovery;
+pub mod remote_approval;
+pub mod redact;
+pub mod retention;
+pub mod review;
+pub mod risk;
+pub mod scratch;
+pub mod secrets;
+#[cfg(feature = "server")]
+pub mod service;
+pub mod session;
+pub mod share;
+pub mod shell_parser;
+pub mod simulation;
+pub mod shell_env;
+pub mod snapshot;
+pub mod sync;
+pub mod terraform;
+pub mod testing;
+pub mod tips;
+#[cfg(any(test, feature = "tool-harness"))]
+pub mod tool_harness;
+pub mod tool_source;
 pub mod tools;
+pub mod translate;
+pub mod transport;
+pub mod undo;
+pub mod workspace;
 
-pub mod google {
-    pub mod generativelanguage {
-        pub mod v1beta {
-            tonic::include_proto!("google.generativelanguage.v1beta");
-        }
-    }
-}
-
-use google::generativelanguage::v1beta::generative_service_client::GenerativeServiceClient;
-use google::generativelanguage::v1beta::{GenerateContentRequest, Content, Part, GenerationConfig};
+pub use gemini_client::{Candidate, GeminiContent, GeminiPart, GeminiRequest, GeminiResponse, InlineData};
 
 #[derive(Error, Debug)]
 pub enum ShellmindError {
@@ -29,16 +92,37 @@ pub enum ShellmindError {
     Api(#[from] reqwest::Error),
     #[error("JSON parsing error: {0}")]
     Json(#[from] serde_json::Error),
-    #[error("gRPC error: {0}")]
-    Grpc(#[from] tonic::Status),
-    #[error("gRPC transport error: {0}")]
-    GrpcTransport(#[from] tonic::transport::Error),
-    #[error("Invalid URI: {0}")]
-    InvalidUri(#[from] uri::InvalidUri),
+    #[error("{0}")]
+    Client(#[from] gemini_client::Error),
+    #[error("API request failed with status: {0}")]
+    ApiStatus(String),
+    #[error("Blocked: {0}")]
+    Blocked(String),
+    #[error("Command failed with exit code {0:?}")]
+    CommandFailed(Option<i32>),
+    #[error("Rejected by the user")]
+    Rejected,
+    #[error("Cancelled by the user")]
+    Cancelled,
     #[error("Other error: {0}")]
     Other(String),
 }
 
+impl ShellmindError {
+    /// Process exit code reported for this error; see `exit_code`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ShellmindError::Config(_) => exit_code::CONFIG_ERROR,
+            ShellmindError::Client(e) if e.is_configuration() => exit_code::CONFIG_ERROR,
+            ShellmindError::Api(_) | ShellmindError::ApiStatus(_) | ShellmindError::Client(_) => exit_code::API_ERROR,
+            ShellmindError::Blocked(_) => exit_code::BLOCKED,
+            ShellmindError::CommandFailed(_) => exit_code::COMMAND_FAILED,
+            ShellmindError::Rejected | ShellmindError::Cancelled => exit_code::REJECTED,
+            ShellmindError::Json(_) | ShellmindError::Other(_) => exit_code::FAILURE,
+        }
+    }
+}
+
 impl From<anyhow::Error> for ShellmindError {
     fn from(err: anyhow::Error) -> Self {
         ShellmindError::Other(err.to_string())
@@ -60,7 +144,247 @@ pub struct ShellmindConfig {
     pub api_type: ApiType,
     pub grpc_endpoint: String,
     pub system_prompt: String,
+    /// Named alternatives to `system_prompt` for A/B testing, e.g. `[system_prompts]`.
+    #[serde(default)]
+    pub system_prompts: std::collections::BTreeMap<String, String>,
+    /// How sessions pick a variant from `system_prompts`.
+    pub prompt_sampling: prompt_trials::PromptSampling,
     pub allowed_commands: Vec<String>,
+    /// Wildcard patterns of commands that are never executed.
+    pub blocked_commands: Vec<String>,
+    /// Flag commands that do something the prompt didn't ask for, e.g. delete when asked to list.
+    pub intent_check: bool,
+    pub max_fix_attempts: usize,
+    pub verify_after_execution: bool,
+    pub context_token_budget: usize,
+    pub summary_model_name: String,
+    /// Title new sessions with the summary model instead of the first request.
+    pub generate_session_titles: bool,
+    /// Load `.env` from the current directory at startup.
+    pub load_dotenv: bool,
+    /// Variables that may be loaded from `.env`; empty allows all.
+    #[serde(default)]
+    pub dotenv_allowlist: Vec<String>,
+    /// Tell the model about the shell aliases and functions captured by `shellmind init`
+    /// and expand them in executed commands.
+    pub use_shell_aliases: bool,
+    /// Start REPL sessions with a snapshot of the directory: top-level files, README and git status.
+    pub startup_context: bool,
+    /// Hash the workspace before and after each command and report the files it changed.
+    pub track_file_changes: bool,
+    /// Add recent `/good` and `/bad` feedback from the current project to the system prompt.
+    pub feedback_steering: bool,
+    /// Number of feedback entries used for steering.
+    pub feedback_examples: usize,
+    /// UI and answer language: `en`, `tr`, or `auto` to follow `LANG`.
+    pub language: String,
+    /// Read explanations aloud.
+    pub tts_enabled: bool,
+    /// Speech program; empty picks `say`, `espeak-ng`, `espeak` or `spd-say`.
+    pub tts_command: String,
+    /// Paging of long tool output: `builtin` (continue with `/more`), `external` ($PAGER) or `off`.
+    pub pager: String,
+    /// Parts of the status line shown above the prompt, in order: `model`, `profile`,
+    /// `approval`, `tokens`, `cwd`. Empty hides the status line.
+    pub status_line: Vec<String>,
+    /// REPL prompt with `{cwd}`, `{dir}`, `{branch}`, `{model}`, `{mode}` and `{time}` tokens
+    /// and color tokens such as `{green}` and `{reset}`.
+    pub prompt_format: String,
+    /// REPL line editing: `emacs` or `vi`.
+    pub edit_mode: String,
+    /// Tab completion: `circular` cycles through candidates, `list` lists them like bash.
+    pub completion_type: String,
+    /// REPL key bindings, e.g. `[key_bindings]` with `"ctrl-p" = "history-search-backward"`.
+    #[serde(default)]
+    pub key_bindings: std::collections::BTreeMap<String, String>,
+    /// Go through approvals and auditing but never run commands or tools that change
+    /// anything; see `simulation`. Only turned on for the process with `--simulate`, never
+    /// read from or saved to the config file.
+    #[serde(skip)]
+    pub simulate_execution: bool,
+    /// Require approval for everything and drop the writing file tools on machines that look
+    /// like production.
+    pub production_safe_mode: bool,
+    /// Hostname patterns (`*` matches anything) of production machines.
+    pub production_hostnames: Vec<String>,
+    /// Kube context patterns that count as production.
+    pub production_kube_contexts: Vec<String>,
+    /// Run a tool call with a misspelled name as the registered tool it almost certainly
+    /// means; otherwise the closest names are only suggested.
+    pub auto_correct_tool_names: bool,
+    /// Text of the startup banner; empty hides it.
+    pub banner_text: String,
+    /// FIGlet font file (`.flf`) for the banner; empty uses the standard font.
+    pub banner_font: String,
+    /// Show a tip at startup, favoring features not used lately.
+    pub startup_tips: bool,
+    /// Encrypt sessions and history at rest with a key kept in secure storage.
+    pub encrypt_storage: bool,
+    /// Command history entries to keep (0 is unlimited).
+    pub max_history_entries: usize,
+    /// Delete session transcripts older than this many days (0 keeps them forever).
+    pub max_session_age_days: u64,
+    /// Delete audit log files older than this many days (0 keeps them forever).
+    pub audit_log_retention_days: u64,
+    /// Record executed commands, modified files and granted approvals under `~/.shellmind/audit/`.
+    pub audit_log: bool,
+    /// Forward audit events to the local syslog.
+    pub audit_syslog: bool,
+    /// POST audit events to this URL, signed with the `audit_hmac_key` secret; empty disables.
+    pub audit_webhook_url: String,
+    /// Undo snapshots to keep, oldest removed first (0 is unlimited).
+    pub max_undo_snapshots: usize,
+    /// Memory for file contents cached by the file tools, in MiB; 0 disables the cache.
+    pub file_cache_mb: usize,
+    /// Keep a trigram index of the workspace for `search_file_content`.
+    pub grep_index: bool,
+    /// Tool steps the agent may take for one task.
+    pub agent_max_steps: usize,
+    /// Time one step's tool calls may take together.
+    pub agent_step_timeout_secs: u64,
+    /// Wall-clock limit for an agent run; 0 means none.
+    pub agent_max_duration_secs: u64,
+    /// Input and output tokens an agent run may use; 0 means no limit.
+    pub agent_token_budget: u64,
+    pub approval_mode: ApprovalMode,
+    /// Sandbox every command must run in; `none` runs commands directly.
+    pub sandbox_backend: String,
+    #[serde(default)]
+    pub disabled_tools: Vec<String>,
+    /// Additional repository roots searched by the file tools alongside the current directory.
+    #[serde(default)]
+    pub workspace_roots: Vec<String>,
+    #[serde(default)]
+    pub custom_tools: Vec<custom_tools::CustomToolDefinition>,
+    #[serde(default)]
+    pub openapi_tools: Vec<openapi::OpenApiSource>,
+    pub request_timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    /// TCP and HTTP/2 keepalive interval for pooled API connections.
+    pub keepalive_interval_secs: u64,
+    /// How often the interactive session's crash-recovery checkpoint is written.
+    pub autosave_interval_secs: u64,
+    /// Tokens per day and per month across all model calls (0 is unlimited).
+    pub daily_token_budget: u64,
+    pub monthly_token_budget: u64,
+    /// Estimated USD per day and per month (0 is unlimited).
+    pub daily_cost_budget: f64,
+    pub monthly_cost_budget: f64,
+    /// What happens once a budget is spent: warn, fall back to a cheaper model, or refuse.
+    pub budget_policy: budget::BudgetPolicy,
+    pub budget_fallback_model: String,
+    /// Git repository `shellmind config sync` keeps config and memory in; empty until set.
+    pub sync_remote: String,
+    /// REST endpoint of the GitHub tools; GitHub Enterprise uses `https://<host>/api/v3`.
+    pub github_api_url: String,
+    /// Secure storage key holding the token of the GitHub tools.
+    pub github_token_secret: String,
+    /// Docker or Podman API socket of the container tools; empty detects it.
+    pub docker_socket: String,
+    /// Hosts `/fleet` runs on at the same time.
+    pub fleet_parallelism: usize,
+    /// Ansible INI inventory for `@group` hosts in `/fleet`; empty tries `ANSIBLE_INVENTORY`,
+    /// `./inventory`, `./hosts` and `/etc/ansible/hosts`.
+    pub fleet_inventory: String,
+    /// Adds the accounts of logged-in `aws`, `gcloud` and `az` CLIs, partially masked, to the system prompt.
+    pub cloud_context: bool,
+    /// Offers the summarized context of the last session started in the same directory.
+    pub directory_context: bool,
+    /// Asks the summary model for a one-sentence explanation of risky commands to show
+    /// when confirming them; off uses the explanation from static analysis.
+    pub explain_risk: bool,
+    /// Paste service `session share --paste` posts transcripts to; empty disables it.
+    pub share_paste_url: String,
+    /// Secure storage key of the paste service token, sent when stored.
+    pub share_paste_token_secret: String,
+    /// Answers simple requests from the bundled command snippets without an API call.
+    pub local_first: bool,
+    /// Text put before every user prompt, e.g. "Answer only with POSIX-compliant commands".
+    pub prompt_prefix: String,
+    /// Text put after every user prompt.
+    pub prompt_suffix: String,
+    /// How images from tools are shown: `auto`, `kitty`, `iterm2`, `sixel` or `off`.
+    pub inline_images: String,
+    /// ntfy topic URL or `telegram:<chat id>` unattended dangerous approvals are forwarded
+    /// to; empty disables forwarding.
+    pub approval_webhook_url: String,
+    /// Secure storage key of the ntfy access token or Telegram bot token.
+    pub approval_webhook_token_secret: String,
+    /// Seconds a dangerous approval waits unattended before it is forwarded.
+    pub approval_webhook_after_secs: u64,
+    /// Shows REST replies as they stream in instead of waiting behind the spinner.
+    pub stream_responses: bool,
+    /// Per-model price overrides used for cost estimates, e.g. `[model_prices."gemini-1.5-pro"]`.
+    #[serde(default)]
+    pub model_prices: std::collections::HashMap<String, budget::ModelPrice>,
+    /// Client-side limits per provider, e.g. `[rate_limits.gemini]`.
+    #[serde(default)]
+    pub rate_limits: std::collections::HashMap<String, rate_limit::RateLimitConfig>,
+    /// Per-tool timeouts and resource caps, e.g. `[tools.limits.run_shell_command]`.
+    #[serde(default)]
+    pub tools: limits::ToolsConfig,
+    /// Agent watchdog thresholds, e.g. `[agent]` with `repeated_calls = 3`.
+    #[serde(default)]
+    pub agent: agent::WatchdogConfig,
+    /// System policy applied on load; never written to the user's config file.
+    #[serde(skip)]
+    pub policy: policy::Policy,
+    /// Set when production safe mode is on; see `production`.
+    #[serde(skip)]
+    pub production: Option<production::ProductionMode>,
+    /// The `system_prompts` variant this session is served, when sampling; see `prompt_trials`.
+    #[serde(skip)]
+    pub prompt_variant: Option<String>,
+}
+
+/// How proposed commands and tool calls are approved.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalMode {
+    /// Ask for shell commands and for tools that request confirmation.
+    Default,
+    /// Ask before every action, including tools that normally run without confirmation.
+    Always,
+    /// Never execute anything; only show what would be run.
+    Suggest,
+}
+
+impl Default for ApprovalMode {
+    fn default() -> Self {
+        ApprovalMode::Default
+    }
+}
+
+impl ShellmindConfig {
+    /// `language` with `auto` resolved from `LC_ALL`/`LANG`; returns `tr` or `en`.
+    pub fn resolved_language(&self) -> &'static str {
+        let code = if self.language.eq_ignore_ascii_case("auto") {
+            std::env::var("LC_ALL").or_else(|_| std::env::var("LANG")).unwrap_or_default()
+        } else {
+            self.language.clone()
+        };
+        if code.to_lowercase().starts_with("tr") {
+            "tr"
+        } else {
+            "en"
+        }
+    }
+
+    /// `prompt` between `prompt_prefix` and `prompt_suffix`, each on its own line. Applied
+    /// to what the user asked for, not to Shellmind's own summary or fix-up prompts.
+    pub fn wrap_prompt(&self, prompt: &str) -> String {
+        let mut wrapped = String::new();
+        if !self.prompt_prefix.trim().is_empty() {
+            wrapped.push_str(self.prompt_prefix.trim());
+            wrapped.push('\n');
+        }
+        wrapped.push_str(prompt);
+        if !self.prompt_suffix.trim().is_empty() {
+            wrapped.push('\n');
+            wrapped.push_str(self.prompt_suffix.trim());
+        }
+        wrapped
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,10 +402,11 @@ impl Default for ApiType {
 pub struct ConfigManager;
 
 impl ConfigManager {
-    pub fn load_configuration() -> Result<ShellmindConfig, ShellmindError> {
+    /// The built-in defaults every other source is layered over.
+    fn default_settings() -> Result<config_rs::builder::ConfigBuilder<config_rs::builder::DefaultState>, ShellmindError> {
         let api_key_from_env = std::env::var("GEMINI_API_KEY").unwrap_or_default();
 
-        let settings = config_rs::Config::builder()
+        Ok(config_rs::Config::builder()
             // Set default values
             .set_default("api_key", api_key_from_env)?
             .set_default("model_name", "gemini-1.5-flash")?
@@ -90,7 +415,92 @@ impl ConfigManager {
             .set_default("api_type", "Rest")?
             .set_default("grpc_endpoint", "https://generativelanguage.googleapis.com")?
             .set_default("system_prompt", "You are Shellmind, an advanced, proactive AI assistant integrated into a Linux terminal. Your primary goal is to understand user requests and directly assist by performing tasks, providing information, or generating and executing appropriate shell commands. You should act as an intelligent agent, anticipating user needs and offering complete solutions. If a task can be directly performed (e.g., file operations, simple data processing), do so. If a command is required, generate it and explain its purpose concisely. Always prioritize direct action and helpfulness over merely translating requests into commands. Maintain context from previous interactions. Be concise, efficient, and user-centric. You should also be able to understand and respond to commands in Turkish.")?
+            .set_default("prompt_sampling", "off")?
             .set_default("allowed_commands", Vec::<String>::new())?
+            .set_default("blocked_commands", vec!["mkfs*", "dd if=* of=/dev/*", ":(){ :|:& };:", "rm -rf /", "rm -rf /*", "rm -rf ~", "* > /dev/sd*"])?
+            .set_default("intent_check", true)?
+            .set_default("max_fix_attempts", 3)?
+            .set_default("verify_after_execution", false)?
+            .set_default("context_token_budget", 32000)?
+            .set_default("summary_model_name", "gemini-1.5-flash")?
+            .set_default("generate_session_titles", true)?
+            .set_default("load_dotenv", true)?
+            .set_default("use_shell_aliases", true)?
+            .set_default("startup_context", true)?
+            .set_default("track_file_changes", true)?
+            .set_default("feedback_steering", false)?
+            .set_default("feedback_examples", 5)?
+            .set_default("language", "auto")?
+            .set_default("tts_enabled", false)?
+            .set_default("tts_command", "")?
+            .set_default("pager", "builtin")?
+            .set_default("status_line", vec!["model", "profile", "approval", "tokens", "cwd"])?
+            .set_default("prompt_format", "{green}> ")?
+            .set_default("edit_mode", "emacs")?
+            .set_default("completion_type", "circular")?
+            .set_default("production_safe_mode", true)?
+            .set_default("production_hostnames", vec!["prod*", "*-prod", "*-prod-*", "*.prod.*"])?
+            .set_default("production_kube_contexts", vec!["*prod*"])?
+            .set_default("auto_correct_tool_names", false)?
+            .set_default("banner_text", "Shellmind")?
+            .set_default("banner_font", "")?
+            .set_default("startup_tips", true)?
+            .set_default("encrypt_storage", false)?
+            .set_default("max_history_entries", 10000)?
+            .set_default("max_session_age_days", 0)?
+            .set_default("audit_log_retention_days", 90)?
+            .set_default("audit_log", true)?
+            .set_default("audit_syslog", false)?
+            .set_default("audit_webhook_url", "")?
+            .set_default("max_undo_snapshots", 100)?
+            .set_default("file_cache_mb", 64)?
+            .set_default("grep_index", true)?
+            .set_default("agent_max_steps", 10)?
+            .set_default("agent_step_timeout_secs", 300)?
+            .set_default("agent_max_duration_secs", 1800)?
+            .set_default("agent_token_budget", 200000)?
+            .set_default("approval_mode", "default")?
+            .set_default("sandbox_backend", "none")?
+            .set_default("request_timeout_secs", 120)?
+            .set_default("connect_timeout_secs", 10)?
+            .set_default("keepalive_interval_secs", 30)?
+            .set_default("autosave_interval_secs", 15)?
+            .set_default("daily_token_budget", 0)?
+            .set_default("monthly_token_budget", 0)?
+            .set_default("daily_cost_budget", 0.0)?
+            .set_default("monthly_cost_budget", 0.0)?
+            .set_default("budget_policy", "warn")?
+            .set_default("budget_fallback_model", "gemini-1.5-flash")?
+            .set_default("sync_remote", "")?
+            .set_default("github_api_url", "https://api.github.com")?
+            .set_default("github_token_secret", "github_token")?
+            .set_default("docker_socket", "")?
+            .set_default("cloud_context", false)?
+            .set_default("fleet_parallelism", 5)?
+            .set_default("fleet_inventory", "")?
+            .set_default("directory_context", true)?
+            .set_default("explain_risk", true)?
+            .set_default("share_paste_url", "")?
+            .set_default("share_paste_token_secret", "paste_token")?
+            .set_default("local_first", false)?
+            .set_default("prompt_prefix", "")?
+            .set_default("prompt_suffix", "")?
+            .set_default("inline_images", "auto")?
+            .set_default("approval_webhook_url", "")?
+            .set_default("approval_webhook_token_secret", "approval_webhook_token")?
+            .set_default("approval_webhook_after_secs", 60)?
+            .set_default("stream_responses", true)?)
+    }
+
+    /// The built-in defaults alone, without the config file, `SHELLMIND_` variables or
+    /// policy; for tests that must not depend on the machine they run on.
+    pub fn defaults() -> Result<ShellmindConfig, ShellmindError> {
+        let settings = Self::default_settings()?.build().map_err(ShellmindError::Config)?;
+        settings.try_deserialize().map_err(ShellmindError::Config)
+    }
+
+    pub fn load_configuration() -> Result<ShellmindConfig, ShellmindError> {
+        let settings = Self::default_settings()?
             // Load config file if it exists
             .add_source(
                 config_rs::File::with_name(&format!(
@@ -103,10 +513,47 @@ impl ConfigManager {
             .add_source(config_rs::Environment::with_prefix("SHELLMIND").separator("_"))
             .build().map_err(ShellmindError::Config)?;
 
-        let config: ShellmindConfig = settings.try_deserialize().map_err(ShellmindError::Config)?;
+        let mut config: ShellmindConfig = settings.try_deserialize().map_err(ShellmindError::Config)?;
+        let mut policy = policy::Policy::load()?;
+        policy.enforce(&mut config);
+        config.policy = policy;
+        production::enforce(&mut config);
+        // Applies process-wide so every writer of private state picks it up
+        crypto::set_enabled(config.encrypt_storage);
+        // `--simulate` turns simulation on for the process before the config is read
+        config.simulate_execution = simulation::enabled();
+        file_cache::set_capacity_mb(config.file_cache_mb);
+        Self::warn_unknown_keys(&config);
         Ok(config)
     }
 
+    /// Top-level keys of the configuration file.
+    pub fn config_keys(config: &ShellmindConfig) -> Vec<String> {
+        match serde_json::to_value(config) {
+            Ok(serde_json::Value::Object(fields)) => fields.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Warns once per process about keys in `config.toml` that no setting reads, which
+    /// are otherwise ignored without a trace.
+    fn warn_unknown_keys(config: &ShellmindConfig) {
+        static WARNED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+        let path = format!("{}/.shellmind/config.toml", std::env::var("HOME").unwrap_or(".".to_string()));
+        let Some(file) = std::fs::read_to_string(&path).ok().and_then(|content| content.parse::<toml::Table>().ok()) else {
+            return;
+        };
+        let known = Self::config_keys(config);
+        let unknown: Vec<&String> = file.keys().filter(|key| !known.contains(key)).collect();
+        if unknown.is_empty() || WARNED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+        for key in unknown {
+            let suggestions = fuzzy::closest(key, known.iter().map(String::as_str));
+            eprintln!("Warning: unknown key `{}` in {}{}", key, path, fuzzy::did_you_mean(&suggestions));
+        }
+    }
+
     pub fn save_configuration(config: &ShellmindConfig) -> Result<(), ShellmindError> {
         let home_dir = std::env::var("HOME").unwrap_or(".".to_string());
         let config_dir = format!("{}/.shellmind", home_dir);
@@ -116,11 +563,11 @@ impl ConfigManager {
         std::fs::create_dir_all(&config_dir)
             .map_err(|e| ShellmindError::Other(format!("Failed to create config directory: {}", e)))?;
 
-        let toml_string = toml::to_string(config)
+        // Policy and safe mode settings belong to the machine, not to the user's config
+        let toml_string = toml::to_string(&config.user_settings())
             .map_err(|e| ShellmindError::Other(format!("Failed to serialize config to TOML: {}", e)))?;
 
-        std::fs::write(&config_path, toml_string)
-            .map_err(|e| ShellmindError::Other(format!("Failed to write config file: {}", e)))?;
+        persist::locked_write(std::path::Path::new(&config_path), toml_string)?;
 
         Ok(())
     }
@@ -133,7 +580,7 @@ impl ConfigManager {
 
     pub fn validate_configuration(config: &ShellmindConfig) -> Result<(), ShellmindError> {
         if config.api_key.is_empty() {
-            return Err(ShellmindError::Other("API Key is not set. Please set it using the config command or GEMINI_API_KEY environment variable.".to_string()));
+            return Err(ShellmindError::Config(config_rs::ConfigError::Message("API Key is not set. Please set it using the config command or GEMINI_API_KEY environment variable.".to_string())));
         }
         Ok(())
     }
@@ -156,14 +603,32 @@ impl SandboxManager {
         // Placeholder for security assessment
         Ok(())
     }
+
+    /// Fails closed when a sandbox backend is required: no backend is implemented yet, so
+    /// commands are refused rather than run unsandboxed.
+    pub fn ensure_backend(backend: &str) -> Result<(), ShellmindError> {
+        if backend == "none" {
+            Ok(())
+        } else {
+            Err(ShellmindError::Other(format!("Sandbox backend '{}' is not available; refusing to run the command", backend)))
+        }
+    }
 }
 
 pub struct SecurityManager;
 
+/// Programs that execute code read from stdin or an argument.
+const INTERPRETERS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish", "python", "python3", "perl", "ruby", "node"];
+/// Programs that fetch remote content.
+const DOWNLOADERS: &[&str] = &["curl", "wget", "fetch"];
+
 impl SecurityManager {
+    /// Risk of a tool call; shell commands are assessed with [`Self::assess_command_risk`].
     pub fn assess_tool_safety(tool_name: &str, params: &serde_json::Value) -> SafetyLevel {
-        // Placeholder for tool safety assessment
-        SafetyLevel::Safe
+        match (tool_name, params.get("command").and_then(|c| c.as_str())) {
+            ("run_shell_command", Some(command)) => Self::assess_command_risk(command).level,
+            _ => SafetyLevel::Safe,
+        }
     }
 
     pub fn requires_confirmation(operation: &str) -> bool {
@@ -175,14 +640,284 @@ impl SecurityManager {
         // Placeholder for input sanitization
         input.to_string()
     }
+
+    /// Returns the `blocked_commands` or policy `banned_commands` pattern matching `command`.
+    pub fn blocked_pattern<'a>(config: &'a ShellmindConfig, command: &str) -> Option<&'a str> {
+        Self::matching_pattern(config.blocked_commands.iter().chain(config.policy.banned_commands.iter()), command)
+    }
+
+    /// The first of `patterns` matching `command`. The whole command and each stage of a
+    /// pipeline or command list are checked, as are the parsed commands with quotes
+    /// removed, those nested in subshells, substitutions and `bash -c`, and their
+    /// canonical argv, so `r""m -rf /`, `(rm -rf /)`, `/bin/rm -fr /` and
+    /// `bash -c 'rm -rf /'` all match `rm -rf /*`.
+    fn matching_pattern<'a>(patterns: impl Iterator<Item = &'a String>, command: &str) -> Option<&'a str> {
+        let normalize = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut candidates = vec![normalize(command)];
+        candidates.extend(
+            command
+                .split(|c| c == ';' || c == '|' || c == '&')
+                .map(normalize)
+                .filter(|stage| !stage.is_empty()),
+        );
+        let mut argvs = Vec::new();
+        for parsed in shell_parser::parse_with_inline_scripts(command) {
+            candidates.push(normalize(&parsed.normalized()));
+            let unwrapped: Vec<&str> = parsed.effective_words().iter().map(|w| w.value.as_str()).collect();
+            candidates.push(normalize(&unwrapped.join(" ")));
+            argvs.push(parsed.canonical());
+        }
+        // `sudo mkfs ...` must match `mkfs*`
+        let unprivileged: Vec<String> = candidates
+            .iter()
+            .filter_map(|c| c.strip_prefix("sudo ").map(str::to_string))
+            .collect();
+        candidates.extend(unprivileged);
+
+        patterns
+            .find(|pattern| {
+                let text = normalize(pattern);
+                if candidates.iter().any(|candidate| policy::wildcard_match(&text, candidate)) {
+                    return true;
+                }
+                // Patterns naming a single command are also matched on its canonical argv
+                match shell_parser::parse(pattern).commands.as_slice() {
+                    [only] if only.redirects.is_empty() => {
+                        let canonical = only.canonical();
+                        argvs.iter().any(|argv| policy::wildcard_match(&canonical, argv))
+                    }
+                    _ => false,
+                }
+            })
+            .map(String::as_str)
+    }
+
+    /// Checks a shell command against the blocklist and sandbox requirement before it runs.
+    /// Blocked commands are refused in every approval mode.
+    pub fn check_command(config: &ShellmindConfig, command: &str) -> Result<(), ShellmindError> {
+        if let Some(pattern) = Self::blocked_pattern(config, command) {
+            return Err(ShellmindError::Blocked(format!("command matches pattern '{}'", pattern)));
+        }
+        SandboxManager::ensure_backend(&config.sandbox_backend)
+    }
+
+    /// Returns true when every stage of `command` is a known read-only program and the
+    /// command writes nothing through redirection.
+    pub fn is_read_only_command(command: &str) -> bool {
+        const READ_ONLY_PROGRAMS: &[&str] = &[
+            "ls", "cat", "head", "tail", "grep", "find", "stat", "test", "[", "wc", "du", "df",
+            "ps", "pgrep", "whoami", "id", "uname", "pwd", "echo", "which", "file", "diff",
+            "systemctl", "service", "ping", "ss", "netstat", "git", "docker", "kubectl",
+        ];
+        // Subcommands that only inspect state for programs that can also mutate it.
+        const READ_ONLY_SUBCOMMANDS: &[(&str, &[&str])] = &[
+            ("systemctl", &["status", "is-active", "is-enabled", "is-failed", "show", "list-units"]),
+            ("service", &["status"]),
+            ("git", &["status", "log", "diff", "show", "branch", "rev-parse"]),
+            ("docker", &["ps", "inspect", "logs", "images"]),
+            ("kubectl", &["get", "describe", "logs"]),
+        ];
+
+        let parsed = shell_parser::parse(command);
+        if !parsed.complete || parsed.has_substitution() {
+            return false;
+        }
+
+        parsed.commands.iter().all(|stage| {
+            if stage.nesting != shell_parser::Nesting::TopLevel
+                || stage.background
+                || !stage.assignments.is_empty()
+                || stage.redirects.iter().any(|r| r.op.writes())
+            {
+                return false;
+            }
+            let mut words = stage.words.iter().map(|w| w.value.as_str());
+            let program = words.next().unwrap_or("");
+            if !READ_ONLY_PROGRAMS.contains(&program) {
+                return false;
+            }
+            if program == "find" && stage.words.iter().any(|w| w.value.starts_with("-exec") || w.value.starts_with("-ok") || w.value == "-delete" || w.value.starts_with("-fprint")) {
+                return false;
+            }
+            match READ_ONLY_SUBCOMMANDS.iter().find(|(p, _)| *p == program) {
+                Some((_, allowed)) => words
+                    .find(|w| !w.starts_with('-'))
+                    .map_or(false, |sub| allowed.contains(&sub)),
+                None => true,
+            }
+        })
+    }
+
+    /// Scores `command` from its parsed structure: what runs, what it writes and where
+    /// command output is executed. Reasons explain everything above `Safe`.
+    pub fn assess_command_risk(command: &str) -> RiskAssessment {
+        use shell_parser::{Nesting, RedirectOp};
+        const DESTRUCTIVE: &[&str] = &["mkfs", "fdisk", "parted", "wipefs", "shred", "dd"];
+
+        let parsed = shell_parser::parse(command);
+        let mut risk = RiskAssessment { level: SafetyLevel::Safe, reasons: Vec::new() };
+        if !parsed.complete {
+            risk.raise(SafetyLevel::Warning, "the command has unbalanced quotes or parentheses");
+        }
+
+        for stage in &parsed.commands {
+            let words = stage.effective_words();
+            let Some(program) = words.first() else {
+                continue;
+            };
+            let name = program.value.rsplit('/').next().unwrap_or("");
+            let args: Vec<&str> = words[1..].iter().map(|w| w.value.as_str()).collect();
+
+            if program.dynamic {
+                risk.raise(SafetyLevel::Dangerous, format!("the program name '{}' is only known at runtime", program.raw));
+            }
+            if stage.words.first().map_or(false, |w| w.value == "sudo" || w.value == "doas") {
+                risk.raise(SafetyLevel::Warning, format!("runs '{}' with elevated privileges", name));
+            }
+            if name == "eval" || (name == "xargs" && args.iter().any(|a| INTERPRETERS.contains(a))) {
+                risk.raise(SafetyLevel::Dangerous, format!("'{}' executes text built at runtime", name));
+            }
+            if name == "rm" {
+                let flags: String = args.iter().filter(|a| a.starts_with('-') && !a.starts_with("--")).map(|a| &a[1..]).collect();
+                let recursive = flags.contains('r') || flags.contains('R') || args.contains(&"--recursive");
+                let targets: Vec<&&str> = args.iter().filter(|a| !a.starts_with('-')).collect();
+                let sweeping = targets.iter().any(|t| matches!(**t, "/" | "/*" | "~" | "~/" | "*" | "." | ".." | "$HOME"));
+                if recursive && sweeping {
+                    risk.raise(SafetyLevel::Dangerous, format!("recursively deletes {}", targets.iter().map(|t| **t).collect::<Vec<_>>().join(" ")));
+                } else {
+                    risk.raise(SafetyLevel::Warning, "deletes files");
+                }
+            }
+            if DESTRUCTIVE.iter().any(|d| name == *d || name.starts_with(&format!("{}.", d))) {
+                risk.raise(SafetyLevel::Dangerous, format!("'{}' can destroy disk contents", name));
+            }
+            if (name == "chmod" || name == "chown") && args.iter().any(|a| *a == "-R" || *a == "--recursive") && args.iter().any(|a| *a == "/") {
+                risk.raise(SafetyLevel::Dangerous, "recursively changes ownership or permissions of /");
+            }
+            for redirect in stage.redirects.iter().filter(|r| r.op.writes()) {
+                if redirect.target.starts_with("/dev/") && !matches!(redirect.target.as_str(), "/dev/null" | "/dev/stdout" | "/dev/stderr" | "/dev/tty") {
+                    risk.raise(SafetyLevel::Dangerous, format!("writes to device {}", redirect.target));
+                } else if redirect.target.starts_with("/etc/") || redirect.target.starts_with("/boot/") {
+                    risk.raise(SafetyLevel::Dangerous, format!("writes to system file {}", redirect.target));
+                } else if redirect.op == RedirectOp::Write && redirect.target != "/dev/null" {
+                    risk.raise(SafetyLevel::Warning, format!("overwrites {}", redirect.target));
+                }
+            }
+            // Output of a download or substitution fed to an interpreter runs unreviewed code
+            if stage.pipeline_stage > 0 && INTERPRETERS.contains(&name) && parsed.pipeline(stage.pipeline).any(|s| {
+                s.pipeline_stage < stage.pipeline_stage
+                    && s.program().map_or(false, |p| DOWNLOADERS.contains(&p.value.rsplit('/').next().unwrap_or("")))
+            }) {
+                risk.raise(SafetyLevel::Dangerous, format!("pipes downloaded content into {}", name));
+            }
+            if stage.nesting == Nesting::Substitution && DOWNLOADERS.contains(&name) && parsed.commands.iter().any(|s| {
+                s.nesting != Nesting::Substitution && s.program().map_or(false, |p| INTERPRETERS.contains(&p.value.as_str()))
+            }) {
+                risk.raise(SafetyLevel::Dangerous, format!("runs a script downloaded with {}", name));
+            }
+            if let Some(reason) = cloud::mutation(name, &args) {
+                risk.raise(SafetyLevel::Dangerous, reason);
+            }
+            if stage.background {
+                risk.raise(SafetyLevel::Warning, format!("leaves '{}' running in the background", name));
+            }
+        }
+        if parsed.has_substitution() {
+            risk.raise(SafetyLevel::Warning, "uses command substitution");
+        }
+        risk
+    }
+}
+
+/// An effect of a command worth pointing out before it is approved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandEffect {
+    /// `>` to a file that already exists.
+    Overwrites(String),
+    /// `>>` to a file that already exists.
+    AppendsTo(String),
+    /// Remote content is executed, e.g. `sh -c "$(curl ...)"` or `curl ... | bash`; holds
+    /// the fetching command.
+    ExecutesOutputOf(String),
+}
+
+impl SecurityManager {
+    /// Redirections that change existing files and remotely fetched content that gets
+    /// executed, from the parsed command. Paths are resolved against `cwd`.
+    pub fn command_effects(command: &str, cwd: &std::path::Path) -> Vec<CommandEffect> {
+        use shell_parser::{Nesting, RedirectOp};
+        let parsed = shell_parser::parse(command);
+        let is_downloader = |stage: &shell_parser::SimpleCommand| {
+            stage.program().map_or(false, |p| DOWNLOADERS.contains(&p.value.rsplit('/').next().unwrap_or("")))
+        };
+        let text = |stage: &shell_parser::SimpleCommand| stage.words.iter().map(|w| w.raw.as_str()).collect::<Vec<_>>().join(" ");
+        let runs_interpreter = parsed.commands.iter().any(|s| {
+            s.nesting != Nesting::Substitution && s.program().map_or(false, |p| INTERPRETERS.contains(&p.value.as_str()))
+        });
+
+        let mut effects = Vec::new();
+        for stage in &parsed.commands {
+            for redirect in &stage.redirects {
+                let target = match redirect.target.strip_prefix("~/") {
+                    Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+                    None => Some(cwd.join(&redirect.target)),
+                };
+                if !target.map_or(false, |t| t.is_file()) {
+                    continue;
+                }
+                let effect = match redirect.op {
+                    RedirectOp::Write | RedirectOp::ReadWrite => CommandEffect::Overwrites(redirect.target.clone()),
+                    RedirectOp::Append => CommandEffect::AppendsTo(redirect.target.clone()),
+                    _ => continue,
+                };
+                if !effects.contains(&effect) {
+                    effects.push(effect);
+                }
+            }
+            if !is_downloader(stage) {
+                continue;
+            }
+            let executed = match stage.nesting {
+                Nesting::Substitution => runs_interpreter || parsed.commands.iter().any(|s| s.program().map_or(false, |p| p.dynamic)),
+                _ => parsed.pipeline(stage.pipeline).any(|s| {
+                    s.pipeline_stage > stage.pipeline_stage && s.program().map_or(false, |p| INTERPRETERS.contains(&p.value.as_str()))
+                }),
+            };
+            if executed {
+                effects.push(CommandEffect::ExecutesOutputOf(secrets::mask(&text(stage))));
+            }
+        }
+        effects
+    }
+}
+
+/// Outcome of [`SecurityManager::assess_command_risk`].
+#[derive(Debug, Clone)]
+pub struct RiskAssessment {
+    pub level: SafetyLevel,
+    pub reasons: Vec<String>,
 }
 
+impl RiskAssessment {
+    fn raise(&mut self, level: SafetyLevel, reason: impl Into<String>) {
+        let reason = reason.into();
+        if !self.reasons.contains(&reason) {
+            self.reasons.push(reason);
+        }
+        self.level = self.level.max(level);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SafetyLevel {
     Safe,
     Warning,
     Dangerous,
 }
 
+/// File in the `DataKind::Memory` directory holding the facts saved by `save_memory`.
+pub const MEMORY_FACTS_FILE: &str = "facts.md";
+
 pub struct MemoryManager {
     context_files: std::collections::HashMap<String, String>,
     runtime_memory: Vec<String>,
@@ -200,9 +935,41 @@ impl MemoryManager {
         // Placeholder for loading context from files (e.g., GEMINI.md)
         // For now, just simulate loading.
         self.context_files.insert("global".to_string(), "Global context loaded.".to_string());
+        let facts = Self::saved_facts()?;
+        if !facts.is_empty() {
+            self.context_files.insert("memory".to_string(), facts);
+        }
         Ok(())
     }
 
+    /// The file under `~/.shellmind/memory/` where `save_memory` keeps facts.
+    fn facts_path() -> Result<std::path::PathBuf, ShellmindError> {
+        Ok(retention::DataKind::Memory.path()?.join(MEMORY_FACTS_FILE))
+    }
+
+    /// Facts saved with `save_memory`, one `- ` line each.
+    pub fn saved_facts() -> Result<String, ShellmindError> {
+        let path = Self::facts_path()?;
+        if !path.exists() {
+            return Ok(String::new());
+        }
+        persist::read_private(&path)
+    }
+
+    /// Appends `fact` to the long-term memory store, with secrets masked.
+    pub fn save_fact(fact: &str) -> Result<(), ShellmindError> {
+        let path = Self::facts_path()?;
+        let dir = retention::DataKind::Memory.path()?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create memory directory: {}", e)))?;
+        // Locked on the directory so the lock file stays out of the synced and exported store
+        persist::with_lock(&dir, || {
+            let mut facts = Self::saved_facts()?;
+            facts.push_str(&format!("- {}\n", secrets::mask(&fact.split_whitespace().collect::<Vec<_>>().join(" "))));
+            persist::atomic_write(&path, persist::seal(facts)?)
+        })
+    }
+
     pub async fn refresh_context(&mut self) -> Result<(), ShellmindError> {
         // Placeholder for refreshing context (e.g., re-reading files)
         self.load_hierarchical_context().await
@@ -232,10 +999,14 @@ impl MemoryManager {
 pub struct CommandHistoryManager {
     history_file_path: std::path::PathBuf,
     history: Vec<String>,
+    /// Entries added by this instance and not yet merged into the history file.
+    pending: Vec<String>,
+    /// Oldest entries beyond this are dropped on save (0 is unlimited).
+    max_entries: usize,
 }
 
 impl CommandHistoryManager {
-    pub fn new() -> Result<Self, ShellmindError> {
+    pub fn new(max_entries: usize) -> Result<Self, ShellmindError> {
         let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
         let history_dir = home_dir.join(".shellmind");
         let history_file_path = history_dir.join("history.txt");
@@ -243,21 +1014,24 @@ impl CommandHistoryManager {
         std::fs::create_dir_all(&history_dir)
             .map_err(|e| ShellmindError::Other(format!("Failed to create history directory: {}", e)))?;
 
-        let history = if history_file_path.exists() {
-            std::fs::read_to_string(&history_file_path)
-                .map_err(|e| ShellmindError::Other(format!("Failed to read history file: {}", e)))?
-                .lines()
-                .map(|s| s.to_string())
-                .collect()
-        } else {
-            Vec::new()
-        };
+        let history = Self::read_history_file(&history_file_path)?;
 
-        Ok(Self { history_file_path, history })
+        Ok(Self { history_file_path, history, pending: Vec::new(), max_entries })
+    }
+
+    fn read_history_file(path: &std::path::Path) -> Result<Vec<String>, ShellmindError> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(persist::read_private(path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to read history file: {}", e)))?
+            .lines()
+            .map(|s| s.to_string())
+            .collect())
     }
 
     pub fn add_command(&mut self, command: &str) -> Result<(), ShellmindError> {
-        self.history.push(command.to_string());
+        self.pending.push(secrets::mask(command));
         self.save_history()
     }
 
@@ -265,14 +1039,41 @@ impl CommandHistoryManager {
         &self.history
     }
 
-    fn save_history(&self) -> Result<(), ShellmindError> {
-        let content = self.history.join("\n");
-        std::fs::write(&self.history_file_path, content)
-            .map_err(|e| ShellmindError::Other(format!("Failed to write history file: {}", e)))?;
-        Ok(())
+    /// Merges pending entries into the history file under a lock. Entries written by other
+    /// instances since we last read the file are kept rather than overwritten.
+    fn save_history(&mut self) -> Result<(), ShellmindError> {
+        let path = self.history_file_path.clone();
+        let pending = std::mem::take(&mut self.pending);
+        let max_entries = self.max_entries;
+        let merged = persist::with_lock(&path, || {
+            let mut merged = Self::read_history_file(&path)?;
+            merged.extend(pending.iter().cloned());
+            if max_entries > 0 && merged.len() > max_entries {
+                merged.drain(..merged.len() - max_entries);
+            }
+            persist::atomic_write(&path, persist::seal(merged.join("\n"))?)?;
+            Ok(merged)
+        });
+        match merged {
+            Ok(merged) => {
+                self.history = merged;
+                Ok(())
+            }
+            Err(e) => {
+                self.pending = pending;
+                Err(e)
+            }
+        }
     }
 }
 
+/// Process-wide HTTP client for integrations (OpenAPI tools, webhooks, paste services),
+/// so their connections are pooled instead of set up for every request.
+pub fn http_client() -> reqwest::Client {
+    static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new).clone()
+}
+
 use std::pin::Pin;
 
 use std::future::Future;
@@ -285,50 +1086,400 @@ pub trait BaseTool: Send + Sync {
     fn validate_tool_params(&self, params: &serde_json::Value) -> bool;
     fn get_description(&self, params: &serde_json::Value) -> String;
     fn should_confirm_execute(&self, params: &serde_json::Value) -> Option<ConfirmationDetails>;
-    fn execute(&self, params: serde_json::Value, signal: Option<tokio::signal::unix::Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>>;
+    /// `Dangerous` calls are always confirmed, with "no" as the default, and never pre-approved.
+    fn safety_level(&self, _params: &serde_json::Value) -> SafetyLevel {
+        SafetyLevel::Safe
+    }
+    /// Runs the tool. Long-running tools report step updates through `progress`.
+    fn execute(&self, params: serde_json::Value, signal: Option<tokio::signal::unix::Signal>, progress: Option<progress::ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>>;
+}
+
+/// A tool whose parameters deserialize into a typed struct. Implementing it provides
+/// `BaseTool` with the parameter schema generated from `Params` and per-field
+/// "missing/invalid" errors from serde.
+pub trait TypedTool: Send + Sync {
+    type Params: serde::de::DeserializeOwned + schemars::JsonSchema + Send + 'static;
+
+    fn name(&self) -> &'static str;
+    fn display_name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn get_description(&self, params: &Self::Params) -> String;
+    fn should_confirm_execute(&self, params: &Self::Params) -> Option<ConfirmationDetails>;
+    fn safety_level(&self, _params: &Self::Params) -> SafetyLevel {
+        SafetyLevel::Safe
+    }
+    fn execute(&self, params: Self::Params, signal: Option<tokio::signal::unix::Signal>, progress: Option<progress::ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>>;
+}
+
+/// Generates an inline JSON schema (no `$schema`/`definitions`) for a parameter struct.
+pub fn params_schema<P: schemars::JsonSchema>() -> serde_json::Value {
+    let settings = schemars::gen::SchemaSettings::draft07().with(|s| {
+        s.meta_schema = None;
+        s.inline_subschemas = true;
+    });
+    let schema = settings.into_generator().into_root_schema_for::<P>();
+    serde_json::to_value(schema).unwrap_or_else(|_| json!({ "type": "object" }))
+}
+
+/// Deserializes tool parameters, naming the tool in the error.
+pub fn parse_tool_params<P: serde::de::DeserializeOwned>(tool_name: &str, params: &serde_json::Value) -> Result<P, ShellmindError> {
+    serde_json::from_value(params.clone())
+        .map_err(|e| ShellmindError::Other(format!("Invalid parameters for {}: {}", tool_name, e)))
+}
+
+impl<T: TypedTool> BaseTool for T {
+    fn name(&self) -> &'static str {
+        TypedTool::name(self)
+    }
+
+    fn display_name(&self) -> &'static str {
+        TypedTool::display_name(self)
+    }
+
+    fn description(&self) -> &'static str {
+        TypedTool::description(self)
+    }
+
+    fn parameter_schema(&self) -> serde_json::Value {
+        params_schema::<T::Params>()
+    }
+
+    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
+        parse_tool_params::<T::Params>(TypedTool::name(self), params).is_ok()
+    }
+
+    fn get_description(&self, params: &serde_json::Value) -> String {
+        match parse_tool_params::<T::Params>(TypedTool::name(self), params) {
+            Ok(params) => TypedTool::get_description(self, &params),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn should_confirm_execute(&self, params: &serde_json::Value) -> Option<ConfirmationDetails> {
+        parse_tool_params::<T::Params>(TypedTool::name(self), params)
+            .ok()
+            .and_then(|params| TypedTool::should_confirm_execute(self, &params))
+    }
+
+    fn safety_level(&self, params: &serde_json::Value) -> SafetyLevel {
+        parse_tool_params::<T::Params>(TypedTool::name(self), params)
+            .map_or(SafetyLevel::Safe, |params| TypedTool::safety_level(self, &params))
+    }
+
+    fn execute(&self, params: serde_json::Value, signal: Option<tokio::signal::unix::Signal>, progress: Option<progress::ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        match parse_tool_params::<T::Params>(TypedTool::name(self), &params) {
+            Ok(params) => {
+                let future = TypedTool::execute(self, params, signal, progress);
+                Box::pin(async move {
+                    let start = std::time::Instant::now();
+                    let result = future.await?;
+                    Ok(result.with_duration(start.elapsed()))
+                })
+            }
+            Err(e) => Box::pin(async move { Err(e) }),
+        }
+    }
 }
 
 pub struct ConfirmationDetails {
     pub message: String,
 }
 
-pub enum ToolResult {
-    Success(String),
-    Error(String),
+/// Outcome of a tool run: what the user sees, what the model gets back, and metadata
+/// about side effects.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolResult {
+    pub is_error: bool,
+    /// Text rendered for the user.
+    pub display: String,
+    /// Content fed back to the model; the display text unless a tool sets structured data.
+    pub llm_content: serde_json::Value,
+    /// Paths created or modified by the tool.
+    pub artifacts: Vec<std::path::PathBuf>,
+    pub duration: Option<std::time::Duration>,
+    /// Set when the output was cut short to stay within a budget.
+    pub truncated: bool,
+}
+
+impl ToolResult {
+    pub fn success(display: impl Into<String>) -> Self {
+        Self::new(false, display.into())
+    }
+
+    pub fn error(display: impl Into<String>) -> Self {
+        Self::new(true, display.into())
+    }
+
+    fn new(is_error: bool, display: String) -> Self {
+        ToolResult {
+            is_error,
+            llm_content: serde_json::Value::String(display.clone()),
+            display,
+            artifacts: Vec::new(),
+            duration: None,
+            truncated: false,
+        }
+    }
+
+    pub fn with_content(mut self, content: serde_json::Value) -> Self {
+        self.llm_content = content;
+        self
+    }
+
+    pub fn with_artifact(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.artifacts.push(path.into());
+        self
+    }
+
+    pub fn with_duration(mut self, duration: std::time::Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn with_truncated(mut self, truncated: bool) -> Self {
+        self.truncated = truncated;
+        self
+    }
 }
 
 pub struct ToolRegistry {
     tools: std::collections::HashMap<String, Box<dyn BaseTool>>,
+    /// Source of every entry in `tools`.
+    sources: std::collections::HashMap<String, tool_source::ToolSource>,
+    /// Qualified names of tools that also hold their bare name, e.g. `custom.deploy` to `deploy`.
+    aliases: std::collections::HashMap<String, String>,
+    conflicts: Vec<tool_source::ToolConflict>,
+    /// Names that `register` silently ignores (`disabled_tools`).
+    disabled: Vec<String>,
+    /// `[tools.limits]`; tools with limits are registered wrapped in `LimitedTool`.
+    limits: limits::ToolsConfig,
+    /// Tool sources that failed to load and were skipped, e.g. an unreadable OpenAPI spec.
+    load_errors: Vec<String>,
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
         ToolRegistry {
             tools: std::collections::HashMap::new(),
+            sources: std::collections::HashMap::new(),
+            aliases: std::collections::HashMap::new(),
+            conflicts: Vec::new(),
+            disabled: Vec::new(),
+            limits: limits::ToolsConfig::default(),
+            load_errors: Vec::new(),
         }
     }
 
+    /// Registry with the built-in tools plus the custom and OpenAPI tools declared in `config`.
+    pub fn from_config(config: &ShellmindConfig) -> Result<Self, ShellmindError> {
+        let mut registry = ToolRegistry::new();
+        registry.disabled = config.disabled_tools.clone();
+        registry.limits = config.tools.clone();
+        registry.register(tools::ReadFileTool);
+        registry.register(tools::WriteFileTool);
+        registry.register(tools::EditTool);
+        registry.register(tools::LSTool);
+        let roots = workspace::Workspace::from_config(config).roots;
+        registry.register(tools::GrepTool { roots: roots.clone(), use_index: config.grep_index });
+        registry.register(tools::GlobTool { roots });
+        registry.register(tools::ShellTool);
+        registry.register(tools::WebFetchTool);
+        registry.register(tools::WebSearchTool);
+        registry.register(tools::MemoryTool);
+        registry.register(tools::ReadManyFilesTool);
+        registry.register(tools::DiffTool);
+        registry.register(tools::MultiEditTool);
+        registry.register(tools::TreeTool);
+        registry.register(calc::CalcTool);
+        registry.register(plot::PlotTool);
+        let github = github::GitHubClient::from_config(config);
+        registry.register(github::GitHubListIssuesTool { client: github.clone() });
+        registry.register(github::GitHubCreateIssueTool { client: github.clone() });
+        registry.register(github::GitHubPrDiffTool { client: github.clone() });
+        registry.register(github::GitHubCommentTool { client: github });
+        let docker = docker::DockerClient::from_config(config);
+        registry.register(docker::DockerListTool { client: docker.clone() });
+        registry.register(docker::DockerLogsTool { client: docker.clone() });
+        registry.register(docker::DockerExecTool { client: docker });
+        registry.register_custom_tools(&config.custom_tools);
+        registry.register_openapi_tools(&config.openapi_tools);
+        Ok(registry)
+    }
+
+    /// Registers a built-in tool.
     pub fn register<T: BaseTool + 'static>(&mut self, tool: T) {
-        self.tools.insert(tool.name().to_string(), Box::new(tool));
+        self.register_from(tool_source::ToolSource::Builtin, tool);
+    }
+
+    /// Registers a tool from `source`. A name already held by a source with higher
+    /// precedence leaves the new tool reachable only by its qualified name.
+    pub fn register_from<T: BaseTool + 'static>(&mut self, source: tool_source::ToolSource, tool: T) {
+        let name = tool.name().to_string();
+        let qualified = tool_source::qualified_name(&source, &name);
+        if self.disabled.iter().any(|disabled| *disabled == name || *disabled == qualified) {
+            return;
+        }
+        let limits = self.limits.limits_for(&name);
+        let tool: Box<dyn BaseTool> = if limits.is_unlimited() {
+            Box::new(tool)
+        } else {
+            Box::new(limits::LimitedTool::new(Box::new(tool), limits))
+        };
+        let tool: Box<dyn BaseTool> = if simulation::enabled() && !simulation::READ_ONLY_TOOLS.contains(&name.as_str()) {
+            Box::new(simulation::SimulatedTool::new(tool))
+        } else {
+            tool
+        };
+
+        match self.sources.get(&name).cloned() {
+            // The same source registering a name again replaces its tool
+            Some(existing) if existing == source => {}
+            Some(existing) if !source.takes_precedence_over(&existing) => {
+                self.conflicts.push(tool_source::ToolConflict { name, kept: existing, shadowed: qualified.clone() });
+                self.tools.insert(qualified.clone(), Box::new(tool_source::NamespacedTool::new(tool, qualified.clone())));
+                self.sources.insert(qualified, source);
+                return;
+            }
+            Some(existing) => {
+                let displaced_name = tool_source::qualified_name(&existing, &name);
+                if let Some(displaced) = self.tools.remove(&name) {
+                    self.tools.insert(displaced_name.clone(), Box::new(tool_source::NamespacedTool::new(displaced, displaced_name.clone())));
+                }
+                self.aliases.remove(&displaced_name);
+                self.sources.insert(displaced_name.clone(), existing);
+                for conflict in self.conflicts.iter_mut().filter(|c| c.name == name) {
+                    conflict.kept = source.clone();
+                }
+                self.conflicts.push(tool_source::ToolConflict { name: name.clone(), kept: source.clone(), shadowed: displaced_name });
+            }
+            None => {}
+        }
+        if qualified != name {
+            self.aliases.insert(qualified, name.clone());
+        }
+        self.sources.insert(name.clone(), source);
+        self.tools.insert(name, tool);
     }
 
     pub async fn discover_tools(&mut self) -> Result<(), ShellmindError> {
-        // Placeholder for tool discovery logic (e.g., from MCP servers, plugins)
+        // Project-level declarative tools; MCP servers and plugins are not discovered yet
+        let project_tools = custom_tools::load_tool_definitions(std::path::Path::new(".shellmind/tools"))?;
+        for definition in project_tools {
+            self.register_from(tool_source::ToolSource::Project, custom_tools::CustomTool::new(definition));
+        }
         Ok(())
     }
 
+    pub fn register_custom_tools(&mut self, definitions: &[custom_tools::CustomToolDefinition]) {
+        for definition in definitions {
+            self.register_from(tool_source::ToolSource::Custom, custom_tools::CustomTool::new(definition.clone()));
+        }
+    }
+
+    /// Registers the tools of every OpenAPI source; a source that fails to load is
+    /// skipped and reported in `load_errors`.
+    pub fn register_openapi_tools(&mut self, sources: &[openapi::OpenApiSource]) {
+        for source in sources {
+            let origin = tool_source::ToolSource::OpenApi(source.namespace());
+            match openapi::load_openapi_tools(source) {
+                Ok(tools) => {
+                    for tool in tools {
+                        self.register_from(origin.clone(), tool);
+                    }
+                }
+                Err(e) => self.load_errors.push(format!("OpenAPI tools '{}' skipped: {}", source.namespace(), e)),
+            }
+        }
+    }
+
+    /// Drops every tool except the built-in read-only ones (`simulation::READ_ONLY_TOOLS`),
+    /// for runs that must not change anything.
+    pub fn retain_read_only(&mut self) {
+        let sources = &self.sources;
+        self.tools.retain(|name, _| {
+            simulation::READ_ONLY_TOOLS.contains(&name.as_str()) && sources.get(name) == Some(&tool_source::ToolSource::Builtin)
+        });
+        let tools = &self.tools;
+        self.aliases.retain(|_, target| tools.contains_key(target));
+        self.sources.retain(|name, _| tools.contains_key(name));
+    }
+
+    /// Tool sources that failed to load and were skipped.
+    pub fn load_errors(&self) -> &[String] {
+        &self.load_errors
+    }
+
+    /// Names registered by more than one source and how each was settled.
+    pub fn conflicts(&self) -> &[tool_source::ToolConflict] {
+        &self.conflicts
+    }
+
+    /// Registered tools grouped by source, built-in tools first, sorted by name.
+    pub fn tools_by_source(&self) -> Vec<(tool_source::ToolSource, Vec<&dyn BaseTool>)> {
+        let mut groups: std::collections::BTreeMap<tool_source::ToolSource, Vec<&dyn BaseTool>> = Default::default();
+        for (name, tool) in &self.tools {
+            if let Some(source) = self.sources.get(name) {
+                groups.entry(source.clone()).or_default().push(&**tool);
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(source, mut tools)| {
+                tools.sort_by_key(|tool| tool.name());
+                (source, tools)
+            })
+            .collect()
+    }
+
+    /// The name `tool` is called by in other namespaces, e.g. `custom.deploy` for `deploy`.
+    pub fn qualified_alias(&self, name: &str) -> Option<&str> {
+        self.aliases.iter().find(|(_, target)| *target == name).map(|(alias, _)| alias.as_str())
+    }
+
     pub fn get_tool_schemas(&self) -> Vec<serde_json::Value> {
         self.tools.values().map(|tool| tool.parameter_schema()).collect()
     }
 
+    /// The tool called `name`, bare or qualified.
     pub fn get_tool(&self, name: &str) -> Option<&dyn BaseTool> {
+        let name = self.aliases.get(name).map_or(name, String::as_str);
         self.tools.get(name).map(|b| &**b)
     }
+
+    fn tool_names(&self) -> impl Iterator<Item = &str> {
+        self.tools.keys().chain(self.aliases.keys()).map(String::as_str)
+    }
+
+    /// Registered tools with names close to an unknown `name`, best first.
+    pub fn similar_tool_names(&self, name: &str) -> Vec<&str> {
+        fuzzy::closest(name, self.tool_names())
+    }
+
+    /// The registered tool an unknown `name` was almost certainly meant to be.
+    pub fn corrected_tool_name(&self, name: &str) -> Option<&str> {
+        fuzzy::auto_correct(name, self.tool_names())
+    }
 }
 
-// Gemini API structs (for REST and shared types)
+/// Parses a model reply of the form `tool_name({...})` or `namespace.tool_name({...})` into
+/// the tool name and its JSON parameters; unparsable parameters become an empty object.
+pub fn parse_tool_call(text: &str) -> Option<(String, serde_json::Value)> {
+    let tool_call_regex = regex::Regex::new(r"^([a-zA-Z_][a-zA-Z0-9_]*(?:\.[a-zA-Z_][a-zA-Z0-9_]*)?)\((.*)\)$").unwrap();
+    let captures = tool_call_regex.captures(text.trim())?;
+    let params = serde_json::from_str(&captures[2]).unwrap_or_else(|_| json!({}));
+    Some((captures[1].to_string(), params))
+}
 
 impl ShellmindConfig {
+    /// The user's own settings, without what the system policy and production safe mode
+    /// override; this is what may leave the process, e.g. in an exported profile.
+    pub fn user_settings(&self) -> ShellmindConfig {
+        let mut config = self.clone();
+        production::restore(&mut config);
+        self.policy.restore(&mut config);
+        config
+    }
+
     pub fn save(&self) -> Result<(), ShellmindError> {
         ConfigManager::save_configuration(self)
     }
@@ -337,140 +1488,180 @@ impl ShellmindConfig {
         ConfigManager::add_allowed_command(self, command)
     }
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GeminiPart {
-    pub text: String,
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GeminiContent {
-    pub role: String,
-    pub parts: Vec<GeminiPart>,
+const TURKISH_INSTRUCTIONS: &str = "Always answer in Turkish. Shell commands stay as they are; explanations, questions and summaries are written in Turkish.\n\
+Examples:\n\
+Kullanıcı: bu dizindeki en büyük 5 dosyayı göster\n\
+Yanıt: du -ah . | sort -rh | head -n 5\n\
+Kullanıcı: nginx servisini yeniden başlat\n\
+Yanıt: sudo systemctl restart nginx\n\
+Kullanıcı: son 1 saatte değişen dosyaları bul\n\
+Yanıt: find . -type f -mmin -60";
+
+pub fn get_system_prompt_text(config: &ShellmindConfig) -> String {
+    let mut prompt = match &config.prompt_variant {
+        Some(variant) => prompt_trials::variant_prompt(config, variant).to_string(),
+        None => config.system_prompt.clone(),
+    };
+    if let Ok(cwd) = std::env::current_dir() {
+        let note = project::ProjectInfo::detect(&cwd).prompt_note();
+        if !note.is_empty() {
+            prompt.push_str("\n\n");
+            prompt.push_str(&note);
+        }
+    }
+    if config.use_shell_aliases {
+        let note = shell_env::ShellEnv::load().prompt_note();
+        if !note.is_empty() {
+            prompt.push_str("\n\n");
+            prompt.push_str(&note);
+        }
+    }
+    if config.feedback_steering {
+        let note = feedback::steering_note(&feedback::recent_for_project(config.feedback_examples));
+        if !note.is_empty() {
+            prompt.push_str("\n\n");
+            prompt.push_str(&note);
+        }
+    }
+    let workspace = workspace::Workspace::from_config(config).prompt_context();
+    if !workspace.is_empty() {
+        prompt.push_str("\n\n");
+        prompt.push_str(&workspace);
+    }
+    if config.cloud_context {
+        let note = cloud::prompt_note(cloud::accounts());
+        if !note.is_empty() {
+            prompt.push_str("\n\n");
+            prompt.push_str(&note);
+        }
+    }
+    if config.resolved_language() == "tr" {
+        prompt.push_str("\n\n");
+        prompt.push_str(TURKISH_INSTRUCTIONS);
+    }
+    prompt
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GeminiRequest {
-    pub contents: Vec<GeminiContent>,
-    pub generation_config: Option<serde_json::Value>,
+/// Builds the prompt asking the model for a cheap read-only check of an executed command.
+pub fn build_verification_prompt(goal: &str, command: &str) -> String {
+    format!(
+        "The user asked: \"{}\". The command `{}` was executed successfully. Propose a single cheap, read-only command whose exit status is 0 only if the goal was achieved (e.g. `systemctl is-active nginx` after a restart). Reply with the command only.",
+        goal,
+        command
+    )
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GeminiResponse {
-    pub candidates: Vec<Candidate>,
+/// Parses `command` with `sh -n`, which reads it without executing anything, and returns
+/// the shell's message for syntax errors such as unbalanced quotes.
+pub fn check_syntax(command: &str) -> Result<(), String> {
+    if cfg!(target_os = "windows") {
+        return Ok(());
+    }
+    // If `sh` can't be started the command couldn't run either; leave that to execution
+    let Ok(output) = std::process::Command::new("sh").arg("-n").arg("-c").arg(command).output() else {
+        return Ok(());
+    };
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Candidate {
-    pub content: GeminiContent,
+/// Asks the model to correct a command that `sh -n` rejected.
+pub fn build_syntax_fix_prompt(command: &str, error: &str) -> String {
+    format!(
+        "The command `{}` is not valid shell syntax:\n{}\nPropose the same command with the syntax fixed (e.g. balanced quotes and brackets). Reply with the command only.",
+        command, error
+    )
 }
 
+/// Builds the follow-up prompt sent to the model when an executed command fails.
+pub fn build_fix_prompt(command: &str, exit_code: Option<i32>, stderr: &str) -> String {
+    let exit_code = exit_code.map(|c| c.to_string()).unwrap_or_else(|| "unknown".to_string());
+    format!(
+        "The command `{}` failed with exit code {}.\nstderr:\n{}\nPropose a single corrected command that achieves the original goal. Reply with the command only.",
+        command,
+        exit_code,
+        stderr.trim()
+    )
+}
 
-pub fn get_system_prompt_text(config: &ShellmindConfig) -> String {
-    config.system_prompt.clone()
-}
-
-pub async fn generate_command_rest(
-    config: &ShellmindConfig,
-    user_prompt: &str,
-    history: &[GeminiContent],
-) -> Result<String, ShellmindError> {
-    let api_url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-        config.model_name,
-        config.api_key
+/// Asks the model for a different answer to `prompt` than the rejected one, optionally
+/// following an extra instruction such as "shorter" or "use awk not sed".
+pub fn build_retry_prompt(prompt: &str, rejected: &str, instruction: &str) -> String {
+    let mut retry = format!(
+        "Answer this request again: \"{}\". Your previous answer was rejected:\n{}\nGive a different answer in the same format.",
+        prompt,
+        rejected.trim()
     );
-
-    let mut contents = history.to_vec();
-    contents.push(GeminiContent {
-        role: "user".to_string(),
-        parts: vec![GeminiPart { text: user_prompt.to_string() }],
-    });
-
-    let req = GeminiRequest {
-        contents,
-        generation_config: Some(json!({
-            "temperature": config.temperature,
-        })),
-    };
-
-    let client = reqwest::Client::new();
-    let resp = client.post(&api_url).json(&req).send().await?;
-
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let error_body = resp.text().await?;
-        return Err(ShellmindError::Other(format!(
-            "API request failed with status: {} - {}",
-            status,
-            error_body
-        )));
+    if !instruction.is_empty() {
+        retry.push_str(&format!(" Additional instruction: {}.", instruction));
     }
-
-    let resp_json: GeminiResponse = resp.json().await?;
-
-    let command = resp_json
-        .candidates
-        .get(0)
-        .and_then(|c| c.content.parts.get(0))
-        .map(|p| p.text.clone())
-        .unwrap_or_else(|| "No command generated".to_string());
-
-    Ok(command)
+    retry
 }
 
-pub async fn generate_command_grpc(
-    config: &ShellmindConfig,
-    user_prompt: &str,
-    history: &[GeminiContent],
-) -> Result<String, ShellmindError> {
-    let channel = Channel::from_shared(config.grpc_endpoint.clone())?.connect().await?;
-    let mut client = GenerativeServiceClient::new(channel);
+/// Asks for an explanation only, so informational questions are never treated as a
+/// command to run.
+pub fn build_ask_prompt(question: &str) -> String {
+    format!(
+        "Answer this question with an explanation only, formatted as Markdown: \"{}\".\nDo not reply with a bare command and do not call any tool; nothing you write will be executed. When commands help the explanation, show them as examples in fenced code blocks.",
+        question
+    )
+}
 
-    let mut contents_grpc: Vec<Content> = history.iter().map(|c| {
-        Content {
-            role: c.role.clone(),
-            parts: c.parts.iter().map(|p| Part { text: p.text.clone() }).collect(),
-        }
-    }).collect();
+/// Tells the model a proposed command was refused so it can suggest a safer alternative.
+pub fn build_blocked_prompt(command: &str, reason: &str) -> String {
+    format!(
+        "The command `{}` was refused and will never be executed: {}.\nPropose a single alternative command that achieves the original goal without matching that pattern, or reply with a short explanation if there is no safe alternative.",
+        command, reason
+    )
+}
 
-    contents_grpc.push(Content {
-        role: "user".to_string(),
-        parts: vec![Part { text: user_prompt.to_string() }],
-    });
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let request = tonic::Request::new(GenerateContentRequest {
-        model: format!("models/{}", config.model_name),
-        contents: contents_grpc,
-        generation_config: Some(GenerationConfig {
-            temperature: config.temperature,
-        }),
-    });
+    fn blocked(command: &str) -> Option<&'static str> {
+        static PATTERNS: std::sync::OnceLock<Vec<String>> = std::sync::OnceLock::new();
+        let patterns = PATTERNS.get_or_init(|| ["mkfs*", "rm -rf /", "rm -rf /*", "* > /dev/sd*"].iter().map(|p| p.to_string()).collect());
+        SecurityManager::matching_pattern(patterns.iter(), command)
+    }
 
-    let response = client.generate_content(request).await?.into_inner();
+    #[test]
+    fn test_blocked_patterns_match_respelled_commands() {
+        assert_eq!(blocked("rm -rf /"), Some("rm -rf /"));
+        assert_eq!(blocked("rm -fr /"), Some("rm -rf /"));
+        assert_eq!(blocked("rm -r -f /"), Some("rm -rf /"));
+        assert_eq!(blocked("/bin/rm -rf /"), Some("rm -rf /"));
+        assert_eq!(blocked("sudo /usr/bin/rm -rf / --no-preserve-root"), Some("rm -rf /*"));
+        assert_eq!(blocked("/sbin/mkfs.ext4 /dev/sdb1"), Some("mkfs*"));
+    }
 
-    let command = response
-        .candidates
-        .get(0)
-        .and_then(|c| c.content.as_ref())
-        .and_then(|c| c.parts.get(0))
-        .map(|p| p.text.clone())
-        .unwrap_or_else(|| "No command generated".to_string());
+    #[test]
+    fn test_blocked_patterns_match_nested_commands() {
+        assert_eq!(blocked("bash -c 'rm -rf /'"), Some("rm -rf /"));
+        assert_eq!(blocked("sh -c \"cd /tmp; rm -fr /\""), Some("rm -rf /"));
+        assert_eq!(blocked("echo $(rm -rf /)"), Some("rm -rf /"));
+        assert_eq!(blocked("(rm -rf /)"), Some("rm -rf /"));
+        assert_eq!(blocked("eval 'rm -rf /'"), Some("rm -rf /"));
+    }
 
-    Ok(command)
-}
+    #[test]
+    fn test_blocked_patterns_leave_other_commands() {
+        assert_eq!(blocked("rm -rf ./build"), None);
+        assert_eq!(blocked("rm -r /tmp/cache"), None);
+        assert_eq!(blocked("echo 'rm -rf /' > notes.txt"), None);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
     #[test]
     fn test_config_load() {
-        let _ = ShellmindConfig {
-            api_key: "test".to_string(),
-            model_name: "gemini-pro".to_string(),
-            temperature: 0.2,
-            context_window_size: 8,
-            api_type: ApiType::Rest,
-            grpc_endpoint: "https://generativelanguage.googleapis.com".to_string(),
-        };
+        let config = ConfigManager::defaults().unwrap();
+        assert_eq!(config.model_name, "gemini-1.5-flash");
+        assert_eq!(config.temperature, 0.2);
+        assert_eq!(config.context_window_size, 8);
+        assert!(matches!(config.api_type, ApiType::Rest));
+        assert_eq!(config.grpc_endpoint, "https://generativelanguage.googleapis.com");
     }
 }
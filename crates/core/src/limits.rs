@@ -0,0 +1,163 @@
+//! Per-tool resource limits (`[tools.limits.<tool>]`): a timeout, a memory cap for the
+//! processes a tool spawns and a cap on the files it may touch. `LimitedTool` wraps a
+//! tool's `execute` to enforce them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::progress::ProgressSender;
+use crate::{BaseTool, ConfirmationDetails, SafetyLevel, ShellmindError, ToolResult};
+
+/// Entry whose limits apply to tools without their own, and fill in unset fields.
+pub const DEFAULT_ENTRY: &str = "default";
+
+/// Limits for one tool. Unset fields fall back to the `default` entry; zero disables a
+/// limit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Virtual memory per spawned process, applied with `ulimit -v`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+    /// Files the tool may read or change in one call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_files: Option<usize>,
+}
+
+impl ToolLimits {
+    fn or(self, fallback: &ToolLimits) -> ToolLimits {
+        ToolLimits {
+            timeout_secs: self.timeout_secs.or(fallback.timeout_secs),
+            max_memory_mb: self.max_memory_mb.or(fallback.max_memory_mb),
+            max_files: self.max_files.or(fallback.max_files),
+        }
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.filter(|secs| *secs > 0).map(Duration::from_secs)
+    }
+
+    fn memory_kib(&self) -> Option<u64> {
+        self.max_memory_mb.filter(|mb| *mb > 0).map(|mb| mb * 1024)
+    }
+
+    fn file_limit(&self) -> Option<usize> {
+        self.max_files.filter(|n| *n > 0)
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.timeout().is_none() && self.memory_kib().is_none() && self.file_limit().is_none()
+    }
+}
+
+/// The `[tools]` section of the configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    #[serde(default)]
+    pub limits: HashMap<String, ToolLimits>,
+}
+
+impl ToolsConfig {
+    /// Limits for `tool`: its own entry over the `default` one.
+    pub fn limits_for(&self, tool: &str) -> ToolLimits {
+        let default = self.limits.get(DEFAULT_ENTRY).cloned().unwrap_or_default();
+        match self.limits.get(tool) {
+            Some(limits) => limits.clone().or(&default),
+            None => default,
+        }
+    }
+}
+
+tokio::task_local! {
+    static CURRENT: ToolLimits;
+}
+
+fn current() -> ToolLimits {
+    CURRENT.try_with(ToolLimits::clone).unwrap_or_default()
+}
+
+/// Prefixes a `sh -c` command with the running tool's memory cap.
+pub fn limit_shell_command(command: &str) -> String {
+    match current().memory_kib() {
+        Some(kib) => format!("ulimit -v {} 2>/dev/null; {}", kib, command),
+        None => command.to_string(),
+    }
+}
+
+/// Fails when the running tool is about to touch more files than it may.
+pub fn check_file_count(count: usize) -> Result<(), ShellmindError> {
+    match current().file_limit() {
+        Some(limit) if count > limit => Err(ShellmindError::Other(format!(
+            "The tool would touch {} files, more than its limit of {} (tools.limits max_files)",
+            count, limit
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// A tool whose calls are held to `limits`.
+pub struct LimitedTool {
+    inner: Box<dyn BaseTool>,
+    limits: ToolLimits,
+}
+
+impl LimitedTool {
+    pub fn new(inner: Box<dyn BaseTool>, limits: ToolLimits) -> Self {
+        LimitedTool { inner, limits }
+    }
+}
+
+impl BaseTool for LimitedTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.inner.display_name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn parameter_schema(&self) -> serde_json::Value {
+        self.inner.parameter_schema()
+    }
+
+    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
+        self.inner.validate_tool_params(params)
+    }
+
+    fn get_description(&self, params: &serde_json::Value) -> String {
+        self.inner.get_description(params)
+    }
+
+    fn should_confirm_execute(&self, params: &serde_json::Value) -> Option<ConfirmationDetails> {
+        self.inner.should_confirm_execute(params)
+    }
+
+    fn safety_level(&self, params: &serde_json::Value) -> SafetyLevel {
+        self.inner.safety_level(params)
+    }
+
+    fn execute(&self, params: serde_json::Value, signal: Option<tokio::signal::unix::Signal>, progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let name = self.inner.name();
+        let limits = self.limits.clone();
+        // Tools that take a list of paths are checked before anything runs
+        let listed = params.get("paths").and_then(|p| p.as_array()).map_or(0, Vec::len);
+        let call = self.inner.execute(params, signal, progress);
+        Box::pin(CURRENT.scope(limits.clone(), async move {
+            check_file_count(listed)?;
+            match limits.timeout() {
+                Some(timeout) => tokio::time::timeout(timeout, call).await.map_err(|_| {
+                    ShellmindError::Other(format!("{} timed out after {}s (tools.limits timeout_secs)", name, timeout.as_secs()))
+                })?,
+                None => call.await,
+            }
+        }))
+    }
+}
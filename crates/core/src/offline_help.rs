@@ -0,0 +1,317 @@
+//! A compact offline database of common command snippets in tldr-pages format, bundled
+//! with the binary and extended by pages in `~/.shellmind/tldr/*.md`. With `local_first`
+//! on, short requests like "extract tar.gz" are answered from it without an API call;
+//! anything it can't match clearly still goes to the model.
+//!
+//! The bundled pages are adapted from tldr-pages (https://github.com/tldr-pages/tldr),
+//! © the tldr-pages contributors, licensed under CC BY 4.0.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const BUNDLED: &str = include_str!("../data/commands.md");
+
+/// Requests with more keywords than this are left to the model.
+const MAX_KEYWORDS: usize = 6;
+
+/// Share of a request's keywords a snippet must cover to answer it locally.
+const MIN_SCORE: f64 = 0.75;
+
+/// How far the best snippet must lead the next one to count as a clear match.
+const MIN_LEAD: f64 = 0.1;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "the", "to", "of", "in", "into", "on", "for", "from", "with", "by", "at", "my", "me", "i", "it", "this", "that",
+    "all", "how", "do", "can", "you", "please", "what", "which", "is", "are", "current", "file", "files", "and", "or",
+];
+
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    /// The page the snippet is from, e.g. `tar`.
+    pub page: String,
+    pub description: String,
+    /// The command, with `{{placeholders}}` for the parts to fill in.
+    pub template: String,
+}
+
+impl Snippet {
+    /// Whether the template still has parts to fill in.
+    pub fn has_placeholders(&self) -> bool {
+        self.template.contains("{{")
+    }
+
+    fn keywords(&self) -> Vec<String> {
+        let literal = strip_placeholders(&self.template);
+        let mut words = keywords(&format!("{} {} {}", self.page, self.description, literal));
+        words.dedup();
+        words
+    }
+}
+
+/// Parses tldr pages: `# name` headings, `- description:` lines and `` `command` `` lines.
+pub fn parse(text: &str) -> Vec<Snippet> {
+    let mut snippets = Vec::new();
+    let mut page = String::new();
+    let mut description: Option<String> = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("# ") {
+            page = name.trim().to_string();
+        } else if let Some(text) = line.strip_prefix("- ") {
+            description = Some(text.trim_end_matches(':').trim().to_string());
+        } else if line.len() > 2 && line.starts_with('`') && line.ends_with('`') {
+            if let Some(description) = description.take() {
+                snippets.push(Snippet { page: page.clone(), description, template: line[1..line.len() - 1].to_string() });
+            }
+        }
+    }
+    snippets
+}
+
+fn user_pages_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".shellmind").join("tldr"))
+}
+
+/// The bundled snippets followed by the user's own pages.
+pub fn snippets() -> &'static [Snippet] {
+    static SNIPPETS: OnceLock<Vec<Snippet>> = OnceLock::new();
+    SNIPPETS.get_or_init(|| {
+        let mut snippets = parse(BUNDLED);
+        if let Some(entries) = user_pages_dir().and_then(|dir| std::fs::read_dir(dir).ok()) {
+            let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.extension().map_or(false, |e| e == "md")).collect();
+            paths.sort();
+            for path in paths {
+                if let Ok(text) = std::fs::read_to_string(&path) {
+                    snippets.extend(parse(&text));
+                }
+            }
+        }
+        snippets
+    })
+}
+
+fn strip_placeholders(template: &str) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find("}}") {
+            Some(end) => rest = &rest[start + end + 2..],
+            None => return out,
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Lowercased words without stop words; `tar.gz` also yields `tar` and `gz`.
+fn keywords(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for word in text.to_lowercase().split(|c: char| !(c.is_alphanumeric() || c == '.' || c == '-')) {
+        let word = word.trim_matches(|c: char| c == '.' || c == '-');
+        if word.is_empty() || STOP_WORDS.contains(&word) {
+            continue;
+        }
+        words.push(word.to_string());
+        if word.contains('.') {
+            words.extend(word.split('.').filter(|w| !w.is_empty() && !STOP_WORDS.contains(w)).map(str::to_string));
+        }
+    }
+    words
+}
+
+/// Share of `query` words found among the snippet's words, allowing small typos and
+/// plural/-ing forms.
+fn score(query: &[String], snippet: &[String]) -> f64 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    let found = query
+        .iter()
+        .filter(|word| {
+            snippet.iter().any(|candidate| {
+                candidate == *word
+                    || (word.len() >= 4 && candidate.len() >= 4 && (candidate.starts_with(&word[..4]) && word.starts_with(&candidate[..4])))
+                    || crate::fuzzy::similarity(word, candidate) >= 0.85
+            })
+        })
+        .count();
+    found as f64 / query.len() as f64
+}
+
+/// The snippets best matching `query`, best first.
+pub fn search(query: &str, limit: usize) -> Vec<&'static Snippet> {
+    let words = keywords(query);
+    let mut scored: Vec<(f64, &Snippet)> = snippets().iter().map(|s| (score(&words, &s.keywords()), s)).filter(|(score, _)| *score > 0.0).collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    scored.into_iter().take(limit).map(|(_, s)| s).collect()
+}
+
+/// Words of the request that look like arguments: paths, file names, hosts and numbers.
+fn arguments(request: &str) -> Vec<String> {
+    request
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| c == '"' || c == '\'' || c == ',' || c == '?'))
+        .filter(|w| w.contains('/') || w.chars().all(|c| c.is_ascii_digit()) || (w.contains('.') && !w.starts_with('.') && w.split('.').all(|p| !p.is_empty()) && !is_extension_name(w)))
+        .map(str::to_string)
+        .collect()
+}
+
+/// `tar.gz` names a format rather than a file.
+fn is_extension_name(word: &str) -> bool {
+    let stem = word.split('.').next().unwrap_or_default();
+    snippets().iter().any(|s| s.page.eq_ignore_ascii_case(stem))
+}
+
+/// What a placeholder stands for, judged from its name (`path/to/file`, `10`, `host`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Role {
+    Path,
+    Url,
+    Number,
+    Host,
+    Other,
+}
+
+fn role(placeholder: &str) -> Role {
+    if placeholder.starts_with("http://") || placeholder.starts_with("https://") {
+        Role::Url
+    } else if placeholder.starts_with("path/") {
+        Role::Path
+    } else if placeholder.chars().all(|c| c.is_ascii_digit()) || matches!(placeholder, "process_id" | "port") {
+        Role::Number
+    } else if placeholder == "host" || placeholder.starts_with("example.") {
+        Role::Host
+    } else {
+        Role::Other
+    }
+}
+
+/// Whether the request word `value` can stand in for a placeholder with `role`.
+fn fits(role: Role, value: &str) -> bool {
+    let number = value.chars().all(|c| c.is_ascii_digit());
+    match role {
+        Role::Url => value.contains("://"),
+        Role::Number => number,
+        Role::Path => !number && !value.contains("://"),
+        Role::Host => !number && !value.contains('/'),
+        Role::Other => false,
+    }
+}
+
+/// `value` as a single shell word: left alone when it has nothing the shell would
+/// interpret, single-quoted otherwise.
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "._/:@%+=,-~".contains(c)) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// `template` with each placeholder replaced by the one value whose kind fits it, quoted
+/// for the shell. `None` when the counts differ or a value fits no placeholder, or more
+/// than one (like the source and target of `cp`), so nothing lands in the wrong place.
+fn fill(template: &str, values: &[String]) -> Option<String> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = template[offset..].find("{{").map(|i| i + offset) {
+        let end = template[start..].find("}}")? + start + 2;
+        spans.push((start, end));
+        offset = end;
+    }
+    if spans.is_empty() || spans.len() != values.len() {
+        return None;
+    }
+    let roles: Vec<Role> = spans.iter().map(|&(start, end)| role(&template[start + 2..end - 2])).collect();
+    let mut assigned: Vec<Option<&String>> = vec![None; spans.len()];
+    for value in values {
+        let fitting: Vec<usize> = (0..roles.len()).filter(|&i| fits(roles[i], value)).collect();
+        let [slot] = fitting[..] else { return None };
+        if assigned[slot].replace(value).is_some() {
+            return None;
+        }
+    }
+    let mut out = String::new();
+    let mut rest = 0;
+    for (&(start, end), value) in spans.iter().zip(&assigned) {
+        out.push_str(&template[rest..start]);
+        out.push_str(&shell_quote(value?));
+        rest = end;
+    }
+    out.push_str(&template[rest..]);
+    Some(out)
+}
+
+/// Answers a short, simple `request` from the database: the command itself when every
+/// placeholder can be filled from the request, otherwise the snippet to adapt. `None`
+/// when the request is too long or no snippet matches it clearly.
+pub fn answer(request: &str) -> Option<String> {
+    let words = keywords(request);
+    if words.is_empty() || words.len() > MAX_KEYWORDS || request.contains('\n') {
+        return None;
+    }
+    let mut scored: Vec<(f64, &Snippet)> = snippets().iter().map(|s| (score(&words, &s.keywords()), s)).collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    let (best_score, best) = *scored.first()?;
+    let runner_up = scored.get(1).map_or(0.0, |(score, _)| *score);
+    if best_score < MIN_SCORE || best_score - runner_up < MIN_LEAD {
+        return None;
+    }
+    if !best.has_placeholders() {
+        return Some(best.template.clone());
+    }
+    if let Some(command) = fill(&best.template, &arguments(request)) {
+        return Some(command);
+    }
+    Some(format!("{} ({}):\n{}\n\nReplace the {{{{…}}}} parts before running it.", best.description, best.page, best.template))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_keywords_drop_stop_words_and_split_extensions() {
+        assert_eq!(keywords("Extract the backup.tar.gz into /tmp"), words(&["extract", "backup.tar.gz", "backup", "tar", "gz", "tmp"]));
+    }
+
+    #[test]
+    fn test_score_counts_covered_words() {
+        let snippet = words(&["list", "running", "processes", "ps"]);
+        assert_eq!(score(&words(&["list", "process"]), &snippet), 1.0);
+        assert_eq!(score(&words(&["list", "kernel"]), &snippet), 0.5);
+        assert_eq!(score(&[], &snippet), 0.0);
+    }
+
+    #[test]
+    fn test_fill_matches_placeholder_roles() {
+        assert_eq!(fill("kill {{process_id}}", &words(&["1234"])).as_deref(), Some("kill 1234"));
+        assert_eq!(fill("ping -c {{4}} {{host}}", &words(&["example.org", "3"])).as_deref(), Some("ping -c 3 example.org"));
+        assert_eq!(fill("kill {{process_id}}", &words(&["notes.txt"])), None);
+    }
+
+    #[test]
+    fn test_fill_leaves_ambiguous_placeholders() {
+        assert_eq!(fill("cp {{path/to/source}} {{path/to/destination}}", &words(&["a.txt", "b.txt"])), None);
+        assert_eq!(fill("cat {{path/to/file}}", &words(&["a.txt", "b.txt"])), None);
+    }
+
+    #[test]
+    fn test_fill_quotes_values() {
+        assert_eq!(fill("cat {{path/to/file}}", &words(&["it's/$(reboot).txt"])).as_deref(), Some(r"cat 'it'\''s/$(reboot).txt'"));
+    }
+
+    #[test]
+    fn test_answer_fills_a_clear_match() {
+        assert_eq!(answer("list contents of tar archive big.tar").as_deref(), Some("tar tvf big.tar"));
+    }
+
+    #[test]
+    fn test_answer_leaves_long_requests_to_the_model() {
+        assert_eq!(answer("find every log older than a week, compress them and upload the archive to the backup host"), None);
+    }
+}
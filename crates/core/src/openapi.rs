@@ -0,0 +1,316 @@
+//! Generates tools from OpenAPI operations: parameters come from the spec, requests are
+//! sent over HTTP with a bearer token taken from secure storage.
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use tokio::signal::unix::Signal;
+
+use crate::progress::ProgressSender;
+use crate::{BaseTool, ConfirmationDetails, ShellmindError, ToolResult};
+
+/// An OpenAPI spec registered in config (`[[openapi_tools]]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenApiSource {
+    /// Path to the spec (JSON or YAML).
+    pub spec: String,
+    /// operationIds to expose; empty exposes every operation.
+    #[serde(default)]
+    pub operations: Vec<String>,
+    /// Secure storage key holding the bearer token.
+    #[serde(default)]
+    pub auth_secret: Option<String>,
+    /// Overrides the first `servers[].url` entry of the spec.
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ParamLocation {
+    Path,
+    Query,
+    Header,
+}
+
+#[derive(Debug, Clone)]
+struct OperationParam {
+    name: String,
+    location: ParamLocation,
+    required: bool,
+    schema: serde_json::Value,
+    description: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenApiOperation {
+    pub operation_id: String,
+    pub method: String,
+    pub path: String,
+    pub summary: String,
+    params: Vec<OperationParam>,
+    body_schema: Option<serde_json::Value>,
+}
+
+/// Parses the spec and returns its default base URL and all operations with an operationId.
+pub fn parse_spec(content: &str) -> Result<(Option<String>, Vec<OpenApiOperation>), ShellmindError> {
+    let spec: serde_json::Value = serde_yaml::from_str(content)
+        .map_err(|e| ShellmindError::Other(format!("Invalid OpenAPI spec: {}", e)))?;
+
+    let base_url = spec
+        .pointer("/servers/0/url")
+        .and_then(|u| u.as_str())
+        .map(|u| u.trim_end_matches('/').to_string());
+
+    let mut operations = Vec::new();
+    let paths = spec.get("paths").and_then(|p| p.as_object()).cloned().unwrap_or_default();
+    for (path, item) in paths {
+        let shared_params = item.get("parameters").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+        for method in ["get", "post", "put", "patch", "delete"] {
+            let Some(operation) = item.get(method) else { continue };
+            let Some(operation_id) = operation.get("operationId").and_then(|o| o.as_str()) else { continue };
+
+            let own_params = operation.get("parameters").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+            let params = shared_params
+                .iter()
+                .chain(own_params.iter())
+                .filter_map(parse_param)
+                .collect();
+            let body_schema = operation
+                .pointer("/requestBody/content/application~1json/schema")
+                .cloned();
+            let summary = operation
+                .get("summary")
+                .or_else(|| operation.get("description"))
+                .and_then(|s| s.as_str())
+                .unwrap_or("")
+                .to_string();
+
+            operations.push(OpenApiOperation {
+                operation_id: operation_id.to_string(),
+                method: method.to_uppercase(),
+                path: path.clone(),
+                summary,
+                params,
+                body_schema,
+            });
+        }
+    }
+    Ok((base_url, operations))
+}
+
+fn parse_param(param: &serde_json::Value) -> Option<OperationParam> {
+    let location = match param.get("in")?.as_str()? {
+        "path" => ParamLocation::Path,
+        "query" => ParamLocation::Query,
+        "header" => ParamLocation::Header,
+        _ => return None,
+    };
+    Some(OperationParam {
+        name: param.get("name")?.as_str()?.to_string(),
+        required: location == ParamLocation::Path || param.get("required").and_then(|r| r.as_bool()).unwrap_or(false),
+        location,
+        schema: param.get("schema").cloned().unwrap_or_else(|| serde_json::json!({ "type": "string" })),
+        description: param.get("description").and_then(|d| d.as_str()).map(str::to_string),
+    })
+}
+
+/// Loads the spec of `source` and builds a tool for each selected operation.
+impl OpenApiSource {
+    /// Namespace of the spec's tools, from its file name (`specs/github.yaml` gives `github`).
+    pub fn namespace(&self) -> String {
+        let stem = std::path::Path::new(&self.spec).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        crate::tool_source::namespace_from(&stem)
+    }
+}
+
+pub fn load_openapi_tools(source: &OpenApiSource) -> Result<Vec<OpenApiTool>, ShellmindError> {
+    let content = std::fs::read_to_string(&source.spec)
+        .map_err(|e| ShellmindError::Other(format!("Failed to read OpenAPI spec '{}': {}", source.spec, e)))?;
+    let (spec_base_url, operations) = parse_spec(&content)?;
+    let base_url = source
+        .base_url
+        .clone()
+        .or(spec_base_url)
+        .ok_or_else(|| ShellmindError::Other(format!("OpenAPI spec '{}' has no servers entry; set base_url", source.spec)))?;
+
+    Ok(operations
+        .into_iter()
+        .filter(|op| source.operations.is_empty() || source.operations.contains(&op.operation_id))
+        .map(|op| OpenApiTool::new(op, base_url.clone(), source.auth_secret.clone()))
+        .collect())
+}
+
+pub struct OpenApiTool {
+    name: &'static str,
+    description: &'static str,
+    operation: OpenApiOperation,
+    base_url: String,
+    auth_secret: Option<String>,
+}
+
+impl OpenApiTool {
+    pub fn new(operation: OpenApiOperation, base_url: String, auth_secret: Option<String>) -> Self {
+        // Tool names must be identifiers for the tool-call syntax
+        let name: String = operation
+            .operation_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect();
+        let description = format!("{} ({} {})", operation.summary, operation.method, operation.path);
+        OpenApiTool {
+            name: crate::tool_source::intern(name),
+            description: crate::tool_source::intern(description),
+            operation,
+            base_url,
+            auth_secret,
+        }
+    }
+
+    fn build_url(&self, params: &serde_json::Value) -> Result<String, ShellmindError> {
+        let mut path = self.operation.path.clone();
+        for param in self.operation.params.iter().filter(|p| p.location == ParamLocation::Path) {
+            let value = param_value(params, &param.name).ok_or_else(|| {
+                ShellmindError::Other(format!("Missing path parameter '{}' for {}", param.name, self.name))
+            })?;
+            path = path.replace(&format!("{{{}}}", param.name), &encode_path_segment(&value));
+        }
+        let mut url = url::Url::parse(&format!("{}{}", self.base_url, path))
+            .map_err(|e| ShellmindError::Other(format!("Invalid URL for {}: {}", self.name, e)))?;
+        for param in self.operation.params.iter().filter(|p| p.location == ParamLocation::Query) {
+            if let Some(value) = param_value(params, &param.name) {
+                url.query_pairs_mut().append_pair(&param.name, &value);
+            }
+        }
+        Ok(url.to_string())
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, so a value can't add path
+/// segments, a query or a fragment.
+fn encode_path_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn param_value(params: &serde_json::Value, name: &str) -> Option<String> {
+    match params.get(name)? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+impl BaseTool for OpenApiTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn parameter_schema(&self) -> serde_json::Value {
+        let mut properties = serde_json::Map::new();
+        let mut required = Vec::new();
+        for param in &self.operation.params {
+            let mut schema = param.schema.clone();
+            if let (Some(description), Some(obj)) = (&param.description, schema.as_object_mut()) {
+                obj.insert("description".to_string(), serde_json::json!(description));
+            }
+            properties.insert(param.name.clone(), schema);
+            if param.required {
+                required.push(param.name.clone());
+            }
+        }
+        if let Some(body) = &self.operation.body_schema {
+            properties.insert("body".to_string(), body.clone());
+            required.push("body".to_string());
+        }
+        serde_json::json!({ "type": "object", "properties": properties, "required": required })
+    }
+
+    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
+        params.is_object()
+            && self.operation.params.iter().filter(|p| p.required).all(|p| params.get(&p.name).is_some())
+            && (self.operation.body_schema.is_none() || params.get("body").is_some())
+    }
+
+    fn get_description(&self, params: &serde_json::Value) -> String {
+        match self.build_url(params) {
+            Ok(url) => format!("{} {}", self.operation.method, url),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn should_confirm_execute(&self, params: &serde_json::Value) -> Option<ConfirmationDetails> {
+        if self.operation.method == "GET" {
+            return None; // Reads through a vetted API are generally safe
+        }
+        Some(ConfirmationDetails {
+            message: format!("This will call {}. Are you sure?", self.get_description(params)),
+        })
+    }
+
+    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let url = self.build_url(&params);
+        let method = self.operation.method.clone();
+        let headers: Vec<(String, String)> = self
+            .operation
+            .params
+            .iter()
+            .filter(|p| p.location == ParamLocation::Header)
+            .filter_map(|p| param_value(&params, &p.name).map(|v| (p.name.clone(), v)))
+            .collect();
+        let body = params.get("body").cloned();
+        let auth_secret = self.auth_secret.clone();
+
+        Box::pin(async move {
+            let url = url?;
+            let method = reqwest::Method::from_bytes(method.as_bytes())
+                .map_err(|e| ShellmindError::Other(format!("Invalid HTTP method: {}", e)))?;
+
+            let mut request = crate::http_client().request(method, &url);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            if let Some(key) = auth_secret {
+                let token = extensions::secure_storage::retrieve_secret(&key).map_err(ShellmindError::Other)?;
+                request = request.bearer_auth(token);
+            }
+            if let Some(body) = body {
+                request = request.json(&body);
+            }
+
+            let response = request.send().await?;
+            let status = response.status();
+            let text = response.text().await?;
+            let content = serde_json::from_str(&text).unwrap_or_else(|_| serde_json::Value::String(text.clone()));
+            if status.is_success() {
+                Ok(ToolResult::success(text).with_content(content))
+            } else {
+                Ok(ToolResult::error(format!("Request failed with status {}: {}", status, text)).with_content(content))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_values_are_percent_encoded() {
+        assert_eq!(encode_path_segment("PROJ-1"), "PROJ-1");
+        assert_eq!(encode_path_segment("../admin?x=1#y"), "..%2Fadmin%3Fx%3D1%23y");
+        assert_eq!(encode_path_segment("a b/ü"), "a%20b%2F%C3%BC");
+    }
+}
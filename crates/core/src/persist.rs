@@ -0,0 +1,117 @@
+//! Safe persistence for state shared between Shellmind instances: advisory locks and
+//! atomic write-replace.
+
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{crypto, ShellmindError};
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Runs `f` while holding an exclusive advisory lock on `<path>.lock`. Other Shellmind
+/// instances block until the lock is released.
+pub fn with_lock<T>(path: &Path, f: impl FnOnce() -> Result<T, ShellmindError>) -> Result<T, ShellmindError> {
+    let lock_file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(lock_path(path))
+        .map_err(|e| ShellmindError::Other(format!("Failed to open lock file for '{}': {}", path.display(), e)))?;
+    lock_file
+        .lock_exclusive()
+        .map_err(|e| ShellmindError::Other(format!("Failed to lock '{}': {}", path.display(), e)))?;
+    let result = f();
+    let _ = lock_file.unlock();
+    result
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it into place, so
+/// readers never observe a partially written file. The file keeps the mode of the one it
+/// replaces; new files are readable only by the owner (0600).
+pub fn atomic_write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), ShellmindError> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+
+    let write = || -> std::io::Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            let mode = std::fs::metadata(path).map_or(0o600, |m| m.permissions().mode() & 0o7777);
+            options.mode(mode);
+        }
+        let mut file = options.open(&tmp_path)?;
+        // The umask may have narrowed the mode given to `open`
+        #[cfg(unix)]
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    };
+    write().map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        ShellmindError::Other(format!("Failed to write '{}': {}", path.display(), e))
+    })
+}
+
+/// Locked atomic write for state that may be written by several instances.
+pub fn locked_write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), ShellmindError> {
+    with_lock(path, || atomic_write(path, contents))
+}
+
+/// Prepares private state (sessions, history, logs) for writing, encrypting it when
+/// `encrypt_storage` is enabled.
+pub fn seal(contents: impl AsRef<[u8]>) -> Result<Vec<u8>, ShellmindError> {
+    if crypto::is_enabled() {
+        crypto::encrypt(contents.as_ref())
+    } else {
+        Ok(contents.as_ref().to_vec())
+    }
+}
+
+/// Reads private state written with `seal`, decrypting it if needed. Plaintext files are
+/// still readable after encryption is turned on (and vice versa, while the key exists).
+pub fn read_private(path: &Path) -> Result<String, ShellmindError> {
+    let data = std::fs::read(path)
+        .map_err(|e| ShellmindError::Other(format!("Failed to read '{}': {}", path.display(), e)))?;
+    let plaintext = crypto::decrypt(&data)?;
+    String::from_utf8(plaintext)
+        .map_err(|e| ShellmindError::Other(format!("'{}' is not valid UTF-8: {}", path.display(), e)))
+}
+
+/// Reads a JSON Lines log written with `append_jsonl`; a missing log is empty. Lines that
+/// don't parse are skipped rather than losing the whole log.
+pub fn read_jsonl<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, ShellmindError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(read_private(path)?.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+/// Appends `entry` to the JSON Lines log at `path` under its lock, keeping only the newest
+/// `max_entries`. The log is sealed like other private state.
+pub fn append_jsonl<T: Serialize + DeserializeOwned>(path: &Path, entry: T, max_entries: usize) -> Result<(), ShellmindError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", dir.display(), e)))?;
+    }
+    with_lock(path, || {
+        let mut entries: Vec<T> = read_jsonl(path)?;
+        entries.push(entry);
+        if entries.len() > max_entries {
+            entries.drain(..entries.len() - max_entries);
+        }
+        let lines = entries.iter().map(serde_json::to_string).collect::<Result<Vec<_>, _>>()?;
+        atomic_write(path, seal(lines.join("\n"))?)
+    })
+}
@@ -0,0 +1,103 @@
+//! Splitting a reply that holds several shell commands into the steps of a plan, so
+//! they can be confirmed and run one at a time.
+
+use std::path::Path;
+
+/// Builtins accepted as the first word of a step in addition to programs on `PATH`.
+const BUILTINS: &[&str] = &["cd", "export", "unset", "source", ".", "echo", "printf", "test", "[", "set", "alias", "exit"];
+
+/// Splits `command` on newlines and top-level `&&`. Returns `None` unless it yields at
+/// least two steps that all look like commands, so prose and single commands keep their
+/// current handling.
+pub fn split_plan(command: &str) -> Option<Vec<String>> {
+    // Heredocs span lines that are not commands of their own
+    if command.contains("<<") {
+        return None;
+    }
+    let mut steps = Vec::new();
+    let mut pending = String::new();
+    for line in command.lines() {
+        let line = line.trim();
+        if line.starts_with("```") || line.starts_with('#') || (line.is_empty() && pending.is_empty()) {
+            continue;
+        }
+        let line = line.strip_prefix("$ ").unwrap_or(line);
+        // A trailing backslash continues the command on the next line
+        if let Some(continued) = line.strip_suffix('\\') {
+            pending.push_str(continued);
+            pending.push(' ');
+            continue;
+        }
+        pending.push_str(line);
+        steps.extend(split_and(&std::mem::take(&mut pending)));
+    }
+    if !pending.trim().is_empty() {
+        steps.extend(split_and(&pending));
+    }
+    (steps.len() >= 2 && steps.iter().all(|s| looks_like_command(s))).then_some(steps)
+}
+
+/// Splits on `&&` outside quotes.
+fn split_and(line: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', _) => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                continue;
+            }
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('&', None) if chars.peek() == Some(&'&') => {
+                chars.next();
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts.into_iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect()
+}
+
+/// True when the first word, after `VAR=value` assignments and `sudo`, is a builtin, an
+/// existing path or a program on `PATH`.
+fn looks_like_command(step: &str) -> bool {
+    let Some(program) = step
+        .split_whitespace()
+        .find(|word| !(word.contains('=') && !word.starts_with('=')) && *word != "sudo")
+    else {
+        return false;
+    };
+    if BUILTINS.contains(&program) {
+        return true;
+    }
+    extensions::which::find_in_path(program).is_some()
+}
+
+/// The directory a `cd <dir>` step changes to, relative to `current`. `None` for other steps.
+pub fn cd_target(step: &str, current: &Path) -> Option<std::path::PathBuf> {
+    // `cd a; ls` is left to the shell
+    if step.contains(|c| c == ';' || c == '|' || c == '&') {
+        return None;
+    }
+    let mut words = step.split_whitespace();
+    if words.next()? != "cd" {
+        return None;
+    }
+    let target = match words.next() {
+        None | Some("~") => dirs::home_dir()?,
+        Some(dir) => match dir.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()?.join(rest),
+            None => current.join(dir.trim_matches(|c| c == '"' || c == '\'')),
+        },
+    };
+    words.next().is_none().then_some(target)
+}
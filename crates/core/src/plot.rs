@@ -0,0 +1,409 @@
+//! `plot`: charts numeric series in the terminal, as braille line charts or block bar
+//! charts, and optionally as a PNG through plotters (the `plot-png` feature), so "plot
+//! response times from this log" shows an actual chart. Series come from the model or
+//! from columns of a CSV file.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use tokio::signal::unix::Signal;
+
+use crate::progress::ProgressSender;
+use crate::{ConfirmationDetails, ShellmindError, ToolResult, TypedTool};
+
+/// Chart size in terminal cells; a braille cell holds 2x4 dots.
+const WIDTH: usize = 60;
+const HEIGHT: usize = 12;
+
+/// Longest bar of a bar chart, in cells.
+const BAR_WIDTH: usize = 50;
+
+/// Bars shown per series before the rest are left out.
+const MAX_BARS: usize = 40;
+
+/// Points accepted per series.
+const MAX_POINTS: usize = 100_000;
+
+/// Braille dot bits by row and column within a cell.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+const EIGHTHS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Line,
+    Bar,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct Series {
+    /// What the values are, e.g. `response time (ms)`.
+    #[serde(default)]
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub title: Option<String>,
+    pub kind: Kind,
+    pub series: Vec<Series>,
+    /// Labels of the points, e.g. timestamps; may be empty.
+    pub labels: Vec<String>,
+}
+
+/// `value` with at most two decimals and no trailing zeros.
+fn format_number(value: f64) -> String {
+    if value.abs() >= 1000.0 || value.fract() == 0.0 {
+        return format!("{:.0}", value);
+    }
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn range(values: &[f64]) -> (f64, f64) {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    (min, max)
+}
+
+/// Sets the braille dots on the line from `from` to `to` (dot coordinates).
+fn draw_line(cells: &mut [Vec<u32>], from: (i64, i64), to: (i64, i64)) {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    loop {
+        cells[y as usize / 4][x as usize / 2] |= BRAILLE_DOTS[y as usize % 4][x as usize % 2];
+        if (x, y) == to {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+fn line_chart(series: &Series, labels: &[String]) -> String {
+    let (min, max) = range(&series.values);
+    let (columns, rows) = (WIDTH as i64 * 2, HEIGHT as i64 * 4);
+    let last = series.values.len().saturating_sub(1).max(1) as f64;
+    let point = |index: usize, value: f64| {
+        let x = (index as f64 / last * (columns - 1) as f64).round() as i64;
+        let share = if max > min { (value - min) / (max - min) } else { 0.5 };
+        (x, rows - 1 - (share * (rows - 1) as f64).round() as i64)
+    };
+    let mut cells = vec![vec![0u32; WIDTH]; HEIGHT];
+    let mut previous = None;
+    for (index, value) in series.values.iter().enumerate() {
+        let current = point(index, *value);
+        draw_line(&mut cells, previous.unwrap_or(current), current);
+        previous = Some(current);
+    }
+
+    let axis = [format_number(max), format_number((min + max) / 2.0), format_number(min)];
+    let margin = axis.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let mut chart = String::new();
+    for (row, cells) in cells.iter().enumerate() {
+        let label = match row {
+            0 => axis[0].as_str(),
+            r if r == HEIGHT / 2 => axis[1].as_str(),
+            r if r == HEIGHT - 1 => axis[2].as_str(),
+            _ => "",
+        };
+        let tick = if label.is_empty() { '│' } else { '┤' };
+        let dots: String = cells.iter().map(|bits| char::from_u32(0x2800 + bits).unwrap_or(' ')).collect();
+        chart.push_str(&format!("{:>margin$} {}{}\n", label, tick, dots.trim_end_matches('\u{2800}'), margin = margin));
+    }
+    chart.push_str(&format!("{:>margin$} └{}\n", "", "─".repeat(WIDTH), margin = margin));
+    let first = labels.first().cloned().unwrap_or_else(|| "0".to_string());
+    let end = labels.get(series.values.len() - 1).cloned().unwrap_or_else(|| (series.values.len() - 1).to_string());
+    let gap = (WIDTH + 1).saturating_sub(first.chars().count() + end.chars().count()).max(1);
+    chart.push_str(&format!("{:>margin$}  {}{}{}", "", first, " ".repeat(gap), end, margin = margin));
+    chart
+}
+
+fn bar_chart(series: &Series, labels: &[String]) -> String {
+    let shown = &series.values[..series.values.len().min(MAX_BARS)];
+    let names: Vec<String> = (0..shown.len()).map(|i| labels.get(i).cloned().unwrap_or_else(|| (i + 1).to_string())).collect();
+    let margin = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    let peak = shown.iter().copied().fold(0.0, f64::max);
+    let mut chart = String::new();
+    for (name, value) in names.iter().zip(shown) {
+        let eighths = if peak > 0.0 { (value.max(0.0) / peak * (BAR_WIDTH * 8) as f64).round() as usize } else { 0 };
+        let mut bar = "█".repeat(eighths / 8);
+        if eighths % 8 > 0 {
+            bar.push(EIGHTHS[eighths % 8 - 1]);
+        }
+        chart.push_str(&format!("{:>margin$} │{} {}\n", name, bar, format_number(*value), margin = margin));
+    }
+    if series.values.len() > shown.len() {
+        chart.push_str(&format!("… {} more values not shown\n", series.values.len() - shown.len()));
+    }
+    chart.trim_end().to_string()
+}
+
+impl Chart {
+    /// The chart as text, one panel per series.
+    pub fn render(&self) -> String {
+        let mut panels = Vec::new();
+        if let Some(title) = &self.title {
+            panels.push(title.clone());
+        }
+        for series in self.series.iter().filter(|s| !s.values.is_empty()) {
+            let mut panel = String::new();
+            if self.series.len() > 1 && !series.name.is_empty() {
+                panel.push_str(&format!("{}\n", series.name));
+            }
+            panel.push_str(&match self.kind {
+                Kind::Line => line_chart(series, &self.labels),
+                Kind::Bar => bar_chart(series, &self.labels),
+            });
+            panels.push(panel);
+        }
+        panels.join("\n\n")
+    }
+
+    /// Count, min, max, mean and percentiles of every series, for the model to describe.
+    pub fn summary(&self) -> serde_json::Value {
+        let series: Vec<serde_json::Value> = self
+            .series
+            .iter()
+            .filter(|s| !s.values.is_empty())
+            .map(|s| {
+                let mut sorted = s.values.clone();
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                let percentile = |share: f64| sorted[((sorted.len() - 1) as f64 * share).round() as usize];
+                serde_json::json!({
+                    "name": s.name,
+                    "count": sorted.len(),
+                    "min": sorted[0],
+                    "max": sorted[sorted.len() - 1],
+                    "mean": sorted.iter().sum::<f64>() / sorted.len() as f64,
+                    "p50": percentile(0.5),
+                    "p95": percentile(0.95),
+                })
+            })
+            .collect();
+        serde_json::json!({ "title": self.title, "series": series })
+    }
+
+    /// Writes the chart as a 960x540 PNG to `path`.
+    #[cfg(feature = "plot-png")]
+    pub fn write_png(&self, path: &Path) -> Result<(), ShellmindError> {
+        use plotters::prelude::*;
+
+        let draw_error = |e: &dyn std::fmt::Display| ShellmindError::Other(format!("Failed to draw the chart: {}", e));
+        let values: Vec<f64> = self.series.iter().flat_map(|s| s.values.iter().copied()).collect();
+        let (mut min, mut max) = range(&values);
+        if self.kind == Kind::Bar {
+            min = min.min(0.0);
+        }
+        if max <= min {
+            max = min + 1.0;
+        }
+        let points = self.series.iter().map(|s| s.values.len()).max().unwrap_or(1);
+
+        let root = BitMapBackend::new(path, (960, 540)).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| draw_error(&e))?;
+        let mut builder = ChartBuilder::on(&root);
+        builder.margin(16).x_label_area_size(32).y_label_area_size(64);
+        if let Some(title) = &self.title {
+            builder.caption(title, ("sans-serif", 22));
+        }
+        let mut chart = builder.build_cartesian_2d(-0.5..points as f64 - 0.5, min..max).map_err(|e| draw_error(&e))?;
+        let labels = &self.labels;
+        chart
+            .configure_mesh()
+            .x_label_formatter(&|x| labels.get(x.round().max(0.0) as usize).cloned().unwrap_or_else(|| format_number(*x)))
+            .draw()
+            .map_err(|e| draw_error(&e))?;
+        let count = self.series.len().max(1) as f64;
+        for (index, series) in self.series.iter().enumerate() {
+            let color = Palette99::pick(index).to_rgba();
+            let drawn = match self.kind {
+                Kind::Line => chart.draw_series(LineSeries::new(series.values.iter().enumerate().map(|(i, v)| (i as f64, *v)), color.stroke_width(2))),
+                Kind::Bar => {
+                    // Series share each slot side by side
+                    let width = 0.8 / count;
+                    let offset = -0.4 + width * index as f64;
+                    chart.draw_series(series.values.iter().enumerate().map(|(i, v)| {
+                        Rectangle::new([(i as f64 + offset, min.max(0.0).min(max)), (i as f64 + offset + width, *v)], color.filled())
+                    }))
+                }
+            }
+            .map_err(|e| draw_error(&e))?;
+            drawn.label(series.name.clone()).legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 16, y + 5)], color.filled()));
+        }
+        if self.series.len() > 1 {
+            chart.configure_series_labels().background_style(WHITE.mix(0.8)).border_style(BLACK).draw().map_err(|e| draw_error(&e))?;
+        }
+        root.present().map_err(|e| draw_error(&e))
+    }
+
+    #[cfg(not(feature = "plot-png"))]
+    pub fn write_png(&self, _path: &Path) -> Result<(), ShellmindError> {
+        Err(ShellmindError::Other("PNG charts need a build with the `plot-png` feature".to_string()))
+    }
+}
+
+fn parse_number(field: &str) -> Option<f64> {
+    let field = field.trim().trim_matches('"');
+    let field = field.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%');
+    field.parse().ok()
+}
+
+/// Series from the columns of a CSV (or semicolon- or tab-separated) file: those named in
+/// `columns` by header or 0-based index, or every numeric column. A leading non-numeric
+/// column not plotted becomes the labels.
+pub fn read_csv(path: &Path, columns: &[String]) -> Result<(Vec<Series>, Vec<String>), ShellmindError> {
+    if crate::redact::active().blocks(path) {
+        return Err(ShellmindError::Blocked(format!("'{}' is {}", path.display(), crate::redact::NOTE)));
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| ShellmindError::Other(format!("Failed to read '{}': {}", path.display(), e)))?;
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let first = lines.next().ok_or_else(|| ShellmindError::Other(format!("'{}' is empty", path.display())))?;
+    let delimiter = [',', ';', '\t'].into_iter().max_by_key(|d| first.matches(*d).count()).unwrap_or(',');
+    let split = |line: &str| line.split(delimiter).map(|f| f.trim().trim_matches('"').to_string()).collect::<Vec<_>>();
+    let first_fields = split(first);
+    let has_header = first_fields.iter().any(|f| parse_number(f).is_none());
+    let header: Vec<String> = if has_header { first_fields.clone() } else { (0..first_fields.len()).map(|i| format!("column {}", i)).collect() };
+    let mut rows: Vec<Vec<String>> = lines.map(split).collect();
+    if !has_header {
+        rows.insert(0, first_fields);
+    }
+
+    let numeric = |index: usize| rows.iter().all(|row| row.get(index).map_or(true, |f| f.is_empty() || parse_number(f).is_some()));
+    let selected: Vec<usize> = if columns.is_empty() {
+        (0..header.len()).filter(|i| numeric(*i)).collect()
+    } else {
+        columns
+            .iter()
+            .map(|column| {
+                header
+                    .iter()
+                    .position(|h| h.eq_ignore_ascii_case(column))
+                    .or_else(|| column.parse().ok().filter(|i| *i < header.len()))
+                    .ok_or_else(|| ShellmindError::Other(format!("No column '{}' in '{}'; columns: {}", column, path.display(), header.join(", "))))
+            })
+            .collect::<Result<_, _>>()?
+    };
+    if selected.is_empty() {
+        return Err(ShellmindError::Other(format!("'{}' has no numeric columns", path.display())));
+    }
+    let series = selected
+        .iter()
+        .map(|index| Series {
+            name: header[*index].clone(),
+            values: rows.iter().filter_map(|row| row.get(*index).and_then(|f| parse_number(f))).collect(),
+        })
+        .collect();
+    let labels = if !selected.contains(&0) && !numeric(0) {
+        rows.iter().map(|row| row.first().cloned().unwrap_or_default()).collect()
+    } else {
+        Vec::new()
+    };
+    Ok((series, labels))
+}
+
+pub struct PlotTool;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PlotParams {
+    /// Series to plot, e.g. `[{"name": "latency (ms)", "values": [120, 98, 143]}]`.
+    #[serde(default)]
+    pub series: Vec<Series>,
+    /// A CSV file to plot columns of instead of `series`.
+    pub file: Option<String>,
+    /// Columns of `file` to plot, by header or 0-based index; every numeric column when empty.
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Labels of the points, e.g. timestamps or categories.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// `line` (default) for trends over time, `bar` to compare categories.
+    pub kind: Option<String>,
+    pub title: Option<String>,
+    /// Also writes the chart as a PNG to this path.
+    pub png: Option<String>,
+}
+
+impl TypedTool for PlotTool {
+    type Params = PlotParams;
+
+    fn name(&self) -> &'static str {
+        "plot"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Plot"
+    }
+
+    fn description(&self) -> &'static str {
+        "Charts numeric series in the terminal: a line chart for values over time or a bar chart to compare categories. \
+         Pass the numbers in `series` (e.g. timings parsed from a log) or plot columns of a CSV file with `file`. \
+         Set `png` to also save the chart as an image. Returns summary statistics of each series."
+    }
+
+    fn get_description(&self, params: &PlotParams) -> String {
+        match (&params.title, &params.file) {
+            (Some(title), _) => format!("Plot {}", title),
+            (None, Some(file)) => format!("Plot {}", file),
+            (None, None) => "Plot data".to_string(),
+        }
+    }
+
+    fn should_confirm_execute(&self, params: &PlotParams) -> Option<ConfirmationDetails> {
+        // Only an existing file is at stake
+        params
+            .png
+            .as_ref()
+            .filter(|png| Path::new(png).exists())
+            .map(|png| ConfirmationDetails { message: format!("This will overwrite '{}' with the chart. Are you sure?", png) })
+    }
+
+    fn execute(&self, params: PlotParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        Box::pin(async move {
+            let kind = match params.kind.as_deref().map(str::to_lowercase).as_deref() {
+                None | Some("line") => Kind::Line,
+                Some("bar") => Kind::Bar,
+                Some(other) => return Ok(ToolResult::error(format!("Unknown chart kind '{}'; use line or bar", other))),
+            };
+            let (series, labels) = match &params.file {
+                Some(file) => {
+                    let (series, labels) = read_csv(Path::new(file), &params.columns)?;
+                    (series, if params.labels.is_empty() { labels } else { params.labels })
+                }
+                None => (params.series, params.labels),
+            };
+            if series.iter().all(|s| s.values.is_empty()) {
+                return Ok(ToolResult::error("Nothing to plot: every series is empty"));
+            }
+            if let Some(series) = series.iter().find(|s| s.values.len() > MAX_POINTS) {
+                return Ok(ToolResult::error(format!("'{}' has {} points; at most {} can be plotted", series.name, series.values.len(), MAX_POINTS)));
+            }
+            if series.iter().any(|s| s.values.iter().any(|v| !v.is_finite())) {
+                return Ok(ToolResult::error("Values must be finite numbers"));
+            }
+
+            let chart = Chart { title: params.title, kind, series, labels };
+            let mut result = ToolResult::success(chart.render()).with_content(chart.summary());
+            if let Some(png) = params.png {
+                let path = PathBuf::from(png);
+                chart.write_png(&path)?;
+                result = result.with_artifact(path);
+            }
+            Ok(result)
+        })
+    }
+}
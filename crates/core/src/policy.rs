@@ -0,0 +1,138 @@
+//! Administrator-managed policy from `/etc/shellmind/policy.toml`. Policy values are
+//! merged over the user's config on every load and cannot be changed with `config set`.
+//!
+//! ```toml
+//! approval_mode = "always"
+//! banned_commands = ["mkfs*", "dd if=* of=/dev/*"]
+//! disabled_tools = ["web_fetch"]
+//! sandbox_backend = "firejail"
+//! prompt_suffix = "Answer only with POSIX-compliant commands."
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::{ApprovalMode, ShellmindConfig, ShellmindError};
+
+pub const SYSTEM_POLICY_PATH: &str = "/etc/shellmind/policy.toml";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Policy {
+    /// Forces the approval mode for every user.
+    #[serde(default)]
+    pub approval_mode: Option<ApprovalMode>,
+    /// Wildcard patterns (`*` matches anything) of commands that are never executed.
+    #[serde(default)]
+    pub banned_commands: Vec<String>,
+    /// Tools that are not registered.
+    #[serde(default)]
+    pub disabled_tools: Vec<String>,
+    /// Sandbox backend every command must run in.
+    #[serde(default)]
+    pub sandbox_backend: Option<String>,
+    /// Forces the text put before every user prompt.
+    #[serde(default)]
+    pub prompt_prefix: Option<String>,
+    /// Forces the text put after every user prompt.
+    #[serde(default)]
+    pub prompt_suffix: Option<String>,
+    /// The user's own values of what `enforce` overrode.
+    #[serde(skip)]
+    replaced: Replaced,
+}
+
+/// What a policy replaced in the user's config, so `restore` can put it back.
+#[derive(Debug, Clone, Default)]
+struct Replaced {
+    approval_mode: Option<ApprovalMode>,
+    sandbox_backend: Option<String>,
+    prompt_prefix: Option<String>,
+    prompt_suffix: Option<String>,
+    disabled_tools: Vec<String>,
+}
+
+impl Policy {
+    /// Loads the system policy; a missing file means no policy.
+    pub fn load() -> Result<Self, ShellmindError> {
+        Self::load_from(Path::new(SYSTEM_POLICY_PATH))
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, ShellmindError> {
+        if !path.exists() {
+            return Ok(Policy::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to read policy file '{}': {}", path.display(), e)))?;
+        toml::from_str(&content)
+            .map_err(|e| ShellmindError::Other(format!("Invalid policy file '{}': {}", path.display(), e)))
+    }
+
+    /// True when the policy overrides anything.
+    pub fn is_active(&self) -> bool {
+        self.approval_mode.is_some()
+            || self.sandbox_backend.is_some()
+            || self.prompt_prefix.is_some()
+            || self.prompt_suffix.is_some()
+            || !self.banned_commands.is_empty()
+            || !self.disabled_tools.is_empty()
+    }
+
+    /// Overrides the policy-controlled fields of `config`, remembering the user's values.
+    pub fn enforce(&mut self, config: &mut ShellmindConfig) {
+        if let Some(mode) = self.approval_mode {
+            self.replaced.approval_mode = Some(std::mem::replace(&mut config.approval_mode, mode));
+        }
+        if let Some(backend) = &self.sandbox_backend {
+            self.replaced.sandbox_backend = Some(std::mem::replace(&mut config.sandbox_backend, backend.clone()));
+        }
+        if let Some(prefix) = &self.prompt_prefix {
+            self.replaced.prompt_prefix = Some(std::mem::replace(&mut config.prompt_prefix, prefix.clone()));
+        }
+        if let Some(suffix) = &self.prompt_suffix {
+            self.replaced.prompt_suffix = Some(std::mem::replace(&mut config.prompt_suffix, suffix.clone()));
+        }
+        for tool in &self.disabled_tools {
+            if !config.disabled_tools.contains(tool) {
+                config.disabled_tools.push(tool.clone());
+                self.replaced.disabled_tools.push(tool.clone());
+            }
+        }
+    }
+
+    /// Undoes `enforce`, for writing the user's own settings anywhere.
+    pub fn restore(&self, config: &mut ShellmindConfig) {
+        let replaced = &self.replaced;
+        if let Some(mode) = replaced.approval_mode {
+            config.approval_mode = mode;
+        }
+        if let Some(backend) = &replaced.sandbox_backend {
+            config.sandbox_backend = backend.clone();
+        }
+        if let Some(prefix) = &replaced.prompt_prefix {
+            config.prompt_prefix = prefix.clone();
+        }
+        if let Some(suffix) = &replaced.prompt_suffix {
+            config.prompt_suffix = suffix.clone();
+        }
+        config.disabled_tools.retain(|tool| !replaced.disabled_tools.contains(tool));
+    }
+
+    /// Returns true when `key` is fixed by policy and must not be set by the user.
+    pub fn is_locked(&self, key: &str) -> bool {
+        match key {
+            "approval_mode" => self.approval_mode.is_some(),
+            "sandbox_backend" => self.sandbox_backend.is_some(),
+            "prompt_prefix" => self.prompt_prefix.is_some(),
+            "prompt_suffix" => self.prompt_suffix.is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any sequence of characters.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let escaped = pattern.split('*').map(regex::escape).collect::<Vec<_>>().join(".*");
+    regex::Regex::new(&format!("^{}$", escaped))
+        .map(|re| re.is_match(text))
+        .unwrap_or(false)
+}
@@ -0,0 +1,146 @@
+//! Sandboxed preview of a command: runs it with bubblewrap against a copy of the
+//! workspace, with the rest of the filesystem read-only and networking disabled, and
+//! reports the files it would create, modify or delete and the connections it attempted.
+
+use regex::Regex;
+use std::path::Path;
+use std::process::Command;
+
+use crate::changes::{Changes, WorkspaceSnapshot};
+use crate::ShellmindError;
+
+/// Workspaces larger than this are not copied; every preview copies and hashes the
+/// whole workspace twice, which must stay quick.
+const MAX_WORKSPACE_BYTES: u64 = 64 * 1024 * 1024;
+/// Seconds the previewed command may run before it is killed.
+const TIMEOUT_SECS: u32 = 60;
+
+#[derive(Debug, Clone, Default)]
+pub struct PreviewReport {
+    /// Changes in the workspace copy, relative to the workspace.
+    pub changes: Changes,
+    /// `address:port` of each connection attempted; empty when not traced.
+    pub network: Vec<String>,
+    /// False when strace was unavailable, so attempted connections are unknown.
+    pub network_traced: bool,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Size of the files under `root`, counted until it passes `limit`.
+fn workspace_size(root: &Path, limit: u64) -> u64 {
+    let mut size = 0;
+    for metadata in walkdir::WalkDir::new(root).into_iter().filter_map(|e| e.ok()).filter_map(|e| e.metadata().ok()) {
+        if metadata.is_file() {
+            size += metadata.len();
+            if size > limit {
+                break;
+            }
+        }
+    }
+    size
+}
+
+/// IP connections in an `strace -e trace=connect` log; Unix sockets are skipped.
+fn traced_connections(log: &str) -> Vec<String> {
+    let pattern = Regex::new(r#"sin6?_port=htons\((\d+)\).*?(?:inet_addr\("([^"]+)"\)|inet_pton\(AF_INET6, "([^"]+)")"#)
+        .expect("invalid connect pattern");
+    let mut connections = Vec::new();
+    for captures in pattern.captures_iter(log) {
+        let Some(address) = captures.get(2).or_else(|| captures.get(3)) else {
+            continue;
+        };
+        let connection = format!("{}:{}", address.as_str(), &captures[1]);
+        if !connections.contains(&connection) {
+            connections.push(connection);
+        }
+    }
+    connections
+}
+
+/// Runs `command` in a bubblewrap sandbox where `workspace` is replaced by a
+/// copy-on-write copy (a plain copy on filesystems without reflinks). Nothing outside
+/// the copy can be written and the network namespace has no interfaces.
+pub fn run(command: &str, workspace: &Path) -> Result<PreviewReport, ShellmindError> {
+    let bwrap = extensions::which::find_in_path("bwrap")
+        .ok_or_else(|| ShellmindError::Other("Previews need bubblewrap (bwrap); install it to use /preview".to_string()))?;
+    if workspace_size(workspace, MAX_WORKSPACE_BYTES) > MAX_WORKSPACE_BYTES {
+        return Err(ShellmindError::Other(format!(
+            "The workspace is too large to preview (over {} MiB)",
+            MAX_WORKSPACE_BYTES / 1024 / 1024
+        )));
+    }
+
+    let scratch = std::env::temp_dir().join(format!("shellmind-preview-{}", uuid::Uuid::new_v4()));
+    let copy = scratch.join("workspace");
+    std::fs::create_dir_all(&copy)
+        .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", copy.display(), e)))?;
+    crate::processes::register_scratch(&scratch);
+    let result = run_in_copy(&bwrap, command, workspace, &scratch, &copy);
+    crate::processes::release_scratch(&scratch);
+    result
+}
+
+fn run_in_copy(bwrap: &Path, command: &str, workspace: &Path, scratch: &Path, copy: &Path) -> Result<PreviewReport, ShellmindError> {
+    let status = Command::new("cp")
+        .arg("-a")
+        .arg("--reflink=auto")
+        .arg(format!("{}/.", workspace.display()))
+        .arg(copy)
+        .status()
+        .map_err(|e| ShellmindError::Other(format!("Failed to copy the workspace: {}", e)))?;
+    if !status.success() {
+        return Err(ShellmindError::Other("Failed to copy the workspace".to_string()));
+    }
+    let before = WorkspaceSnapshot::take(copy);
+
+    let workspace_arg = workspace.display().to_string();
+    let copy_arg = copy.display().to_string();
+    let timeout_arg = TIMEOUT_SECS.to_string();
+    let sandbox_args: Vec<&str> = vec![
+        "--ro-bind", "/", "/",
+        "--dev", "/dev",
+        "--proc", "/proc",
+        "--tmpfs", "/tmp",
+        "--bind", copy_arg.as_str(), workspace_arg.as_str(),
+        "--chdir", workspace_arg.as_str(),
+        "--unshare-net",
+        "--unshare-pid",
+        "--die-with-parent",
+        "timeout", timeout_arg.as_str(),
+        "sh", "-c", command,
+    ];
+
+    // strace cannot follow a setuid bwrap, so connections are only traced for the
+    // unprivileged (user namespace) build
+    #[cfg(unix)]
+    let setuid = {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(bwrap).map_or(true, |m| m.permissions().mode() & 0o4000 != 0)
+    };
+    #[cfg(not(unix))]
+    let setuid = true;
+    let strace = extensions::which::find_in_path("strace").filter(|_| !setuid);
+    let trace_log = scratch.join("connect.log");
+    let output = match &strace {
+        Some(strace) => Command::new(strace)
+            .args(["-f", "-qq", "-e", "trace=connect", "-o"])
+            .arg(&trace_log)
+            .arg(bwrap)
+            .args(&sandbox_args)
+            .output(),
+        None => Command::new(bwrap).args(&sandbox_args).output(),
+    }
+    .map_err(|e| ShellmindError::Other(format!("Failed to start the sandbox: {}", e)))?;
+
+    let changes = before.diff(&WorkspaceSnapshot::take(copy));
+    Ok(PreviewReport {
+        changes,
+        network_traced: strace.is_some(),
+        network: std::fs::read_to_string(&trace_log).map(|log| traced_connections(&log)).unwrap_or_default(),
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
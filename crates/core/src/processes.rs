@@ -0,0 +1,164 @@
+//! Shutdown cleanup: finds processes started by this Shellmind that are still running
+//! (background jobs, orphaned process groups, preview sandboxes), stops them and removes
+//! scratch directories, so nothing outlives the process unnoticed.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long processes get to exit after SIGTERM before they are killed.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+static SCRATCH_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    /// Processes that exited after SIGTERM or SIGKILL.
+    pub stopped: Vec<ProcessInfo>,
+    /// Processes that survived SIGKILL, e.g. stuck in uninterruptible sleep.
+    pub still_running: Vec<ProcessInfo>,
+    /// Scratch directories that were still present.
+    pub scratch_removed: usize,
+}
+
+/// Registers a temporary directory to remove at shutdown if it is still there.
+pub fn register_scratch(path: &Path) {
+    SCRATCH_DIRS.lock().unwrap_or_else(|e| e.into_inner()).push(path.to_path_buf());
+}
+
+/// Removes a registered scratch directory now.
+pub fn release_scratch(path: &Path) {
+    let _ = std::fs::remove_dir_all(path);
+    SCRATCH_DIRS.lock().unwrap_or_else(|e| e.into_inner()).retain(|p| p != path);
+}
+
+pub fn alive(pid: u32) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .map_or(false, |s| s.success())
+}
+
+fn signal(pids: &[u32], name: &str) {
+    if pids.is_empty() {
+        return;
+    }
+    let _ = Command::new("kill")
+        .arg(format!("-{}", name))
+        .args(pids.iter().map(u32::to_string))
+        .stderr(Stdio::null())
+        .status();
+}
+
+struct Entry {
+    ppid: u32,
+    pgid: u32,
+    command: String,
+}
+
+/// Every process visible to `ps`, except the `ps` itself.
+fn process_table() -> HashMap<u32, Entry> {
+    let mut table = HashMap::new();
+    let Ok(child) = Command::new("ps")
+        .args(["-A", "-o", "pid=", "-o", "ppid=", "-o", "pgid=", "-o", "args="])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return table;
+    };
+    let ps_pid = child.id();
+    let Ok(output) = child.wait_with_output() else {
+        return table;
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(pid), Some(ppid), Some(pgid)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let (Ok(pid), Ok(ppid), Ok(pgid)) = (pid.parse(), ppid.parse(), pgid.parse()) else {
+            continue;
+        };
+        if pid != ps_pid {
+            table.insert(pid, Entry { ppid, pgid, command: fields.collect::<Vec<_>>().join(" ") });
+        }
+    }
+    table
+}
+
+/// Processes this one started that are still running: its descendants, plus background
+/// jobs that were orphaned when their shell exited but are still in its process group.
+/// Other members of that group (e.g. the rest of a pipeline Shellmind runs in) are
+/// not children of init and are left alone.
+pub fn leftovers() -> Vec<ProcessInfo> {
+    let own = std::process::id();
+    let table = process_table();
+    let Some(own_pgid) = table.get(&own).map(|e| e.pgid) else {
+        return Vec::new();
+    };
+    let mut roots: Vec<u32> = vec![own];
+    roots.extend(table.iter().filter(|(pid, e)| **pid != own && e.ppid == 1 && e.pgid == own_pgid).map(|(pid, _)| *pid));
+
+    let mut found: HashSet<u32> = roots.iter().copied().filter(|pid| *pid != own).collect();
+    let mut queue = roots;
+    while let Some(parent) = queue.pop() {
+        for (pid, entry) in &table {
+            if entry.ppid == parent && found.insert(*pid) {
+                queue.push(*pid);
+            }
+        }
+    }
+    let mut processes: Vec<ProcessInfo> = found
+        .into_iter()
+        .map(|pid| ProcessInfo { pid, command: table[&pid].command.clone() })
+        .collect();
+    processes.sort_by_key(|p| p.pid);
+    processes
+}
+
+/// Sends SIGTERM, waits up to `grace` and sends SIGKILL to the rest. Returns the
+/// processes that are still alive afterwards.
+pub fn terminate(processes: &[ProcessInfo], grace: Duration) -> Vec<ProcessInfo> {
+    let pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
+    signal(&pids, "TERM");
+    let deadline = Instant::now() + grace;
+    let mut remaining: Vec<u32> = pids;
+    while !remaining.is_empty() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+        remaining.retain(|pid| alive(*pid));
+    }
+    if !remaining.is_empty() {
+        signal(&remaining, "KILL");
+        std::thread::sleep(Duration::from_millis(200));
+        remaining.retain(|pid| alive(*pid));
+    }
+    processes.iter().filter(|p| remaining.contains(&p.pid)).cloned().collect()
+}
+
+/// Stops leftover child processes and removes scratch directories.
+pub fn shutdown(grace: Duration) -> ShutdownReport {
+    let mut report = ShutdownReport::default();
+    let processes = leftovers();
+    report.still_running = terminate(&processes, grace);
+    report.stopped = processes
+        .into_iter()
+        .filter(|p| !report.still_running.iter().any(|s| s.pid == p.pid))
+        .collect();
+
+    let scratch_dirs = std::mem::take(&mut *SCRATCH_DIRS.lock().unwrap_or_else(|e| e.into_inner()));
+    for dir in scratch_dirs {
+        if dir.exists() && std::fs::remove_dir_all(&dir).is_ok() {
+            report.scratch_removed += 1;
+        }
+    }
+    report
+}
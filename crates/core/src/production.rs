@@ -0,0 +1,85 @@
+//! Safe mode for production-like machines. A host is treated as production when its
+//! hostname or current kube context matches a configured pattern, or when
+//! `/etc/production` exists. There every action needs approval and the file tools that
+//! write are not registered.
+
+use std::path::{Path, PathBuf};
+
+use crate::policy::wildcard_match;
+use crate::{ApprovalMode, ShellmindConfig};
+
+pub const FLAG_FILE: &str = "/etc/production";
+
+/// File tools that are not registered in production.
+pub const WRITE_TOOLS: &[&str] = &["write_file", "edit_file", "multi_edit"];
+
+/// Why safe mode is on, and what it changed so saving the config can put it back.
+#[derive(Debug, Clone)]
+pub struct ProductionMode {
+    pub reason: String,
+    previous_approval_mode: ApprovalMode,
+    disabled_tools: Vec<String>,
+}
+
+fn kubeconfig_path() -> Option<PathBuf> {
+    match std::env::var("KUBECONFIG") {
+        Ok(paths) if !paths.is_empty() => std::env::split_paths(&paths).next(),
+        _ => dirs::home_dir().map(|home| home.join(".kube").join("config")),
+    }
+}
+
+/// `current-context` of the kubeconfig in use.
+fn kube_context(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    content.lines().find_map(|line| {
+        let context = line.strip_prefix("current-context:")?.trim().trim_matches(|c| c == '"' || c == '\'');
+        (!context.is_empty()).then(|| context.to_string())
+    })
+}
+
+fn matching(patterns: &[String], value: &str) -> bool {
+    patterns.iter().any(|pattern| wildcard_match(&pattern.to_lowercase(), &value.to_lowercase()))
+}
+
+/// Why this machine looks like production, if it does.
+pub fn detect(config: &ShellmindConfig) -> Option<String> {
+    if Path::new(FLAG_FILE).exists() {
+        return Some(format!("{} exists", FLAG_FILE));
+    }
+    let hostname = crate::audit::hostname();
+    if !hostname.is_empty() && matching(&config.production_hostnames, &hostname) {
+        return Some(format!("hostname {}", hostname));
+    }
+    let context = kubeconfig_path().and_then(|path| kube_context(&path))?;
+    matching(&config.production_kube_contexts, &context).then(|| format!("kube context {}", context))
+}
+
+/// Turns on safe mode when `config.production_safe_mode` is set and the machine looks
+/// like production. `approval_mode` becomes at least `always`.
+pub fn enforce(config: &mut ShellmindConfig) {
+    if !config.production_safe_mode {
+        return;
+    }
+    let Some(reason) = detect(config) else {
+        return;
+    };
+    let disabled_tools: Vec<String> = WRITE_TOOLS
+        .iter()
+        .filter(|tool| !config.disabled_tools.iter().any(|d| d == *tool))
+        .map(|tool| tool.to_string())
+        .collect();
+    config.disabled_tools.extend(disabled_tools.iter().cloned());
+    config.production = Some(ProductionMode { reason, previous_approval_mode: config.approval_mode, disabled_tools });
+    // `suggest` never executes anything and is stricter still
+    if config.approval_mode == ApprovalMode::Default {
+        config.approval_mode = ApprovalMode::Always;
+    }
+}
+
+/// Undoes `enforce`, for writing the user's own settings back to disk.
+pub fn restore(config: &mut ShellmindConfig) {
+    if let Some(mode) = config.production.take() {
+        config.approval_mode = mode.previous_approval_mode;
+        config.disabled_tools.retain(|tool| !mode.disabled_tools.contains(tool));
+    }
+}
@@ -0,0 +1,170 @@
+//! Portable Shellmind profiles: config (without secrets), custom tools, allowed-command
+//! patterns, OpenAPI specs and memory bundled into a single JSON file.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::retention::DataKind;
+use crate::{persist, ConfigManager, ShellmindConfig, ShellmindError};
+
+pub(crate) const PROFILE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub version: u32,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+    /// Config with `api_key` cleared; secure storage is never exported.
+    pub config: ShellmindConfig,
+    /// OpenAPI spec contents keyed by file name, so tools work without the original paths.
+    #[serde(default)]
+    pub openapi_specs: BTreeMap<String, String>,
+    /// Memory files keyed by path relative to `~/.shellmind/memory`.
+    #[serde(default)]
+    pub memory: BTreeMap<String, String>,
+}
+
+fn spec_file_name(spec: &str) -> String {
+    Path::new(spec)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| spec.to_string())
+}
+
+impl Profile {
+    /// Collects the current user's profile; policy and production overrides are left out.
+    pub fn collect(config: &ShellmindConfig) -> Result<Self, ShellmindError> {
+        let mut config = config.user_settings();
+        config.api_key = String::new();
+
+        let mut openapi_specs = BTreeMap::new();
+        for source in &config.openapi_tools {
+            let content = std::fs::read_to_string(&source.spec)
+                .map_err(|e| ShellmindError::Other(format!("Failed to read OpenAPI spec '{}': {}", source.spec, e)))?;
+            openapi_specs.insert(spec_file_name(&source.spec), content);
+        }
+
+        let mut memory = BTreeMap::new();
+        let memory_dir = DataKind::Memory.path()?;
+        if memory_dir.exists() {
+            for entry in walkdir::WalkDir::new(&memory_dir).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+                let relative = entry.path().strip_prefix(&memory_dir).unwrap_or(entry.path());
+                memory.insert(relative.to_string_lossy().into_owned(), persist::read_private(entry.path())?);
+            }
+        }
+
+        Ok(Profile {
+            version: PROFILE_VERSION,
+            exported_at: chrono::Utc::now(),
+            config,
+            openapi_specs,
+            memory,
+        })
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), ShellmindError> {
+        persist::atomic_write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ShellmindError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to read profile '{}': {}", path.display(), e)))?;
+        let profile: Profile = serde_json::from_str(&content)?;
+        if profile.version > PROFILE_VERSION {
+            return Err(ShellmindError::Other(format!(
+                "Profile version {} is newer than this Shellmind supports ({})",
+                profile.version, PROFILE_VERSION
+            )));
+        }
+        Ok(profile)
+    }
+
+    /// Security-relevant settings (`sync::LOCAL_ONLY_KEYS`) the profile would change, as
+    /// `key = new value`. A profile is a shared file, so these need the user's consent.
+    pub fn security_changes(&self, current: &ShellmindConfig) -> Result<Vec<String>, ShellmindError> {
+        let before = crate::sync::config_table(&current.user_settings())?;
+        let after = crate::sync::config_table(&self.config)?;
+        Ok(crate::sync::LOCAL_ONLY_KEYS
+            .iter()
+            // The local API key is always kept
+            .filter(|key| **key != "api_key" && before.get(**key) != after.get(**key))
+            .map(|key| match after.get(*key) {
+                Some(value) => format!("{} = {}", key, value),
+                None => format!("{} (unset)", key),
+            })
+            .collect())
+    }
+
+    /// Installs the profile over the current setup. The local API key is kept, OpenAPI
+    /// specs are written to `~/.shellmind/openapi/` and memory files are restored.
+    pub fn apply(self, current: &ShellmindConfig) -> Result<ShellmindConfig, ShellmindError> {
+        let mut config = self.config;
+        config.api_key = current.api_key.clone();
+
+        let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+        let specs_dir = home_dir.join(".shellmind").join("openapi");
+        if !self.openapi_specs.is_empty() {
+            std::fs::create_dir_all(&specs_dir)
+                .map_err(|e| ShellmindError::Other(format!("Failed to create OpenAPI spec directory: {}", e)))?;
+        }
+        for source in &mut config.openapi_tools {
+            let name = spec_file_name(&source.spec);
+            if let Some(content) = self.openapi_specs.get(&name) {
+                let path = specs_dir.join(&name);
+                persist::atomic_write(&path, content)?;
+                source.spec = path.to_string_lossy().into_owned();
+            }
+        }
+
+        let memory_dir = DataKind::Memory.path()?;
+        for (relative, content) in &self.memory {
+            let path = memory_dir.join(relative);
+            // Entries come from an untrusted file; keep them inside the memory directory
+            if !path.starts_with(&memory_dir) || relative.contains("..") {
+                return Err(ShellmindError::Other(format!("Invalid memory path in profile: {}", relative)));
+            }
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| ShellmindError::Other(format!("Failed to create memory directory: {}", e)))?;
+            }
+            persist::atomic_write(&path, persist::seal(content)?)?;
+        }
+
+        ConfigManager::save_configuration(&config)?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(config: ShellmindConfig) -> Profile {
+        Profile {
+            version: PROFILE_VERSION,
+            exported_at: chrono::Utc::now(),
+            config,
+            openapi_specs: BTreeMap::new(),
+            memory: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_security_changes_list_local_only_keys() {
+        let current = ConfigManager::defaults().unwrap();
+        let mut shared = current.user_settings();
+        shared.allowed_commands = vec!["curl *".to_string()];
+        shared.audit_webhook_url = "https://example.com/hook".to_string();
+        shared.banner_text = "Team".to_string();
+        let changes = profile(shared).security_changes(&current).unwrap();
+        assert_eq!(changes, vec!["allowed_commands = [\"curl *\"]", "audit_webhook_url = \"https://example.com/hook\""]);
+    }
+
+    #[test]
+    fn test_security_changes_ignore_api_key() {
+        let current = ConfigManager::defaults().unwrap();
+        let mut shared = current.user_settings();
+        shared.api_key = String::new();
+        assert!(profile(shared).security_changes(&current).unwrap().is_empty());
+    }
+}
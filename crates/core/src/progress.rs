@@ -0,0 +1,29 @@
+//! Progress updates from long-running tools to whoever renders them (terminal progress
+//! bars, editor notifications).
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolProgress {
+    /// Steps completed so far.
+    pub current: u64,
+    /// Total steps when known; `None` renders as an indeterminate spinner.
+    pub total: Option<u64>,
+    pub message: String,
+}
+
+/// Handed to `BaseTool::execute`; sending never blocks and is ignored once the receiver is gone.
+#[derive(Debug, Clone)]
+pub struct ProgressSender(mpsc::UnboundedSender<ToolProgress>);
+
+impl ProgressSender {
+    pub fn report(&self, current: u64, total: Option<u64>, message: impl Into<String>) {
+        let _ = self.0.send(ToolProgress { current, total, message: message.into() });
+    }
+}
+
+pub fn channel() -> (ProgressSender, mpsc::UnboundedReceiver<ToolProgress>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (ProgressSender(sender), receiver)
+}
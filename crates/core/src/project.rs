@@ -0,0 +1,124 @@
+//! Project-type detection, used to tell the model which build, test and run commands fit
+//! the current directory.
+
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stack {
+    Rust,
+    Node,
+    Python,
+    Go,
+}
+
+impl Stack {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stack::Rust => "Rust (Cargo)",
+            Stack::Node => "Node.js",
+            Stack::Python => "Python",
+            Stack::Go => "Go",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProjectInfo {
+    pub stacks: Vec<Stack>,
+    /// `npm`, `yarn` or `pnpm`, from the lockfile.
+    pub node_package_manager: Option<&'static str>,
+    pub makefile: bool,
+    pub docker_compose: bool,
+    pub kubernetes: bool,
+}
+
+fn has_yaml(dir: &Path) -> bool {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries.filter_map(|e| e.ok()).any(|e| {
+                let name = e.file_name().to_string_lossy().to_lowercase();
+                name.ends_with(".yaml") || name.ends_with(".yml")
+            })
+        })
+        .unwrap_or(false)
+}
+
+impl ProjectInfo {
+    /// Looks for marker files in `dir` (not recursively).
+    pub fn detect(dir: &Path) -> Self {
+        let exists = |name: &str| dir.join(name).exists();
+        let mut info = ProjectInfo::default();
+
+        if exists("Cargo.toml") {
+            info.stacks.push(Stack::Rust);
+        }
+        if exists("package.json") {
+            info.stacks.push(Stack::Node);
+            info.node_package_manager = Some(if exists("pnpm-lock.yaml") {
+                "pnpm"
+            } else if exists("yarn.lock") {
+                "yarn"
+            } else {
+                "npm"
+            });
+        }
+        if ["pyproject.toml", "setup.py", "setup.cfg", "requirements.txt", "Pipfile"].iter().any(|f| exists(f)) {
+            info.stacks.push(Stack::Python);
+        }
+        if exists("go.mod") {
+            info.stacks.push(Stack::Go);
+        }
+        info.makefile = ["Makefile", "makefile", "GNUmakefile"].iter().any(|f| exists(f));
+        info.docker_compose = ["docker-compose.yml", "docker-compose.yaml", "compose.yml", "compose.yaml"].iter().any(|f| exists(f));
+        info.kubernetes = exists("kustomization.yaml")
+            || exists("Chart.yaml")
+            || ["k8s", "kubernetes", "manifests", "deploy"].iter().any(|d| has_yaml(&dir.join(d)));
+        info
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty() && !self.makefile && !self.docker_compose && !self.kubernetes
+    }
+
+    /// The command that runs the project's tests, preferring the stack's own runner.
+    pub fn test_command(&self) -> Option<String> {
+        match self.stacks.first() {
+            Some(Stack::Rust) => Some("cargo test".to_string()),
+            Some(Stack::Node) => Some(format!("{} test", self.node_package_manager.unwrap_or("npm"))),
+            Some(Stack::Python) => Some("pytest".to_string()),
+            Some(Stack::Go) => Some("go test ./...".to_string()),
+            None if self.makefile => Some("make test".to_string()),
+            None => None,
+        }
+    }
+
+    /// Capability note appended to the system prompt; empty when nothing was detected.
+    pub fn prompt_note(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut lines = vec!["The current directory is a project with these conventions:".to_string()];
+        for stack in &self.stacks {
+            let commands = match stack {
+                Stack::Rust => "build `cargo build`, test `cargo test`, run `cargo run`, lint `cargo clippy`".to_string(),
+                Stack::Node => {
+                    let pm = self.node_package_manager.unwrap_or("npm");
+                    format!("install `{pm} install`, test `{pm} test`, run scripts from package.json with `{pm} run <script>`")
+                }
+                Stack::Python => "test `pytest`, run modules with `python -m <module>`, prefer the project's virtualenv if one exists".to_string(),
+                Stack::Go => "build `go build ./...`, test `go test ./...`, run `go run .`".to_string(),
+            };
+            lines.push(format!("- {}: {}", stack.name(), commands));
+        }
+        if self.makefile {
+            lines.push("- Makefile: prefer existing `make` targets for building and testing".to_string());
+        }
+        if self.docker_compose {
+            lines.push("- Docker Compose: services are managed with `docker compose up/down/logs`".to_string());
+        }
+        if self.kubernetes {
+            lines.push("- Kubernetes manifests: use `kubectl apply --dry-run=client -f` to check changes before applying".to_string());
+        }
+        lines.join("\n")
+    }
+}
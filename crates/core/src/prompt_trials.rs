@@ -0,0 +1,170 @@
+//! A/B testing of system prompts. `system_prompts` names alternatives to `system_prompt`,
+//! which takes part as the `default` variant. With `prompt_sampling` set, each session is
+//! served one variant; the requests it served and what followed them (the command
+//! succeeded or failed, the user declined it, `/good`, `/bad`) are kept in
+//! `~/.shellmind/prompt_trials.jsonl` and compared by `shellmind prompts report`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::retention::DataKind;
+use crate::{persist, ShellmindConfig, ShellmindError};
+
+/// The variant served by `system_prompt` itself.
+pub const DEFAULT_VARIANT: &str = "default";
+
+/// Oldest entries beyond this are dropped when the log is written.
+const MAX_ENTRIES: usize = 5000;
+/// Share of sessions that try a random variant under `best` sampling.
+const EXPLORATION_PERCENT: u128 = 10;
+/// Signals a variant needs before it is compared with the others.
+pub const MIN_SIGNALS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptSampling {
+    /// Always use `system_prompt`; nothing is recorded.
+    Off,
+    /// Pick a variant uniformly at random.
+    Random,
+    /// Try every variant until it has `MIN_SIGNALS` signals, then mostly use the best one.
+    Best,
+}
+
+impl Default for PromptSampling {
+    fn default() -> Self {
+        PromptSampling::Off
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Signal {
+    /// The variant answered a request.
+    Served,
+    Succeeded,
+    Failed,
+    /// The user declined to run the suggested command.
+    Declined,
+    Good,
+    Bad,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trial {
+    pub timestamp: DateTime<Utc>,
+    pub variant: String,
+    pub session: String,
+    pub signal: Signal,
+}
+
+/// `default` followed by the names in `system_prompts`.
+pub fn variant_names(config: &ShellmindConfig) -> Vec<String> {
+    std::iter::once(DEFAULT_VARIANT.to_string())
+        .chain(config.system_prompts.keys().filter(|name| name.as_str() != DEFAULT_VARIANT).cloned())
+        .collect()
+}
+
+pub fn variant_prompt<'a>(config: &'a ShellmindConfig, variant: &str) -> &'a str {
+    match config.system_prompts.get(variant) {
+        Some(prompt) if variant != DEFAULT_VARIANT => prompt,
+        _ => &config.system_prompt,
+    }
+}
+
+fn random_index(len: usize) -> usize {
+    (uuid::Uuid::new_v4().as_u128() % len as u128) as usize
+}
+
+/// The variant to serve a new session with, or `None` when sampling is off or there is
+/// nothing to compare.
+pub fn choose_variant(config: &ShellmindConfig) -> Option<String> {
+    let names = variant_names(config);
+    if config.prompt_sampling == PromptSampling::Off || names.len() < 2 {
+        return None;
+    }
+    if config.prompt_sampling == PromptSampling::Best {
+        // Variants that were removed from the config are not candidates
+        let mut stats = summarize(&load().unwrap_or_default(), &names);
+        stats.truncate(names.len());
+        let untried: Vec<&VariantStats> = stats.iter().filter(|s| s.signals() < MIN_SIGNALS).collect();
+        if !untried.is_empty() {
+            return Some(untried[random_index(untried.len())].name.clone());
+        }
+        if uuid::Uuid::new_v4().as_u128() % 100 >= EXPLORATION_PERCENT {
+            if let Some(best) = best(&stats) {
+                return Some(best.name.clone());
+            }
+        }
+    }
+    Some(names[random_index(names.len())].clone())
+}
+
+pub fn load() -> Result<Vec<Trial>, ShellmindError> {
+    persist::read_jsonl(&DataKind::PromptTrials.path()?)
+}
+
+pub fn record(variant: &str, session: &str, signal: Signal) -> Result<(), ShellmindError> {
+    let trial = Trial { timestamp: Utc::now(), variant: variant.to_string(), session: session.to_string(), signal };
+    persist::append_jsonl(&DataKind::PromptTrials.path()?, trial, MAX_ENTRIES)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VariantStats {
+    pub name: String,
+    pub sessions: usize,
+    pub requests: u32,
+    pub succeeded: u32,
+    pub failed: u32,
+    pub declined: u32,
+    pub good: u32,
+    pub bad: u32,
+}
+
+impl VariantStats {
+    pub fn signals(&self) -> u32 {
+        self.succeeded + self.failed + self.declined + self.good + self.bad
+    }
+
+    /// Share of positive signals, pulled toward one half so a handful of signals can't
+    /// decide on their own.
+    pub fn score(&self) -> f64 {
+        (self.succeeded + self.good + 1) as f64 / (self.signals() + 2) as f64
+    }
+}
+
+/// Totals for each of `names`, then for variants that are only in the log.
+pub fn summarize(trials: &[Trial], names: &[String]) -> Vec<VariantStats> {
+    let mut stats: BTreeMap<&str, (VariantStats, BTreeSet<&str>)> = BTreeMap::new();
+    for trial in trials {
+        let (entry, sessions) = stats.entry(&trial.variant).or_default();
+        sessions.insert(&trial.session);
+        match trial.signal {
+            Signal::Served => entry.requests += 1,
+            Signal::Succeeded => entry.succeeded += 1,
+            Signal::Failed => entry.failed += 1,
+            Signal::Declined => entry.declined += 1,
+            Signal::Good => entry.good += 1,
+            Signal::Bad => entry.bad += 1,
+        }
+    }
+    let mut ordered: Vec<VariantStats> = names
+        .iter()
+        .map(|name| {
+            let (entry, sessions) = stats.remove(name.as_str()).unwrap_or_default();
+            VariantStats { name: name.clone(), sessions: sessions.len(), ..entry }
+        })
+        .collect();
+    ordered.extend(stats.into_iter().map(|(name, (entry, sessions))| VariantStats { name: name.to_string(), sessions: sessions.len(), ..entry }));
+    ordered
+}
+
+/// The best-scoring variant once at least two have `MIN_SIGNALS` signals.
+pub fn best(stats: &[VariantStats]) -> Option<&VariantStats> {
+    let compared: Vec<&VariantStats> = stats.iter().filter(|s| s.signals() >= MIN_SIGNALS).collect();
+    if compared.len() < 2 {
+        return None;
+    }
+    compared.into_iter().max_by(|a, b| a.score().total_cmp(&b.score()))
+}
@@ -0,0 +1,95 @@
+//! Client-side rate limiting for model API calls: requests and tokens per minute over a
+//! sliding window, plus a cap on concurrent requests.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits for one provider (`[rate_limits.gemini]`). Zero disables a limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+    #[serde(default)]
+    pub tokens_per_minute: u32,
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+}
+
+fn default_requests_per_minute() -> u32 {
+    60
+}
+
+fn default_max_concurrent() -> usize {
+    4
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            requests_per_minute: default_requests_per_minute(),
+            tokens_per_minute: 0,
+            max_concurrent: default_max_concurrent(),
+        }
+    }
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    concurrency: Arc<Semaphore>,
+    /// Time, token count and whether the entry is a request (rather than response tokens)
+    /// for usage in the current window.
+    window: Mutex<VecDeque<(Instant, u32, bool)>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let permits = if config.max_concurrent == 0 { Semaphore::MAX_PERMITS } else { config.max_concurrent };
+        RateLimiter {
+            concurrency: Arc::new(Semaphore::new(permits)),
+            window: Mutex::new(VecDeque::new()),
+            config,
+        }
+    }
+
+    /// Waits until a request of roughly `tokens` tokens fits within the limits. The
+    /// returned permit holds a concurrency slot until dropped.
+    pub async fn acquire(&self, tokens: u32) -> OwnedSemaphorePermit {
+        let permit = Arc::clone(&self.concurrency).acquire_owned().await.expect("rate limiter semaphore closed");
+        loop {
+            let wait = {
+                let mut window = self.window.lock().await;
+                let now = Instant::now();
+                while window.front().map_or(false, |(start, _, _)| now.duration_since(*start) >= WINDOW) {
+                    window.pop_front();
+                }
+
+                let used_tokens: u32 = window.iter().map(|(_, t, _)| *t).sum();
+                let requests = window.iter().filter(|(_, _, is_request)| *is_request).count() as u32;
+                let requests_ok = self.config.requests_per_minute == 0 || requests < self.config.requests_per_minute;
+                // A request larger than the whole budget is let through once the window is empty
+                let tokens_ok = self.config.tokens_per_minute == 0
+                    || window.is_empty()
+                    || used_tokens.saturating_add(tokens) <= self.config.tokens_per_minute;
+                if requests_ok && tokens_ok {
+                    window.push_back((now, tokens, true));
+                    return permit;
+                }
+                window
+                    .front()
+                    .map(|(start, _, _)| WINDOW.saturating_sub(now.duration_since(*start)))
+                    .unwrap_or(Duration::from_millis(100))
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Counts tokens of a response against the tokens-per-minute limit.
+    pub async fn record_tokens(&self, tokens: u32) {
+        self.window.lock().await.push_back((Instant::now(), tokens, false));
+    }
+}
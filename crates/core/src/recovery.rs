@@ -0,0 +1,186 @@
+//! Crash recovery: a checkpoint of where an interactive session is (the prompt being
+//! handled, a reply awaiting approval, the working directory), autosaved periodically
+//! and written by a panic hook, so `shellmind session recover` can pick up after a crash.
+//! The transcript itself is already saved turn by turn and undo snapshots are on disk.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::{persist, ShellmindError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub session_id: String,
+    pub pid: u32,
+    pub working_dir: PathBuf,
+    /// Prompt being handled; `None` between turns.
+    #[serde(default)]
+    pub pending_input: Option<String>,
+    /// Model reply shown for approval but not yet acted on.
+    #[serde(default)]
+    pub pending_reply: Option<String>,
+    /// When the pending prompt was submitted; undo snapshots after it belong to the turn.
+    #[serde(default)]
+    pub turn_started_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+    /// Written by the panic hook rather than an autosave.
+    #[serde(default)]
+    pub panicked: bool,
+}
+
+static CURRENT: Mutex<Option<Checkpoint>> = Mutex::new(None);
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+fn recovery_dir() -> Result<PathBuf, ShellmindError> {
+    let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+    Ok(home_dir.join(".shellmind").join("recovery"))
+}
+
+fn write(checkpoint: &Checkpoint) -> Result<(), ShellmindError> {
+    let dir = recovery_dir()?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| ShellmindError::Other(format!("Failed to create recovery directory: {}", e)))?;
+    let path = dir.join(format!("{}.json", checkpoint.session_id));
+    persist::atomic_write(&path, persist::seal(serde_json::to_string(checkpoint)?)?)
+}
+
+/// Starts checkpointing `session` in this process. The session file is saved first, so
+/// the checkpoint never points at a transcript that isn't on disk yet. A checkpoint left
+/// for the same session by a crashed process is replaced.
+pub fn begin(session: &crate::session::Session) -> Result<(), ShellmindError> {
+    session.file.save()?;
+    let checkpoint = Checkpoint {
+        session_id: session.id().to_string(),
+        pid: std::process::id(),
+        working_dir: session.working_dir.clone(),
+        pending_input: None,
+        pending_reply: None,
+        turn_started_at: None,
+        updated_at: Utc::now(),
+        panicked: false,
+    };
+    write(&checkpoint)?;
+    *CURRENT.lock().unwrap_or_else(|e| e.into_inner()) = Some(checkpoint);
+    Ok(())
+}
+
+/// Changes the checkpoint in memory; it reaches disk with the next autosave or `flush`.
+pub fn update(f: impl FnOnce(&mut Checkpoint)) {
+    if let Some(checkpoint) = CURRENT.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        f(checkpoint);
+        checkpoint.updated_at = Utc::now();
+        DIRTY.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Records that `input` is being handled.
+pub fn start_turn(input: &str) {
+    update(|c| {
+        c.pending_input = Some(input.to_string());
+        c.pending_reply = None;
+        c.turn_started_at = Some(Utc::now());
+    });
+}
+
+/// Records `reply` as awaiting approval and writes the checkpoint right away, since the
+/// user may take a while to decide.
+pub fn awaiting_approval(reply: &str) -> Result<(), ShellmindError> {
+    update(|c| c.pending_reply = Some(reply.to_string()));
+    flush()
+}
+
+/// Clears the pending turn once it has been handled.
+pub fn end_turn(working_dir: PathBuf) {
+    update(|c| {
+        c.pending_input = None;
+        c.pending_reply = None;
+        c.turn_started_at = None;
+        c.working_dir = working_dir;
+    });
+}
+
+/// Writes the checkpoint if it changed since the last write.
+pub fn flush() -> Result<(), ShellmindError> {
+    if !DIRTY.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let checkpoint = CURRENT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    match checkpoint {
+        Some(checkpoint) => write(&checkpoint),
+        None => Ok(()),
+    }
+}
+
+/// Removes the checkpoint on a clean exit; nothing is left to recover.
+pub fn finish() {
+    if let Some(checkpoint) = CURRENT.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        if let Ok(dir) = recovery_dir() {
+            let _ = std::fs::remove_file(dir.join(format!("{}.json", checkpoint.session_id)));
+        }
+    }
+}
+
+/// Flushes the checkpoint every `interval` until the process exits.
+pub fn spawn_autosave(interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = flush() {
+                eprintln!("Warning: failed to autosave the session checkpoint: {}", e);
+            }
+        }
+    });
+}
+
+/// Writes the checkpoint before the default panic output, so a crash mid-turn keeps the
+/// pending prompt and approval.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // `try_lock`: the panic may have happened while the checkpoint was locked
+        if let Ok(mut current) = CURRENT.try_lock() {
+            if let Some(checkpoint) = current.as_mut() {
+                checkpoint.panicked = true;
+                checkpoint.updated_at = Utc::now();
+                if write(checkpoint).is_ok() {
+                    eprintln!("Shellmind crashed; run `shellmind session recover {}` to continue.", checkpoint.session_id);
+                }
+            }
+        }
+        previous(info);
+    }));
+}
+
+/// Checkpoints left by processes that are no longer running, newest first.
+pub fn recoverable() -> Result<Vec<Checkpoint>, ShellmindError> {
+    let dir = recovery_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut checkpoints: Vec<Checkpoint> = std::fs::read_dir(&dir)
+        .map_err(|e| ShellmindError::Other(format!("Failed to read recovery directory: {}", e)))?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| persist::read_private(&e.path()).ok())
+        .filter_map(|content| serde_json::from_str::<Checkpoint>(&content).ok())
+        .filter(|c| !crate::processes::alive(c.pid))
+        .collect();
+    checkpoints.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(checkpoints)
+}
+
+/// The checkpoint of `session_id`, or the newest recoverable one. It stays on disk until
+/// the resumed session's `begin` replaces it, so a failed resume can be retried.
+pub fn find(session_id: Option<&str>) -> Result<Checkpoint, ShellmindError> {
+    recoverable()?
+        .into_iter()
+        .find(|c| session_id.map_or(true, |id| c.session_id == id))
+        .ok_or_else(|| ShellmindError::Other(match session_id {
+            Some(id) => format!("No recoverable session '{}'", id),
+            None => "No crashed session to recover".to_string(),
+        }))
+}
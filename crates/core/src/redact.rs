@@ -0,0 +1,143 @@
+//! Workspace redaction profile from `.shellmind/redact.toml`. Files matching `paths` are
+//! never read into a request, and text matching `patterns` is replaced before anything is
+//! sent to the model; both leave `[redacted by policy]` in their place.
+//!
+//! ```toml
+//! paths = ["customer-data/**", "*.pem", "config/credentials.yml"]
+//! patterns = ['\b\d{3}-\d{2}-\d{4}\b', '(?i)iban:\s*\S+']
+//! ```
+
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::ShellmindError;
+
+pub const PROFILE_PATH: &str = ".shellmind/redact.toml";
+
+/// What replaces redacted file contents and matches.
+pub const NOTE: &str = "[redacted by policy]";
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileFile {
+    /// Globs relative to the workspace root; a matching directory covers everything in it.
+    #[serde(default)]
+    paths: Vec<String>,
+    #[serde(default)]
+    patterns: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Profile {
+    root: PathBuf,
+    paths: Vec<glob::Pattern>,
+    patterns: Vec<Regex>,
+    /// Set when the profile exists but can't be used; every file in the workspace is
+    /// then withheld rather than risk sending what it was meant to protect.
+    broken: bool,
+    /// Why the profile is broken, for the UI to report.
+    error: Option<String>,
+}
+
+impl Profile {
+    /// Reads `root/.shellmind/redact.toml`; a missing file is an empty profile.
+    pub fn load(root: &Path) -> Result<Self, ShellmindError> {
+        let path = root.join(PROFILE_PATH);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Profile { root: root.to_path_buf(), ..Default::default() }),
+            Err(e) => return Err(ShellmindError::Other(format!("Failed to read '{}': {}", path.display(), e))),
+        };
+        let file: ProfileFile =
+            toml::from_str(&content).map_err(|e| ShellmindError::Other(format!("Invalid redaction profile '{}': {}", path.display(), e)))?;
+        let paths = file
+            .paths
+            .iter()
+            .map(|p| glob::Pattern::new(p.trim_start_matches("./").trim_end_matches('/')))
+            .collect::<Result<_, _>>()
+            .map_err(|e| ShellmindError::Other(format!("Invalid path in '{}': {}", path.display(), e)))?;
+        let patterns = file
+            .patterns
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<Result<_, _>>()
+            .map_err(|e| ShellmindError::Other(format!("Invalid pattern in '{}': {}", path.display(), e)))?;
+        Ok(Profile { root: root.to_path_buf(), paths, patterns, ..Default::default() })
+    }
+
+    /// Why the profile couldn't be loaded, when every workspace file is withheld.
+    pub fn load_error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.patterns.is_empty() && !self.broken
+    }
+
+    /// Whether the contents of `path` must not be sent. Paths outside the workspace are
+    /// only matched by absolute globs.
+    pub fn blocks(&self, path: &Path) -> bool {
+        let absolute = if path.is_absolute() { path.to_path_buf() } else { self.root.join(path) };
+        let absolute = absolute.canonicalize().unwrap_or(absolute);
+        let root = self.root.canonicalize().unwrap_or_else(|_| self.root.clone());
+        let relative = absolute.strip_prefix(&root).ok();
+        if self.broken {
+            return relative.is_some();
+        }
+        let options = glob::MatchOptions { require_literal_separator: true, ..Default::default() };
+        self.paths.iter().any(|pattern| {
+            if pattern.as_str().starts_with('/') {
+                return absolute.ancestors().any(|a| pattern.matches_path_with(a, options));
+            }
+            // A file is covered when it or a directory above it matches; globs without a
+            // slash, like `*.pem`, match names at any depth as in `.gitignore`
+            relative.map_or(false, |relative| {
+                relative.ancestors().filter(|a| !a.as_os_str().is_empty()).any(|a| {
+                    pattern.matches_path_with(a, options)
+                        || (!pattern.as_str().contains('/') && a.file_name().map_or(false, |name| pattern.matches_with(&name.to_string_lossy(), options)))
+                })
+            })
+        })
+    }
+
+    /// `text` with every pattern match replaced by `NOTE`.
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for pattern in &self.patterns {
+            if pattern.is_match(&text) {
+                text = pattern.replace_all(&text, NOTE).into_owned();
+            }
+        }
+        text
+    }
+
+    /// `Blocked` when the contents of `path` must not be sent.
+    pub fn check(&self, path: &Path) -> Result<(), ShellmindError> {
+        if self.blocks(path) {
+            return Err(ShellmindError::Blocked(format!("'{}' is {}", path.display(), NOTE)));
+        }
+        Ok(())
+    }
+}
+
+/// The profile of the current directory, loaded once per process. An unusable profile
+/// withholds every workspace file; `load_error` says why.
+pub fn active() -> &'static Profile {
+    static PROFILE: OnceLock<Profile> = OnceLock::new();
+    PROFILE.get_or_init(|| {
+        let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Profile::load(&root).unwrap_or_else(|e| Profile { root, broken: true, error: Some(e.to_string()), ..Default::default() })
+    })
+}
+
+/// Reads a file for a tool whose output goes to the model: blocked files are refused
+/// and pattern matches are redacted. Every file tool reads through this.
+pub async fn read_to_string(path: &Path) -> Result<String, ShellmindError> {
+    let profile = active();
+    profile.check(path)?;
+    let content = crate::file_cache::read_to_string(path)
+        .await
+        .map_err(|e| ShellmindError::Other(format!("Failed to read file '{}': {}", path.display(), e)))?;
+    Ok(profile.redact(&content))
+}
@@ -0,0 +1,192 @@
+//! Forwarding dangerous approvals to a phone: when a confirmation has waited unattended
+//! for `approval_webhook_after_secs`, its details go to an ntfy topic or a Telegram chat
+//! with a one-time token, and a reply of `approve <token>` or `deny <token>` answers it.
+//!
+//! `approval_webhook_url` is an ntfy topic URL (`https://ntfy.example.com/my-shellmind`)
+//! or `telegram:<chat id>`. The ntfy access token or Telegram bot token is read from
+//! secure storage under `approval_webhook_token_secret`. Both are required: the reply
+//! token is sent over the channel, so anyone able to read it could otherwise approve.
+//! An ntfy topic must only be readable and writable with that access token.
+
+use std::time::Duration;
+
+use crate::{ShellmindConfig, ShellmindError};
+
+/// How often replies are polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// How long a forwarded approval waits for its reply before it counts as denied.
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Longest details text sent; Telegram messages are capped at 4096 characters.
+const MAX_DETAILS_CHARS: usize = 3000;
+
+#[derive(Debug, Clone)]
+pub enum Channel {
+    Ntfy { url: String, token: String },
+    Telegram { chat_id: String, bot_token: String },
+}
+
+/// A forwarded approval awaiting its reply.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub token: String,
+    /// Unix time the request was sent; earlier ntfy messages are ignored.
+    sent_at: i64,
+}
+
+impl Channel {
+    /// The channel configured in `approval_webhook_url`, if any.
+    pub fn from_config(config: &ShellmindConfig) -> Result<Option<Self>, ShellmindError> {
+        let url = config.approval_webhook_url.trim();
+        if url.is_empty() {
+            return Ok(None);
+        }
+        if !url.starts_with("telegram:") && !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(ShellmindError::Other(format!("Invalid approval_webhook_url '{}'; use an ntfy topic URL or telegram:<chat id>", url)));
+        }
+        let token = extensions::secure_storage::retrieve_secret(&config.approval_webhook_token_secret).map_err(|_| {
+            ShellmindError::Other(format!(
+                "Remote approvals need the ntfy access token or Telegram bot token: shellmind secret set {}",
+                config.approval_webhook_token_secret
+            ))
+        })?;
+        if let Some(chat_id) = url.strip_prefix("telegram:") {
+            return Ok(Some(Channel::Telegram { chat_id: chat_id.trim().to_string(), bot_token: token }));
+        }
+        Ok(Some(Channel::Ntfy { url: url.trim_end_matches('/').to_string(), token }))
+    }
+
+    /// Sends the approval request and returns its reply token.
+    pub async fn send(&self, title: &str, details: &str) -> Result<Request, ShellmindError> {
+        let token = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+        let sent_at = chrono::Utc::now().timestamp();
+        let details = match details.char_indices().nth(MAX_DETAILS_CHARS) {
+            Some((end, _)) => format!("{}…", &details[..end]),
+            None => details.to_string(),
+        };
+        let host = crate::audit::hostname();
+        let body = format!("{}\n\n{}\n\nReply \"approve {}\" or \"deny {}\".", title, details, token, token);
+        let client = crate::http_client();
+        let response = match self {
+            Channel::Ntfy { url, token: auth } => {
+                let actions = format!("http, Approve, {url}, body=approve {token}, clear=true; http, Deny, {url}, body=deny {token}, clear=true");
+                let request = client
+                    .post(url)
+                    .header("Title", format!("Shellmind on {} needs approval", host))
+                    .header("Priority", "high")
+                    .header("Tags", "warning")
+                    .header("Actions", actions)
+                    .bearer_auth(auth)
+                    .body(body);
+                request.send().await?
+            }
+            Channel::Telegram { chat_id, bot_token } => {
+                let keyboard = serde_json::json!({ "inline_keyboard": [[
+                    { "text": "Approve", "callback_data": format!("approve {}", token) },
+                    { "text": "Deny", "callback_data": format!("deny {}", token) },
+                ]] });
+                client
+                    .post(format!("https://api.telegram.org/bot{}/sendMessage", bot_token))
+                    .json(&serde_json::json!({
+                        "chat_id": chat_id,
+                        "text": format!("Shellmind on {} needs approval\n\n{}", host, body),
+                        "reply_markup": keyboard,
+                    }))
+                    .send()
+                    .await?
+            }
+        };
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(ShellmindError::Other(format!("The approval webhook returned {}: {}", status, response.text().await.unwrap_or_default().trim())));
+        }
+        Ok(Request { token, sent_at })
+    }
+
+    /// Polls until a reply carrying the request's token arrives: true to approve, false to
+    /// deny. Replies with other tokens are ignored. `None` once `REPLY_TIMEOUT` has passed
+    /// without one, which callers treat as a denial.
+    pub async fn wait_for_reply(&self, request: &Request) -> Result<Option<bool>, ShellmindError> {
+        let client = crate::http_client();
+        let deadline = tokio::time::Instant::now() + REPLY_TIMEOUT;
+        let mut offset: Option<i64> = None;
+        while tokio::time::Instant::now() < deadline {
+            let replies: Vec<String> = match self {
+                Channel::Ntfy { url, token } => {
+                    let poll = client.get(format!("{}/json?poll=1&since={}", url, request.sent_at)).bearer_auth(token);
+                    let body = poll.send().await?.text().await?;
+                    body.lines()
+                        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+                        .filter_map(|message| message["message"].as_str().map(str::to_string))
+                        .collect()
+                }
+                Channel::Telegram { chat_id, bot_token } => {
+                    let mut poll = client.get(format!("https://api.telegram.org/bot{}/getUpdates", bot_token));
+                    if let Some(offset) = offset {
+                        poll = poll.query(&[("offset", offset)]);
+                    }
+                    let updates: serde_json::Value = poll.send().await?.json().await?;
+                    let mut replies = Vec::new();
+                    for update in updates["result"].as_array().into_iter().flatten() {
+                        if let Some(id) = update["update_id"].as_i64() {
+                            offset = Some(offset.map_or(id + 1, |o| o.max(id + 1)));
+                        }
+                        // Only the configured chat may answer
+                        let (chat, text) = match update.get("callback_query") {
+                            Some(callback) => (&callback["message"]["chat"]["id"], &callback["data"]),
+                            None => (&update["message"]["chat"]["id"], &update["message"]["text"]),
+                        };
+                        if chat.to_string().trim_matches('"') == chat_id.as_str() {
+                            if let Some(text) = text.as_str() {
+                                replies.push(text.to_string());
+                            }
+                        }
+                    }
+                    replies
+                }
+            };
+            if let Some(approved) = replies.iter().find_map(|reply| parse_reply(reply, &request.token)) {
+                return Ok(Some(approved));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        Ok(None)
+    }
+}
+
+/// `Some(true)` for `approve <token>`, `Some(false)` for `deny <token>`, case-insensitive.
+pub fn parse_reply(reply: &str, token: &str) -> Option<bool> {
+    let mut words = reply.split_whitespace();
+    let verdict = words.next()?.to_lowercase();
+    if words.next()? != token || words.next().is_some() {
+        return None;
+    }
+    match verdict.as_str() {
+        "approve" | "yes" => Some(true),
+        "deny" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply_accepts_verdicts_for_the_token() {
+        assert_eq!(parse_reply("approve 1a2b3c4d", "1a2b3c4d"), Some(true));
+        assert_eq!(parse_reply("  YES 1a2b3c4d ", "1a2b3c4d"), Some(true));
+        assert_eq!(parse_reply("deny 1a2b3c4d", "1a2b3c4d"), Some(false));
+        assert_eq!(parse_reply("No 1a2b3c4d", "1a2b3c4d"), Some(false));
+    }
+
+    #[test]
+    fn test_parse_reply_ignores_other_replies() {
+        assert_eq!(parse_reply("approve ffffffff", "1a2b3c4d"), None);
+        assert_eq!(parse_reply("approve", "1a2b3c4d"), None);
+        assert_eq!(parse_reply("approve 1a2b3c4d please", "1a2b3c4d"), None);
+        assert_eq!(parse_reply("maybe 1a2b3c4d", "1a2b3c4d"), None);
+        assert_eq!(parse_reply("", "1a2b3c4d"), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! Retention for data Shellmind stores under `~/.shellmind/`: age/count-based pruning
+//! applied at startup and secure deletion for `shellmind purge`.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::{ShellmindConfig, ShellmindError};
+
+/// Kinds of stored data that can be purged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataKind {
+    Sessions,
+    History,
+    Memory,
+    Audit,
+    Undo,
+    Feedback,
+    Scratch,
+    PromptTrials,
+}
+
+impl DataKind {
+    pub const ALL: [DataKind; 8] = [
+        DataKind::Sessions,
+        DataKind::History,
+        DataKind::Memory,
+        DataKind::Audit,
+        DataKind::Undo,
+        DataKind::Feedback,
+        DataKind::Scratch,
+        DataKind::PromptTrials,
+    ];
+
+    /// Path of this data relative to `~/.shellmind`.
+    fn relative_path(self) -> &'static str {
+        match self {
+            DataKind::Sessions => "sessions",
+            DataKind::History => "history.txt",
+            DataKind::Memory => "memory",
+            DataKind::Audit => "audit",
+            DataKind::Undo => "undo",
+            DataKind::Feedback => "feedback.jsonl",
+            DataKind::Scratch => "scratch",
+            DataKind::PromptTrials => "prompt_trials.jsonl",
+        }
+    }
+
+    pub fn path(self) -> Result<PathBuf, ShellmindError> {
+        let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+        Ok(home_dir.join(".shellmind").join(self.relative_path()))
+    }
+}
+
+/// Overwrites a file with zeros before unlinking it, so the contents don't linger in
+/// the freed blocks. Best effort on copy-on-write filesystems and SSDs.
+pub fn secure_delete(path: &Path) -> Result<(), ShellmindError> {
+    let overwrite = || -> std::io::Result<()> {
+        let len = std::fs::metadata(path)?.len();
+        let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+        let zeros = vec![0u8; 64 * 1024];
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..chunk])?;
+            remaining -= chunk as u64;
+        }
+        file.sync_all()?;
+        std::fs::remove_file(path)
+    };
+    overwrite().map_err(|e| ShellmindError::Other(format!("Failed to delete '{}': {}", path.display(), e)))
+}
+
+fn files_by_age(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let mut files: Vec<(PathBuf, SystemTime)> = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let modified = e.metadata().ok()?.modified().ok()?;
+            Some((e.into_path(), modified))
+        })
+        .collect();
+    // Newest first
+    files.sort_by(|a, b| b.1.cmp(&a.1));
+    files
+}
+
+/// Securely deletes files in `dir` older than `max_age_days` (0 keeps everything) and
+/// beyond the newest `max_files` (0 is unlimited). Returns the number of files removed.
+pub fn prune_dir(dir: &Path, max_age_days: u64, max_files: usize) -> Result<usize, ShellmindError> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let cutoff = (max_age_days > 0).then(|| SystemTime::now() - Duration::from_secs(max_age_days * 24 * 60 * 60));
+    let mut removed = 0;
+    for (index, (path, modified)) in files_by_age(dir).into_iter().enumerate() {
+        let too_old = cutoff.map_or(false, |cutoff| modified < cutoff);
+        let over_limit = max_files > 0 && index >= max_files;
+        if too_old || over_limit {
+            secure_delete(&path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Applies the configured retention policy. Called at startup and hourly by the daemon.
+pub fn apply_retention(config: &ShellmindConfig) -> Result<usize, ShellmindError> {
+    Ok(prune_dir(&DataKind::Sessions.path()?, config.max_session_age_days, 0)?
+        + prune_dir(&DataKind::Audit.path()?, config.audit_log_retention_days, 0)?
+        + prune_dir(&DataKind::Undo.path()?, 0, config.max_undo_snapshots)?
+        // Scripts are kept as long as the transcripts that ran them
+        + prune_dir(&DataKind::Scratch.path()?, config.max_session_age_days, 0)?)
+}
+
+/// Securely deletes all stored data of `kind`. Returns the number of files removed.
+pub fn purge(kind: DataKind) -> Result<usize, ShellmindError> {
+    let path = kind.path()?;
+    if path.is_file() {
+        secure_delete(&path)?;
+        return Ok(1);
+    }
+    let mut count = 0;
+    for (file, _) in files_by_age(&path) {
+        secure_delete(&file)?;
+        count += 1;
+    }
+    if path.exists() {
+        std::fs::remove_dir_all(&path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to remove '{}': {}", path.display(), e)))?;
+    }
+    Ok(count)
+}
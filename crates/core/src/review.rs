@@ -0,0 +1,129 @@
+//! `shellmind review`: collects a diff and asks the model for a structured review.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+use crate::ShellmindError;
+
+/// Diffs larger than this are cut before being sent to the model.
+const MAX_DIFF_CHARS: usize = 200_000;
+
+#[derive(Debug, Clone)]
+pub enum DiffSource {
+    /// Unstaged changes in the working tree.
+    Working,
+    Staged,
+    /// A git revision range such as `main..HEAD`.
+    Range(String),
+    /// A patch file.
+    Patch(std::path::PathBuf),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    /// `high`, `medium` or `low`.
+    pub severity: String,
+    /// `bug`, `security` or `style`.
+    pub category: String,
+    #[serde(default)]
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReview {
+    pub path: String,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Review {
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub files: Vec<FileReview>,
+}
+
+fn git_diff(args: &[&str]) -> Result<String, ShellmindError> {
+    let output = Command::new("git")
+        .arg("diff")
+        .args(args)
+        .output()
+        .map_err(|e| ShellmindError::Other(format!("Failed to run git diff: {}", e)))?;
+    if !output.status.success() {
+        return Err(ShellmindError::Other(format!("git diff failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn collect_diff(source: &DiffSource) -> Result<String, ShellmindError> {
+    let diff = match source {
+        DiffSource::Working => git_diff(&[])?,
+        DiffSource::Staged => git_diff(&["--staged"])?,
+        DiffSource::Range(range) => git_diff(&[range.as_str()])?,
+        DiffSource::Patch(path) => std::fs::read_to_string(path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to read patch '{}': {}", path.display(), e)))?,
+    };
+    if diff.trim().is_empty() {
+        return Err(ShellmindError::Other("The diff is empty; nothing to review".to_string()));
+    }
+    Ok(diff)
+}
+
+pub fn build_review_prompt(diff: &str) -> String {
+    let diff = if diff.len() > MAX_DIFF_CHARS {
+        let mut end = MAX_DIFF_CHARS;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}\n[diff truncated]", &diff[..end])
+    } else {
+        diff.to_string()
+    };
+    format!(
+        "Review the following diff. Look for bugs, security issues and style problems in the changed lines. \
+         Reply with JSON only, in the form \
+         {{\"summary\": \"...\", \"files\": [{{\"path\": \"...\", \"findings\": [{{\"severity\": \"high|medium|low\", \"category\": \"bug|security|style\", \"line\": 12, \"message\": \"...\"}}]}}]}}. \
+         Use line numbers of the new file and leave out files without findings.\n\n{}",
+        diff
+    )
+}
+
+/// Parses the model's reply, tolerating a surrounding Markdown code fence.
+pub fn parse_review(reply: &str) -> Result<Review, ShellmindError> {
+    let trimmed = reply.trim();
+    let json = match (trimmed.find('{'), trimmed.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => return Err(ShellmindError::Other("The model did not return a JSON review".to_string())),
+    };
+    Ok(serde_json::from_str(json)?)
+}
+
+impl Review {
+    pub fn finding_count(&self) -> usize {
+        self.files.iter().map(|f| f.findings.len()).sum()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Code Review\n\n");
+        if !self.summary.is_empty() {
+            markdown.push_str(&format!("{}\n\n", self.summary.trim()));
+        }
+        for file in &self.files {
+            markdown.push_str(&format!("## `{}`\n\n", file.path));
+            for finding in &file.findings {
+                let location = finding.line.map(|l| format!(" (line {})", l)).unwrap_or_default();
+                markdown.push_str(&format!(
+                    "- **{}** {}{}: {}\n",
+                    finding.severity.to_uppercase(),
+                    finding.category,
+                    location,
+                    finding.message
+                ));
+            }
+            markdown.push('\n');
+        }
+        markdown
+    }
+}
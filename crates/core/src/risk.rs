@@ -0,0 +1,123 @@
+//! One-sentence explanations of what a risky command will do, shown in its confirmation
+//! prompt, e.g. "deletes 1.2k files under ./build irrecoverably". The parser works out
+//! deletions it can count; the model explains the rest.
+
+use std::path::Path;
+
+use crate::{shell_parser, RiskAssessment, SafetyLevel};
+
+/// Files counted under a deletion target before the count is reported as "more than".
+const MAX_COUNTED: usize = 100_000;
+
+/// Longest explanation shown, in characters.
+const MAX_EXPLANATION_CHARS: usize = 160;
+
+/// Counts regular files under `path` without following symlinks, up to `limit`.
+fn count_files(path: &Path, limit: usize) -> usize {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return 1;
+    }
+    let mut count = 0;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => pending.push(entry.path()),
+                Ok(_) => count += 1,
+                Err(_) => {}
+            }
+            if count >= limit {
+                return count;
+            }
+        }
+    }
+    count
+}
+
+/// `1234` becomes `1.2k`, `2500000` becomes `2.5M`.
+fn human_count(count: usize) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => format!("{:.1}k", count as f64 / 1_000.0),
+        _ => format!("{:.1}M", count as f64 / 1_000_000.0),
+    }
+}
+
+/// What the `rm` stages of `command` delete, with file counts, e.g. "deletes 1.2k files
+/// under ./build irrecoverably". Targets are resolved against `cwd` and globs expanded.
+fn deletion(command: &str, cwd: &Path) -> Option<String> {
+    let parsed = shell_parser::parse(command);
+    let mut targets: Vec<String> = Vec::new();
+    let mut count = 0;
+    for stage in &parsed.commands {
+        let words = stage.effective_words();
+        if words.first().map_or(true, |w| w.value.rsplit('/').next() != Some("rm")) {
+            continue;
+        }
+        for word in words[1..].iter().filter(|w| !w.value.starts_with('-')) {
+            if word.dynamic {
+                return None;
+            }
+            let pattern = cwd.join(&word.value);
+            let matches: Vec<_> = glob::glob(&pattern.to_string_lossy()).map(|paths| paths.flatten().collect()).unwrap_or_default();
+            for path in matches {
+                count += count_files(&path, MAX_COUNTED.saturating_sub(count));
+            }
+            targets.push(word.value.clone());
+        }
+    }
+    if targets.is_empty() {
+        return None;
+    }
+    let amount = if count >= MAX_COUNTED { format!("more than {}", human_count(MAX_COUNTED)) } else { human_count(count) };
+    let files = if count == 1 { "file" } else { "files" };
+    Some(match targets.as_slice() {
+        [target] if Path::new(&cwd.join(target)).is_dir() => format!("deletes {} {} under {} irrecoverably", amount, files, target),
+        _ => format!("deletes {} {} ({}) irrecoverably", amount, files, targets.join(" ")),
+    })
+}
+
+/// An explanation from the parser alone; `None` for safe commands.
+pub fn local_explanation(command: &str, cwd: &Path, risk: &RiskAssessment) -> Option<String> {
+    if risk.level == SafetyLevel::Safe {
+        return None;
+    }
+    // The counted deletion replaces the analysis' own "deletes" reasons
+    let mut parts: Vec<String> = deletion(command, cwd).into_iter().collect();
+    let counted = !parts.is_empty();
+    parts.extend(risk.reasons.iter().filter(|r| !(counted && r.contains("deletes"))).cloned());
+    (!parts.is_empty()).then(|| parts.join("; "))
+}
+
+pub fn build_explanation_prompt(command: &str, risk: &RiskAssessment, facts: Option<&str>, language_code: &str) -> String {
+    let mut prompt = format!(
+        "In one short sentence in {}, say concretely what the shell command below will do that the user should weigh before \
+         approving it: what it deletes, overwrites or changes, where, and whether it can be undone. No preamble, no \
+         command, no advice.\n\nCommand: {}\nRisks found by static analysis: {}",
+        crate::translate::language_name(language_code),
+        command,
+        risk.reasons.join("; ")
+    );
+    if let Some(facts) = facts {
+        prompt.push_str(&format!("\nWhat Shellmind worked out: {}", facts));
+    }
+    prompt
+}
+
+/// The first line of the model's reply, without quotes, code or a final period, shortened;
+/// `None` when nothing usable is left.
+pub fn clean_explanation(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|l| !l.is_empty() && !l.starts_with("```"))?;
+    let line = line.trim_matches(|c: char| c == '"' || c == '\'' || c == '`' || c == '*').trim_end_matches('.').trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(match line.char_indices().nth(MAX_EXPLANATION_CHARS) {
+        Some((end, _)) => format!("{}…", line[..end].trim_end()),
+        None => line.to_string(),
+    })
+}
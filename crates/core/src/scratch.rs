@@ -0,0 +1,142 @@
+//! Multi-line scripts: scratch files for scripts the model wants to run, written to
+//! `~/.shellmind/scratch/<session>/` and kept for later review, and complete script
+//! files generated by `shellmind script`.
+
+use std::path::{Path, PathBuf};
+
+use crate::retention::DataKind;
+use crate::ShellmindError;
+
+#[derive(Debug, Clone)]
+pub struct Script {
+    /// Language as named in the code fence or shebang, e.g. `bash` or `python`.
+    pub language: String,
+    pub body: String,
+}
+
+impl Script {
+    /// Interpreter and file extension for the script's language.
+    pub fn interpreter(&self) -> Option<(&'static str, &'static str)> {
+        Some(match self.language.as_str() {
+            "bash" | "shell" => ("bash", "sh"),
+            "sh" => ("sh", "sh"),
+            "zsh" => ("zsh", "zsh"),
+            "python" | "python3" | "py" => ("python3", "py"),
+            "javascript" | "js" | "node" => ("node", "js"),
+            "ruby" | "rb" => ("ruby", "rb"),
+            "perl" => ("perl", "pl"),
+            _ => return None,
+        })
+    }
+}
+
+fn language_from_shebang(line: &str) -> Option<String> {
+    let command = line.strip_prefix("#!")?.trim();
+    // `#!/usr/bin/env python3` names the interpreter as an argument
+    let program = match command.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [env, program, ..] if env.ends_with("/env") => program.to_string(),
+        [path, ..] => Path::new(path).file_name()?.to_string_lossy().into_owned(),
+        [] => return None,
+    };
+    Some(program)
+}
+
+/// The script in `reply`: a fenced code block in a supported language, or a reply that
+/// starts with a shebang. Single-line blocks are left to normal command handling.
+pub fn extract(reply: &str) -> Option<Script> {
+    let trimmed = reply.trim();
+    let script = if trimmed.starts_with("#!") {
+        Script { language: language_from_shebang(trimmed.lines().next()?)?, body: trimmed.to_string() }
+    } else {
+        let start = trimmed.find("```")?;
+        let after_fence = &trimmed[start + 3..];
+        let (language, rest) = after_fence.split_once('\n')?;
+        let end = rest.find("```")?;
+        let body = rest[..end].trim_end().to_string();
+        let language = match language.trim().to_lowercase() {
+            language if language.is_empty() => body.lines().next().and_then(language_from_shebang)?,
+            language => language,
+        };
+        Script { language, body }
+    };
+    let lines = script.body.lines().filter(|l| !l.trim().is_empty()).count();
+    (lines >= 2 && script.interpreter().is_some()).then_some(script)
+}
+
+pub fn session_dir(session_id: &str) -> Result<PathBuf, ShellmindError> {
+    Ok(DataKind::Scratch.path()?.join(session_id))
+}
+
+/// Writes `script` to the session's scratch directory, readable and executable only by
+/// the owner. Returns its path.
+pub fn write(session_id: &str, script: &Script) -> Result<PathBuf, ShellmindError> {
+    let (_, extension) = script
+        .interpreter()
+        .ok_or_else(|| ShellmindError::Other(format!("Unsupported script language: {}", script.language)))?;
+    let dir = session_dir(session_id)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", dir.display(), e)))?;
+    let path = dir.join(format!("script-{}.{}", chrono::Utc::now().format("%Y%m%d-%H%M%S"), extension));
+    let mut body = script.body.clone();
+    body.push('\n');
+    crate::persist::atomic_write(&path, body)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| ShellmindError::Other(format!("Failed to set permissions on '{}': {}", path.display(), e)))?;
+    }
+    Ok(path)
+}
+
+/// Shell command running the script at `path` with its interpreter.
+pub fn command_for(script: &Script, path: &Path) -> Option<String> {
+    let (interpreter, _) = script.interpreter()?;
+    Some(format!("{} '{}'", interpreter, path.display().to_string().replace('\'', "'\\''")))
+}
+
+/// Asks for a complete, commented script in `language` that performs `task`.
+pub fn build_script_prompt(task: &str, language: &str) -> String {
+    let conventions = match language {
+        "python" => "Target Python 3 with only the standard library unless the task needs more. Start with `#!/usr/bin/env python3`, put the logic in functions with a `main()` guarded by `if __name__ == \"__main__\":`, parse arguments with argparse and exit non-zero on errors.",
+        _ => "Start with `#!/usr/bin/env bash` and `set -euo pipefail`, quote every expansion, accept inputs as arguments with a usage message, and make the script pass shellcheck.",
+    };
+    format!(
+        "Write a complete {} script for this task: {}\n\n{} Begin with a comment block describing what the script does, its arguments and an example invocation, and comment each non-obvious step. Reply with the script only, without Markdown fences or explanations.",
+        language, task, conventions
+    )
+}
+
+/// Removes a Markdown fence the model wrapped the script in despite being asked not to.
+pub fn strip_fences(reply: &str) -> String {
+    let trimmed = reply.trim();
+    match trimmed.strip_prefix("```") {
+        Some(rest) => {
+            let body = rest.split_once('\n').map_or("", |(_, body)| body);
+            body.trim_end().strip_suffix("```").unwrap_or(body).trim_end().to_string()
+        }
+        None => trimmed.to_string(),
+    }
+}
+
+/// File name for a generated script, derived from the first words of the task.
+pub fn default_file_name(task: &str, extension: &str) -> String {
+    let slug: Vec<String> = task
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(4)
+        .map(str::to_lowercase)
+        .collect();
+    let stem = if slug.is_empty() { "script".to_string() } else { slug.join("-") };
+    format!("{}.{}", stem, extension)
+}
+
+/// Linter invocation for a generated script: shellcheck for shell, py_compile for Python.
+pub fn lint_command(language: &str, path: &Path) -> Option<(&'static str, Vec<String>)> {
+    let path = path.display().to_string();
+    match language {
+        "bash" | "sh" => Some(("shellcheck", vec![path])),
+        "python" => Some(("python3", vec!["-m".to_string(), "py_compile".to_string(), path])),
+        _ => None,
+    }
+}
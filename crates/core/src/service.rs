@@ -0,0 +1,116 @@
+//! `shellmind install-service`: generates a systemd user unit (Linux) or launchd agent
+//! (macOS) that starts `shellmind daemon` at login, and removes it again.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{persist, ShellmindError};
+
+const SYSTEMD_UNIT: &str = "shellmind.service";
+const LAUNCHD_LABEL: &str = "dev.shellmind.daemon";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServiceManager {
+    Systemd,
+    Launchd,
+}
+
+impl ServiceManager {
+    pub fn detect() -> Result<Self, ShellmindError> {
+        if cfg!(target_os = "macos") {
+            Ok(ServiceManager::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Ok(ServiceManager::Systemd)
+        } else {
+            Err(ShellmindError::Other("Services are supported on Linux (systemd) and macOS (launchd) only".to_string()))
+        }
+    }
+
+    /// Where the unit or agent definition is installed.
+    pub fn definition_path(self) -> Result<PathBuf, ShellmindError> {
+        let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+        Ok(match self {
+            ServiceManager::Systemd => home_dir.join(".config/systemd/user").join(SYSTEMD_UNIT),
+            ServiceManager::Launchd => home_dir.join("Library/LaunchAgents").join(format!("{}.plist", LAUNCHD_LABEL)),
+        })
+    }
+
+    /// Unit or plist running `executable daemon`.
+    pub fn definition(self, executable: &Path) -> Result<String, ShellmindError> {
+        let executable = executable.display().to_string();
+        Ok(match self {
+            ServiceManager::Systemd => format!(
+                "[Unit]\nDescription=Shellmind daemon\nAfter=network-online.target\n\n[Service]\nExecStart=\"{}\" daemon\nRestart=on-failure\nRestartSec=5\n\n[Install]\nWantedBy=default.target\n",
+                executable.replace('"', "\\\"")
+            ),
+            ServiceManager::Launchd => {
+                let log = self.definition_path()?.with_file_name("shellmind-daemon.log");
+                let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+                format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n<plist version=\"1.0\">\n<dict>\n  <key>Label</key>\n  <string>{}</string>\n  <key>ProgramArguments</key>\n  <array>\n    <string>{}</string>\n    <string>daemon</string>\n  </array>\n  <key>RunAtLoad</key>\n  <true/>\n  <key>KeepAlive</key>\n  <dict>\n    <key>SuccessfulExit</key>\n    <false/>\n  </dict>\n  <key>StandardErrorPath</key>\n  <string>{}</string>\n</dict>\n</plist>\n",
+                    LAUNCHD_LABEL,
+                    escape(&executable),
+                    escape(&log.display().to_string())
+                )
+            }
+        })
+    }
+
+    fn control(self, install: bool, path: &Path) -> Result<(), ShellmindError> {
+        let path = path.display().to_string();
+        let commands: Vec<Vec<&str>> = match (self, install) {
+            (ServiceManager::Systemd, true) => vec![
+                vec!["systemctl", "--user", "daemon-reload"],
+                vec!["systemctl", "--user", "enable", "--now", SYSTEMD_UNIT],
+            ],
+            (ServiceManager::Systemd, false) => vec![
+                vec!["systemctl", "--user", "disable", "--now", SYSTEMD_UNIT],
+                vec!["systemctl", "--user", "daemon-reload"],
+            ],
+            (ServiceManager::Launchd, true) => vec![vec!["launchctl", "load", "-w", &path]],
+            (ServiceManager::Launchd, false) => vec![vec!["launchctl", "unload", "-w", &path]],
+        };
+        for command in commands {
+            let output = Command::new(command[0])
+                .args(&command[1..])
+                .output()
+                .map_err(|e| ShellmindError::Other(format!("Failed to run {}: {}", command[0], e)))?;
+            if !output.status.success() {
+                return Err(ShellmindError::Other(format!(
+                    "`{}` failed: {}",
+                    command.join(" "),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the definition for the running executable and starts the service. Returns the
+/// definition's path.
+pub fn install() -> Result<PathBuf, ShellmindError> {
+    let manager = ServiceManager::detect()?;
+    let executable = std::env::current_exe()
+        .map_err(|e| ShellmindError::Other(format!("Failed to locate the shellmind executable: {}", e)))?;
+    let path = manager.definition_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", parent.display(), e)))?;
+    }
+    persist::atomic_write(&path, manager.definition(&executable)?)?;
+    manager.control(true, &path)?;
+    Ok(path)
+}
+
+/// Stops the service and removes its definition.
+pub fn uninstall() -> Result<PathBuf, ShellmindError> {
+    let manager = ServiceManager::detect()?;
+    let path = manager.definition_path()?;
+    if !path.exists() {
+        return Err(ShellmindError::Other("The Shellmind service is not installed".to_string()));
+    }
+    manager.control(false, &path)?;
+    std::fs::remove_file(&path).map_err(|e| ShellmindError::Other(format!("Failed to remove '{}': {}", path.display(), e)))?;
+    Ok(path)
+}
@@ -0,0 +1,432 @@
+//! Conversation sessions and their transcripts persisted under `~/.shellmind/sessions/`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::context::{initial_history, PREAMBLE_LEN};
+use crate::{GeminiContent, GeminiPart, MemoryManager, ShellmindConfig, ShellmindError};
+
+/// Longest pinned file included in a request.
+const MAX_PINNED_FILE_BYTES: usize = 32 * 1024;
+
+/// Longest session title, in characters.
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Context marked with `/pin`, sent with every request of the session regardless of how
+/// much of the history has been summarized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pin {
+    /// Read fresh on every request, so edits are picked up.
+    File(PathBuf),
+    Note(String),
+}
+
+impl Pin {
+    /// A file when `target` names an existing file, a note otherwise.
+    pub fn parse(target: &str) -> Self {
+        let path = PathBuf::from(target);
+        if path.is_file() {
+            Pin::File(path.canonicalize().unwrap_or(path))
+        } else {
+            Pin::Note(target.to_string())
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Pin::File(path) => path.display().to_string(),
+            Pin::Note(note) => note.clone(),
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Pin::File(path) if crate::redact::active().blocks(path) => format!("File {}: {}", path.display(), crate::redact::NOTE),
+            Pin::File(path) => match std::fs::read(path) {
+                Ok(bytes) => {
+                    let end = bytes.len().min(MAX_PINNED_FILE_BYTES);
+                    let marker = if end < bytes.len() { "\n[truncated]" } else { "" };
+                    format!("File {}:\n```\n{}{}\n```", path.display(), String::from_utf8_lossy(&bytes[..end]), marker)
+                }
+                Err(e) => format!("File {} could not be read: {}", path.display(), e),
+            },
+            Pin::Note(note) => format!("Note: {}", note),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionFile {
+    pub id: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Every turn of the conversation, including those later folded into a summary.
+    pub transcript: Vec<GeminiContent>,
+    #[serde(default)]
+    pub pins: Vec<Pin>,
+    /// Short description of what the session is about, set after the first turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Models that answered in this session.
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Directory Shellmind was started in, so later runs there can pick up its context.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl SessionFile {
+    pub fn new() -> Result<Self, ShellmindError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = Self::sessions_dir()?.join(format!("{}.json", id));
+        Ok(Self {
+            id,
+            created_at: chrono::Utc::now(),
+            transcript: Vec::new(),
+            pins: Vec::new(),
+            title: None,
+            models: Vec::new(),
+            working_dir: std::env::current_dir().ok(),
+            path,
+        })
+    }
+
+    /// Loads a previously saved session by ID so it can be continued.
+    pub fn load(id: &str) -> Result<Self, ShellmindError> {
+        // IDs can come from clients; anything but a UUID could name a path outside the directory
+        uuid::Uuid::parse_str(id).map_err(|_| ShellmindError::Other(format!("Invalid session ID: {}", id)))?;
+        let path = Self::sessions_dir()?.join(format!("{}.json", id));
+        let content = crate::persist::read_private(&path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to read session '{}': {}", id, e)))?;
+        let mut session: SessionFile = serde_json::from_str(&content)?;
+        session.path = path;
+        Ok(session)
+    }
+
+    pub fn sessions_dir() -> Result<PathBuf, ShellmindError> {
+        let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+        let sessions_dir = home_dir.join(".shellmind").join("sessions");
+        std::fs::create_dir_all(&sessions_dir)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create sessions directory: {}", e)))?;
+        Ok(sessions_dir)
+    }
+
+    /// Appends `content` with secrets masked; the live history keeps the real values.
+    pub fn append(&mut self, mut content: GeminiContent) -> Result<(), ShellmindError> {
+        for part in &mut content.parts {
+            part.text = crate::secrets::mask(&part.text);
+        }
+        self.transcript.push(content);
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<(), ShellmindError> {
+        let json = serde_json::to_string_pretty(self)?;
+        crate::persist::locked_write(&self.path, crate::persist::seal(json)?)
+    }
+}
+
+/// A title made from the first prompt, used until or instead of a generated one.
+pub fn fallback_title(prompt: &str) -> String {
+    clean_title(prompt.lines().next().unwrap_or_default())
+}
+
+/// Collapses whitespace, strips quotes and trailing punctuation and shortens to
+/// `MAX_TITLE_CHARS`.
+pub fn clean_title(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = text.trim_matches(|c: char| c == '"' || c == '\'' || c == '`' || c == '.' || c == '#' || c.is_whitespace());
+    match text.char_indices().nth(MAX_TITLE_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+pub fn build_title_prompt(first_prompt: &str) -> String {
+    format!(
+        "Write a title of at most six words for a terminal assistant session that starts with the request below. \
+         Use the language of the request. Reply with the title only, without quotes.\n\n{}",
+        first_prompt
+    )
+}
+
+/// What `shellmind session list` shows for a saved session.
+#[derive(Debug, Clone)]
+pub struct SessionSummary {
+    pub id: String,
+    pub title: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// User requests in the transcript.
+    pub turns: usize,
+    pub models: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+}
+
+impl SessionSummary {
+    fn from_file(file: &SessionFile) -> Self {
+        let title = file.title.clone().unwrap_or_else(|| {
+            let first = file.transcript.iter().find(|c| c.role == "user").and_then(|c| c.parts.first());
+            first.map(|p| fallback_title(&p.text)).unwrap_or_default()
+        });
+        SessionSummary {
+            id: file.id.clone(),
+            title,
+            created_at: file.created_at,
+            turns: file.transcript.iter().filter(|c| c.role == "user").count(),
+            models: file.models.clone(),
+            working_dir: file.working_dir.clone(),
+        }
+    }
+
+    /// How well the title matches `query`: the share of query words found in the title,
+    /// exactly or as a close typo.
+    fn relevance(&self, query: &str) -> f64 {
+        let title: Vec<String> = self.title.split_whitespace().map(str::to_lowercase).collect();
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return 1.0;
+        }
+        let found = words
+            .iter()
+            .filter(|word| title.iter().any(|t| t.contains(word.as_str()) || crate::fuzzy::similarity(t, word) >= 0.85))
+            .count();
+        found as f64 / words.len() as f64
+    }
+}
+
+/// Saved sessions that have at least one turn, newest first. Sessions that can't be read
+/// are skipped.
+pub fn list_sessions() -> Result<Vec<SessionSummary>, ShellmindError> {
+    let dir = SessionFile::sessions_dir()?;
+    let entries = std::fs::read_dir(&dir)
+        .map_err(|e| ShellmindError::Other(format!("Failed to read '{}': {}", dir.display(), e)))?;
+    let mut sessions: Vec<SessionSummary> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().strip_suffix(".json")?.to_string();
+            SessionFile::load(&id).ok()
+        })
+        .filter(|file| !file.transcript.is_empty())
+        .map(|file| SessionSummary::from_file(&file))
+        .collect();
+    sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(sessions)
+}
+
+/// Sessions whose titles match `query`, best matches first and newest first among equals.
+pub fn search_sessions(sessions: Vec<SessionSummary>, query: &str) -> Vec<SessionSummary> {
+    let mut scored: Vec<(f64, SessionSummary)> = sessions
+        .into_iter()
+        .map(|session| (session.relevance(query), session))
+        .filter(|(score, _)| *score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| b.1.created_at.cmp(&a.1.created_at)));
+    scored.into_iter().map(|(_, session)| session).collect()
+}
+
+/// The newest saved session started in `dir`, other than `current`.
+pub fn latest_in_dir(dir: &Path, current: &str) -> Option<SessionSummary> {
+    list_sessions().ok()?.into_iter().find(|s| s.id != current && s.working_dir.as_deref() == Some(dir))
+}
+
+/// The exchange that carries a previous session's summarized context into a new one.
+pub fn carried_context(title: &str, summary: &str) -> Vec<GeminiContent> {
+    vec![
+        GeminiContent {
+            role: "user".to_string(),
+            parts: vec![GeminiPart {
+                text: format!(
+                    "Context from an earlier session in this directory (\"{}\"); it may include build quirks, environment variables and decisions that still apply:\n{}",
+                    title,
+                    summary.trim()
+                ),
+                inline_data: None,
+            }],
+        },
+        GeminiContent {
+            role: "model".to_string(),
+            parts: vec![GeminiPart { text: "Understood, I'll keep that context in mind.".to_string(), inline_data: None }],
+        },
+    ]
+}
+
+/// Isolated conversation state: history, memory, working directory and jobs. Each
+/// terminal, editor connection or server client works on its own `Session`.
+pub struct Session {
+    pub file: SessionFile,
+    pub history: Vec<GeminiContent>,
+    pub memory: MemoryManager,
+    pub working_dir: PathBuf,
+    /// Background commands and agent steps, for `/jobs` and `/panes`.
+    pub jobs: crate::jobs::Jobs,
+}
+
+impl Session {
+    pub fn new(config: &ShellmindConfig) -> Result<Self, ShellmindError> {
+        Ok(Session {
+            file: SessionFile::new()?,
+            history: initial_history(config),
+            memory: MemoryManager::new(),
+            working_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            jobs: Default::default(),
+        })
+    }
+
+    /// Continues a saved session, replaying its transcript after the system prompt.
+    pub fn resume(id: &str, config: &ShellmindConfig) -> Result<Self, ShellmindError> {
+        let file = SessionFile::load(id)?;
+        let mut history = initial_history(config);
+        history.extend(file.transcript.iter().cloned());
+        Ok(Session {
+            file,
+            history,
+            memory: MemoryManager::new(),
+            working_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            jobs: Default::default(),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.file.id
+    }
+
+    /// Pins `pin` unless it is already pinned. Returns false for duplicates.
+    pub fn pin(&mut self, pin: Pin) -> Result<bool, ShellmindError> {
+        if self.file.pins.contains(&pin) {
+            return Ok(false);
+        }
+        self.file.pins.push(pin);
+        self.file.save()?;
+        Ok(true)
+    }
+
+    /// Removes the pin at 1-based `target` (as listed by `/pins`) or with a matching label.
+    pub fn unpin(&mut self, target: &str) -> Result<Option<Pin>, ShellmindError> {
+        let index = match target.parse::<usize>() {
+            Ok(n) if (1..=self.file.pins.len()).contains(&n) => Some(n - 1),
+            _ => {
+                let wanted = Pin::parse(target);
+                self.file.pins.iter().position(|p| *p == wanted || p.label() == target)
+            }
+        };
+        let Some(index) = index else {
+            return Ok(None);
+        };
+        let removed = self.file.pins.remove(index);
+        self.file.save()?;
+        Ok(Some(removed))
+    }
+
+    pub fn pins(&self) -> &[Pin] {
+        &self.file.pins
+    }
+
+    /// The history sent to the model: `history` with the pinned context inserted right
+    /// after the system prompt, where summarization never reaches it.
+    pub fn request_history(&self) -> Vec<GeminiContent> {
+        if self.file.pins.is_empty() {
+            return self.history.clone();
+        }
+        let pinned = self.file.pins.iter().map(Pin::render).collect::<Vec<_>>().join("\n\n");
+        let mut history = self.history.clone();
+        let at = PREAMBLE_LEN.min(history.len());
+        history.splice(
+            at..at,
+            [
+                GeminiContent {
+                    role: "user".to_string(),
+                    parts: vec![GeminiPart { text: format!("Pinned context; keep it in mind for every request:\n\n{}", pinned), inline_data: None }],
+                },
+                GeminiContent {
+                    role: "model".to_string(),
+                    parts: vec![GeminiPart { text: "Understood.".to_string(), inline_data: None }],
+                },
+            ],
+        );
+        history
+    }
+
+    pub fn set_title(&mut self, title: &str) -> Result<(), ShellmindError> {
+        self.file.title = Some(clean_title(&crate::secrets::mask(title)));
+        self.file.save()
+    }
+
+    /// Notes that `model` answered in this session; saved with the next turn.
+    pub fn note_model(&mut self, model: &str) {
+        if !self.file.models.iter().any(|m| m == model) {
+            self.file.models.push(model.to_string());
+        }
+    }
+
+    /// Appends a user/model exchange to the history and the persisted transcript.
+    pub fn record_turn(&mut self, input: &str, response: &str) -> Result<(), ShellmindError> {
+        for (role, text) in [("user", input), ("model", response)] {
+            let content = GeminiContent {
+                role: role.to_string(),
+                parts: vec![GeminiPart { text: text.to_string(), inline_data: None }],
+            };
+            self.file.append(content.clone())?;
+            self.history.push(content);
+        }
+        Ok(())
+    }
+}
+
+pub type SharedSession = Arc<Mutex<Session>>;
+
+/// Sessions of one long-lived process, keyed by ID.
+#[derive(Clone, Default)]
+pub struct SessionManager {
+    sessions: Arc<Mutex<HashMap<String, SharedSession>>>,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn create(&self, config: &ShellmindConfig) -> Result<SharedSession, ShellmindError> {
+        let session = Session::new(config)?;
+        let id = session.id().to_string();
+        let session = Arc::new(Mutex::new(session));
+        self.sessions.lock().await.insert(id, Arc::clone(&session));
+        Ok(session)
+    }
+
+    /// Returns the live session with `id`, resuming it from disk if this process hasn't seen it.
+    pub async fn get_or_resume(&self, id: &str, config: &ShellmindConfig) -> Result<SharedSession, ShellmindError> {
+        let mut sessions = self.sessions.lock().await;
+        if let Some(session) = sessions.get(id) {
+            return Ok(Arc::clone(session));
+        }
+        let session = Arc::new(Mutex::new(Session::resume(id, config)?));
+        sessions.insert(id.to_string(), Arc::clone(&session));
+        Ok(session)
+    }
+
+    pub async fn remove(&self, id: &str) -> Option<SharedSession> {
+        self.sessions.lock().await.remove(id)
+    }
+
+    pub async fn ids(&self) -> Vec<String> {
+        self.sessions.lock().await.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_rejects_non_uuid_ids() {
+        for id in ["../config", "../../etc/passwd", "abc/def", ""] {
+            let err = SessionFile::load(id).unwrap_err();
+            assert!(err.to_string().contains("Invalid session ID"), "{}: {}", id, err);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! `shellmind session share`: a saved session as a redacted Markdown transcript, for
+//! asking a teammate to look over a plan. Secrets are masked, the workspace redaction
+//! profile is applied and home directories are shortened to `~`; the result can be
+//! uploaded as a secret gist or to a paste service.
+
+use crate::github::GitHubClient;
+use crate::redact::Profile;
+use crate::session::SessionFile;
+use crate::{secrets, ShellmindConfig, ShellmindError};
+
+/// Turns longer than this are cut in the transcript.
+const MAX_TURN_CHARS: usize = 8_000;
+
+/// `text` with secrets masked, the profile applied and the home directory replaced by `~`.
+pub fn redact(text: &str, profile: &Profile) -> String {
+    let mut text = profile.redact(&secrets::mask(text));
+    if let Some(home) = dirs::home_dir() {
+        let home = home.to_string_lossy();
+        if home.len() > 1 {
+            text = text.replace(home.as_ref(), "~");
+        }
+    }
+    text
+}
+
+fn shorten(text: &str) -> String {
+    match text.char_indices().nth(MAX_TURN_CHARS) {
+        Some((end, _)) => format!("{}\n\n_[… {} more characters]_", &text[..end], text[end..].chars().count()),
+        None => text.to_string(),
+    }
+}
+
+/// The session as Markdown, redacted with `profile`.
+pub fn render(session: &SessionFile, profile: &Profile) -> String {
+    let title = session.title.clone().unwrap_or_else(|| "Shellmind session".to_string());
+    let mut markdown = format!("# {}\n\n", redact(&title, profile));
+    markdown.push_str(&format!("_{}", session.created_at.format("%Y-%m-%d %H:%M UTC")));
+    if !session.models.is_empty() {
+        markdown.push_str(&format!(" · {}", session.models.join(", ")));
+    }
+    markdown.push_str(" · secrets and policy-protected content redacted_\n");
+    for turn in &session.transcript {
+        let text = turn.parts.iter().map(|p| p.text.as_str()).collect::<Vec<_>>().join("\n");
+        let heading = if turn.role == "user" { "User" } else { "Shellmind" };
+        markdown.push_str(&format!("\n## {}\n\n{}\n", heading, shorten(redact(text.trim(), profile).as_str())));
+    }
+    markdown
+}
+
+/// File name of the shared transcript, e.g. `shellmind-3f2a9c1e.md`.
+pub fn file_name(session: &SessionFile) -> String {
+    format!("shellmind-{}.md", session.id.chars().take(8).collect::<String>())
+}
+
+/// Uploads `markdown` as a gist, secret unless `public`, and returns its URL.
+pub async fn upload_gist(config: &ShellmindConfig, session: &SessionFile, markdown: &str, public: bool) -> Result<String, ShellmindError> {
+    let description = session.title.clone().unwrap_or_else(|| "Shellmind session".to_string());
+    GitHubClient::from_config(config).create_gist(&description, &file_name(session), markdown, public).await
+}
+
+/// Posts `markdown` to `share_paste_url`, authenticated with the `share_paste_token_secret`
+/// token when one is stored, and returns the URL from the reply: a `url` or `link` field
+/// of a JSON reply, or the first line of a plain one.
+pub async fn upload_paste(config: &ShellmindConfig, markdown: &str) -> Result<String, ShellmindError> {
+    if config.share_paste_url.is_empty() {
+        return Err(ShellmindError::Other("No paste service configured; set share_paste_url".to_string()));
+    }
+    let mut request = crate::http_client()
+        .post(&config.share_paste_url)
+        .header("User-Agent", "shellmind")
+        .header("Content-Type", "text/markdown; charset=utf-8")
+        .body(markdown.to_string());
+    if let Ok(token) = extensions::secure_storage::retrieve_secret(&config.share_paste_token_secret) {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(ShellmindError::Other(format!("The paste service returned {}: {}", status, body.trim())));
+    }
+    let url = match serde_json::from_str::<serde_json::Value>(&body) {
+        Ok(reply) => ["url", "link", "html_url"].iter().find_map(|key| reply[key].as_str().map(str::to_string)),
+        Err(_) => body.lines().map(str::trim).find(|l| l.starts_with("http")).map(str::to_string),
+    };
+    url.ok_or_else(|| ShellmindError::Other("The paste service did not return a URL".to_string()))
+}
@@ -0,0 +1,177 @@
+//! The user's shell aliases and functions, captured by the `shellmind init` hook into
+//! `~/.shellmind/shell-env.sh`, so the model can use and explain them and executed
+//! commands behave as they would in the user's shell.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::ShellmindError;
+
+/// Longest function body included in the system prompt.
+const MAX_PROMPT_BODY_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Default)]
+pub struct ShellEnv {
+    pub aliases: BTreeMap<String, String>,
+    /// Function name to full definition.
+    pub functions: BTreeMap<String, String>,
+}
+
+pub fn env_file_path() -> Result<PathBuf, ShellmindError> {
+    let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+    Ok(home_dir.join(".shellmind").join("shell-env.sh"))
+}
+
+/// Ctrl-G widget for bash: replaces the line being edited with the command generated
+/// for it, leaving it to be reviewed and run with Enter.
+const BASH_WIDGET: &str = r#"__shellmind_widget() {
+  [ -n "$READLINE_LINE" ] || return
+  local suggestion
+  suggestion="$(shellmind suggest --print "$READLINE_LINE")" || return
+  READLINE_LINE="$suggestion"
+  READLINE_POINT=${#READLINE_LINE}
+}
+bind -x '"\C-g": __shellmind_widget'
+"#;
+
+const ZSH_WIDGET: &str = r#"__shellmind_widget() {
+  [[ -n "$BUFFER" ]] || return
+  local suggestion
+  suggestion="$(shellmind suggest --print "$BUFFER")" || { zle reset-prompt; return; }
+  BUFFER="$suggestion"
+  CURSOR=${#BUFFER}
+  zle reset-prompt
+}
+zle -N __shellmind_widget
+bindkey '^G' __shellmind_widget
+"#;
+
+/// Shell snippet for `eval "$(shellmind init bash)"`: wraps `shellmind` so every run
+/// first dumps the current aliases and functions, and binds Ctrl-G to turn the line
+/// being edited into a generated command.
+pub fn init_script(shell: &str) -> Result<String, ShellmindError> {
+    let (dump_functions, widget) = match shell {
+        "bash" => ("declare -f", BASH_WIDGET),
+        "zsh" => ("functions", ZSH_WIDGET),
+        _ => return Err(ShellmindError::Other(format!("Unsupported shell '{}'; use bash or zsh", shell))),
+    };
+    Ok(format!(
+        "shellmind() {{\n  mkdir -p \"$HOME/.shellmind\"\n  {{ alias; {}; }} > \"$HOME/.shellmind/shell-env.sh\" 2>/dev/null\n  command shellmind \"$@\"\n}}\n{}",
+        dump_functions, widget
+    ))
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.len() >= 2 && ((value.starts_with('\'') && value.ends_with('\'')) || (value.starts_with('"') && value.ends_with('"'))) {
+        // bash writes an embedded quote as '\''
+        value[1..value.len() - 1].replace("'\\''", "'")
+    } else {
+        value.to_string()
+    }
+}
+
+/// `name () {` (bash `declare -f`) or `name () {` / `name() {` (zsh `functions`).
+fn function_name(line: &str) -> Option<&str> {
+    let name = line.strip_suffix('{')?.trim_end().strip_suffix("()")?.trim_end();
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || "_-:.".contains(c));
+    valid.then_some(name)
+}
+
+impl ShellEnv {
+    /// Loads the dump written by the init hook; empty when the hook isn't installed.
+    pub fn load() -> Self {
+        env_file_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// Parses `alias` output (bash `alias k='kubectl'`, zsh `k=kubectl`) followed by
+    /// function definitions.
+    pub fn parse(content: &str) -> Self {
+        let mut env = ShellEnv::default();
+        let mut current: Option<(String, Vec<&str>)> = None;
+        for line in content.lines() {
+            if let Some((name, body)) = current.as_mut() {
+                body.push(line);
+                if line == "}" {
+                    env.functions.insert(std::mem::take(name), body.join("\n"));
+                    current = None;
+                }
+                continue;
+            }
+            if let Some(name) = function_name(line) {
+                current = Some((name.to_string(), vec![line]));
+                continue;
+            }
+            let definition = line.strip_prefix("alias ").unwrap_or(line);
+            if let Some((name, value)) = definition.split_once('=') {
+                if !name.is_empty() && !name.contains(char::is_whitespace) {
+                    env.aliases.insert(name.to_string(), unquote(value));
+                }
+            }
+        }
+        env
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty() && self.functions.is_empty()
+    }
+
+    /// Note for the system prompt listing the aliases and functions.
+    pub fn prompt_note(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut note = String::from("The user's shell defines these aliases and functions. You may use them in commands and should explain them when asked:");
+        for (name, value) in &self.aliases {
+            note.push_str(&format!("\n- alias {} = {}", name, value));
+        }
+        for (name, body) in &self.functions {
+            let body: String = body.chars().take(MAX_PROMPT_BODY_CHARS).collect();
+            note.push_str(&format!("\n- function {}:\n{}", name, body));
+        }
+        note
+    }
+
+    /// Rewrites `command` so it runs under `sh`: aliases at the start of each pipeline
+    /// stage are expanded and definitions of functions it calls are prepended.
+    pub fn expand(&self, command: &str) -> String {
+        if self.is_empty() {
+            return command.to_string();
+        }
+        let mut expanded = String::with_capacity(command.len());
+        let mut rest = command;
+        while !rest.is_empty() {
+            let split = rest.find(|c: char| c == '|' || c == ';' || c == '&' || c == '\n').unwrap_or(rest.len());
+            let (stage, tail) = rest.split_at(split);
+            let trimmed = stage.trim_start();
+            let leading = &stage[..stage.len() - trimmed.len()];
+            let (word, args) = trimmed.split_at(trimmed.find(char::is_whitespace).unwrap_or(trimmed.len()));
+            match self.aliases.get(word) {
+                Some(value) => expanded.push_str(&format!("{}{}{}", leading, value, args)),
+                None => expanded.push_str(stage),
+            }
+            let mut chars = tail.chars();
+            if let Some(separator) = chars.next() {
+                expanded.push(separator);
+            }
+            rest = chars.as_str();
+        }
+
+        let words: Vec<&str> = expanded.split(|c: char| c.is_whitespace() || "|;&()".contains(c)).collect();
+        let definitions: Vec<&str> = self
+            .functions
+            .iter()
+            .filter(|(name, _)| words.contains(&name.as_str()))
+            .map(|(_, body)| body.as_str())
+            .collect();
+        if definitions.is_empty() {
+            expanded
+        } else {
+            format!("{}\n{}", definitions.join("\n"), expanded)
+        }
+    }
+}
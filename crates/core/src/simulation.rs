@@ -0,0 +1,80 @@
+//! Simulated execution (`--simulate`) for evaluating what the model
+//! would do. Approvals, risk checks and the audit trail work as usual, but commands are
+//! not run and tools that change or reach outside the machine return a canned result.
+//! Audit events written meanwhile are marked `simulated`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::progress::ProgressSender;
+use crate::{BaseTool, ConfirmationDetails, SafetyLevel, ShellmindError, ToolResult};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Tools that only read local files or compute; they run for real so the model sees real input.
+pub const READ_ONLY_TOOLS: &[&str] = &["read_file", "list_directory", "search_file_content", "glob", "read_many_files", "diff", "tree", "github_list_issues", "github_pr_diff", "docker_list_containers", "docker_logs", "calculate"];
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// What a simulated command "printed".
+pub fn command_output(command: &str) -> String {
+    format!("[simulated] `{}` was not executed", crate::secrets::mask(command))
+}
+
+/// A tool whose calls are answered without running it.
+pub struct SimulatedTool {
+    inner: Box<dyn BaseTool>,
+}
+
+impl SimulatedTool {
+    pub fn new(inner: Box<dyn BaseTool>) -> Self {
+        SimulatedTool { inner }
+    }
+}
+
+impl BaseTool for SimulatedTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.inner.display_name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn parameter_schema(&self) -> serde_json::Value {
+        self.inner.parameter_schema()
+    }
+
+    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
+        self.inner.validate_tool_params(params)
+    }
+
+    fn get_description(&self, params: &serde_json::Value) -> String {
+        self.inner.get_description(params)
+    }
+
+    fn should_confirm_execute(&self, params: &serde_json::Value) -> Option<ConfirmationDetails> {
+        self.inner.should_confirm_execute(params)
+    }
+
+    fn safety_level(&self, params: &serde_json::Value) -> SafetyLevel {
+        self.inner.safety_level(params)
+    }
+
+    fn execute(&self, params: serde_json::Value, _signal: Option<tokio::signal::unix::Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let display = format!("[simulated] {} was not run: {}", self.inner.name(), self.inner.get_description(&params));
+        let content = serde_json::json!({ "simulated": true, "tool": self.inner.name(), "params": params });
+        Box::pin(async move { Ok(ToolResult::success(display).with_content(content)) })
+    }
+}
@@ -0,0 +1,106 @@
+//! Lightweight snapshot of the working directory taken when the REPL starts: top-level
+//! entries, the start of the README and the git branch and status.
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::{GeminiContent, GeminiPart};
+
+const MAX_ENTRIES: usize = 100;
+const README_LINES: usize = 100;
+const MAX_STATUS_LINES: usize = 20;
+
+fn top_level_entries(dir: &Path) -> Option<String> {
+    let mut entries: Vec<String> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            if e.file_type().map_or(false, |t| t.is_dir()) {
+                format!("{}/", name)
+            } else {
+                name
+            }
+        })
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+    entries.sort();
+    let total = entries.len();
+    entries.truncate(MAX_ENTRIES);
+    let mut listing = entries.join("\n");
+    if total > MAX_ENTRIES {
+        listing.push_str(&format!("\n... and {} more", total - MAX_ENTRIES));
+    }
+    Some(listing)
+}
+
+fn readme(dir: &Path) -> Option<(String, String)> {
+    let path = ["README.md", "README", "README.rst", "README.txt", "readme.md"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file() && !crate::redact::active().blocks(path))?;
+    let content = std::fs::read_to_string(&path).ok()?;
+    let head = content.lines().take(README_LINES).collect::<Vec<_>>().join("\n");
+    Some((path.file_name()?.to_string_lossy().into_owned(), head))
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+/// Current branch of the repository containing `dir`.
+pub fn git_branch(dir: &Path) -> Option<String> {
+    git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])
+}
+
+fn git_summary(dir: &Path) -> Option<String> {
+    let branch = git_branch(dir)?;
+    let status = git(dir, &["status", "--short"]).unwrap_or_default();
+    let changed: Vec<&str> = status.lines().collect();
+    let mut summary = format!("Branch: {}", branch);
+    if changed.is_empty() {
+        summary.push_str("\nWorking tree clean");
+    } else {
+        summary.push_str(&format!("\n{} changed file(s):\n", changed.len()));
+        summary.push_str(&changed.iter().take(MAX_STATUS_LINES).copied().collect::<Vec<_>>().join("\n"));
+        if changed.len() > MAX_STATUS_LINES {
+            summary.push_str("\n...");
+        }
+    }
+    Some(summary)
+}
+
+/// Describes `dir`; `None` when there is nothing worth sending.
+pub fn take(dir: &Path) -> Option<String> {
+    let mut sections = Vec::new();
+    if let Some(listing) = top_level_entries(dir) {
+        sections.push(format!("Top-level entries:\n{}", listing));
+    }
+    if let Some((name, head)) = readme(dir) {
+        sections.push(format!("{} (first {} lines):\n{}", name, README_LINES, head));
+    }
+    if let Some(summary) = git_summary(dir) {
+        sections.push(format!("Git:\n{}", summary));
+    }
+    if sections.is_empty() {
+        return None;
+    }
+    Some(format!("Snapshot of the current directory ({}), taken at startup:\n\n{}", dir.display(), sections.join("\n\n")))
+}
+
+/// The snapshot as a user/model exchange to add to the history.
+pub fn exchange(snapshot: &str) -> [GeminiContent; 2] {
+    [
+        GeminiContent {
+            role: "user".to_string(),
+            parts: vec![GeminiPart { text: snapshot.to_string(), inline_data: None }],
+        },
+        GeminiContent {
+            role: "model".to_string(),
+            parts: vec![GeminiPart { text: "Got it, I'll use this when you ask about the project.".to_string(), inline_data: None }],
+        },
+    ]
+}
@@ -0,0 +1,269 @@
+//! `shellmind tf-review`: reads a Terraform plan, from `terraform plan -json` or a saved
+//! plan file, lists the resources it creates, changes and destroys with the risky ones
+//! highlighted, and builds the prompts that summarize it and answer questions about it.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ShellmindError;
+
+/// Where `tf-review` saves the plan it runs, so `apply` applies exactly what was reviewed.
+pub const PLAN_FILE: &str = ".shellmind.tfplan";
+
+/// Resource types whose replacement or deletion loses data.
+const STATEFUL_TYPES: &[&str] = &[
+    "db_instance", "rds_cluster", "sql_database", "s3_bucket", "storage_bucket", "ebs_volume", "compute_disk", "managed_disk",
+    "dynamodb_table", "efs_file_system", "elasticache", "redshift", "storage_account", "kms_key", "bigquery", "cosmosdb",
+];
+
+/// Resource types that control access or network exposure.
+const ACCESS_TYPES: &[&str] = &["iam", "security_group", "firewall", "network_acl", "policy", "role", "route", "kms", "key_vault_access"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Create,
+    Update,
+    /// Destroyed and created again.
+    Replace,
+    Delete,
+}
+
+impl Action {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Action::Create => "+",
+            Action::Update => "~",
+            Action::Replace => "-/+",
+            Action::Delete => "-",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "create" => Some(Action::Create),
+            "update" => Some(Action::Update),
+            "replace" => Some(Action::Replace),
+            "delete" => Some(Action::Delete),
+            _ => None,
+        }
+    }
+
+    /// From the `actions` list of the JSON plan representation; reads and no-ops are `None`.
+    fn from_actions(actions: &[&str]) -> Option<Self> {
+        match actions {
+            [single] => Self::from_name(single),
+            [a, b] if actions.contains(&"create") && actions.contains(&"delete") && a != b => Some(Action::Replace),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourceChange {
+    pub address: String,
+    pub resource_type: String,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    pub changes: Vec<ResourceChange>,
+    /// Warnings and errors Terraform reported while planning.
+    pub diagnostics: Vec<String>,
+    /// Saved plan `terraform apply` can run, relative to `dir`; `None` for plans read from
+    /// JSON output.
+    pub plan_file: Option<PathBuf>,
+    /// Terraform configuration directory the plan belongs to.
+    pub dir: PathBuf,
+}
+
+fn terraform(args: &[&str], dir: &Path) -> Result<std::process::Output, ShellmindError> {
+    Command::new("terraform")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| ShellmindError::Other(format!("Failed to run terraform: {}", e)))
+}
+
+/// Parses the machine-readable lines of `terraform plan -json`.
+fn parse_stream(output: &str) -> Plan {
+    let mut plan = Plan::default();
+    for message in output.lines().filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok()) {
+        match message["type"].as_str() {
+            Some("planned_change") => {
+                let change = &message["change"];
+                if let Some(action) = change["action"].as_str().and_then(Action::from_name) {
+                    plan.changes.push(ResourceChange {
+                        address: change["resource"]["addr"].as_str().unwrap_or_default().to_string(),
+                        resource_type: change["resource"]["resource_type"].as_str().unwrap_or_default().to_string(),
+                        action,
+                    });
+                }
+            }
+            Some("diagnostic") => {
+                let diagnostic = &message["diagnostic"];
+                plan.diagnostics.push(format!(
+                    "{}: {}",
+                    diagnostic["severity"].as_str().unwrap_or("warning"),
+                    diagnostic["summary"].as_str().unwrap_or_default()
+                ));
+            }
+            _ => {}
+        }
+    }
+    plan
+}
+
+/// Parses the JSON plan representation printed by `terraform show -json <plan>`.
+fn parse_representation(value: &serde_json::Value) -> Plan {
+    let changes = value["resource_changes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|resource| {
+            let actions: Vec<&str> = resource["change"]["actions"].as_array()?.iter().filter_map(|a| a.as_str()).collect();
+            Some(ResourceChange {
+                address: resource["address"].as_str()?.to_string(),
+                resource_type: resource["type"].as_str().unwrap_or_default().to_string(),
+                action: Action::from_actions(&actions)?,
+            })
+        })
+        .collect();
+    Plan { changes, ..Default::default() }
+}
+
+/// Runs `terraform plan -json` in `dir`, saving the plan to `PLAN_FILE` there.
+pub fn run_plan(dir: &Path) -> Result<Plan, ShellmindError> {
+    let out = format!("-out={}", PLAN_FILE);
+    let output = terraform(&["plan", "-json", "-input=false", &out], dir)?;
+    let mut plan = parse_stream(&String::from_utf8_lossy(&output.stdout));
+    if !output.status.success() {
+        let errors: Vec<&String> = plan.diagnostics.iter().filter(|d| d.starts_with("error")).collect();
+        let detail = if errors.is_empty() {
+            String::from_utf8_lossy(&output.stderr).trim().to_string()
+        } else {
+            errors.iter().map(|e| e.as_str()).collect::<Vec<_>>().join("; ")
+        };
+        return Err(ShellmindError::Other(format!("terraform plan failed: {}", detail)));
+    }
+    plan.plan_file = Some(PathBuf::from(PLAN_FILE));
+    plan.dir = dir.to_path_buf();
+    Ok(plan)
+}
+
+/// Reads a saved plan: a binary plan file (converted with `terraform show -json`), the
+/// JSON representation, or the saved output of `terraform plan -json`.
+pub fn read_plan(path: &Path) -> Result<Plan, ShellmindError> {
+    let bytes = std::fs::read(path).map_err(|e| ShellmindError::Other(format!("Failed to read plan '{}': {}", path.display(), e)))?;
+    if let Ok(text) = std::str::from_utf8(&bytes) {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(text) {
+            if value.get("resource_changes").is_some() || value.get("format_version").is_some() {
+                return Ok(parse_representation(&value));
+            }
+        }
+        let plan = parse_stream(text);
+        if !plan.changes.is_empty() || text.lines().any(|line| line.contains("\"planned_change\"") || line.contains("\"change_summary\"")) {
+            return Ok(plan);
+        }
+    }
+    // Plan files are relative to the configuration they were made from
+    let output = terraform(&["show", "-json", &path.to_string_lossy()], Path::new("."))?;
+    if !output.status.success() {
+        return Err(ShellmindError::Other(format!("terraform show failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    Ok(Plan { plan_file: Some(path.to_path_buf()), dir: PathBuf::from("."), ..parse_representation(&value) })
+}
+
+impl Plan {
+    fn count(&self, matches: impl Fn(Action) -> bool) -> usize {
+        self.changes.iter().filter(|c| matches(c.action)).count()
+    }
+
+    /// Resources to add, change and destroy, counted the way Terraform does: a
+    /// replacement is both an addition and a destruction.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        (
+            self.count(|a| matches!(a, Action::Create | Action::Replace)),
+            self.count(|a| a == Action::Update),
+            self.count(|a| matches!(a, Action::Delete | Action::Replace)),
+        )
+    }
+
+    /// Resources the plan destroys, including replaced ones.
+    pub fn destroyed(&self) -> Vec<&ResourceChange> {
+        self.changes.iter().filter(|c| matches!(c.action, Action::Delete | Action::Replace)).collect()
+    }
+
+    /// Risky changes with their severity (`high` or `medium`).
+    pub fn risks(&self) -> Vec<(&'static str, String)> {
+        let mut risks = Vec::new();
+        for change in &self.changes {
+            let stateful = STATEFUL_TYPES.iter().any(|t| change.resource_type.contains(t));
+            let access = ACCESS_TYPES.iter().any(|t| change.resource_type.contains(t));
+            match change.action {
+                Action::Delete if stateful => risks.push(("high", format!("destroys {}, and the data in it", change.address))),
+                Action::Delete => risks.push(("high", format!("destroys {}", change.address))),
+                Action::Replace if stateful => risks.push(("high", format!("replaces {}; its data is lost unless it is backed up", change.address))),
+                Action::Replace => risks.push(("high", format!("replaces {} (destroyed, then created again)", change.address))),
+                Action::Update | Action::Create if access => {
+                    risks.push(("medium", format!("changes access or network rules: {}", change.address)));
+                }
+                _ => {}
+            }
+        }
+        risks
+    }
+
+    /// One line per change, e.g. `-/+ aws_instance.web`.
+    pub fn listing(&self) -> String {
+        self.changes.iter().map(|c| format!("{} {}", c.action.symbol(), c.address)).collect::<Vec<_>>().join("\n")
+    }
+}
+
+fn plan_context(plan: &Plan) -> String {
+    let (add, change, destroy) = plan.counts();
+    let mut context = format!(
+        "Terraform plan: {} to add, {} to change, {} to destroy. Changes (+ create, ~ update, -/+ replace, - destroy):\n{}",
+        add,
+        change,
+        destroy,
+        plan.listing()
+    );
+    if !plan.diagnostics.is_empty() {
+        context.push_str(&format!("\nTerraform diagnostics:\n{}", plan.diagnostics.join("\n")));
+    }
+    context
+}
+
+pub fn build_summary_prompt(plan: &Plan) -> String {
+    format!(
+        "Summarize the following Terraform plan for the engineer about to apply it. Group the resources into creations, \
+         changes and destroys, call out anything risky (destroyed or replaced stateful resources, access and network \
+         changes, unexpected replacements) and say what to check before applying. Be concise and use Markdown.\n\n{}",
+        plan_context(plan)
+    )
+}
+
+pub fn build_question_prompt(plan: &Plan, question: &str) -> String {
+    format!(
+        "Answer the question about the Terraform plan below. Use only what the plan shows and say so when it doesn't tell. \
+         Don't propose commands to run.\n\n{}\n\nQuestion: {}",
+        plan_context(plan),
+        question
+    )
+}
+
+fn shell_quote(value: &Path) -> String {
+    format!("'{}'", value.to_string_lossy().replace('\'', r"'\''"))
+}
+
+/// The command that applies exactly the reviewed plan, if it was saved.
+pub fn apply_command(plan: &Plan) -> Option<String> {
+    let plan_file = plan.plan_file.as_ref()?;
+    Some(if plan.dir.as_os_str().is_empty() || plan.dir == Path::new(".") {
+        format!("terraform apply -input=false {}", shell_quote(plan_file))
+    } else {
+        format!("terraform -chdir={} apply -input=false {}", shell_quote(&plan.dir), shell_quote(plan_file))
+    })
+}
@@ -0,0 +1,253 @@
+//! `shellmind test`: runs the project's tests and extracts failures from cargo test,
+//! pytest and jest output so the first one can be handed to the model.
+
+use std::process::Command;
+
+use crate::ShellmindError;
+
+/// Lines of failure output passed to the model.
+const MAX_DETAIL_LINES: usize = 60;
+
+#[derive(Debug, Clone)]
+pub struct TestRun {
+    pub command: String,
+    pub exit_code: Option<i32>,
+    /// Combined stdout and stderr; cargo test prints failures on stdout, pytest on both.
+    pub output: String,
+}
+
+impl TestRun {
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestFailure {
+    pub name: String,
+    pub details: String,
+}
+
+pub fn run_tests(command: &str) -> Result<TestRun, ShellmindError> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .map_err(|e| ShellmindError::Other(format!("Failed to run test command '{}': {}", command, e)))?;
+    Ok(TestRun {
+        command: command.to_string(),
+        exit_code: output.status.code(),
+        output: format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)),
+    })
+}
+
+/// Parses failures in whichever supported format the output uses, in report order.
+pub fn parse_failures(output: &str) -> Vec<TestFailure> {
+    let failures = parse_cargo(output);
+    if !failures.is_empty() {
+        return failures;
+    }
+    let failures = parse_pytest(output);
+    if !failures.is_empty() {
+        return failures;
+    }
+    parse_jest(output)
+}
+
+/// Collects the lines after each header until the next header or a terminator.
+fn blocks(lines: &[&str], header: impl Fn(&str) -> Option<String>, end: impl Fn(&str) -> bool) -> Vec<TestFailure> {
+    let mut failures: Vec<TestFailure> = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in lines {
+        if let Some(name) = header(line) {
+            if let Some((name, body)) = current.take() {
+                failures.push(TestFailure { name, details: join_details(&body) });
+            }
+            current = Some((name, Vec::new()));
+        } else if end(line) {
+            if let Some((name, body)) = current.take() {
+                failures.push(TestFailure { name, details: join_details(&body) });
+            }
+        } else if let Some((_, body)) = current.as_mut() {
+            body.push(line);
+        }
+    }
+    if let Some((name, body)) = current {
+        failures.push(TestFailure { name, details: join_details(&body) });
+    }
+    failures
+}
+
+fn join_details(lines: &[&str]) -> String {
+    lines.iter().take(MAX_DETAIL_LINES).copied().collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+/// `---- tests::name stdout ----` blocks, or `test name ... FAILED` lines when output was captured.
+fn parse_cargo(output: &str) -> Vec<TestFailure> {
+    let lines: Vec<&str> = output.lines().collect();
+    let failures = blocks(
+        &lines,
+        |line| {
+            line.strip_prefix("---- ")
+                .and_then(|rest| rest.strip_suffix(" stdout ----"))
+                .map(str::to_string)
+        },
+        |line| line.trim() == "failures:" || line.starts_with("test result:"),
+    );
+    if !failures.is_empty() {
+        return failures;
+    }
+    lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("test ")?.strip_suffix(" ... FAILED"))
+        .map(|name| TestFailure { name: name.to_string(), details: String::new() })
+        .collect()
+}
+
+/// `____ test_name ____` blocks of the failures section, or `FAILED path::test - message` summary lines.
+fn parse_pytest(output: &str) -> Vec<TestFailure> {
+    let lines: Vec<&str> = output.lines().collect();
+    let failures = blocks(
+        &lines,
+        |line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with("___") && trimmed.ends_with("___") {
+                let name = trimmed.trim_matches('_').trim();
+                (!name.is_empty()).then(|| name.to_string())
+            } else {
+                None
+            }
+        },
+        |line| line.starts_with("====="),
+    );
+    if !failures.is_empty() {
+        return failures;
+    }
+    lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("FAILED "))
+        .map(|rest| {
+            let (name, message) = rest.split_once(" - ").unwrap_or((rest, ""));
+            TestFailure { name: name.trim().to_string(), details: message.trim().to_string() }
+        })
+        .collect()
+}
+
+/// `● Suite › test name` blocks.
+fn parse_jest(output: &str) -> Vec<TestFailure> {
+    let lines: Vec<&str> = output.lines().collect();
+    blocks(
+        &lines,
+        |line| {
+            line.trim_start()
+                .strip_prefix("● ")
+                .filter(|name| !name.starts_with("Console"))
+                .map(|name| name.trim().to_string())
+        },
+        |line| line.starts_with("Test Suites:"),
+    )
+}
+
+/// Asks the model to explain a failing test, or to fix it with the file tools.
+pub fn build_test_prompt(run: &TestRun, failure: &TestFailure, explain_only: bool) -> String {
+    let details = if failure.details.is_empty() {
+        let tail: Vec<&str> = run.output.lines().rev().take(MAX_DETAIL_LINES).collect();
+        tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+    } else {
+        failure.details.clone()
+    };
+    let task = if explain_only {
+        "Explain why this test fails and what change would fix it. Do not modify any files.".to_string()
+    } else {
+        "Find the cause and fix it. You may call tools using the form `tool_name({json params})`, one call per line and nothing else, \
+         e.g. `read_file({\"path\": \"src/lib.rs\"})`, `edit_file({\"file_path\": \"...\", \"old_string\": \"...\", \"new_string\": \"...\"})` \
+         or `write_file({\"path\": \"...\", \"content\": \"...\"})`. Several reads in one reply run in parallel, so batch the files you need. Each tool result is sent back to you. \
+         Prefer fixing the code under test over changing the test's expectations. When you are done, reply with a short explanation of the fix and no tool call."
+            .to_string()
+    };
+    format!(
+        "The test command `{}` failed (exit code {}). The first failing test is `{}`:\n{}\n\n{}",
+        run.command,
+        run.exit_code.map(|c| c.to_string()).unwrap_or_else(|| "unknown".to_string()),
+        failure.name,
+        details,
+        task
+    )
+}
+
+/// Feeds a tool result back to the model during a multi-step workflow.
+pub fn build_tool_result_prompt(tool_name: &str, result: &crate::ToolResult) -> String {
+    let status = if result.is_error { "failed" } else { "succeeded" };
+    let content = match &result.llm_content {
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    format!("Tool `{}` {}:\n{}\n\nContinue with the next tool call, or reply with a short explanation when done.", tool_name, status, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_blocks() {
+        let output = "running 2 tests\ntest tests::ok ... ok\ntest tests::bad ... FAILED\n\nfailures:\n\n---- tests::bad stdout ----\nthread 'tests::bad' panicked at src/lib.rs:10:5:\nassertion failed\n\nfailures:\n    tests::bad\n\ntest result: FAILED. 1 passed; 1 failed";
+        assert_eq!(
+            parse_failures(output),
+            vec![TestFailure { name: "tests::bad".to_string(), details: "thread 'tests::bad' panicked at src/lib.rs:10:5:\nassertion failed".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_parse_cargo_summary_lines() {
+        let output = "test tests::ok ... ok\ntest tests::bad ... FAILED\ntest result: FAILED. 1 passed; 1 failed";
+        assert_eq!(parse_failures(output), vec![TestFailure { name: "tests::bad".to_string(), details: String::new() }]);
+    }
+
+    #[test]
+    fn test_parse_pytest_blocks() {
+        let output = "===== FAILURES =====\n_____ test_add _____\n\n    def test_add():\n>       assert add(1, 1) == 3\nE       assert 2 == 3\n\ntest_math.py:4: AssertionError\n===== short test summary info =====\nFAILED test_math.py::test_add - assert 2 == 3";
+        let failures = parse_failures(output);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "test_add");
+        assert!(failures[0].details.contains("E       assert 2 == 3"));
+        assert!(!failures[0].details.contains("short test summary"));
+    }
+
+    #[test]
+    fn test_parse_pytest_summary_lines() {
+        let output = "FAILED test_math.py::test_add - assert 2 == 3\nFAILED test_math.py::test_sub";
+        assert_eq!(
+            parse_failures(output),
+            vec![
+                TestFailure { name: "test_math.py::test_add".to_string(), details: "assert 2 == 3".to_string() },
+                TestFailure { name: "test_math.py::test_sub".to_string(), details: String::new() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_jest_blocks() {
+        let output = "FAIL src/sum.test.js\n  ● Console\n\n    console.log hello\n\n  ● sum › adds numbers\n\n    expect(received).toBe(expected)\n\nTest Suites: 1 failed, 1 total";
+        let failures = parse_failures(output);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "sum › adds numbers");
+        assert_eq!(failures[0].details, "expect(received).toBe(expected)");
+    }
+
+    #[test]
+    fn test_parse_failures_without_a_known_format() {
+        assert!(parse_failures("error: could not compile `demo`").is_empty());
+    }
+
+    #[test]
+    fn test_explain_prompt_asks_for_no_changes() {
+        let run = TestRun { command: "cargo test".to_string(), exit_code: Some(101), output: "last line".to_string() };
+        let failure = TestFailure { name: "tests::bad".to_string(), details: String::new() };
+        let prompt = build_test_prompt(&run, &failure, true);
+        assert!(prompt.contains("exit code 101"));
+        assert!(prompt.contains("last line"));
+        assert!(prompt.contains("Do not modify any files"));
+        assert!(!prompt.contains("edit_file"));
+    }
+}
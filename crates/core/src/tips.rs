@@ -0,0 +1,47 @@
+//! Which built-in commands were used when, kept in `~/.shellmind/features.json`, so the
+//! startup tip can point at features the user hasn't tried lately.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::{persist, ShellmindError};
+
+/// A feature not used for this many days counts as unused.
+pub const UNUSED_AFTER_DAYS: i64 = 30;
+
+fn usage_path() -> Result<PathBuf, ShellmindError> {
+    let home_dir = dirs::home_dir().ok_or_else(|| ShellmindError::Other("Could not find home directory.".to_string()))?;
+    Ok(home_dir.join(".shellmind").join("features.json"))
+}
+
+fn load(path: &PathBuf) -> HashMap<String, DateTime<Utc>> {
+    std::fs::read_to_string(path).ok().and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+}
+
+/// Notes that `feature` (a command such as `/retry`) was just used.
+pub fn record_use(feature: &str) -> Result<(), ShellmindError> {
+    let path = usage_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", dir.display(), e)))?;
+    }
+    persist::with_lock(&path, || {
+        let mut used = load(&path);
+        used.insert(feature.to_string(), Utc::now());
+        persist::atomic_write(&path, serde_json::to_string_pretty(&used)?)
+    })
+}
+
+/// Features used within the last `UNUSED_AFTER_DAYS` days.
+pub fn recently_used() -> Vec<String> {
+    let Ok(path) = usage_path() else {
+        return Vec::new();
+    };
+    let now = Utc::now();
+    load(&path)
+        .into_iter()
+        .filter(|(_, last)| (now - *last).num_days() < UNUSED_AFTER_DAYS)
+        .map(|(feature, _)| feature)
+        .collect()
+}
@@ -0,0 +1,469 @@
+//! Test harness for tools, behind the `tool-harness` feature: a throwaway `TempWorkspace`,
+//! golden-output snapshots of tool results, and the conformance checks every built-in,
+//! custom or plugin tool is expected to pass.
+//!
+//! Snapshots live in `<dir>/<name>.snap` and are committed with the tests. A missing or
+//! different snapshot fails; set `SHELLMIND_UPDATE_SNAPSHOTS=1` to record or rewrite them.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::{BaseTool, ShellmindError, ToolResult};
+
+/// Placeholder the workspace root is replaced with in snapshots.
+const WORKSPACE_PLACEHOLDER: &str = "<workspace>";
+
+/// How long a tool is left running before the cancellation check signals it.
+const CANCEL_AFTER: Duration = Duration::from_millis(20);
+
+/// How soon a signalled tool must return.
+const CANCEL_DEADLINE: Duration = Duration::from_secs(2);
+
+/// A temporary directory for one test, removed when dropped.
+pub struct TempWorkspace {
+    root: PathBuf,
+}
+
+impl TempWorkspace {
+    pub fn new() -> Result<Self, ShellmindError> {
+        let root = std::env::temp_dir().join(format!("shellmind-tool-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root)
+            .map_err(|e| ShellmindError::Other(format!("Failed to create test workspace '{}': {}", root.display(), e)))?;
+        // Snapshots must not depend on symlinked temp directories such as /tmp on macOS
+        let root = root.canonicalize().unwrap_or(root);
+        Ok(TempWorkspace { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Absolute path of `relative` inside the workspace.
+    pub fn path(&self, relative: &str) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    /// `path` as a string, for tool parameters.
+    pub fn param(&self, relative: &str) -> String {
+        self.path(relative).to_string_lossy().into_owned()
+    }
+
+    /// Writes a file, creating its parent directories.
+    pub fn file(&self, relative: &str, content: &str) -> Result<PathBuf, ShellmindError> {
+        let path = self.path(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ShellmindError::Other(format!("Failed to create '{}': {}", parent.display(), e)))?;
+        }
+        std::fs::write(&path, content).map_err(|e| ShellmindError::Other(format!("Failed to write '{}': {}", path.display(), e)))?;
+        Ok(path)
+    }
+
+    pub fn read(&self, relative: &str) -> Result<String, ShellmindError> {
+        let path = self.path(relative);
+        std::fs::read_to_string(&path).map_err(|e| ShellmindError::Other(format!("Failed to read '{}': {}", path.display(), e)))
+    }
+
+    /// `text` with the workspace root replaced by `<workspace>`.
+    pub fn normalize(&self, text: &str) -> String {
+        text.replace(&*self.root.to_string_lossy(), WORKSPACE_PLACEHOLDER)
+    }
+}
+
+impl Drop for TempWorkspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Runs `tool` with `params` as the agent would, without a signal or progress channel.
+pub async fn run_tool(tool: &dyn BaseTool, params: Value) -> Result<ToolResult, ShellmindError> {
+    tool.execute(params, None, None).await
+}
+
+/// A tool result as stable text: status, artifacts, display and (when different) the
+/// content fed back to the model. Durations are left out.
+pub fn render_result(result: &ToolResult, workspace: &TempWorkspace) -> String {
+    let mut out = format!("is_error: {}\ntruncated: {}\n", result.is_error, result.truncated);
+    if !result.artifacts.is_empty() {
+        out.push_str("artifacts:\n");
+        for artifact in &result.artifacts {
+            out.push_str(&format!("  {}\n", workspace.normalize(&artifact.to_string_lossy())));
+        }
+    }
+    out.push_str("--- display\n");
+    out.push_str(&workspace.normalize(&result.display));
+    if result.llm_content != Value::String(result.display.clone()) {
+        let content = serde_json::to_string_pretty(&result.llm_content).unwrap_or_default();
+        out.push_str("\n--- llm_content\n");
+        out.push_str(&workspace.normalize(&content));
+    }
+    out.push('\n');
+    out
+}
+
+/// Golden files in one directory, e.g. `concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots")`.
+pub struct Snapshots {
+    dir: PathBuf,
+}
+
+impl Snapshots {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Snapshots { dir: dir.into() }
+    }
+
+    /// Panics with a diff when `actual` differs from the snapshot called `name`.
+    pub fn assert(&self, name: &str, actual: &str) {
+        let path = self.dir.join(format!("{}.snap", name));
+        let update = std::env::var("SHELLMIND_UPDATE_SNAPSHOTS").map_or(false, |v| v == "1");
+        match std::fs::read_to_string(&path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) if !update => {
+                let diff = similar::TextDiff::from_lines(&expected, actual)
+                    .unified_diff()
+                    .header(&format!("{} (snapshot)", name), &format!("{} (actual)", name))
+                    .to_string();
+                panic!("Snapshot '{}' does not match; rerun with SHELLMIND_UPDATE_SNAPSHOTS=1 to accept:\n{}", path.display(), diff);
+            }
+            Err(_) if !update => {
+                panic!("Snapshot '{}' is missing; record it with SHELLMIND_UPDATE_SNAPSHOTS=1 and commit it", path.display());
+            }
+            _ => {
+                std::fs::create_dir_all(&self.dir).expect("create snapshot directory");
+                std::fs::write(&path, actual).unwrap_or_else(|e| panic!("Failed to write snapshot '{}': {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Runs `tool` and compares its rendered result to the snapshot called `name`.
+    pub async fn assert_tool(&self, name: &str, tool: &dyn BaseTool, params: Value, workspace: &TempWorkspace) {
+        let rendered = match run_tool(tool, params).await {
+            Ok(result) => render_result(&result, workspace),
+            Err(e) => format!("error: {}\n", workspace.normalize(&e.to_string())),
+        };
+        self.assert(name, &rendered);
+    }
+}
+
+/// Problems `conformance` found with one tool; empty when it passed.
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    pub tool: String,
+    pub failures: Vec<String>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Panics listing every failure.
+    pub fn assert_passed(&self) {
+        if !self.passed() {
+            panic!("Tool '{}' failed conformance:\n  - {}", self.tool, self.failures.join("\n  - "));
+        }
+    }
+}
+
+/// Types a schema allows for one property, `null` aside; empty when it accepts anything.
+fn allowed_types(schema: &Value) -> Vec<&str> {
+    match &schema["type"] {
+        Value::String(kind) => vec![kind.as_str()],
+        Value::Array(kinds) => kinds.iter().filter_map(Value::as_str).filter(|kind| *kind != "null").collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A value of a type the property does not allow.
+fn wrong_value(allowed: &[&str]) -> Option<Value> {
+    let candidates = [
+        ("object", json!({ "unexpected": true })),
+        ("boolean", json!(true)),
+        ("string", json!("unexpected")),
+        ("integer", json!(42)),
+    ];
+    candidates
+        .into_iter()
+        .find(|(kind, _)| !allowed.contains(kind) && !(*kind == "integer" && allowed.contains(&"number")))
+        .map(|(_, value)| value)
+}
+
+/// Name and description are set and the parameter schema is an object schema whose
+/// required properties exist.
+pub fn check_schema(tool: &dyn BaseTool, report: &mut ConformanceReport) {
+    let name = tool.name();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c)) {
+        report.failures.push(format!("name '{}' must be non-empty and use only letters, digits, '_', '.' or '-'", name));
+    }
+    if tool.description().trim().is_empty() {
+        report.failures.push("description is empty".to_string());
+    }
+    let schema = tool.parameter_schema();
+    if schema["type"] != "object" {
+        report.failures.push(format!("parameter schema must have type \"object\", got {}", schema["type"]));
+    }
+    let properties = schema["properties"].as_object();
+    if properties.is_none() {
+        report.failures.push("parameter schema has no properties object".to_string());
+    }
+    for required in schema["required"].as_array().into_iter().flatten() {
+        let known = required.as_str().map_or(false, |name| properties.map_or(false, |p| p.contains_key(name)));
+        if !known {
+            report.failures.push(format!("required parameter {} is not among the properties", required));
+        }
+    }
+}
+
+/// `valid` is accepted; non-objects, params missing a required property and properties
+/// of the wrong type are rejected, and executing rejected params fails without panicking.
+pub async fn check_validation(tool: &dyn BaseTool, valid: &Value, report: &mut ConformanceReport) {
+    if !tool.validate_tool_params(valid) {
+        report.failures.push(format!("valid parameters {} were rejected", valid));
+        return;
+    }
+    let schema = tool.parameter_schema();
+    let mut invalid: Vec<(String, Value)> = [json!(null), json!(42), json!("text"), json!([])]
+        .into_iter()
+        .map(|value| (format!("non-object parameters {}", value), value))
+        .collect();
+    for required in schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+        let mut params = valid.clone();
+        if let Some(object) = params.as_object_mut() {
+            object.remove(required);
+        }
+        invalid.push((format!("parameters without required '{}'", required), params));
+    }
+    for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+        let allowed = allowed_types(property);
+        if allowed.is_empty() {
+            continue;
+        }
+        if let Some(value) = wrong_value(&allowed) {
+            let mut params = valid.clone();
+            params[name.as_str()] = value.clone();
+            invalid.push((format!("'{}' set to {} (expected {})", name, value, allowed.join(" or ")), params));
+        }
+    }
+    for (case, params) in invalid {
+        if tool.validate_tool_params(&params) {
+            report.failures.push(format!("{} were accepted", case));
+            continue;
+        }
+        let tool_name = tool.name().to_string();
+        let execution = tool.execute(params, None, None);
+        match tokio::spawn(execution).await {
+            Ok(Ok(result)) if !result.is_error => report.failures.push(format!("executing with {} succeeded", case)),
+            Ok(_) => {}
+            Err(e) if e.is_panic() => report.failures.push(format!("{} panicked when executed with {}", tool_name, case)),
+            Err(_) => {}
+        }
+    }
+}
+
+/// Sends the process `SIGUSR1`, the signal `check_cancellation` hands to tools.
+fn raise_cancel_signal() -> std::io::Result<()> {
+    let status = std::process::Command::new("kill").args(["-USR1", &std::process::id().to_string()]).status()?;
+    if !status.success() {
+        return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("kill exited with {}", status)));
+    }
+    Ok(())
+}
+
+/// A call with `valid` params is given a cancellation `Signal`; once the signal is
+/// delivered the call must return within `CANCEL_DEADLINE`. Calls finishing before the
+/// signal also pass.
+pub async fn check_cancellation(tool: &dyn BaseTool, valid: &Value, report: &mut ConformanceReport) {
+    use tokio::signal::unix::{signal, SignalKind};
+    let cancel = match signal(SignalKind::user_defined1()) {
+        Ok(cancel) => cancel,
+        Err(e) => {
+            report.failures.push(format!("could not set up the cancellation signal: {}", e));
+            return;
+        }
+    };
+    let mut handle = tokio::spawn(tool.execute(valid.clone(), Some(cancel), None));
+    match tokio::time::timeout(CANCEL_AFTER, &mut handle).await {
+        Ok(Err(e)) if e.is_panic() => {
+            report.failures.push("panicked while running".to_string());
+            return;
+        }
+        Ok(_) => return,
+        Err(_) => {}
+    }
+    if let Err(e) = raise_cancel_signal() {
+        handle.abort();
+        report.failures.push(format!("could not deliver the cancellation signal: {}", e));
+        return;
+    }
+    match tokio::time::timeout(CANCEL_DEADLINE, &mut handle).await {
+        Ok(Err(e)) if e.is_panic() => report.failures.push("panicked after being cancelled".to_string()),
+        Ok(_) => {}
+        Err(_) => {
+            handle.abort();
+            report.failures.push(format!("kept running for more than {}s after being cancelled", CANCEL_DEADLINE.as_secs()));
+        }
+    }
+}
+
+/// Runs every conformance check. `valid` must be harmless to execute, e.g. paths inside a
+/// `TempWorkspace`. Use a multi-threaded runtime so a blocking tool can't stall the
+/// cancellation timer.
+pub async fn conformance(tool: &dyn BaseTool, valid: Value) -> ConformanceReport {
+    let mut report = ConformanceReport { tool: tool.name().to_string(), failures: Vec::new() };
+    check_schema(tool, &mut report);
+    check_validation(tool, &valid, &mut report).await;
+    check_cancellation(tool, &valid, &mut report).await;
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::ProgressSender;
+    use crate::tools::{DiffTool, EditTool, LSTool, ReadFileTool, TreeTool, WriteFileTool};
+    use crate::{ConfirmationDetails, TypedTool};
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use std::future::Future;
+    use std::pin::Pin;
+    use tokio::signal::unix::Signal;
+
+    /// Sleeps, returning early on cancellation only when `honors_signal` is set.
+    struct SleepTool {
+        honors_signal: bool,
+    }
+
+    #[derive(Debug, Deserialize, JsonSchema)]
+    struct SleepParams {
+        /// Seconds to sleep.
+        secs: u64,
+    }
+
+    impl TypedTool for SleepTool {
+        type Params = SleepParams;
+
+        fn name(&self) -> &'static str {
+            "sleep"
+        }
+
+        fn display_name(&self) -> &'static str {
+            "Sleep"
+        }
+
+        fn description(&self) -> &'static str {
+            "Sleeps for a number of seconds."
+        }
+
+        fn get_description(&self, params: &SleepParams) -> String {
+            format!("Sleep {}s", params.secs)
+        }
+
+        fn should_confirm_execute(&self, _params: &SleepParams) -> Option<ConfirmationDetails> {
+            None
+        }
+
+        fn execute(&self, params: SleepParams, signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+            let honors_signal = self.honors_signal;
+            Box::pin(async move {
+                let sleep = tokio::time::sleep(Duration::from_secs(params.secs));
+                match signal {
+                    Some(mut signal) if honors_signal => tokio::select! {
+                        _ = sleep => {}
+                        _ = signal.recv() => return Ok(ToolResult::error("Cancelled")),
+                    },
+                    _ => sleep.await,
+                }
+                Ok(ToolResult::success("Slept"))
+            })
+        }
+    }
+
+    fn snapshots() -> Snapshots {
+        Snapshots::new(concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots"))
+    }
+
+    fn workspace() -> TempWorkspace {
+        let workspace = TempWorkspace::new().unwrap();
+        workspace.file("notes.txt", "first line\nsecond line\n").unwrap();
+        workspace.file("src/main.rs", "fn main() {\n    println!(\"hello\");\n}\n").unwrap();
+        workspace
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_file_tools_conform() {
+        let workspace = workspace();
+        let cases: Vec<(Box<dyn BaseTool>, Value)> = vec![
+            (Box::new(ReadFileTool), json!({ "path": workspace.param("notes.txt") })),
+            (Box::new(WriteFileTool), json!({ "path": workspace.param("out.txt"), "content": "x" })),
+            (Box::new(EditTool), json!({ "file_path": workspace.param("notes.txt"), "old_string": "first", "new_string": "1st" })),
+            (Box::new(LSTool), json!({ "path": workspace.param("") })),
+            (Box::new(TreeTool), json!({ "path": workspace.param("") })),
+            (Box::new(DiffTool), json!({ "left": workspace.param("notes.txt"), "right": workspace.param("src/main.rs") })),
+            (Box::new(crate::calc::CalcTool), json!({ "expression": "1 day in hours" })),
+        ];
+        for (tool, valid) in cases {
+            conformance(tool.as_ref(), valid).await.assert_passed();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancellation_is_signal_driven() {
+        let valid = json!({ "secs": 30 });
+
+        let mut report = ConformanceReport::default();
+        check_cancellation(&SleepTool { honors_signal: true }, &valid, &mut report).await;
+        report.assert_passed();
+
+        let mut report = ConformanceReport::default();
+        check_cancellation(&SleepTool { honors_signal: false }, &valid, &mut report).await;
+        assert!(!report.passed(), "a tool ignoring the cancellation signal passed");
+    }
+
+    #[test]
+    fn test_builtin_tool_schemas_conform() {
+        use crate::tools::*;
+        let tools: Vec<Box<dyn BaseTool>> = vec![
+            Box::new(ReadFileTool),
+            Box::new(WriteFileTool),
+            Box::new(EditTool),
+            Box::new(LSTool),
+            Box::new(GrepTool { roots: Vec::new(), use_index: false }),
+            Box::new(GlobTool { roots: Vec::new() }),
+            Box::new(ShellTool),
+            Box::new(WebFetchTool),
+            Box::new(WebSearchTool),
+            Box::new(MemoryTool),
+            Box::new(ReadManyFilesTool),
+            Box::new(DiffTool),
+            Box::new(MultiEditTool),
+            Box::new(TreeTool),
+            Box::new(crate::calc::CalcTool),
+            Box::new(crate::plot::PlotTool),
+        ];
+        for tool in tools {
+            let mut report = ConformanceReport { tool: tool.name().to_string(), failures: Vec::new() };
+            check_schema(tool.as_ref(), &mut report);
+            report.assert_passed();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_tool_snapshots() {
+        let workspace = workspace();
+        let snapshots = snapshots();
+        snapshots.assert_tool("read_file", &ReadFileTool, json!({ "path": workspace.param("notes.txt") }), &workspace).await;
+        snapshots.assert_tool("read_file_missing", &ReadFileTool, json!({ "path": workspace.param("missing.txt") }), &workspace).await;
+        snapshots
+            .assert_tool("edit", &EditTool, json!({ "file_path": workspace.param("notes.txt"), "old_string": "second", "new_string": "2nd" }), &workspace)
+            .await;
+        assert_eq!(workspace.read("notes.txt").unwrap(), "first line\n2nd line\n");
+        let root = workspace.root().to_string_lossy().into_owned();
+        snapshots.assert_tool("ls", &LSTool, json!({ "path": root }), &workspace).await;
+        snapshots.assert_tool("tree", &TreeTool, json!({ "path": root }), &workspace).await;
+        snapshots
+            .assert_tool("diff", &DiffTool, json!({ "left": workspace.param("notes.txt"), "right": workspace.param("src/main.rs") }), &workspace)
+            .await;
+    }
+}
@@ -0,0 +1,160 @@
+//! Where registered tools come from, and how name collisions between sources are settled.
+//! Every tool outside the built-in set can also be called by its qualified name
+//! (`namespace.tool`, e.g. `github.create_issue`). When two sources register the same
+//! name, the source with the higher precedence keeps it and the other is only reachable
+//! qualified.
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+
+use crate::progress::ProgressSender;
+use crate::{BaseTool, ConfirmationDetails, SafetyLevel, ShellmindError, ToolResult};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToolSource {
+    Builtin,
+    /// `custom_tools` in the user's config.
+    Custom,
+    /// `.shellmind/tools` in the current project.
+    Project,
+    /// An OpenAPI spec, named after its file.
+    OpenApi(String),
+    Plugin(String),
+    Mcp(String),
+}
+
+impl ToolSource {
+    /// Namespace of qualified names; built-in tools have none.
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            ToolSource::Builtin => None,
+            ToolSource::Custom => Some("custom"),
+            ToolSource::Project => Some("project"),
+            ToolSource::OpenApi(name) | ToolSource::Plugin(name) | ToolSource::Mcp(name) => Some(name),
+        }
+    }
+
+    /// Lower wins a name: built-in tools, then the user's own definitions, then the
+    /// project's, then integrations. Equal ranks fall back to the namespace, so the outcome
+    /// never depends on load order.
+    fn rank(&self) -> u8 {
+        match self {
+            ToolSource::Builtin => 0,
+            ToolSource::Custom => 1,
+            ToolSource::Project => 2,
+            ToolSource::OpenApi(_) => 3,
+            ToolSource::Plugin(_) => 4,
+            ToolSource::Mcp(_) => 5,
+        }
+    }
+
+    /// True when `self` keeps a name that `other` also registers.
+    pub fn takes_precedence_over(&self, other: &ToolSource) -> bool {
+        (self.rank(), self.namespace()) < (other.rank(), other.namespace())
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            ToolSource::Builtin => "built-in".to_string(),
+            ToolSource::Custom => "custom (config)".to_string(),
+            ToolSource::Project => "project (.shellmind/tools)".to_string(),
+            ToolSource::OpenApi(name) => format!("OpenAPI {}", name),
+            ToolSource::Plugin(name) => format!("plugin {}", name),
+            ToolSource::Mcp(name) => format!("MCP server {}", name),
+        }
+    }
+}
+
+/// `namespace.name`, or the bare name for built-in tools.
+pub fn qualified_name(source: &ToolSource, name: &str) -> String {
+    match source.namespace() {
+        Some(namespace) => format!("{}.{}", namespace, name),
+        None => name.to_string(),
+    }
+}
+
+/// Turns a file or server name into a namespace usable in tool calls.
+pub fn namespace_from(name: &str) -> String {
+    let namespace: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect();
+    let namespace = namespace.trim_matches('_').to_string();
+    if namespace.is_empty() {
+        "unnamed".to_string()
+    } else {
+        namespace
+    }
+}
+
+/// A name registered by more than one source.
+#[derive(Debug, Clone)]
+pub struct ToolConflict {
+    pub name: String,
+    pub kept: ToolSource,
+    /// Qualified name the losing tool remains callable by.
+    pub shadowed: String,
+}
+
+/// A `'static` copy of `text` for the names and descriptions `BaseTool` returns. Each
+/// distinct string is leaked once, so registries rebuilt for every daemon connection
+/// reuse the copies instead of growing memory.
+pub fn intern(text: String) -> &'static str {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut interned = INTERNED.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(existing) = interned.get(text.as_str()) {
+        return *existing;
+    }
+    let leaked: &'static str = Box::leak(text.into_boxed_str());
+    interned.insert(leaked);
+    leaked
+}
+
+/// A tool that lost its bare name to another source, exposed under its qualified name.
+pub struct NamespacedTool {
+    inner: Box<dyn BaseTool>,
+    name: &'static str,
+}
+
+impl NamespacedTool {
+    pub fn new(inner: Box<dyn BaseTool>, qualified: String) -> Self {
+        NamespacedTool { inner, name: intern(qualified) }
+    }
+}
+
+impl BaseTool for NamespacedTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.inner.display_name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn parameter_schema(&self) -> serde_json::Value {
+        self.inner.parameter_schema()
+    }
+
+    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
+        self.inner.validate_tool_params(params)
+    }
+
+    fn get_description(&self, params: &serde_json::Value) -> String {
+        self.inner.get_description(params)
+    }
+
+    fn should_confirm_execute(&self, params: &serde_json::Value) -> Option<ConfirmationDetails> {
+        self.inner.should_confirm_execute(params)
+    }
+
+    fn safety_level(&self, params: &serde_json::Value) -> SafetyLevel {
+        self.inner.safety_level(params)
+    }
+
+    fn execute(&self, params: serde_json::Value, signal: Option<tokio::signal::unix::Signal>, progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        self.inner.execute(params, signal, progress)
+    }
+}
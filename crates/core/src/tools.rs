@@ -1,15 +1,23 @@
-use async_trait::async_trait;
-use serde_json::json;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use std::future::Future;
 use std::pin::Pin;
 use tokio::signal::unix::Signal;
 
-use crate::{BaseTool, ConfirmationDetails, ShellmindError, ToolResult};
+use crate::progress::ProgressSender;
+use crate::{ConfirmationDetails, SafetyLevel, ShellmindError, ToolResult, TypedTool};
 
 pub struct ReadFileTool;
 
-#[async_trait]
-impl BaseTool for ReadFileTool {
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadFileParams {
+    /// The path to the file to read.
+    pub path: String,
+}
+
+impl TypedTool for ReadFileTool {
+    type Params = ReadFileParams;
+
     fn name(&self) -> &'static str {
         "read_file"
     }
@@ -22,41 +30,22 @@ impl BaseTool for ReadFileTool {
         "Reads the content of a specified file."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "path": {
-                    "type": "string",
-                    "description": "The path to the file to read."
-                }
-            },
-            "required": ["path"]
-        })
-    }
-
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("path").and_then(|p| p.as_str()).is_some()
-    }
-
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let path = params.get("path").and_then(|p| p.as_str()).unwrap_or("unknown path");
-        format!("Read file: {}", path)
+    fn get_description(&self, params: &ReadFileParams) -> String {
+        format!("Read file: {}", params.path)
     }
 
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &ReadFileParams) -> Option<ConfirmationDetails> {
         None // No confirmation needed for reading files
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: ReadFileParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let path = params.get("path").and_then(|p| p.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'path' parameter for ReadFileTool".to_string())
-            })?;
+            let path = params.path;
 
-            match tokio::fs::read_to_string(path).await {
-                Ok(content) => Ok(ToolResult::Success(content)),
-                Err(e) => Ok(ToolResult::Error(format!("Failed to read file '{}': {}", path, e))),
+            match crate::redact::read_to_string(std::path::Path::new(&path)).await {
+                Ok(content) => Ok(ToolResult::success(content)),
+                Err(ShellmindError::Other(message)) => Ok(ToolResult::error(message)),
+                Err(e) => Ok(ToolResult::error(e.to_string())),
             }
         })
     }
@@ -64,8 +53,17 @@ impl BaseTool for ReadFileTool {
 
 pub struct WriteFileTool;
 
-#[async_trait]
-impl BaseTool for WriteFileTool {
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WriteFileParams {
+    /// The path to the file to write.
+    pub path: String,
+    /// The content to write to the file.
+    pub content: String,
+}
+
+impl TypedTool for WriteFileTool {
+    type Params = WriteFileParams;
+
     fn name(&self) -> &'static str {
         "write_file"
     }
@@ -78,49 +76,24 @@ impl BaseTool for WriteFileTool {
         "Writes content to a specified file."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "path": {
-                    "type": "string",
-                    "description": "The path to the file to write."
-                },
-                "content": {
-                    "type": "string",
-                    "description": "The content to write to the file."
-                }
-            },
-            "required": ["path", "content"]
-        })
-    }
-
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("path").and_then(|p| p.as_str()).is_some() &&
-        params.get("content").and_then(|c| c.as_str()).is_some()
+    fn get_description(&self, params: &WriteFileParams) -> String {
+        format!("Write to file: {}", params.path)
     }
 
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let path = params.get("path").and_then(|p| p.as_str()).unwrap_or("unknown path");
-        format!("Write to file: {}", path)
-    }
-
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &WriteFileParams) -> Option<ConfirmationDetails> {
         Some(ConfirmationDetails { message: "This will write content to a file. Are you sure?".to_string() })
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: WriteFileParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let path = params.get("path").and_then(|p| p.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'path' parameter for WriteFileTool".to_string())
-            })?;
-            let content = params.get("content").and_then(|c| c.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'content' parameter for WriteFileTool".to_string())
-            })?;
-
-            match tokio::fs::write(path, content).await {
-                Ok(_) => Ok(ToolResult::Success(format!("Successfully wrote to file '{}'.", path))),
-                Err(e) => Ok(ToolResult::Error(format!("Failed to write to file '{}': {}", path, e))),
+            let WriteFileParams { path, content } = params;
+
+            let result = tokio::fs::write(&path, content).await;
+            crate::file_cache::invalidate(std::path::Path::new(&path));
+            crate::grep_index::invalidate();
+            match result {
+                Ok(_) => Ok(ToolResult::success(format!("Successfully wrote to file '{}'.", path)).with_artifact(&path)),
+                Err(e) => Ok(ToolResult::error(format!("Failed to write to file '{}': {}", path, e))),
             }
         })
     }
@@ -128,8 +101,19 @@ impl BaseTool for WriteFileTool {
 
 pub struct EditTool;
 
-#[async_trait]
-impl BaseTool for EditTool {
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EditParams {
+    /// The path to the file to edit.
+    pub file_path: String,
+    /// The string to be replaced.
+    pub old_string: String,
+    /// The string to replace the old string with.
+    pub new_string: String,
+}
+
+impl TypedTool for EditTool {
+    type Params = EditParams;
+
     fn name(&self) -> &'static str {
         "edit_file"
     }
@@ -142,65 +126,34 @@ impl BaseTool for EditTool {
         "Edits a file by replacing an old string with a new string."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "file_path": {
-                    "type": "string",
-                    "description": "The path to the file to edit."
-                },
-                "old_string": {
-                    "type": "string",
-                    "description": "The string to be replaced."
-                },
-                "new_string": {
-                    "type": "string",
-                    "description": "The string to replace the old string with."
-                }
-            },
-            "required": ["file_path", "old_string", "new_string"]
-        })
-    }
-
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("file_path").and_then(|p| p.as_str()).is_some() &&
-        params.get("old_string").and_then(|o| o.as_str()).is_some() &&
-        params.get("new_string").and_then(|n| n.as_str()).is_some()
-    }
-
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let file_path = params.get("file_path").and_then(|p| p.as_str()).unwrap_or("unknown file");
-        let old_string = params.get("old_string").and_then(|o| o.as_str()).unwrap_or("unknown old string");
-        let new_string = params.get("new_string").and_then(|n| n.as_str()).unwrap_or("unknown new string");
-        format!("Edit file '{}': replace \"{}\" with \"{}\"", file_path, old_string, new_string)
+    fn get_description(&self, params: &EditParams) -> String {
+        format!("Edit file '{}': replace \"{}\" with \"{}\"", params.file_path, params.old_string, params.new_string)
     }
 
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &EditParams) -> Option<ConfirmationDetails> {
         Some(ConfirmationDetails { message: "This will modify a file. Are you sure?".to_string() })
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: EditParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let file_path = params.get("file_path").and_then(|p| p.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'file_path' parameter for EditTool".to_string())
-            })?;
-            let old_string = params.get("old_string").and_then(|o| o.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'old_string' parameter for EditTool".to_string())
-            })?;
-            let new_string = params.get("new_string").and_then(|n| n.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'new_string' parameter for EditTool".to_string())
-            })?;
-
-            match tokio::fs::read_to_string(file_path).await {
+            let EditParams { file_path, old_string, new_string } = params;
+            // Results and errors would tell the model what a withheld file contains
+            if let Err(e) = crate::redact::active().check(std::path::Path::new(&file_path)) {
+                return Ok(ToolResult::error(e.to_string()));
+            }
+
+            match crate::file_cache::read_to_string(std::path::Path::new(&file_path)).await {
                 Ok(content) => {
-                    let new_content = content.replace(old_string, new_string);
-                    match tokio::fs::write(file_path, new_content).await {
-                        Ok(_) => Ok(ToolResult::Success(format!("Successfully edited file '{}'.", file_path))),
-                        Err(e) => Ok(ToolResult::Error(format!("Failed to write to file '{}': {}", file_path, e))),
+                    let new_content = content.replace(&old_string, &new_string);
+                    let result = tokio::fs::write(&file_path, new_content).await;
+                    crate::file_cache::invalidate(std::path::Path::new(&file_path));
+                    crate::grep_index::invalidate();
+                    match result {
+                        Ok(_) => Ok(ToolResult::success(format!("Successfully edited file '{}'.", file_path)).with_artifact(&file_path)),
+                        Err(e) => Ok(ToolResult::error(format!("Failed to write to file '{}': {}", file_path, e))),
                     }
                 },
-                Err(e) => Ok(ToolResult::Error(format!("Failed to read file '{}': {}", file_path, e))),
+                Err(e) => Ok(ToolResult::error(format!("Failed to read file '{}': {}", file_path, e))),
             }
         })
     }
@@ -208,8 +161,15 @@ impl BaseTool for EditTool {
 
 pub struct LSTool;
 
-#[async_trait]
-impl BaseTool for LSTool {
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LSParams {
+    /// The path to the directory to list.
+    pub path: String,
+}
+
+impl TypedTool for LSTool {
+    type Params = LSParams;
+
     fn name(&self) -> &'static str {
         "list_directory"
     }
@@ -222,39 +182,19 @@ impl BaseTool for LSTool {
         "Lists the contents of a specified directory."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "path": {
-                    "type": "string",
-                    "description": "The path to the directory to list."
-                }
-            },
-            "required": ["path"]
-        })
+    fn get_description(&self, params: &LSParams) -> String {
+        format!("List contents of directory: {}", params.path)
     }
 
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("path").and_then(|p| p.as_str()).is_some()
-    }
-
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let path = params.get("path").and_then(|p| p.as_str()).unwrap_or("current directory");
-        format!("List contents of directory: {}", path)
-    }
-
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &LSParams) -> Option<ConfirmationDetails> {
         None // Listing directory contents is generally safe
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: LSParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let path = params.get("path").and_then(|p| p.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'path' parameter for LSTool".to_string())
-            })?;
+            let path = params.path;
 
-            let mut entries = tokio::fs::read_dir(path).await
+            let mut entries = tokio::fs::read_dir(&path).await
                 .map_err(|e| ShellmindError::Other(format!("Failed to read directory '{}': {}", path, e)))?;
 
             let mut file_names = Vec::new();
@@ -264,15 +204,33 @@ impl BaseTool for LSTool {
             }
             file_names.sort();
 
-            Ok(ToolResult::Success(file_names.join("\n")))
+            Ok(ToolResult::success(file_names.join("\n")).with_content(serde_json::json!(file_names)))
         })
     }
 }
 
-pub struct GrepTool;
+pub struct GrepTool {
+    /// Searched when no path is given.
+    pub roots: Vec<std::path::PathBuf>,
+    /// Narrow the files to read with `grep_index`.
+    pub use_index: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GrepParams {
+    /// The absolute path to the directory to search within. If omitted, searches every workspace root.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// The regular expression (regex) pattern to search for within file contents.
+    pub pattern: String,
+    /// Optional: A glob pattern to filter which files are searched (e.g., *.js, *.{ts,tsx}, src/**). If omitted, searches all files.
+    #[serde(default)]
+    pub include: Option<String>,
+}
+
+impl TypedTool for GrepTool {
+    type Params = GrepParams;
 
-#[async_trait]
-impl BaseTool for GrepTool {
     fn name(&self) -> &'static str {
         "search_file_content"
     }
@@ -285,91 +243,101 @@ impl BaseTool for GrepTool {
         "Searches for a regular expression pattern within the content of files in a specified directory."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "path": {
-                    "type": "string",
-                    "description": "The absolute path to the directory to search within. If omitted, searches the current working directory."
-                },
-                "pattern": {
-                    "type": "string",
-                    "description": "The regular expression (regex) pattern to search for within file contents."
-                },
-                "include": {
-                    "type": "string",
-                    "description": "Optional: A glob pattern to filter which files are searched (e.g., *.js, *.{ts,tsx}, src/**). If omitted, searches all files."
-                }
-            },
-            "required": ["pattern"]
-        })
+    fn get_description(&self, params: &GrepParams) -> String {
+        let path = params.path.as_deref().unwrap_or("workspace roots");
+        format!("Search for pattern \"{}\" in files under '{}'", params.pattern, path)
     }
 
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("pattern").and_then(|p| p.as_str()).is_some()
-    }
-
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let pattern = params.get("pattern").and_then(|p| p.as_str()).unwrap_or("unknown pattern");
-        let path = params.get("path").and_then(|p| p.as_str()).unwrap_or("current directory");
-        format!("Search for pattern \"{}\" in files under '{}'", pattern, path)
-    }
-
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &GrepParams) -> Option<ConfirmationDetails> {
         None // Searching file content is generally safe
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: GrepParams, _signal: Option<Signal>, progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let roots = match &params.path {
+            Some(path) => vec![std::path::PathBuf::from(path)],
+            None if self.roots.is_empty() => vec![std::path::PathBuf::from(".")],
+            None => self.roots.clone(),
+        };
+        let use_index = self.use_index;
         Box::pin(async move {
-            let pattern_str = params.get("pattern").and_then(|p| p.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'pattern' parameter for GrepTool".to_string())
-            })?;
-            let path_str = params.get("path").and_then(|p| p.as_str()).unwrap_or(".");
-            let include_glob = params.get("include").and_then(|i| i.as_str());
+            let include_glob = params.include.as_deref();
 
-            let regex = regex::Regex::new(pattern_str)
+            let regex = regex::Regex::new(&params.pattern)
                 .map_err(|e| ShellmindError::Other(format!("Invalid regex pattern: {}", e)))?;
 
-            let mut results = Vec::new();
-            let walker = ignore::WalkBuilder::new(path_str)
-                .git_ignore(true)
-                .build();
-
-            for entry in walker {
-                let entry = entry.map_err(|e| ShellmindError::Other(format!("Error walking directory: {}", e)))?;
-                if entry.file_type().map_or(false, |ft| ft.is_file()) {
-                    let file_path = entry.path();
-                    if let Some(glob_pattern) = include_glob {
-                        if !file_path.to_string_lossy().contains(glob_pattern) { // Simple glob check for now
-                            continue;
+            // Collect candidates first so progress can be reported as a percentage
+            let indexed = if use_index {
+                let (roots, pattern) = (roots.clone(), params.pattern.clone());
+                tokio::task::spawn_blocking(move || crate::grep_index::candidates(&roots, &pattern))
+                    .await
+                    .map_err(|e| ShellmindError::Other(format!("Search index task failed: {}", e)))??
+            } else {
+                None
+            };
+            let mut files = match indexed {
+                Some(files) => files,
+                None => {
+                    let mut files = Vec::new();
+                    let walker = crate::workspace::Workspace::walker(&roots)
+                        .ok_or_else(|| ShellmindError::Other("No directory to search".to_string()))?;
+                    for entry in walker {
+                        let entry = entry.map_err(|e| ShellmindError::Other(format!("Error walking directory: {}", e)))?;
+                        if entry.file_type().map_or(false, |ft| ft.is_file()) {
+                            files.push(entry.into_path());
                         }
                     }
+                    files
+                }
+            };
+            if let Some(glob_pattern) = include_glob {
+                files.retain(|path| path.to_string_lossy().contains(glob_pattern)); // Simple glob check for now
+            }
+            // Even the names of matching files would say what a withheld file contains
+            let profile = crate::redact::active();
+            files.retain(|path| !profile.blocks(path));
+            crate::limits::check_file_count(files.len())?;
 
-                    let content = tokio::fs::read_to_string(file_path).await
-                        .map_err(|e| ShellmindError::Other(format!("Failed to read file '{}': {}", file_path.display(), e)))?;
+            let mut results = Vec::new();
+            let total = files.len() as u64;
+            for (index, file_path) in files.iter().enumerate() {
+                if let Some(progress) = &progress {
+                    progress.report(index as u64 + 1, Some(total), file_path.display().to_string());
+                }
 
-                    for (line_num, line) in content.lines().enumerate() {
-                        if regex.is_match(line) {
-                            results.push(format!("{}:{}:{}", file_path.display(), line_num + 1, line));
-                        }
+                let content = crate::redact::read_to_string(file_path).await?;
+                for (line_num, line) in content.lines().enumerate() {
+                    if regex.is_match(line) {
+                        results.push(format!("{}:{}:{}", file_path.display(), line_num + 1, line));
                     }
                 }
             }
 
             if results.is_empty() {
-                Ok(ToolResult::Success("No matches found.".to_string()))
+                Ok(ToolResult::success("No matches found.".to_string()))
             } else {
-                Ok(ToolResult::Success(results.join("\n")))
+                Ok(ToolResult::success(results.join("\n")))
             }
         })
     }
 }
 
-pub struct GlobTool;
+pub struct GlobTool {
+    /// Searched when no path is given.
+    pub roots: Vec<std::path::PathBuf>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GlobParams {
+    /// The glob pattern to match against (e.g., src/**/*.py, docs/*.md).
+    pub pattern: String,
+    /// Optional: The absolute path to the directory to search within. If omitted, searches every workspace root.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl TypedTool for GlobTool {
+    type Params = GlobParams;
 
-#[async_trait]
-impl BaseTool for GlobTool {
     fn name(&self) -> &'static str {
         "glob"
     }
@@ -382,60 +350,40 @@ impl BaseTool for GlobTool {
         "Finds files matching specific glob patterns."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "pattern": {
-                    "type": "string",
-                    "description": "The glob pattern to match against (e.g., src/**/*.py, docs/*.md)."
-                },
-                "path": {
-                    "type": "string",
-                    "description": "Optional: The absolute path to the directory to search within. If omitted, searches the root directory."
-                }
-            },
-            "required": ["pattern"]
-        })
+    fn get_description(&self, params: &GlobParams) -> String {
+        let path = params.path.as_deref().unwrap_or("workspace roots");
+        format!("Find files matching pattern \"{}\" in '{}'", params.pattern, path)
     }
 
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("pattern").and_then(|p| p.as_str()).is_some()
-    }
-
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let pattern = params.get("pattern").and_then(|p| p.as_str()).unwrap_or("unknown pattern");
-        let path = params.get("path").and_then(|p| p.as_str()).unwrap_or("current directory");
-        format!("Find files matching pattern \"{}\" in '{}'", pattern, path)
-    }
-
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &GlobParams) -> Option<ConfirmationDetails> {
         None // Glob search is generally safe
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: GlobParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let roots = match &params.path {
+            Some(path) => vec![std::path::PathBuf::from(path)],
+            None if self.roots.is_empty() => vec![std::path::PathBuf::from(".")],
+            None => self.roots.clone(),
+        };
         Box::pin(async move {
-            let pattern_str = params.get("pattern").and_then(|p| p.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'pattern' parameter for GlobTool".to_string())
-            })?;
-            let path_str = params.get("path").and_then(|p| p.as_str()).unwrap_or(".");
-
             let mut results = Vec::new();
-            let glob_pattern = format!("{}/{}", path_str, pattern_str);
-
-            for entry in glob::glob(&glob_pattern)
-                .map_err(|e| ShellmindError::Other(format!("Invalid glob pattern: {}", e)))? {
-                match entry {
-                    Ok(path) => results.push(path.to_string_lossy().into_owned()),
-                    Err(e) => return Err(ShellmindError::Other(format!("Error matching glob pattern: {}", e))),
+            for root in &roots {
+                let glob_pattern = format!("{}/{}", root.display(), params.pattern);
+
+                for entry in glob::glob(&glob_pattern)
+                    .map_err(|e| ShellmindError::Other(format!("Invalid glob pattern: {}", e)))? {
+                    match entry {
+                        Ok(path) => results.push(path.to_string_lossy().into_owned()),
+                        Err(e) => return Err(ShellmindError::Other(format!("Error matching glob pattern: {}", e))),
+                    }
                 }
             }
             results.sort();
 
             if results.is_empty() {
-                Ok(ToolResult::Success("No matches found.".to_string()))
+                Ok(ToolResult::success("No matches found.".to_string()))
             } else {
-                Ok(ToolResult::Success(results.join("\n")))
+                Ok(ToolResult::success(results.join("\n")))
             }
         })
     }
@@ -443,8 +391,18 @@ impl BaseTool for GlobTool {
 
 pub struct ShellTool;
 
-#[async_trait]
-impl BaseTool for ShellTool {
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShellParams {
+    /// The exact bash command to execute.
+    pub command: String,
+    /// Brief description of the command for the user. Be specific and concise.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl TypedTool for ShellTool {
+    type Params = ShellParams;
+
     fn name(&self) -> &'static str {
         "run_shell_command"
     }
@@ -457,63 +415,54 @@ impl BaseTool for ShellTool {
         "Executes a given shell command."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "command": {
-                    "type": "string",
-                    "description": "The exact bash command to execute."
-                },
-                "description": {
-                    "type": "string",
-                    "description": "Brief description of the command for the user. Be specific and concise."
-                }
-            },
-            "required": ["command"]
-        })
+    fn get_description(&self, params: &ShellParams) -> String {
+        let description = params.description.as_deref().unwrap_or("no description");
+        format!("Run shell command: '{}' (Description: {})", params.command, description)
     }
 
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("command").and_then(|c| c.as_str()).is_some()
+    fn should_confirm_execute(&self, params: &ShellParams) -> Option<ConfirmationDetails> {
+        Some(ConfirmationDetails { message: format!("This will execute the command: '{}'. Are you sure?", params.command) })
     }
 
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let command = params.get("command").and_then(|c| c.as_str()).unwrap_or("unknown command");
-        let description = params.get("description").and_then(|d| d.as_str()).unwrap_or("no description");
-        format!("Run shell command: '{}' (Description: {})", command, description)
+    fn safety_level(&self, params: &ShellParams) -> SafetyLevel {
+        crate::SecurityManager::assess_command_risk(&params.command).level
     }
 
-    fn should_confirm_execute(&self, params: &serde_json::Value) -> Option<ConfirmationDetails> {
-        let command = params.get("command").and_then(|c| c.as_str()).unwrap_or("unknown command");
-        Some(ConfirmationDetails { message: format!("This will execute the command: '{}'. Are you sure?", command) })
-    }
-
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: ShellParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let command_str = params.get("command").and_then(|c| c.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'command' parameter for ShellTool".to_string())
-            })?;
+            let command_str = crate::limits::limit_shell_command(&params.command);
 
             let output = if cfg!(target_os = "windows") {
                 tokio::process::Command::new("cmd")
-                    .args(&["/C", command_str])
+                    .args(&["/C", &command_str])
+                    .kill_on_drop(true)
                     .output()
                     .await
                     .map_err(|e| ShellmindError::Other(format!("Failed to execute command: {}", e)))?
             } else {
                 tokio::process::Command::new("sh")
                     .arg("-c")
-                    .arg(command_str)
+                    .arg(&command_str)
+                    // A timed-out call drops this future; the process must not outlive it
+                    .kill_on_drop(true)
                     .output()
                     .await
                     .map_err(|e| ShellmindError::Other(format!("Failed to execute command: {}", e)))?
             };
+            crate::grep_index::invalidate();
+
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let content = serde_json::json!({
+                "exit_code": output.status.code(),
+                "stdout": stdout,
+                "stderr": stderr,
+            });
 
             if output.status.success() {
-                Ok(ToolResult::Success(String::from_utf8_lossy(&output.stdout).to_string()))
+                Ok(ToolResult::success(stdout).with_content(content))
             } else {
-                Ok(ToolResult::Error(format!("Command failed with exit code {:?}: {}", output.status.code(), String::from_utf8_lossy(&output.stderr))))
+                Ok(ToolResult::error(format!("Command failed with exit code {:?}: {}", output.status.code(), stderr)).with_content(content))
             }
         })
     }
@@ -521,8 +470,15 @@ impl BaseTool for ShellTool {
 
 pub struct WebFetchTool;
 
-#[async_trait]
-impl BaseTool for WebFetchTool {
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WebFetchParams {
+    /// The URL to fetch content from.
+    pub url: String,
+}
+
+impl TypedTool for WebFetchTool {
+    type Params = WebFetchParams;
+
     fn name(&self) -> &'static str {
         "web_fetch"
     }
@@ -535,50 +491,30 @@ impl BaseTool for WebFetchTool {
         "Fetches content from a specified URL."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "url": {
-                    "type": "string",
-                    "description": "The URL to fetch content from."
-                }
-            },
-            "required": ["url"]
-        })
-    }
-
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("url").and_then(|u| u.as_str()).is_some()
-    }
-
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let url = params.get("url").and_then(|u| u.as_str()).unwrap_or("unknown URL");
-        format!("Fetch content from URL: {}", url)
+    fn get_description(&self, params: &WebFetchParams) -> String {
+        format!("Fetch content from URL: {}", params.url)
     }
 
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &WebFetchParams) -> Option<ConfirmationDetails> {
         None // Fetching web content is generally safe
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: WebFetchParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let url = params.get("url").and_then(|u| u.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'url' parameter for WebFetchTool".to_string())
-            })?;
+            let url = params.url;
 
-            match reqwest::get(url).await {
+            match reqwest::get(&url).await {
                 Ok(response) => {
                     if response.status().is_success() {
                         match response.text().await {
-                            Ok(text) => Ok(ToolResult::Success(text)),
-                            Err(e) => Ok(ToolResult::Error(format!("Failed to read response text: {}", e))),
+                            Ok(text) => Ok(ToolResult::success(text)),
+                            Err(e) => Ok(ToolResult::error(format!("Failed to read response text: {}", e))),
                         }
                     } else {
-                        Ok(ToolResult::Error(format!("Failed to fetch URL: {} (Status: {})", url, response.status())))
+                        Ok(ToolResult::error(format!("Failed to fetch URL: {} (Status: {})", url, response.status())))
                     }
                 },
-                Err(e) => Ok(ToolResult::Error(format!("Failed to send request to URL: {}", e))),
+                Err(e) => Ok(ToolResult::error(format!("Failed to send request to URL: {}", e))),
             }
         })
     }
@@ -586,8 +522,15 @@ impl BaseTool for WebFetchTool {
 
 pub struct WebSearchTool;
 
-#[async_trait]
-impl BaseTool for WebSearchTool {
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WebSearchParams {
+    /// The search query to find information on the web.
+    pub query: String,
+}
+
+impl TypedTool for WebSearchTool {
+    type Params = WebSearchParams;
+
     fn name(&self) -> &'static str {
         "google_web_search"
     }
@@ -600,50 +543,37 @@ impl BaseTool for WebSearchTool {
         "Performs a web search using Google Search (via the Gemini API) and returns the results."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "query": {
-                    "type": "string",
-                    "description": "The search query to find information on the web."
-                }
-            },
-            "required": ["query"]
-        })
-    }
-
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("query").and_then(|q| q.as_str()).is_some()
-    }
-
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let query = params.get("query").and_then(|q| q.as_str()).unwrap_or("unknown query");
-        format!("Search the web for: {}", query)
+    fn get_description(&self, params: &WebSearchParams) -> String {
+        format!("Search the web for: {}", params.query)
     }
 
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &WebSearchParams) -> Option<ConfirmationDetails> {
         None // Web search is generally safe
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: WebSearchParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let query = params.get("query").and_then(|q| q.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'query' parameter for WebSearchTool".to_string())
-            })?;
+            let query = params.query;
 
             // Placeholder for actual Google Web Search API call
             // In a real scenario, this would involve calling the Gemini API with a search tool request.
             // For now, we'll return a dummy result.
-            Ok(ToolResult::Success(format!("Search results for '{}': [Dummy result from Google Search]", query)))
+            Ok(ToolResult::success(format!("Search results for '{}': [Dummy result from Google Search]", query)))
         })
     }
 }
 
 pub struct MemoryTool;
 
-#[async_trait]
-impl BaseTool for MemoryTool {
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MemoryParams {
+    /// The specific fact or piece of information to remember.
+    pub fact: String,
+}
+
+impl TypedTool for MemoryTool {
+    type Params = MemoryParams;
+
     fn name(&self) -> &'static str {
         "save_memory"
     }
@@ -656,49 +586,116 @@ impl BaseTool for MemoryTool {
         "Saves a specific piece of information or fact to your long-term memory."
     }
 
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "fact": {
-                    "type": "string",
-                    "description": "The specific fact or piece of information to remember."
-                }
-            },
-            "required": ["fact"]
-        })
-    }
-
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("fact").and_then(|f| f.as_str()).is_some()
+    fn get_description(&self, params: &MemoryParams) -> String {
+        format!("Save to memory: {}", params.fact)
     }
 
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let fact = params.get("fact").and_then(|f| f.as_str()).unwrap_or("unknown fact");
-        format!("Save to memory: {}", fact)
-    }
-
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &MemoryParams) -> Option<ConfirmationDetails> {
         None // Saving to memory is generally safe
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: MemoryParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let fact = params.get("fact").and_then(|f| f.as_str()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'fact' parameter for MemoryTool".to_string())
-            })?;
-
-            // In a real scenario, this would write to a persistent memory store.
-            // For now, we'll just acknowledge the save.
-            Ok(ToolResult::Success(format!("Fact saved to memory: '{}'.", fact)))
+            let fact = params.fact;
+
+            let saved = tokio::task::spawn_blocking({
+                let fact = fact.clone();
+                move || crate::MemoryManager::save_fact(&fact)
+            })
+            .await
+            .map_err(|e| ShellmindError::Other(format!("Memory task failed: {}", e)))?;
+            match saved {
+                Ok(()) => Ok(ToolResult::success(format!("Fact saved to memory: '{}'.", crate::secrets::mask(&fact)))),
+                Err(e) => Ok(ToolResult::error(format!("Failed to save to memory: {}", e))),
+            }
         })
     }
 }
 
 pub struct ReadManyFilesTool;
 
-#[async_trait]
-impl BaseTool for ReadManyFilesTool {
+const MAX_CONCURRENT_READS: usize = 8;
+const DEFAULT_MAX_FILES: usize = 200;
+const DEFAULT_MAX_TOTAL_BYTES: usize = 1024 * 1024;
+/// Files larger than this are sampled: the first and last half of the limit are kept.
+const MAX_FILE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadManyFilesParams {
+    /// An array of glob patterns or paths to files/directories.
+    pub paths: Vec<String>,
+    /// Optional: Maximum number of files to read (default 200).
+    #[serde(default)]
+    pub max_files: Option<usize>,
+    /// Optional: Maximum total bytes of content to return (default 1 MiB).
+    #[serde(default)]
+    pub max_total_bytes: Option<usize>,
+}
+
+enum FileRead {
+    Text { content: String, sampled: bool },
+    Binary,
+    Failed(String),
+}
+
+/// Same heuristic as git: a NUL byte near the start means binary.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|b| *b == 0)
+}
+
+/// Reads a file, skipping binary content and keeping only the head and tail of oversized
+/// files; the middle of those is never read.
+async fn read_sampled(path: &std::path::Path) -> FileRead {
+    if let Err(e) = crate::redact::active().check(path) {
+        return FileRead::Failed(e.to_string());
+    }
+    let size = match tokio::fs::metadata(path).await {
+        Ok(metadata) => metadata.len() as usize,
+        Err(e) => return FileRead::Failed(e.to_string()),
+    };
+    if size <= MAX_FILE_BYTES {
+        let bytes = match crate::file_cache::read(path).await {
+            Ok(bytes) => bytes,
+            Err(e) => return FileRead::Failed(e.to_string()),
+        };
+        if is_binary(&bytes) {
+            return FileRead::Binary;
+        }
+        return FileRead::Text { content: String::from_utf8_lossy(&bytes).into_owned(), sampled: false };
+    }
+    let half = MAX_FILE_BYTES / 2;
+    let (head, tail) = match read_head_and_tail(path, half).await {
+        Ok(parts) => parts,
+        Err(e) => return FileRead::Failed(e.to_string()),
+    };
+    if is_binary(&head) {
+        return FileRead::Binary;
+    }
+    let content = format!(
+        "{}\n... [{} bytes omitted] ...\n{}",
+        String::from_utf8_lossy(&head),
+        size - 2 * half,
+        String::from_utf8_lossy(&tail)
+    );
+    FileRead::Text { content, sampled: true }
+}
+
+/// The first and last `len` bytes of a file longer than `2 * len`.
+async fn read_head_and_tail(path: &std::path::Path, len: usize) -> std::io::Result<(Vec<u8>, Vec<u8>)> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut head = vec![0; len];
+    file.read_exact(&mut head).await?;
+    file.seek(std::io::SeekFrom::End(-(len as i64))).await?;
+    let mut tail = vec![0; len];
+    file.read_exact(&mut tail).await?;
+    Ok((head, tail))
+}
+
+impl TypedTool for ReadManyFilesTool {
+    type Params = ReadManyFilesParams;
+
     fn name(&self) -> &'static str {
         "read_many_files"
     }
@@ -708,103 +705,567 @@ impl BaseTool for ReadManyFilesTool {
     }
 
     fn description(&self) -> &'static str {
-        "Reads content from multiple files specified by paths or glob patterns."
-    }
-
-    fn parameter_schema(&self) -> serde_json::Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "paths": {
-                    "type": "array",
-                    "items": { "type": "string" },
-                    "description": "An array of glob patterns or paths to files/directories."
-                }
-            },
-            "required": ["paths"]
-        })
+        "Reads content from multiple files specified by paths or glob patterns. Binary files are skipped, large files are sampled, and output is capped by file and byte budgets."
     }
 
-    fn validate_tool_params(&self, params: &serde_json::Value) -> bool {
-        params.get("paths").and_then(|p| p.as_array()).is_some()
+    fn get_description(&self, params: &ReadManyFilesParams) -> String {
+        format!("Read content from multiple files: {}", params.paths.join(", "))
     }
 
-    fn get_description(&self, params: &serde_json::Value) -> String {
-        let paths = params.get("paths").and_then(|p| p.as_array()).map(|arr| {
-            arr.iter().filter_map(|v| v.as_str()).collect::<Vec<&str>>().join(", ")
-        }).unwrap_or("unknown paths".to_string());
-        format!("Read content from multiple files: {}", paths)
-    }
-
-    fn should_confirm_execute(&self, _params: &serde_json::Value) -> Option<ConfirmationDetails> {
+    fn should_confirm_execute(&self, _params: &ReadManyFilesParams) -> Option<ConfirmationDetails> {
         None // Reading files is generally safe
     }
 
-    fn execute(&self, params: serde_json::Value, _signal: Option<Signal>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+    fn execute(&self, params: ReadManyFilesParams, _signal: Option<Signal>, progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
         Box::pin(async move {
-            let paths_json = params.get("paths").and_then(|p| p.as_array()).ok_or_else(|| {
-                ShellmindError::Other("Missing 'paths' parameter for ReadManyFilesTool".to_string())
-            })?;
-
             let mut all_content = Vec::new();
 
-            for path_json in paths_json {
-                let path_str = path_json.as_str().ok_or_else(|| {
-                    ShellmindError::Other("Invalid path in 'paths' array for ReadManyFilesTool".to_string())
-                })?;
+            // Resolve globs and directories first so progress can be reported as a percentage
+            let mut files = Vec::new();
+            for path_str in &params.paths {
+                let path_str = path_str.as_str();
 
                 // Handle glob patterns
                 if path_str.contains('*') || path_str.contains('?') || path_str.contains('[') {
                     for entry in glob::glob(path_str)
                         .map_err(|e| ShellmindError::Other(format!("Invalid glob pattern '{}': {}", path_str, e)))? {
                         match entry {
-                            Ok(path) => {
-                                if path.is_file() {
-                                    match tokio::fs::read_to_string(&path).await {
-                                        Ok(content) => all_content.push(format!("--- {} ---
-{}", path.display(), content)),
-                                        Err(e) => all_content.push(format!("--- {} ---
-Error reading file: {}", path.display(), e)),
-                                    }
-                                }
-                            },
+                            Ok(path) if path.is_file() => files.push(path),
+                            Ok(_) => {}
                             Err(e) => all_content.push(format!("Error matching glob entry: {}", e)),
                         }
                     }
                 } else { // Handle direct file/directory paths
                     let path = std::path::PathBuf::from(path_str);
                     if path.is_file() {
-                        match tokio::fs::read_to_string(&path).await {
-                            Ok(content) => all_content.push(format!("--- {} ---
-{}", path.display(), content)),
-                            Err(e) => all_content.push(format!("--- {} ---
-Error reading file: {}", path.display(), e)),
-                        }
+                        files.push(path);
                     } else if path.is_dir() {
-                        for entry in walkdir::WalkDir::new(&path) {
+                        for entry in ignore::WalkBuilder::new(&path).git_ignore(true).build() {
                             let entry = entry.map_err(|e| ShellmindError::Other(format!("Error walking directory: {}", e)))?;
-                            if entry.file_type().is_file() {
-                                let file_path = entry.path();
-                                match tokio::fs::read_to_string(file_path).await {
-                                    Ok(content) => all_content.push(format!("--- {} ---
-{}", file_path.display(), content)),
-                                    Err(e) => all_content.push(format!("--- {} ---
-Error reading file: {}", file_path.display(), e)),
-                                }
+                            if entry.file_type().map_or(false, |ft| ft.is_file()) {
+                                files.push(entry.into_path());
                             }
                         }
                     } else {
-                        all_content.push(format!("--- {} ---
-File or directory not found.", path.display()));
+                        all_content.push(format!("--- {} ---\nFile or directory not found.", path.display()));
                     }
                 }
             }
 
+            // Apply the file and byte budgets up front, in order, using sizes from metadata
+            let max_files = params.max_files.unwrap_or(DEFAULT_MAX_FILES);
+            let max_total_bytes = params.max_total_bytes.unwrap_or(DEFAULT_MAX_TOTAL_BYTES);
+            let found = files.len();
+            let mut selected = Vec::new();
+            let mut budgeted_bytes = 0;
+            for path in files.into_iter().take(max_files) {
+                let size = std::fs::metadata(&path).map(|m| m.len() as usize).unwrap_or(0).min(MAX_FILE_BYTES);
+                if !selected.is_empty() && budgeted_bytes + size > max_total_bytes {
+                    break;
+                }
+                budgeted_bytes += size;
+                selected.push(path);
+            }
+            let over_budget = found - selected.len();
+            crate::limits::check_file_count(selected.len())?;
+
+            // Read concurrently, keeping results in the original order
+            let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_READS));
+            let mut tasks = tokio::task::JoinSet::new();
+            for (index, path) in selected.iter().cloned().enumerate() {
+                let semaphore = std::sync::Arc::clone(&semaphore);
+                tasks.spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    (index, read_sampled(&path).await)
+                });
+            }
+            let total = selected.len() as u64;
+            let mut reads: Vec<Option<FileRead>> = (0..selected.len()).map(|_| None).collect();
+            let mut completed = 0;
+            while let Some(joined) = tasks.join_next().await {
+                let (index, read) = joined.map_err(|e| ShellmindError::Other(format!("File read task failed: {}", e)))?;
+                completed += 1;
+                if let Some(progress) = &progress {
+                    progress.report(completed, Some(total), selected[index].display().to_string());
+                }
+                reads[index] = Some(read);
+            }
+
+            let mut binary = 0;
+            let mut sampled_files = 0;
+            for (path, read) in selected.iter().zip(reads) {
+                match read {
+                    Some(FileRead::Text { content, sampled }) => {
+                        if sampled {
+                            sampled_files += 1;
+                        }
+                        all_content.push(format!("--- {} ---\n{}", path.display(), crate::redact::active().redact(&content)));
+                    }
+                    Some(FileRead::Binary) => binary += 1,
+                    Some(FileRead::Failed(e)) => all_content.push(format!("--- {} ---\nError reading file: {}", path.display(), e)),
+                    None => {}
+                }
+            }
+
             if all_content.is_empty() {
-                Ok(ToolResult::Success("No readable files found.".to_string()))
+                return Ok(ToolResult::success("No readable files found.".to_string()));
+            }
+            let truncated = over_budget > 0 || sampled_files > 0;
+            if truncated || binary > 0 {
+                all_content.push(format!(
+                    "[Read {} of {} files; {} skipped as binary; {} omitted by the file/byte budget; {} sampled to head and tail]",
+                    selected.len() - binary,
+                    found,
+                    binary,
+                    over_budget,
+                    sampled_files
+                ));
+            }
+            Ok(ToolResult::success(all_content.join("\n")).with_truncated(truncated))
+        })
+    }
+}
+
+pub struct DiffTool;
+
+/// Diff output lines returned to the model before the rest is summarized.
+const MAX_DIFF_OUTPUT_LINES: usize = 400;
+/// Width of each column in side-by-side output.
+const SIDE_BY_SIDE_WIDTH: usize = 60;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DiffParams {
+    /// The original file or directory.
+    pub left: String,
+    /// The changed file or directory.
+    pub right: String,
+    /// Optional: `unified` (default) or `side_by_side`.
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Optional: Treat lines differing only in whitespace as equal.
+    #[serde(default)]
+    pub ignore_whitespace: bool,
+    /// Optional: Unchanged lines shown around each change (default 3).
+    #[serde(default)]
+    pub context_lines: Option<usize>,
+}
+
+struct DiffOptions {
+    side_by_side: bool,
+    ignore_whitespace: bool,
+    context: usize,
+}
+
+fn normalize_whitespace(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn fit(text: &str, width: usize) -> String {
+    let text = text.replace('\t', "    ");
+    if text.chars().count() > width {
+        format!("{}…", text.chars().take(width - 1).collect::<String>())
+    } else {
+        format!("{:width$}", text, width = width)
+    }
+}
+
+/// Diff of two texts, compared on normalized lines when ignoring whitespace but shown
+/// with the original lines. Empty when they are equal.
+fn diff_texts(left_name: &str, right_name: &str, old: &str, new: &str, options: &DiffOptions) -> Vec<String> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let key = |line: &&str| if options.ignore_whitespace { normalize_whitespace(line) } else { line.to_string() };
+    let old_keys: Vec<String> = old_lines.iter().map(key).collect();
+    let new_keys: Vec<String> = new_lines.iter().map(key).collect();
+    let ops = similar::capture_diff_slices(similar::Algorithm::Myers, &old_keys, &new_keys);
+    let groups = similar::group_diff_ops(ops, options.context);
+    if groups.is_empty() {
+        return Vec::new();
+    }
+
+    let mut output = vec![format!("--- {}", left_name), format!("+++ {}", right_name)];
+    for group in groups {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_start = first.old_range().start;
+        let new_start = first.new_range().start;
+        let old_len = last.old_range().end - old_start;
+        let new_len = last.new_range().end - new_start;
+        output.push(format!("@@ -{},{} +{},{} @@", old_start + 1, old_len, new_start + 1, new_len));
+        for op in &group {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            if options.side_by_side {
+                let rows = old_range.len().max(new_range.len());
+                for row in 0..rows {
+                    let left = old_lines.get(old_range.start + row).filter(|_| row < old_range.len()).copied().unwrap_or("");
+                    let right = new_lines.get(new_range.start + row).filter(|_| row < new_range.len()).copied().unwrap_or("");
+                    let marker = match tag {
+                        similar::DiffTag::Equal => ' ',
+                        similar::DiffTag::Delete => '<',
+                        similar::DiffTag::Insert => '>',
+                        similar::DiffTag::Replace if row >= new_range.len() => '<',
+                        similar::DiffTag::Replace if row >= old_range.len() => '>',
+                        similar::DiffTag::Replace => '|',
+                    };
+                    output.push(format!("{} {} {}", fit(left, SIDE_BY_SIDE_WIDTH), marker, right));
+                }
+                continue;
+            }
+            if tag == similar::DiffTag::Equal {
+                output.extend(old_range.map(|i| format!(" {}", old_lines[i])));
+                continue;
+            }
+            output.extend(old_range.map(|i| format!("-{}", old_lines[i])));
+            output.extend(new_range.map(|i| format!("+{}", new_lines[i])));
+        }
+    }
+    output
+}
+
+/// Relative paths of the files under `dir`, respecting ignore files.
+fn relative_files(dir: &std::path::Path) -> std::collections::BTreeSet<std::path::PathBuf> {
+    ignore::WalkBuilder::new(dir)
+        .build()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map_or(false, |t| t.is_file()))
+        .filter_map(|e| e.path().strip_prefix(dir).ok().map(|p| p.to_path_buf()))
+        .collect()
+}
+
+fn diff_paths(left: &std::path::Path, right: &std::path::Path, options: &DiffOptions) -> Result<Vec<String>, ShellmindError> {
+    let profile = crate::redact::active();
+    let read = |path: &std::path::Path| -> Result<Option<String>, ShellmindError> {
+        let bytes = std::fs::read(path).map_err(|e| ShellmindError::Other(format!("Failed to read '{}': {}", path.display(), e)))?;
+        Ok((!is_binary(&bytes)).then(|| profile.redact(&String::from_utf8_lossy(&bytes))))
+    };
+    if left.is_file() && right.is_file() {
+        if profile.blocks(left) || profile.blocks(right) {
+            if std::fs::read(left).ok() == std::fs::read(right).ok() {
+                return Ok(Vec::new());
+            }
+            return Ok(vec![format!("Files {} and {} differ; their contents are {}", left.display(), right.display(), crate::redact::NOTE)]);
+        }
+        return Ok(match (read(left)?, read(right)?) {
+            (Some(old), Some(new)) => diff_texts(&left.display().to_string(), &right.display().to_string(), &old, &new, options),
+            _ if std::fs::read(left).ok() == std::fs::read(right).ok() => Vec::new(),
+            _ => vec![format!("Binary files {} and {} differ", left.display(), right.display())],
+        });
+    }
+    if !(left.is_dir() && right.is_dir()) {
+        return Err(ShellmindError::Other(format!("'{}' and '{}' must both be files or both be directories", left.display(), right.display())));
+    }
+
+    let left_files = relative_files(left);
+    let right_files = relative_files(right);
+    let mut output = Vec::new();
+    for only in left_files.difference(&right_files) {
+        output.push(format!("Only in {}: {}", left.display(), only.display()));
+    }
+    for only in right_files.difference(&left_files) {
+        output.push(format!("Only in {}: {}", right.display(), only.display()));
+    }
+    for common in left_files.intersection(&right_files) {
+        output.extend(diff_paths(&left.join(common), &right.join(common), options)?);
+    }
+    Ok(output)
+}
+
+impl TypedTool for DiffTool {
+    type Params = DiffParams;
+
+    fn name(&self) -> &'static str {
+        "diff"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Diff"
+    }
+
+    fn description(&self) -> &'static str {
+        "Compares two files or directories and returns a compact unified or side-by-side diff, optionally ignoring whitespace."
+    }
+
+    fn get_description(&self, params: &DiffParams) -> String {
+        format!("Diff {} against {}", params.left, params.right)
+    }
+
+    fn should_confirm_execute(&self, _params: &DiffParams) -> Option<ConfirmationDetails> {
+        None // Comparing files only reads them
+    }
+
+    fn execute(&self, params: DiffParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        Box::pin(async move {
+            let options = DiffOptions {
+                side_by_side: match params.format.as_deref() {
+                    None | Some("unified") => false,
+                    Some("side_by_side") | Some("side-by-side") => true,
+                    Some(other) => return Ok(ToolResult::error(format!("Unknown diff format '{}'; use unified or side_by_side", other))),
+                },
+                ignore_whitespace: params.ignore_whitespace,
+                context: params.context_lines.unwrap_or(3),
+            };
+            let (left, right) = (std::path::PathBuf::from(&params.left), std::path::PathBuf::from(&params.right));
+            let mut lines = tokio::task::spawn_blocking(move || diff_paths(&left, &right, &options))
+                .await
+                .map_err(|e| ShellmindError::Other(format!("Diff task failed: {}", e)))??;
+            if lines.is_empty() {
+                return Ok(ToolResult::success(format!("No differences between {} and {}.", params.left, params.right)));
+            }
+            let truncated = lines.len() > MAX_DIFF_OUTPUT_LINES;
+            if truncated {
+                let hidden = lines.len() - MAX_DIFF_OUTPUT_LINES;
+                lines.truncate(MAX_DIFF_OUTPUT_LINES);
+                lines.push(format!("[{} more diff line(s) omitted]", hidden));
+            }
+            Ok(ToolResult::success(lines.join("\n")).with_truncated(truncated))
+        })
+    }
+}
+
+pub struct MultiEditTool;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MultiEditParams {
+    /// Glob pattern selecting the files to edit, e.g. `src/**/*.rs`.
+    pub glob: String,
+    /// The text or regular expression to find.
+    pub find: String,
+    /// The replacement; with `regex`, `$1` and `${name}` refer to capture groups.
+    pub replace: String,
+    /// Optional: Treat `find` as a regular expression.
+    #[serde(default)]
+    pub regex: bool,
+    /// Optional: Only edit these of the matched files.
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+}
+
+/// A pending change to one file.
+#[derive(Debug, Clone)]
+pub struct FileEdit {
+    pub path: std::path::PathBuf,
+    pub original: String,
+    pub updated: String,
+    pub replacements: usize,
+}
+
+/// The edits `params` would make, in path order; files without a match are left out.
+pub fn plan_multi_edit(params: &MultiEditParams) -> Result<Vec<FileEdit>, ShellmindError> {
+    let pattern = if params.regex {
+        regex::Regex::new(&params.find)
+    } else {
+        regex::Regex::new(&regex::escape(&params.find))
+    }
+    .map_err(|e| ShellmindError::Other(format!("Invalid pattern '{}': {}", params.find, e)))?;
+    let only: Option<Vec<std::path::PathBuf>> = params.paths.as_ref().map(|paths| paths.iter().map(std::path::PathBuf::from).collect());
+
+    let mut edits = Vec::new();
+    for entry in glob::glob(&params.glob).map_err(|e| ShellmindError::Other(format!("Invalid glob pattern '{}': {}", params.glob, e)))? {
+        let Ok(path) = entry else { continue };
+        if !path.is_file() || only.as_ref().map_or(false, |only| !only.contains(&path)) {
+            continue;
+        }
+        // Withheld files are never edited, so previews and results can't reveal them
+        if crate::redact::active().blocks(&path) {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(&path) else { continue };
+        if is_binary(&bytes) {
+            continue;
+        }
+        let Ok(original) = String::from_utf8(bytes) else { continue };
+        let replacements = pattern.find_iter(&original).count();
+        if replacements == 0 {
+            continue;
+        }
+        // A literal replacement must not expand `$` references
+        let updated = if params.regex {
+            pattern.replace_all(&original, params.replace.as_str()).into_owned()
+        } else {
+            pattern.replace_all(&original, regex::NoExpand(&params.replace)).into_owned()
+        };
+        edits.push(FileEdit { path, original, updated, replacements });
+    }
+    Ok(edits)
+}
+
+impl TypedTool for MultiEditTool {
+    type Params = MultiEditParams;
+
+    fn name(&self) -> &'static str {
+        "multi_edit"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Multi-File Edit"
+    }
+
+    fn description(&self) -> &'static str {
+        "Replaces a literal string or regular expression in every file matched by a glob. The previous contents are saved to the undo store."
+    }
+
+    fn get_description(&self, params: &MultiEditParams) -> String {
+        format!("Replace \"{}\" with \"{}\" in {}", params.find, params.replace, params.glob)
+    }
+
+    fn should_confirm_execute(&self, _params: &MultiEditParams) -> Option<ConfirmationDetails> {
+        Some(ConfirmationDetails { message: "This will modify every listed file. Are you sure?".to_string() })
+    }
+
+    fn execute(&self, params: MultiEditParams, _signal: Option<Signal>, progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        let description = self.get_description(&params);
+        Box::pin(async move {
+            let edits = plan_multi_edit(&params)?;
+            if edits.is_empty() {
+                return Ok(ToolResult::success(format!("No matches for \"{}\" in {}.", params.find, params.glob)));
+            }
+            let paths: Vec<std::path::PathBuf> = edits.iter().map(|e| e.path.clone()).collect();
+            let snapshot = crate::undo::save(&description, &paths)?;
+
+            let mut result_lines = Vec::new();
+            let mut written = Vec::new();
+            for (index, edit) in edits.iter().enumerate() {
+                if let Some(progress) = &progress {
+                    progress.report(index as u64 + 1, Some(edits.len() as u64), edit.path.display().to_string());
+                }
+                let write = tokio::fs::write(&edit.path, &edit.updated).await;
+                crate::file_cache::invalidate(&edit.path);
+                crate::grep_index::invalidate();
+                match write {
+                    Ok(()) => {
+                        result_lines.push(format!("{}: {} replacement(s)", edit.path.display(), edit.replacements));
+                        written.push(edit.path.clone());
+                    }
+                    Err(e) => result_lines.push(format!("{}: failed to write: {}", edit.path.display(), e)),
+                }
+            }
+            result_lines.push(format!(
+                "Edited {} of {} file(s). Undo with `shellmind undo {}`.",
+                written.len(),
+                edits.len(),
+                snapshot
+            ));
+            let mut result = if written.len() == edits.len() {
+                ToolResult::success(result_lines.join("\n"))
             } else {
-                Ok(ToolResult::Success(all_content.join("\n")))
+                ToolResult::error(result_lines.join("\n"))
+            };
+            for path in written {
+                result = result.with_artifact(path);
+            }
+            Ok(result)
+        })
+    }
+}
+
+pub struct TreeTool;
+
+const DEFAULT_TREE_DEPTH: usize = 3;
+/// Entries listed before the tree is cut short.
+const MAX_TREE_ENTRIES: usize = 500;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TreeParams {
+    /// The directory to render.
+    pub path: String,
+    /// Optional: How many levels deep to descend (default 3).
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Optional: Include hidden files and files excluded by .gitignore.
+    #[serde(default)]
+    pub all: bool,
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "K", "M", "G"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}{}", bytes, UNITS[0])
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+impl TypedTool for TreeTool {
+    type Params = TreeParams;
+
+    fn name(&self) -> &'static str {
+        "tree"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Directory Tree"
+    }
+
+    fn description(&self) -> &'static str {
+        "Renders a depth-limited tree of a directory with file sizes, skipping ignored files. Much cheaper than reading files when only the layout is needed."
+    }
+
+    fn get_description(&self, params: &TreeParams) -> String {
+        format!("Show directory tree: {}", params.path)
+    }
+
+    fn should_confirm_execute(&self, _params: &TreeParams) -> Option<ConfirmationDetails> {
+        None // Listing directory contents is generally safe
+    }
+
+    fn execute(&self, params: TreeParams, _signal: Option<Signal>, _progress: Option<ProgressSender>) -> Pin<Box<dyn Future<Output = Result<ToolResult, ShellmindError>> + Send>> {
+        Box::pin(async move {
+            let root = std::path::PathBuf::from(&params.path);
+            if !root.is_dir() {
+                return Ok(ToolResult::error(format!("'{}' is not a directory", params.path)));
+            }
+            let max_depth = params.max_depth.unwrap_or(DEFAULT_TREE_DEPTH).max(1);
+            let all = params.all;
+            let (lines, files, dirs, total) = tokio::task::spawn_blocking(move || {
+                let walker = ignore::WalkBuilder::new(&root)
+                    .max_depth(Some(max_depth))
+                    .hidden(!all)
+                    .git_ignore(!all)
+                    .ignore(!all)
+                    .sort_by_file_name(|a, b| a.cmp(b))
+                    .build();
+                let profile = crate::redact::active();
+                let mut lines = vec![format!("{}/", root.display())];
+                let (mut files, mut dirs, mut total) = (0usize, 0usize, 0usize);
+                for entry in walker.filter_map(|e| e.ok()).filter(|e| e.depth() > 0 && !profile.blocks(e.path())) {
+                    total += 1;
+                    let is_dir = entry.file_type().map_or(false, |t| t.is_dir());
+                    if is_dir {
+                        dirs += 1;
+                    } else {
+                        files += 1;
+                    }
+                    if lines.len() > MAX_TREE_ENTRIES {
+                        continue;
+                    }
+                    let indent = "  ".repeat(entry.depth());
+                    let name = entry.file_name().to_string_lossy();
+                    if is_dir {
+                        lines.push(format!("{}{}/", indent, name));
+                    } else {
+                        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                        lines.push(format!("{}{} ({})", indent, name, human_size(size)));
+                    }
+                }
+                (lines, files, dirs, total)
+            })
+            .await
+            .map_err(|e| ShellmindError::Other(format!("Tree task failed: {}", e)))?;
+
+            let truncated = total > MAX_TREE_ENTRIES;
+            let mut output = lines.join("\n");
+            if truncated {
+                output.push_str(&format!("\n[{} more entries omitted]", total - MAX_TREE_ENTRIES));
             }
+            output.push_str(&format!("\n{} director(ies), {} file(s) within depth {}", dirs, files, max_depth));
+            Ok(ToolResult::success(output).with_truncated(truncated))
         })
     }
 }
@@ -0,0 +1,57 @@
+//! Translation of command output, error messages and man page excerpts into the
+//! configured language, keeping commands, paths and identifiers untouched.
+
+/// Longer input is cut, keeping the start where errors and synopses usually are.
+pub const MAX_INPUT_CHARS: usize = 12_000;
+
+/// Frequent words of English prose and error messages.
+const ENGLISH_MARKERS: &[&str] = &[
+    "the", "is", "are", "was", "not", "no", "of", "to", "and", "or", "for", "with", "cannot", "can't", "could",
+    "failed", "error", "denied", "permission", "such", "file", "directory", "found", "invalid", "missing",
+    "expected", "unable", "usage", "option", "options", "command", "unknown", "must", "should", "this", "that",
+];
+
+/// Characters that only occur in Turkish text.
+const TURKISH_LETTERS: &[char] = &['ç', 'ğ', 'ı', 'ö', 'ş', 'ü', 'Ç', 'Ğ', 'İ', 'Ö', 'Ş', 'Ü'];
+
+/// True when `text` reads like English prose rather than data such as file listings,
+/// numbers or paths.
+pub fn looks_english(text: &str) -> bool {
+    if text.chars().any(|c| TURKISH_LETTERS.contains(&c)) {
+        return false;
+    }
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let markers = words.iter().filter(|w| ENGLISH_MARKERS.contains(&w.as_str())).count();
+    markers >= 2 && markers * 10 >= words.len()
+}
+
+/// English name of a language code understood by the model.
+pub fn language_name(code: &str) -> &str {
+    match code {
+        "tr" => "Turkish",
+        "en" => "English",
+        other => other,
+    }
+}
+
+/// Cuts `text` to `MAX_INPUT_CHARS`; the flag is set when something was dropped.
+pub fn truncate(text: &str) -> (&str, bool) {
+    match text.char_indices().nth(MAX_INPUT_CHARS) {
+        Some((end, _)) => (&text[..end], true),
+        None => (text, false),
+    }
+}
+
+pub fn build_translation_prompt(text: &str, language_code: &str) -> String {
+    format!(
+        "Translate the terminal output below into {}. Translate only the prose. Keep commands, options, file paths, \
+         identifiers, code, quoted strings, numbers and the line layout exactly as they are. Reply with the translation \
+         only, without any commentary or code fences around the whole reply.\n\n```\n{}\n```",
+        language_name(language_code),
+        text.trim_end()
+    )
+}
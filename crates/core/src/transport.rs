@@ -0,0 +1,346 @@
+//! Model transport abstraction: live Gemini calls, or recording and replaying exchanges
+//! from a fixture file for offline demos and deterministic tests.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use gemini_client::GeminiClient;
+
+use crate::api_client;
+use crate::budget::{self, BudgetDecision};
+use crate::context::estimate_tokens;
+use crate::rate_limit::RateLimiter;
+use crate::{ApiType, GeminiContent, InlineData, ShellmindConfig, ShellmindError};
+
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn generate(
+        &self,
+        config: &ShellmindConfig,
+        prompt: &str,
+        history: &[GeminiContent],
+    ) -> Result<String, ShellmindError> {
+        self.generate_with_images(config, prompt, &[], history).await
+    }
+
+    /// Sends `prompt` with images attached as inline data.
+    async fn generate_with_images(
+        &self,
+        config: &ShellmindConfig,
+        prompt: &str,
+        images: &[InlineData],
+        history: &[GeminiContent],
+    ) -> Result<String, ShellmindError>;
+
+    /// Like `generate_with_images`, passing the reply text to `on_text` as it arrives.
+    /// Transports that can't stream call it once with the whole reply.
+    async fn generate_streaming(
+        &self,
+        config: &ShellmindConfig,
+        prompt: &str,
+        images: &[InlineData],
+        history: &[GeminiContent],
+        on_text: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String, ShellmindError> {
+        let response = self.generate_with_images(config, prompt, images, history).await?;
+        on_text(&response);
+        Ok(response)
+    }
+}
+
+/// Provider name used for `rate_limits` entries.
+const GEMINI_PROVIDER: &str = "gemini";
+
+/// Sends requests to the Gemini API over REST or gRPC, according to `config.api_type`.
+/// Calls are throttled by a rate limiter per provider, shared by everything that holds
+/// this transport (all sessions in server mode).
+/// Connections are pooled in a `GeminiClient` created on first use. Usage is recorded
+/// against the configured budgets, which are checked before every call.
+#[derive(Default)]
+pub struct LiveTransport {
+    limiters: Mutex<HashMap<String, Arc<RateLimiter>>>,
+    client: Mutex<Option<Arc<GeminiClient>>>,
+    /// Set once the over-budget warning has been shown, so agent loops don't repeat it.
+    budget_warned: AtomicBool,
+}
+
+impl LiveTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn limiter(&self, config: &ShellmindConfig, provider: &str) -> Arc<RateLimiter> {
+        let mut limiters = self.limiters.lock().unwrap();
+        let limiter = limiters.entry(provider.to_string()).or_insert_with(|| {
+            let limits = config.rate_limits.get(provider).cloned().unwrap_or_default();
+            Arc::new(RateLimiter::new(limits))
+        });
+        Arc::clone(limiter)
+    }
+
+    fn client(&self, config: &ShellmindConfig) -> Result<Arc<GeminiClient>, ShellmindError> {
+        let mut client = self.client.lock().unwrap();
+        if let Some(client) = client.as_ref() {
+            return Ok(Arc::clone(client));
+        }
+        let created = Arc::new(api_client::new_client(config)?);
+        *client = Some(Arc::clone(&created));
+        Ok(created)
+    }
+
+    /// Sends one request; with `on_text`, REST replies are streamed to it as they arrive
+    /// (gRPC replies are passed whole).
+    async fn send(
+        &self,
+        config: &ShellmindConfig,
+        prompt: &str,
+        images: &[InlineData],
+        history: &[GeminiContent],
+        on_text: Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<String, ShellmindError> {
+        let fallback_config;
+        let config = match budget::check(config)? {
+            BudgetDecision::Allow => config,
+            BudgetDecision::Warn(reason) => {
+                if !self.budget_warned.swap(true, Ordering::Relaxed) {
+                    eprintln!("Warning: {}", reason);
+                }
+                config
+            }
+            BudgetDecision::Fallback(model) => {
+                if !self.budget_warned.swap(true, Ordering::Relaxed) {
+                    eprintln!("Warning: budget reached; using {} for further requests", model);
+                }
+                fallback_config = ShellmindConfig { model_name: model, ..config.clone() };
+                &fallback_config
+            }
+            BudgetDecision::Refuse(reason) => {
+                return Err(ShellmindError::Blocked(format!("request refused: {} (budget_policy = refuse)", reason)));
+            }
+        };
+
+        let client = self.client(config)?;
+        let limiter = self.limiter(config, GEMINI_PROVIDER);
+        let request_tokens = estimate_tokens(history) + prompt.chars().count() / 4 + 1;
+        let _permit = limiter.acquire(request_tokens as u32).await;
+
+        // Nothing leaves the machine without the workspace redaction profile applied
+        let profile = crate::redact::active();
+        let (prompt, history) = if profile.is_empty() {
+            (prompt.to_string(), history.to_vec())
+        } else {
+            let mut history = history.to_vec();
+            for part in history.iter_mut().flat_map(|c| c.parts.iter_mut()) {
+                part.text = profile.redact(&part.text);
+            }
+            (profile.redact(prompt), history)
+        };
+        let request = api_client::generate_request(config, &prompt, images, &history);
+        let response = match (&config.api_type, on_text) {
+            (ApiType::Rest, Some(on_text)) => client.stream_rest(&request, |text| on_text(text)).await?,
+            (ApiType::Rest, None) => client.generate_rest(&request).await?,
+            (ApiType::Grpc, on_text) => {
+                let response = client.generate_grpc(&config.grpc_endpoint, &request).await?;
+                if let Some(on_text) = on_text {
+                    on_text(&response);
+                }
+                response
+            }
+        };
+        let response_tokens = response.chars().count() / 4 + 1;
+        limiter.record_tokens(response_tokens as u32).await;
+        if let Err(e) = budget::record(config, &config.model_name, request_tokens as u64, response_tokens as u64) {
+            eprintln!("Warning: failed to record usage: {}", e);
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl Transport for LiveTransport {
+    async fn generate_with_images(
+        &self,
+        config: &ShellmindConfig,
+        prompt: &str,
+        images: &[InlineData],
+        history: &[GeminiContent],
+    ) -> Result<String, ShellmindError> {
+        self.send(config, prompt, images, history, None).await
+    }
+
+    async fn generate_streaming(
+        &self,
+        config: &ShellmindConfig,
+        prompt: &str,
+        images: &[InlineData],
+        history: &[GeminiContent],
+        on_text: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String, ShellmindError> {
+        self.send(config, prompt, images, history, Some(on_text)).await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub prompt: String,
+    pub response: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fixture {
+    pub exchanges: Vec<Exchange>,
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> Result<Self, ShellmindError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to read fixture '{}': {}", path.display(), e)))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ShellmindError> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .map_err(|e| ShellmindError::Other(format!("Failed to write fixture '{}': {}", path.display(), e)))
+    }
+}
+
+/// Forwards to an inner transport and appends every successful exchange to a fixture file.
+pub struct RecordingTransport {
+    inner: Box<dyn Transport>,
+    path: PathBuf,
+    fixture: Mutex<Fixture>,
+}
+
+impl RecordingTransport {
+    pub fn new(inner: Box<dyn Transport>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            fixture: Mutex::new(Fixture::default()),
+        }
+    }
+
+    fn record(&self, prompt: &str, response: &str) -> Result<(), ShellmindError> {
+        let mut fixture = self.fixture.lock().unwrap();
+        fixture.exchanges.push(Exchange {
+            prompt: prompt.to_string(),
+            response: response.to_string(),
+        });
+        fixture.save(&self.path)
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn generate_with_images(
+        &self,
+        config: &ShellmindConfig,
+        prompt: &str,
+        images: &[InlineData],
+        history: &[GeminiContent],
+    ) -> Result<String, ShellmindError> {
+        let response = self.inner.generate_with_images(config, prompt, images, history).await?;
+        self.record(prompt, &response)?;
+        Ok(response)
+    }
+
+    async fn generate_streaming(
+        &self,
+        config: &ShellmindConfig,
+        prompt: &str,
+        images: &[InlineData],
+        history: &[GeminiContent],
+        on_text: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String, ShellmindError> {
+        let response = self.inner.generate_streaming(config, prompt, images, history, on_text).await?;
+        self.record(prompt, &response)?;
+        Ok(response)
+    }
+}
+
+/// Answers from recorded exchanges without touching the network. Each exchange is used
+/// at most once, matched by prompt in recording order.
+pub struct ReplayTransport {
+    exchanges: Mutex<Vec<Option<Exchange>>>,
+}
+
+impl ReplayTransport {
+    pub fn new(fixture: Fixture) -> Self {
+        Self {
+            exchanges: Mutex::new(fixture.exchanges.into_iter().map(Some).collect()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ShellmindError> {
+        Ok(Self::new(Fixture::load(path)?))
+    }
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    /// Images are not recorded; exchanges are matched by prompt text alone.
+    async fn generate_with_images(
+        &self,
+        _config: &ShellmindConfig,
+        prompt: &str,
+        _images: &[InlineData],
+        _history: &[GeminiContent],
+    ) -> Result<String, ShellmindError> {
+        let mut exchanges = self.exchanges.lock().unwrap();
+        exchanges
+            .iter_mut()
+            .find(|e| e.as_ref().map_or(false, |e| e.prompt == prompt))
+            .and_then(Option::take)
+            .map(|e| e.response)
+            .ok_or_else(|| ShellmindError::Other(format!("No recorded response for prompt: {}", prompt)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers every prompt with its own text reversed.
+    struct ReverseTransport;
+
+    #[async_trait]
+    impl Transport for ReverseTransport {
+        async fn generate_with_images(
+            &self,
+            _config: &ShellmindConfig,
+            prompt: &str,
+            _images: &[InlineData],
+            _history: &[GeminiContent],
+        ) -> Result<String, ShellmindError> {
+            Ok(prompt.chars().rev().collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recorded_exchanges_replay() {
+        let config = crate::ConfigManager::defaults().unwrap();
+        let path = std::env::temp_dir().join(format!("shellmind-fixture-{}.json", uuid::Uuid::new_v4()));
+
+        let recorder = RecordingTransport::new(Box::new(ReverseTransport), &path);
+        assert_eq!(recorder.generate(&config, "list files", &[]).await.unwrap(), "selif tsil");
+        let mut streamed = String::new();
+        let reply = recorder
+            .generate_streaming(&config, "disk usage", &[], &[], &mut |text: &str| streamed.push_str(text))
+            .await
+            .unwrap();
+        assert_eq!(reply, "egasu ksid");
+        assert_eq!(streamed, reply);
+
+        let replay = ReplayTransport::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.generate(&config, "disk usage", &[]).await.unwrap(), "egasu ksid");
+        assert_eq!(replay.generate(&config, "list files", &[]).await.unwrap(), "selif tsil");
+        // Each exchange answers once
+        assert!(replay.generate(&config, "list files", &[]).await.is_err());
+    }
+}
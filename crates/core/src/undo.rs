@@ -0,0 +1,195 @@
+//! Undo store: snapshots of files taken before a tool changes them, one file per
+//! snapshot under `~/.shellmind/undo/` so retention can cap their number.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::retention::DataKind;
+use crate::{persist, ShellmindError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    /// Contents before the change; `None` when the file did not exist.
+    pub content: Option<String>,
+    /// Unix permission bits before the change, so a script gets its `+x` back.
+    #[serde(default)]
+    pub mode: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// What made the change, e.g. the tool description.
+    pub description: String,
+    pub files: Vec<FileSnapshot>,
+}
+
+fn snapshot_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+#[cfg(unix)]
+fn file_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).ok().map(|m| m.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Records the current contents of `paths` before they are modified. Returns the snapshot ID.
+pub fn save(description: &str, paths: &[PathBuf]) -> Result<String, ShellmindError> {
+    save_in(&DataKind::Undo.path()?, description, paths)
+}
+
+fn save_in(dir: &Path, description: &str, paths: &[PathBuf]) -> Result<String, ShellmindError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| ShellmindError::Other(format!("Failed to create undo directory: {}", e)))?;
+    let files = paths
+        .iter()
+        .map(|path| {
+            let absolute = path.canonicalize().unwrap_or_else(|_| path.clone());
+            let content = match std::fs::read_to_string(path) {
+                Ok(content) => Some(content),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(ShellmindError::Other(format!("Failed to snapshot '{}': {}", path.display(), e))),
+            };
+            let mode = content.as_ref().and_then(|_| file_mode(path));
+            Ok(FileSnapshot { path: absolute, content, mode })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let created_at = Utc::now();
+    // Sortable by name as well as by modification time
+    let id = format!("{}-{}", created_at.format("%Y%m%d%H%M%S"), &uuid::Uuid::new_v4().to_string()[..8]);
+    let snapshot = Snapshot { id: id.clone(), created_at, description: description.to_string(), files };
+    persist::atomic_write(&snapshot_path(dir, &id), persist::seal(serde_json::to_string(&snapshot)?)?)?;
+    Ok(id)
+}
+
+/// All snapshots, newest first.
+pub fn list() -> Result<Vec<Snapshot>, ShellmindError> {
+    list_in(&DataKind::Undo.path()?)
+}
+
+fn list_in(dir: &Path) -> Result<Vec<Snapshot>, ShellmindError> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots: Vec<Snapshot> = std::fs::read_dir(dir)
+        .map_err(|e| ShellmindError::Other(format!("Failed to read undo directory: {}", e)))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().map_or(false, |ext| ext == "json"))
+        .filter_map(|e| persist::read_private(&e.path()).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(snapshots)
+}
+
+/// Restores the files of snapshot `id`, or of the newest snapshot, and removes it from
+/// the store. Files that did not exist before are deleted.
+pub fn restore(id: Option<&str>) -> Result<Snapshot, ShellmindError> {
+    restore_from(&DataKind::Undo.path()?, id)
+}
+
+fn restore_from(dir: &Path, id: Option<&str>) -> Result<Snapshot, ShellmindError> {
+    let snapshot = match id {
+        Some(id) => list_in(dir)?.into_iter().find(|s| s.id == id),
+        None => list_in(dir)?.into_iter().next(),
+    }
+    .ok_or_else(|| ShellmindError::Other(match id {
+        Some(id) => format!("Unknown undo snapshot: {}", id),
+        None => "Nothing to undo".to_string(),
+    }))?;
+    for file in &snapshot.files {
+        match &file.content {
+            Some(content) => {
+                persist::atomic_write(&file.path, content)?;
+                #[cfg(unix)]
+                if let Some(mode) = file.mode {
+                    use std::os::unix::fs::PermissionsExt;
+                    std::fs::set_permissions(&file.path, std::fs::Permissions::from_mode(mode))
+                        .map_err(|e| ShellmindError::Other(format!("Failed to set permissions on '{}': {}", file.path.display(), e)))?;
+                }
+            }
+            None if file.path.exists() => std::fs::remove_file(&file.path)
+                .map_err(|e| ShellmindError::Other(format!("Failed to remove '{}': {}", file.path.display(), e)))?,
+            None => {}
+        }
+    }
+    let path = snapshot_path(dir, &snapshot.id);
+    std::fs::remove_file(&path).map_err(|e| ShellmindError::Other(format!("Failed to remove '{}': {}", path.display(), e)))?;
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("shellmind-undo-test-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_restore_brings_back_contents_and_removes_new_files() {
+        let temp = TempDir::new();
+        let store = temp.0.join("undo");
+        let edited = temp.0.join("notes.txt");
+        let created = temp.0.join("new.txt");
+        std::fs::write(&edited, "before").unwrap();
+
+        let id = save_in(&store, "edit notes", &[edited.clone(), created.clone()]).unwrap();
+        std::fs::write(&edited, "after").unwrap();
+        std::fs::write(&created, "new").unwrap();
+
+        let snapshot = restore_from(&store, None).unwrap();
+        assert_eq!(snapshot.id, id);
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "before");
+        assert!(!created.exists());
+        assert!(list_in(&store).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_keeps_the_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new();
+        let store = temp.0.join("undo");
+        let script = temp.0.join("deploy.sh");
+        std::fs::write(&script, "#!/bin/sh\necho deploy\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let id = save_in(&store, "edit script", &[script.clone()]).unwrap();
+        std::fs::remove_file(&script).unwrap();
+        std::fs::write(&script, "rewritten").unwrap();
+
+        restore_from(&store, Some(&id)).unwrap();
+        assert_eq!(std::fs::read_to_string(&script).unwrap(), "#!/bin/sh\necho deploy\n");
+        assert_eq!(std::fs::metadata(&script).unwrap().permissions().mode() & 0o7777, 0o755);
+    }
+
+    #[test]
+    fn test_restore_of_an_unknown_snapshot_fails() {
+        let temp = TempDir::new();
+        assert!(restore_from(&temp.0.join("undo"), Some("missing")).is_err());
+        assert!(restore_from(&temp.0.join("undo"), None).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+//! Workspace roots: the current directory plus related repositories registered with
+//! `shellmind workspace add`, searched together by the file tools.
+
+use std::path::{Path, PathBuf};
+
+use crate::{ShellmindConfig, ShellmindError};
+
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+    /// The current directory first, then the registered roots that still exist.
+    pub roots: Vec<PathBuf>,
+}
+
+impl Workspace {
+    pub fn from_config(config: &ShellmindConfig) -> Self {
+        let mut roots = Vec::new();
+        if let Ok(cwd) = std::env::current_dir() {
+            roots.push(cwd);
+        }
+        for root in &config.workspace_roots {
+            let root = PathBuf::from(root);
+            if root.is_dir() && !roots.iter().any(|r| r == &root || root.starts_with(r)) {
+                roots.push(root);
+            }
+        }
+        Workspace { roots }
+    }
+
+    /// Registers `path` as an additional root, stored as an absolute path.
+    pub fn add(config: &mut ShellmindConfig, path: &Path) -> Result<PathBuf, ShellmindError> {
+        let root = std::fs::canonicalize(path)
+            .map_err(|e| ShellmindError::Other(format!("Failed to resolve workspace root '{}': {}", path.display(), e)))?;
+        if !root.is_dir() {
+            return Err(ShellmindError::Other(format!("Workspace root '{}' is not a directory", root.display())));
+        }
+        let entry = root.to_string_lossy().into_owned();
+        if !config.workspace_roots.contains(&entry) {
+            config.workspace_roots.push(entry);
+        }
+        Ok(root)
+    }
+
+    /// Unregisters a root; returns false when it was not registered.
+    pub fn remove(config: &mut ShellmindConfig, path: &Path) -> bool {
+        let resolved = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let before = config.workspace_roots.len();
+        config
+            .workspace_roots
+            .retain(|root| Path::new(root) != resolved && Path::new(root) != path);
+        config.workspace_roots.len() != before
+    }
+
+    /// Walks every root, honouring each root's `.gitignore` and `.ignore` files.
+    pub fn walker(roots: &[PathBuf]) -> Option<ignore::Walk> {
+        let (first, rest) = roots.split_first()?;
+        let mut builder = ignore::WalkBuilder::new(first);
+        for root in rest {
+            builder.add(root);
+        }
+        Some(builder.git_ignore(true).build())
+    }
+
+    /// Lists the roots for the system prompt; empty for a single-root workspace.
+    pub fn prompt_context(&self) -> String {
+        if self.roots.len() < 2 {
+            return String::new();
+        }
+        let roots = self.roots.iter().map(|r| format!("- {}", r.display())).collect::<Vec<_>>().join("\n");
+        format!(
+            "The workspace spans several repositories. File searches cover all of these roots; use absolute paths for files outside the current directory:\n{}",
+            roots
+        )
+    }
+}
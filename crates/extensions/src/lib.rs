@@ -3,23 +3,137 @@
 // Basic trait-based plugin architecture
 pub trait ShellmindPlugin {
     fn name(&self) -> &str;
-    // TODO: Add more plugin methods
 }
 
-// Secure storage (placeholder)
+// Secure storage
 pub mod secure_storage {
-    // TODO: Implement secure storage using rust-crypto or a similar library
-    pub fn store_secret(_key: &str, _value: &str) -> Result<(), String> {
-        // Placeholder implementation
-        println!("Storing secret (placeholder): {}", _key);
-        Ok(())
+    use fs2::FileExt;
+    use std::collections::BTreeMap;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    // Secrets live in a file readable only by the owner
+    fn secrets_path() -> Result<PathBuf, String> {
+        let home_dir = dirs::home_dir().ok_or_else(|| "Could not find home directory.".to_string())?;
+        Ok(home_dir.join(".shellmind").join("secrets.json"))
+    }
+
+    fn load_secrets() -> Result<BTreeMap<String, String>, String> {
+        let path = secrets_path()?;
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read secrets file: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse secrets file: {}", e))
+    }
+
+    /// Writes the secrets to a temporary file created as 0600 and renames it into place,
+    /// so they are never readable by others, not even briefly.
+    fn save_secrets(secrets: &BTreeMap<String, String>) -> Result<(), String> {
+        let path = secrets_path()?;
+        let content = serde_json::to_string_pretty(secrets).map_err(|e| format!("Failed to serialize secrets: {}", e))?;
+        let tmp_path = path.with_file_name(format!(".secrets.json.{}.tmp", std::process::id()));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let write = || -> std::io::Result<()> {
+            let mut file = options.open(&tmp_path)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            std::fs::rename(&tmp_path, &path)
+        };
+        write().map_err(|e| {
+            let _ = std::fs::remove_file(&tmp_path);
+            format!("Failed to write secrets file: {}", e)
+        })
+    }
+
+    /// Runs a read-modify-write of the secrets file under an exclusive lock, so
+    /// concurrent instances don't drop each other's changes.
+    fn update_secrets(f: impl FnOnce(&mut BTreeMap<String, String>)) -> Result<(), String> {
+        let path = secrets_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create secrets directory: {}", e))?;
+        }
+        let lock = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(path.with_file_name("secrets.json.lock"))
+            .map_err(|e| format!("Failed to open secrets lock: {}", e))?;
+        lock.lock_exclusive().map_err(|e| format!("Failed to lock secrets file: {}", e))?;
+        let result = load_secrets().and_then(|mut secrets| {
+            f(&mut secrets);
+            save_secrets(&secrets)
+        });
+        let _ = lock.unlock();
+        result
+    }
+
+    pub fn store_secret(key: &str, value: &str) -> Result<(), String> {
+        update_secrets(|secrets| {
+            secrets.insert(key.to_string(), value.to_string());
+        })
     }
 
-    pub fn retrieve_secret(_key: &str) -> Result<String, String> {
-        // Placeholder implementation
-        println!("Retrieving secret (placeholder): {}", _key);
-        Ok("retrieved_secret_placeholder".to_string())
+    pub fn retrieve_secret(key: &str) -> Result<String, String> {
+        load_secrets()?
+            .remove(key)
+            .ok_or_else(|| format!("Secret '{}' not found in secure storage", key))
+    }
+
+    pub fn delete_secret(key: &str) -> Result<(), String> {
+        update_secrets(|secrets| {
+            secrets.remove(key);
+        })
     }
 }
 
+// Program lookup shared by every crate that checks for an external tool
+pub mod which {
+    use std::path::{Path, PathBuf};
 
+    fn is_executable(path: &Path) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::metadata(path).map_or(false, |m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        }
+        #[cfg(not(unix))]
+        {
+            path.is_file()
+        }
+    }
+
+    /// The executable `program` resolves to: a path containing `/` is taken as is,
+    /// anything else is looked up in the directories of `PATH`, like the shell does.
+    pub fn find_in_path(program: &str) -> Option<PathBuf> {
+        if program.is_empty() {
+            return None;
+        }
+        if program.contains('/') {
+            let path = PathBuf::from(program);
+            return is_executable(&path).then_some(path);
+        }
+        let paths = std::env::var_os("PATH")?;
+        std::env::split_paths(&paths).map(|dir| dir.join(program)).find(|path| is_executable(path))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_find_in_path() {
+            assert!(find_in_path("sh").is_some());
+            assert_eq!(find_in_path("/bin/sh"), Some(PathBuf::from("/bin/sh")));
+            assert_eq!(find_in_path("shellmind-no-such-program"), None);
+            assert_eq!(find_in_path(""), None);
+            // Not executable
+            assert_eq!(find_in_path("/etc/hostname"), None);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
     tonic_build::configure()
         .build_server(false) // We only need the client
         .compile(
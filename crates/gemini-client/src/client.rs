@@ -0,0 +1,139 @@
+//! The reusable client: a pooled HTTP client and gRPC channels, shared by every request so
+//! they don't reconnect each time.
+
+use std::time::Duration;
+
+use crate::retry::RetryPolicy;
+use crate::types::{GeminiContent, InlineData};
+use crate::{Error, REST_ENDPOINT};
+
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    pub request_timeout: Duration,
+    pub connect_timeout: Duration,
+    pub keepalive_interval: Duration,
+    pub retry: RetryPolicy,
+    /// Base URL for REST requests; tests point it at a local server.
+    pub rest_endpoint: String,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        ClientOptions {
+            request_timeout: Duration::from_secs(120),
+            connect_timeout: Duration::from_secs(10),
+            keepalive_interval: Duration::from_secs(30),
+            retry: RetryPolicy::default(),
+            rest_endpoint: REST_ENDPOINT.to_string(),
+        }
+    }
+}
+
+/// One `generateContent` call: the conversation so far plus a new user prompt.
+#[derive(Debug, Clone)]
+pub struct GenerateRequest<'a> {
+    pub api_key: &'a str,
+    pub model: &'a str,
+    pub temperature: f32,
+    pub prompt: &'a str,
+    pub images: &'a [InlineData],
+    pub history: &'a [GeminiContent],
+}
+
+impl GenerateRequest<'_> {
+    /// `history` followed by the prompt as a user turn.
+    pub fn contents(&self) -> Vec<GeminiContent> {
+        let mut contents = self.history.to_vec();
+        contents.push(GeminiContent::user_prompt(self.prompt, self.images));
+        contents
+    }
+}
+
+pub struct GeminiClient {
+    pub(crate) http: reqwest::Client,
+    /// Connected channels keyed by endpoint URL; `Channel` clones share the connection.
+    #[cfg(feature = "grpc")]
+    pub(crate) channels: tokio::sync::Mutex<std::collections::HashMap<String, tonic::transport::Channel>>,
+    pub(crate) options: ClientOptions,
+}
+
+impl GeminiClient {
+    pub fn new(options: ClientOptions) -> Result<Self, Error> {
+        let http = reqwest::Client::builder()
+            .timeout(options.request_timeout)
+            .connect_timeout(options.connect_timeout)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(options.keepalive_interval)
+            .http2_keep_alive_interval(options.keepalive_interval)
+            .http2_keep_alive_while_idle(true)
+            .build()?;
+
+        Ok(GeminiClient {
+            http,
+            #[cfg(feature = "grpc")]
+            channels: tokio::sync::Mutex::new(std::collections::HashMap::new()),
+            options,
+        })
+    }
+
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    pub fn options(&self) -> &ClientOptions {
+        &self.options
+    }
+
+    /// Lists the available models, which verifies the key without generating content.
+    pub async fn check_api_key(&self, api_key: &str) -> Result<(), Error> {
+        let url = format!("{}/v1beta/models", self.options.rest_endpoint);
+        let response = self.http.get(&url).header(crate::API_KEY_HEADER, api_key).send().await?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            return Err(Error::Status { status, body: response.text().await.unwrap_or_default() });
+        }
+        Ok(())
+    }
+
+    /// Generates a reply over REST, retrying transient failures.
+    pub async fn generate_rest(&self, request: &GenerateRequest<'_>) -> Result<String, Error> {
+        self.options.retry.run(|| crate::rest::generate(self, request)).await
+    }
+
+    /// Generates a reply over REST, passing each piece of text to `on_text` as it arrives.
+    /// Returns the whole reply. Only the connection is retried: once text has been
+    /// delivered, a failure is returned as is.
+    pub async fn stream_rest(&self, request: &GenerateRequest<'_>, on_text: impl FnMut(&str)) -> Result<String, Error> {
+        let response = self.options.retry.run(|| crate::rest::start_stream(self, request)).await?;
+        crate::rest::read_stream(response, on_text).await
+    }
+
+    /// Starts a REST `streamGenerateContent` call and returns its text chunks as a stream.
+    /// Only the connection is retried; errors while reading end the stream.
+    pub async fn stream_rest_text(&self, request: &GenerateRequest<'_>) -> Result<crate::TextStream, Error> {
+        let response = self.options.retry.run(|| crate::rest::start_stream(self, request)).await?;
+        Ok(crate::rest::text_stream(response))
+    }
+
+    /// Generates a reply over gRPC at `endpoint`, retrying transient failures.
+    #[cfg(feature = "grpc")]
+    pub async fn generate_grpc(&self, endpoint: &str, request: &GenerateRequest<'_>) -> Result<String, Error> {
+        self.options.retry.run(|| crate::grpc::generate(self, endpoint, request)).await
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    pub async fn generate_grpc(&self, _endpoint: &str, _request: &GenerateRequest<'_>) -> Result<String, Error> {
+        Err(Error::GrpcUnavailable)
+    }
+
+    /// Connects to a gRPC endpoint, or reuses the connection made earlier.
+    #[cfg(feature = "grpc")]
+    pub async fn connect_grpc(&self, endpoint: &str) -> Result<(), Error> {
+        self.grpc_channel(endpoint).await.map(|_| ())
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    pub async fn connect_grpc(&self, _endpoint: &str) -> Result<(), Error> {
+        Err(Error::GrpcUnavailable)
+    }
+}
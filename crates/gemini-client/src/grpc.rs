@@ -0,0 +1,71 @@
+//! `GenerateContent` over gRPC.
+
+use base64::Engine;
+use tonic::transport::{Channel, Endpoint};
+
+use crate::client::{GeminiClient, GenerateRequest};
+use crate::google::generativelanguage::v1beta::generative_service_client::GenerativeServiceClient;
+use crate::google::generativelanguage::v1beta::{Blob, Content, GenerateContentRequest, GenerationConfig, Part};
+use crate::types::GeminiPart;
+use crate::{Error, API_KEY_HEADER};
+
+impl GeminiClient {
+    /// Returns a channel to `endpoint`, connecting on first use.
+    pub(crate) async fn grpc_channel(&self, endpoint: &str) -> Result<Channel, Error> {
+        let mut channels = self.channels.lock().await;
+        if let Some(channel) = channels.get(endpoint) {
+            return Ok(channel.clone());
+        }
+        let channel = Endpoint::from_shared(endpoint.to_string())?
+            .timeout(self.options.request_timeout)
+            .connect_timeout(self.options.connect_timeout)
+            .http2_keep_alive_interval(self.options.keepalive_interval)
+            .keep_alive_while_idle(true)
+            .connect()
+            .await?;
+        channels.insert(endpoint.to_string(), channel.clone());
+        Ok(channel)
+    }
+}
+
+fn to_grpc_part(part: &GeminiPart) -> Result<Part, Error> {
+    let inline_data = match &part.inline_data {
+        Some(image) => Some(Blob {
+            mime_type: image.mime_type.clone(),
+            data: base64::engine::general_purpose::STANDARD
+                .decode(&image.data)
+                .map_err(|e| Error::InvalidRequest(format!("Invalid image data: {}", e)))?,
+        }),
+        None => None,
+    };
+    Ok(Part { text: part.text.clone(), inline_data })
+}
+
+pub(crate) async fn generate(client: &GeminiClient, endpoint: &str, request: &GenerateRequest<'_>) -> Result<String, Error> {
+    let channel = client.grpc_channel(endpoint).await?;
+    let mut service = GenerativeServiceClient::new(channel);
+
+    let contents = request
+        .contents()
+        .iter()
+        .map(|c| Ok(Content { role: c.role.clone(), parts: c.parts.iter().map(to_grpc_part).collect::<Result<_, Error>>()? }))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut grpc_request = tonic::Request::new(GenerateContentRequest {
+        model: format!("models/{}", request.model),
+        contents,
+        generation_config: Some(GenerationConfig { temperature: request.temperature }),
+    });
+    let key = request.api_key.parse().map_err(|_| Error::InvalidRequest("API key is not a valid header value".to_string()))?;
+    grpc_request.metadata_mut().insert(API_KEY_HEADER, key);
+
+    let response = service.generate_content(grpc_request).await?.into_inner();
+
+    Ok(response
+        .candidates
+        .first()
+        .and_then(|c| c.content.as_ref())
+        .and_then(|c| c.parts.first())
+        .map(|p| p.text.clone())
+        .unwrap_or_else(|| crate::rest::NO_REPLY.to_string()))
+}
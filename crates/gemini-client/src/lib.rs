@@ -0,0 +1,82 @@
+//! Client for the Gemini `generateContent` API over REST or gRPC (`grpc` feature).
+//! Connections are pooled, the API key is sent as a header, and requests that fail with a
+//! transient error are retried with backoff. Knows nothing about Shellmind's config.
+
+use thiserror::Error;
+
+pub mod client;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod rest;
+pub mod retry;
+pub mod types;
+
+#[cfg(feature = "grpc")]
+pub mod google {
+    pub mod generativelanguage {
+        pub mod v1beta {
+            tonic::include_proto!("google.generativelanguage.v1beta");
+        }
+    }
+}
+
+pub use client::{ClientOptions, GeminiClient, GenerateRequest};
+pub use retry::RetryPolicy;
+pub use types::{Candidate, GeminiContent, GeminiPart, GeminiRequest, GeminiResponse, InlineData};
+
+/// Reply text from a streaming call, one chunk per server-sent event.
+pub type TextStream = std::pin::Pin<Box<dyn futures_util::Stream<Item = Result<String, Error>> + Send>>;
+
+/// Base URL of the public REST API.
+pub const REST_ENDPOINT: &str = "https://generativelanguage.googleapis.com";
+
+/// Header carrying the API key, on REST requests and as gRPC metadata.
+pub const API_KEY_HEADER: &str = "x-goog-api-key";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("API error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("JSON parsing error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("API request failed with status: {status} - {body}")]
+    Status { status: u16, body: String },
+    #[cfg(feature = "grpc")]
+    #[error("gRPC error: {0}")]
+    Grpc(#[from] tonic::Status),
+    #[cfg(feature = "grpc")]
+    #[error("gRPC transport error: {0}")]
+    GrpcTransport(#[from] tonic::transport::Error),
+    #[cfg(feature = "grpc")]
+    #[error("Invalid URI: {0}")]
+    InvalidUri(#[from] http::uri::InvalidUri),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("gRPC support was not compiled in; use the REST API")]
+    GrpcUnavailable,
+}
+
+impl Error {
+    /// True for failures a later attempt may not hit: rate limiting, server errors and
+    /// dropped connections.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Http(e) => e.is_timeout() || e.is_connect(),
+            Error::Status { status, .. } => retry::is_transient_status(*status),
+            #[cfg(feature = "grpc")]
+            Error::Grpc(status) => matches!(status.code(), tonic::Code::Unavailable | tonic::Code::ResourceExhausted),
+            _ => false,
+        }
+    }
+
+    /// True when the request could not be built from the caller's settings, e.g. a
+    /// malformed endpoint, as opposed to the API failing.
+    pub fn is_configuration(&self) -> bool {
+        match self {
+            #[cfg(feature = "grpc")]
+            Error::InvalidUri(_) => true,
+            Error::GrpcUnavailable => true,
+            _ => false,
+        }
+    }
+}
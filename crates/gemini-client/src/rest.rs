@@ -0,0 +1,137 @@
+//! `generateContent` and `streamGenerateContent` over HTTPS.
+
+use std::collections::VecDeque;
+
+use futures_util::{stream, StreamExt};
+use serde_json::json;
+
+use crate::client::{GeminiClient, GenerateRequest};
+use crate::types::{GeminiRequest, GeminiResponse};
+use crate::{Error, API_KEY_HEADER};
+
+/// Reply text used when the model returned no candidates.
+pub(crate) const NO_REPLY: &str = "No command generated";
+
+fn body(request: &GenerateRequest<'_>) -> GeminiRequest {
+    GeminiRequest {
+        contents: request.contents(),
+        generation_config: Some(json!({
+            "temperature": request.temperature,
+        })),
+    }
+}
+
+async fn send(client: &GeminiClient, request: &GenerateRequest<'_>, method: &str) -> Result<reqwest::Response, Error> {
+    let url = format!("{}/v1beta/models/{}:{}", client.options.rest_endpoint, request.model, method);
+    let response = client.http.post(&url).header(API_KEY_HEADER, request.api_key).json(&body(request)).send().await?;
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        return Err(Error::Status { status, body: response.text().await? });
+    }
+    Ok(response)
+}
+
+pub(crate) async fn generate(client: &GeminiClient, request: &GenerateRequest<'_>) -> Result<String, Error> {
+    let response: GeminiResponse = send(client, request, "generateContent").await?.json().await?;
+    Ok(response.text().unwrap_or(NO_REPLY).to_string())
+}
+
+pub(crate) async fn start_stream(client: &GeminiClient, request: &GenerateRequest<'_>) -> Result<reqwest::Response, Error> {
+    send(client, request, "streamGenerateContent?alt=sse").await
+}
+
+/// The text of each event in a `streamGenerateContent` response, as it arrives.
+pub(crate) fn text_stream(response: reqwest::Response) -> crate::TextStream {
+    let state = (response, SseBuffer::default(), VecDeque::new(), false);
+    stream::unfold(state, |(mut response, mut events, mut ready, mut done)| async move {
+        loop {
+            if let Some(text) = ready.pop_front() {
+                return Some((Ok(text), (response, events, ready, done)));
+            }
+            if done {
+                return None;
+            }
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    for data in events.push(&String::from_utf8_lossy(&chunk)) {
+                        match serde_json::from_str::<GeminiResponse>(&data) {
+                            Ok(event) => ready.extend(event.text().filter(|text| !text.is_empty()).map(str::to_string)),
+                            Err(e) => return Some((Err(e.into()), (response, events, ready, true))),
+                        }
+                    }
+                }
+                Ok(None) => done = true,
+                Err(e) => return Some((Err(e.into()), (response, events, ready, true))),
+            }
+        }
+    })
+    .boxed()
+}
+
+/// Drains `text_stream`, passing each chunk to `on_text`, and returns the whole reply.
+pub(crate) async fn read_stream(response: reqwest::Response, mut on_text: impl FnMut(&str)) -> Result<String, Error> {
+    let mut texts = text_stream(response);
+    let mut reply = String::new();
+    while let Some(text) = texts.next().await {
+        let text = text?;
+        on_text(&text);
+        reply.push_str(&text);
+    }
+    Ok(reply)
+}
+
+/// Collects server-sent events from arbitrarily split chunks.
+#[derive(Default)]
+struct SseBuffer {
+    pending: String,
+}
+
+impl SseBuffer {
+    /// Adds a chunk and returns the `data` of every event it completed.
+    fn push(&mut self, chunk: &str) -> Vec<String> {
+        self.pending.push_str(&chunk.replace("\r\n", "\n"));
+        let mut events = Vec::new();
+        while let Some(end) = self.pending.find("\n\n") {
+            let event: String = self.pending.drain(..end + 2).collect();
+            let data: Vec<&str> = event.lines().filter_map(|line| line.strip_prefix("data:")).map(str::trim_start).collect();
+            if !data.is_empty() {
+                events.push(data.join("\n"));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_split_across_chunks_are_joined() {
+        let mut buffer = SseBuffer::default();
+        assert!(buffer.push("data: {\"a\":").is_empty());
+        assert_eq!(buffer.push("1}\n\ndata: {\"b\":2}\r\n\r\n"), vec!["{\"a\":1}", "{\"b\":2}"]);
+    }
+
+    #[test]
+    fn lines_without_data_are_ignored() {
+        let mut buffer = SseBuffer::default();
+        assert_eq!(buffer.push(": keep-alive\n\nevent: message\ndata: x\n\n"), vec!["x"]);
+    }
+
+    #[test]
+    fn request_body_ends_with_the_prompt() {
+        let history = [crate::GeminiContent::user_prompt("earlier", &[])];
+        let request = GenerateRequest {
+            api_key: "key",
+            model: "gemini-pro",
+            temperature: 0.5,
+            prompt: "now",
+            images: &[],
+            history: &history,
+        };
+        let body = serde_json::to_value(body(&request)).unwrap();
+        assert_eq!(body["contents"][1]["parts"][0]["text"], "now");
+        assert_eq!(body["generation_config"]["temperature"], 0.5);
+    }
+}
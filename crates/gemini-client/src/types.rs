@@ -0,0 +1,93 @@
+//! Request and response bodies of the REST API, also used as the transport-neutral
+//! conversation format.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiPart {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    #[serde(default, rename = "inlineData", skip_serializing_if = "Option::is_none")]
+    pub inline_data: Option<InlineData>,
+}
+
+/// Base64-encoded media sent inline with a prompt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlineData {
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiContent {
+    pub role: String,
+    pub parts: Vec<GeminiPart>,
+}
+
+impl GeminiContent {
+    /// A user turn: the text followed by any attached images.
+    pub fn user_prompt(text: &str, images: &[InlineData]) -> Self {
+        let mut parts = vec![GeminiPart { text: text.to_string(), inline_data: None }];
+        parts.extend(images.iter().map(|image| GeminiPart { text: String::new(), inline_data: Some(image.clone()) }));
+        GeminiContent { role: "user".to_string(), parts }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiRequest {
+    pub contents: Vec<GeminiContent>,
+    pub generation_config: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiResponse {
+    #[serde(default)]
+    pub candidates: Vec<Candidate>,
+}
+
+impl GeminiResponse {
+    /// Text of the first part of the first candidate.
+    pub fn text(&self) -> Option<&str> {
+        self.candidates.first().and_then(|c| c.content.parts.first()).map(|p| p.text.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    pub content: GeminiContent,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_prompt_puts_images_after_text() {
+        let image = InlineData { mime_type: "image/png".to_string(), data: "aGk=".to_string() };
+        let content = GeminiContent::user_prompt("what is this?", &[image]);
+        let json = serde_json::to_value(&content).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "role": "user",
+                "parts": [{ "text": "what is this?" }, { "inlineData": { "mimeType": "image/png", "data": "aGk=" } }]
+            })
+        );
+    }
+
+    #[test]
+    fn response_text_is_first_part_of_first_candidate() {
+        let response: GeminiResponse = serde_json::from_str(
+            r#"{"candidates":[{"content":{"role":"model","parts":[{"text":"ls -la"},{"text":"ignored"}]}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(response.text(), Some("ls -la"));
+    }
+
+    #[test]
+    fn response_without_candidates_has_no_text() {
+        let response: GeminiResponse = serde_json::from_str(r#"{"promptFeedback":{"blockReason":"SAFETY"}}"#).unwrap();
+        assert_eq!(response.text(), None);
+    }
+}
@@ -1,110 +1,225 @@
-use core::{generate_command_rest, generate_command_grpc, get_system_prompt_text, GeminiContent, ShellmindConfig, ShellmindError, ToolRegistry, SandboxManager, SecurityManager, MemoryManager, CommandHistoryManager};
-use core::tools::{ReadFileTool, WriteFileTool, EditTool, LSTool, GrepTool, GlobTool, ShellTool, WebFetchTool, WebSearchTool, MemoryTool, ReadManyFilesTool};
+use core::agent::{AgentLimits, AgentRun, CallStatus, CallTrace, Outcome, ToolCall};
+use core::audit::{AuditEvent, AuditKind};
+use core::prompt_trials::Signal;
+use core::{ApprovalMode, ConfirmationDetails, GeminiContent, ShellmindConfig, ShellmindError, ToolRegistry, SandboxManager, SecurityManager, CommandHistoryManager};
+use core::session::Session;
+use core::transport::Transport;
 use std::io::{self, Write};
 use ui::CLIInterface;
+use ui::i18n::{resolve_alias, Language, Message};
 use cli::Cli;
 use dialoguer::{Select, theme::ColorfulTheme};
 use std::process::Command;
 use anyhow::Result;
 use rustyline::error::ReadlineError;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Sessions listed by `/sessions`.
+const SESSION_LIST_LIMIT: usize = 20;
+
+/// How long a confirmation waits for the model's risk explanation before using the
+/// static one.
+const RISK_EXPLANATION_TIMEOUT: Duration = Duration::from_secs(8);
 
 struct ShellmindCLI {
     config: ShellmindConfig,
     tool_registry: ToolRegistry,
     sandbox_manager: SandboxManager,
     security_manager: SecurityManager,
-    memory_manager: MemoryManager,
     command_history_manager: CommandHistoryManager,
+    session: Session,
+    transport: Arc<dyn Transport>,
     ui: CLIInterface,
+    /// Images pasted with `/paste-image`, sent with the next prompt.
+    pending_images: Vec<core::InlineData>,
+    /// Variables found in `.env` at startup, for `/env`.
+    dotenv_vars: Vec<core::environment::DotenvVar>,
+    /// Aliases and functions from the `shellmind init` hook, expanded before commands run.
+    shell_env: core::shell_env::ShellEnv,
+    /// The last prompt as typed and the latest response to it, for `/retry`.
+    last_exchange: Option<(String, String)>,
+    /// Output of the last executed command, for `/translate`.
+    last_output: std::cell::RefCell<String>,
+    /// Where unattended dangerous approvals are forwarded; see `remote_approval`.
+    approval_channel: Option<core::remote_approval::Channel>,
 }
 
 impl ShellmindCLI {
-    async fn new() -> Result<Self> {
-        // Load .env file
-        dotenv::dotenv().ok();
-
-        // Load configuration
+    async fn new(cli: &Cli) -> Result<Self> {
+        // Load configuration; replayed sessions never reach the API and need no key.
+        // `.env` is applied afterwards as configured, then the configuration is reloaded
+        // so `SHELLMIND_*` and `GEMINI_API_KEY` from `.env` take effect.
         let config = core::ConfigManager::load_configuration()?;
-        core::ConfigManager::validate_configuration(&config)?;
-
-        let mut tool_registry = ToolRegistry::new();
-        tool_registry.register(ReadFileTool);
-        tool_registry.register(WriteFileTool);
-        tool_registry.register(EditTool);
-        tool_registry.register(LSTool);
-        tool_registry.register(GrepTool);
-        tool_registry.register(GlobTool);
-        tool_registry.register(ShellTool);
-        tool_registry.register(WebFetchTool);
-        tool_registry.register(WebSearchTool);
-        tool_registry.register(MemoryTool);
-        tool_registry.register(ReadManyFilesTool);
+        let dotenv_vars = core::environment::load_dotenv(&config);
+        let mut config = if dotenv_vars.iter().any(|v| v.loaded) {
+            core::ConfigManager::load_configuration()?
+        } else {
+            config
+        };
+        // One variant serves the whole session, so the conversation stays consistent
+        config.prompt_variant = core::prompt_trials::choose_variant(&config);
+        if !cli.offline {
+            core::ConfigManager::validate_configuration(&config)?;
+        }
+
+        let tool_registry = ToolRegistry::from_config(&config)?;
+        if let Err(e) = core::retention::apply_retention(&config) {
+            eprintln!("Warning: failed to apply retention policy: {}", e);
+        }
+        let command_history_manager = CommandHistoryManager::new(config.max_history_entries)?;
+        let session = Session::new(&config)?;
+        let shell_env = if config.use_shell_aliases { core::shell_env::ShellEnv::load() } else { Default::default() };
+        let mut ui = CLIInterface::new()?;
+        ui.set_language(Language::from_code(config.resolved_language()));
+        ui.set_pager_mode(ui::pager::PagerMode::from_name(&config.pager));
+        ui.set_prompt_format(&config.prompt_format);
+        ui.set_inline_images(&config.inline_images);
+        for setting in ui.configure_editor(&config.edit_mode, &config.completion_type, &config.key_bindings) {
+            ui.print_error(&ui.tf(Message::InvalidEditorSetting, &[&setting]));
+        }
+        for error in tool_registry.load_errors() {
+            ui.print_error(error);
+        }
+        if let Some(error) = core::redact::active().load_error() {
+            ui.print_error(&ui.tf(Message::RedactionProfileBroken, &[&error]));
+        }
+        if config.tts_enabled && !ui.enable_speech(Some(&config.tts_command)) {
+            ui.print_error(ui.t(Message::SpeechUnavailable));
+        }
+        let approval_channel = core::remote_approval::Channel::from_config(&config).unwrap_or_else(|e| {
+            ui.print_error(&e.to_string());
+            None
+        });
 
         Ok(Self {
             config,
             tool_registry,
             sandbox_manager: SandboxManager,
             security_manager: SecurityManager,
-            memory_manager: MemoryManager::new(),
-            command_history_manager: CommandHistoryManager::new()?,
-            ui: CLIInterface::new()?,
+            command_history_manager,
+            session,
+            transport: cli.transport()?,
+            ui,
+            pending_images: Vec::new(),
+            last_exchange: None,
+            last_output: Default::default(),
+            shell_env,
+            dotenv_vars,
+            approval_channel,
         })
     }
 
-    async fn start(&mut self) -> Result<()> {
+    async fn start(&mut self, cli: &Cli) -> Result<()> {
         // Discover tools
         self.tool_registry.discover_tools().await?;
 
         // Load hierarchical context
-        self.memory_manager.load_hierarchical_context().await?;
+        self.session.memory.load_hierarchical_context().await?;
+
+        if let Some(options) = cli.test_options() {
+            self.run_test_workflow(options).await?;
+            return Ok(());
+        }
+        if let Some(options) = cli.script_options() {
+            self.run_script_workflow(options).await?;
+            return Ok(());
+        }
+        if let Some(options) = cli.run_options() {
+            self.run_once_workflow(options).await?;
+            return Ok(());
+        }
+        if let Some(options) = cli.suggest_options() {
+            self.suggest_workflow(options).await?;
+            return Ok(());
+        }
+        if let Some(options) = cli.tf_review_options() {
+            self.tf_review_workflow(options).await?;
+            return Ok(());
+        }
+
+        // A recovered session continues in the interactive loop below
+        let recovered = match cli.recover_session() {
+            Some(id) => Some(core::recovery::find(id.as_deref())?),
+            None => None,
+        };
 
-        // Check for CLI arguments
-        let args: Vec<String> = std::env::args().collect();
-        if args.len() > 1 {
-            // If arguments are present, pass them to the CLI crate and exit
-            Cli::run(args, &self.ui).await?;
+        // If a subcommand is present, let the CLI crate handle it and exit
+        if recovered.is_none() && cli.has_command() {
+            cli.execute(&self.ui).await?;
             return Ok(());
         }
 
+        // Answer the first question about "this project" without a tool call
+        if self.config.startup_context && recovered.is_none() {
+            if let Some(snapshot) = core::snapshot::take(&self.session.working_dir) {
+                self.session.history.extend(core::snapshot::exchange(&snapshot));
+            }
+        }
+
         // Show banner
-        self.ui.print_banner();
+        self.ui.print_banner(&self.config.banner_text, &self.config.banner_font);
 
-        println!("Shellmind is initialized. Type 'exit' to quit.");
+        if self.config.simulate_execution {
+            self.ui.print_alert(self.ui.t(Message::SimulationMode));
+        }
+        if let Some(production) = &self.config.production {
+            self.ui.print_alert(&self.ui.tf(Message::ProductionSafeMode, &[&production.reason]));
+        }
+        println!("{}", self.ui.t(Message::Initialized));
+        if self.config.startup_tips {
+            let day = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86_400) as usize;
+            let tip = ui::tips::pick(day, &core::tips::recently_used());
+            self.ui.print_tip(tip.text(Language::from_code(self.config.resolved_language())));
+        }
+        if self.config.directory_context && recovered.is_none() {
+            self.offer_directory_context().await?;
+        }
 
-        // Initialize conversation history with the system prompt
-        let mut history = vec![
-            GeminiContent {
-                role: "user".to_string(),
-                parts: vec![core::GeminiPart {
-                    text: get_system_prompt_text(&self.config),
-                }],
-            },
-            GeminiContent {
-                role: "model".to_string(),
-                parts: vec![core::GeminiPart {
-                    text: "Okay, I'm ready. What can I help you with?".to_string(),
-                }],
-            },
-        ];
+        core::recovery::install_panic_hook();
+        core::recovery::spawn_autosave(std::time::Duration::from_secs(self.config.autosave_interval_secs.max(1)));
+        #[cfg(unix)]
+        spawn_termination_handler(Language::from_code(self.config.resolved_language()));
+        let mut queued_input = match recovered {
+            Some(checkpoint) => self.resume_checkpoint(checkpoint).await?,
+            None => {
+                core::recovery::begin(&self.session)?;
+                None
+            }
+        };
 
         // Main interactive loop
         loop {
-            let input = match self.ui.read_user_input() {
-                Ok(line) => line,
-                Err(ReadlineError::Interrupted) => {
-                    println!("Ctrl-C received, exiting.");
-                    break;
-                },
-                Err(ReadlineError::Eof) => {
-                    println!("Ctrl-D received, exiting.");
-                    break;
-                },
-                Err(err) => {
-                    self.ui.print_error(&format!("Error reading input: {}", err));
-                    continue;
+            core::recovery::end_turn(self.session.working_dir.clone());
+            if queued_input.is_none() {
+                self.announce_finished_jobs();
+                self.ui.print_status_line(&self.status_line());
+            }
+            let input = match queued_input.take() {
+                Some(input) => input,
+                None => match self.ui.read_user_input(&self.prompt_context()) {
+                    Ok(line) => line,
+                    Err(ReadlineError::Interrupted) => {
+                        println!("{}", self.ui.t(Message::CtrlCExit));
+                        break;
+                    },
+                    Err(ReadlineError::Eof) => {
+                        println!("{}", self.ui.t(Message::CtrlDExit));
+                        break;
+                    },
+                    Err(err) => {
+                        self.ui.print_error(&self.ui.tf(Message::ReadInputError, &[&err]));
+                        continue;
+                    },
                 },
             };
-            let input = input.trim();
+            let input = resolve_alias(input.trim());
+            let input = input.as_str();
+            if let Some(command) = input.split_whitespace().next().filter(|word| word.starts_with('/')) {
+                // Only feeds the startup tips, so failures are not worth reporting
+                let _ = core::tips::record_use(command);
+            }
 
             if input.eq_ignore_ascii_case("exit") {
                 break;
@@ -114,151 +229,2369 @@ impl ShellmindCLI {
                 continue;
             }
 
-            let indicator = self.ui.start_thinking_indicator();
-            self.ui.print_status("Generating command...");
-            
-            let result = match self.config.api_type {
-                core::ApiType::Rest => generate_command_rest(&self.config, input, &history).await,
-                core::ApiType::Grpc => generate_command_grpc(&self.config, input, &history).await,
+            if input == "/speak" || input.starts_with("/speak ") {
+                self.toggle_speech(input["/speak".len()..].trim());
+                continue;
+            }
+
+            if input == "/sessions" || input.starts_with("/sessions ") {
+                self.show_sessions(input["/sessions".len()..].trim());
+                continue;
+            }
+
+            if input == "/tools" {
+                self.show_tools();
+                continue;
+            }
+
+            if input == "/cheat" || input.starts_with("/cheat ") {
+                self.show_cheats(input["/cheat".len()..].trim());
+                continue;
+            }
+
+            if input == "/config effective" {
+                self.show_effective_config();
+                continue;
+            }
+
+            if input == "/env" {
+                self.show_environment();
+                continue;
+            }
+
+            if input == "/bg" || input.starts_with("/bg ") {
+                self.start_job(input["/bg".len()..].trim());
+                continue;
+            }
+
+            if input == "/jobs" || input.starts_with("/jobs ") {
+                self.show_jobs(input["/jobs".len()..].trim());
+                continue;
+            }
+
+            if let Some(argument) = input.strip_prefix("/stop ") {
+                self.stop_job(argument.trim());
+                continue;
+            }
+
+            if input == "/panes" {
+                self.show_panes();
+                continue;
+            }
+
+            if input == "/fleet" || input.starts_with("/fleet ") {
+                self.run_fleet(input["/fleet".len()..].trim()).await?;
+                continue;
+            }
+
+            if input == "/pins" || input.starts_with("/pin ") || input.starts_with("/unpin ") {
+                self.manage_pins(input);
+                continue;
+            }
+
+            if input == "/good" || input == "/bad" || input.starts_with("/bad ") {
+                self.record_feedback(input);
+                continue;
+            }
+
+            if input == "/more" {
+                if !self.ui.print_more() {
+                    self.ui.print_status(self.ui.t(Message::NothingMore));
+                }
+                continue;
+            }
+
+            if input == "/translate" {
+                self.translate_last_output().await;
+                continue;
+            }
+
+            if input == "/ask" || input.starts_with("/ask ") {
+                self.ask(input["/ask".len()..].trim()).await?;
+                continue;
+            }
+
+            // `/retry` re-asks the last prompt; `original` stays the prompt as first typed
+            let retry_prompt;
+            let mut temperature = None;
+            let (input, original) = match input.strip_prefix("/retry") {
+                Some(argument) => {
+                    let Some((original, rejected)) = self.last_exchange.clone() else {
+                        self.ui.print_status(self.ui.t(Message::NothingToRetry));
+                        continue;
+                    };
+                    let (bumped, instruction) = parse_retry_argument(argument.trim(), self.config.temperature);
+                    temperature = bumped;
+                    retry_prompt = core::build_retry_prompt(&original, &rejected, instruction);
+                    (retry_prompt.as_str(), original)
+                }
+                None => (input, input.to_string()),
+            };
+
+            let input = match input.strip_prefix("/paste-image") {
+                Some(question) => {
+                    self.paste_image();
+                    // Without a question the image waits for the next prompt
+                    if question.trim().is_empty() {
+                        continue;
+                    }
+                    question.trim()
+                }
+                None => input,
+            };
+
+            let mut images = std::mem::take(&mut self.pending_images);
+            images.extend(self.referenced_images(input));
+            core::recovery::start_turn(input);
+            let configured_temperature = self.config.temperature;
+            if let Some(temperature) = temperature {
+                self.ui.print_status(&self.ui.tf(Message::RetryingWithTemperature, &[&temperature]));
+                self.config.temperature = temperature;
+            }
+            // Simple requests are answered from the offline snippets without an API call;
+            // retries always go to the model
+            let offline = if self.config.local_first && images.is_empty() && temperature.is_none() {
+                core::offline_help::answer(input)
+            } else {
+                None
+            };
+            let result = match offline {
+                Some(answer) => {
+                    self.ui.print_status(&self.ui.t(Message::AnsweredOffline));
+                    Ok(answer)
+                }
+                None => self.generate_with_images(&self.config.wrap_prompt(input), &images).await,
             };
-            self.ui.stop_thinking_indicator(indicator);
-            self.ui.print_status("Command generation complete.");
+            self.config.temperature = configured_temperature;
 
             match result {
                 Ok(command) => {
-                    self.ui.print_command(&command);
-
-                    // Check if the command contains a newline, indicating it’s an informational message
-                    if command.contains('\n') {
-                        println!("\n{}", command); // Print the informational message
-                        history.push(GeminiContent {
-                            role: "user".to_string(),
-                            parts: vec![core::GeminiPart {
-                                text: input.to_string(),
-                            }],
-                        });
-                        
-                        history.push(GeminiContent {
-                            role: "model".to_string(),
-                            parts: vec![core::GeminiPart {
-                                text: command
-                            }],
-                        });
-                        continue; // Skip command execution and prompt for next input
-                    }
+                    self.last_exchange = Some((original, command.clone()));
+                    core::recovery::awaiting_approval(&command)?;
+                    self.handle_reply(input, command).await?;
+                },
+                // Keep the request in history so a follow-up like "try again" has context
+                Err(ShellmindError::Cancelled) => self.record_turn(input, "[generation cancelled by the user]").await?,
+                Err(e) => {
+                    self.ui.print_error(&self.ui.tf(Message::GenerationError, &[&e]));
+                }
+            }
+        }
 
-                    // Attempt to parse as a tool call
-                    let tool_call_regex = regex::Regex::new(r"^([a-zA-Z_]+)\((.*)\)$").unwrap();
-                    if let Some(captures) = tool_call_regex.captures(&command) {
-                        let tool_name = captures.get(1).unwrap().as_str();
-                        let params_str = captures.get(2).unwrap().as_str();
-
-                        if let Some(tool) = self.tool_registry.get_tool(tool_name) {
-                            let params: serde_json::Value = serde_json::from_str(params_str).unwrap_or_else(|_| serde_json::json!({}));
-                            
-                            if let Some(confirmation_details) = tool.should_confirm_execute(&params) {
-                                let confirmed = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
-                                    .with_prompt(&confirmation_details.message)
-                                    .interact()?;
-
-                                if confirmed {
-                                    self.ui.print_status(&format!("Executing tool: {}", tool.display_name()));
-                                    let tool_result = tool.execute(params, None).await?;
-                                    match tool_result {
-                                        core::ToolResult::Success(output) => self.ui.print_status(&format!("Tool output: {}", output)),
-                                        core::ToolResult::Error(err) => self.ui.print_error(&format!("Tool error: {}", err)),
-                                    }
-                                } else {
-                                    self.ui.print_status("Tool execution cancelled.");
-                                }
-                            } else { // No confirmation needed, execute directly
-                                self.ui.print_status(&format!("Executing tool: {}", tool.display_name()));
-                                let tool_result = tool.execute(params, None).await?;
-                                match tool_result {
-                                    core::ToolResult::Success(output) => self.ui.print_status(&format!("Tool output: {}", output)),
-                                    core::ToolResult::Error(err) => self.ui.print_error(&format!("Tool error: {}", err)),
-                                }
-                            }
-                        } else {
-                            self.ui.print_error(&format!("Unknown tool: {}", tool_name));
-                        }
-                    } else { // Not a tool call, treat as a regular shell command
-                        let options = &["Evet (Bir Kez Çalıştır)", "Her Zaman İzin Ver", "Hayır"];
-                        let selection = Select::with_theme(&ColorfulTheme::default())
-                            .with_prompt("Bu komutu çalıştırmak ister misiniz?")
-                            .default(0)
-                            .items(&options[..])
-                            .interact_opt()?;
-
-                        match selection {
-                            Some(0) => { // Evet (Bir Kez Çalıştır)
-                                run_command(&command)?;
-                            },
-                            Some(1) => { // Her Zaman İzin Ver
-                                core::ConfigManager::add_allowed_command(&mut self.config, &command);
-                                core::ConfigManager::save_configuration(&self.config)?;
-                                run_command(&command)?;
-                            },
-                            _ => { // Hayır veya iptal
-                                println!("Komut çalıştırılmadı.");
-                            }
-                        }
-                    }
+        println!("{}", self.ui.t(Message::ShuttingDown));
+        clean_up(Language::from_code(self.config.resolved_language())).await;
+        core::recovery::finish();
+
+        Ok(())
+    }
+
+    /// Continues the session of a crash-recovery checkpoint: restores its transcript and
+    /// working directory, lists undo snapshots from the interrupted turn and re-offers a
+    /// reply that was awaiting approval. Returns an interrupted prompt to send again.
+    async fn resume_checkpoint(&mut self, checkpoint: core::recovery::Checkpoint) -> Result<Option<String>, ShellmindError> {
+        self.session = Session::resume(&checkpoint.session_id, &self.config)?;
+        self.session.memory.load_hierarchical_context().await?;
+        if std::env::set_current_dir(&checkpoint.working_dir).is_ok() {
+            self.session.working_dir = checkpoint.working_dir.clone();
+        }
+        self.ui.print_status(&self.ui.tf(Message::RecoveringSession, &[&checkpoint.session_id]));
+        if let Some(started) = checkpoint.turn_started_at {
+            for snapshot in core::undo::list()?.iter().filter(|s| s.created_at >= started) {
+                self.ui.print_status(&self.ui.tf(Message::RecoveredUndo, &[&snapshot.description, &snapshot.id]));
+            }
+        }
+
+        core::recovery::begin(&self.session)?;
+        match (checkpoint.pending_reply, checkpoint.pending_input) {
+            (Some(reply), input) => {
+                let input = input.unwrap_or_default();
+                println!("{}", self.ui.t(Message::RecoveredPendingReply));
+                core::recovery::start_turn(&input);
+                core::recovery::awaiting_approval(&reply)?;
+                self.last_exchange = Some((input.clone(), reply.clone()));
+                self.handle_reply(&input, reply).await?;
+                Ok(None)
+            }
+            (None, Some(input)) => {
+                self.ui.print_status(&self.ui.tf(Message::RecoveredPendingInput, &[&input]));
+                Ok(Some(input))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// `/ask`: answers `question` as rendered Markdown without ever offering to run
+    /// anything, so explanations are not mistaken for commands.
+    async fn ask(&mut self, question: &str) -> Result<(), ShellmindError> {
+        if question.is_empty() {
+            self.ui.print_status(self.ui.t(Message::AskUsage));
+            return Ok(());
+        }
+        core::recovery::start_turn(question);
+        match self.generate(&core::build_ask_prompt(question)).await {
+            Ok(answer) => {
+                self.record_trial(Signal::Served);
+                self.ui.print_markdown(&answer);
+                self.ui.speak(&answer);
+                self.record_turn(question, &answer).await?;
+            }
+            Err(ShellmindError::Cancelled) => self.record_turn(question, "[generation cancelled by the user]").await?,
+            Err(e) => self.ui.print_error(&self.ui.tf(Message::GenerationError, &[&e])),
+        }
+        Ok(())
+    }
+
+    /// Shows a reply to `input` and handles it: plans and scripts run step by step, tool
+    /// calls and commands go through approval, anything else is printed.
+    async fn handle_reply(&mut self, input: &str, command: String) -> Result<(), ShellmindError> {
+        self.record_trial(Signal::Served);
+        self.ui.print_command(&core::secrets::mask(&command));
 
+        // Several commands become a numbered plan run step by step
+        if let Some(steps) = core::plan::split_plan(&command) {
+            self.run_plan(input, &steps).await?;
+            self.command_history_manager.add_command(input)?;
+            self.record_turn(input, &command).await?;
+            return Ok(());
+        }
+
+        // Multi-line scripts run from a scratch file rather than `sh -c`
+        if let Some(script) = core::scratch::extract(&command) {
+            self.run_script(&script)?;
+            self.command_history_manager.add_command(input)?;
+            self.record_turn(input, &command).await?;
+            return Ok(());
+        }
+
+        // Check if the command contains a newline, indicating it’s an informational message
+        if command.contains('\n') {
+            println!("\n{}", command); // Print the informational message
+            self.ui.speak(&command);
+            self.record_turn(input, &command).await?;
+            return Ok(()); // Skip command execution and prompt for next input
+        }
+
+        // Catch syntax errors before offering to run the command
+        let command = if core::parse_tool_call(&command).is_some() {
+            command
+        } else {
+            match self.ensure_valid_syntax(&command).await {
+                Some(command) => command,
+                None => {
+                    self.record_turn(input, &command).await?;
+                    return Ok(());
+                }
+            }
+        };
+
+        // Attempt to parse as a tool call
+        if let Some((tool_name, params)) = core::parse_tool_call(&command) {
+            if let Some(result) = self.run_tool_call(&tool_name, params).await? {
+                self.record_trial(if result.is_error { Signal::Failed } else { Signal::Succeeded });
+            }
+        } else if self.config.approval_mode == ApprovalMode::Suggest {
+            println!("{}", self.ui.t(Message::SuggestModeCommand));
+        } else if SecurityManager::blocked_pattern(&self.config, &command).is_some() {
+            // Never run; the correction loop tells the model why and offers its alternative
+            self.run_and_verify(input, &command).await?;
+        } else { // Not a tool call, treat as a regular shell command
+            // `always` mode never remembers approvals, and neither does a simulated run
+            let options = if self.config.approval_mode == ApprovalMode::Always || self.config.simulate_execution {
+                vec![Message::RunOnce, Message::PreviewOption, Message::No]
+            } else {
+                vec![Message::RunOnce, Message::AlwaysAllow, Message::PreviewOption, Message::No]
+            };
+            let cwd = std::env::current_dir().unwrap_or_default();
+            for effect in SecurityManager::command_effects(&command, &cwd) {
+                let (severity, callout) = match &effect {
+                    core::CommandEffect::Overwrites(path) => ("medium", self.ui.tf(Message::WillOverwrite, &[path])),
+                    core::CommandEffect::AppendsTo(path) => ("medium", self.ui.tf(Message::WillAppend, &[path])),
+                    core::CommandEffect::ExecutesOutputOf(source) => ("high", self.ui.tf(Message::ExecutesOutputOf, &[source])),
+                };
+                self.ui.print_finding(severity, &callout);
+            }
+            // Risky commands default to not running
+            let risk = SecurityManager::assess_command_risk(&command);
+            let severity = match risk.level {
+                core::SafetyLevel::Dangerous => "high",
+                core::SafetyLevel::Warning => "medium",
+                core::SafetyLevel::Safe => "low",
+            };
+            for reason in &risk.reasons {
+                self.ui.print_finding(severity, reason);
+            }
+            // A command doing something the request didn't ask for needs an explicit yes first
+            let mismatches = self.intent_mismatches(input, &command);
+            if !mismatches.is_empty() {
+                for mismatch in &mismatches {
+                    self.ui.print_alert(&self.ui.tf(Message::IntentMismatch, &[&mismatch.explanation()]));
+                }
+                let confirmed = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(self.ui.t(Message::IntentMismatchConfirm))
+                    .default(false)
+                    .interact()?;
+                if !confirmed {
+                    println!("{}", self.ui.t(Message::CommandNotRun));
+                    self.record_trial(Signal::Declined);
                     self.command_history_manager.add_command(input)?;
+                    self.record_turn(input, &command).await?;
+                    return Ok(());
+                }
+            }
+            let default = if risk.level == core::SafetyLevel::Dangerous || !mismatches.is_empty() { options.len() - 1 } else { 0 };
+            let labels: Vec<&str> = options.iter().map(|m| self.ui.t(*m)).collect();
+            let prompt = match self.explain_risk(&command, &risk).await {
+                Some(explanation) => self.ui.tf(Message::RunRiskyCommandPrompt, &[&explanation]),
+                None => self.ui.t(Message::RunCommandPrompt).to_string(),
+            };
+            // A preview returns to the menu so the command can still be run or declined
+            let selection = loop {
+                let selection = Select::with_theme(&ColorfulTheme::default())
+                    .with_prompt(&prompt)
+                    .default(default)
+                    .items(&labels)
+                    .interact_opt()?
+                    .map(|i| options[i]);
+                if selection != Some(Message::PreviewOption) {
+                    break selection;
+                }
+                self.preview_command(&command);
+            };
 
-                    history.push(GeminiContent {
-                        role: "user".to_string(),
-                        parts: vec![core::GeminiPart {
-                            text: input.to_string(),
-                        }],
-                    });
-                    
-                    history.push(GeminiContent {
-                        role: "model".to_string(),
-                        parts: vec![core::GeminiPart {
-                            text: command
-                        }],
-                    });
+            match selection {
+                Some(Message::RunOnce) => {
+                    self.audit(AuditKind::ApprovalGranted, &command, serde_json::json!({ "scope": "once" }));
+                    self.run_and_verify(input, &command).await?;
                 },
-                Err(e) => {
-                    self.ui.print_error(&format!("Error generating command: {}", e));
+                Some(Message::AlwaysAllow) => {
+                    core::ConfigManager::add_allowed_command(&mut self.config, &command);
+                    core::ConfigManager::save_configuration(&self.config)?;
+                    self.audit(AuditKind::ApprovalGranted, &command, serde_json::json!({ "scope": "always" }));
+                    self.run_and_verify(input, &command).await?;
+                },
+                _ => { // Hayır veya iptal
+                    println!("{}", self.ui.t(Message::CommandNotRun));
+                    self.record_trial(Signal::Declined);
                 }
             }
         }
 
-        println!("Shutting down Shellmind.");
+        self.command_history_manager.add_command(input)?;
 
+        self.record_turn(input, &command).await?;
         Ok(())
     }
-}
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let mut cli = ShellmindCLI::new().await?;
-    cli.start().await
-}
+    /// One sentence on what a Warning or Dangerous command will do, for its confirmation
+    /// prompt. The summary model phrases it from the static analysis when `explain_risk` is
+    /// on; the analysis alone is used otherwise and when the model is slow or fails.
+    async fn explain_risk(&self, command: &str, risk: &core::RiskAssessment) -> Option<String> {
+        let local = core::risk::local_explanation(command, &self.session.working_dir, risk)?;
+        if !self.config.explain_risk {
+            return Some(local);
+        }
+        let mut explain_config = self.config.clone();
+        explain_config.model_name = self.config.summary_model_name.clone();
+        let prompt = core::risk::build_explanation_prompt(command, risk, Some(&local), self.config.resolved_language());
+        let reply = tokio::time::timeout(RISK_EXPLANATION_TIMEOUT, self.transport.generate(&explain_config, &prompt, &[])).await;
+        match reply {
+            Ok(Ok(reply)) => core::risk::clean_explanation(&reply).or(Some(local)),
+            _ => Some(local),
+        }
+    }
 
-fn run_command(command_str: &str) -> Result<(), ShellmindError> {
-    println!("Çalıştırılıyor: {}", command_str);
-    let output = if cfg!(target_os = "windows") {
-        Command::new("cmd")
-            .args(&["/C", command_str])
-            .output()
-            .map_err(|e| ShellmindError::Other(format!("Komut çalıştırılamadı: {}", e)))?
-    } else {
-        Command::new("sh")
-            .arg("-c")
-            .arg(command_str)
-            .output()
-            .map_err(|e| ShellmindError::Other(format!("Komut çalıştırılamadı: {}", e)))?
-    };
+    /// Runs a command with self-correction, verifies it when it succeeds and records the
+    /// outcome for the prompt variant.
+    async fn run_and_verify(&self, input: &str, command: &str) -> Result<(), ShellmindError> {
+        match self.run_with_self_correction(command).await? {
+            Some(executed) => {
+                self.record_trial(Signal::Succeeded);
+                self.verify_execution(input, &executed).await
+            }
+            None => {
+                self.record_trial(Signal::Failed);
+                Ok(())
+            }
+        }
+    }
 
-    io::stdout().write_all(&output.stdout).map_err(|e| ShellmindError::Other(e.to_string()))?;
-    io::stderr().write_all(&output.stderr).map_err(|e| ShellmindError::Other(e.to_string()))?;
+    /// Validates, confirms and runs a tool call proposed by the model. Returns `None` when
+    /// the call was rejected, cancelled or only suggested.
+    async fn run_tool_call(&self, tool_name: &str, params: serde_json::Value) -> Result<Option<core::ToolResult>, ShellmindError> {
+        let Some((tool, params)) = self.approve_tool_call(tool_name, params).await? else {
+            return Ok(None);
+        };
+        self.ui.print_status(&self.ui.tf(Message::ExecutingTool, &[&tool.display_name()]));
+        let result = self.execute_tool(tool, params).await;
+        Ok(Some(self.report_tool_result(tool.name(), result)))
+    }
+
+    /// Resolves, validates and confirms a tool call. Returns the tool and the parameters to
+    /// run it with, or `None` when the call was rejected, cancelled or only suggested.
+    async fn approve_tool_call(&self, tool_name: &str, mut params: serde_json::Value) -> Result<Option<(&dyn core::BaseTool, serde_json::Value)>, ShellmindError> {
+        let corrected = match self.tool_registry.get_tool(tool_name) {
+            Some(_) => None,
+            None if self.config.auto_correct_tool_names => self.tool_registry.corrected_tool_name(tool_name),
+            None => None,
+        };
+        if let Some(corrected) = corrected {
+            self.ui.print_status(&self.ui.tf(Message::ToolNameCorrected, &[&tool_name, &corrected]));
+        }
+        let Some(tool) = self.tool_registry.get_tool(corrected.unwrap_or(tool_name)) else {
+            let suggestions = self.tool_registry.similar_tool_names(tool_name);
+            if suggestions.is_empty() {
+                self.ui.print_error(&self.ui.tf(Message::UnknownTool, &[&tool_name]));
+            } else {
+                let names = suggestions.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", ");
+                self.ui.print_error(&self.ui.tf(Message::UnknownToolDidYouMean, &[&tool_name, &names]));
+            }
+            return Ok(None);
+        };
+        if !tool.validate_tool_params(&params) {
+            self.ui.print_error(&tool.get_description(&params));
+            return Ok(None);
+        }
+        if let Some(shell_command) = params.get("command").and_then(|c| c.as_str()) {
+            if let Err(e) = SecurityManager::check_command(&self.config, shell_command) {
+                self.ui.print_error(&e.to_string());
+                return Ok(None);
+            }
+        }
+
+        if self.config.approval_mode == ApprovalMode::Suggest {
+            self.ui.print_status(&self.ui.tf(Message::SuggestModeTool, &[&tool.get_description(&params)]));
+            return Ok(None);
+        }
+        // In `always` mode even tools that don't ask for confirmation are confirmed, and so
+        // are dangerous calls in any mode
+        let dangerous = tool.safety_level(&params) == core::SafetyLevel::Dangerous;
+        let confirmation = tool.should_confirm_execute(&params).or_else(|| {
+            (self.config.approval_mode == ApprovalMode::Always || dangerous).then(|| ConfirmationDetails {
+                message: format!("Run {}?", tool.get_description(&params)),
+            })
+        });
+        let confirmed = match &confirmation {
+            Some(confirmation_details) => {
+                let preview = core::approval::build_preview(tool, &params);
+                self.ui.print_approval_panel(&preview.title, &preview.description, &preview.params, &preview.details);
+                let mut confirm = dialoguer::Confirm::with_theme(&ColorfulTheme::default()).with_prompt(&confirmation_details.message);
+                if dangerous {
+                    self.ui.print_alert(&self.ui.tf(Message::DangerousToolCall, &[&tool.display_name()]));
+                    confirm = confirm.default(false);
+                }
+                match &self.approval_channel {
+                    Some(channel) if dangerous => {
+                        let details = format!("{}\n{}\n{}", preview.description, preview.params, preview.details.join("\n"));
+                        self.confirm_with_remote(channel, &confirmation_details.message, &details).await?
+                    }
+                    _ => confirm.interact()?,
+                }
+            }
+            None => true,
+        };
+        if !confirmed {
+            self.ui.print_status(self.ui.t(Message::ToolCancelled));
+            return Ok(None);
+        }
+        // Multi-file edits are also confirmed file by file
+        if tool_name == "multi_edit" && params.get("paths").map_or(true, |p| p.is_null()) {
+            match self.select_edited_files(&params)? {
+                Some(paths) => params["paths"] = serde_json::json!(paths),
+                None => {
+                    self.ui.print_status(self.ui.t(Message::ToolCancelled));
+                    return Ok(None);
+                }
+            }
+        }
+        if confirmation.is_some() {
+            self.audit(AuditKind::ApprovalGranted, &tool.get_description(&params), serde_json::json!({ "tool": tool_name, "dangerous": dangerous }));
+        }
+        Ok(Some((tool, params)))
+    }
+
+    /// Asks for a dangerous approval here and, once it has waited unattended for
+    /// `approval_webhook_after_secs`, also on the remote channel; the first answer wins.
+    /// Falls back to the plain prompt when stdin is not a terminal.
+    async fn confirm_with_remote(&self, channel: &core::remote_approval::Channel, prompt: &str, details: &str) -> Result<bool, ShellmindError> {
+        let after = Duration::from_secs(self.config.approval_webhook_after_secs);
+        println!("{}", self.ui.tf(Message::RemoteApprovalPrompt, &[&prompt, &after.as_secs()]));
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut local = {
+            let stop = Arc::clone(&stop);
+            tokio::task::spawn_blocking(move || ui::wait_for_yes_no(false, &stop))
+        };
+        let answered = |answer: Result<Option<bool>, tokio::task::JoinError>| answer.ok().flatten();
+
+        let remote = tokio::select! {
+            answer = &mut local => match answered(answer) {
+                Some(answer) => return Ok(answer),
+                None => {
+                    return Ok(dialoguer::Confirm::with_theme(&ColorfulTheme::default()).with_prompt(prompt).default(false).interact()?);
+                }
+            },
+            _ = tokio::time::sleep(after) => {
+                match channel.send(prompt, &core::secrets::mask(details)).await {
+                    Ok(request) => {
+                        self.ui.print_status(&self.ui.tf(Message::RemoteApprovalSent, &[&request.token, &request.token]));
+                        Some(request)
+                    }
+                    Err(e) => {
+                        self.ui.print_error(&self.ui.tf(Message::RemoteApprovalFailed, &[&e]));
+                        None
+                    }
+                }
+            }
+        };
+        let approved = match remote {
+            Some(request) => tokio::select! {
+                answer = &mut local => return Ok(answered(answer).unwrap_or(false)),
+                reply = channel.wait_for_reply(&request) => match reply {
+                    Ok(None) => {
+                        self.ui.print_status(&self.ui.tf(Message::RemoteApprovalTimedOut, &[&(core::remote_approval::REPLY_TIMEOUT.as_secs() / 60)]));
+                        self.audit(AuditKind::RemoteApproval, prompt, serde_json::json!({ "approved": false, "token": request.token, "timed_out": true }));
+                        false
+                    }
+                    Ok(Some(approved)) => {
+                        let message = if approved { Message::ApprovedRemotely } else { Message::DeniedRemotely };
+                        self.ui.print_status(self.ui.t(message));
+                        self.audit(AuditKind::RemoteApproval, prompt, serde_json::json!({ "approved": approved, "token": request.token }));
+                        approved
+                    }
+                    Err(e) => {
+                        self.ui.print_error(&self.ui.tf(Message::RemoteApprovalFailed, &[&e]));
+                        return Ok(answered(local.await).unwrap_or(false));
+                    }
+                },
+            },
+            None => return Ok(answered(local.await).unwrap_or(false)),
+        };
+        // Release the terminal from the key listener
+        stop.store(true, Ordering::Relaxed);
+        let _ = local.await;
+        Ok(approved)
+    }
+
+    /// Prints a tool's result and audits the files it changed. An error becomes an error
+    /// result, so it can be fed back to the model.
+    fn report_tool_result(&self, tool_name: &str, result: Result<core::ToolResult, ShellmindError>) -> core::ToolResult {
+        match result {
+            Ok(result) => {
+                self.print_tool_result(&result);
+                for path in &result.artifacts {
+                    self.audit(AuditKind::FileModified, &path.to_string_lossy(), serde_json::json!({ "tool": tool_name }));
+                }
+                result
+            }
+            Err(e) => {
+                self.ui.print_error(&self.ui.tf(Message::ToolError, &[&e]));
+                core::ToolResult::error(e.to_string())
+            }
+        }
+    }
+
+    fn audit(&self, kind: AuditKind, subject: &str, detail: serde_json::Value) {
+        core::audit::record(&self.config, AuditEvent::new(kind, subject, detail));
+    }
+
+    /// Runs an approved command, audits it and, with `track_file_changes`, reports the
+    /// files it created, modified or deleted in the working directory.
+    fn execute(&self, command: &str) -> Result<CommandOutcome, ShellmindError> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let before = self.config.track_file_changes.then(|| core::changes::WorkspaceSnapshot::take(&cwd));
+        let outcome = run_command(&self.ui, command)?;
+        core::grep_index::invalidate();
+        self.audit(AuditKind::CommandExecuted, command, serde_json::json!({ "exit_code": outcome.exit_code }));
+        self.remember_output(&outcome);
+        let Some(before) = before else {
+            return Ok(outcome);
+        };
+        let changes = before.diff(&core::changes::WorkspaceSnapshot::take(&cwd));
+        let masked = core::secrets::mask(command);
+        for (message, change, paths) in [
+            (Message::ChangeCreated, "created", &changes.created),
+            (Message::ChangeModified, "modified", &changes.modified),
+            (Message::ChangeDeleted, "deleted", &changes.deleted),
+        ] {
+            for path in paths {
+                println!("{}", self.ui.tf(message, &[&path.display()]));
+                self.audit(AuditKind::FileModified, &cwd.join(path).display().to_string(), serde_json::json!({ "change": change, "command": masked }));
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Keeps the output of a command for `/translate` and offers the translation when
+    /// Turkish is configured and the output, preferably its errors, reads as English.
+    fn remember_output(&self, outcome: &CommandOutcome) {
+        let output = format!("{}{}", outcome.stdout, outcome.stderr);
+        let sample = if outcome.stderr.trim().is_empty() { &outcome.stdout } else { &outcome.stderr };
+        if self.config.resolved_language() == "tr" && core::translate::looks_english(sample) {
+            self.ui.print_status(self.ui.t(Message::OfferTranslation));
+        }
+        *self.last_output.borrow_mut() = output;
+    }
+
+    /// `/translate`: translates the last command output into the configured language.
+    async fn translate_last_output(&self) {
+        let output = self.last_output.borrow().clone();
+        if output.trim().is_empty() {
+            self.ui.print_status(self.ui.t(Message::NothingToTranslate));
+            return;
+        }
+        let (output, _) = core::translate::truncate(&output);
+        let prompt = core::translate::build_translation_prompt(output, self.config.resolved_language());
+        match self.generate(&prompt).await {
+            Ok(translation) => println!("{}", translation.trim_end()),
+            Err(ShellmindError::Cancelled) => {}
+            Err(e) => self.ui.print_error(&self.ui.tf(Message::GenerationError, &[&e])),
+        }
+    }
+
+    /// Runs `command` in the preview sandbox and prints what it would change.
+    fn preview_command(&self, command: &str) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let report = match core::preview::run(command, &cwd) {
+            Ok(report) => report,
+            Err(e) => {
+                self.ui.print_error(&e.to_string());
+                return;
+            }
+        };
+        self.audit(AuditKind::CommandPreviewed, command, serde_json::json!({ "exit_code": report.exit_code }));
+        println!("{}", self.ui.t(Message::PreviewHeader));
+        for path in &report.changes.created {
+            println!("{}", self.ui.tf(Message::PreviewCreated, &[&path.display()]));
+        }
+        for path in &report.changes.modified {
+            println!("{}", self.ui.tf(Message::PreviewModified, &[&path.display()]));
+        }
+        for path in &report.changes.deleted {
+            println!("{}", self.ui.tf(Message::PreviewDeleted, &[&path.display()]));
+        }
+        if report.changes.is_empty() {
+            println!("{}", self.ui.t(Message::PreviewNoChanges));
+        }
+        for connection in &report.network {
+            println!("{}", self.ui.tf(Message::PreviewNetwork, &[connection]));
+        }
+        if !report.network_traced {
+            println!("{}", self.ui.t(Message::PreviewNetworkUntraced));
+        }
+        let exit_code = report.exit_code.map_or("-".to_string(), |code| code.to_string());
+        println!("{}", self.ui.tf(Message::PreviewExitCode, &[&exit_code]));
+        if !report.stderr.trim().is_empty() {
+            eprintln!("{}", core::secrets::mask(report.stderr.trim_end()));
+        }
+    }
+
+    /// Asks which of the files matched by a `multi_edit` call to change. `None` when the
+    /// user deselects them all.
+    fn select_edited_files(&self, params: &serde_json::Value) -> Result<Option<Vec<String>>, ShellmindError> {
+        let params = core::parse_tool_params::<core::tools::MultiEditParams>("multi_edit", params)?;
+        let files: Vec<String> = core::tools::plan_multi_edit(&params)?
+            .iter()
+            .map(|edit| edit.path.display().to_string())
+            .collect();
+        if files.len() <= 1 {
+            return Ok(Some(files));
+        }
+        let selected = dialoguer::MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(self.ui.t(Message::SelectFilesToEdit))
+            .items(&files)
+            .defaults(&vec![true; files.len()])
+            .interact()?;
+        if selected.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(selected.into_iter().map(|i| files[i].clone()).collect()))
+    }
+
+    /// Lets the model work through a task with tool calls, feeding the results back, until it
+    /// replies without a tool call, a call is refused, a limit from the `agent_*` settings is
+    /// reached, the user stops it when the watchdog halts it, or the user presses Ctrl-C. The
+    /// run's trace is written to the audit log.
+    async fn run_agent(&mut self, task: &str) -> Result<(), ShellmindError> {
+        let limits = AgentLimits::from_config(&self.config);
+        let mut run = AgentRun::start(limits.clone(), self.config.agent.clone());
+        let result = self.run_agent_steps(&mut run, task).await;
+        let outcome = match &result {
+            Ok(outcome) => *outcome,
+            Err(ShellmindError::Cancelled) => Outcome::Cancelled,
+            Err(_) => Outcome::Failed,
+        };
+        let trace = run.finish(outcome);
+        self.audit(AuditKind::AgentRun, task, serde_json::to_value(&trace).unwrap_or_default());
+        match outcome {
+            Outcome::StepLimit => self.ui.print_status(&self.ui.tf(Message::AgentStepLimit, &[&limits.max_steps])),
+            Outcome::TimeLimit => {
+                let seconds = limits.max_duration.unwrap_or_default().as_secs();
+                self.ui.print_status(&self.ui.tf(Message::AgentTimeLimit, &[&seconds]));
+            }
+            Outcome::TokenBudget => self.ui.print_status(&self.ui.tf(Message::AgentTokenBudget, &[&trace.tokens])),
+            _ => {}
+        }
+        result.map(|_| ())
+    }
+
+    async fn run_agent_steps(&mut self, run: &mut AgentRun, task: &str) -> Result<Outcome, ShellmindError> {
+        let mut prompt = self.config.wrap_prompt(task);
+        loop {
+            if let Some(outcome) = run.exhausted() {
+                return Ok(outcome);
+            }
+            let reply = self.generate(&prompt).await?;
+            self.record_turn(&prompt, &reply).await?;
+            let calls = core::agent::parse_tool_calls(&reply);
+            if calls.is_empty() {
+                println!("\n{}", reply);
+                self.ui.speak(&reply);
+                return Ok(Outcome::Completed);
+            }
+            self.ui.print_command(&core::secrets::mask(&reply));
+            run.begin_step();
+            let (results, stop) = self.run_agent_step(run, &calls).await?;
+            if let Some(outcome) = stop {
+                return Ok(outcome);
+            }
+            calls.iter().for_each(|call| run.watchdog.observe_call(call));
+            results.iter().for_each(|(_, result)| run.watchdog.observe_result(result));
+            prompt = core::agent::build_results_prompt(&results);
+            if let Some(reason) = run.watchdog.end_step() {
+                match self.ask_after_watchdog(&reason)? {
+                    Some(guidance) => {
+                        run.watchdog.reset();
+                        if !guidance.is_empty() {
+                            prompt.push_str(&format!("\n\nThe user paused the run ({}) and says: {}", reason, guidance));
+                        }
+                    }
+                    None => return Ok(Outcome::Watchdog),
+                }
+            }
+        }
+    }
 
-    if !output.status.success() {
-        return Err(ShellmindError::Other(format!("Komut hata koduyla çıktı: {:?}", output.status.code())));
+    /// Asks how to go on after the watchdog halted a run: `Some` with the user's
+    /// instructions (empty to just continue) or `None` to stop.
+    fn ask_after_watchdog(&self, reason: &str) -> Result<Option<String>, ShellmindError> {
+        self.ui.print_alert(&self.ui.tf(Message::AgentWatchdog, &[&reason]));
+        let options = [Message::WatchdogContinue, Message::WatchdogGuide, Message::WatchdogStop];
+        let labels: Vec<&str> = options.iter().map(|m| self.ui.t(*m)).collect();
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(self.ui.t(Message::AgentWatchdogPrompt))
+            .default(options.len() - 1)
+            .items(&labels)
+            .interact_opt()?
+            .map(|i| options[i]);
+        match selection {
+            Some(Message::WatchdogContinue) => Ok(Some(String::new())),
+            Some(Message::WatchdogGuide) => {
+                let guidance: String = dialoguer::Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt(self.ui.t(Message::WatchdogGuidancePrompt))
+                    .interact_text()?;
+                Ok(Some(guidance.trim().to_string()))
+            }
+            _ => Ok(None),
+        }
     }
-    Ok(())
-}
\ No newline at end of file
+
+    /// Runs the tool calls of one agent step stage by stage. Returns the results so far and,
+    /// when the run ends here, why.
+    async fn run_agent_step(&self, run: &mut AgentRun, calls: &[ToolCall]) -> Result<(Vec<(String, core::ToolResult)>, Option<Outcome>), ShellmindError> {
+        let mut results = Vec::new();
+        for (stage, indices) in core::agent::stages(calls, |call| self.runs_concurrently(call)).into_iter().enumerate() {
+            let timeout = run.stage_timeout();
+            if timeout.is_zero() {
+                return Ok((results, Some(Outcome::TimeLimit)));
+            }
+            let concurrent = indices.len() > 1;
+            // Everything in a stage is approved before any of it starts
+            let mut approved = Vec::new();
+            for &index in &indices {
+                let call = &calls[index];
+                match self.approve_tool_call(&call.name, call.params.clone()).await? {
+                    Some(approval) => approved.push(approval),
+                    None => {
+                        run.record(CallTrace { tool: call.name.clone(), stage, concurrent, status: CallStatus::Refused, duration_ms: 0 });
+                        return Ok((results, Some(Outcome::Stopped)));
+                    }
+                }
+            }
+
+            let ends = self.run_stage(&approved, timeout).await;
+            let mut cancelled = false;
+            for ((tool, _), (end, elapsed)) in approved.iter().zip(ends) {
+                let (status, result) = match end {
+                    CallEnd::Finished(result) => {
+                        let result = self.report_tool_result(tool.name(), result);
+                        let status = if result.is_error { CallStatus::Failed } else { CallStatus::Succeeded };
+                        (status, Some(result))
+                    }
+                    CallEnd::TimedOut => {
+                        let message = self.ui.tf(Message::ToolTimedOut, &[&tool.display_name(), &timeout.as_secs()]);
+                        self.ui.print_error(&message);
+                        (CallStatus::TimedOut, Some(core::ToolResult::error(message)))
+                    }
+                    CallEnd::Cancelled => {
+                        cancelled = true;
+                        (CallStatus::Cancelled, None)
+                    }
+                };
+                run.record(CallTrace { tool: tool.name().to_string(), stage, concurrent, status, duration_ms: elapsed.as_millis() as u64 });
+                if let Some(result) = result {
+                    results.push((tool.name().to_string(), result));
+                }
+            }
+            if cancelled {
+                self.ui.print_status(&self.ui.tf(Message::AgentCancelled, &[&results.len(), &calls.len()]));
+                return Ok((results, Some(Outcome::Cancelled)));
+            }
+        }
+        Ok((results, None))
+    }
+
+    /// Whether a call may run alongside its neighbours: read-only tools that don't ask for
+    /// confirmation, unless `approval_mode` confirms every call.
+    fn runs_concurrently(&self, call: &ToolCall) -> bool {
+        self.config.approval_mode == ApprovalMode::Default
+            && core::simulation::READ_ONLY_TOOLS.contains(&call.name.as_str())
+            && self.tool_registry.get_tool(&call.name).map_or(false, |tool| tool.should_confirm_execute(&call.params).is_none())
+    }
+
+    /// Runs the approved calls of a stage, concurrently when there are several, until they
+    /// finish, `timeout` passes or the user presses Ctrl-C. Returns how each call ended and
+    /// how long it ran, in the order given.
+    async fn run_stage(&self, calls: &[(&dyn core::BaseTool, serde_json::Value)], timeout: Duration) -> Vec<(CallEnd, Duration)> {
+        let started = Instant::now();
+        if let [(tool, params)] = calls {
+            self.ui.print_status(&self.ui.tf(Message::ExecutingTool, &[&tool.display_name()]));
+            let end = self.execute_tool_within(*tool, params.clone(), timeout).await;
+            return vec![(end, started.elapsed())];
+        }
+
+        // Parallel calls are kept as jobs from the start, so `/panes` can tail them side by side
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut jobs = Vec::with_capacity(calls.len());
+        for (position, (tool, params)) in calls.iter().enumerate() {
+            self.ui.print_status(&self.ui.tf(Message::ExecutingTool, &[&tool.display_name()]));
+            let job = self.session.jobs.track(&tool.display_name());
+            let (sender, mut receiver) = core::progress::channel();
+            let future = tool.execute(params.clone(), None, Some(sender));
+            let step = job.clone();
+            tasks.spawn(async move {
+                tokio::pin!(future);
+                let result = loop {
+                    tokio::select! {
+                        result = &mut future => break result,
+                        Some(update) = receiver.recv() => match update.total {
+                            Some(total) => step.push_line(&format!("[{}/{}] {}", update.current, total, update.message)),
+                            None => step.push_line(&format!("[{}] {}", update.current, update.message)),
+                        },
+                    }
+                };
+                (position, result, started.elapsed())
+            });
+            jobs.push(job);
+        }
+        let mut ends: Vec<Option<(CallEnd, Duration)>> = calls.iter().map(|_| None).collect();
+        let deadline = tokio::time::Instant::now() + timeout;
+        let stopped = loop {
+            tokio::select! {
+                joined = tasks.join_next() => match joined {
+                    Some(Ok((position, result, elapsed))) => ends[position] = Some((CallEnd::Finished(result), elapsed)),
+                    // A panicked tool is reported below as a failure
+                    Some(Err(_)) => {}
+                    None => break None,
+                },
+                _ = tokio::time::sleep_until(deadline) => break Some(CallStatus::TimedOut),
+                _ = tokio::signal::ctrl_c() => break Some(CallStatus::Cancelled),
+            }
+        };
+        tasks.abort_all();
+        let ends: Vec<(CallEnd, Duration)> = ends
+            .into_iter()
+            .map(|end| {
+                end.unwrap_or_else(|| {
+                    let end = match stopped {
+                        Some(CallStatus::TimedOut) => CallEnd::TimedOut,
+                        Some(_) => CallEnd::Cancelled,
+                        None => CallEnd::Finished(Err(ShellmindError::Other("The tool stopped unexpectedly".to_string()))),
+                    };
+                    (end, started.elapsed())
+                })
+            })
+            .collect();
+        for (((tool, _), (end, _)), job) in calls.iter().zip(&ends).zip(&jobs) {
+            let (text, succeeded) = match end {
+                CallEnd::Finished(Ok(result)) => (result.display.clone(), !result.is_error),
+                CallEnd::Finished(Err(e)) => (e.to_string(), false),
+                CallEnd::TimedOut => (self.ui.tf(Message::ToolTimedOut, &[&tool.display_name(), &timeout.as_secs()]), false),
+                CallEnd::Cancelled => (self.ui.t(Message::AgentCancelled).to_string(), false),
+            };
+            text.lines().for_each(|line| job.push_line(line));
+            job.finish(core::jobs::JobStatus::Done(succeeded));
+        }
+        ends
+    }
+
+    /// `shellmind script`: generates a complete script, writes it through `write_file` with
+    /// the usual preview, lints it and offers to run it.
+    async fn run_script_workflow(&mut self, options: cli::ScriptOptions) -> Result<(), ShellmindError> {
+        let script = core::scratch::Script { language: options.language.clone(), body: String::new() };
+        let Some((_, extension)) = script.interpreter().filter(|_| ["bash", "sh", "python"].contains(&options.language.as_str())) else {
+            return Err(ShellmindError::Other(format!("Unsupported script language '{}'; use bash or python", options.language)));
+        };
+
+        let prompt = core::scratch::build_script_prompt(&options.task, &options.language);
+        let body = core::scratch::strip_fences(&self.generate(&prompt).await?);
+        self.record_turn(&prompt, &body).await?;
+        let path = options.output.unwrap_or_else(|| core::scratch::default_file_name(&options.task, extension));
+        let params = serde_json::json!({ "path": path, "content": format!("{}\n", body) });
+        match self.run_tool_call("write_file", params).await? {
+            Some(result) if !result.is_error => {}
+            Some(result) => return Err(ShellmindError::Other(result.display)),
+            None if self.config.approval_mode == ApprovalMode::Suggest => return Ok(()),
+            None => return Err(ShellmindError::Rejected),
+        }
+        // Nothing was written to lint or run
+        if core::simulation::enabled() {
+            return Ok(());
+        }
+        let path = std::path::PathBuf::from(&path);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+                .map_err(|e| ShellmindError::Other(format!("Failed to make '{}' executable: {}", path.display(), e)))?;
+        }
+
+        if options.lint {
+            if let Some((program, args)) = core::scratch::lint_command(&options.language, &path) {
+                match Command::new(program).args(&args).output() {
+                    Ok(output) if output.status.success() => self.ui.print_status(&self.ui.tf(Message::LintPassed, &[&program])),
+                    Ok(output) => {
+                        self.ui.print_error(&self.ui.tf(Message::LintFailed, &[&program]));
+                        io::stdout().write_all(&output.stdout).map_err(|e| ShellmindError::Other(e.to_string()))?;
+                        io::stderr().write_all(&output.stderr).map_err(|e| ShellmindError::Other(e.to_string()))?;
+                    }
+                    Err(_) => self.ui.print_status(&self.ui.tf(Message::LinterMissing, &[&program])),
+                }
+            }
+        }
+
+        let script = core::scratch::Script { language: options.language, body };
+        let Some(command) = core::scratch::command_for(&script, &path) else {
+            return Ok(());
+        };
+        if self.config.approval_mode == ApprovalMode::Suggest {
+            return Ok(());
+        }
+        let run = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(self.ui.t(Message::RunScriptPrompt))
+            .default(false)
+            .interact()?;
+        // Declining to run is a normal end: the script was written
+        if run {
+            SecurityManager::check_command(&self.config, &command)?;
+            self.audit(AuditKind::ApprovalGranted, &command, serde_json::json!({ "scope": "script", "language": script.language }));
+            let outcome = self.execute(&command)?;
+            if !outcome.success() {
+                self.ui.print_error(&self.ui.tf(Message::CommandFailed, &[&format!("{:?}", outcome.exit_code)]));
+                return Err(ShellmindError::CommandFailed(outcome.exit_code));
+            }
+        }
+        Ok(())
+    }
+
+    /// `shellmind test`: runs the tests and hands the first failure to the model.
+    async fn run_test_workflow(&mut self, options: cli::TestOptions) -> Result<(), ShellmindError> {
+        let detected = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| core::project::ProjectInfo::detect(&cwd).test_command());
+        let Some(command) = options.command.or(detected) else {
+            return Err(ShellmindError::Other(self.ui.t(Message::NoTestCommand).to_string()));
+        };
+        SecurityManager::check_command(&self.config, &command)?;
+        if core::simulation::enabled() {
+            return Err(ShellmindError::Other(self.ui.t(Message::TestsNotSimulated).to_string()));
+        }
+
+        self.ui.print_status(&self.ui.tf(Message::RunningTests, &[&core::secrets::mask(&command)]));
+        let run = core::testing::run_tests(&command)?;
+        self.audit(AuditKind::CommandExecuted, &command, serde_json::json!({ "exit_code": run.exit_code }));
+        if run.passed() {
+            self.ui.print_status(self.ui.t(Message::TestsPassed));
+            return Ok(());
+        }
+
+        let failures = core::testing::parse_failures(&run.output);
+        let failure = match failures.first() {
+            Some(failure) => {
+                self.ui.print_error(&self.ui.tf(Message::TestsFailed, &[&failures.len(), &failure.name]));
+                failure.clone()
+            }
+            None => {
+                self.ui.print_error(self.ui.t(Message::NoFailuresParsed));
+                core::testing::TestFailure { name: command.clone(), details: String::new() }
+            }
+        };
+        let prompt = core::testing::build_test_prompt(&run, &failure, options.explain_only);
+        if options.explain_only {
+            // Only explaining, so the model gets nothing that could edit files or run commands
+            self.tool_registry.retain_read_only();
+        }
+        self.run_agent(&prompt).await?;
+        Err(ShellmindError::CommandFailed(run.exit_code))
+    }
+
+    /// Generates a reply for a one-shot invocation (`run`, `suggest`), refusing tool calls
+    /// and multi-step answers. A reply spanning several lines is an explanation.
+    async fn generate_single_reply(&mut self, prompt: &str) -> Result<String, ShellmindError> {
+        let indicator = self.ui.start_thinking_indicator();
+        let history = self.session.request_history();
+        let result = self.transport.generate_with_images(&self.config, &self.config.wrap_prompt(prompt), &[], &history).await;
+        self.ui.stop_thinking_indicator(indicator);
+        let reply = result?.trim().to_string();
+        self.record_turn(prompt, &reply).await?;
+
+        if let Some((tool_name, _)) = core::parse_tool_call(&reply) {
+            return Err(ShellmindError::Other(self.ui.tf(Message::RunNotACommand, &[&tool_name])));
+        }
+        if core::plan::split_plan(&reply).is_some() || core::scratch::extract(&reply).is_some() {
+            return Err(ShellmindError::Other(self.ui.t(Message::RunMultiStep).to_string()));
+        }
+        Ok(reply)
+    }
+
+    /// Prints what `command` would overwrite or execute and why it is risky, to stderr.
+    fn print_command_notes(&self, command: &str) -> core::RiskAssessment {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        for effect in SecurityManager::command_effects(command, &cwd) {
+            let callout = match &effect {
+                core::CommandEffect::Overwrites(path) => self.ui.tf(Message::WillOverwrite, &[path]),
+                core::CommandEffect::AppendsTo(path) => self.ui.tf(Message::WillAppend, &[path]),
+                core::CommandEffect::ExecutesOutputOf(source) => self.ui.tf(Message::ExecutesOutputOf, &[source]),
+            };
+            eprintln!("  {}", callout);
+        }
+        let risk = SecurityManager::assess_command_risk(command);
+        for reason in &risk.reasons {
+            eprintln!("  [{:?}] {}", risk.level, reason);
+        }
+        risk
+    }
+
+    /// What `command` does that `prompt` didn't ask for, unless `intent_check` is off.
+    fn intent_mismatches(&self, prompt: &str, command: &str) -> Vec<core::intent::Mismatch> {
+        if !self.config.intent_check {
+            return Vec::new();
+        }
+        core::intent::mismatches(prompt, command, &std::env::current_dir().unwrap_or_default())
+    }
+
+    /// Prints the intent mismatches of `command` to stderr. True when there were any.
+    fn print_intent_mismatches(&self, prompt: &str, command: &str) -> bool {
+        let mismatches = self.intent_mismatches(prompt, command);
+        for mismatch in &mismatches {
+            eprintln!("  {}", self.ui.tf(Message::IntentMismatch, &[&mismatch.explanation()]));
+        }
+        !mismatches.is_empty()
+    }
+
+    /// `shellmind suggest`: generates a command and prints it without running it. With
+    /// `--print` stdout gets the bare command and nothing else, so it can be captured.
+    async fn suggest_workflow(&mut self, options: cli::SuggestOptions) -> Result<(), ShellmindError> {
+        let reply = self.generate_single_reply(&options.prompt).await?;
+        if reply.contains('\n') {
+            if options.print_only {
+                eprintln!("{}", reply);
+                return Err(ShellmindError::Other(self.ui.t(Message::SuggestNoCommand).to_string()));
+            }
+            println!("{}", reply);
+            return Ok(());
+        }
+
+        // Checked as it would run, but printed as generated so the user's shell expands aliases
+        let expanded = self.shell_env.expand(&reply);
+        core::check_syntax(&expanded).map_err(|e| ShellmindError::Other(self.ui.tf(Message::SyntaxError, &[&e])))?;
+        SecurityManager::check_command(&self.config, &expanded)?;
+        if options.print_only {
+            self.print_command_notes(&expanded);
+            self.print_intent_mismatches(&options.prompt, &expanded);
+            println!("{}", reply);
+        } else {
+            self.ui.print_command(&reply);
+            self.print_command_notes(&expanded);
+            self.print_intent_mismatches(&options.prompt, &expanded);
+        }
+        Ok(())
+    }
+
+    /// `shellmind tf-review`: summarizes a Terraform plan, then answers questions about it
+    /// until the user leaves or asks to apply it.
+    async fn tf_review_workflow(&mut self, options: cli::TfReviewOptions) -> Result<(), ShellmindError> {
+        let plan = match &options.plan {
+            Some(path) => core::terraform::read_plan(std::path::Path::new(path))?,
+            None => {
+                self.ui.print_status(self.ui.t(Message::TfPlanning));
+                core::terraform::run_plan(std::path::Path::new(options.dir.as_deref().unwrap_or(".")))?
+            }
+        };
+        if plan.changes.is_empty() {
+            self.ui.print_status(self.ui.t(Message::TfNoChanges));
+            return Ok(());
+        }
+        let (add, change, destroy) = plan.counts();
+        self.ui.print_section(&self.ui.tf(Message::TfPlanCounts, &[&add, &change, &destroy]));
+        for (severity, risk) in plan.risks() {
+            self.ui.print_finding(severity, &risk);
+        }
+        for diagnostic in &plan.diagnostics {
+            self.ui.print_finding("medium", diagnostic);
+        }
+        let prompt = core::terraform::build_summary_prompt(&plan);
+        let summary = self.generate(&prompt).await?;
+        self.ui.print_markdown(&summary);
+        self.record_turn(&prompt, &summary).await?;
+
+        self.ui.print_status(self.ui.t(Message::TfReviewHelp));
+        loop {
+            let input = match self.ui.read_user_input(&self.prompt_context()) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+                Err(err) => {
+                    self.ui.print_error(&self.ui.tf(Message::ReadInputError, &[&err]));
+                    continue;
+                }
+            };
+            match input.trim() {
+                "" => continue,
+                "exit" | "/exit" | "çıkış" => break,
+                "apply" | "/apply" | "uygula" => return self.apply_terraform_plan(&plan),
+                question => match self.generate(&core::terraform::build_question_prompt(&plan, question)).await {
+                    Ok(answer) => {
+                        self.ui.print_markdown(&answer);
+                        self.record_turn(question, &answer).await?;
+                    }
+                    Err(ShellmindError::Cancelled) => {}
+                    Err(e) => self.ui.print_error(&self.ui.tf(Message::GenerationError, &[&e])),
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a reviewed plan. A plan that destroys resources lists them and needs their
+    /// number typed back; a plain yes is not enough.
+    fn apply_terraform_plan(&self, plan: &core::terraform::Plan) -> Result<(), ShellmindError> {
+        let Some(command) = core::terraform::apply_command(plan) else {
+            self.ui.print_error(self.ui.t(Message::TfNoPlanFile));
+            return Ok(());
+        };
+        if self.config.approval_mode == ApprovalMode::Suggest {
+            println!("{}", self.ui.t(Message::SuggestModeCommand));
+            return Ok(());
+        }
+        SecurityManager::check_command(&self.config, &command)?;
+        let destroyed = plan.destroyed();
+        let confirmed = if destroyed.is_empty() {
+            dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(self.ui.tf(Message::TfApplyConfirm, &[&command]))
+                .default(false)
+                .interact()?
+        } else {
+            self.ui.print_alert(&self.ui.tf(Message::TfDestroyWarning, &[&destroyed.len()]));
+            for change in &destroyed {
+                self.ui.print_finding("high", &format!("{} {}", change.action.symbol(), change.address));
+            }
+            let typed: String = dialoguer::Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(self.ui.tf(Message::TfDestroyConfirm, &[&destroyed.len()]))
+                .allow_empty(true)
+                .interact_text()?;
+            typed.trim() == destroyed.len().to_string()
+        };
+        if !confirmed {
+            println!("{}", self.ui.t(Message::CommandNotRun));
+            return Ok(());
+        }
+        self.audit(AuditKind::ApprovalGranted, &command, serde_json::json!({ "scope": "tf-review", "destroys": destroyed.len(), "dangerous": !destroyed.is_empty() }));
+        let outcome = self.execute(&command)?;
+        if !outcome.success() {
+            return Err(ShellmindError::CommandFailed(outcome.exit_code));
+        }
+        Ok(())
+    }
+
+    /// `shellmind run`: generates a single command and runs it if the approval policy and
+    /// risk checks allow. The command and any findings go to stderr and only the command's
+    /// own output reaches stdout, so the result can be piped.
+    async fn run_once_workflow(&mut self, options: cli::RunOptions) -> Result<(), ShellmindError> {
+        use std::io::IsTerminal;
+
+        let reply = self.generate_single_reply(&options.prompt).await?;
+        // An explanation rather than a command is the answer itself
+        if reply.contains('\n') {
+            println!("{}", reply);
+            return Ok(());
+        }
+
+        let command = self.shell_env.expand(&reply);
+        eprintln!("$ {}", core::secrets::mask(&command));
+        core::check_syntax(&command).map_err(|e| ShellmindError::Other(self.ui.tf(Message::SyntaxError, &[&e])))?;
+        SecurityManager::check_command(&self.config, &command)?;
+        if self.config.approval_mode == ApprovalMode::Suggest {
+            eprintln!("{}", self.ui.t(Message::SuggestModeCommand));
+            return Ok(());
+        }
+
+        let risk = self.print_command_notes(&command);
+        let mismatched = self.print_intent_mismatches(&options.prompt, &command);
+
+        // `--yes` and remembered approvals stand in for the prompt, except for dangerous
+        // commands, commands the prompt didn't ask for and in `always` mode
+        let pre_approved = self.config.approval_mode == ApprovalMode::Default
+            && risk.level != core::SafetyLevel::Dangerous
+            && !mismatched
+            && (options.yes || self.config.allowed_commands.contains(&command));
+        if !pre_approved {
+            if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+                let message = if mismatched { Message::IntentMismatchNeedsTerminal } else { Message::RunNeedsConfirmation };
+                self.ui.print_error(self.ui.t(message));
+                return Err(ShellmindError::Rejected);
+            }
+            let approved = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(self.ui.t(if mismatched { Message::IntentMismatchConfirm } else { Message::RunCommandPrompt }))
+                .default(risk.level != core::SafetyLevel::Dangerous && !mismatched)
+                .interact()?;
+            if !approved {
+                return Err(ShellmindError::Rejected);
+            }
+        }
+        self.audit(AuditKind::ApprovalGranted, &command, serde_json::json!({ "scope": "run", "pre_approved": pre_approved }));
+
+        if core::simulation::enabled() {
+            self.audit(AuditKind::CommandExecuted, &command, serde_json::json!({ "exit_code": 0 }));
+            println!("{}", core::simulation::command_output(&command));
+            return Ok(());
+        }
+        // Output streams straight through rather than being captured as in the session
+        let status = shell(&command)
+            .status()
+            .map_err(|e| ShellmindError::Other(format!("Komut çalıştırılamadı: {}", e)))?;
+        self.audit(AuditKind::CommandExecuted, &command, serde_json::json!({ "exit_code": status.code() }));
+        self.command_history_manager.add_command(&options.prompt)?;
+        if !status.success() {
+            return Err(ShellmindError::CommandFailed(status.code()));
+        }
+        Ok(())
+    }
+
+    /// Runs a tool, rendering its progress updates as a progress bar.
+    async fn execute_tool(&self, tool: &dyn core::BaseTool, params: serde_json::Value) -> Result<core::ToolResult, ShellmindError> {
+        let (sender, mut receiver) = core::progress::channel();
+        let bar = self.ui.start_progress_bar();
+        let future = tool.execute(params, None, Some(sender));
+        tokio::pin!(future);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut future => break result,
+                Some(update) = receiver.recv() => {
+                    self.ui.update_progress(&bar, update.current, update.total, &update.message);
+                }
+            }
+        };
+        bar.finish_and_clear();
+        result
+    }
+
+    /// Like `execute_tool`, but gives up after `timeout` or when the user presses Ctrl-C.
+    async fn execute_tool_within(&self, tool: &dyn core::BaseTool, params: serde_json::Value, timeout: Duration) -> CallEnd {
+        let (sender, mut receiver) = core::progress::channel();
+        let bar = self.ui.start_progress_bar();
+        let future = tool.execute(params, None, Some(sender));
+        tokio::pin!(future);
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+
+        let end = loop {
+            tokio::select! {
+                result = &mut future => break CallEnd::Finished(result),
+                Some(update) = receiver.recv() => {
+                    self.ui.update_progress(&bar, update.current, update.total, &update.message);
+                }
+                _ = &mut deadline => break CallEnd::TimedOut,
+                _ = tokio::signal::ctrl_c() => break CallEnd::Cancelled,
+            }
+        };
+        bar.finish_and_clear();
+        end
+    }
+
+    /// `/tools`: registered tools grouped by where they come from, and name conflicts.
+    fn show_tools(&self) {
+        for (source, tools) in self.tool_registry.tools_by_source() {
+            self.ui.print_section(&source.label());
+            let mut table = ui::table::Table::new([self.ui.t(Message::ToolsColumnName), self.ui.t(Message::ToolsColumnDescription)]);
+            for tool in tools {
+                let name = match self.tool_registry.qualified_alias(tool.name()) {
+                    Some(alias) => format!("{} ({})", tool.name(), alias),
+                    None => tool.name().to_string(),
+                };
+                table.add_row([name, tool.description().to_string()]);
+            }
+            self.ui.print_table(&table);
+        }
+        for conflict in self.tool_registry.conflicts() {
+            self.ui.print_status(&self.ui.tf(Message::ToolNameConflict, &[&conflict.name, &conflict.kept.label(), &conflict.shadowed]));
+        }
+    }
+
+    /// `/cheat <query>`: searches the offline command snippets.
+    fn show_cheats(&self, query: &str) {
+        if query.is_empty() {
+            self.ui.print_status(self.ui.t(Message::CheatUsage));
+            return;
+        }
+        let matches = core::offline_help::search(query, 8);
+        if matches.is_empty() {
+            self.ui.print_status(&self.ui.tf(Message::NoCheats, &[&query]));
+            return;
+        }
+        let mut table = ui::table::Table::new([self.ui.t(Message::CheatColumnCommand), self.ui.t(Message::ToolsColumnDescription)]);
+        for snippet in matches {
+            table.add_row([snippet.template.clone(), snippet.description.clone()]);
+        }
+        self.ui.print_table(&table);
+    }
+
+    /// `/bg <command>`: runs a command in the background. It is checked against the
+    /// blocked patterns like any other command; its output goes to `/jobs` and `/panes`.
+    fn start_job(&self, command: &str) {
+        if command.is_empty() {
+            self.ui.print_status(self.ui.t(Message::BgUsage));
+            return;
+        }
+        let command = self.shell_env.expand(command);
+        if let Some(pattern) = SecurityManager::blocked_pattern(&self.config, &command) {
+            let reason = format!("Command blocked by pattern '{}'", pattern);
+            self.ui.print_error(&self.ui.tf(Message::CommandBlocked, &[&reason]));
+            return;
+        }
+        if core::simulation::enabled() {
+            self.audit(AuditKind::CommandExecuted, &command, serde_json::json!({ "exit_code": 0, "background": true }));
+            println!("{}", core::simulation::command_output(&command));
+            return;
+        }
+        match self.session.jobs.spawn(&core::secrets::mask(&command), shell(&command)) {
+            Ok(job) => {
+                self.audit(AuditKind::CommandExecuted, &command, serde_json::json!({ "background": true, "job": job.id }));
+                self.ui.print_status(&self.ui.tf(Message::JobStarted, &[&job.id, &job.title]));
+            }
+            Err(e) => self.ui.print_error(&e.to_string()),
+        }
+    }
+
+    fn job_status(&self, job: &core::jobs::Job) -> String {
+        use core::jobs::JobStatus;
+        let elapsed = format_elapsed(job.elapsed());
+        match job.status() {
+            JobStatus::Running => self.ui.tf(Message::JobRunning, &[&elapsed]),
+            JobStatus::Exited(Some(code)) => self.ui.tf(Message::JobExited, &[&code, &elapsed]),
+            JobStatus::Exited(None) => self.ui.tf(Message::JobKilled, &[&elapsed]),
+            JobStatus::Stopped => self.ui.tf(Message::JobStoppedStatus, &[&elapsed]),
+            JobStatus::Done(true) => self.ui.tf(Message::JobDone, &[&elapsed]),
+            JobStatus::Done(false) => self.ui.tf(Message::JobFailed, &[&elapsed]),
+        }
+    }
+
+    /// `/jobs`: every job of the session; `/jobs <number>` prints one job's output.
+    fn show_jobs(&self, argument: &str) {
+        if let Ok(id) = argument.parse::<usize>() {
+            match self.session.jobs.get(id) {
+                Some(job) => {
+                    self.ui.print_section(&format!("{} · {}", job.title, self.job_status(&job)));
+                    self.ui.print_status_paged(&job.output().join("\n"));
+                }
+                None => self.ui.print_error(&self.ui.tf(Message::NoSuchJob, &[&id])),
+            }
+            return;
+        }
+        let jobs = self.session.jobs.list();
+        if jobs.is_empty() {
+            self.ui.print_status(self.ui.t(Message::NoJobs));
+            return;
+        }
+        let mut table = ui::table::Table::new([
+            "#",
+            self.ui.t(Message::JobsColumnJob),
+            self.ui.t(Message::JobsColumnStatus),
+            self.ui.t(Message::JobsColumnOutput),
+        ]);
+        for job in jobs {
+            let last: String = job.output().pop().unwrap_or_default().chars().take(60).collect();
+            table.add_row([job.id.to_string(), job.title.clone(), self.job_status(&job), last]);
+        }
+        self.ui.print_table(&table);
+    }
+
+    /// `/stop <number>`: stops a running background command.
+    fn stop_job(&self, argument: &str) {
+        match argument.parse::<usize>() {
+            Ok(id) if self.session.jobs.stop(id) => self.ui.print_status(&self.ui.tf(Message::JobStopped, &[&id])),
+            _ => self.ui.print_error(&self.ui.tf(Message::JobNotRunning, &[&argument])),
+        }
+    }
+
+    /// `/fleet <task>`: has the model plan the task as steps for a set of hosts, shows the
+    /// hosts and steps for one review, then runs the steps over SSH and reports every host.
+    async fn run_fleet(&mut self, task: &str) -> Result<(), ShellmindError> {
+        if task.is_empty() {
+            self.ui.print_status(self.ui.t(Message::FleetUsage));
+            return Ok(());
+        }
+        let reply = match self.generate(&core::fleet::build_fleet_prompt(task)).await {
+            Ok(reply) => reply,
+            Err(ShellmindError::Cancelled) => return Ok(()),
+            Err(e) => {
+                self.ui.print_error(&self.ui.tf(Message::GenerationError, &[&e]));
+                return Ok(());
+            }
+        };
+        let plan = match core::fleet::parse_plan(&self.config, &reply) {
+            Ok(plan) => plan,
+            Err(e) => {
+                self.ui.print_error(&e.to_string());
+                return self.record_turn(task, &reply).await;
+            }
+        };
+
+        self.ui.print_section(&self.ui.tf(Message::FleetPlanHeader, &[&plan.hosts.len(), &plan.steps.len()]));
+        if !plan.summary.is_empty() {
+            println!("{}", plan.summary);
+        }
+        println!("{}", self.ui.tf(Message::FleetHosts, &[&core::fleet::host_list(&plan.hosts)]));
+        let mut dangerous = false;
+        for (index, step) in plan.steps.iter().enumerate() {
+            self.ui.print_command(&format!("{}. {}", index + 1, core::secrets::mask(step)));
+            if let Err(e) = SecurityManager::check_command(&self.config, step) {
+                self.ui.print_error(&e.to_string());
+                return self.record_turn(task, &reply).await;
+            }
+            let risk = SecurityManager::assess_command_risk(step);
+            let severity = match risk.level {
+                core::SafetyLevel::Dangerous => "high",
+                core::SafetyLevel::Warning => "medium",
+                core::SafetyLevel::Safe => "low",
+            };
+            for reason in &risk.reasons {
+                self.ui.print_finding(severity, reason);
+            }
+            dangerous |= risk.level == core::SafetyLevel::Dangerous;
+        }
+        if self.config.approval_mode == ApprovalMode::Suggest {
+            println!("{}", self.ui.t(Message::SuggestModeCommand));
+            return self.record_turn(task, &reply).await;
+        }
+        if dangerous {
+            self.ui.print_alert(self.ui.t(Message::FleetDangerous));
+        }
+        // The one approval covers every host, so it never defaults to yes
+        let confirmed = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(self.ui.tf(Message::FleetConfirm, &[&plan.hosts.len(), &self.config.fleet_parallelism.max(1)]))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            println!("{}", self.ui.t(Message::CommandNotRun));
+            self.record_trial(Signal::Declined);
+            return self.record_turn(task, &reply).await;
+        }
+        let script = plan.steps.join(" && ");
+        self.audit(AuditKind::ApprovalGranted, &script, serde_json::json!({ "scope": "fleet", "hosts": &plan.hosts, "dangerous": dangerous }));
+
+        let mut results = Vec::new();
+        let mut run = core::fleet::run(&plan, self.config.fleet_parallelism);
+        let mut cancelled = false;
+        loop {
+            let result = tokio::select! {
+                result = run.results.recv() => match result {
+                    Some(result) => result,
+                    None => break,
+                },
+                // Running steps are killed and report back, so the loop keeps draining
+                _ = tokio::signal::ctrl_c(), if !cancelled => {
+                    run.cancel();
+                    cancelled = true;
+                    self.ui.print_status(self.ui.t(Message::FleetCancelled));
+                    continue;
+                }
+            };
+            self.audit(AuditKind::CommandExecuted, &script, serde_json::json!({ "host": &result.host, "exit_code": result.exit_code }));
+            let elapsed = format_elapsed(result.duration);
+            match result.failed_step {
+                None => self.ui.print_status(&self.ui.tf(Message::FleetHostDone, &[&result.host, &elapsed])),
+                Some(step) => self.ui.print_error(&self.ui.tf(Message::FleetHostFailed, &[&result.host, &(step + 1), &exit_code(result.exit_code), &elapsed])),
+            }
+            results.push(result);
+        }
+        results.sort_by_key(|r| plan.hosts.iter().position(|h| *h == r.host));
+
+        let mut table = ui::table::Table::new([
+            self.ui.t(Message::FleetColumnHost),
+            self.ui.t(Message::JobsColumnStatus),
+            self.ui.t(Message::JobsColumnOutput),
+        ]);
+        for result in &results {
+            let status = match result.failed_step {
+                None => self.ui.tf(Message::JobDone, &[&format_elapsed(result.duration)]),
+                Some(_) => self.ui.tf(Message::JobExited, &[&exit_code(result.exit_code), &format_elapsed(result.duration)]),
+            };
+            let last: String = result.output.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("").chars().take(60).collect();
+            table.add_row([result.host.clone(), status, last]);
+        }
+        self.ui.print_table(&table);
+        let report = core::fleet::report(&plan, &results);
+        self.ui.print_markdown(&report);
+        self.record_trial(if results.iter().all(|r| r.success()) { Signal::Succeeded } else { Signal::Failed });
+        self.record_turn(task, &format!("{}\n\n{}", reply, report)).await
+    }
+
+    /// Tells about `/bg` jobs that ended while the user was busy with something else.
+    fn announce_finished_jobs(&self) {
+        for job in self.session.jobs.take_finished() {
+            self.ui.print_status(&self.ui.tf(Message::JobFinished, &[&job.id, &self.job_status(&job), &job.title, &job.id]));
+        }
+    }
+
+    /// `/paste-image`: keeps the clipboard image for the next prompt.
+    #[cfg(feature = "image")]
+    fn paste_image(&mut self) {
+        match core::attachments::clipboard_image() {
+            Ok(image) => {
+                self.ui.print_status(&self.ui.tf(Message::ImageAttached, &[&(image.data.len() * 3 / 4 / 1024)]));
+                self.pending_images.push(image);
+            }
+            Err(e) => self.ui.print_error(&e.to_string()),
+        }
+    }
+
+    #[cfg(not(feature = "image"))]
+    fn paste_image(&mut self) {
+        self.ui.print_error(self.ui.t(Message::ImagesUnavailable));
+    }
+
+    /// The image files mentioned in `input` that could be loaded.
+    #[cfg(feature = "image")]
+    fn referenced_images(&self, input: &str) -> Vec<core::InlineData> {
+        let mut images = Vec::new();
+        for path in core::attachments::referenced_images(input) {
+            match core::attachments::load_image(&path) {
+                Ok(image) => {
+                    self.ui.print_status(&self.ui.tf(Message::AttachingImage, &[&path.display()]));
+                    images.push(image);
+                }
+                Err(e) => self.ui.print_error(&e.to_string()),
+            }
+        }
+        images
+    }
+
+    #[cfg(not(feature = "image"))]
+    fn referenced_images(&self, _input: &str) -> Vec<core::InlineData> {
+        Vec::new()
+    }
+
+    /// `/panes`: every job in its own pane, tailing output live until the view is closed.
+    #[cfg(feature = "tui")]
+    fn show_panes(&self) {
+        use std::io::IsTerminal;
+
+        if self.session.jobs.list().is_empty() {
+            self.ui.print_status(self.ui.t(Message::NoJobs));
+            return;
+        }
+        if !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+            self.ui.print_status(self.ui.t(Message::PanesNeedTerminal));
+            return;
+        }
+        let refresh = || {
+            self.session.jobs
+                .list()
+                .iter()
+                .map(|job| {
+                    let status = job.status();
+                    ui::panes::Pane {
+                        title: format!("{} {}", job.id, job.title),
+                        status: self.job_status(job),
+                        running: status.is_running(),
+                        failed: matches!(status, core::jobs::JobStatus::Exited(code) if code != Some(0))
+                            || status == core::jobs::JobStatus::Done(false),
+                        lines: job.output(),
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        if let Err(e) = ui::panes::show(refresh, self.ui.t(Message::PanesHelp)) {
+            self.ui.print_error(&e.to_string());
+        }
+    }
+
+    #[cfg(not(feature = "tui"))]
+    fn show_panes(&self) {
+        self.ui.print_status(self.ui.t(Message::PanesUnavailable));
+    }
+
+    /// `/sessions [query]`: saved sessions, newest first or best matching `query`.
+    fn show_sessions(&self, query: &str) {
+        let sessions = match core::session::list_sessions() {
+            Ok(sessions) if query.is_empty() => sessions,
+            Ok(sessions) => core::session::search_sessions(sessions, query),
+            Err(e) => {
+                self.ui.print_error(&e.to_string());
+                return;
+            }
+        };
+        if sessions.is_empty() {
+            self.ui.print_status(self.ui.t(Message::NoSavedSessions));
+            return;
+        }
+        let mut table = ui::table::Table::new([
+            self.ui.t(Message::SessionsColumnSession),
+            self.ui.t(Message::SessionsColumnTitle),
+            self.ui.t(Message::SessionsColumnDate),
+            self.ui.t(Message::SessionsColumnTurns),
+            self.ui.t(Message::SessionsColumnModels),
+        ]);
+        for session in sessions.into_iter().take(SESSION_LIST_LIMIT) {
+            let id = if session.id == self.session.id() { format!("{} *", session.id) } else { session.id };
+            table.add_row([
+                id,
+                session.title,
+                session.created_at.format("%Y-%m-%d %H:%M").to_string(),
+                session.turns.to_string(),
+                session.models.join(", "),
+            ]);
+        }
+        self.ui.print_table(&table);
+    }
+
+    /// `/config effective`: the settings requests are made with after the system policy
+    /// and production safe mode are applied, and where each comes from.
+    fn show_effective_config(&self) {
+        let config = &self.config;
+        let source = |key: &str| {
+            if config.policy.is_locked(key) {
+                "policy"
+            } else if config.production.is_some() && ["approval_mode", "disabled_tools"].contains(&key) {
+                "production"
+            } else {
+                "user"
+            }
+        };
+        let or_none = |value: &str| if value.is_empty() { "none".to_string() } else { value.to_string() };
+        let rows = [
+            ("model_name", config.model_name.clone()),
+            ("approval_mode", format!("{:?}", config.approval_mode).to_lowercase()),
+            ("sandbox_backend", or_none(&config.sandbox_backend)),
+            ("disabled_tools", or_none(&config.disabled_tools.join(", "))),
+            ("system_prompt", config.prompt_variant.clone().unwrap_or_else(|| "system_prompt".to_string())),
+            ("prompt_prefix", or_none(&config.prompt_prefix)),
+            ("prompt_suffix", or_none(&config.prompt_suffix)),
+            ("language", config.resolved_language().to_string()),
+            ("local_first", config.local_first.to_string()),
+        ];
+        let mut table = ui::table::Table::new([self.ui.t(Message::ConfigColumnKey), self.ui.t(Message::ConfigColumnValue), self.ui.t(Message::ConfigColumnSource)]);
+        for (key, value) in rows {
+            table.add_row([key.to_string(), value, source(key).to_string()]);
+        }
+        self.ui.print_table(&table);
+    }
+
+    /// `/env`: what was loaded from `.env` and what executed commands would see.
+    fn show_environment(&self) {
+        if !self.config.load_dotenv {
+            self.ui.print_status(self.ui.t(Message::DotenvDisabled));
+        } else if self.dotenv_vars.is_empty() {
+            self.ui.print_status(self.ui.t(Message::DotenvNothingLoaded));
+        } else {
+            self.ui.print_section(self.ui.t(Message::DotenvHeader));
+            for var in &self.dotenv_vars {
+                let state = if var.loaded { self.ui.t(Message::DotenvLoaded) } else { self.ui.t(Message::DotenvSkipped) };
+                println!("  {} ({})", var.name, state);
+            }
+        }
+        let visible = core::environment::visible_to_commands();
+        self.ui.print_section(&self.ui.tf(Message::VisibleEnvHeader, &[&visible.len()]));
+        for (name, value) in visible {
+            println!("  {}={}", name, value);
+        }
+    }
+
+    /// Handles `/pin <path|text>`, `/unpin <number|path|text>` and `/pins`.
+    fn manage_pins(&mut self, input: &str) {
+        let result = if let Some(target) = input.strip_prefix("/pin ") {
+            let pin = core::session::Pin::parse(target.trim());
+            let label = pin.label();
+            self.session.pin(pin).map(|added| {
+                let message = if added { Message::Pinned } else { Message::AlreadyPinned };
+                self.ui.print_status(&self.ui.tf(message, &[&label]));
+            })
+        } else if let Some(target) = input.strip_prefix("/unpin ") {
+            self.session.unpin(target.trim()).map(|removed| match removed {
+                Some(pin) => self.ui.print_status(&self.ui.tf(Message::Unpinned, &[&pin.label()])),
+                None => self.ui.print_error(&self.ui.tf(Message::PinNotFound, &[&target.trim()])),
+            })
+        } else {
+            if self.session.pins().is_empty() {
+                self.ui.print_status(self.ui.t(Message::NoPins));
+            } else {
+                self.ui.print_section(self.ui.t(Message::PinsHeader));
+                for (index, pin) in self.session.pins().iter().enumerate() {
+                    println!("  {}. {}", index + 1, pin.label());
+                }
+            }
+            Ok(())
+        };
+        if let Err(e) = result {
+            self.ui.print_error(&e.to_string());
+        }
+    }
+
+    /// Handles `/good` and `/bad <reason>` for the last generated answer.
+    fn record_feedback(&self, input: &str) {
+        let Some((prompt, command)) = &self.last_exchange else {
+            self.ui.print_status(self.ui.t(Message::NothingToRate));
+            return;
+        };
+        let good = input == "/good";
+        let reason = input.strip_prefix("/bad").map(str::trim).filter(|r| !r.is_empty()).map(str::to_string);
+        match core::feedback::record(core::feedback::FeedbackEntry::new(prompt, command, good, reason)) {
+            Ok(()) => self.ui.print_status(self.ui.t(Message::FeedbackRecorded)),
+            Err(e) => self.ui.print_error(&e.to_string()),
+        }
+        self.record_trial(if good { Signal::Good } else { Signal::Bad });
+    }
+
+    /// Records a signal for the system prompt variant serving this session, if any.
+    fn record_trial(&self, signal: Signal) {
+        if let Some(variant) = &self.config.prompt_variant {
+            if let Err(e) = core::prompt_trials::record(variant, self.session.id(), signal) {
+                eprintln!("Warning: failed to record prompt trial: {}", e);
+            }
+        }
+    }
+
+    /// Handles `/speak on|off`.
+    fn toggle_speech(&mut self, argument: &str) {
+        match argument {
+            "on" => {
+                if self.ui.enable_speech(Some(&self.config.tts_command)) {
+                    self.ui.print_status(self.ui.t(Message::SpeechOn));
+                } else {
+                    self.ui.print_error(self.ui.t(Message::SpeechUnavailable));
+                }
+            }
+            "off" => {
+                self.ui.disable_speech();
+                self.ui.print_status(self.ui.t(Message::SpeechOff));
+            }
+            _ => self.ui.print_status(self.ui.t(Message::SpeakUsage)),
+        }
+    }
+
+    /// The working directory with the home directory shown as `~`.
+    fn display_working_dir(&self) -> String {
+        match std::env::var("HOME").ok().and_then(|home| self.session.working_dir.strip_prefix(home).ok().map(|p| p.to_path_buf())) {
+            Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+            Some(relative) => format!("~/{}", relative.display()),
+            None => self.session.working_dir.display().to_string(),
+        }
+    }
+
+    /// Values for the `prompt_format` tokens; the git branch is only looked up when used.
+    fn prompt_context(&self) -> ui::prompt::PromptContext {
+        ui::prompt::PromptContext {
+            cwd: self.display_working_dir(),
+            branch: if self.config.prompt_format.contains("{branch}") {
+                core::snapshot::git_branch(&self.session.working_dir)
+            } else {
+                None
+            },
+            model: self.config.model_name.clone(),
+            approval_mode: format!("{:?}", self.config.approval_mode).to_lowercase(),
+        }
+    }
+
+    /// Parts of the status line, as chosen with `status_line`.
+    fn status_line(&self) -> Vec<String> {
+        self.config
+            .status_line
+            .iter()
+            .filter_map(|part| match part.as_str() {
+                "model" => Some(self.config.model_name.clone()),
+                "profile" => Some(if self.config.production.is_some() {
+                    "production"
+                } else if self.config.policy.is_active() {
+                    "policy"
+                } else {
+                    "user"
+                }.to_string()),
+                "approval" => Some(format!("{:?}", self.config.approval_mode).to_lowercase()),
+                "tokens" => Some(self.ui.tf(Message::StatusLineTokens, &[&format_tokens(core::budget::session_usage().tokens())])),
+                "cwd" => Some(self.display_working_dir()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn print_tool_result(&self, result: &core::ToolResult) {
+        if result.is_error {
+            self.ui.print_error(&self.ui.tf(Message::ToolError, &[&result.display]));
+        } else if let Some(table) = tabular(&result.llm_content) {
+            let rendered = format!("\n{}", self.ui.render_table(&table));
+            self.ui.print_status_paged(&self.ui.tf(Message::ToolOutput, &[&rendered]));
+        } else {
+            self.ui.print_status_paged(&self.ui.tf(Message::ToolOutput, &[&result.display]));
+        }
+        for artifact in &result.artifacts {
+            if ui::graphics::is_image(artifact) {
+                self.ui.print_image(artifact);
+            } else {
+                self.ui.print_status(&self.ui.tf(Message::Modified, &[&artifact.display()]));
+            }
+        }
+        if result.truncated {
+            self.ui.print_status(self.ui.t(Message::OutputTruncated));
+        }
+        if let Some(duration) = result.duration {
+            self.ui.print_status(&self.ui.tf(Message::CompletedIn, &[&format!("{:.2}", duration.as_secs_f64())]));
+        }
+    }
+
+    /// Appends a user/model exchange to the session, folding older turns into a summary
+    /// when the history nears `context_token_budget`.
+    async fn record_turn(&mut self, input: &str, response: &str) -> Result<(), ShellmindError> {
+        let first_turn = self.session.file.transcript.is_empty();
+        self.session.note_model(&self.config.model_name);
+        self.session.record_turn(input, response)?;
+        if first_turn {
+            let title = self.session_title(input).await;
+            self.session.set_title(&title)?;
+        }
+
+        let history = &self.session.history;
+        if core::context::needs_compression(history, self.config.context_token_budget) {
+            let older = history[core::context::compressible_range(history)].to_vec();
+            let summary = self.summarize(&older).await;
+            core::context::apply_summary(&mut self.session.history, &summary);
+        }
+        Ok(())
+    }
+
+    /// Titles a new session from its first request with the summary model, or uses the
+    /// request itself when `generate_session_titles` is off or the model fails.
+    async fn session_title(&self, first_prompt: &str) -> String {
+        if !self.config.generate_session_titles {
+            return core::session::fallback_title(first_prompt);
+        }
+        let mut title_config = self.config.clone();
+        title_config.model_name = self.config.summary_model_name.clone();
+        let prompt = core::session::build_title_prompt(first_prompt);
+        match self.transport.generate(&title_config, &prompt, &[]).await {
+            Ok(title) if !core::session::clean_title(&title).is_empty() => title,
+            _ => core::session::fallback_title(first_prompt),
+        }
+    }
+
+    /// Offers to carry over the summarized context of the newest session started in the
+    /// working directory, so project knowledge from earlier runs isn't lost.
+    async fn offer_directory_context(&mut self) -> Result<(), ShellmindError> {
+        let Some(previous) = core::session::latest_in_dir(&self.session.working_dir, self.session.id()) else {
+            return Ok(());
+        };
+        let when = previous.created_at.format("%Y-%m-%d %H:%M").to_string();
+        let load = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(self.ui.tf(Message::DirectoryContextOffer, &[&previous.title, &when]))
+            .default(true)
+            .interact()?;
+        if !load {
+            return Ok(());
+        }
+        let file = core::session::SessionFile::load(&previous.id)?;
+        let summary = self.summarize(&file.transcript).await;
+        self.session.history.extend(core::session::carried_context(&previous.title, &summary));
+        self.ui.print_status(&self.ui.tf(Message::DirectoryContextLoaded, &[&previous.title]));
+        Ok(())
+    }
+
+    /// Summarizes turns with the cheap summary model, falling back to a local summary.
+    async fn summarize(&self, turns: &[GeminiContent]) -> String {
+        let mut summary_config = self.config.clone();
+        summary_config.model_name = self.config.summary_model_name.clone();
+        let prompt = core::context::build_summary_prompt(turns);
+
+        self.transport
+            .generate(&summary_config, &prompt, &[])
+            .await
+            .unwrap_or_else(|_| core::context::local_summary(turns))
+    }
+
+    /// Generates a reply; Esc or Ctrl-C drops the in-flight request and returns
+    /// `ShellmindError::Cancelled` instead of exiting.
+    async fn generate(&self, prompt: &str) -> Result<String, ShellmindError> {
+        self.generate_with_images(prompt, &[]).await
+    }
+
+    async fn generate_with_images(&self, prompt: &str, images: &[core::InlineData]) -> Result<String, ShellmindError> {
+        let indicator = self.ui.start_thinking_indicator();
+        self.ui.print_status(self.ui.t(Message::GeneratingCommand));
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut listener = {
+            let stop = Arc::clone(&stop);
+            tokio::task::spawn_blocking(move || ui::wait_for_cancel_key(&stop))
+        };
+        let history = self.session.request_history();
+        // Streamed text replaces the spinner and is erased once the reply is complete
+        let mut preview = ui::StreamPreview::new();
+        let mut on_text = |text: &str| {
+            indicator.finish_and_clear();
+            preview.push(text);
+        };
+        let mut generation = if self.config.stream_responses {
+            self.transport.generate_streaming(&self.config, prompt, images, &history, &mut on_text)
+        } else {
+            self.transport.generate_with_images(&self.config, prompt, images, &history)
+        };
+
+        // False once the key listener has given up without a cancel (e.g. stdin is not a terminal)
+        let mut listening = true;
+        let result = loop {
+            tokio::select! {
+                result = &mut generation => break result,
+                cancelled = &mut listener, if listening => match cancelled {
+                    Ok(true) => break Err(ShellmindError::Cancelled),
+                    _ => listening = false,
+                },
+                _ = tokio::signal::ctrl_c() => break Err(ShellmindError::Cancelled),
+            }
+        };
+        drop(generation);
+        // Restore the terminal before printing anything else
+        stop.store(true, Ordering::Relaxed);
+        if !listener.is_finished() {
+            let _ = listener.await;
+        }
+
+        preview.clear();
+        self.ui.stop_thinking_indicator(indicator);
+        match &result {
+            Err(ShellmindError::Cancelled) => self.ui.print_status(self.ui.t(Message::GenerationCancelled)),
+            _ => self.ui.print_status(self.ui.t(Message::GenerationComplete)),
+        }
+        result
+    }
+
+    /// Runs `command` and, when it exits non-zero or is blocked, offers to send the failure
+    /// back to the model for a corrected command, up to `max_fix_attempts` times. Returns
+    /// the command that finally succeeded, if any.
+    async fn run_with_self_correction(&self, command: &str) -> Result<Option<String>, ShellmindError> {
+        let mut command = command.to_string();
+        let mut attempts = 0;
+
+        loop {
+            // Check and run what `sh` will actually execute
+            command = self.shell_env.expand(&command);
+            let fix_prompt = if let Some(pattern) = SecurityManager::blocked_pattern(&self.config, &command) {
+                let reason = format!("Command blocked by pattern '{}'", pattern);
+                self.ui.print_error(&self.ui.tf(Message::CommandBlocked, &[&reason]));
+                core::build_blocked_prompt(&command, &reason)
+            } else {
+                if let Err(e) = SandboxManager::ensure_backend(&self.config.sandbox_backend) {
+                    self.ui.print_error(&e.to_string());
+                    return Ok(None);
+                }
+                let outcome = self.execute(&command)?;
+                if outcome.success() {
+                    return Ok(Some(command));
+                }
+                self.ui.print_error(&self.ui.tf(Message::CommandFailed, &[&format!("{:?}", outcome.exit_code)]));
+                core::build_fix_prompt(&command, outcome.exit_code, &outcome.stderr)
+            };
+
+            if attempts >= self.config.max_fix_attempts {
+                return Ok(None);
+            }
+
+            let fix = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(self.ui.t(Message::AskForFix))
+                .default(true)
+                .interact()?;
+            if !fix {
+                return Ok(None);
+            }
+            attempts += 1;
+
+            let corrected = match self.generate(&fix_prompt).await {
+                Ok(corrected) => corrected.trim().to_string(),
+                Err(e) => {
+                    self.ui.print_error(&self.ui.tf(Message::GenerationError, &[&e]));
+                    return Ok(None);
+                }
+            };
+            self.ui.print_command(&core::secrets::mask(&corrected));
+            let Some(corrected) = self.ensure_valid_syntax(&corrected).await else {
+                return Ok(None);
+            };
+
+            let run_corrected = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(self.ui.t(Message::RunCorrectedPrompt))
+                .default(true)
+                .interact()?;
+            if !run_corrected {
+                println!("{}", self.ui.t(Message::CommandNotRun));
+                return Ok(None);
+            }
+            self.audit(AuditKind::ApprovalGranted, &corrected, serde_json::json!({ "scope": "correction" }));
+            command = corrected;
+        }
+    }
+
+    /// Writes `script` to the session's scratch directory, shows it for review and runs it
+    /// from there once approved. The file is kept for later inspection.
+    fn run_script(&self, script: &core::scratch::Script) -> Result<(), ShellmindError> {
+        if let Some(Err(e)) = script.body.lines().map(|line| SecurityManager::check_command(&self.config, line)).find(Result::is_err) {
+            self.ui.print_error(&e.to_string());
+            return Ok(());
+        }
+        let path = core::scratch::write(self.session.id(), script)?;
+        self.ui.print_script(&path.display().to_string(), &core::secrets::mask(&script.body));
+        if self.config.approval_mode == ApprovalMode::Suggest {
+            println!("{}", self.ui.t(Message::SuggestModeCommand));
+            return Ok(());
+        }
+        let run = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(self.ui.t(Message::RunScriptPrompt))
+            .default(false)
+            .interact()?;
+        let (true, Some(command)) = (run, core::scratch::command_for(script, &path)) else {
+            println!("{}", self.ui.t(Message::CommandNotRun));
+            return Ok(());
+        };
+        self.audit(AuditKind::ApprovalGranted, &command, serde_json::json!({ "scope": "script", "language": script.language }));
+        let outcome = self.execute(&command)?;
+        if !outcome.success() {
+            self.ui.print_error(&self.ui.tf(Message::CommandFailed, &[&format!("{:?}", outcome.exit_code)]));
+        }
+        Ok(())
+    }
+
+    /// Shows `steps` as a numbered plan and runs them in order, confirming each one and
+    /// stopping at the first step that is declined or fails. `cd` steps move the session
+    /// to the new directory so later steps run there.
+    async fn run_plan(&mut self, goal: &str, steps: &[String]) -> Result<(), ShellmindError> {
+        self.ui.print_section(&self.ui.tf(Message::PlanHeader, &[&steps.len()]));
+        for (index, step) in steps.iter().enumerate() {
+            println!("  {}. {}", index + 1, core::secrets::mask(step));
+        }
+        if self.config.approval_mode == ApprovalMode::Suggest {
+            println!("{}", self.ui.t(Message::SuggestModeCommand));
+            return Ok(());
+        }
+
+        for (index, step) in steps.iter().enumerate() {
+            let risk = SecurityManager::assess_command_risk(step);
+            let mut prompt = self.ui.tf(Message::RunStepPrompt, &[&(index + 1), &steps.len(), &core::secrets::mask(step)]);
+            if let Some(explanation) = self.explain_risk(step, &risk).await {
+                prompt = format!("{} ({})", prompt, explanation);
+            }
+            let run = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(prompt)
+                .default(risk.level != core::SafetyLevel::Dangerous)
+                .interact()?;
+            if !run {
+                self.ui.print_status(&self.ui.tf(Message::PlanStopped, &[&(index + 1)]));
+                return Ok(());
+            }
+            self.audit(AuditKind::ApprovalGranted, step, serde_json::json!({ "scope": "plan_step", "step": index + 1 }));
+
+            // Each step runs in its own shell, so a directory change has to be applied here
+            if let Some(dir) = core::plan::cd_target(step, &self.session.working_dir) {
+                match std::env::set_current_dir(&dir) {
+                    Ok(()) => self.session.working_dir = std::env::current_dir().unwrap_or(dir),
+                    Err(e) => {
+                        self.ui.print_error(&format!("cd {}: {}", dir.display(), e));
+                        self.ui.print_status(&self.ui.tf(Message::PlanStopped, &[&(index + 1)]));
+                        return Ok(());
+                    }
+                }
+                continue;
+            }
+
+            match self.run_with_self_correction(step).await? {
+                Some(executed) => self.verify_execution(goal, &executed).await?,
+                None => {
+                    self.ui.print_status(&self.ui.tf(Message::PlanStopped, &[&(index + 1)]));
+                    return Ok(());
+                }
+            }
+        }
+        self.ui.print_status(&self.ui.tf(Message::PlanComplete, &[&steps.len()]));
+        Ok(())
+    }
+
+    /// Checks `command` with `sh -n` and, while it has syntax errors, asks the model for a
+    /// corrected version, up to `max_fix_attempts` times. `None` when it stays invalid.
+    async fn ensure_valid_syntax(&self, command: &str) -> Option<String> {
+        let mut command = command.to_string();
+        for attempt in 0..=self.config.max_fix_attempts {
+            let Err(error) = core::check_syntax(&command) else {
+                return Some(command);
+            };
+            self.ui.print_error(&self.ui.tf(Message::SyntaxError, &[&error]));
+            if attempt == self.config.max_fix_attempts {
+                break;
+            }
+            self.ui.print_status(self.ui.t(Message::FixingSyntax));
+            match self.generate(&core::build_syntax_fix_prompt(&command, &error)).await {
+                Ok(corrected) => command = corrected.trim().to_string(),
+                Err(e) => {
+                    self.ui.print_error(&self.ui.tf(Message::GenerationError, &[&e]));
+                    return None;
+                }
+            }
+            self.ui.print_command(&core::secrets::mask(&command));
+        }
+        None
+    }
+
+    /// Asks the model for a read-only check of a state-changing command and runs it
+    /// automatically, reporting whether the user's goal appears achieved.
+    async fn verify_execution(&self, goal: &str, command: &str) -> Result<(), ShellmindError> {
+        if !self.config.verify_after_execution || SecurityManager::is_read_only_command(command) {
+            return Ok(());
+        }
+
+        let prompt = core::build_verification_prompt(goal, command);
+        let check = match self.generate(&prompt).await {
+            Ok(check) => check.trim().to_string(),
+            Err(e) => {
+                self.ui.print_error(&self.ui.tf(Message::VerificationGenerationError, &[&e]));
+                return Ok(());
+            }
+        };
+
+        if !SecurityManager::is_read_only_command(&check) || SecurityManager::check_command(&self.config, &check).is_err() {
+            self.ui.print_status(&self.ui.tf(Message::VerificationSkipped, &[&core::secrets::mask(&check)]));
+            return Ok(());
+        }
+
+        let check = self.shell_env.expand(&check);
+        self.ui.print_status(&self.ui.tf(Message::Verifying, &[&core::secrets::mask(&check)]));
+        let outcome = run_command(&self.ui, &check)?;
+        self.audit(AuditKind::CommandExecuted, &check, serde_json::json!({ "exit_code": outcome.exit_code, "verification": true }));
+        if outcome.success() {
+            self.ui.print_status(self.ui.t(Message::VerificationPassed));
+        } else {
+            self.ui.print_error(&self.ui.tf(Message::VerificationFailed, &[&format!("{:?}", outcome.exit_code)]));
+        }
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse_args(std::env::args().collect());
+    if let Err(e) = run(&cli).await {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(core::exit_code::for_error(&e));
+    }
+}
+
+async fn run(cli: &Cli) -> Result<()> {
+    // Before anything loads the configuration, which picks the flag up
+    if cli.simulate {
+        core::simulation::set_enabled(true);
+    }
+    // Diagnostics must run even when the configuration is broken
+    if !cli.needs_setup() {
+        cli.execute(&CLIInterface::new()?).await?;
+        return Ok(());
+    }
+    let mut shellmind = ShellmindCLI::new(cli).await?;
+    shellmind.start(cli).await
+}
+
+/// How long exit waits for audit events still being forwarded to the webhook.
+const AUDIT_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Stops processes Shellmind started that are still running, removes preview sandboxes
+/// and waits for pending audit deliveries, then reports anything left behind.
+async fn clean_up(language: Language) {
+    let report = tokio::task::spawn_blocking(|| core::processes::shutdown(core::processes::GRACE_PERIOD))
+        .await
+        .unwrap_or_default();
+    let undelivered = core::audit::drain(AUDIT_DRAIN_TIMEOUT).await;
+
+    let text = |message| ui::i18n::text(language, message);
+    for (message, processes) in [
+        (Message::StoppedLeftoverProcesses, &report.stopped),
+        (Message::StillRunningAfterShutdown, &report.still_running),
+    ] {
+        if processes.is_empty() {
+            continue;
+        }
+        eprintln!("{}", ui::i18n::format_message(text(message), &[&processes.len()]));
+        for process in processes {
+            eprintln!("  {:>7}  {}", process.pid, core::secrets::mask(&process.command));
+        }
+    }
+    if report.scratch_removed > 0 {
+        eprintln!("{}", ui::i18n::format_message(text(Message::RemovedPreviewScratch), &[&report.scratch_removed]));
+    }
+    if undelivered > 0 {
+        eprintln!("{}", ui::i18n::format_message(text(Message::AuditEventsUndelivered), &[&undelivered]));
+    }
+}
+
+/// Cleans up on SIGTERM and SIGHUP as on a normal exit, but keeps the recovery
+/// checkpoint so the session can be resumed.
+#[cfg(unix)]
+fn spawn_termination_handler(language: Language) {
+    use tokio::signal::unix::{signal, SignalKind};
+    let (Ok(mut terminate), Ok(mut hangup)) = (signal(SignalKind::terminate()), signal(SignalKind::hangup())) else {
+        return;
+    };
+    tokio::spawn(async move {
+        let code = tokio::select! {
+            _ = terminate.recv() => 143,
+            _ = hangup.recv() => 129,
+        };
+        clean_up(language).await;
+        if let Err(e) = core::recovery::flush() {
+            eprintln!("Warning: failed to save the session checkpoint: {}", e);
+        }
+        std::process::exit(code);
+    });
+}
+
+/// Highest temperature `/retry` will use, whether given directly or raised by `--hot`.
+const MAX_RETRY_TEMPERATURE: f32 = 2.0;
+
+/// Splits a `/retry` argument into an optional temperature and an instruction. A leading
+/// number sets the temperature; `--hot` raises the configured one by 0.3.
+fn parse_retry_argument(argument: &str, configured: f32) -> (Option<f32>, &str) {
+    let (first, rest) = argument.split_once(' ').unwrap_or((argument, ""));
+    if first == "--hot" {
+        (Some((configured + 0.3).clamp(0.0, MAX_RETRY_TEMPERATURE)), rest.trim())
+    } else if let Ok(temperature) = first.parse::<f32>() {
+        (Some(temperature.clamp(0.0, MAX_RETRY_TEMPERATURE)), rest.trim())
+    } else {
+        (None, argument)
+    }
+}
+
+/// An exit code for display; `-` when the process was killed by a signal or never ran.
+fn exit_code(code: Option<i32>) -> String {
+    code.map_or_else(|| "-".to_string(), |code| code.to_string())
+}
+
+/// E.g. "45s" or "3m 05s".
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+/// `1234` as `1.2k`, `1500000` as `1.5M`.
+fn format_tokens(tokens: u64) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// A table for tool results that are a list of flat objects, with one column per key in
+/// order of first appearance.
+fn tabular(value: &serde_json::Value) -> Option<ui::table::Table> {
+    let rows = value.as_array().filter(|rows| !rows.is_empty())?;
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        for (key, cell) in row.as_object()? {
+            if cell.is_object() || cell.is_array() {
+                return None;
+            }
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+    let mut table = ui::table::Table::new(columns.iter().copied());
+    for row in rows {
+        table.add_row(columns.iter().map(|column| match row.get(*column) {
+            Some(serde_json::Value::String(text)) => text.clone(),
+            Some(serde_json::Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        }));
+    }
+    Some(table)
+}
+
+/// Exit status and captured output of a command run through `run_command`.
+struct CommandOutcome {
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+impl CommandOutcome {
+    fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// How a tool call made by the agent ended.
+enum CallEnd {
+    Finished(Result<core::ToolResult, ShellmindError>),
+    TimedOut,
+    Cancelled,
+}
+
+/// `command_str` run through the platform shell.
+fn shell(command_str: &str) -> Command {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command.arg(command_str);
+    command
+}
+
+fn run_command(ui: &CLIInterface, command_str: &str) -> Result<CommandOutcome, ShellmindError> {
+    println!("{}", ui.tf(Message::Running, &[&core::secrets::mask(command_str)]));
+    if core::simulation::enabled() {
+        let stdout = core::simulation::command_output(command_str);
+        println!("{}", stdout);
+        return Ok(CommandOutcome { exit_code: Some(0), stdout, stderr: String::new() });
+    }
+    let output = shell(command_str)
+        .output()
+        .map_err(|e| ShellmindError::Other(format!("Komut çalıştırılamadı: {}", e)))?;
+
+    io::stdout().write_all(&output.stdout).map_err(|e| ShellmindError::Other(e.to_string()))?;
+    io::stderr().write_all(&output.stderr).map_err(|e| ShellmindError::Other(e.to_string()))?;
+
+    Ok(CommandOutcome {
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
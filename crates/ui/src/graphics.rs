@@ -0,0 +1,111 @@
+//! Inline images in the terminal through the kitty, iTerm2 or sixel graphics protocols,
+//! for images tools produce such as charts and screenshots. Terminals without one get
+//! the file path instead.
+
+use base64::Engine;
+use crossterm::tty::IsTty;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Larger images are not sent to the terminal; their path is printed instead.
+const MAX_INLINE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Base64 bytes per kitty graphics escape; the protocol's limit.
+const KITTY_CHUNK: usize = 4096;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Kitty,
+    Iterm2,
+    Sixel,
+}
+
+fn in_path(program: &str) -> bool {
+    extensions::which::find_in_path(program).is_some()
+}
+
+/// Whether `path` has an image extension.
+pub fn is_image(path: &Path) -> bool {
+    path.extension().map_or(false, |e| IMAGE_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+}
+
+impl Protocol {
+    /// The protocol for `inline_images`: `kitty`, `iterm2` or `sixel` force one, `off`
+    /// disables inline images and `auto` detects the terminal. Sixel needs `img2sixel`.
+    pub fn detect(setting: &str) -> Option<Self> {
+        let protocol = match setting.to_lowercase().as_str() {
+            "kitty" => Some(Protocol::Kitty),
+            "iterm2" => Some(Protocol::Iterm2),
+            "sixel" => Some(Protocol::Sixel),
+            "auto" => Self::from_environment(),
+            _ => None,
+        }?;
+        (protocol != Protocol::Sixel || in_path("img2sixel")).then_some(protocol)
+    }
+
+    fn from_environment() -> Option<Self> {
+        // Multiplexers swallow graphics escapes unless configured to pass them through
+        if !io::stdout().is_tty() || std::env::var_os("TMUX").is_some() || std::env::var("TERM").map_or(false, |t| t.starts_with("screen")) {
+            return None;
+        }
+        let term = std::env::var("TERM").unwrap_or_default();
+        let program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+        if std::env::var_os("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" || program == "ghostty" {
+            Some(Protocol::Kitty)
+        } else if program == "iTerm.app" || program == "WezTerm" || std::env::var_os("ITERM_SESSION_ID").is_some() {
+            Some(Protocol::Iterm2)
+        } else if term.starts_with("foot") || term.starts_with("mlterm") || term.contains("sixel") {
+            Some(Protocol::Sixel)
+        } else {
+            None
+        }
+    }
+
+    /// Draws the image at `path` below the cursor. Fails when the file is too large or
+    /// in a format the protocol can't show (kitty only takes PNG here).
+    pub fn render(&self, path: &Path) -> io::Result<()> {
+        let size = std::fs::metadata(path)?.len();
+        if size > MAX_INLINE_BYTES {
+            return Err(io::Error::new(io::ErrorKind::Other, format!("{} is too large to show inline", path.display())));
+        }
+        match self {
+            Protocol::Kitty => {
+                if !path.extension().map_or(false, |e| e.eq_ignore_ascii_case("png")) {
+                    return Err(io::Error::new(io::ErrorKind::Unsupported, "kitty images must be PNG"));
+                }
+                let data = base64::engine::general_purpose::STANDARD.encode(std::fs::read(path)?);
+                let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+                let mut out = io::stdout().lock();
+                for (index, chunk) in chunks.iter().enumerate() {
+                    let more = u8::from(index + 1 < chunks.len());
+                    // The first chunk carries the keys: transmit and display (a=T) a PNG (f=100) quietly (q=2)
+                    let keys = if index == 0 { "a=T,f=100,q=2," } else { "" };
+                    write!(out, "\x1b_G{}m={};", keys, more)?;
+                    out.write_all(chunk)?;
+                    out.write_all(b"\x1b\\")?;
+                }
+                writeln!(out)?;
+                out.flush()
+            }
+            Protocol::Iterm2 => {
+                let data = base64::engine::general_purpose::STANDARD.encode(std::fs::read(path)?);
+                let mut out = io::stdout().lock();
+                write!(out, "\x1b]1337;File=inline=1;size={};preserveAspectRatio=1:{}\x07", size, data)?;
+                writeln!(out)?;
+                out.flush()
+            }
+            Protocol::Sixel => {
+                io::stdout().flush()?;
+                let status = Command::new("img2sixel").arg(path).stdin(Stdio::null()).stderr(Stdio::null()).status()?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(io::Error::new(io::ErrorKind::Other, format!("img2sixel exited with {}", status)))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,691 @@
+//! Localized UI strings (English and Turkish) and Turkish command aliases.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+    Turkish,
+}
+
+impl Language {
+    /// Maps a resolved language code (`en`, `tr`) to a UI language; unknown codes fall back to English.
+    pub fn from_code(code: &str) -> Self {
+        if code.eq_ignore_ascii_case("tr") {
+            Language::Turkish
+        } else {
+            Language::English
+        }
+    }
+}
+
+/// UI messages. Templates use `{}` placeholders filled in order by `format_message`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    ErrorPrefix,
+    StatusPrefix,
+    Thinking,
+    Initialized,
+    CtrlCExit,
+    CtrlDExit,
+    ShuttingDown,
+    ReadInputError,
+    GeneratingCommand,
+    GenerationComplete,
+    GenerationError,
+    SuggestModeTool,
+    SuggestModeCommand,
+    ExecutingTool,
+    ToolError,
+    ToolCancelled,
+    ToolOutput,
+    UnknownTool,
+    UnknownToolDidYouMean,
+    ToolNameCorrected,
+    ToolsColumnName,
+    ToolsColumnDescription,
+    ToolNameConflict,
+    NoSavedSessions,
+    SessionsColumnSession,
+    SessionsColumnTitle,
+    SessionsColumnDate,
+    SessionsColumnTurns,
+    SessionsColumnModels,
+    Modified,
+    OutputTruncated,
+    CompletedIn,
+    RunCommandPrompt,
+    RunOnce,
+    AlwaysAllow,
+    No,
+    CommandNotRun,
+    CommandBlocked,
+    CommandFailed,
+    AskForFix,
+    RunCorrectedPrompt,
+    Running,
+    VerificationGenerationError,
+    VerificationSkipped,
+    Verifying,
+    VerificationPassed,
+    VerificationFailed,
+    SpeechOn,
+    SpeechOff,
+    SpeechUnavailable,
+    InvalidEditorSetting,
+    SpeakUsage,
+    RunningTests,
+    NoTestCommand,
+    TestsPassed,
+    TestsFailed,
+    NoFailuresParsed,
+    AgentStepLimit,
+    AgentTimeLimit,
+    AgentTokenBudget,
+    AgentCancelled,
+    ToolTimedOut,
+    GenerationCancelled,
+    ImageAttached,
+    AttachingImage,
+    NothingToRetry,
+    RetryingWithTemperature,
+    NothingToRate,
+    FeedbackRecorded,
+    DotenvDisabled,
+    DotenvNothingLoaded,
+    DotenvHeader,
+    DotenvLoaded,
+    DotenvSkipped,
+    VisibleEnvHeader,
+    Pinned,
+    AlreadyPinned,
+    Unpinned,
+    PinNotFound,
+    NoPins,
+    PinsHeader,
+    PlanHeader,
+    RunStepPrompt,
+    PlanStopped,
+    PlanComplete,
+    SelectFilesToEdit,
+    RunScriptPrompt,
+    LintPassed,
+    LintFailed,
+    LinterMissing,
+    SyntaxError,
+    FixingSyntax,
+    WillOverwrite,
+    WillAppend,
+    ExecutesOutputOf,
+    IntentMismatch,
+    IntentMismatchConfirm,
+    IntentMismatchNeedsTerminal,
+    PreviewOption,
+    PreviewHeader,
+    PreviewCreated,
+    PreviewModified,
+    PreviewDeleted,
+    PreviewNoChanges,
+    PreviewNetwork,
+    PreviewNetworkUntraced,
+    PreviewExitCode,
+    ChangeCreated,
+    ChangeModified,
+    ChangeDeleted,
+    RecoveringSession,
+    RecoveredUndo,
+    RecoveredPendingReply,
+    RecoveredPendingInput,
+    StoppedLeftoverProcesses,
+    StillRunningAfterShutdown,
+    RemovedPreviewScratch,
+    AuditEventsUndelivered,
+    RunNotACommand,
+    RunMultiStep,
+    RunNeedsConfirmation,
+    SuggestNoCommand,
+    AskUsage,
+    OfferTranslation,
+    NothingToTranslate,
+    MoreLines,
+    NothingMore,
+    StatusLineTokens,
+    TipPrefix,
+    ProductionSafeMode,
+    SimulationMode,
+    TestsNotSimulated,
+    BannerFontError,
+    BgUsage,
+    JobStarted,
+    NoJobs,
+    JobsColumnJob,
+    JobsColumnStatus,
+    JobsColumnOutput,
+    JobRunning,
+    JobExited,
+    JobKilled,
+    JobStoppedStatus,
+    JobDone,
+    JobFailed,
+    JobStopped,
+    JobNotRunning,
+    NoSuchJob,
+    JobFinished,
+    PanesHelp,
+    PanesNeedTerminal,
+    PanesUnavailable,
+    ImagesUnavailable,
+    DangerousToolCall,
+    TfPlanning,
+    TfNoChanges,
+    TfPlanCounts,
+    TfReviewHelp,
+    TfNoPlanFile,
+    TfApplyConfirm,
+    TfDestroyWarning,
+    TfDestroyConfirm,
+    FleetUsage,
+    FleetPlanHeader,
+    FleetHosts,
+    FleetDangerous,
+    FleetConfirm,
+    FleetHostDone,
+    FleetHostFailed,
+    FleetColumnHost,
+    FleetCancelled,
+    DirectoryContextOffer,
+    DirectoryContextLoaded,
+    RunRiskyCommandPrompt,
+    AgentWatchdog,
+    AgentWatchdogPrompt,
+    WatchdogContinue,
+    WatchdogGuide,
+    WatchdogStop,
+    WatchdogGuidancePrompt,
+    AnsweredOffline,
+    CheatUsage,
+    NoCheats,
+    CheatColumnCommand,
+    ConfigColumnKey,
+    ConfigColumnValue,
+    ConfigColumnSource,
+    ImageSaved,
+    RemoteApprovalPrompt,
+    RemoteApprovalSent,
+    RemoteApprovalFailed,
+    ApprovedRemotely,
+    DeniedRemotely,
+    RemoteApprovalTimedOut,
+    RedactionProfileBroken,
+}
+
+pub fn text(language: Language, message: Message) -> &'static str {
+    use Message::*;
+    match language {
+        Language::English => match message {
+            ErrorPrefix => "Error",
+            StatusPrefix => "Status",
+            Thinking => "Thinking... (Esc to cancel)",
+            Initialized => "Shellmind is initialized. Type 'exit' to quit.",
+            CtrlCExit => "Ctrl-C received, exiting.",
+            CtrlDExit => "Ctrl-D received, exiting.",
+            ShuttingDown => "Shutting down Shellmind.",
+            ReadInputError => "Error reading input: {}",
+            GeneratingCommand => "Generating command...",
+            GenerationComplete => "Command generation complete.",
+            GenerationError => "Error generating command: {}",
+            SuggestModeTool => "Suggest mode, not executed: {}",
+            SuggestModeCommand => "Suggest mode: command not executed.",
+            ExecutingTool => "Executing tool: {}",
+            ToolError => "Tool error: {}",
+            ToolCancelled => "Tool execution cancelled.",
+            ToolOutput => "Tool output: {}",
+            UnknownTool => "Unknown tool: {}",
+            UnknownToolDidYouMean => "Unknown tool `{}`; did you mean {}?",
+            ToolNameCorrected => "Unknown tool `{}`; using `{}` instead.",
+            ToolsColumnName => "Name",
+            ToolsColumnDescription => "Description",
+            ToolNameConflict => "`{}` is registered by more than one source; {} keeps the name and the other is available as `{}`.",
+            NoSavedSessions => "No saved sessions.",
+            SessionsColumnSession => "Session",
+            SessionsColumnTitle => "Title",
+            SessionsColumnDate => "Date",
+            SessionsColumnTurns => "Turns",
+            SessionsColumnModels => "Models",
+            Modified => "Modified: {}",
+            OutputTruncated => "Output was truncated.",
+            CompletedIn => "Completed in {}s",
+            RunCommandPrompt => "Do you want to run this command?",
+            RunOnce => "Yes (Run Once)",
+            AlwaysAllow => "Always Allow",
+            No => "No",
+            CommandNotRun => "Command not executed.",
+            CommandBlocked => "Command blocked and will never be executed: {}",
+            CommandFailed => "Command exited with error code: {}",
+            AskForFix => "Ask Shellmind to fix it?",
+            RunCorrectedPrompt => "Do you want to run the corrected command?",
+            Running => "Running: {}",
+            VerificationGenerationError => "Error generating verification command: {}",
+            VerificationSkipped => "Verification skipped, the proposed command is not read-only: {}",
+            Verifying => "Verifying: {}",
+            VerificationPassed => "Verification passed: the goal appears to be achieved.",
+            VerificationFailed => "Verification failed (exit code {}): the goal may not have been achieved.",
+            SpeechOn => "Speech output enabled.",
+            SpeechOff => "Speech output disabled.",
+            SpeechUnavailable => "No speech program found; install espeak-ng or set tts_command.",
+            InvalidEditorSetting => "Ignoring invalid line editor setting: {}",
+            SpeakUsage => "Usage: /speak on|off",
+            RunningTests => "Running tests: {}",
+            NoTestCommand => "Could not detect a test command; pass one with --command.",
+            TestsPassed => "All tests passed.",
+            TestsFailed => "{} failing test(s); first: {}",
+            NoFailuresParsed => "Tests failed but no failing test was recognized; sending the end of the output.",
+            AgentStepLimit => "Stopped after {} tool steps.",
+            AgentTimeLimit => "Stopped: the task reached its time limit of {}s.",
+            AgentTokenBudget => "Stopped: the task used its token budget ({} tokens).",
+            AgentCancelled => "Cancelled; {} of {} tool call(s) in this step finished and are shown above.",
+            ToolTimedOut => "{} did not finish within {}s.",
+            GenerationCancelled => "Generation cancelled.",
+            ImageAttached => "Image from the clipboard attached ({} KB).",
+            AttachingImage => "Attaching image: {}",
+            NothingToRetry => "Nothing to retry yet.",
+            RetryingWithTemperature => "Retrying with temperature {}.",
+            NothingToRate => "No answer to give feedback on yet.",
+            FeedbackRecorded => "Feedback recorded, thanks.",
+            DotenvDisabled => ".env loading is disabled (load_dotenv = false).",
+            DotenvNothingLoaded => "No .env file was found.",
+            DotenvHeader => "Variables in .env",
+            DotenvLoaded => "loaded",
+            DotenvSkipped => "skipped: not allowlisted or already set",
+            VisibleEnvHeader => "Visible to executed commands ({})",
+            Pinned => "Pinned: {}",
+            AlreadyPinned => "Already pinned: {}",
+            Unpinned => "Unpinned: {}",
+            PinNotFound => "No pin matches '{}'. Use /pins to list them.",
+            NoPins => "Nothing is pinned. Use /pin <path|text> to add context.",
+            PinsHeader => "Pinned context",
+            PlanHeader => "Plan ({} steps)",
+            RunStepPrompt => "Run step {}/{}: {}?",
+            PlanStopped => "Plan stopped at step {}.",
+            PlanComplete => "All {} steps completed.",
+            SelectFilesToEdit => "Files to edit (space toggles, enter confirms)",
+            RunScriptPrompt => "Run this script?",
+            LintPassed => "{} found no problems.",
+            LintFailed => "{} reported problems:",
+            LinterMissing => "{} is not installed; skipping the check.",
+            SyntaxError => "The command has a syntax error: {}",
+            FixingSyntax => "Asking for a corrected command...",
+            WillOverwrite => "will overwrite {}",
+            WillAppend => "will append to {}",
+            ExecutesOutputOf => "executes output of {}",
+            IntentMismatch => "Not what you asked for: this command {}",
+            IntentMismatchConfirm => "This command does something your request didn't ask for. Run it anyway?",
+            IntentMismatchNeedsTerminal => "This command does something the request didn't ask for and no terminal is attached to confirm it; --yes does not cover this.",
+            PreviewOption => "Preview in sandbox (/preview)",
+            PreviewHeader => "Sandboxed preview (workspace copy, network disabled):",
+            PreviewCreated => "  + would create {}",
+            PreviewModified => "  ~ would modify {}",
+            PreviewDeleted => "  - would delete {}",
+            PreviewNoChanges => "  No files in the workspace would change.",
+            PreviewNetwork => "  network call attempted: {}",
+            PreviewNetworkUntraced => "  Network access was blocked but not traced; install strace to see attempted connections.",
+            PreviewExitCode => "  Exit status in the sandbox: {}",
+            ChangeCreated => "  + created {}",
+            ChangeModified => "  ~ modified {}",
+            ChangeDeleted => "  - deleted {}",
+            RecoveringSession => "Recovering session {}",
+            RecoveredUndo => "Change from the interrupted turn: {} (undo with `shellmind undo {}`)",
+            RecoveredPendingReply => "This reply was awaiting approval when Shellmind stopped:",
+            RecoveredPendingInput => "Sending the interrupted prompt again: {}",
+            StoppedLeftoverProcesses => "Stopped {} process(es) Shellmind had started:",
+            StillRunningAfterShutdown => "Still running, could not be stopped ({}):",
+            RemovedPreviewScratch => "Removed {} leftover preview sandbox(es).",
+            AuditEventsUndelivered => "{} audit event(s) could not be forwarded to the webhook before exit.",
+            RunNotACommand => "The model proposed the {} tool; `shellmind run` only executes shell commands.",
+            RunMultiStep => "The model proposed several steps; use the interactive session to approve them one by one.",
+            RunNeedsConfirmation => "This command needs confirmation but no terminal is attached; rerun with --yes if it is not dangerous.",
+            SuggestNoCommand => "The model answered with an explanation instead of a command.",
+            AskUsage => "Usage: /ask <question>",
+            OfferTranslation => "The output is in English; type /translate to translate it.",
+            NothingToTranslate => "No command output to translate yet.",
+            MoreLines => "-- {} more line(s); type /more to continue --",
+            NothingMore => "No more output to show.",
+            StatusLineTokens => "{} tokens",
+            TipPrefix => "Tip",
+            ProductionSafeMode => "PRODUCTION ({}): every action needs approval and file-writing tools are off",
+            SimulationMode => "SIMULATION: approvals and the audit trail are real, but nothing is executed",
+            TestsNotSimulated => "`shellmind test` needs real test runs and is not available with --simulate.",
+            BannerFontError => "Could not load banner font '{}': {}",
+            BgUsage => "Usage: /bg <command>",
+            JobStarted => "Started job {} in the background: {}",
+            NoJobs => "No background jobs. Start one with /bg <command>.",
+            JobsColumnJob => "Job",
+            JobsColumnStatus => "Status",
+            JobsColumnOutput => "Output",
+            JobRunning => "running {}",
+            JobExited => "exit {} after {}",
+            JobKilled => "killed after {}",
+            JobStoppedStatus => "stopped after {}",
+            JobDone => "done in {}",
+            JobFailed => "failed in {}",
+            JobStopped => "Stopped job {}.",
+            JobNotRunning => "Job {} is not a running background command.",
+            NoSuchJob => "There is no job {}.",
+            JobFinished => "Background job {} finished ({}): {}. /jobs {} prints its output.",
+            PanesHelp => "Tab/←→ switch pane · ↑↓ PgUp PgDn scroll back · End follow output · q close",
+            PanesNeedTerminal => "The pane view needs a terminal; /jobs <number> prints a job's output instead.",
+            PanesUnavailable => "This build has no pane view (the `tui` feature is off); /jobs <number> prints a job's output instead.",
+            ImagesUnavailable => "This build cannot attach images (the `image` feature is off).",
+            DangerousToolCall => "{} is classified as dangerous; check the parameters carefully.",
+            TfPlanning => "Running terraform plan...",
+            TfNoChanges => "The plan has no changes.",
+            TfPlanCounts => "Plan: {} to add, {} to change, {} to destroy",
+            TfReviewHelp => "Ask about the plan, type `apply` to apply it or `exit` to leave.",
+            TfNoPlanFile => "This plan was read from JSON output and can't be applied; review a saved plan file or let tf-review run terraform plan.",
+            TfApplyConfirm => "Run `{}`?",
+            TfDestroyWarning => "Applying this plan destroys {} resource(s):",
+            TfDestroyConfirm => "Type the number of resources to destroy ({}) to apply",
+            FleetUsage => "Usage: /fleet <task>, e.g. /fleet restart nginx on web01-web05",
+            FleetPlanHeader => "Fleet plan: {} host(s), {} step(s)",
+            FleetHosts => "Hosts: {}",
+            FleetDangerous => "The plan has dangerous steps and runs them on every host.",
+            FleetConfirm => "Run these steps on {} host(s), {} at a time?",
+            FleetHostDone => "{}: done in {}",
+            FleetHostFailed => "{}: step {} failed with exit code {} after {}",
+            FleetColumnHost => "Host",
+            FleetCancelled => "Cancelling the fleet run; remaining hosts are skipped.",
+            DirectoryContextOffer => "Load the context of your last session in this directory (\"{}\", {})?",
+            DirectoryContextLoaded => "Loaded the summarized context of \"{}\"",
+            RunRiskyCommandPrompt => "{} — run this command?",
+            AgentWatchdog => "Watchdog halted the agent: {}",
+            AgentWatchdogPrompt => "How should the agent go on?",
+            WatchdogContinue => "Continue",
+            WatchdogGuide => "Give it instructions",
+            WatchdogStop => "Stop",
+            WatchdogGuidancePrompt => "Instructions for the agent",
+            AnsweredOffline => "Answered from the offline command database (no API call)",
+            CheatUsage => "Usage: /cheat <what you want to do>, e.g. /cheat extract tar.gz",
+            NoCheats => "No offline snippets match '{}'",
+            CheatColumnCommand => "Command",
+            ConfigColumnKey => "Setting",
+            ConfigColumnValue => "Value",
+            ConfigColumnSource => "Source",
+            ImageSaved => "Image saved to {}",
+            RemoteApprovalPrompt => "{} [y/N] (sent to your phone if unanswered for {}s)",
+            RemoteApprovalSent => "Approval request sent; reply \"approve {}\" or \"deny {}\" from your phone, or press y or n here",
+            RemoteApprovalFailed => "Could not forward the approval: {}",
+            ApprovedRemotely => "Approved remotely",
+            DeniedRemotely => "Denied remotely",
+            RemoteApprovalTimedOut => "No remote reply within {} minutes; denied",
+            RedactionProfileBroken => "{}; withholding all workspace files",
+        },
+        Language::Turkish => match message {
+            ErrorPrefix => "Hata",
+            StatusPrefix => "Durum",
+            Thinking => "Düşünüyor... (iptal için Esc)",
+            Initialized => "Shellmind hazır. Çıkmak için 'çıkış' yazın.",
+            CtrlCExit => "Ctrl-C alındı, çıkılıyor.",
+            CtrlDExit => "Ctrl-D alındı, çıkılıyor.",
+            ShuttingDown => "Shellmind kapatılıyor.",
+            ReadInputError => "Girdi okunurken hata: {}",
+            GeneratingCommand => "Komut oluşturuluyor...",
+            GenerationComplete => "Komut oluşturma tamamlandı.",
+            GenerationError => "Komut oluşturulurken hata: {}",
+            SuggestModeTool => "Öneri modu, çalıştırılmadı: {}",
+            SuggestModeCommand => "Öneri modu: komut çalıştırılmadı.",
+            ExecutingTool => "Araç çalıştırılıyor: {}",
+            ToolError => "Araç hatası: {}",
+            ToolCancelled => "Araç çalıştırma iptal edildi.",
+            ToolOutput => "Araç çıktısı: {}",
+            UnknownTool => "Bilinmeyen araç: {}",
+            UnknownToolDidYouMean => "Bilinmeyen araç `{}`; şunu mu demek istediniz: {}?",
+            ToolNameCorrected => "Bilinmeyen araç `{}`; yerine `{}` kullanılıyor.",
+            ToolsColumnName => "Ad",
+            ToolsColumnDescription => "Açıklama",
+            ToolNameConflict => "`{}` birden fazla kaynak tarafından kaydedildi; ad {} kaynağında kalıyor, diğeri `{}` olarak kullanılabilir.",
+            NoSavedSessions => "Kayıtlı oturum yok.",
+            SessionsColumnSession => "Oturum",
+            SessionsColumnTitle => "Başlık",
+            SessionsColumnDate => "Tarih",
+            SessionsColumnTurns => "Tur",
+            SessionsColumnModels => "Modeller",
+            Modified => "Değiştirildi: {}",
+            OutputTruncated => "Çıktı kısaltıldı.",
+            CompletedIn => "{} sn içinde tamamlandı",
+            RunCommandPrompt => "Bu komutu çalıştırmak ister misiniz?",
+            RunOnce => "Evet (Bir Kez Çalıştır)",
+            AlwaysAllow => "Her Zaman İzin Ver",
+            No => "Hayır",
+            CommandNotRun => "Komut çalıştırılmadı.",
+            CommandBlocked => "Komut engellendi, asla çalıştırılmaz: {}",
+            CommandFailed => "Komut hata koduyla çıktı: {}",
+            AskForFix => "Shellmind'dan düzeltmesini iste?",
+            RunCorrectedPrompt => "Düzeltilmiş komutu çalıştırmak ister misiniz?",
+            Running => "Çalıştırılıyor: {}",
+            VerificationGenerationError => "Doğrulama komutu oluşturulurken hata: {}",
+            VerificationSkipped => "Doğrulama atlandı, önerilen komut salt okunur değil: {}",
+            Verifying => "Doğrulanıyor: {}",
+            VerificationPassed => "Doğrulama başarılı: hedefe ulaşılmış görünüyor.",
+            VerificationFailed => "Doğrulama başarısız (çıkış kodu {}): hedefe ulaşılmamış olabilir.",
+            SpeechOn => "Sesli okuma açıldı.",
+            SpeechOff => "Sesli okuma kapatıldı.",
+            SpeechUnavailable => "Konuşma programı bulunamadı; espeak-ng kurun veya tts_command ayarlayın.",
+            InvalidEditorSetting => "Geçersiz satır düzenleyici ayarı yok sayılıyor: {}",
+            SpeakUsage => "Kullanım: /konuş aç|kapat",
+            RunningTests => "Testler çalıştırılıyor: {}",
+            NoTestCommand => "Test komutu algılanamadı; --command ile belirtin.",
+            TestsPassed => "Tüm testler geçti.",
+            TestsFailed => "{} başarısız test; ilki: {}",
+            NoFailuresParsed => "Testler başarısız oldu ancak başarısız test tanınamadı; çıktının sonu gönderiliyor.",
+            AgentStepLimit => "{} araç adımından sonra durduruldu.",
+            AgentTimeLimit => "Durduruldu: görev {} saniyelik süre sınırına ulaştı.",
+            AgentTokenBudget => "Durduruldu: görev token bütçesini kullandı ({} token).",
+            AgentCancelled => "İptal edildi; bu adımdaki {} / {} araç çağrısı tamamlandı ve yukarıda gösterildi.",
+            ToolTimedOut => "{} {} saniye içinde tamamlanmadı.",
+            GenerationCancelled => "Yanıt üretimi iptal edildi.",
+            ImageAttached => "Panodaki görüntü eklendi ({} KB).",
+            AttachingImage => "Görüntü ekleniyor: {}",
+            NothingToRetry => "Yeniden denenecek bir istem yok.",
+            RetryingWithTemperature => "{} sıcaklığıyla yeniden deneniyor.",
+            NothingToRate => "Henüz geri bildirim verilecek bir yanıt yok.",
+            FeedbackRecorded => "Geri bildirim kaydedildi, teşekkürler.",
+            DotenvDisabled => ".env yükleme kapalı (load_dotenv = false).",
+            DotenvNothingLoaded => ".env dosyası bulunamadı.",
+            DotenvHeader => ".env içindeki değişkenler",
+            DotenvLoaded => "yüklendi",
+            DotenvSkipped => "atlandı: izin listesinde değil veya zaten tanımlı",
+            VisibleEnvHeader => "Çalıştırılan komutların gördüğü değişkenler ({})",
+            Pinned => "Sabitlendi: {}",
+            AlreadyPinned => "Zaten sabitlenmiş: {}",
+            Unpinned => "Sabitleme kaldırıldı: {}",
+            PinNotFound => "'{}' ile eşleşen sabitleme yok. Listelemek için /pins kullanın.",
+            NoPins => "Sabitlenmiş bir şey yok. Bağlam eklemek için /pin <yol|metin> kullanın.",
+            PinsHeader => "Sabitlenmiş bağlam",
+            PlanHeader => "Plan ({} adım)",
+            RunStepPrompt => "{}/{}. adım çalıştırılsın mı: {}?",
+            PlanStopped => "Plan {}. adımda durduruldu.",
+            PlanComplete => "{} adımın tümü tamamlandı.",
+            SelectFilesToEdit => "Düzenlenecek dosyalar (boşluk seçer, enter onaylar)",
+            RunScriptPrompt => "Bu betik çalıştırılsın mı?",
+            LintPassed => "{} sorun bulmadı.",
+            LintFailed => "{} sorunlar bildirdi:",
+            LinterMissing => "{} kurulu değil; denetim atlanıyor.",
+            SyntaxError => "Komutta sözdizimi hatası var: {}",
+            FixingSyntax => "Düzeltilmiş komut isteniyor...",
+            WillOverwrite => "{} dosyasının üzerine yazılacak",
+            WillAppend => "{} dosyasının sonuna eklenecek",
+            ExecutesOutputOf => "{} komutunun çıktısı çalıştırılacak",
+            IntentMismatch => "İstediğiniz bu değil: bu komut {}",
+            IntentMismatchConfirm => "Bu komut isteğinizde olmayan bir şey yapıyor. Yine de çalıştırılsın mı?",
+            IntentMismatchNeedsTerminal => "Bu komut istekte olmayan bir şey yapıyor ve onaylamak için bağlı bir terminal yok; --yes bunu kapsamaz.",
+            PreviewOption => "Korumalı alanda önizle (/preview)",
+            PreviewHeader => "Korumalı alan önizlemesi (çalışma alanı kopyası, ağ kapalı):",
+            PreviewCreated => "  + oluşturulacak: {}",
+            PreviewModified => "  ~ değiştirilecek: {}",
+            PreviewDeleted => "  - silinecek: {}",
+            PreviewNoChanges => "  Çalışma alanında hiçbir dosya değişmeyecek.",
+            PreviewNetwork => "  denenen ağ bağlantısı: {}",
+            PreviewNetworkUntraced => "  Ağ erişimi engellendi ancak izlenmedi; denenen bağlantıları görmek için strace kurun.",
+            PreviewExitCode => "  Korumalı alandaki çıkış kodu: {}",
+            ChangeCreated => "  + oluşturuldu: {}",
+            ChangeModified => "  ~ değiştirildi: {}",
+            ChangeDeleted => "  - silindi: {}",
+            RecoveringSession => "{} oturumu kurtarılıyor",
+            RecoveredUndo => "Yarıda kalan turdaki değişiklik: {} (geri almak için `shellmind undo {}`)",
+            RecoveredPendingReply => "Shellmind durduğunda bu yanıt onay bekliyordu:",
+            RecoveredPendingInput => "Yarıda kalan istem yeniden gönderiliyor: {}",
+            StoppedLeftoverProcesses => "Shellmind'ın başlattığı {} işlem durduruldu:",
+            StillRunningAfterShutdown => "Hâlâ çalışıyor, durdurulamadı ({}):",
+            RemovedPreviewScratch => "Artakalan {} önizleme korumalı alanı kaldırıldı.",
+            AuditEventsUndelivered => "{} denetim olayı çıkıştan önce webhook'a iletilemedi.",
+            RunNotACommand => "Model {} aracını önerdi; `shellmind run` yalnızca kabuk komutları çalıştırır.",
+            RunMultiStep => "Model birden fazla adım önerdi; adımları tek tek onaylamak için etkileşimli oturumu kullanın.",
+            RunNeedsConfirmation => "Bu komut onay gerektiriyor ancak bağlı bir terminal yok; tehlikeli değilse --yes ile yeniden çalıştırın.",
+            SuggestNoCommand => "Model komut yerine bir açıklama ile yanıt verdi.",
+            AskUsage => "Kullanım: /ask <soru>",
+            OfferTranslation => "Çıktı İngilizce; Türkçeye çevirmek için /translate (veya /çevir) yazın.",
+            NothingToTranslate => "Henüz çevrilecek bir komut çıktısı yok.",
+            MoreLines => "-- {} satır daha; devam etmek için /more (veya /devam) yazın --",
+            NothingMore => "Gösterilecek başka çıktı yok.",
+            StatusLineTokens => "{} token",
+            TipPrefix => "İpucu",
+            ProductionSafeMode => "ÜRETİM ORTAMI ({}): her işlem onay gerektirir ve dosya yazan araçlar kapalı",
+            SimulationMode => "SİMÜLASYON: onaylar ve denetim kaydı gerçek, ancak hiçbir şey çalıştırılmıyor",
+            TestsNotSimulated => "`shellmind test` gerçek test çalıştırması gerektirir ve --simulate ile kullanılamaz.",
+            BannerFontError => "Banner yazı tipi '{}' yüklenemedi: {}",
+            BgUsage => "Kullanım: /bg <komut>",
+            JobStarted => "{} numaralı iş arka planda başlatıldı: {}",
+            NoJobs => "Arka planda iş yok. /bg <komut> ile bir tane başlatın.",
+            JobsColumnJob => "İş",
+            JobsColumnStatus => "Durum",
+            JobsColumnOutput => "Çıktı",
+            JobRunning => "{} süredir çalışıyor",
+            JobExited => "{} koduyla çıktı, {} sürdü",
+            JobKilled => "öldürüldü, {} sürdü",
+            JobStoppedStatus => "durduruldu, {} sürdü",
+            JobDone => "{} içinde tamamlandı",
+            JobFailed => "{} içinde başarısız oldu",
+            JobStopped => "{} numaralı iş durduruldu.",
+            JobNotRunning => "{} numaralı iş çalışan bir arka plan komutu değil.",
+            NoSuchJob => "{} numaralı bir iş yok.",
+            JobFinished => "{} numaralı arka plan işi bitti ({}): {}. Çıktısı için /jobs {} yazın.",
+            PanesHelp => "Tab/←→ bölme değiştir · ↑↓ PgUp PgDn geri kaydır · End çıktıyı izle · q kapat",
+            PanesNeedTerminal => "Bölmeli görünüm bir terminal gerektirir; bunun yerine /jobs <numara> bir işin çıktısını yazdırır.",
+            DangerousToolCall => "{} tehlikeli olarak sınıflandırıldı; parametreleri dikkatle kontrol edin.",
+            TfPlanning => "terraform plan çalıştırılıyor...",
+            TfNoChanges => "Planda değişiklik yok.",
+            TfPlanCounts => "Plan: {} eklenecek, {} değişecek, {} silinecek",
+            TfReviewHelp => "Plan hakkında soru sorun, uygulamak için `uygula`, çıkmak için `çıkış` yazın.",
+            TfNoPlanFile => "Bu plan JSON çıktısından okundu ve uygulanamaz; kaydedilmiş bir plan dosyasını inceleyin ya da terraform plan'ı tf-review çalıştırsın.",
+            TfApplyConfirm => "`{}` çalıştırılsın mı?",
+            TfDestroyWarning => "Bu planı uygulamak {} kaynağı siler:",
+            TfDestroyConfirm => "Uygulamak için silinecek kaynak sayısını ({}) yazın",
+            FleetUsage => "Kullanım: /fleet <görev>, örn. /fleet web01-web05 üzerinde nginx'i yeniden başlat",
+            FleetPlanHeader => "Filo planı: {} sunucu, {} adım",
+            FleetHosts => "Sunucular: {}",
+            FleetDangerous => "Plan tehlikeli adımlar içeriyor ve bunları her sunucuda çalıştırır.",
+            FleetConfirm => "Bu adımlar {} sunucuda, aynı anda {} tanesinde çalıştırılsın mı?",
+            FleetHostDone => "{}: {} içinde tamamlandı",
+            FleetHostFailed => "{}: {}. adım {} koduyla başarısız oldu, {} sürdü",
+            FleetColumnHost => "Sunucu",
+            FleetCancelled => "Filo çalıştırması iptal ediliyor; kalan sunucular atlanıyor.",
+            DirectoryContextOffer => "Bu dizindeki son oturumun bağlamı yüklensin mi (\"{}\", {})?",
+            DirectoryContextLoaded => "\"{}\" oturumunun özetlenmiş bağlamı yüklendi",
+            RunRiskyCommandPrompt => "{} — bu komut çalıştırılsın mı?",
+            AgentWatchdog => "Bekçi ajanı durdurdu: {}",
+            AgentWatchdogPrompt => "Ajan nasıl devam etsin?",
+            WatchdogContinue => "Devam et",
+            WatchdogGuide => "Talimat ver",
+            WatchdogStop => "Durdur",
+            WatchdogGuidancePrompt => "Ajan için talimatlar",
+            AnsweredOffline => "Çevrimdışı komut veritabanından yanıtlandı (API çağrısı yapılmadı)",
+            CheatUsage => "Kullanım: /kopya <ne yapmak istediğiniz>, ör. /kopya extract tar.gz",
+            NoCheats => "'{}' ile eşleşen çevrimdışı komut yok",
+            CheatColumnCommand => "Komut",
+            ConfigColumnKey => "Ayar",
+            ConfigColumnValue => "Değer",
+            ConfigColumnSource => "Kaynak",
+            ImageSaved => "Görsel kaydedildi: {}",
+            RemoteApprovalPrompt => "{} [y/N] ({} sn içinde yanıtlanmazsa telefonunuza gönderilir)",
+            RemoteApprovalSent => "Onay isteği gönderildi; telefonunuzdan \"approve {}\" ya da \"deny {}\" ile yanıtlayın veya burada y ya da n tuşuna basın",
+            RemoteApprovalFailed => "Onay iletilemedi: {}",
+            ApprovedRemotely => "Uzaktan onaylandı",
+            DeniedRemotely => "Uzaktan reddedildi",
+            RemoteApprovalTimedOut => "{} dakika içinde uzaktan yanıt gelmedi; reddedildi",
+            RedactionProfileBroken => "{}; tüm çalışma alanı dosyaları gizleniyor",
+            PanesUnavailable => "Bu derlemede bölmeli görünüm yok (`tui` özelliği kapalı); bunun yerine /jobs <numara> bir işin çıktısını yazdırır.",
+            ImagesUnavailable => "Bu derleme görsel ekleyemez (`image` özelliği kapalı).",
+        },
+    }
+}
+
+/// Fills the `{}` placeholders of a message template in order.
+pub fn format_message(template: &str, args: &[&dyn std::fmt::Display]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        output.push_str(first);
+    }
+    for (index, part) in parts.enumerate() {
+        if let Some(arg) = args.get(index) {
+            output.push_str(&arg.to_string());
+        }
+        output.push_str(part);
+    }
+    output
+}
+
+/// Turkish aliases for built-in commands and their arguments, accepted in every language.
+const COMMAND_ALIASES: &[(&str, &str)] = &[
+    ("çıkış", "exit"),
+    ("/çıkış", "exit"),
+    ("/exit", "exit"),
+    ("/konuş", "/speak"),
+    ("/tekrar", "/retry"),
+    ("/sor", "/ask"),
+    ("/çevir", "/translate"),
+    ("/devam", "/more"),
+    ("/araçlar", "/tools"),
+    ("/kopya", "/cheat"),
+    ("/işler", "/jobs"),
+    ("/bölmeler", "/panes"),
+    ("/durdur", "/stop"),
+    ("/filo", "/fleet"),
+    ("/oturumlar", "/sessions"),
+    ("/iyi", "/good"),
+    ("/kötü", "/bad"),
+    ("/sabitle", "/pin"),
+    ("aç", "on"),
+    ("kapat", "off"),
+];
+
+fn canonical(word: &str) -> Option<&'static str> {
+    let lowered = word.to_lowercase();
+    COMMAND_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == lowered)
+        .map(|(_, canonical)| *canonical)
+}
+
+/// Rewrites a built-in command typed with aliases to its canonical form, e.g.
+/// `/konuş aç` to `/speak on`, and a bare `çıkış` to `exit`. Other input, including
+/// prompts that merely start with an alias like `aç dosyayı`, is returned unchanged.
+pub fn resolve_alias(input: &str) -> String {
+    let mut words = input.split_whitespace();
+    let Some(first) = words.next() else {
+        return input.to_string();
+    };
+    if !first.starts_with('/') {
+        return match (canonical(first), words.next()) {
+            (Some("exit"), None) => "exit".to_string(),
+            _ => input.to_string(),
+        };
+    }
+    match canonical(first) {
+        // Only `/speak` takes keyword arguments; free text after other commands is kept as typed
+        Some(command) if command == "/speak" => std::iter::once(command)
+            .chain(words.map(|w| canonical(w).unwrap_or(w)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Some(command) => std::iter::once(command).chain(words).collect::<Vec<_>>().join(" "),
+        None => input.to_string(),
+    }
+}
@@ -0,0 +1,155 @@
+//! Line editor settings from the config: vi or emacs editing, how completions are listed,
+//! and custom key bindings written as `"ctrl-p" = "history-search-backward"`.
+
+use rustyline::{Anchor, At, Cmd, CompletionType, EditMode, KeyCode, KeyEvent, Modifiers, Movement, Word};
+
+/// Parses the `edit_mode` setting: `emacs` or `vi`.
+pub fn edit_mode(name: &str) -> Option<EditMode> {
+    match name.to_lowercase().as_str() {
+        "emacs" => Some(EditMode::Emacs),
+        "vi" | "vim" => Some(EditMode::Vi),
+        _ => None,
+    }
+}
+
+/// Parses the `completion_type` setting: `circular` cycles through the candidates on each
+/// Tab, `list` completes the common prefix and lists the rest like bash.
+pub fn completion_type(name: &str) -> Option<CompletionType> {
+    match name.to_lowercase().as_str() {
+        "circular" => Some(CompletionType::Circular),
+        "list" => Some(CompletionType::List),
+        _ => None,
+    }
+}
+
+/// Parses a key such as `ctrl-p`, `alt-b`, `ctrl-alt-h`, `shift-tab`, `f5` or `pageup`.
+pub fn parse_key(spec: &str) -> Option<KeyEvent> {
+    let spec = spec.trim().to_lowercase();
+    let mut modifiers = Modifiers::NONE;
+    let mut rest = spec.as_str();
+    loop {
+        let (modifier, after) = match rest.split_once('-') {
+            // A trailing `-` is the key itself, as in `ctrl--`
+            Some((prefix, after)) if !after.is_empty() => (prefix, after),
+            _ => break,
+        };
+        modifiers |= match modifier {
+            "ctrl" | "control" | "c" => Modifiers::CTRL,
+            "alt" | "meta" | "m" => Modifiers::ALT,
+            "shift" | "s" => Modifiers::SHIFT,
+            _ => return None,
+        };
+        rest = after;
+    }
+    let code = match rest {
+        "tab" if modifiers.contains(Modifiers::SHIFT) => return Some(KeyEvent(KeyCode::BackTab, modifiers.difference(Modifiers::SHIFT))),
+        "tab" => KeyCode::Tab,
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => return Some(KeyEvent::new(' ', modifiers)),
+        _ if rest.len() > 1 && rest.starts_with('f') => KeyCode::F(rest[1..].parse().ok().filter(|n| (1..=24).contains(n))?),
+        _ => {
+            let mut chars = rest.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            return Some(KeyEvent::new(c, modifiers));
+        }
+    };
+    Some(KeyEvent(code, modifiers))
+}
+
+/// Action names accepted in `key_bindings`, besides `insert:<text>`.
+pub const ACTIONS: &[&str] = &[
+    "accept-line",
+    "complete",
+    "previous-history",
+    "next-history",
+    "history-search-backward",
+    "history-search-forward",
+    "reverse-search-history",
+    "forward-search-history",
+    "beginning-of-history",
+    "end-of-history",
+    "beginning-of-line",
+    "end-of-line",
+    "backward-char",
+    "forward-char",
+    "backward-word",
+    "forward-word",
+    "kill-line",
+    "unix-line-discard",
+    "kill-whole-line",
+    "backward-kill-word",
+    "kill-word",
+    "yank",
+    "yank-pop",
+    "transpose-chars",
+    "upcase-word",
+    "downcase-word",
+    "capitalize-word",
+    "undo",
+    "clear-screen",
+    "insert-newline",
+    "abort",
+    "interrupt",
+    "end-of-file",
+    "noop",
+];
+
+/// Parses an action name from `ACTIONS` (readline's names), or `insert:<text>`.
+pub fn parse_action(name: &str) -> Option<Cmd> {
+    if let Some(text) = name.strip_prefix("insert:") {
+        return Some(Cmd::Insert(1, text.to_string()));
+    }
+    let cmd = match name.trim().to_lowercase().as_str() {
+        "accept-line" => Cmd::AcceptLine,
+        "complete" => Cmd::Complete,
+        "previous-history" => Cmd::PreviousHistory,
+        "next-history" => Cmd::NextHistory,
+        "history-search-backward" => Cmd::HistorySearchBackward,
+        "history-search-forward" => Cmd::HistorySearchForward,
+        "reverse-search-history" => Cmd::ReverseSearchHistory,
+        "forward-search-history" => Cmd::ForwardSearchHistory,
+        "beginning-of-history" => Cmd::BeginningOfHistory,
+        "end-of-history" => Cmd::EndOfHistory,
+        "beginning-of-line" => Cmd::Move(Movement::BeginningOfLine),
+        "end-of-line" => Cmd::Move(Movement::EndOfLine),
+        "backward-char" => Cmd::Move(Movement::BackwardChar(1)),
+        "forward-char" => Cmd::Move(Movement::ForwardChar(1)),
+        "backward-word" => Cmd::Move(Movement::BackwardWord(1, Word::Emacs)),
+        "forward-word" => Cmd::Move(Movement::ForwardWord(1, At::AfterEnd, Word::Emacs)),
+        "kill-line" => Cmd::Kill(Movement::EndOfLine),
+        "unix-line-discard" => Cmd::Kill(Movement::BeginningOfLine),
+        "kill-whole-line" => Cmd::Kill(Movement::WholeLine),
+        "backward-kill-word" => Cmd::Kill(Movement::BackwardWord(1, Word::Emacs)),
+        "kill-word" => Cmd::Kill(Movement::ForwardWord(1, At::AfterEnd, Word::Emacs)),
+        "yank" => Cmd::Yank(1, Anchor::Before),
+        "yank-pop" => Cmd::YankPop,
+        "transpose-chars" => Cmd::TransposeChars,
+        "upcase-word" => Cmd::UpcaseWord,
+        "downcase-word" => Cmd::DowncaseWord,
+        "capitalize-word" => Cmd::CapitalizeWord,
+        "undo" => Cmd::Undo(1),
+        "clear-screen" => Cmd::ClearScreen,
+        "insert-newline" => Cmd::Newline,
+        "abort" => Cmd::Abort,
+        "interrupt" => Cmd::Interrupt,
+        "end-of-file" => Cmd::EndOfFile,
+        "noop" => Cmd::Noop,
+        _ => return None,
+    };
+    Some(cmd)
+}
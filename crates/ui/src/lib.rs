@@ -1,5 +1,18 @@
 //! Terminal UI for Shellmind
 
+pub mod graphics;
+pub mod i18n;
+pub mod keymap;
+pub mod markdown;
+pub mod pager;
+#[cfg(feature = "tui")]
+pub mod panes;
+pub mod prompt;
+pub mod speech;
+pub mod table;
+pub mod tips;
+
+#[cfg(feature = "banner")]
 use figlet_rs::FIGfont;
 use ansi_term::Colour;
 use std::io::{self, Write};
@@ -17,35 +30,38 @@ use std::borrow::Cow::{self, Owned};
 use rustyline::history::DefaultHistory;
 
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-// Custom completer for rustyline
+// Custom completer for rustyline: completes the word before the cursor as a path
 struct ShellmindCompleter;
 
 impl Completer for ShellmindCompleter {
     type Candidate = Pair;
 
     fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> RLResult<(usize, Vec<Pair>)> {
-        let path = Path::new(line);
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        // `dir` keeps the typed directory part so the replacement can include it
+        let (dir, prefix) = match word.rfind('/') {
+            Some(i) => (&word[..=i], &word[i + 1..]),
+            None => ("", word),
+        };
+        let search = if dir.is_empty() { Path::new(".") } else { Path::new(dir) };
         let mut completions = Vec::new();
-
-        if let Some(parent) = path.parent() {
-            if let Ok(entries) = std::fs::read_dir(parent) {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let file_name = entry.file_name();
-                        let file_name_str = file_name.to_string_lossy();
-                        if file_name_str.starts_with(&line[path.parent().unwrap_or(Path::new("")).to_string_lossy().len()..]) {
-                            completions.push(Pair {
-                                display: file_name_str.to_string(),
-                                replacement: file_name_str.to_string(),
-                            });
-                        }
-                    }
+        if let Ok(entries) = std::fs::read_dir(search) {
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                // Hidden files only when asked for
+                if !file_name.starts_with(prefix) || (file_name.starts_with('.') && !prefix.starts_with('.')) {
+                    continue;
                 }
+                let suffix = if entry.file_type().map_or(false, |t| t.is_dir()) { "/" } else { "" };
+                completions.push(Pair { display: format!("{}{}", file_name, suffix), replacement: format!("{}{}{}", dir, file_name, suffix) });
             }
         }
+        completions.sort_by(|a, b| a.display.cmp(&b.display));
 
-        Ok((pos, completions))
+        Ok((start, completions))
     }
 }
 
@@ -82,6 +98,14 @@ impl ThemeManager {
         Colour::Green
     }
 
+    /// The REPL prompt for `format`; an empty format gives the plain `> ` prompt.
+    pub fn render_prompt(&self, format: &str, context: &prompt::PromptContext) -> String {
+        if format.is_empty() {
+            return self.get_prompt_color().paint("> ").to_string();
+        }
+        prompt::render(format, context)
+    }
+
     pub fn get_command_color(&self) -> Colour {
         Colour::Yellow
     }
@@ -93,36 +117,314 @@ impl ThemeManager {
     pub fn get_spinner_color(&self) -> Colour {
         Colour::Green
     }
+
+    /// Color for a review finding's severity (`high`, `medium`, `low`).
+    pub fn get_severity_color(&self, severity: &str) -> Colour {
+        match severity.to_lowercase().as_str() {
+            "high" | "critical" => Colour::Red,
+            "medium" => Colour::Yellow,
+            _ => Colour::Cyan,
+        }
+    }
+}
+
+/// Blocks until Esc or Ctrl-C is pressed (returns true) or `stop` is set (returns false).
+/// The terminal is in raw mode meanwhile so the keys arrive as events rather than signals;
+/// returns false at once when stdin is not a terminal.
+pub fn wait_for_cancel_key(stop: &AtomicBool) -> bool {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::tty::IsTty;
+
+    if !io::stdin().is_tty() || crossterm::terminal::enable_raw_mode().is_err() {
+        return false;
+    }
+    let mut cancelled = false;
+    while !stop.load(Ordering::Relaxed) {
+        match event::poll(Duration::from_millis(50)) {
+            Ok(true) => {
+                if let Ok(Event::Key(key)) = event::read() {
+                    let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                    if key.kind == KeyEventKind::Press && (key.code == KeyCode::Esc || ctrl_c) {
+                        cancelled = true;
+                        break;
+                    }
+                }
+            }
+            Ok(false) => {}
+            Err(_) => break,
+        }
+    }
+    let _ = crossterm::terminal::disable_raw_mode();
+    cancelled
+}
+
+/// Blocks until `y` or `n` is pressed (Some(true) or Some(false); Enter, Esc and Ctrl-C
+/// give `default`) or `stop` is set (None). Returns `None` at once when stdin is not a
+/// terminal.
+pub fn wait_for_yes_no(default: bool, stop: &AtomicBool) -> Option<bool> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::tty::IsTty;
+
+    if !io::stdin().is_tty() || crossterm::terminal::enable_raw_mode().is_err() {
+        return None;
+    }
+    let mut answer = None;
+    while answer.is_none() && !stop.load(Ordering::Relaxed) {
+        match event::poll(Duration::from_millis(50)) {
+            Ok(true) => {
+                if let Ok(Event::Key(key)) = event::read() {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    answer = match key.code {
+                        KeyCode::Char('y') | KeyCode::Char('Y') => Some(true),
+                        KeyCode::Char('n') | KeyCode::Char('N') => Some(false),
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(default),
+                        KeyCode::Enter | KeyCode::Esc => Some(default),
+                        _ => None,
+                    };
+                }
+            }
+            Ok(false) => {}
+            Err(_) => break,
+        }
+    }
+    let _ = crossterm::terminal::disable_raw_mode();
+    answer
+}
+
+/// Shows a reply dimmed while it streams in, then erases it so the finished reply can be
+/// rendered in its place. Does nothing when stdout is not a terminal.
+pub struct StreamPreview {
+    enabled: bool,
+    width: usize,
+    /// Rows below the first one written so far, and the column on the last row.
+    rows: usize,
+    column: usize,
+}
+
+impl StreamPreview {
+    pub fn new() -> Self {
+        use crossterm::tty::IsTty;
+        let width = crossterm::terminal::size().map_or(80, |(columns, _)| columns.max(1) as usize);
+        StreamPreview { enabled: io::stdout().is_tty(), width, rows: 0, column: 0 }
+    }
+
+    /// Appends a piece of the reply. Newlines are written as `\r\n` since the cancel key
+    /// listener keeps the terminal in raw mode.
+    pub fn push(&mut self, text: &str) {
+        if !self.enabled {
+            return;
+        }
+        let mut out = io::stdout().lock();
+        let mut line = String::new();
+        for c in text.chars() {
+            if c == '\n' {
+                line.push_str("\r\n");
+                self.rows += 1;
+                self.column = 0;
+                continue;
+            }
+            if self.column == self.width {
+                self.rows += 1;
+                self.column = 0;
+            }
+            line.push(c);
+            self.column += 1;
+        }
+        let _ = write!(out, "{}", ansi_term::Style::new().dimmed().paint(line));
+        let _ = out.flush();
+    }
+
+    /// Erases everything written so far.
+    pub fn clear(&mut self) {
+        use crossterm::cursor::{MoveToColumn, MoveUp};
+        use crossterm::terminal::{Clear, ClearType};
+
+        if !self.enabled || (self.rows == 0 && self.column == 0) {
+            return;
+        }
+        let mut out = io::stdout();
+        let _ = crossterm::execute!(out, MoveToColumn(0));
+        if self.rows > 0 {
+            let _ = crossterm::execute!(out, MoveUp(self.rows as u16));
+        }
+        let _ = crossterm::execute!(out, Clear(ClearType::FromCursorDown));
+        self.rows = 0;
+        self.column = 0;
+    }
+}
+
+impl Default for StreamPreview {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub struct CLIInterface {
     theme_manager: ThemeManager,
     editor: Editor<ShellmindCompleter, DefaultHistory>,
+    language: i18n::Language,
+    speaker: Option<speech::Speaker>,
+    pager: pager::Pager,
+    prompt_format: String,
+    graphics: Option<graphics::Protocol>,
 }
 
 impl CLIInterface {
     pub fn new() -> Result<Self, ReadlineError> {
-        let editor = Editor::new()?;
+        let mut editor = Editor::new()?;
+        editor.set_helper(Some(ShellmindCompleter));
         Ok(CLIInterface {
             theme_manager: ThemeManager::new(),
             editor,
+            language: i18n::Language::English,
+            speaker: None,
+            pager: pager::Pager::new(pager::PagerMode::Builtin),
+            prompt_format: String::new(),
+            graphics: None,
         })
     }
 
-    pub fn print_banner(&self) {
-        let standard_font = FIGfont::standard().unwrap();
-        let figure = standard_font.convert("Shellmind");
-        if let Some(ref fig) = figure {
-            println!("{}", self.theme_manager.get_banner_color().paint(fig.to_string()));
+    pub fn set_language(&mut self, language: i18n::Language) {
+        self.language = language;
+    }
+
+    pub fn set_pager_mode(&mut self, mode: pager::PagerMode) {
+        self.pager.set_mode(mode);
+    }
+
+    pub fn set_prompt_format(&mut self, format: &str) {
+        self.prompt_format = format.to_string();
+    }
+
+    /// Picks the inline image protocol for `inline_images`; see `graphics::Protocol::detect`.
+    pub fn set_inline_images(&mut self, setting: &str) {
+        self.graphics = graphics::Protocol::detect(setting);
+    }
+
+    /// Shows the image at `path` inline when the terminal supports it, otherwise prints
+    /// where it was saved.
+    pub fn print_image(&self, path: &Path) {
+        if let Some(protocol) = self.graphics {
+            if protocol.render(path).is_ok() {
+                return;
+            }
+        }
+        self.print_status(&self.tf(i18n::Message::ImageSaved, &[&path.display()]));
+    }
+
+    /// Applies `edit_mode`, `completion_type` and `key_bindings`. Returns the settings that
+    /// couldn't be parsed; those are skipped.
+    pub fn configure_editor(&mut self, edit_mode: &str, completion: &str, bindings: &std::collections::BTreeMap<String, String>) -> Vec<String> {
+        use rustyline::config::Configurer;
+        let mut invalid = Vec::new();
+        match keymap::edit_mode(edit_mode) {
+            Some(mode) => self.editor.set_edit_mode(mode),
+            None => invalid.push(format!("edit_mode = {}", edit_mode)),
+        }
+        match keymap::completion_type(completion) {
+            Some(completion_type) => self.editor.set_completion_type(completion_type),
+            None => invalid.push(format!("completion_type = {}", completion)),
+        }
+        for (key, action) in bindings {
+            match (keymap::parse_key(key), keymap::parse_action(action)) {
+                (Some(key), Some(cmd)) => {
+                    self.editor.bind_sequence(key, rustyline::EventHandler::Simple(cmd));
+                }
+                _ => invalid.push(format!("key_bindings.{} = {}", key, action)),
+            }
         }
+        invalid
+    }
+
+    /// Turns on reading responses aloud; returns false when no speech program is available.
+    pub fn enable_speech(&mut self, program: Option<&str>) -> bool {
+        self.speaker = speech::Speaker::detect(program, self.language);
+        self.speaker.is_some()
+    }
+
+    pub fn disable_speech(&mut self) {
+        self.speaker = None;
+    }
+
+    /// Reads `text` aloud when speech output is on.
+    pub fn speak(&self, text: &str) {
+        if let Some(speaker) = &self.speaker {
+            if let Err(e) = speaker.speak(text) {
+                self.print_error(&e.to_string());
+            }
+        }
+    }
+
+    /// Localized message text.
+    pub fn t(&self, message: i18n::Message) -> &'static str {
+        i18n::text(self.language, message)
+    }
+
+    /// Localized message with its `{}` placeholders filled in.
+    pub fn tf(&self, message: i18n::Message, args: &[&dyn std::fmt::Display]) -> String {
+        i18n::format_message(self.t(message), args)
+    }
+
+    /// `text` in large letters; `font` is the path of a FIGlet `.flf` file, or empty for the
+    /// standard font. Nothing is printed for empty text.
+    #[cfg(feature = "banner")]
+    pub fn print_banner(&self, text: &str, font: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        let font = if font.is_empty() {
+            FIGfont::standard()
+        } else {
+            FIGfont::from_file(font).or_else(|e| {
+                self.print_error(&self.tf(i18n::Message::BannerFontError, &[&font, &e]));
+                FIGfont::standard()
+            })
+        };
+        match font.ok().and_then(|font| font.convert(text)) {
+            Some(figure) => println!("{}", self.theme_manager.get_banner_color().paint(figure.to_string())),
+            None => println!("{}", self.theme_manager.get_banner_color().bold().paint(text)),
+        }
+    }
+
+    #[cfg(not(feature = "banner"))]
+    pub fn print_banner(&self, text: &str, _font: &str) {
+        if !text.trim().is_empty() {
+            println!("{}", self.theme_manager.get_banner_color().bold().paint(text));
+        }
+    }
+
+    /// A line that must not be missed, e.g. that safe mode is on.
+    pub fn print_alert(&self, message: &str) {
+        println!("{}", self.theme_manager.get_error_color().bold().reverse().paint(format!(" {} ", message)));
+    }
+
+    pub fn print_tip(&self, tip: &str) {
+        println!("{}", Colour::Fixed(244).paint(format!("💡 {}: {}", self.t(i18n::Message::TipPrefix), tip)));
     }
 
     pub fn print_error(&self, message: &str) {
-        eprintln!("{}", self.theme_manager.get_error_color().paint(format!("Error: {}", message)));
+        eprintln!("{}", self.theme_manager.get_error_color().paint(format!("{}: {}", self.t(i18n::Message::ErrorPrefix), message)));
+    }
+
+    /// One dim line above the prompt with the session's context, e.g. the model and the
+    /// approval mode.
+    pub fn print_status_line(&self, parts: &[String]) {
+        if parts.is_empty() {
+            return;
+        }
+        let width = crossterm::terminal::size().map_or(100, |(columns, _)| columns as usize);
+        let line = parts.join(" │ ");
+        let line = match line.char_indices().nth(width.saturating_sub(1)) {
+            Some((end, _)) => format!("{}…", &line[..end]),
+            None => line,
+        };
+        println!("{}", Colour::Fixed(244).paint(line));
     }
 
-    pub fn read_user_input(&mut self) -> Result<String, ReadlineError> {
-        let p = format!("{}", self.theme_manager.get_prompt_color().paint("> "));
+    pub fn read_user_input(&mut self, context: &prompt::PromptContext) -> Result<String, ReadlineError> {
+        let p = self.theme_manager.render_prompt(&self.prompt_format, context);
         let readline = self.editor.readline_with_initial(&p, ("", ""));
         match readline {
             Ok(line) => {
@@ -138,7 +440,144 @@ impl CLIInterface {
     }
 
     pub fn print_status(&self, message: &str) {
-        println!("{}", self.theme_manager.get_status_color().paint(format!("Status: {}", message)));
+        println!("{}", self.theme_manager.get_status_color().paint(format!("{}: {}", self.t(i18n::Message::StatusPrefix), message)));
+    }
+
+    /// Boxed panel shown before a tool call is approved: what it does, its full parameters
+    /// and any diff or paths involved.
+    pub fn print_approval_panel(&self, title: &str, description: &str, params: &str, details: &[String]) {
+        let border = self.theme_manager.get_banner_color();
+        let width = 72;
+        println!("{}", border.paint(format!("┌─ {} {}", title, "─".repeat(width.saturating_sub(title.chars().count() + 4)))));
+        let line = |text: &str| println!("{} {}", border.paint("│"), text);
+        line(description);
+        line("");
+        for param_line in params.lines() {
+            line(param_line);
+        }
+        if !details.is_empty() {
+            line("");
+            for detail in details {
+                let painted = if detail.starts_with('+') && !detail.starts_with("+++") {
+                    Colour::Green.paint(detail.as_str()).to_string()
+                } else if detail.starts_with('-') && !detail.starts_with("---") {
+                    Colour::Red.paint(detail.as_str()).to_string()
+                } else if detail.starts_with("@@") {
+                    Colour::Cyan.paint(detail.as_str()).to_string()
+                } else {
+                    detail.clone()
+                };
+                line(&painted);
+            }
+        }
+        println!("{}", border.paint(format!("└{}", "─".repeat(width - 1))));
+    }
+
+    /// A script with line numbers and light syntax highlighting: comments, strings and
+    /// common keywords of shell and Python.
+    pub fn print_script(&self, path: &str, body: &str) {
+        const KEYWORDS: &[&str] = &[
+            "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac", "in", "function",
+            "return", "local", "export", "set", "def", "class", "import", "from", "as", "with", "try", "except",
+            "finally", "raise", "not", "and", "or", "const", "let", "var",
+        ];
+        self.print_section(path);
+        let width = body.lines().count().to_string().len();
+        for (number, line) in body.lines().enumerate() {
+            let gutter = Colour::Fixed(244).paint(format!("{:>width$} │", number + 1, width = width));
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                println!("{} {}", gutter, Colour::Fixed(244).paint(line));
+                continue;
+            }
+            let mut painted = String::new();
+            let mut word = String::new();
+            let mut quote: Option<char> = None;
+            let flush = |word: &mut String, painted: &mut String| {
+                if KEYWORDS.contains(&word.as_str()) {
+                    painted.push_str(&Colour::Purple.bold().paint(word.as_str()).to_string());
+                } else {
+                    painted.push_str(word);
+                }
+                word.clear();
+            };
+            for c in line.chars() {
+                match quote {
+                    Some(q) => {
+                        word.push(c);
+                        if c == q {
+                            painted.push_str(&Colour::Green.paint(word.as_str()).to_string());
+                            word.clear();
+                            quote = None;
+                        }
+                    }
+                    None if c == '"' || c == '\'' => {
+                        flush(&mut word, &mut painted);
+                        word.push(c);
+                        quote = Some(c);
+                    }
+                    None if c.is_alphanumeric() || c == '_' => word.push(c),
+                    None => {
+                        flush(&mut word, &mut painted);
+                        painted.push(c);
+                    }
+                }
+            }
+            if quote.is_some() {
+                painted.push_str(&Colour::Green.paint(word.as_str()).to_string());
+            } else {
+                flush(&mut word, &mut painted);
+            }
+            println!("{} {}", gutter, painted);
+        }
+    }
+
+    /// A status message with a possibly long body, paged so it cannot scroll a following
+    /// confirmation prompt out of view.
+    pub fn print_status_paged(&self, message: &str) {
+        let prefix = self.theme_manager.get_status_color().paint(format!("{}:", self.t(i18n::Message::StatusPrefix)));
+        if self.pager.show(&format!("{} {}", prefix, message)) {
+            self.print_more_hint();
+        }
+    }
+
+    /// `/more`: prints the next page of held-back output. Returns false when there is none.
+    pub fn print_more(&self) -> bool {
+        let shown = self.pager.more();
+        if shown && self.pager.remaining() > 0 {
+            self.print_more_hint();
+        }
+        shown
+    }
+
+    fn print_more_hint(&self) {
+        println!("{}", Colour::Fixed(244).paint(self.tf(i18n::Message::MoreLines, &[&self.pager.remaining()])));
+    }
+
+    /// `table` as text sized to the terminal width.
+    pub fn render_table(&self, table: &table::Table) -> String {
+        let width = crossterm::terminal::size().map_or(100, |(columns, _)| columns as usize);
+        table.render(width, self.theme_manager.get_banner_color().bold())
+    }
+
+    pub fn print_table(&self, table: &table::Table) {
+        println!("{}", self.render_table(table));
+    }
+
+    /// A Markdown answer, rendered for the terminal.
+    pub fn print_markdown(&self, text: &str) {
+        println!("{}", markdown::render(text.trim(), self.theme_manager.get_banner_color()));
+    }
+
+    /// Heading for a group of output, e.g. the findings for one file.
+    pub fn print_section(&self, title: &str) {
+        println!("\n{}", self.theme_manager.get_banner_color().bold().paint(title));
+    }
+
+    /// One line of a review, colored by severity.
+    pub fn print_finding(&self, severity: &str, message: &str) {
+        let label = self.theme_manager.get_severity_color(severity).bold().paint(format!("[{}]", severity.to_uppercase()));
+        println!("  {} {}", label, message);
     }
 
     pub fn start_thinking_indicator(&self) -> ProgressBar {
@@ -148,7 +587,7 @@ impl CLIInterface {
                 .unwrap()
                 .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]),
         );
-        spinner.set_message("Thinking...");
+        spinner.set_message(self.t(i18n::Message::Thinking));
         spinner.enable_steady_tick(Duration::from_millis(100));
         spinner
     }
@@ -156,4 +595,29 @@ impl CLIInterface {
     pub fn stop_thinking_indicator(&self, spinner: ProgressBar) {
         spinner.finish_and_clear();
     }
+
+    /// Progress bar for a running tool; it starts hidden until the first update.
+    pub fn start_progress_bar(&self) -> ProgressBar {
+        let bar = ProgressBar::hidden();
+        bar.set_style(
+            ProgressStyle::with_template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {wide_msg}")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+        bar
+    }
+
+    /// Applies a progress update; without a total the bar is shown as a spinner.
+    pub fn update_progress(&self, bar: &ProgressBar, current: u64, total: Option<u64>, message: &str) {
+        if bar.is_hidden() {
+            bar.set_draw_target(indicatif::ProgressDrawTarget::stderr());
+            bar.enable_steady_tick(Duration::from_millis(100));
+        }
+        match total {
+            Some(total) => bar.set_length(total),
+            None => bar.set_style(ProgressStyle::with_template("{spinner:.green} {wide_msg}").unwrap()),
+        }
+        bar.set_position(current);
+        bar.set_message(message.to_string());
+    }
 }
\ No newline at end of file
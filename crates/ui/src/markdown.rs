@@ -0,0 +1,63 @@
+//! Minimal Markdown rendering for terminal output: headings, fenced code blocks, lists,
+//! block quotes, bold and inline code. Anything else is printed as written.
+
+use ansi_term::{Colour, Style};
+
+/// Applies `**bold**` and `` `code` `` spans within one line.
+fn render_inline(line: &str) -> String {
+    let mut rendered = String::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        let code = rest.find('`');
+        let bold = rest.find("**");
+        let (start, marker, style) = match (code, bold) {
+            (Some(c), Some(b)) if b < c => (b, "**", Style::new().bold()),
+            (Some(c), _) => (c, "`", Colour::Yellow.normal()),
+            (None, Some(b)) => (b, "**", Style::new().bold()),
+            (None, None) => break,
+        };
+        let after = &rest[start + marker.len()..];
+        let Some(end) = after.find(marker) else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&style.paint(&after[..end]).to_string());
+        rest = &after[end + marker.len()..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Renders `text` with ANSI styles. Code blocks keep their content verbatim, so commands
+/// shown as examples can be copied as they are.
+pub fn render(text: &str, accent: Colour) -> String {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            let language = trimmed.trim_start_matches('`').trim();
+            if in_code && !language.is_empty() {
+                lines.push(Colour::Fixed(244).paint(format!("  ┌ {}", language)).to_string());
+            }
+            continue;
+        }
+        if in_code {
+            lines.push(format!("{} {}", Colour::Fixed(244).paint("  │"), Colour::Green.paint(line)));
+            continue;
+        }
+        let heading = trimmed.chars().take_while(|c| *c == '#').count();
+        if heading > 0 && trimmed[heading..].starts_with(' ') {
+            lines.push(accent.bold().paint(trimmed[heading..].trim()).to_string());
+        } else if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            let indent = &line[..line.len() - trimmed.len()];
+            lines.push(format!("{}  • {}", indent, render_inline(item)));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            lines.push(format!("{} {}", Colour::Fixed(244).paint("  ▌"), render_inline(quote.trim_start())));
+        } else {
+            lines.push(render_inline(line));
+        }
+    }
+    lines.join("\n")
+}
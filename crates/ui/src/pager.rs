@@ -0,0 +1,98 @@
+//! Paging of long output: the built-in pager shows one screen and keeps the rest for
+//! `/more`; the external one hands everything to `$PAGER`.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Lines kept free below a page for the hint and the next prompt.
+const RESERVED_LINES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PagerMode {
+    Builtin,
+    External,
+    Off,
+}
+
+impl PagerMode {
+    /// Parses the `pager` setting; unknown values mean the built-in pager.
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "external" => PagerMode::External,
+            "off" | "none" => PagerMode::Off,
+            _ => PagerMode::Builtin,
+        }
+    }
+}
+
+pub struct Pager {
+    mode: PagerMode,
+    /// Lines not shown yet, for `/more`.
+    pending: RefCell<VecDeque<String>>,
+}
+
+fn page_height() -> usize {
+    crossterm::terminal::size().map_or(24, |(_, rows)| rows as usize).saturating_sub(RESERVED_LINES).max(5)
+}
+
+fn run_external(text: &str) -> bool {
+    let pager = std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()).unwrap_or_else(|| "less -R".to_string());
+    let Ok(mut child) = Command::new("sh").arg("-c").arg(&pager).stdin(Stdio::piped()).spawn() else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may quit before reading everything
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait().map_or(false, |status| status.success())
+}
+
+impl Pager {
+    pub fn new(mode: PagerMode) -> Self {
+        Pager { mode, pending: RefCell::new(VecDeque::new()) }
+    }
+
+    pub fn set_mode(&mut self, mode: PagerMode) {
+        self.mode = mode;
+    }
+
+    /// Lines left for `/more`.
+    pub fn remaining(&self) -> usize {
+        self.pending.borrow().len()
+    }
+
+    /// Prints `text`, paging it when it is taller than the terminal. New output replaces
+    /// whatever was left from the previous one. Returns true when lines were held back.
+    pub fn show(&self, text: &str) -> bool {
+        self.pending.borrow_mut().clear();
+        let height = page_height();
+        let lines: Vec<&str> = text.lines().collect();
+        if self.mode == PagerMode::Off || lines.len() <= height || !std::io::stdout().is_terminal() {
+            println!("{}", text);
+            return false;
+        }
+        if self.mode == PagerMode::External && run_external(text) {
+            return false;
+        }
+        for line in &lines[..height] {
+            println!("{}", line);
+        }
+        self.pending.borrow_mut().extend(lines[height..].iter().map(|line| line.to_string()));
+        true
+    }
+
+    /// Prints the next page. Returns false when nothing was left.
+    pub fn more(&self) -> bool {
+        let mut pending = self.pending.borrow_mut();
+        if pending.is_empty() {
+            return false;
+        }
+        let count = page_height().min(pending.len());
+        for line in pending.drain(..count) {
+            println!("{}", line);
+        }
+        true
+    }
+}
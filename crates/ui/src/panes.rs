@@ -0,0 +1,164 @@
+//! Full-screen view of background jobs and agent steps in split panes (`/panes`). Panes
+//! follow their output as it arrives; the focused one can be scrolled back through its
+//! history, after which it stays put until `End` resumes following.
+
+use std::io::{self, Stdout};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{Frame, Terminal};
+
+/// How often running panes are refreshed.
+const REFRESH: Duration = Duration::from_millis(200);
+
+/// What one pane shows, refreshed from the job on every frame.
+pub struct Pane {
+    pub title: String,
+    /// E.g. "running 12s" or "exit 1".
+    pub status: String,
+    pub running: bool,
+    pub failed: bool,
+    pub lines: Vec<String>,
+}
+
+/// Rows of the grid, each with the number of panes in it, for `count` panes.
+fn grid(count: usize) -> Vec<usize> {
+    let columns = (1..).find(|c| c * c >= count).unwrap_or(1);
+    let rows = (count + columns - 1) / columns;
+    (0..rows).map(|row| columns.min(count - row * columns)).collect()
+}
+
+fn areas(area: Rect, count: usize) -> Vec<Rect> {
+    let rows = grid(count);
+    let row_areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Ratio(1, rows.len() as u32); rows.len()])
+        .split(area);
+    rows.iter()
+        .zip(row_areas.iter())
+        .flat_map(|(columns, row)| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Ratio(1, *columns as u32); *columns])
+                .split(*row)
+                .to_vec()
+        })
+        .collect()
+}
+
+struct View {
+    focus: usize,
+    /// Lines scrolled back from the end for each pane; 0 follows new output.
+    scroll: Vec<usize>,
+    /// Inner height of the focused pane at the last draw, the size of a page.
+    page: usize,
+}
+
+impl View {
+    fn draw(&mut self, frame: &mut Frame, panes: &[Pane], help: &str) {
+        let [body, footer] = {
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)])
+                .split(frame.size());
+            [split[0], split[1]]
+        };
+        self.scroll.resize(panes.len(), 0);
+        for (index, (pane, area)) in panes.iter().zip(areas(body, panes.len())).enumerate() {
+            let height = area.height.saturating_sub(2) as usize;
+            let scroll = self.scroll[index].min(pane.lines.len().saturating_sub(height));
+            self.scroll[index] = scroll;
+            if index == self.focus {
+                self.page = height.max(1);
+            }
+            let end = pane.lines.len() - scroll;
+            let start = end.saturating_sub(height);
+            let lines: Vec<Line> = pane.lines[start..end].iter().map(|l| Line::from(l.as_str())).collect();
+
+            let mut title = format!(" {} · {} ", pane.title, pane.status);
+            if scroll > 0 {
+                title.push_str(&format!("↑{} ", scroll));
+            }
+            let colour = if pane.failed {
+                Color::Red
+            } else if pane.running {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            let mut border = Style::default().fg(colour);
+            if index == self.focus {
+                border = border.add_modifier(Modifier::BOLD);
+            }
+            let block = Block::default().borders(Borders::ALL).border_style(border).title(title);
+            frame.render_widget(Paragraph::new(lines).block(block), area);
+        }
+        frame.render_widget(Paragraph::new(help).style(Style::default().add_modifier(Modifier::DIM)), footer);
+    }
+
+    /// Handles a key; returns false when the view should close.
+    fn key(&mut self, code: KeyCode, modifiers: KeyModifiers, count: usize) -> bool {
+        let scroll = &mut self.scroll[self.focus];
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => self.focus = (self.focus + 1) % count,
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => self.focus = (self.focus + count - 1) % count,
+            KeyCode::Up | KeyCode::Char('k') => *scroll += 1,
+            KeyCode::Down | KeyCode::Char('j') => *scroll = scroll.saturating_sub(1),
+            KeyCode::PageUp => *scroll += self.page,
+            KeyCode::PageDown => *scroll = scroll.saturating_sub(self.page),
+            // Clamped to the top on the next draw
+            KeyCode::Home | KeyCode::Char('g') => *scroll = usize::MAX / 2,
+            KeyCode::End | KeyCode::Char('G') => *scroll = 0,
+            _ => {}
+        }
+        true
+    }
+}
+
+fn restore(terminal: &mut Terminal<CrosstermBackend<Stdout>>) {
+    let _ = terminal::disable_raw_mode();
+    let _ = crossterm::execute!(terminal.backend_mut(), LeaveAlternateScreen);
+    let _ = terminal.show_cursor();
+}
+
+/// Shows the panes `refresh` returns until `q`, Esc or Ctrl-C. `refresh` is called for
+/// every frame, so running jobs are tailed live; `help` is the key summary at the bottom.
+pub fn show(mut refresh: impl FnMut() -> Vec<Pane>, help: &str) -> io::Result<()> {
+    let mut panes = refresh();
+    if panes.is_empty() {
+        return Ok(());
+    }
+    terminal::enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    if let Err(e) = crossterm::execute!(stdout, EnterAlternateScreen) {
+        let _ = terminal::disable_raw_mode();
+        return Err(e);
+    }
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    let mut view = View { focus: 0, scroll: vec![0; panes.len()], page: 1 };
+
+    let result = (|| -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| view.draw(frame, &panes, help))?;
+            if event::poll(REFRESH)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !view.key(key.code, key.modifiers, panes.len()) {
+                        return Ok(());
+                    }
+                }
+            }
+            // Jobs are only ever added, so pane positions stay stable
+            panes = refresh();
+        }
+    })();
+    restore(&mut terminal);
+    result
+}
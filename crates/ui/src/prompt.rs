@@ -0,0 +1,70 @@
+//! The REPL prompt, rendered from the `prompt_format` setting. Tokens in braces are
+//! replaced with session details or switch colors, e.g. `{cyan}{dir}{reset} ({branch}) > `.
+//! Unknown tokens are printed as written.
+
+use ansi_term::{Colour, Style};
+
+/// Values the prompt tokens expand to.
+#[derive(Debug, Clone, Default)]
+pub struct PromptContext {
+    pub cwd: String,
+    pub branch: Option<String>,
+    pub model: String,
+    pub approval_mode: String,
+}
+
+fn style(name: &str) -> Option<Style> {
+    let colour = match name {
+        "black" => Colour::Black,
+        "red" => Colour::Red,
+        "green" => Colour::Green,
+        "yellow" => Colour::Yellow,
+        "blue" => Colour::Blue,
+        "magenta" | "purple" => Colour::Purple,
+        "cyan" => Colour::Cyan,
+        "white" => Colour::White,
+        "gray" | "grey" => Colour::Fixed(244),
+        "bold" => return Some(Style::new().bold()),
+        "dim" => return Some(Style::new().dimmed()),
+        "reset" => return Some(Style::new()),
+        _ => return None,
+    };
+    Some(colour.normal())
+}
+
+fn value(name: &str, context: &PromptContext) -> Option<String> {
+    let dir = || context.cwd.rsplit('/').find(|part| !part.is_empty()).unwrap_or("/").to_string();
+    Some(match name {
+        "cwd" => context.cwd.clone(),
+        "dir" => dir(),
+        "branch" => context.branch.clone().unwrap_or_default(),
+        "model" => context.model.clone(),
+        "mode" => context.approval_mode.clone(),
+        "time" => chrono::Local::now().format("%H:%M").to_string(),
+        _ => return None,
+    })
+}
+
+/// Expands `format`. The text ends in the default style so typed input is not colored.
+pub fn render(format: &str, context: &PromptContext) -> String {
+    let mut rendered = String::new();
+    let mut current = Style::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        let token = &rest[start + 1..start + length];
+        rendered.push_str(&current.paint(&rest[..start]).to_string());
+        if let Some(next) = style(token) {
+            current = next;
+        } else if let Some(text) = value(token, context) {
+            rendered.push_str(&current.paint(text).to_string());
+        } else {
+            rendered.push_str(&current.paint(&rest[start..=start + length]).to_string());
+        }
+        rest = &rest[start + length + 1..];
+    }
+    rendered.push_str(&current.paint(rest).to_string());
+    rendered
+}
@@ -0,0 +1,59 @@
+//! Text-to-speech output through the platform speech command (`say`, `espeak-ng`,
+//! `espeak` or `spd-say`).
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::i18n::Language;
+
+/// Speech programs tried in order when no command is configured.
+const CANDIDATES: &[&str] = &["say", "espeak-ng", "espeak", "spd-say"];
+
+pub struct Speaker {
+    program: String,
+    language: Language,
+}
+
+fn in_path(program: &str) -> bool {
+    extensions::which::find_in_path(program).is_some()
+}
+
+impl Speaker {
+    /// Uses `program` when given, otherwise the first available platform speech command.
+    pub fn detect(program: Option<&str>, language: Language) -> Option<Self> {
+        let program = match program.filter(|p| !p.is_empty()) {
+            Some(program) => program.to_string(),
+            None => CANDIDATES.iter().find(|c| in_path(c))?.to_string(),
+        };
+        Some(Speaker { program, language })
+    }
+
+    /// Speaks `text` in the background; text is passed on stdin so it is never parsed as arguments.
+    pub fn speak(&self, text: &str) -> std::io::Result<()> {
+        let mut command = Command::new(&self.program);
+        let binary = std::path::Path::new(&self.program)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if self.language == Language::Turkish && binary.starts_with("espeak") {
+            command.args(["-v", "tr"]);
+        }
+        if binary == "spd-say" {
+            // spd-say doesn't read stdin
+            command.arg("--").arg(text);
+            let mut child = command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+            std::thread::spawn(move || child.wait());
+            return Ok(());
+        }
+        if binary.starts_with("espeak") {
+            command.arg("--stdin");
+        }
+        let mut child = command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        // Reap the process without blocking the REPL
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
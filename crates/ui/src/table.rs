@@ -0,0 +1,111 @@
+//! Column-aligned tables for listings and tabular tool results. Numeric columns are
+//! right-aligned and the widest columns are shortened to fit the terminal.
+
+use ansi_term::Style;
+
+/// Columns are never shortened below this many characters.
+const MIN_COLUMN_WIDTH: usize = 6;
+const COLUMN_GAP: &str = "  ";
+
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn fit(text: &str, max: usize) -> String {
+    if width(text) <= max {
+        return text.to_string();
+    }
+    let mut shortened: String = text.chars().take(max.saturating_sub(1)).collect();
+    shortened.push('…');
+    shortened
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        Table { headers: headers.into_iter().map(Into::into).collect(), rows: Vec::new() }
+    }
+
+    /// Adds a row; missing cells are left empty and extra ones are dropped. Line breaks
+    /// inside a cell are shown as spaces.
+    pub fn add_row<S: Into<String>>(&mut self, cells: impl IntoIterator<Item = S>) {
+        let mut row: Vec<String> = cells
+            .into_iter()
+            .map(|cell| cell.into().replace(['\n', '\r', '\t'], " "))
+            .take(self.headers.len())
+            .collect();
+        row.resize(self.headers.len(), String::new());
+        self.rows.push(row);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn is_numeric(&self, column: usize) -> bool {
+        let mut cells = self.rows.iter().map(|row| row[column].trim()).filter(|cell| !cell.is_empty()).peekable();
+        cells.peek().is_some()
+            && cells.all(|cell| cell.trim_start_matches('$').trim_end_matches('%').replace(',', "").parse::<f64>().is_ok())
+    }
+
+    /// Column widths that fit within `max_width`, shrinking the widest column first.
+    fn column_widths(&self, max_width: usize) -> Vec<usize> {
+        let mut widths: Vec<usize> = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| self.rows.iter().map(|row| width(&row[i])).chain([width(header)]).max().unwrap_or(0))
+            .collect();
+        let gaps = COLUMN_GAP.len() * widths.len().saturating_sub(1);
+        while widths.iter().sum::<usize>() + gaps > max_width {
+            let Some((widest, current)) = widths.iter().copied().enumerate().max_by_key(|(_, w)| *w) else {
+                break;
+            };
+            if current <= MIN_COLUMN_WIDTH {
+                break;
+            }
+            widths[widest] = current - 1;
+        }
+        widths
+    }
+
+    /// The table as text no wider than `max_width` where possible, with the header row
+    /// painted in `header_style`.
+    pub fn render(&self, max_width: usize, header_style: Style) -> String {
+        let widths = self.column_widths(max_width);
+        let numeric: Vec<bool> = (0..self.headers.len()).map(|i| self.is_numeric(i)).collect();
+        let format_row = |cells: &[String]| {
+            cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    let cell = fit(cell, widths[i]);
+                    if numeric[i] {
+                        format!("{:>w$}", cell, w = widths[i])
+                    } else {
+                        format!("{:<w$}", cell, w = widths[i])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(COLUMN_GAP)
+                .trim_end()
+                .to_string()
+        };
+
+        let mut lines = vec![
+            header_style.paint(format_row(&self.headers)).to_string(),
+            widths.iter().map(|w| "─".repeat(*w)).collect::<Vec<_>>().join(COLUMN_GAP),
+        ];
+        lines.extend(self.rows.iter().map(|row| format_row(row)));
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,93 @@
+//! Startup tips. One is shown per day, preferring features the user hasn't used lately.
+
+use crate::i18n::Language;
+
+pub struct Tip {
+    /// Command the tip is about; the tip is skipped while it is in recent use.
+    pub feature: &'static str,
+    english: &'static str,
+    turkish: &'static str,
+}
+
+impl Tip {
+    pub fn text(&self, language: Language) -> &'static str {
+        match language {
+            Language::English => self.english,
+            Language::Turkish => self.turkish,
+        }
+    }
+}
+
+pub const TIPS: &[Tip] = &[
+    Tip {
+        feature: "/retry",
+        english: "Not the command you wanted? /retry asks again, and /retry --hot tries something more creative.",
+        turkish: "İstediğiniz komut bu değil mi? /tekrar yeniden sorar, /tekrar --hot daha yaratıcı bir deneme yapar.",
+    },
+    Tip {
+        feature: "/ask",
+        english: "/ask <question> explains without running anything.",
+        turkish: "/sor <soru> hiçbir şey çalıştırmadan açıklama yapar.",
+    },
+    Tip {
+        feature: "/translate",
+        english: "/translate translates the output of the last command into your language.",
+        turkish: "/çevir son komutun çıktısını dilinize çevirir.",
+    },
+    Tip {
+        feature: "/pin",
+        english: "/pin <file> keeps a file in the context of every request; /pins lists what is pinned.",
+        turkish: "/sabitle <dosya> bir dosyayı her isteğin bağlamında tutar; /pins sabitlenenleri listeler.",
+    },
+    Tip {
+        feature: "/good",
+        english: "/good and /bad <reason> rate the last command and can steer later suggestions.",
+        turkish: "/iyi ve /kötü <neden> son komutu değerlendirir ve sonraki önerileri yönlendirebilir.",
+    },
+    Tip {
+        feature: "/env",
+        english: "/env shows which .env variables were loaded and what commands will see.",
+        turkish: "/env hangi .env değişkenlerinin yüklendiğini ve komutların neleri göreceğini gösterir.",
+    },
+    Tip {
+        feature: "/bg",
+        english: "/bg <command> runs a build or test suite in the background; /panes shows every job's output side by side.",
+        turkish: "/bg <komut> bir derlemeyi veya test paketini arka planda çalıştırır; /bölmeler tüm işlerin çıktısını yan yana gösterir.",
+    },
+    Tip {
+        feature: "/fleet",
+        english: "/fleet <task> plans a fix for many hosts, e.g. web01-web05 or @webservers, and runs it over SSH after one review.",
+        turkish: "/filo <görev> birden çok sunucu (örn. web01-web05 veya @webservers) için bir düzeltme planlar ve tek bir onaydan sonra SSH ile çalıştırır.",
+    },
+    Tip {
+        feature: "/cheat",
+        english: "/cheat <query> looks up common commands offline, e.g. /cheat extract tar.gz.",
+        turkish: "/kopya <arama> yaygın komutları çevrimdışı arar, örn. /kopya extract tar.gz.",
+    },
+    Tip {
+        feature: "/paste-image",
+        english: "/paste-image attaches a screenshot from the clipboard to your next question.",
+        turkish: "/paste-image panodaki ekran görüntüsünü bir sonraki sorunuza ekler.",
+    },
+    Tip {
+        feature: "/speak",
+        english: "/speak on reads explanations aloud.",
+        turkish: "/konuş aç açıklamaları sesli okur.",
+    },
+    Tip {
+        feature: "/more",
+        english: "Long output is paged; /more shows the next page.",
+        turkish: "Uzun çıktılar sayfalanır; /devam sonraki sayfayı gösterir.",
+    },
+];
+
+/// Tip for day `day`, rotating through the tips whose feature is not in `recently_used`,
+/// or through all of them when every feature is.
+pub fn pick(day: usize, recently_used: &[String]) -> &'static Tip {
+    let unused: Vec<&Tip> = TIPS.iter().filter(|tip| !recently_used.iter().any(|f| f == tip.feature)).collect();
+    if unused.is_empty() {
+        &TIPS[day % TIPS.len()]
+    } else {
+        unused[day % unused.len()]
+    }
+}